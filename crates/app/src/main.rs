@@ -8,13 +8,29 @@
 
 use std::io::{self, Write};
 
+use clap::Parser;
+
 use audio::{
-    AudioConfig, AudioPipelineImpl, AudioPipeline,
+    AudioConfig, AudioPipelineImpl, AudioPipeline, AudioSettings,
     CpalCapture, CpalPlayback, OpusCodec,
-    AudioCapture, AudioPlayback, AudioCodec,
+    AudioCapture, AudioPlayback, AudioCodec, TestTone,
 };
+
+/// Fichier des réglages audio (périphériques préférés)
+const AUDIO_SETTINGS_FILE: &str = "voc_audio.toml";
+
+#[derive(Parser)]
+#[command(author, version, about = "Application de test audio Voc")]
+struct Cli {
+    /// Lance uniquement les tests automatiques, sans menu interactif (CI, scripts)
+    #[arg(long)]
+    headless: bool,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    
     println!("🎤 Application de test audio Voc");
     println!("==================================");
     
@@ -30,6 +46,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n3️⃣  Test du codec Opus...");
     test_codec()?;
     
+    if cli.headless {
+        return Ok(());
+    }
+    
     // Menu interactif
     loop {
         println!("\n🎛️  Menu principal :");
@@ -79,13 +99,15 @@ fn test_config() -> Result<(), Box<dyn std::error::Error>> {
 
 /// Test des périphériques audio
 async fn test_devices() -> Result<(), Box<dyn std::error::Error>> {
-    let config = AudioConfig::default();
+    // Périphériques préférés enregistrés lors d'un lancement précédent
+    let settings = AudioSettings::load_or_default(AUDIO_SETTINGS_FILE);
+    let config = settings.audio.clone();
     
     // Test du microphone
     print!("🎤 Test du microphone... ");
-    match CpalCapture::new(config.clone()) {
+    match CpalCapture::with_preferred_device(config.clone(), settings.devices.input_name.as_deref()) {
         Ok(capture) => {
-            println!("✅ {}", capture.device_info());
+            println!("✅ {}", capture.device_details());
         },
         Err(e) => {
             println!("❌ Erreur : {}", e);
@@ -95,9 +117,9 @@ async fn test_devices() -> Result<(), Box<dyn std::error::Error>> {
     
     // Test des haut-parleurs
     print!("🔊 Test des haut-parleurs... ");
-    match CpalPlayback::new(config) {
+    match CpalPlayback::with_preferred_device(config, settings.devices.output_name.as_deref()) {
         Ok(playback) => {
-            println!("✅ {}", playback.device_info());
+            println!("✅ {}", playback.device_details());
         },
         Err(e) => {
             println!("❌ Erreur : {}", e);
@@ -196,24 +218,42 @@ async fn test_loopback() -> Result<(), Box<dyn std::error::Error>> {
     
     let duration: u32 = input.trim().parse().unwrap_or(5).clamp(1, 30);
     
+    print!("Mesurer avec un signal de test (latence acoustique, distorsion) ? (o/N) : ");
+    io::stdout().flush().unwrap();
+    input.clear();
+    io::stdin().read_line(&mut input).unwrap();
+    let test_tone = matches!(input.trim(), "o" | "O" | "oui").then(TestTone::default);
+    
     println!("\n🚀 Démarrage du test loopback pour {}s...", duration);
-    println!("💬 Parlez dans le microphone !");
+    if test_tone.is_none() {
+        println!("💬 Parlez dans le microphone !");
+    }
     
     let config = AudioConfig::default();
     let mut pipeline = AudioPipelineImpl::new(config)?;
     
-    match pipeline.run_loopback_test(duration).await {
-        Ok(stats) => {
+    match pipeline.run_loopback_test(duration, test_tone).await {
+        Ok(report) => {
+            let stats = &report.stats;
             println!("\n📊 Résultats du test :");
             println!("   ✅ Test terminé avec succès");
             println!("   📈 Frames traitées : {}", stats.frames_captured);
             println!("   🕐 Latence moyenne : {:.1}ms", stats.avg_latency_ms);
+            if let (Some(p99), Some(max)) = (stats.latency.p99(), stats.latency.max()) {
+                println!("   🕐 Latence p99 : {:?} (max {:?})", p99, max);
+            }
             println!("   🔊 Niveau audio : {:.3}", stats.avg_rms_level);
             println!("   📦 Compression : {:.1}x", stats.avg_compression_ratio);
             
             if stats.buffer_overflows > 0 {
                 println!("   ⚠️  Overflows : {}", stats.buffer_overflows);
             }
+            if let Some(latency) = report.acoustic_latency {
+                println!("   🎵 Latence acoustique : {:?}", latency);
+            }
+            if let (Some(thd), Some(snr)) = (report.thd_percent, report.snr_db) {
+                println!("   🎵 THD {:.2}%, SNR {:.1} dB, {} décrochage(s)", thd, snr, report.dropouts);
+            }
         },
         Err(e) => {
             println!("❌ Erreur pendant le test : {}", e);
@@ -283,11 +323,17 @@ async fn show_system_info() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("\n🎤 Périphériques :");
     if let Ok(capture) = CpalCapture::new(config.clone()) {
-        println!("   Entrée : {}", capture.device_info());
+        println!("   Entrée : {}", capture.device_details());
     }
     if let Ok(playback) = CpalPlayback::new(config) {
-        println!("   Sortie : {}", playback.device_info());
+        println!("   Sortie : {}", playback.device_details());
     }
+    println!("   Entrées disponibles : {:?}", audio::devices::input_device_names());
+    println!("   Sorties disponibles : {:?}", audio::devices::output_device_names());
+    
+    let settings = AudioSettings::load_or_default(AUDIO_SETTINGS_FILE);
+    println!("   Préférences ({}) : entrée {:?}, sortie {:?}",
+             AUDIO_SETTINGS_FILE, settings.devices.input_name, settings.devices.output_name);
     
     println!("\n💾 Mémoire :");
     println!("   Taille AudioFrame : {} bytes", std::mem::size_of::<audio::AudioFrame>());
@@ -0,0 +1,293 @@
+// Test d'endurance : appel simulé de plusieurs heures
+//
+// Deux managers reliés par `SimulatedTransport::pair` échangent de l'audio
+// en temps réel. À intervalle régulier, la mémoire résidente, les
+// allocations (feature `alloc-stats`), la profondeur des buffers et l'écart
+// entre compteurs d'envoi et de réception sont relevés ; le test échoue si
+// l'une de ces mesures grandit sans borne après la phase de chauffe.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use audio::CompressedFrame;
+use network::{NetworkConfig, NetworkManager, SimulatedTransport, UdpNetworkManager, utils};
+
+/// Croissance tolérée du nombre d'allocations vivantes après la chauffe
+const LIVE_ALLOCATIONS_SLACK: u64 = 1000;
+
+/// Paramètres du test d'endurance
+pub struct SoakParams {
+    pub duration: Duration,
+    pub interval: Duration,
+    pub latency_ms: u32,
+    pub jitter_ms: u32,
+    pub loss_percent: f32,
+    pub max_rss_growth_kb: u64,
+}
+
+/// Relevé périodique
+#[derive(Clone, Debug, Serialize)]
+pub struct SoakSample {
+    pub elapsed_s: u64,
+    /// Mémoire résidente (Linux uniquement)
+    pub rss_kb: Option<u64>,
+    /// Allocations non libérées (feature `alloc-stats`)
+    pub live_allocations: Option<u64>,
+    pub live_bytes: Option<u64>,
+    pub buffered_packets: usize,
+    pub state_history_len: usize,
+    pub quality_history_len: usize,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    /// Paquets envoyés mais ni reçus ni encore en vol
+    pub stats_drift: u64,
+}
+
+/// Résultat du test d'endurance
+#[derive(Debug, Serialize)]
+pub struct SoakTestResult {
+    pub duration_ms: u128,
+    pub samples: Vec<SoakSample>,
+    /// Mesures ayant grandi au-delà de la tolérance
+    pub failures: Vec<String>,
+    pub passed: bool,
+}
+
+/// Lance l'appel simulé et relève les mesures toutes les `params.interval`
+pub async fn run_soak(params: &SoakParams, quiet: bool) -> Result<SoakTestResult, Box<dyn std::error::Error>> {
+    let config = NetworkConfig::lan_optimized();
+    let (mut caller_transport, callee_transport) = SimulatedTransport::pair(config.clone())?;
+    caller_transport.set_simulation_params(params.latency_ms, params.loss_percent / 100.0, params.jitter_ms);
+    let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport))?;
+    let mut callee = UdpNetworkManager::with_transport(config.clone(), Box::new(callee_transport))?;
+
+    let (dialed, accepted) = tokio::join!(
+        caller.open(9001, Some(utils::localhost(9002))),
+        callee.open(9002, None),
+    );
+    dialed?;
+    accepted?;
+
+    if !quiet {
+        println!("🏋️  Endurance : {} d'appel simulé, relevé toutes les {}",
+                 utils::format_duration(params.duration), utils::format_duration(params.interval));
+    }
+
+    let start = Instant::now();
+    let deadline = start + params.duration;
+    let packets_sent = AtomicU64::new(0);
+    let mut samples: Vec<SoakSample> = Vec::new();
+
+    let send_side = async {
+        let mut ticker = tokio::time::interval(Duration::from_millis(20));
+        let mut sequence: u64 = 0;
+        while Instant::now() < deadline {
+            ticker.tick().await;
+            sequence += 1;
+            let frame = CompressedFrame::new(vec![0xF8; 120], 960, Instant::now(), sequence);
+            caller.send_audio(frame).await?;
+            packets_sent.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    let receive_side = async {
+        let mut next_sample = start + params.interval;
+        let mut packets_received: u64 = 0;
+        // Paquets pouvant légitimement être en vol ou en attente de réordonnancement
+        let in_flight = (params.latency_ms + params.jitter_ms + config.max_delay_ms) as u64 / 20 + 2;
+        loop {
+            let now = Instant::now();
+            if now >= next_sample {
+                let sent = packets_sent.load(Ordering::Relaxed);
+                let sample = SoakSample {
+                    elapsed_s: now.duration_since(start).as_secs(),
+                    rss_kb: resident_memory_kb(),
+                    live_allocations: alloc_counter::live_allocations(),
+                    live_bytes: alloc_counter::live_bytes(),
+                    buffered_packets: callee.buffered_packets(),
+                    state_history_len: callee.state_history().await.len(),
+                    quality_history_len: callee.quality_history().len(),
+                    packets_sent: sent,
+                    packets_received,
+                    stats_drift: sent.saturating_sub(packets_received).saturating_sub(in_flight),
+                };
+                if !quiet {
+                    println!("   ⏱️  {}s : RSS {} kB, allocations {}, buffer {}, écart {}",
+                             sample.elapsed_s,
+                             sample.rss_kb.map_or("?".to_string(), |kb| kb.to_string()),
+                             sample.live_allocations.map_or("?".to_string(), |n| n.to_string()),
+                             sample.buffered_packets, sample.stats_drift);
+                }
+                samples.push(sample);
+                next_sample += params.interval;
+                if now >= deadline {
+                    break;
+                }
+                continue;
+            }
+
+            let wait = next_sample.saturating_duration_since(now);
+            match tokio::time::timeout(wait, callee.receive_audio()).await {
+                Ok(result) => {
+                    result?;
+                    packets_received += 1;
+                }
+                Err(_) => continue, // Heure du prochain relevé
+            }
+        }
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    let (sent, received) = tokio::join!(send_side, receive_side);
+    sent?;
+    received?;
+
+    caller.disconnect().await?;
+    callee.disconnect().await?;
+
+    let max_buffered = config.delay_frames(Duration::from_millis(20)).1;
+    let failures = growth_failures(&samples, params, max_buffered);
+    if !quiet {
+        for failure in &failures {
+            println!("   ❌ {}", failure);
+        }
+    }
+
+    Ok(SoakTestResult {
+        duration_ms: start.elapsed().as_millis(),
+        passed: failures.is_empty(),
+        samples,
+        failures,
+    })
+}
+
+/// Compare le dernier relevé au premier (fin de la chauffe)
+fn growth_failures(samples: &[SoakSample], params: &SoakParams, max_buffered: usize) -> Vec<String> {
+    let mut failures = Vec::new();
+    let [first, .., last] = samples else {
+        failures.push("moins de deux relevés : durée trop courte pour l'intervalle".to_string());
+        return failures;
+    };
+
+    if let (Some(before), Some(after)) = (first.rss_kb, last.rss_kb) {
+        if after > before + params.max_rss_growth_kb {
+            failures.push(format!("mémoire résidente : {} kB → {} kB", before, after));
+        }
+    }
+    if let (Some(before), Some(after)) = (first.live_allocations, last.live_allocations) {
+        if after > before + before / 2 + LIVE_ALLOCATIONS_SLACK {
+            failures.push(format!("allocations vivantes : {} → {}", before, after));
+        }
+    }
+    if let Some(sample) = samples.iter().find(|sample| sample.buffered_packets > max_buffered) {
+        failures.push(format!("buffer de réception : {} paquets (max {})", sample.buffered_packets, max_buffered));
+    }
+    // Sans perte configurée, tout paquet envoyé finit par être reçu
+    if params.loss_percent == 0.0 && last.stats_drift > first.stats_drift {
+        failures.push(format!("écart envoyés/reçus : {} → {}", first.stats_drift, last.stats_drift));
+    }
+    failures
+}
+
+/// Mémoire résidente du processus en kB (`VmRSS`, Linux uniquement)
+fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    line.trim().trim_end_matches("kB").trim().parse().ok()
+}
+
+/// Allocateur global comptant les allocations (feature `alloc-stats`)
+#[cfg(feature = "alloc-stats")]
+mod alloc_counter {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+    static FREED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            FREED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            FREED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    pub fn live_allocations() -> Option<u64> {
+        Some(ALLOCATIONS.load(Ordering::Relaxed).saturating_sub(DEALLOCATIONS.load(Ordering::Relaxed)))
+    }
+
+    pub fn live_bytes() -> Option<u64> {
+        Some(ALLOCATED_BYTES.load(Ordering::Relaxed).saturating_sub(FREED_BYTES.load(Ordering::Relaxed)))
+    }
+}
+
+/// Sans la feature `alloc-stats`, les allocations ne sont pas mesurées
+#[cfg(not(feature = "alloc-stats"))]
+mod alloc_counter {
+    pub fn live_allocations() -> Option<u64> {
+        None
+    }
+
+    pub fn live_bytes() -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(rss_kb: u64, buffered_packets: usize, stats_drift: u64) -> SoakSample {
+        SoakSample {
+            elapsed_s: 0,
+            rss_kb: Some(rss_kb),
+            live_allocations: None,
+            live_bytes: None,
+            buffered_packets,
+            state_history_len: 2,
+            quality_history_len: 60,
+            packets_sent: 0,
+            packets_received: 0,
+            stats_drift,
+        }
+    }
+
+    #[test]
+    fn test_growth_failures() {
+        let params = SoakParams {
+            duration: Duration::from_secs(3600),
+            interval: Duration::from_secs(60),
+            latency_ms: 20,
+            jitter_ms: 10,
+            loss_percent: 0.0,
+            max_rss_growth_kb: 8192,
+        };
+
+        let stable = [sample(20_000, 3, 0), sample(24_000, 5, 0)];
+        assert!(growth_failures(&stable, &params, 100).is_empty());
+
+        let leaking = [sample(20_000, 3, 0), sample(40_000, 101, 12)];
+        assert_eq!(growth_failures(&leaking, &params, 100).len(), 3);
+        assert_eq!(growth_failures(&stable[..1], &params, 100).len(), 1);
+    }
+}
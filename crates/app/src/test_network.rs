@@ -11,18 +11,30 @@ use std::time::{Duration, Instant};
 use std::net::SocketAddr;
 
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 use network::{
     NetworkConfig, UdpNetworkManager, NetworkManager, NetworkTransport,
     UdpTransport, SimulatedTransport, NetworkStats, ConnectionState,
-    utils, NetworkResult, NetworkError, NetworkPacket, PacketType
+    utils, NetworkResult, NetworkPacket, PacketType, SendOutcome
 };
 use audio::{CompressedFrame};
 
+mod soak;
+use soak::{SoakParams, SoakTestResult};
+
 #[derive(Parser)]
 #[command(author, version, about = "Application de test réseau Voc")]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    
+    /// N'attend jamais d'entrée clavier (CI, scripts)
+    #[arg(long, global = true)]
+    headless: bool,
+    
+    /// Affiche le résultat en JSON sur la dernière ligne (implique --headless)
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[derive(Subcommand)]
@@ -60,30 +72,168 @@ enum Commands {
         #[arg(short, long, default_value = "9001")]
         port: u16,
     },
+    /// Test d'endurance : appel simulé long, détection des fuites mémoire
+    Soak {
+        /// Durée de l'appel en secondes
+        #[arg(short, long, default_value = "3600")]
+        duration: u64,
+        /// Intervalle entre deux relevés en secondes
+        #[arg(short, long, default_value = "60")]
+        interval: u64,
+        #[arg(long, default_value = "20")]
+        latency: u32,
+        #[arg(long, default_value = "10")]
+        jitter: u32,
+        #[arg(long, default_value = "0.0")]
+        loss: f32,
+        /// Croissance tolérée de la mémoire résidente après la chauffe, en kB
+        #[arg(long, default_value = "8192")]
+        max_rss_growth_kb: u64,
+    },
+}
+
+/// Résultat du test de transport UDP
+#[derive(Serialize)]
+struct TransportTestResult {
+    port: u16,
+    local_addr: Option<SocketAddr>,
+    active_after_bind: bool,
+    active_after_shutdown: bool,
+    passed: bool,
+}
+
+/// Résultat du test loopback simulé
+#[derive(Serialize)]
+struct LoopbackTestResult {
+    duration_ms: u128,
+    latency_ms: u32,
+    configured_loss_percent: f32,
+    packets_sent: u64,
+    packets_received: u64,
+    packets_lost: u64,
+    loss_percent: f32,
+    passed: bool,
+}
+
+/// Résultat du test de performance
+#[derive(Serialize)]
+struct PerformanceTestResult {
+    duration_ms: u128,
+    port: u16,
+    frames: u32,
+    frames_per_second: f32,
+    bytes_per_second: f32,
+    passed: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let headless = cli.headless || cli.json;
+    let quiet = cli.json;
     
-    match &cli.command {
-        Some(Commands::Interactive) => run_interactive().await?,
-        Some(Commands::Transport { port }) => test_transport(*port).await?,
+    let result = match &cli.command {
+        Some(Commands::Interactive) if headless => {
+            Err("le mode interactif n'est pas disponible avec --headless".into())
+        },
+        Some(Commands::Interactive) => run_interactive().await,
+        Some(Commands::Transport { port }) => {
+            test_transport(*port, quiet).await.and_then(|result| report(cli.json, "transport", &result))
+        },
         Some(Commands::Loopback { duration, latency, loss }) => {
-            test_loopback(*duration, *latency, *loss).await?
+            test_loopback(*duration, *latency, *loss, quiet).await
+                .and_then(|result| report(cli.json, "loopback", &result))
         },
         Some(Commands::Performance { duration, port }) => {
-            test_performance(*duration, *port).await?
+            test_performance(*duration, *port, quiet).await
+                .and_then(|result| report(cli.json, "performance", &result))
         },
-        Some(Commands::Client { server }) => {
-            run_client(server).await?
+        Some(Commands::Soak { duration, interval, latency, jitter, loss, max_rss_growth_kb }) => {
+            let params = SoakParams {
+                duration: Duration::from_secs(*duration),
+                interval: Duration::from_secs(*interval),
+                latency_ms: *latency,
+                jitter_ms: *jitter,
+                loss_percent: *loss,
+                max_rss_growth_kb: *max_rss_growth_kb,
+            };
+            soak::run_soak(&params, quiet).await
+                .and_then(|result| report(cli.json, "soak", &result))
         },
-        Some(Commands::Server { port }) => {
-            run_server(*port).await?
-        },
-        None => run_interactive().await?,
+        Some(Commands::Client { server }) => run_client(server).await,
+        Some(Commands::Server { port }) => run_server(*port).await,
+        None if headless => run_checks(),
+        None => run_interactive().await,
+    };
+    
+    // En JSON, les erreurs sont elles aussi rapportées en JSON
+    if let (true, Err(e)) = (cli.json, &result) {
+        println!("{}", serde_json::json!({ "passed": false, "error": e.to_string() }));
+        std::process::exit(1);
+    }
+    
+    result
+}
+
+/// Résultat d'un test pouvant être rapporté en JSON
+trait TestResult: Serialize {
+    fn passed(&self) -> bool;
+}
+
+impl TestResult for TransportTestResult {
+    fn passed(&self) -> bool {
+        self.passed
+    }
+}
+
+impl TestResult for LoopbackTestResult {
+    fn passed(&self) -> bool {
+        self.passed
+    }
+}
+
+impl TestResult for PerformanceTestResult {
+    fn passed(&self) -> bool {
+        self.passed
+    }
+}
+
+impl TestResult for SoakTestResult {
+    fn passed(&self) -> bool {
+        self.passed
+    }
+}
+
+/// Rapporte le résultat d'un test
+/// 
+/// En mode `--json`, le résultat est écrit sur une seule ligne, la dernière
+/// de la sortie standard, et le code de sortie vaut 1 si le test a échoué.
+fn report<T: TestResult>(json: bool, test: &str, result: &T) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        let mut value = serde_json::to_value(result)?;
+        value["test"] = test.into();
+        println!("{}", value);
+        if !result.passed() {
+            std::process::exit(1);
+        }
+        return Ok(());
     }
     
+    if result.passed() {
+        Ok(())
+    } else {
+        Err(format!("test {} échoué", test).into())
+    }
+}
+
+/// Vérifications non interactives (mode `--headless` sans sous-commande)
+fn run_checks() -> Result<(), Box<dyn std::error::Error>> {
+    println!("1️⃣  Test de la configuration...");
+    test_config()?;
+    
+    println!("\n2️⃣  Test des utilitaires...");
+    test_utilities()?;
+    
     Ok(())
 }
 
@@ -140,7 +290,7 @@ fn test_config() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Configuration par défaut :");
     println!("   Port local : {}", config.local_port);
     println!("   Taille buffer socket : {} bytes", utils::format_bytes(config.socket_buffer_size));
-    println!("   Taille buffer réception : {} paquets", config.receive_buffer_size);
+    println!("   Buffer réception : {}ms visés, {}ms max", config.target_delay_ms, config.max_delay_ms);
     println!("   Timeout connexion : {}", utils::format_duration(config.connection_timeout));
     println!("   Taille max paquet : {} bytes", NetworkPacket::MAX_PACKET_SIZE);
     println!("   Intervalle heartbeat : {}", utils::format_duration(config.heartbeat_interval));
@@ -212,48 +362,71 @@ async fn interactive_transport_test() -> Result<(), Box<dyn std::error::Error>>
     io::stdin().read_line(&mut input).unwrap();
     let port: u16 = input.trim().parse().unwrap_or(9001);
     
-    test_transport(port).await
+    test_transport(port, false).await.map(|_| ())
 }
 
 /// Test transport UDP sur un port donné
-async fn test_transport(port: u16) -> Result<(), Box<dyn std::error::Error>> {
+/// 
+/// `quiet` supprime l'affichage détaillé (mode `--json`).
+async fn test_transport(port: u16, quiet: bool) -> Result<TransportTestResult, Box<dyn std::error::Error>> {
     let config = NetworkConfig::default();
     let mut transport = UdpTransport::new(config)?;
     
-    println!("🔧 Test création transport... ✅");
+    if !quiet {
+        println!("🔧 Test création transport... ✅");
+        print!("🔌 Test bind sur port {}... ", port);
+    }
     
     // Test bind
-    print!("🔌 Test bind sur port {}... ", port);
     match transport.bind(port).await {
         Ok(()) => {
-            println!("✅");
-            if let Some(addr) = transport.local_addr() {
-                println!("   Adresse locale : {}", addr);
+            if !quiet {
+                println!("✅");
+                if let Some(addr) = transport.local_addr() {
+                    println!("   Adresse locale : {}", addr);
+                }
             }
         },
         Err(e) => {
-            println!("❌ {}", e);
+            if !quiet {
+                println!("❌ {}", e);
+            }
             return Err(e.into());
         }
     }
     
-    // Test état
-    println!("📊 État transport : {}", if transport.is_active() { "Actif ✅" } else { "Inactif ❌" });
+    let local_addr = transport.local_addr();
+    let active_after_bind = transport.is_active();
     
-    // Test statistiques
-    let stats = transport.stats();
-    println!("📈 Statistiques initiales :");
-    println!("   Paquets envoyés : {}", stats.packets_sent);
-    println!("   Paquets reçus : {}", stats.packets_received);
+    if !quiet {
+        // Test état
+        println!("📊 État transport : {}", if active_after_bind { "Actif ✅" } else { "Inactif ❌" });
+        
+        // Test statistiques
+        let stats = transport.stats();
+        println!("📈 Statistiques initiales :");
+        println!("   Paquets envoyés : {}", stats.packets_sent);
+        println!("   Paquets reçus : {}", stats.packets_received);
+        
+        print!("🛑 Test arrêt... ");
+    }
     
     // Test shutdown
-    print!("🛑 Test arrêt... ");
     transport.shutdown().await?;
-    println!("✅");
+    let active_after_shutdown = transport.is_active();
     
-    println!("📊 État final : {}", if transport.is_active() { "Actif ❌" } else { "Inactif ✅" });
+    if !quiet {
+        println!("✅");
+        println!("📊 État final : {}", if active_after_shutdown { "Actif ❌" } else { "Inactif ✅" });
+    }
     
-    Ok(())
+    Ok(TransportTestResult {
+        port,
+        local_addr,
+        active_after_bind,
+        active_after_shutdown,
+        passed: active_after_bind && !active_after_shutdown,
+    })
 }
 
 /// Test loopback simulé interactif
@@ -279,32 +452,34 @@ async fn interactive_loopback_test() -> Result<(), Box<dyn std::error::Error>> {
     io::stdin().read_line(&mut input).unwrap();
     let loss: f32 = input.trim().parse().unwrap_or(0.0_f32).clamp(0.0, 50.0);
     
-    test_loopback(duration, latency, loss).await
+    test_loopback(duration, latency, loss, false).await.map(|_| ())
 }
 
 /// Test loopback avec simulation réseau
-async fn test_loopback(duration: u32, latency_ms: u32, loss_rate: f32) -> Result<(), Box<dyn std::error::Error>> {
+async fn test_loopback(duration: u32, latency_ms: u32, loss_rate: f32, quiet: bool) -> Result<LoopbackTestResult, Box<dyn std::error::Error>> {
     let config = NetworkConfig::test_config();
     let mut transport = SimulatedTransport::new(config)?;
     
     // Configuration simulation
     transport.set_simulation_params(latency_ms, loss_rate / 100.0, latency_ms / 4);
     
-    println!("🚀 Démarrage test loopback pour {}s...", duration);
-    println!("📊 Paramètres : latence={}ms, perte={:.1}%", latency_ms, loss_rate);
+    if !quiet {
+        println!("🚀 Démarrage test loopback pour {}s...", duration);
+        println!("📊 Paramètres : latence={}ms, perte={:.1}%", latency_ms, loss_rate);
+    }
     
     // Bind
     transport.bind(9001).await?;
     
     let start = Instant::now();
-    let mut packets_sent = 0;
-    let mut packets_received = 0;
+    let mut packets_sent: u64 = 0;
+    let mut packets_received: u64 = 0;
     
     // Boucle de test
     while start.elapsed().as_secs() < duration as u64 {
         // Crée et envoie un paquet test
         let frame = create_test_frame(packets_sent as u32);
-        let packet = NetworkPacket::new_audio(frame, 12345, packets_sent as u32);
+        let packet = NetworkPacket::new_audio(frame, 12345, packets_sent);
         
         // Envoie vers soi-même
         let target_addr = utils::localhost(9001);
@@ -327,7 +502,7 @@ async fn test_loopback(duration: u32, latency_ms: u32, loss_rate: f32) -> Result
         tokio::time::sleep(Duration::from_millis(20)).await;
         
         // Affichage progressif
-        if packets_sent % 50 == 0 {
+        if !quiet && packets_sent.is_multiple_of(50) {
             println!("📊 Envoyés: {}, Reçus: {}, Perte: {:.1}%", 
                      packets_sent, packets_received, 
                      (packets_sent - packets_received) as f32 / packets_sent as f32 * 100.0);
@@ -336,16 +511,28 @@ async fn test_loopback(duration: u32, latency_ms: u32, loss_rate: f32) -> Result
     
     // Statistiques finales
     let stats = transport.stats();
-    println!("\n📈 Résultats finaux :");
-    println!("   Durée : {}", utils::format_duration(start.elapsed()));
-    println!("   Paquets envoyés : {}", stats.packets_sent);
-    println!("   Paquets reçus : {}", stats.packets_received);
-    println!("   Paquets perdus : {}", stats.packets_lost);
-    println!("   Taux de perte : {:.2}%", stats.loss_percentage());
+    let elapsed = start.elapsed();
+    if !quiet {
+        println!("\n📈 Résultats finaux :");
+        println!("   Durée : {}", utils::format_duration(elapsed));
+        println!("   Paquets envoyés : {}", stats.packets_sent);
+        println!("   Paquets reçus : {}", stats.packets_received);
+        println!("   Paquets perdus : {}", stats.packets_lost);
+        println!("   Taux de perte : {:.2}%", stats.loss_percentage());
+    }
     
     transport.shutdown().await?;
     
-    Ok(())
+    Ok(LoopbackTestResult {
+        duration_ms: elapsed.as_millis(),
+        latency_ms,
+        configured_loss_percent: loss_rate,
+        packets_sent,
+        packets_received,
+        packets_lost: packets_sent - packets_received,
+        loss_percent: (packets_sent - packets_received) as f32 / packets_sent.max(1) as f32 * 100.0,
+        passed: packets_sent > 0 && packets_received > 0,
+    })
 }
 
 /// Test de performance interactif
@@ -366,20 +553,23 @@ async fn interactive_performance_test() -> Result<(), Box<dyn std::error::Error>
     io::stdin().read_line(&mut input).unwrap();
     let port: u16 = input.trim().parse().unwrap_or(9002);
     
-    test_performance(duration, port).await
+    test_performance(duration, port, false).await.map(|_| ())
 }
 
 /// Test de performance réseau
-async fn test_performance(duration: u32, port: u16) -> Result<(), Box<dyn std::error::Error>> {
-    let config = NetworkConfig::lan_optimized();
-    let mut manager = UdpNetworkManager::new(config)?;
-    
-    println!("🚀 Test performance pour {}s sur port {}...", duration, port);
+async fn test_performance(duration: u32, port: u16, quiet: bool) -> Result<PerformanceTestResult, Box<dyn std::error::Error>> {
+    if !quiet {
+        println!("🚀 Test performance pour {}s sur port {}...", duration, port);
+    }
     
-    // Démarrage serveur
-    manager.start_listening(port).await?;
+    // Réserve le port (start_listening bloquerait en attente d'un pair)
+    check_port_available(port)?;
+    let mut transport = UdpTransport::new(NetworkConfig::lan_optimized())?;
+    transport.bind(port).await?;
     
-    println!("✅ Manager en écoute");
+    if !quiet {
+        println!("✅ Transport en écoute");
+    }
     
     let start = Instant::now();
     let mut total_frames = 0;
@@ -398,7 +588,7 @@ async fn test_performance(duration: u32, port: u16) -> Result<(), Box<dyn std::e
         // Simulation intervalle audio (20ms par frame)
         tokio::time::sleep(Duration::from_millis(20)).await;
         
-        if total_frames % 50 == 0 {
+        if !quiet && total_frames % 50 == 0 {
             let elapsed = start.elapsed().as_secs_f32();
             let fps = total_frames as f32 / elapsed;
             let bps = total_bytes as f32 / elapsed;
@@ -410,17 +600,26 @@ async fn test_performance(duration: u32, port: u16) -> Result<(), Box<dyn std::e
     
     // Résultats finaux
     let elapsed = start.elapsed();
-    println!("\n📈 Performance finale :");
-    println!("   Durée : {}", utils::format_duration(elapsed));
-    println!("   Frames traitées : {}", total_frames);
-    println!("   Débit moyen : {:.1} fps", total_frames as f32 / elapsed.as_secs_f32());
-    println!("   Données : {}/s", utils::format_bytes(
-        (total_bytes as f32 / elapsed.as_secs_f32()) as usize
-    ));
+    let frames_per_second = total_frames as f32 / elapsed.as_secs_f32();
+    let bytes_per_second = total_bytes as f32 / elapsed.as_secs_f32();
+    if !quiet {
+        println!("\n📈 Performance finale :");
+        println!("   Durée : {}", utils::format_duration(elapsed));
+        println!("   Frames traitées : {}", total_frames);
+        println!("   Débit moyen : {:.1} fps", frames_per_second);
+        println!("   Données : {}/s", utils::format_bytes(bytes_per_second as usize));
+    }
     
-    manager.disconnect().await?;
+    transport.shutdown().await?;
     
-    Ok(())
+    Ok(PerformanceTestResult {
+        duration_ms: elapsed.as_millis(),
+        port,
+        frames: total_frames,
+        frames_per_second,
+        bytes_per_second,
+        passed: total_frames > 0,
+    })
 }
 
 /// Test serveur P2P interactif
@@ -445,6 +644,7 @@ async fn run_server(port: u16) -> Result<(), Box<dyn std::error::Error>> {
     
     println!("🚀 Démarrage serveur sur port {}...", port);
     
+    check_port_available(port)?;
     manager.start_listening(port).await?;
     
     if let Ok(local_ip) = utils::get_local_ip() {
@@ -501,7 +701,9 @@ async fn run_client(server_str: &str) -> Result<(), Box<dyn std::error::Error>>
                 let frame = create_test_frame(i);
                 
                 match manager.send_audio(frame).await {
-                    Ok(()) => println!("📤 Frame {} envoyée", i),
+                    Ok(SendOutcome::Sent { bytes }) => println!("📤 Frame {} envoyée ({} octets)", i, bytes),
+                    Ok(SendOutcome::Queued { queue_len }) => println!("⏳ Frame {} en file d'envoi ({} frames)", i, queue_len),
+                    Ok(SendOutcome::DroppedDueToBackpressure) => println!("⏭️  Frame {} abandonnée (pair saturé)", i),
                     Err(e) => println!("❌ Erreur envoi frame {} : {}", i, e),
                 }
                 
@@ -520,6 +722,14 @@ async fn run_client(server_str: &str) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+/// Vérifie que le port UDP est utilisable et suggère une alternative sinon
+fn check_port_available(port: u16) -> NetworkResult<()> {
+    utils::check_port_available(port, 100).map_err(|diagnostic| {
+        println!("❌ {}", diagnostic);
+        diagnostic.into()
+    })
+}
+
 /// Affiche les informations réseau système
 async fn show_network_info() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🌐 Informations Réseau");
@@ -536,21 +746,25 @@ async fn show_network_info() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n⚙️  Configuration par défaut :");
     println!("   Port : {}", config.local_port);
     println!("   Buffer socket : {}", utils::format_bytes(config.socket_buffer_size));
-    println!("   Buffer réception : {} paquets", config.receive_buffer_size);
+    println!("   Buffer réception : {}ms visés, {}ms max", config.target_delay_ms, config.max_delay_ms);
     println!("   Timeout : {}", utils::format_duration(config.connection_timeout));
     
-    // Test de connectivité localhost
-    println!("\n🔗 Test connectivité localhost :");
+    // Test de disponibilité des ports UDP
+    println!("\n🔗 Test disponibilité ports UDP :");
     let test_ports = [9001, 8080, 3000];
     
     for port in test_ports {
-        let addr = utils::localhost(port);
-        match tokio::net::TcpListener::bind(addr).await {
-            Ok(_) => println!("   Port {} : ✅ Disponible", port),
-            Err(_) => println!("   Port {} : ❌ Occupé", port),
+        if utils::is_port_available(port) {
+            println!("   Port {} : ✅ Disponible", port);
+        } else {
+            println!("   Port {} : ❌ Occupé", port);
         }
     }
     
+    if let Some(free_port) = utils::find_free_udp_port(9001..=9100) {
+        println!("   💡 Premier port libre (9001-9100) : {}", free_port);
+    }
+    
     // Statistiques système
     println!("\n💻 Système :");
     println!("   Threads CPU : {}", num_cpus::get());
@@ -4,13 +4,16 @@
 // la communication P2P entre deux instances.
 
 use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 use tokio::signal;
 use network::{
-    NetworkConfig, UdpNetworkManager, NetworkManager, 
-    utils, NetworkResult
+    NetworkConfig, UdpNetworkManager, NetworkManager, NetworkPacket, NetworkStats, RedundancyMode, SendOutcome,
+    utils, NetworkResult, NetworkError, CaptureWriter
 };
 use audio::CompressedFrame;
 
@@ -19,8 +22,29 @@ use audio::CompressedFrame;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Enregistre tous les datagrammes dans un fichier pcapng (diagnostic)
+    #[arg(long, global = true)]
+    capture: Option<PathBuf>,
+    /// Format du résumé de connect/open (json : une ligne, en dernier)
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    format: OutputFormat,
 }
 
+/// Format du résumé final
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Texte lisible uniquement
+    Text,
+    /// Résumé JSON sur la dernière ligne de la sortie standard
+    Json,
+}
+
+/// Code de sortie : la connexion au pair a échoué
+const EXIT_CONNECTION_FAILED: i32 = 2;
+
+/// Code de sortie : perte au-delà du seuil `--max-loss`
+const EXIT_LOSS_EXCEEDED: i32 = 3;
+
 #[derive(Subcommand)]
 enum Commands {
     /// Lance un serveur d'écoute
@@ -29,15 +53,51 @@ enum Commands {
         port: u16,
         #[arg(short, long)]
         verbose: bool,
+        /// Demande la redirection du port au routeur (UPnP / NAT-PMP)
+        #[arg(long)]
+        upnp: bool,
     },
     /// Se connecte à un serveur
     Connect {
+        /// Adresse IP:PORT ou code de connexion (ex: R2M02-S1355-3)
         #[arg(short, long)]
         server: String,
         #[arg(short, long)]
         verbose: bool,
         #[arg(short, long, default_value = "10")]
         frames: u32,
+        /// Envoie chaque paquet audio N fois (liens Wi-Fi très instables)
+        #[arg(long, value_name = "N")]
+        duplicate: Option<u8>,
+        /// Perte maximale tolérée en % avant un code de sortie non nul
+        #[arg(long, value_name = "PERCENT")]
+        max_loss: Option<f32>,
+    },
+    /// Ouvre une connexion symétrique : même commande des deux côtés
+    Open {
+        #[arg(short, long, default_value = "9001")]
+        port: u16,
+        /// Adresse IP:PORT du pair à appeler (sinon attend son appel)
+        #[arg(long)]
+        peer: Option<String>,
+        #[arg(short, long)]
+        verbose: bool,
+        #[arg(short, long, default_value = "10")]
+        frames: u32,
+        /// Envoie chaque paquet audio N fois (liens Wi-Fi très instables)
+        #[arg(long, value_name = "N")]
+        duplicate: Option<u8>,
+        /// Perte maximale tolérée en % avant un code de sortie non nul
+        #[arg(long, value_name = "PERCENT")]
+        max_loss: Option<f32>,
+    },
+    /// Auto-diagnostic sans pair : appel simulé de bout en bout
+    SelfTest,
+    /// Affiche le format des paquets (outil de développement)
+    DumpPacketLayout {
+        /// Génère le dissecteur Wireshark Lua au lieu du tableau
+        #[arg(long)]
+        lua: bool,
     },
 }
 
@@ -46,30 +106,206 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     
     match cli.command {
-        Commands::Listen { port, verbose } => {
-            run_server(port, verbose).await?
+        Commands::Listen { port, verbose, upnp } => {
+            run_server(port, verbose, upnp, cli.capture).await?
+        },
+        Commands::Connect { server, verbose, frames, duplicate, max_loss } => {
+            let summary = run_client(&server, verbose, frames, sender_config(duplicate), cli.capture).await?;
+            finish_call(&summary, cli.format, max_loss)?
         },
-        Commands::Connect { server, verbose, frames } => {
-            run_client(&server, verbose, frames).await?
+        Commands::Open { port, peer, verbose, frames, duplicate, max_loss } => {
+            let summary = run_open(port, peer.as_deref(), verbose, frames, sender_config(duplicate), cli.capture).await?;
+            finish_call(&summary, cli.format, max_loss)?
         },
+        Commands::SelfTest => run_self_test().await?,
+        Commands::DumpPacketLayout { lua } => dump_packet_layout(lua),
     }
     
     Ok(())
 }
 
+/// Résumé d'un appel de test (connect/open), pour les tests automatisés
+#[derive(Debug, Serialize)]
+struct CallSummary {
+    /// Sous-commande exécutée
+    command: &'static str,
+    connected: bool,
+    peer: Option<SocketAddr>,
+    /// Raison de l'échec de connexion
+    error: Option<String>,
+    frames_requested: u32,
+    frames_sent: u32,
+    frames_failed: u32,
+    send_success_percent: f32,
+    /// Perte signalée par le pair dans ses heartbeats, si reçus
+    peer_loss_percent: Option<f32>,
+    /// Statistiques finales, avant déconnexion
+    stats: Option<NetworkStats>,
+}
+
+impl CallSummary {
+    /// Résumé d'une connexion échouée
+    fn failed(command: &'static str, peer: Option<SocketAddr>, frames_requested: u32, error: &NetworkError) -> Self {
+        Self {
+            command,
+            connected: false,
+            peer,
+            error: Some(error.to_string()),
+            frames_requested,
+            frames_sent: 0,
+            frames_failed: 0,
+            send_success_percent: 0.0,
+            peer_loss_percent: None,
+            stats: None,
+        }
+    }
+    
+    /// Résumé après l'envoi des frames de test
+    fn sent(command: &'static str, manager: &UdpNetworkManager, frames_requested: u32, sends: (u32, u32)) -> Self {
+        let (frames_sent, frames_failed) = sends;
+        Self {
+            command,
+            connected: true,
+            peer: manager.peer_addr(),
+            error: None,
+            frames_requested,
+            frames_sent,
+            frames_failed,
+            send_success_percent: if frames_requested == 0 {
+                100.0
+            } else {
+                frames_sent as f32 / frames_requested as f32 * 100.0
+            },
+            peer_loss_percent: manager.remote_stats().map(|report| report.loss_percent),
+            stats: Some(manager.network_stats()),
+        }
+    }
+    
+    /// Perte retenue pour le seuil : échecs d'envoi ou perte vue par le pair
+    fn loss_percent(&self) -> f32 {
+        (100.0 - self.send_success_percent).max(self.peer_loss_percent.unwrap_or(0.0))
+    }
+    
+    /// Code de sortie du processus
+    fn exit_code(&self, max_loss: Option<f32>) -> i32 {
+        if !self.connected {
+            EXIT_CONNECTION_FAILED
+        } else if max_loss.is_some_and(|max| self.loss_percent() > max) {
+            EXIT_LOSS_EXCEEDED
+        } else {
+            0
+        }
+    }
+}
+
+/// Affiche le résumé demandé et quitte avec le code correspondant
+fn finish_call(summary: &CallSummary, format: OutputFormat, max_loss: Option<f32>) -> Result<(), serde_json::Error> {
+    let exit_code = summary.exit_code(max_loss);
+    if exit_code == EXIT_LOSS_EXCEEDED {
+        println!("❌ Perte {:.1}% au-delà du seuil de {:.1}%", summary.loss_percent(), max_loss.unwrap_or(0.0));
+    }
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string(summary)?);
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Configuration LAN des commandes qui envoient de l'audio
+fn sender_config(duplicate: Option<u8>) -> NetworkConfig {
+    let mut config = NetworkConfig::lan_optimized();
+    if let Some(copies) = duplicate {
+        config.redundancy = RedundancyMode::Duplicate(copies);
+    }
+    config
+}
+
+/// Lance l'auto-diagnostic et affiche le rapport
+async fn run_self_test() -> NetworkResult<()> {
+    println!("🩺 Auto-diagnostic ({}s d'appel simulé)...", network::SELF_TEST_DURATION.as_secs());
+    
+    let manager = UdpNetworkManager::new(NetworkConfig::lan_optimized())?;
+    let report = manager.self_test().await?;
+    
+    let status = |ok: bool| if ok { "✅" } else { "❌" };
+    println!("   {} Codec", status(report.codec_ok));
+    println!("   {} Buffer (ordre des frames)", status(report.buffer_ok));
+    println!("   {} Perte simulée ({:.0}%)", status(report.loss_handled), report.simulated_loss_rate * 100.0);
+    println!("   📊 {} envoyées, {} reçues, {} perdues",
+             report.frames_sent, report.frames_received, report.frames_lost);
+    for error in &report.errors {
+        println!("   ⚠️  {}", error);
+    }
+    
+    if report.passed() {
+        println!("✅ Auto-diagnostic réussi");
+        Ok(())
+    } else {
+        println!("❌ Auto-diagnostic échoué");
+        std::process::exit(1);
+    }
+}
+
+/// Affiche le format de l'en-tête des paquets ou le dissecteur Lua
+fn dump_packet_layout(lua: bool) {
+    if lua {
+        print!("{}", utils::wireshark_dissector_lua());
+        return;
+    }
+    
+    println!("Protocole Voc v{} - en-tête {} octets (big-endian)",
+             NetworkPacket::CURRENT_PROTOCOL_VERSION, NetworkPacket::HEADER_SIZE);
+    println!("{:>6}  {:>6}  {:<16} Description", "Offset", "Taille", "Champ");
+    for field in NetworkPacket::WIRE_LAYOUT {
+        println!("{:>6}  {:>6}  {:<16} {}", field.offset, field.size, field.name, field.description);
+    }
+    println!("{:>6}  {:>6}  {:<16} Données de la frame", NetworkPacket::HEADER_SIZE, "n", "payload");
+}
+
+/// Installe la capture pcapng si demandée
+fn setup_capture(manager: &mut UdpNetworkManager, capture: Option<PathBuf>) -> NetworkResult<()> {
+    if let Some(path) = capture {
+        manager.set_tap(Some(CaptureWriter::create(&path)?.into_tap()));
+        println!("📼 Capture des paquets dans {}", path.display());
+    }
+    Ok(())
+}
+
 /// Lance un serveur d'écoute
-async fn run_server(port: u16, verbose: bool) -> NetworkResult<()> {
-    let config = NetworkConfig::lan_optimized();
+async fn run_server(port: u16, verbose: bool, upnp: bool, capture: Option<PathBuf>) -> NetworkResult<()> {
+    let mut config = NetworkConfig::lan_optimized();
+    config.port_mapping = upnp;
     let mut manager = UdpNetworkManager::new(config)?;
+    setup_capture(&mut manager, capture)?;
     
     println!("🚀 Démarrage serveur Voc sur port {}...", port);
     
-    manager.start_listening(port).await?;
+    // Vérifie le port UDP avant de démarrer pour proposer une alternative
+    if let Err(diagnostic) = utils::check_port_available(port, 100) {
+        println!("❌ Port UDP {} inutilisable : {} ({})", port, diagnostic.failure.description(), diagnostic.reason);
+        println!("💡 {}", diagnostic.remedy());
+        if let Some(free_port) = diagnostic.alternative_port {
+            println!("💡 Port libre suggéré : voc-client listen --port {}", free_port);
+        }
+        return Err(diagnostic.into());
+    }
     
     if let Ok(local_ip) = utils::get_local_ip() {
+        let code = utils::encode_connection_code(std::net::SocketAddr::new(local_ip, port));
+        println!("🔑 Code de connexion : {}", code);
+    }
+    
+    manager.start_listening(port).await?;
+    
+    let local_ips = utils::local_ip_candidates(None).unwrap_or_default();
+    if !local_ips.is_empty() {
         println!("✅ Serveur prêt !");
         println!("📡 Connexion possible via :");
-        println!("   🌍 {}:{}", local_ip, port);
+        for local_ip in &local_ips {
+            println!("   🌍 {}", std::net::SocketAddr::new(*local_ip, port));
+        }
         println!("   🏠 127.0.0.1:{}", port);
     } else {
         println!("✅ Serveur prêt sur port {} !", port);
@@ -102,65 +338,50 @@ async fn run_server(port: u16, verbose: bool) -> NetworkResult<()> {
     }
     
     println!("🔌 Fermeture du serveur...");
-    manager.disconnect().await?;
+    manager.shutdown().await?;
     println!("👋 Serveur arrêté");
     
     Ok(())
 }
 
 /// Lance un client et se connecte au serveur
-async fn run_client(server_str: &str, verbose: bool, frame_count: u32) -> NetworkResult<()> {
-    let server_addr = utils::parse_address(server_str)?;
-    
-    let config = NetworkConfig::lan_optimized();
+async fn run_client(
+    server_str: &str,
+    verbose: bool,
+    frame_count: u32,
+    config: NetworkConfig,
+    capture: Option<PathBuf>,
+) -> NetworkResult<CallSummary> {
     let mut manager = UdpNetworkManager::new(config)?;
+    setup_capture(&mut manager, capture)?;
     
     println!("🚀 Client Voc");
-    println!("📡 Connexion au serveur {}...", server_addr);
     
     if verbose {
         println!("🔍 Mode verbose activé");
     }
     
-    // Tentative de connexion
-    match manager.connect_to_peer(server_addr).await {
+    // Tentative de connexion : adresse IP:PORT ou, à défaut, code de connexion
+    let connect_result = match utils::parse_address(server_str) {
+        Ok(server_addr) => {
+            println!("📡 Connexion au serveur {}...", server_addr);
+            manager.connect_to_peer(server_addr).await
+        }
+        Err(_) => {
+            println!("🔑 Connexion via le code {}...", server_str);
+            manager.connect_with_code(server_str).await
+        }
+    };
+    
+    let summary = match connect_result {
         Ok(()) => {
             println!("✅ Connexion établie avec succès !");
             
             // Test d'envoi de frames audio
             println!("📤 Envoi de {} frames de test...", frame_count);
             
-            let mut successful_sends = 0;
-            let mut failed_sends = 0;
-            
-            for i in 0..frame_count {
-                let frame = create_test_audio_frame(i);
-                
-                match manager.send_audio(frame).await {
-                    Ok(()) => {
-                        successful_sends += 1;
-                        if verbose {
-                            println!("   📤 Frame {} envoyée ✅", i);
-                        } else if i % 10 == 0 {
-                            print!(".");
-                            io::stdout().flush().unwrap();
-                        }
-                    },
-                    Err(e) => {
-                        failed_sends += 1;
-                        if verbose {
-                            println!("   ❌ Frame {} échouée : {}", i, e);
-                        }
-                    }
-                }
-                
-                // Pause inter-frames (simulation audio temps réel)
-                tokio::time::sleep(Duration::from_millis(20)).await;
-            }
-            
-            if !verbose {
-                println!(); // Nouvelle ligne après les points
-            }
+            let (successful_sends, failed_sends) = send_test_frames(&mut manager, frame_count, verbose).await;
+            let summary = CallSummary::sent("connect", &manager, frame_count, (successful_sends, failed_sends));
             
             // Résultats
             println!("\n📈 Résultats :");
@@ -170,6 +391,7 @@ async fn run_client(server_str: &str, verbose: bool, frame_count: u32) -> Networ
             }
             println!("   📊 Taux de succès : {:.1}%", 
                      (successful_sends as f32 / frame_count as f32) * 100.0);
+            print_uptime(&manager);
             
             // Test de réception (optionnel)
             if verbose {
@@ -203,19 +425,113 @@ async fn run_client(server_str: &str, verbose: bool, frame_count: u32) -> Networ
             }
             
             println!("✅ Test terminé avec succès");
+            summary
         },
         Err(e) => {
             println!("❌ Échec de connexion : {}", e);
-            return Err(e);
+            println!("   État : {}", manager.connection_state().description());
+            return Ok(CallSummary::failed("connect", manager.peer_addr(), frame_count, &e));
         }
-    }
+    };
     
     // Déconnexion propre
     println!("🔌 Déconnexion...");
     manager.disconnect().await?;
     println!("👋 Client fermé");
     
-    Ok(())
+    Ok(summary)
+}
+
+/// Ouvre une connexion sans rôle client/serveur puis envoie des frames de test
+async fn run_open(
+    port: u16,
+    peer: Option<&str>,
+    verbose: bool,
+    frame_count: u32,
+    config: NetworkConfig,
+    capture: Option<PathBuf>,
+) -> NetworkResult<CallSummary> {
+    let mut manager = UdpNetworkManager::new(config)?;
+    setup_capture(&mut manager, capture)?;
+    
+    let peer_addr = peer.map(utils::parse_address).transpose()?;
+    match peer_addr {
+        Some(addr) => println!("📡 Ouverture sur le port {} et appel de {}...", port, addr),
+        None => println!("⏳ Ouverture sur le port {} - en attente d'un pair...", port),
+    }
+    
+    let connected = match manager.open(port, peer_addr).await {
+        Ok(connected) => connected,
+        Err(e) => {
+            println!("❌ Échec de connexion : {}", e);
+            return Ok(CallSummary::failed("open", peer_addr, frame_count, &e));
+        }
+    };
+    println!("✅ Connecté à {}", connected);
+    
+    println!("📤 Envoi de {} frames de test...", frame_count);
+    let (successful_sends, failed_sends) = send_test_frames(&mut manager, frame_count, verbose).await;
+    println!("📈 Frames envoyées : {} (échecs : {})", successful_sends, failed_sends);
+    print_uptime(&manager);
+    let summary = CallSummary::sent("open", &manager, frame_count, (successful_sends, failed_sends));
+    
+    println!("🔌 Déconnexion...");
+    manager.shutdown().await?;
+    Ok(summary)
+}
+
+/// Affiche la durée de la session et le temps connecté cumulé
+fn print_uptime(manager: &UdpNetworkManager) {
+    let stats = manager.network_stats();
+    println!("   ⏱️  Session : {} (connecté {} au total, {} reconnexion(s))",
+             utils::format_duration(stats.current_session_uptime()),
+             utils::format_duration(stats.total_uptime()),
+             stats.reconnection_count);
+}
+
+/// Envoie `frame_count` frames de test au pair connecté
+/// 
+/// Retourne le nombre d'envois réussis et échoués.
+async fn send_test_frames(manager: &mut UdpNetworkManager, frame_count: u32, verbose: bool) -> (u32, u32) {
+    let mut successful_sends = 0;
+    let mut failed_sends = 0;
+    
+    for i in 0..frame_count {
+        let frame = create_test_audio_frame(i);
+        
+        match manager.send_audio(frame).await {
+            Ok(SendOutcome::DroppedDueToBackpressure) => {
+                failed_sends += 1;
+                if verbose {
+                    println!("   ⏭️  Frame {} abandonnée (buffer du pair saturé)", i);
+                }
+            },
+            Ok(_) => {
+                successful_sends += 1;
+                if verbose {
+                    println!("   📤 Frame {} envoyée ✅", i);
+                } else if i % 10 == 0 {
+                    print!(".");
+                    io::stdout().flush().unwrap();
+                }
+            },
+            Err(e) => {
+                failed_sends += 1;
+                if verbose {
+                    println!("   ❌ Frame {} échouée : {}", i, e);
+                }
+            }
+        }
+        
+        // Pause inter-frames (simulation audio temps réel)
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    
+    if !verbose {
+        println!(); // Nouvelle ligne après les points
+    }
+    
+    (successful_sends, failed_sends)
 }
 
 /// Crée une frame audio de test
@@ -259,6 +575,26 @@ mod tests {
         assert_eq!(frame.data[1], 42);
     }
     
+    #[test]
+    fn test_call_summary_exit_code() {
+        let addr: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let failed = CallSummary::failed("connect", Some(addr), 10, &NetworkError::connection_timeout(addr, 2000));
+        assert_eq!(failed.exit_code(None), EXIT_CONNECTION_FAILED);
+        
+        let lossy = CallSummary {
+            connected: true,
+            error: None,
+            frames_sent: 10,
+            send_success_percent: 100.0,
+            peer_loss_percent: Some(8.0),
+            ..failed
+        };
+        assert_eq!(lossy.exit_code(None), 0);
+        assert_eq!(lossy.exit_code(Some(10.0)), 0);
+        assert_eq!(lossy.exit_code(Some(5.0)), EXIT_LOSS_EXCEEDED);
+        assert!(serde_json::to_string(&lossy).unwrap().contains("\"peer_loss_percent\":8.0"));
+    }
+    
     #[test] 
     fn test_different_sequences() {
         let frame1 = create_test_audio_frame(0);
@@ -0,0 +1,247 @@
+// Simulateur hors-ligne de la qualité réseau
+//
+// Fait passer un fichier WAV par toute la chaîne d'un appel, sans second
+// poste : encodage Opus → transport simulé (perte, latence, gigue) → jitter
+// buffer → décodage, puis écrit le résultat dégradé pour l'écouter.
+// Les frames perdues sont reconstruites par FEC ou extrapolées par le
+// décodeur, comme à la lecture.
+// L'émission est cadencée en temps réel : un fichier de 30s prend 30s.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+
+use audio::{io, AudioCodec, AudioConfig, AudioFrame, FrameOrigin, OpusCodec};
+use network::{NetworkConfig, NetworkManager, SimulatedTransport, UdpNetworkManager, utils};
+
+/// Attente des derniers paquets après la fin de l'émission
+const DRAIN_MARGIN: Duration = Duration::from_millis(500);
+
+#[derive(Parser)]
+#[command(author, version, about = "Simule un appel Voc dégradé à partir d'un fichier WAV")]
+struct Cli {
+    /// Fichier WAV d'entrée (48 kHz mono)
+    input: PathBuf,
+    /// Fichier WAV de sortie (audio tel qu'entendu par le pair)
+    output: PathBuf,
+    /// Conditions réseau de base
+    #[arg(long, value_enum, default_value = "wifi")]
+    profile: Profile,
+    /// Taux de perte en % (remplace celui du profil)
+    #[arg(long)]
+    loss: Option<f32>,
+    /// Latence en ms (remplace celle du profil)
+    #[arg(long)]
+    latency: Option<u32>,
+    /// Gigue maximale en ms (remplace celle du profil)
+    #[arg(long)]
+    jitter: Option<u32>,
+    /// Attente des paquets en retard, en ms
+    #[arg(long)]
+    target_delay: Option<u32>,
+    /// Ajoute de la redondance (FEC Opus) calibrée sur le taux de perte
+    #[arg(long)]
+    fec: bool,
+    /// Écrit le rapport de statistiques en JSON dans ce fichier
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+/// Conditions réseau prédéfinies
+#[derive(Clone, Copy, Debug, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Profile {
+    /// Réseau local filaire
+    Lan,
+    /// Wi-Fi domestique
+    Wifi,
+    /// Réseau mobile (4G)
+    Mobile,
+    /// Lien très dégradé
+    Bad,
+}
+
+impl Profile {
+    /// (perte en %, latence en ms, gigue en ms)
+    fn conditions(self) -> (f32, u32, u32) {
+        match self {
+            Profile::Lan => (0.0, 1, 1),
+            Profile::Wifi => (1.0, 10, 20),
+            Profile::Mobile => (3.0, 60, 40),
+            Profile::Bad => (10.0, 150, 100),
+        }
+    }
+}
+
+/// Rapport de simulation
+#[derive(Debug, Serialize)]
+struct SimulationReport {
+    profile: Profile,
+    loss_percent: f32,
+    latency_ms: u32,
+    jitter_ms: u32,
+    target_delay_ms: u32,
+    /// Frames du fichier d'entrée, toutes envoyées
+    frames_sent: u64,
+    /// Frames reçues et décodées
+    frames_received: u64,
+    /// Frames perdues reconstruites depuis la redondance de la suivante
+    frames_fec: u64,
+    /// Frames perdues extrapolées par le décodeur
+    frames_concealed: u64,
+    /// Frames remplacées par du silence (fin de fichier perdue, non décodables)
+    frames_silence: u64,
+    /// Paquets réinsérés dans l'ordre après un retard
+    packets_late: u64,
+    /// Gigue mesurée par le récepteur
+    measured_jitter_ms: f32,
+}
+
+impl SimulationReport {
+    fn concealed_percent(&self) -> f32 {
+        if self.frames_sent == 0 {
+            0.0
+        } else {
+            (self.frames_concealed + self.frames_silence) as f32 / self.frames_sent as f32 * 100.0
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let (profile_loss, profile_latency, profile_jitter) = cli.profile.conditions();
+    let loss_percent = cli.loss.unwrap_or(profile_loss).clamp(0.0, 100.0);
+    let latency_ms = cli.latency.unwrap_or(profile_latency);
+    let jitter_ms = cli.jitter.unwrap_or(profile_jitter);
+
+    let mut audio_config = AudioConfig::default();
+    if cli.fec {
+        audio_config.opus_fec_loss_percent = (loss_percent.ceil() as u8).max(1);
+    }
+    let input = io::read_wav(&cli.input, &audio_config)?;
+    println!("🎧 {} : {} frames ({:.1}s)",
+             cli.input.display(), input.len(), input.len() as f32 * audio_config.frame_duration_ms as f32 / 1000.0);
+    println!("📡 Profil {:?} : perte {:.1}%, latence {}ms, gigue {}ms",
+             cli.profile, loss_percent, latency_ms, jitter_ms);
+
+    let mut config = NetworkConfig::wan_optimized();
+    if let Some(delay_ms) = cli.target_delay {
+        config.target_delay_ms = delay_ms;
+    }
+    let target_delay_ms = config.target_delay_ms;
+
+    let (mut caller_transport, callee_transport) = SimulatedTransport::pair(config.clone())?;
+    caller_transport.set_simulation_params(latency_ms, loss_percent / 100.0, jitter_ms);
+    let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport))?;
+    let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport))?;
+
+    let (dialed, accepted) = tokio::join!(
+        caller.open(9001, Some(utils::localhost(9002))),
+        callee.open(9002, None),
+    );
+    dialed?;
+    accepted?;
+
+    let mut encoder = OpusCodec::new(audio_config.clone())?;
+    let mut decoder = OpusCodec::new(audio_config.clone())?;
+    let frame_duration = Duration::from_millis(audio_config.frame_duration_ms as u64);
+    let frame_len = audio_config.samples_per_frame() * audio_config.channels as usize;
+
+    // Frames décodées, indexées par numéro de séquence réseau (à partir de 1)
+    let mut decoded: Vec<Option<AudioFrame>> = vec![None; input.len()];
+    let deadline = Instant::now() + frame_duration * input.len() as u32;
+
+    let send_side = async {
+        let mut ticker = tokio::time::interval(frame_duration);
+        for frame in &input {
+            ticker.tick().await;
+            caller.send_audio(encoder.encode(frame)?).await?;
+        }
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    let receive_side = async {
+        let mut next_index = 0;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now()) + DRAIN_MARGIN;
+            let compressed = match tokio::time::timeout(wait, callee.receive_audio()).await {
+                Ok(result) => result?,
+                Err(_) => break,
+            };
+            let index = compressed.sequence_number.saturating_sub(1) as usize;
+            // Après une longue rafale de pertes, l'état prédictif d'Opus est périmé
+            if callee.take_stream_resync().is_some() {
+                decoder.reset()?;
+            }
+            // Frames manquantes avant celle-ci : extrapolées, sauf la
+            // dernière, reconstruite depuis la redondance du paquet reçu
+            if index < decoded.len() {
+                while next_index < index {
+                    let sequence_number = next_index as u64 + 1;
+                    let missing = if next_index + 1 == index {
+                        decoder.decode_fec(&compressed, sequence_number)
+                    } else {
+                        decoder.conceal(frame_len, sequence_number)
+                    };
+                    if let Ok(frame) = missing {
+                        decoded[next_index] = Some(frame);
+                    }
+                    next_index += 1;
+                }
+                next_index = next_index.max(index + 1);
+            }
+            match (decoded.get_mut(index), decoder.decode(&compressed)) {
+                (Some(slot), Ok(frame)) => *slot = Some(frame),
+                (_, Err(e)) => println!("⚠️  Frame {} non décodable : {}", compressed.sequence_number, e),
+                (None, Ok(_)) => {}
+            }
+        }
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    let (sent, received) = tokio::join!(send_side, receive_side);
+    sent?;
+    received?;
+
+    let stats = callee.network_stats();
+    caller.disconnect().await?;
+    callee.disconnect().await?;
+
+    // Frames jamais reçues ni reconstruites (fin de fichier perdue) : silence
+    let output: Vec<AudioFrame> = decoded.into_iter()
+        .enumerate()
+        .map(|(index, frame)| frame.unwrap_or_else(|| AudioFrame::silence(frame_len, index as u64 + 1)))
+        .collect();
+    io::write_wav(&cli.output, &output, &audio_config)?;
+    let count = |origin| output.iter().filter(|frame| frame.origin == origin).count() as u64;
+
+    let report = SimulationReport {
+        profile: cli.profile,
+        loss_percent,
+        latency_ms,
+        jitter_ms,
+        target_delay_ms,
+        frames_sent: input.len() as u64,
+        frames_received: count(FrameOrigin::Normal),
+        frames_fec: count(FrameOrigin::Fec),
+        frames_concealed: count(FrameOrigin::Concealed),
+        frames_silence: count(FrameOrigin::Silence),
+        packets_late: stats.packets_late,
+        measured_jitter_ms: stats.avg_jitter_ms,
+    };
+
+    println!("📊 {} envoyées, {} reçues, {} reconstruites (FEC), {} masquées ({:.1}%), {} réordonnées, gigue mesurée {:.1}ms",
+             report.frames_sent, report.frames_received, report.frames_fec,
+             report.frames_concealed + report.frames_silence,
+             report.concealed_percent(), report.packets_late, report.measured_jitter_ms);
+    println!("💾 Résultat : {}", cli.output.display());
+
+    if let Some(path) = &cli.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("📝 Rapport : {}", path.display());
+    }
+
+    Ok(())
+}
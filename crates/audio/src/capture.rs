@@ -7,15 +7,22 @@
 //! Elle supporte Windows (WASAPI), macOS (CoreAudio), et Linux (ALSA/PulseAudio).
 
 use async_trait::async_trait;
-use cpal::{Device, Stream, SupportedStreamConfig, SampleFormat};
-use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
+use cpal::{BufferSize, Device, FromSample, HostId, Sample, SizedSample, Stream, StreamConfig, SupportedStreamConfig, SampleFormat};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
+use tokio::time::timeout;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 
 use crate::{
-    AudioCapture, AudioFrame, AudioConfig, AudioError, AudioResult,
+    AudioCapture, AudioFrame, AudioConfig, AudioError, AudioResult, DeviceInfo, DeviceSelection,
+    HostModeReport,
 };
+use crate::{devices, samples};
+use crate::resample::Resampler;
+use crate::mixer::SidetoneTap;
+use crate::realtime::CallbackPromotion;
 
 /// Implémentation de capture audio avec cpal
 /// 
@@ -55,6 +62,42 @@ pub struct CpalCapture {
     
     /// Nom du périphérique pour debug
     device_name: String,
+    
+    /// Résultat de la sélection (préférence trouvée ou repli sur le défaut)
+    selection: DeviceSelection,
+    
+    /// Hôte audio du périphérique (`AudioConfig::audio_host`)
+    host_id: HostId,
+    
+    /// Mode d'accès obtenu à l'ouverture du dernier stream
+    host_mode: Option<HostModeReport>,
+    
+    /// Frames par callback accordées par le périphérique (0 = pas encore connu)
+    granted_buffer_frames: Arc<AtomicU32>,
+    
+    /// Configuration et format du dernier stream construit
+    stream_params: Option<(StreamConfig, SampleFormat)>,
+    
+    /// Destination du sidetone (micro renvoyé dans le casque), si activé
+    sidetone: Option<SidetoneTap>,
+    
+    /// Micro coupé par `pause()` (partagé avec le callback)
+    paused: Arc<AtomicBool>,
+    
+    /// Nombre de callbacks reçus, surveillé par le watchdog de `next_frame()`
+    callback_count: Arc<AtomicU64>,
+    
+    /// Streams reconstruits après un blocage de la capture
+    stall_recoveries: u32,
+    
+    /// Configuration native changée, signalée par le callback d'erreur
+    route_changed: Arc<AtomicBool>,
+    
+    /// Streams reconstruits après un changement de configuration native
+    route_changes: u32,
+    
+    /// Périphérique débranché, signalé par le callback d'erreur
+    disconnected: Arc<AtomicBool>,
 }
 
 impl CpalCapture {
@@ -70,20 +113,35 @@ impl CpalCapture {
     /// - `AudioError::NoDeviceFound` si aucun microphone n'est disponible
     /// - `AudioError::ConfigError` si la configuration n'est pas supportée
     pub fn new(config: AudioConfig) -> AudioResult<Self> {
-        // Obtient l'host audio par défaut du système
-        let host = cpal::default_host();
+        Self::with_preferred_device(config, None)
+    }
+    
+    /// Crée une instance de capture sur le microphone préféré
+    /// 
+    /// Le nom est comparé aux périphériques disponibles avec une
+    /// correspondance approximative (voir `devices::match_device_name`).
+    /// S'il est introuvable, le périphérique par défaut est utilisé et
+    /// `device_selection()` porte l'avertissement.
+    /// 
+    /// # Arguments
+    /// * `config` - Configuration audio à utiliser
+    /// * `preferred_name` - Nom du microphone préféré (`AudioDevicePreferences::input_name`)
+    /// 
+    /// # Erreurs
+    /// - `AudioError::NoDeviceFound` si aucun microphone n'est disponible
+    pub fn with_preferred_device(config: AudioConfig, preferred_name: Option<&str>) -> AudioResult<Self> {
+        // Trouve le périphérique préféré, ou celui par défaut
+        let (host, host_warning) = devices::select_host(config.audio_host.as_deref());
+        if let Some(warning) = host_warning {
+            println!("⚠️  {}", warning);
+        }
+        let (device, selection) = devices::select_input_device(&host, preferred_name)?;
+        if let Some(warning) = selection.warning() {
+            println!("⚠️  {}", warning);
+        }
         
-        // Trouve le périphérique d'entrée par défaut
-        let device = host
-            .default_input_device()
-            .ok_or(AudioError::NoDeviceFound)?;
-            
         // Récupère la description du périphérique pour debug
-        // description() remplace name() et fournit des informations plus complètes
-        let device_name = device.description()
-            .ok()
-            .map(|desc| desc.name().to_string())
-            .unwrap_or_else(|| "Périphérique inconnu".to_string());
+        let device_name = devices::device_name(&device);
             
         // Crée le channel pour communiquer entre le callback et async
         let (frame_sender, frame_receiver) = mpsc::channel(10);
@@ -99,12 +157,71 @@ impl CpalCapture {
             is_recording: false,
             sequence_counter: Arc::new(Mutex::new(0)),
             device_name,
+            selection,
+            host_id: host.id(),
+            host_mode: None,
+            granted_buffer_frames: Arc::new(AtomicU32::new(0)),
+            stream_params: None,
+            sidetone: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            callback_count: Arc::new(AtomicU64::new(0)),
+            stall_recoveries: 0,
+            route_changed: Arc::new(AtomicBool::new(false)),
+            route_changes: 0,
+            disconnected: Arc::new(AtomicBool::new(false)),
         })
     }
     
+    /// Résultat de la sélection du microphone
+    pub fn device_selection(&self) -> &DeviceSelection {
+        &self.selection
+    }
+    
+    /// Mode d'accès obtenu auprès de l'hôte audio (`AudioConfig::latency_mode`)
+    /// 
+    /// Connu après `start()` ; `None` avant.
+    pub fn host_mode(&self) -> Option<&HostModeReport> {
+        self.host_mode.as_ref()
+    }
+    
+    /// Taille de buffer accordée par le périphérique, en frames par callback
+    /// 
+    /// Connue après le premier callback du stream ; `None` avant.
+    pub fn device_buffer_frames(&self) -> Option<u32> {
+        match self.granted_buffer_frames.load(Ordering::Relaxed) {
+            0 => None,
+            frames => Some(frames),
+        }
+    }
+    
+    /// Nombre de streams reconstruits par le watchdog de capture
+    /// 
+    /// Voir `AudioConfig::capture_stall_timeout_ms`.
+    pub fn stall_recoveries(&self) -> u32 {
+        self.stall_recoveries
+    }
+    
+    /// Nombre de streams reconstruits après un changement de route
+    /// 
+    /// Un casque Bluetooth passant en profil HFP pour le micro change la
+    /// fréquence et les canaux du périphérique en cours d'appel.
+    pub fn route_changes(&self) -> u32 {
+        self.route_changes
+    }
+    
+    /// Envoie aussi les échantillons du micro à `tap` (sidetone)
+    /// 
+    /// Le tap vient de `CpalPlayback::sidetone_tap`. Les échantillons lui
+    /// sont transmis à chaque callback, sans attendre une frame complète.
+    /// Pris en compte au prochain `start()` ; `None` le retire.
+    pub fn set_sidetone_tap(&mut self, tap: Option<SidetoneTap>) {
+        self.sidetone = tap;
+    }
+    
     /// Vérifie que la configuration audio est supportée par le périphérique
     /// 
-    /// Cette fonction valide que le périphérique peut capturer avec nos paramètres.
+    /// Cette fonction lit la configuration native du périphérique, que le
+    /// callback ramène ensuite aux paramètres de la chaîne.
     fn validate_config(&self) -> AudioResult<SupportedStreamConfig> {
         // Obtient la configuration par défaut du périphérique
         let default_config = self.device
@@ -116,133 +233,250 @@ impl CpalCapture {
         println!("   Channels: {}", default_config.channels());
         println!("   Sample format: {:?}", default_config.sample_format());
         
-        // Vérifie que le périphérique supporte notre sample rate
-        let supported_rates = self.device
-            .supported_input_configs()
-            .map_err(|e| AudioError::ConfigError(format!("Impossible d'obtenir configs supportées: {}", e)))?;
-        
-        let mut config_found = false;
-        for supported_range in supported_rates {
-            let min_rate = supported_range.min_sample_rate();
-            let max_rate = supported_range.max_sample_rate();
-            
-            if self.config.sample_rate >= min_rate && self.config.sample_rate <= max_rate {
-                config_found = true;
-                break;
-            }
-        }
-        
-        if !config_found {
-            return Err(AudioError::ConfigError(format!(
-                "Sample rate {} Hz non supporté par le périphérique", 
-                self.config.sample_rate
-            )));
+        // La configuration native est gardée telle quelle : fréquence et
+        // canaux sont convertis dans le callback (profil HFP d'un casque
+        // Bluetooth à 16 kHz, par exemple)
+        if default_config.sample_rate() != self.config.sample_rate {
+            println!("   Rééchantillonné vers {} Hz", self.config.sample_rate);
         }
-        
-        // Utilise la configuration par défaut avec nos paramètres si possible
-        // Pour l'instant, on accepte la config du périphérique et on adapte notre côté
         println!("✅ Configuration validée - utilise la config par défaut");
         
         Ok(default_config)
     }
     
     /// Construit et configure le stream audio
+    /// 
+    /// La taille de buffer découle du mode demandé (`AudioConfig::latency_mode`)
+    /// et de `AudioConfig::device_buffer_frames`, bornée à la plage du
+    /// périphérique ; s'il la refuse malgré tout, le stream est reconstruit
+    /// avec la taille par défaut du pilote, en mode partagé.
     fn build_stream(&mut self) -> AudioResult<Stream> {
-        let stream_config = self.validate_config()?;
+        let supported_config = self.validate_config()?;
+        let sample_format = supported_config.sample_format();
+        let mut stream_config = supported_config.config();
+        let (buffer_size, mut host_mode) = devices::negotiate_latency_mode(
+            self.config.latency_mode,
+            self.config.device_buffer_frames,
+            self.host_id,
+            supported_config.buffer_size(),
+        );
+        stream_config.buffer_size = buffer_size;
         
+        println!("🎵 Démarrage capture :");
+        println!("   Échantillons par frame : {}", self.config.samples_per_frame());
+        println!("   Durée par frame : {}ms", self.config.frame_duration_ms);
+        println!("   Buffer périphérique : {:?}", stream_config.buffer_size);
+        
+        let stream = match (self.build_stream_with(&stream_config, sample_format), stream_config.buffer_size) {
+            (Err(e), BufferSize::Fixed(frames)) => {
+                println!("⚠️  Buffer de {} frames refusé ({}) - taille par défaut du pilote", frames, e);
+                host_mode.fall_back_to_shared(format!("buffer de {} frames refusé", frames));
+                stream_config.buffer_size = BufferSize::Default;
+                self.build_stream_with(&stream_config, sample_format)?
+            }
+            (result, _) => result?,
+        };
+        if host_mode.is_degraded() {
+            println!("⚠️  Hôte {}", host_mode);
+        } else {
+            println!("   Hôte {}", host_mode);
+        }
+        self.stream_params = Some((stream_config, sample_format));
+        self.host_mode = Some(host_mode);
+        Ok(stream)
+    }
+    
+    /// Construit le stream cpal avec une configuration donnée
+    fn build_stream_with(&self, stream_config: &StreamConfig, sample_format: SampleFormat) -> AudioResult<Stream> {
+        // Chaque format est converti en f32 dès le callback
+        match sample_format {
+            SampleFormat::I8 => self.build_typed_stream::<i8>(stream_config),
+            SampleFormat::I16 => self.build_typed_stream::<i16>(stream_config),
+            SampleFormat::I24 => self.build_typed_stream::<cpal::I24>(stream_config),
+            SampleFormat::I32 => self.build_typed_stream::<i32>(stream_config),
+            SampleFormat::I64 => self.build_typed_stream::<i64>(stream_config),
+            SampleFormat::U8 => self.build_typed_stream::<u8>(stream_config),
+            SampleFormat::U16 => self.build_typed_stream::<u16>(stream_config),
+            SampleFormat::U24 => self.build_typed_stream::<cpal::U24>(stream_config),
+            SampleFormat::U32 => self.build_typed_stream::<u32>(stream_config),
+            SampleFormat::U64 => self.build_typed_stream::<u64>(stream_config),
+            SampleFormat::F32 => self.build_typed_stream::<f32>(stream_config),
+            SampleFormat::F64 => self.build_typed_stream::<f64>(stream_config),
+            _ => Err(AudioError::ConfigError(format!("Format d'échantillon non supporté : {:?}", sample_format))),
+        }
+    }
+    
+    /// Construit le stream d'entrée pour un type d'échantillon donné
+    fn build_typed_stream<T>(&self, stream_config: &StreamConfig) -> AudioResult<Stream>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
         // Clone des variables nécessaires pour le callback
         let sender = self.frame_sender.as_ref().unwrap().clone();
-        let samples_per_frame = self.config.samples_per_frame();
+        let channels = self.config.channels as usize;
+        let frame_len = self.config.samples_per_frame() * channels;
         let sequence_counter = Arc::clone(&self.sequence_counter);
         
-        println!("🎵 Démarrage capture :");
-        println!("   Échantillons par frame : {}", samples_per_frame);
-        println!("   Durée par frame : {}ms", self.config.frame_duration_ms);
+        // Taille réellement accordée, relevée à chaque callback
+        let granted_buffer_frames = Arc::clone(&self.granted_buffer_frames);
+        granted_buffer_frames.store(0, Ordering::Relaxed);
+        let device_channels = stream_config.channels as usize;
+        if device_channels != channels {
+            println!("   Canaux : {} côté périphérique, ramenés à {}", device_channels, channels);
+        }
+        
+        // Fréquence native ramenée à celle de la chaîne
+        let mut resampler = Resampler::new(stream_config.sample_rate, self.config.sample_rate, self.config.channels);
+        let mut native_buffer = Vec::with_capacity(frame_len);
+        let mut converted_buffer = Vec::with_capacity(frame_len);
         
         // Buffer pour accumuler les échantillons
-        let mut sample_buffer = Vec::with_capacity(samples_per_frame);
-        
-        // Détermine le format d'échantillons du périphérique
-        let sample_format = stream_config.sample_format();
-        
-        // Construit le stream selon le format d'échantillons
-        let stream = match sample_format {
-            SampleFormat::F32 => {
-                self.device.build_input_stream(
-                    &stream_config.config(),
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        Self::process_samples_f32(
-                            data, 
-                            &mut sample_buffer, 
-                            samples_per_frame,
-                            &sender,
-                            &sequence_counter
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio : {}", err);
-                    },
-                    None
-                )?
-            },
-            SampleFormat::I16 => {
-                self.device.build_input_stream(
-                    &stream_config.config(),
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        Self::process_samples_i16(
-                            data, 
-                            &mut sample_buffer, 
-                            samples_per_frame,
-                            &sender,
-                            &sequence_counter
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio : {}", err);
-                    },
-                    None
-                )?
+        let mut sample_buffer = Vec::with_capacity(frame_len);
+        
+        // Horloge de capture : échantillons par canal, à la fréquence de la
+        // chaîne, reçus depuis la construction du stream (frames perdues comprises)
+        let mut sample_clock = 0u64;
+        
+        // Priorité temps réel du thread cpal, si activée
+        let mut promotion = CallbackPromotion::new(&self.config);
+        
+        // Micro coupé : le callback livre du silence
+        let paused = Arc::clone(&self.paused);
+        
+        // Activité du callback, pour le watchdog
+        let callback_count = Arc::clone(&self.callback_count);
+        
+        // Sidetone : échantillons convertis transmis à chaque callback
+        let sidetone = self.sidetone.clone();
+        
+        // Changement de route : comparé à la configuration native courante
+        let route_changed = Arc::clone(&self.route_changed);
+        let disconnected = Arc::clone(&self.disconnected);
+        let device = self.device.clone();
+        let built_config = stream_config.clone();
+        
+        let stream = self.device.build_input_stream(
+            stream_config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                promotion.ensure();
+                callback_count.fetch_add(1, Ordering::Relaxed);
+                granted_buffer_frames.store((data.len() / device_channels) as u32, Ordering::Relaxed);
+                let muted = paused.load(Ordering::Relaxed);
+                Self::convert_samples(
+                    data,
+                    device_channels,
+                    channels,
+                    muted,
+                    &mut native_buffer,
+                    &mut resampler,
+                    &mut converted_buffer,
+                );
+                if let Some(tap) = sidetone.as_ref().filter(|_| !muted) {
+                    tap.push(&converted_buffer);
+                }
+                Self::process_samples(
+                    &converted_buffer,
+                    channels,
+                    &mut sample_buffer, 
+                    frame_len,
+                    &sender,
+                    &sequence_counter,
+                    &mut sample_clock
+                );
             },
-            SampleFormat::U16 => {
-                self.device.build_input_stream(
-                    &stream_config.config(),
-                    move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                        Self::process_samples_u16(
-                            data, 
-                            &mut sample_buffer, 
-                            samples_per_frame,
-                            &sender,
-                            &sequence_counter
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio : {}", err);
-                    },
-                    None
-                )?
+            move |err| {
+                eprintln!("❌ Erreur stream audio : {}", err);
+                if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                    disconnected.store(true, Ordering::Relaxed);
+                } else if devices::native_config_changed(device.default_input_config().ok(), &built_config) {
+                    route_changed.store(true, Ordering::Relaxed);
+                }
             },
-            _ => return Err(AudioError::ConfigError(format!("Format d'échantillon non supporté : {:?}", sample_format))),
-        };
+            None
+        )?;
         
         Ok(stream)
     }
     
-    /// Traite les échantillons f32 depuis cpal
+    /// Reconstruit le stream après un blocage de la capture
+    fn rebuild_stalled_stream(&mut self) {
+        println!("⚠️  Capture bloquée depuis {}ms - reconstruction du stream", self.config.capture_stall_timeout_ms);
+        self.stall_recoveries += 1;
+        self.restart_stream();
+    }
+    
+    /// Reconstruit le stream avec la nouvelle configuration native du micro
+    fn rebuild_after_route_change(&mut self) {
+        println!("🔀 Configuration du micro changée - reconstruction du stream");
+        self.route_changes += 1;
+        self.restart_stream();
+    }
+    
+    /// Remplace le stream en cours par un stream neuf, démarré
+    /// 
+    /// Un échec est seulement affiché : le prochain blocage retentera.
+    fn restart_stream(&mut self) {
+        self.stream = None;
+        match self.build_stream() {
+            Ok(stream) => match stream.play() {
+                Ok(()) => self.stream = Some(stream),
+                Err(e) => eprintln!("❌ Redémarrage du stream de capture impossible : {}", e),
+            },
+            Err(e) => eprintln!("❌ Reconstruction du stream de capture impossible : {}", e),
+        }
+    }
+    
+    /// Convertit les échantillons du callback vers le format de la chaîne
+    /// 
+    /// Chaque trame de `device_channels` canaux est ramenée aux `channels`
+    /// canaux de la configuration (downmix d'un micro multicanal), ou
+    /// remplacée par du silence si le micro est coupé (`muted`), puis le tout
+    /// est rééchantillonné à `AudioConfig::sample_rate` dans `converted`.
+    /// Cette fonction est appelée dans le callback audio (thread temps réel).
+    fn convert_samples<T>(
+        data: &[T],
+        device_channels: usize,
+        channels: usize,
+        muted: bool,
+        native: &mut Vec<f32>,
+        resampler: &mut Resampler,
+        converted: &mut Vec<f32>,
+    ) where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        native.clear();
+        for device_frame in data.chunks(device_channels) {
+            if muted {
+                native.resize(native.len() + channels, 0.0);
+            } else {
+                samples::push_frame(device_frame, channels, native);
+            }
+        }
+        converted.clear();
+        resampler.process(native, converted);
+    }
+    
+    /// Découpe les échantillons convertis en frames et les transmet
     /// 
     /// Cette fonction est appelée dans le callback audio (thread temps réel).
     /// Elle doit être très rapide pour éviter les coupures.
-    fn process_samples_f32(
-        data: &[f32],
+    fn process_samples(
+        converted: &[f32],
+        channels: usize,
         sample_buffer: &mut Vec<f32>,
-        samples_per_frame: usize,
+        frame_len: usize,
         sender: &mpsc::Sender<AudioFrame>,
         sequence_counter: &Arc<Mutex<u64>>,
+        sample_clock: &mut u64,
     ) {
-        for &sample in data {
-            sample_buffer.push(sample);
+        for converted_frame in converted.chunks(channels) {
+            sample_buffer.extend_from_slice(converted_frame);
+            *sample_clock += 1;
             
             // Si on a assez d'échantillons pour une frame
-            if sample_buffer.len() >= samples_per_frame {
+            if sample_buffer.len() >= frame_len {
                 // Obtient le numéro de séquence (non-bloquant)
                 let sequence = if let Ok(mut counter) = sequence_counter.try_lock() {
                     let seq = *counter;
@@ -252,11 +486,12 @@ impl CpalCapture {
                     0 // Fallback si le lock échoue (rare)
                 };
                 
-                // Crée la frame audio
-                let frame = AudioFrame::new(
+                // Crée la frame audio, datée de son premier échantillon
+                let mut frame = AudioFrame::new(
                     sample_buffer.drain(..).collect(),
                     sequence
                 );
+                frame.media_timestamp = Some(*sample_clock - (frame_len / channels) as u64);
                 
                 // Envoie la frame (non-bloquant)
                 if let Err(_) = sender.try_send(frame) {
@@ -266,70 +501,6 @@ impl CpalCapture {
             }
         }
     }
-    
-    /// Traite les échantillons i16 depuis cpal (conversion vers f32)
-    fn process_samples_i16(
-        data: &[i16],
-        sample_buffer: &mut Vec<f32>,
-        samples_per_frame: usize,
-        sender: &mpsc::Sender<AudioFrame>,
-        sequence_counter: &Arc<Mutex<u64>>,
-    ) {
-        for &sample in data {
-            // Convertit i16 vers f32 (plage [-1.0, 1.0])
-            let f32_sample = sample as f32 / i16::MAX as f32;
-            sample_buffer.push(f32_sample);
-            
-            if sample_buffer.len() >= samples_per_frame {
-                let sequence = if let Ok(mut counter) = sequence_counter.try_lock() {
-                    let seq = *counter;
-                    *counter += 1;
-                    seq
-                } else {
-                    0
-                };
-                
-                let frame = AudioFrame::new(
-                    sample_buffer.drain(..).collect(),
-                    sequence
-                );
-                
-                let _ = sender.try_send(frame);
-            }
-        }
-    }
-    
-    /// Traite les échantillons u16 depuis cpal (conversion vers f32)
-    fn process_samples_u16(
-        data: &[u16],
-        sample_buffer: &mut Vec<f32>,
-        samples_per_frame: usize,
-        sender: &mpsc::Sender<AudioFrame>,
-        sequence_counter: &Arc<Mutex<u64>>,
-    ) {
-        for &sample in data {
-            // Convertit u16 vers f32 (plage [-1.0, 1.0])
-            let f32_sample = (sample as f32 / u16::MAX as f32) * 2.0 - 1.0;
-            sample_buffer.push(f32_sample);
-            
-            if sample_buffer.len() >= samples_per_frame {
-                let sequence = if let Ok(mut counter) = sequence_counter.try_lock() {
-                    let seq = *counter;
-                    *counter += 1;
-                    seq
-                } else {
-                    0
-                };
-                
-                let frame = AudioFrame::new(
-                    sample_buffer.drain(..).collect(),
-                    sequence
-                );
-                
-                let _ = sender.try_send(frame);
-            }
-        }
-    }
 }
 
 #[async_trait]
@@ -341,8 +512,11 @@ impl AudioCapture for CpalCapture {
         
         println!("🚀 Démarrage de la capture audio...");
         
-        // Construit et démarre le stream
-        let stream = self.build_stream()?;
+        // Stream ouvert par `prepare`, sinon construit maintenant
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => self.build_stream()?,
+        };
         stream.play()?;
         
         self.stream = Some(stream);
@@ -352,8 +526,25 @@ impl AudioCapture for CpalCapture {
         Ok(())
     }
     
+    async fn prepare(&mut self) -> AudioResult<()> {
+        if self.is_recording || self.stream.is_some() {
+            return Ok(()); // Déjà démarré ou en veille
+        }
+        
+        // Certains backends démarrent le stream dès sa construction
+        let stream = self.build_stream()?;
+        if let Err(e) = stream.pause() {
+            println!("⚠️  Mise en veille du stream impossible : {}", e);
+        }
+        self.stream = Some(stream);
+        
+        println!("💤 Capture audio en veille");
+        Ok(())
+    }
+    
     async fn stop(&mut self) -> AudioResult<()> {
         if !self.is_recording {
+            self.stream = None; // Ferme un stream en veille
             return Ok(()); // Déjà arrêté
         }
         
@@ -365,22 +556,70 @@ impl AudioCapture for CpalCapture {
         }
         
         self.is_recording = false;
+        self.paused.store(false, Ordering::Relaxed);
         
         println!("✅ Capture audio arrêtée");
         Ok(())
     }
     
+    async fn pause(&mut self) -> AudioResult<()> {
+        if !self.is_recording {
+            return Err(AudioError::InitializationError("Capture non démarrée".to_string()));
+        }
+        self.paused.store(true, Ordering::Relaxed);
+        println!("🔇 Micro coupé");
+        Ok(())
+    }
+    
+    async fn resume(&mut self) -> AudioResult<()> {
+        if !self.is_recording {
+            return Err(AudioError::InitializationError("Capture non démarrée".to_string()));
+        }
+        self.paused.store(false, Ordering::Relaxed);
+        println!("🎤 Micro rétabli");
+        Ok(())
+    }
+    
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    
     async fn next_frame(&mut self) -> AudioResult<AudioFrame> {
-        // Récupère le receiver depuis le mutex
-        let mut receiver_guard = self.frame_receiver.lock().await;
-        let receiver = receiver_guard.as_mut()
-            .ok_or(AudioError::InitializationError("Receiver non initialisé".to_string()))?;
-        
-        // Attend la prochaine frame
-        match receiver.recv().await {
-            Some(frame) => Ok(frame),
-            None => Err(AudioError::DeviceDisconnected),
+        // Micro débranché : à rouvrir par l'appelant (voir `AudioDeviceManager`)
+        if self.disconnected.load(Ordering::Relaxed) {
+            return Err(AudioError::DeviceDisconnected);
+        }
+        // Route changée (casque Bluetooth en HFP) : nouveau stream, même format de frames
+        if self.is_recording && self.route_changed.swap(false, Ordering::Relaxed) {
+            self.rebuild_after_route_change();
+        }
+        let callbacks_before = self.callback_count.load(Ordering::Relaxed);
+        {
+            // Récupère le receiver depuis le mutex
+            let mut receiver_guard = self.frame_receiver.lock().await;
+            let receiver = receiver_guard.as_mut()
+                .ok_or(AudioError::InitializationError("Receiver non initialisé".to_string()))?;
+            
+            // Attend la prochaine frame, au plus le délai du watchdog
+            let received = match self.config.capture_stall_timeout() {
+                Some(stall_timeout) => timeout(stall_timeout, receiver.recv()).await,
+                None => Ok(receiver.recv().await),
+            };
+            match received {
+                Ok(Some(frame)) => return Ok(frame),
+                Ok(None) => return Err(AudioError::DeviceDisconnected),
+                Err(_) => {}
+            }
+        }
+        
+        // Aucun callback pendant tout le délai : le stream a été invalidé
+        // par un changement de route, ou suspendu sans prévenir par le système
+        if self.is_recording && self.route_changed.swap(false, Ordering::Relaxed) {
+            self.rebuild_after_route_change();
+        } else if self.is_recording && self.callback_count.load(Ordering::Relaxed) == callbacks_before {
+            self.rebuild_stalled_stream();
         }
+        Err(AudioError::Timeout)
     }
     
     fn is_recording(&self) -> bool {
@@ -390,6 +629,14 @@ impl AudioCapture for CpalCapture {
     fn device_info(&self) -> String {
         self.device_name.clone()
     }
+    
+    /// Paramètres du stream en cours, ou ceux qu'utiliserait `start()`
+    fn device_details(&self) -> DeviceInfo {
+        let stream = self.stream_params.clone().or_else(|| {
+            self.device.default_input_config().ok().map(|config| (config.config(), config.sample_format()))
+        });
+        devices::describe_device(self.host_id, &self.device_name, stream, self.device_buffer_frames())
+    }
 }
 
 // Implémentation de Drop pour nettoyer proprement
@@ -418,6 +665,7 @@ mod tests {
             Ok(capture) => {
                 assert!(!capture.is_recording());
                 assert!(!capture.device_info().is_empty());
+                assert_eq!(capture.device_details().name, capture.device_info());
             },
             Err(AudioError::NoDeviceFound) => {
                 // Acceptable dans un environnement de test sans audio
@@ -14,7 +14,7 @@ use opus::{Encoder, Decoder, Application, Channels};
 use std::sync::Mutex;
 
 use crate::{
-    AudioCodec, AudioFrame, CompressedFrame, AudioConfig, AudioError, AudioResult,
+    AudioCodec, AudioFrame, CompressedFrame, FrameMetadata, FrameOrigin, AudioConfig, AudioError, AudioResult,
 };
 
 /// Implémentation du codec Opus avec thread safety
@@ -52,12 +52,6 @@ struct OpusCodecInner {
     
     /// Configuration audio utilisée
     config: AudioConfig,
-    
-    /// Buffer pour les données compressées
-    compressed_buffer: Vec<u8>,
-    
-    /// Buffer pour les données décompressées  
-    decompressed_buffer: Vec<f32>,
 }
 
 impl OpusCodec {
@@ -82,6 +76,11 @@ impl OpusCodec {
         println!("   Channels : {}", config.channels);
         println!("   Bitrate : {} bps", config.opus_bitrate);
         println!("   Complexité : {}", config.opus_complexity);
+        println!("   FEC : {}", if config.opus_fec_loss_percent > 0 {
+            format!("{}% de perte attendue", config.opus_fec_loss_percent)
+        } else {
+            "désactivée".to_string()
+        });
         
         // Convertit notre configuration vers le format Opus
         let opus_channels = match config.channels {
@@ -114,13 +113,22 @@ impl OpusCodec {
         encoder.set_vbr(true)
             .map_err(|e| AudioError::OpusError(format!("Impossible d'activer VBR: {:?}", e)))?;
         
+        // Redondance (FEC) : chaque paquet porte une version basse qualité
+        // du précédent, que le récepteur utilise si celui-ci est perdu
+        if config.opus_fec_loss_percent > 0 {
+            encoder.set_inband_fec(true)
+                .map_err(|e| AudioError::OpusError(format!("Impossible d'activer la FEC: {:?}", e)))?;
+            encoder.set_packet_loss_perc(config.opus_fec_loss_percent as i32)
+                .map_err(|e| AudioError::OpusError(format!("Impossible de définir le taux de perte: {:?}", e)))?;
+        }
+        
         // Crée le décodeur Opus
         let decoder = Decoder::new(
             config.sample_rate,
             opus_channels,
         ).map_err(|e| AudioError::OpusError(format!("Impossible de créer le décodeur: {:?}", e)))?;
         
-        // Prépare les buffers de travail
+        // Tailles maximales d'une frame (buffers de l'appelant pour encode_into/decode_into)
         let max_compressed_size = config.max_compressed_frame_size();
         let max_samples = config.samples_per_frame() * config.channels as usize;
         
@@ -132,8 +140,6 @@ impl OpusCodec {
             encoder,
             decoder,
             config,
-            compressed_buffer: vec![0u8; max_compressed_size],
-            decompressed_buffer: vec![0.0f32; max_samples],
         };
 
         Ok(Self {
@@ -195,6 +201,32 @@ impl OpusCodec {
 
 impl AudioCodec for OpusCodec {
     fn encode(&mut self, frame: &AudioFrame) -> AudioResult<CompressedFrame> {
+        let mut compressed_data = Vec::new();
+        self.encode_into(frame, &mut compressed_data)?;
+        
+        // Crée la frame compressée
+        let mut compressed = CompressedFrame::new(
+            compressed_data,
+            frame.samples.len(),
+            frame.timestamp,
+            frame.sequence_number,
+        );
+        compressed.metadata = Some(FrameMetadata::from_frame(frame));
+        compressed.media_timestamp = frame.media_timestamp;
+        Ok(compressed)
+    }
+    
+    fn decode(&mut self, compressed: &CompressedFrame) -> AudioResult<AudioFrame> {
+        let mut samples = Vec::new();
+        self.decode_into(compressed, &mut samples)?;
+        
+        // Crée la frame décodée
+        let mut frame = AudioFrame::new(samples, compressed.sequence_number);
+        frame.media_timestamp = compressed.media_timestamp;
+        Ok(frame)
+    }
+    
+    fn encode_into(&mut self, frame: &AudioFrame, output: &mut Vec<u8>) -> AudioResult<()> {
         let mut inner = self.inner.lock().unwrap();
         
         // Vérifie que la frame a la bonne taille
@@ -207,46 +239,26 @@ impl AudioCodec for OpusCodec {
             )));
         }
         
-        // Encode la frame avec Opus
-        // Nous devons séparer l'accès à l'encoder et au buffer pour satisfaire le borrow checker
-        let encoded_size = {
-            let OpusCodecInner { encoder, compressed_buffer, .. } = &mut *inner;
-            encoder.encode_float(
-                &frame.samples,
-                compressed_buffer
-            ).map_err(|e| AudioError::OpusError(format!("Erreur encodage: {:?}", e)))?
-        };
-        
-        // Crée la frame compressée
-        let compressed_data = inner.compressed_buffer[..encoded_size].to_vec();
-        
-        Ok(CompressedFrame::new(
-            compressed_data,
-            frame.samples.len(),
-            frame.timestamp,
-            frame.sequence_number,
-        ))
+        // Encode directement dans le buffer de l'appelant, ramené ensuite à
+        // la taille du paquet (sans réallocation une fois la capacité acquise)
+        output.resize(inner.config.max_compressed_frame_size(), 0);
+        let encoded_size = inner.encoder.encode_float(&frame.samples, output)
+            .map_err(|e| AudioError::OpusError(format!("Erreur encodage: {:?}", e)))?;
+        output.truncate(encoded_size);
+        Ok(())
     }
     
-    fn decode(&mut self, compressed: &CompressedFrame) -> AudioResult<AudioFrame> {
+    fn decode_into(&mut self, compressed: &CompressedFrame, output: &mut Vec<f32>) -> AudioResult<()> {
         let mut inner = self.inner.lock().unwrap();
         
-        // Redimensionne le buffer si nécessaire
+        // Décode avec Opus, directement dans le buffer de l'appelant
         let expected_samples = compressed.original_sample_count;
-        if inner.decompressed_buffer.len() < expected_samples {
-            inner.decompressed_buffer.resize(expected_samples, 0.0);
-        }
-        
-        // Décode avec Opus
-        // Utilisation de destructuring pour éviter les conflits de borrow
-        let decoded_samples = {
-            let OpusCodecInner { decoder, decompressed_buffer, .. } = &mut *inner;
-            decoder.decode_float(
-                &compressed.data,
-                &mut decompressed_buffer[..expected_samples],
-                false // fec (forward error correction) désactivé pour l'instant
-            ).map_err(|e| AudioError::OpusError(format!("Erreur décodage Opus: {:?}", e)))?
-        };
+        output.resize(expected_samples, 0.0);
+        let decoded_samples = inner.decoder.decode_float(
+            &compressed.data,
+            output,
+            false // La FEC ne sert qu'à reconstruire une frame perdue (decode_fec)
+        ).map_err(|e| AudioError::OpusError(format!("Erreur décodage Opus: {:?}", e)))?;
         
         // Vérifie que le décodage a produit le bon nombre d'échantillons
         if decoded_samples != expected_samples {
@@ -256,12 +268,37 @@ impl AudioCodec for OpusCodec {
                 expected_samples
             )));
         }
+        Ok(())
+    }
+    
+    fn conceal(&mut self, sample_count: usize, sequence_number: u64) -> AudioResult<AudioFrame> {
+        let mut inner = self.inner.lock().unwrap();
         
-        // Crée la frame décodée
-        Ok(AudioFrame::new(
-            inner.decompressed_buffer[..decoded_samples].to_vec(),
-            compressed.sequence_number,
-        ))
+        // Un paquet vide demande au décodeur d'extrapoler la frame (PLC)
+        let mut samples = vec![0.0; sample_count];
+        inner.decoder.decode_float(&[], &mut samples, false)
+            .map_err(|e| AudioError::OpusError(format!("Erreur masquage Opus: {:?}", e)))?;
+        
+        let mut frame = AudioFrame::new(samples, sequence_number);
+        frame.origin = FrameOrigin::Concealed;
+        Ok(frame)
+    }
+    
+    fn decode_fec(&mut self, next: &CompressedFrame, sequence_number: u64) -> AudioResult<AudioFrame> {
+        // Sans FEC à l'émission, le paquet suivant n'a pas de redondance
+        let fec_enabled = self.inner.lock().unwrap().config.opus_fec_loss_percent > 0;
+        if !fec_enabled || next.data.is_empty() {
+            return self.conceal(next.original_sample_count, sequence_number);
+        }
+        
+        let mut inner = self.inner.lock().unwrap();
+        let mut samples = vec![0.0; next.original_sample_count];
+        inner.decoder.decode_float(&next.data, &mut samples, true)
+            .map_err(|e| AudioError::OpusError(format!("Erreur décodage FEC Opus: {:?}", e)))?;
+        
+        let mut frame = AudioFrame::new(samples, sequence_number);
+        frame.origin = FrameOrigin::Fec;
+        Ok(frame)
     }
     
     fn reset(&mut self) -> AudioResult<()> {
@@ -315,7 +352,8 @@ mod tests {
         let mut codec = OpusCodec::new(config.clone()).expect("Création codec");
         
         // Test avec du silence
-        let silence_frame = AudioFrame::silence(config.samples_per_frame(), 42);
+        let mut silence_frame = AudioFrame::silence(config.samples_per_frame(), 42);
+        silence_frame.media_timestamp = Some(42 * config.samples_per_frame() as u64);
         
         // Encode
         let compressed = codec.encode(&silence_frame).expect("Encodage");
@@ -327,6 +365,7 @@ mod tests {
         let decoded = codec.decode(&compressed).expect("Décodage");
         assert_eq!(decoded.samples.len(), silence_frame.samples.len());
         assert_eq!(decoded.sequence_number, 42);
+        assert_eq!(decoded.media_timestamp, silence_frame.media_timestamp);
         
         // Pour le silence, on s'attend à des valeurs très proches de 0
         let max_silence_error = decoded.samples.iter()
@@ -341,6 +380,27 @@ mod tests {
                 compressed.compression_ratio());
     }
     
+    #[test]
+    fn test_opus_into_reuses_buffers() {
+        let config = AudioConfig::default();
+        let mut codec = OpusCodec::new(config.clone()).expect("Création codec");
+        let frame = AudioFrame::silence(config.samples_per_frame(), 1);
+        
+        let mut compressed = CompressedFrame::default();
+        let mut samples = Vec::new();
+        codec.encode_into(&frame, &mut compressed.data).expect("Encodage");
+        compressed.original_sample_count = frame.samples.len();
+        codec.decode_into(&compressed, &mut samples).expect("Décodage");
+        assert_eq!(samples.len(), frame.samples.len());
+        
+        // Frame suivante : mêmes buffers, sans réallocation
+        let (data_ptr, samples_ptr) = (compressed.data.as_ptr(), samples.as_ptr());
+        codec.encode_into(&frame, &mut compressed.data).expect("Encodage");
+        codec.decode_into(&compressed, &mut samples).expect("Décodage");
+        assert_eq!((compressed.data.as_ptr(), samples.as_ptr()), (data_ptr, samples_ptr));
+        assert!(!compressed.data.is_empty());
+    }
+    
     #[test]
     fn test_opus_sine_wave() {
         let config = AudioConfig::default();
@@ -396,6 +456,33 @@ mod tests {
         println!("✅ Test reset codec réussi");
     }
     
+    #[test]
+    fn test_opus_conceal_and_fec_origins() {
+        let config = AudioConfig { opus_fec_loss_percent: 10, ..AudioConfig::default() };
+        let samples_per_frame = config.samples_per_frame();
+        let mut codec = OpusCodec::new(config).expect("Création codec");
+        
+        let tone: Vec<f32> = (0..samples_per_frame)
+            .map(|i| (i as f32 * 0.05).sin() * 0.5)
+            .collect();
+        let first = codec.encode(&AudioFrame::new(tone.clone(), 1)).expect("Encodage");
+        let next = codec.encode(&AudioFrame::new(tone, 2)).expect("Encodage");
+        codec.decode(&first).expect("Décodage");
+        
+        let concealed = codec.conceal(samples_per_frame, 2).expect("Masquage");
+        assert_eq!(concealed.origin, FrameOrigin::Concealed);
+        assert_eq!(concealed.samples.len(), samples_per_frame);
+        
+        let recovered = codec.decode_fec(&next, 2).expect("FEC");
+        assert_eq!(recovered.origin, FrameOrigin::Fec);
+        assert_eq!(recovered.sequence_number, 2);
+        assert_eq!(recovered.samples.len(), samples_per_frame);
+        
+        // Sans FEC à l'émission, la frame perdue est extrapolée
+        let mut plain = OpusCodec::new(AudioConfig::default()).expect("Création codec");
+        assert_eq!(plain.decode_fec(&next, 2).expect("FEC").origin, FrameOrigin::Concealed);
+    }
+    
     #[test]
     fn test_opus_invalid_frame_size() {
         let config = AudioConfig::default();
@@ -0,0 +1,313 @@
+//! Pool de threads de codage pour les appels à plusieurs pairs
+//!
+//! Avec un seul codec protégé par un mutex, décoder N pairs et encoder notre
+//! flux se fait l'un après l'autre sur un seul cœur. Le pool répartit ce
+//! travail sur quelques threads dédiés :
+//! - chaque pair est rattaché à un worker (affinité) qui possède son
+//!   décodeur : ses paquets restent décodés dans l'ordre, l'état du décodeur
+//!   (prédictions, masquage des pertes) n'est jamais partagé
+//! - l'encodeur de notre flux vit sur le premier worker, compté comme un
+//!   pair dans la répartition
+//! - les appels sont asynchrones : la tâche appelante attend la réponse
+//!   sans bloquer le runtime tokio
+//!
+//! Les threads sont des threads système (le codage occupe le CPU), élevés en
+//! priorité temps réel si `AudioConfig::realtime_priority` est activé.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tokio::sync::oneshot;
+
+use crate::realtime::CallbackPromotion;
+use crate::{AudioCodec, AudioConfig, AudioError, AudioFrame, AudioResult, CompressedFrame};
+
+/// Nombre maximal de workers retenu par `CodecPool::default_workers` : au-delà,
+/// les appels de groupe courants n'ont plus assez de pairs pour en profiter
+pub const MAX_CODEC_WORKERS: usize = 4;
+
+/// Identifiant d'un pair distant (identifiant d'émetteur du protocole)
+pub type PeerId = u32;
+
+/// Création d'un codec, appelée sur le worker qui l'utilisera
+pub type CodecFactory = dyn Fn(&AudioConfig) -> AudioResult<Box<dyn AudioCodec>> + Send + Sync;
+
+/// Travail confié à un worker
+enum Job {
+    Encode { frame: AudioFrame, reply: oneshot::Sender<AudioResult<CompressedFrame>> },
+    Decode { peer: PeerId, compressed: CompressedFrame, reply: oneshot::Sender<AudioResult<AudioFrame>> },
+    ResetPeer { peer: PeerId, reply: oneshot::Sender<AudioResult<()>> },
+    RemovePeer { peer: PeerId },
+}
+
+/// Rattachement des pairs aux workers
+struct Assignments {
+    peers: HashMap<PeerId, usize>,
+    /// Codecs portés par chaque worker (encodeur compris)
+    loads: Vec<usize>,
+}
+
+/// Pool de workers de codage avec affinité par pair
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{AudioConfig, AudioFrame, CodecPool};
+///
+/// # async fn example() -> audio::AudioResult<()> {
+/// let config = AudioConfig::default();
+/// let pool = CodecPool::opus(config, CodecPool::default_workers())?;
+///
+/// let compressed = pool.encode(AudioFrame::silence(960, 1)).await?;
+/// // Paquets reçus de deux pairs, décodés en parallèle
+/// let decoded = pool.decode_batch(vec![(7, compressed.clone()), (9, compressed)]).await;
+/// assert_eq!(decoded.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CodecPool {
+    senders: Vec<mpsc::Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+    assignments: Mutex<Assignments>,
+}
+
+impl CodecPool {
+    /// Démarre `workers` threads de codage (au moins un)
+    ///
+    /// `factory` crée l'encodeur et chaque décodeur, à la demande, sur le
+    /// worker concerné.
+    ///
+    /// # Erreurs
+    /// - `AudioError::InitializationError` si un thread ne peut être créé
+    pub fn new<F>(config: AudioConfig, workers: usize, factory: F) -> AudioResult<Self>
+    where
+        F: Fn(&AudioConfig) -> AudioResult<Box<dyn AudioCodec>> + Send + Sync + 'static,
+    {
+        let factory: Arc<CodecFactory> = Arc::new(factory);
+        let workers = workers.max(1);
+        let mut senders = Vec::with_capacity(workers);
+        let mut threads = Vec::with_capacity(workers);
+
+        for index in 0..workers {
+            let (sender, receiver) = mpsc::channel();
+            let worker = Worker { config: config.clone(), factory: Arc::clone(&factory), encoder: None, decoders: HashMap::new() };
+            let thread = std::thread::Builder::new()
+                .name(format!("voc-codec-{}", index))
+                .spawn(move || worker.run(receiver))
+                .map_err(|e| AudioError::InitializationError(format!("Impossible de créer le worker codec {}: {}", index, e)))?;
+            senders.push(sender);
+            threads.push(thread);
+        }
+
+        // L'encodeur occupe le premier worker
+        let mut loads = vec![0; workers];
+        loads[0] = 1;
+        Ok(Self { senders, threads, assignments: Mutex::new(Assignments { peers: HashMap::new(), loads }) })
+    }
+
+    /// Pool de codecs Opus
+    #[cfg(feature = "opus")]
+    pub fn opus(config: AudioConfig, workers: usize) -> AudioResult<Self> {
+        Self::new(config, workers, |config| Ok(Box::new(crate::OpusCodec::new(config.clone())?) as Box<dyn AudioCodec>))
+    }
+
+    /// Nombre de workers adapté à la machine : un par cœur, au plus
+    /// `MAX_CODEC_WORKERS`
+    pub fn default_workers() -> usize {
+        std::thread::available_parallelism()
+            .map(|cores| cores.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_CODEC_WORKERS)
+    }
+
+    /// Nombre de workers du pool
+    pub fn worker_count(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Worker qui décode les paquets de `peer`, s'il en a déjà reçu
+    pub fn worker_for(&self, peer: PeerId) -> Option<usize> {
+        self.lock_assignments().peers.get(&peer).copied()
+    }
+
+    fn lock_assignments(&self) -> std::sync::MutexGuard<'_, Assignments> {
+        self.assignments.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Worker de `peer`, attribué au moins chargé au premier paquet
+    fn assign(&self, peer: PeerId) -> usize {
+        let mut assignments = self.lock_assignments();
+        if let Some(&worker) = assignments.peers.get(&peer) {
+            return worker;
+        }
+        let worker = (0..assignments.loads.len())
+            .min_by_key(|&index| assignments.loads[index])
+            .unwrap_or(0);
+        assignments.loads[worker] += 1;
+        assignments.peers.insert(peer, worker);
+        worker
+    }
+
+    /// Confie un travail à un worker et attend sa réponse
+    async fn submit<T>(&self, worker: usize, job: impl FnOnce(oneshot::Sender<AudioResult<T>>) -> Job) -> AudioResult<T> {
+        let (reply, response) = oneshot::channel();
+        self.senders[worker].send(job(reply)).map_err(|_| worker_stopped(worker))?;
+        response.await.map_err(|_| worker_stopped(worker))?
+    }
+
+    /// Encode une frame de notre flux
+    ///
+    /// # Erreurs
+    /// - Erreurs de `AudioCodec::encode`
+    /// - `AudioError::InitializationError` si le worker s'est arrêté
+    pub async fn encode(&self, frame: AudioFrame) -> AudioResult<CompressedFrame> {
+        self.submit(0, |reply| Job::Encode { frame, reply }).await
+    }
+
+    /// Décode un paquet de `peer` sur le worker qui lui est rattaché
+    ///
+    /// # Erreurs
+    /// - Erreurs de `AudioCodec::decode` (le décodeur du pair est conservé)
+    /// - `AudioError::InitializationError` si le worker s'est arrêté
+    pub async fn decode(&self, peer: PeerId, compressed: CompressedFrame) -> AudioResult<AudioFrame> {
+        let worker = self.assign(peer);
+        self.submit(worker, |reply| Job::Decode { peer, compressed, reply }).await
+    }
+
+    /// Décode les paquets de plusieurs pairs en parallèle
+    ///
+    /// Tous les paquets sont confiés aux workers avant d'attendre le premier
+    /// résultat ; les résultats suivent l'ordre de `packets`.
+    pub async fn decode_batch(&self, packets: Vec<(PeerId, CompressedFrame)>) -> Vec<(PeerId, AudioResult<AudioFrame>)> {
+        let pending: Vec<_> = packets
+            .into_iter()
+            .map(|(peer, compressed)| {
+                let worker = self.assign(peer);
+                let (reply, response) = oneshot::channel();
+                let sent = self.senders[worker].send(Job::Decode { peer, compressed, reply }).is_ok();
+                (peer, worker, sent.then_some(response))
+            })
+            .collect();
+
+        let mut decoded = Vec::with_capacity(pending.len());
+        for (peer, worker, response) in pending {
+            let result = match response {
+                Some(response) => response.await.unwrap_or_else(|_| Err(worker_stopped(worker))),
+                None => Err(worker_stopped(worker)),
+            };
+            decoded.push((peer, result));
+        }
+        decoded
+    }
+
+    /// Réinitialise le décodeur de `peer` (reprise après une coupure)
+    pub async fn reset_peer(&self, peer: PeerId) -> AudioResult<()> {
+        match self.worker_for(peer) {
+            Some(worker) => self.submit(worker, |reply| Job::ResetPeer { peer, reply }).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Libère le décodeur de `peer`, parti de l'appel
+    pub fn remove_peer(&self, peer: PeerId) {
+        let mut assignments = self.lock_assignments();
+        if let Some(worker) = assignments.peers.remove(&peer) {
+            assignments.loads[worker] -= 1;
+            let _ = self.senders[worker].send(Job::RemovePeer { peer });
+        }
+    }
+}
+
+impl Drop for CodecPool {
+    fn drop(&mut self) {
+        // Canaux fermés : chaque worker termine son travail en cours et s'arrête
+        self.senders.clear();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn worker_stopped(worker: usize) -> AudioError {
+    AudioError::InitializationError(format!("Worker codec {} arrêté", worker))
+}
+
+/// Codecs possédés par un thread du pool
+struct Worker {
+    config: AudioConfig,
+    factory: Arc<CodecFactory>,
+    encoder: Option<Box<dyn AudioCodec>>,
+    decoders: HashMap<PeerId, Box<dyn AudioCodec>>,
+}
+
+impl Worker {
+    fn run(mut self, jobs: mpsc::Receiver<Job>) {
+        CallbackPromotion::new(&self.config).ensure();
+
+        while let Ok(job) = jobs.recv() {
+            // Réponses ignorées si l'appelant a abandonné l'attente
+            match job {
+                Job::Encode { frame, reply } => {
+                    let _ = reply.send(self.encode(&frame));
+                }
+                Job::Decode { peer, compressed, reply } => {
+                    let _ = reply.send(self.decode(peer, &compressed));
+                }
+                Job::ResetPeer { peer, reply } => {
+                    let result = self.decoders.get_mut(&peer).map_or(Ok(()), |decoder| decoder.reset());
+                    let _ = reply.send(result);
+                }
+                Job::RemovePeer { peer } => {
+                    self.decoders.remove(&peer);
+                }
+            }
+        }
+    }
+
+    fn encode(&mut self, frame: &AudioFrame) -> AudioResult<CompressedFrame> {
+        let encoder = match self.encoder.take() {
+            Some(encoder) => encoder,
+            None => (self.factory)(&self.config)?,
+        };
+        self.encoder.insert(encoder).encode(frame)
+    }
+
+    fn decode(&mut self, peer: PeerId, compressed: &CompressedFrame) -> AudioResult<AudioFrame> {
+        let decoder = match self.decoders.entry(peer) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert((self.factory)(&self.config)?),
+        };
+        decoder.decode(compressed)
+    }
+}
+
+#[cfg(all(test, feature = "opus"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_peers_spread_across_workers() {
+        let pool = CodecPool::opus(AudioConfig::default(), 3).unwrap();
+        let compressed = pool.encode(AudioFrame::silence(960, 1)).await.unwrap();
+
+        let packets = (1..=4).map(|peer| (peer, compressed.clone())).collect();
+        let decoded = pool.decode_batch(packets).await;
+        assert!(decoded.iter().all(|(_, result)| result.as_ref().is_ok_and(|frame| frame.samples.len() == 960)));
+
+        // Encodeur sur le worker 0 : les deux premiers pairs vont ailleurs
+        assert_eq!(pool.worker_for(1), Some(1));
+        assert_eq!(pool.worker_for(2), Some(2));
+        assert_eq!(pool.worker_for(3), Some(0));
+        assert_eq!(pool.worker_for(4), Some(1));
+
+        // Affinité conservée, puis libérée au départ du pair
+        pool.decode(2, compressed.clone()).await.unwrap();
+        assert_eq!(pool.worker_for(2), Some(2));
+        pool.remove_peer(2);
+        assert_eq!(pool.worker_for(2), None);
+        pool.reset_peer(1).await.unwrap();
+        pool.decode(5, compressed).await.unwrap();
+        assert_eq!(pool.worker_for(5), Some(2));
+    }
+}
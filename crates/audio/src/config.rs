@@ -4,6 +4,10 @@
 //! Ces paramètres sont cruciaux pour la qualité et la latence de la communication vocale.
 
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{AudioDevicePreferences, AudioError, AudioResult};
 
 /// Configuration principale pour tout le système audio
 /// 
@@ -16,6 +20,7 @@ use serde::{Deserialize, Serialize};
 /// `#[derive(Debug)]` : Permet d'afficher la config pour le débogage  
 /// `#[derive(Serialize, Deserialize)]` : Permet de sauvegarder/charger depuis un fichier
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct AudioConfig {
     /// Fréquence d'échantillonnage en Hz (échantillons par seconde)
     /// 
@@ -50,12 +55,119 @@ pub struct AudioConfig {
     /// 5 = Bon compromis pour temps réel
     pub opus_complexity: u32,
     
-    /// Taille du buffer de réception en nombre de frames
+    /// Taux de perte attendu, en %, pour lequel l'encodeur Opus ajoute de
+    /// la redondance (FEC) à chaque paquet
+    /// 
+    /// 0 = désactivé. Une frame perdue peut alors être reconstruite depuis
+    /// la suivante (voir `AudioCodec::decode_fec`), au prix de quelques
+    /// kbps ; sinon le décodeur l'extrapole (voir `AudioCodec::conceal`)
+    #[serde(default)]
+    pub opus_fec_loss_percent: u8,
+    
+    /// Délai de lecture visé, en ms
+    /// 
+    /// Audio accumulé avant de (re)démarrer la lecture, au début et après
+    /// un underrun. Plus grand = plus de tolérance au jitter réseau,
+    /// plus petit = moins de latence
+    #[serde(default = "default_target_delay_ms")]
+    pub target_delay_ms: u32,
+    
+    /// Délai de lecture maximum, en ms
+    /// 
+    /// Au-delà, `overflow_policy` s'applique. Les deux délais sont convertis
+    /// en frames de `frame_duration_ms` (voir `target_buffer_frames`)
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u32,
+    
+    /// Élève les threads des callbacks audio en priorité temps réel
     /// 
-    /// Plus grand = plus de tolérance au jitter réseau
-    /// Plus petit = moins de latence
-    /// 3 frames = ~60ms de buffer
-    pub receive_buffer_size: usize,
+    /// Limite les craquements quand la machine est chargée. Désactivé par
+    /// défaut : sous Linux, il faut `CAP_SYS_NICE` ou une limite
+    /// `RLIMIT_RTPRIO` suffisante (voir le module `realtime`)
+    #[serde(default)]
+    pub realtime_priority: bool,
+    
+    /// Taille du buffer cpal demandée, en frames par callback
+    /// 
+    /// `None` = taille choisie par le pilote, parfois 10ms ou plus sous
+    /// Windows. Si le périphérique refuse, le stream est recréé avec la
+    /// taille par défaut ; la taille obtenue est donnée par
+    /// `CpalCapture::device_buffer_frames` / `CpalPlayback::device_buffer_frames`
+    #[serde(default)]
+    pub device_buffer_frames: Option<u32>,
+    
+    /// Part du micro renvoyée dans le casque (sidetone), de 0.0 à 1.0
+    /// 
+    /// 0.0 = désactivé. Vers 0.1-0.2, on s'entend parler comme sans casque ;
+    /// les échantillons vont de la capture à la lecture sans passer par le
+    /// codec (voir le module `mixer`)
+    #[serde(default)]
+    pub sidetone_gain: f32,
+    
+    /// Gain des signaux sonores de l'application pendant que le pair parle
+    /// 
+    /// 1.0 = pas d'atténuation ; 0.3 par défaut (environ -10 dB)
+    #[serde(default = "default_ducking_gain")]
+    pub ducking_gain: f32,
+    
+    /// Nivelle le volume des pairs reçus (voir le module `loudness`)
+    #[serde(default = "default_loudness_normalization")]
+    pub loudness_normalization: bool,
+    
+    /// Sonie visée par la normalisation, en LUFS (-18 par défaut)
+    #[serde(default = "default_loudness_target_lufs")]
+    pub loudness_target_lufs: f32,
+    
+    /// Que faire d'une frame reçue quand le buffer de lecture est plein
+    #[serde(default)]
+    pub overflow_policy: OverflowPolicy,
+    
+    /// Délai sans callback du micro au-delà duquel la capture est bloquée, en ms
+    /// 
+    /// Le système peut suspendre le stream sans erreur (mise en veille,
+    /// périphérique pris en mode exclusif) : `next_frame()` retourne alors
+    /// `AudioError::Timeout` et le stream est reconstruit. 0 = désactivé
+    #[serde(default = "default_capture_stall_timeout_ms")]
+    pub capture_stall_timeout_ms: u32,
+    
+    /// Mode d'accès au périphérique demandé à l'hôte audio
+    /// 
+    /// Le mode réellement obtenu est donné par `CpalCapture::host_mode` /
+    /// `CpalPlayback::host_mode` : sans support de l'hôte, le stream est
+    /// ouvert en mode partagé
+    #[serde(default)]
+    pub latency_mode: LatencyMode,
+    
+    /// Hôte audio à utiliser ("wasapi", "asio", "alsa", "jack", "coreaudio"...)
+    /// 
+    /// `None` = hôte par défaut du système. Un hôte absent ou non compilé
+    /// est remplacé par celui par défaut, avec un avertissement
+    #[serde(default)]
+    pub audio_host: Option<String>,
+}
+
+fn default_target_delay_ms() -> u32 {
+    40
+}
+
+fn default_max_delay_ms() -> u32 {
+    60
+}
+
+fn default_ducking_gain() -> f32 {
+    0.3
+}
+
+fn default_loudness_normalization() -> bool {
+    true
+}
+
+fn default_capture_stall_timeout_ms() -> u32 {
+    1000
+}
+
+fn default_loudness_target_lufs() -> f32 {
+    crate::loudness::LoudnessNormalizer::DEFAULT_TARGET_LUFS
 }
 
 impl Default for AudioConfig {
@@ -67,7 +179,19 @@ impl Default for AudioConfig {
             frame_duration_ms: 20,      // 20ms - standard VoIP
             opus_bitrate: 32000,        // 32 kbps - excellente qualité vocale
             opus_complexity: 5,         // Complexité moyenne
-            receive_buffer_size: 3,     // 3 frames = 60ms buffer
+            opus_fec_loss_percent: 0,   // Pas de redondance sur LAN
+            target_delay_ms: 40,        // 2 frames de 20ms
+            max_delay_ms: 60,           // 3 frames de 20ms
+            realtime_priority: false,   // Nécessite des droits sous Linux
+            device_buffer_frames: None, // Taille choisie par le pilote
+            sidetone_gain: 0.0,         // Pas de retour micro
+            ducking_gain: 0.3,          // Signaux atténués sous la voix
+            loudness_normalization: true, // Pairs nivelés
+            loudness_target_lufs: -18.0,  // Voix, avec marge pour les pics
+            overflow_policy: OverflowPolicy::DropOldest, // Délai réduit d'une frame
+            capture_stall_timeout_ms: 1000, // 50 frames sans callback
+            latency_mode: LatencyMode::Shared, // Cohabite avec les autres applications
+            audio_host: None,           // Hôte par défaut du système
         }
     }
 }
@@ -97,12 +221,35 @@ impl AudioConfig {
         4000
     }
     
+    /// Délai de lecture visé, en frames (au moins une)
+    /// 
+    /// Arrondi à la frame la plus proche : 40ms donnent 4 frames de 10ms,
+    /// 2 de 20ms ou une de 60ms.
+    pub fn target_buffer_frames(&self) -> usize {
+        let frame_ms = self.frame_duration_ms.max(1) as u32;
+        ((self.target_delay_ms + frame_ms / 2) / frame_ms).max(1) as usize
+    }
+    
+    /// Capacité du buffer de lecture, en frames (au moins `target_buffer_frames`)
+    /// 
+    /// Arrondie à la frame inférieure : le délai ne dépasse pas `max_delay_ms`.
+    pub fn max_buffer_frames(&self) -> usize {
+        let frames = (self.max_delay_ms / self.frame_duration_ms.max(1) as u32) as usize;
+        frames.max(self.target_buffer_frames())
+    }
+    
+    /// Délai du watchdog de capture (`None` si désactivé)
+    pub fn capture_stall_timeout(&self) -> Option<Duration> {
+        (self.capture_stall_timeout_ms > 0)
+            .then(|| Duration::from_millis(self.capture_stall_timeout_ms as u64))
+    }
+    
     /// Calcule la latence théorique minimale du système
     /// 
-    /// Latence = durée_frame + buffer_size * durée_frame
+    /// Latence = durée_frame + délai de lecture visé
     /// C'est le temps minimal entre la capture et la lecture
     pub fn theoretical_latency_ms(&self) -> u32 {
-        self.frame_duration_ms as u32 * (1 + self.receive_buffer_size as u32)
+        self.frame_duration_ms as u32 + self.target_delay_ms
     }
     
     /// Valide que la configuration est cohérente
@@ -129,6 +276,36 @@ impl AudioConfig {
             return Err(format!("Complexité Opus invalide: {} (doit être entre 0 et 10)", self.opus_complexity));
         }
         
+        if self.opus_fec_loss_percent > 100 {
+            return Err(format!("Taux de perte FEC invalide: {}% (doit être entre 0 et 100)", self.opus_fec_loss_percent));
+        }
+        
+        if self.max_delay_ms < self.frame_duration_ms as u32 || self.target_delay_ms > self.max_delay_ms {
+            return Err(format!("Délais de lecture invalides: {}ms visés, {}ms max (au moins une frame, visé ≤ max)",
+                               self.target_delay_ms, self.max_delay_ms));
+        }
+        
+        if self.device_buffer_frames == Some(0) {
+            return Err("Taille de buffer périphérique invalide: 0 frame".to_string());
+        }
+        
+        if !(0.0..=1.0).contains(&self.sidetone_gain) {
+            return Err(format!("Gain du sidetone invalide: {} (doit être entre 0.0 et 1.0)", self.sidetone_gain));
+        }
+        
+        if !(0.0..=1.0).contains(&self.ducking_gain) {
+            return Err(format!("Gain de ducking invalide: {} (doit être entre 0.0 et 1.0)", self.ducking_gain));
+        }
+        
+        if self.capture_stall_timeout_ms != 0 && self.capture_stall_timeout_ms < 4 * self.frame_duration_ms as u32 {
+            return Err(format!("Délai de blocage de la capture invalide: {}ms (au moins 4 frames, ou 0)",
+                               self.capture_stall_timeout_ms));
+        }
+        
+        if !(-40.0..=-6.0).contains(&self.loudness_target_lufs) {
+            return Err(format!("Sonie cible invalide: {} LUFS (doit être entre -40 et -6)", self.loudness_target_lufs));
+        }
+        
         Ok(())
     }
     
@@ -136,23 +313,173 @@ impl AudioConfig {
     pub fn low_latency() -> Self {
         Self {
             frame_duration_ms: 10,      // Frames plus petites
-            receive_buffer_size: 2,     // Buffer plus petit
+            target_delay_ms: 20,        // Buffer plus petit
+            max_delay_ms: 30,
             opus_complexity: 3,         // Moins de complexité CPU
             ..Default::default()
         }
     }
     
+    /// Crée une configuration économe en CPU (ARM, Raspberry Pi Zero 2 W)
+    /// 
+    /// Des frames de 40ms divisent par deux le nombre d'encodages, de
+    /// décodages et de paquets par seconde, au prix de 20ms de latence ;
+    /// l'encodeur tourne à la complexité minimale.
+    pub fn low_power() -> Self {
+        Self {
+            frame_duration_ms: 40,      // 25 frames/s au lieu de 50
+            opus_complexity: 0,         // Encodeur le plus léger
+            target_delay_ms: 80,        // 2 frames de 40ms
+            max_delay_ms: 120,          // 3 frames de 40ms
+            ..Default::default()
+        }
+    }
+    
     /// Crée une configuration optimisée pour la qualité
     pub fn high_quality() -> Self {
         Self {
             opus_bitrate: 64000,        // Bitrate plus élevé
             opus_complexity: 8,         // Plus de complexité
-            receive_buffer_size: 5,     // Buffer plus grand pour stabilité
+            target_delay_ms: 60,        // Buffer plus grand pour stabilité
+            max_delay_ms: 100,
             ..Default::default()
         }
     }
 }
 
+/// Politique du buffer de lecture quand il est plein
+/// 
+/// Le nombre de frames jetées est retourné par `AudioPlayback::play_frame`
+/// et compté dans `AudioStats::buffer_overflows`.
+/// 
+/// # Example
+/// ```rust
+/// use audio::{AudioConfig, OverflowPolicy};
+/// 
+/// // Diffusion : garder le début de la phrase plutôt que rattraper le direct
+/// let mut config = AudioConfig::default();
+/// config.overflow_policy = OverflowPolicy::DropNewest;
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// La frame la plus ancienne disparaît (fusionnée à la suivante par
+    /// étirement, sans trou audible) : le délai de lecture diminue
+    #[default]
+    DropOldest,
+    /// La frame reçue est jetée : le délai de lecture reste le même
+    DropNewest,
+    /// La frame reçue est jetée et `AudioError::BufferOverflow` retournée
+    Error,
+}
+
+/// Mode d'accès au périphérique audio
+/// 
+/// Sous Windows, le mode partagé de WASAPI ajoute le buffer du mixeur
+/// système (souvent 10ms par sens) : pour descendre sous 40ms de bouche à
+/// oreille, il faut le plus petit buffer du pilote, voire un accès exclusif.
+/// Le mode demandé n'est qu'une préférence : l'hôte peut ne pas le proposer.
+/// 
+/// # Example
+/// ```rust
+/// use audio::{AudioConfig, LatencyMode};
+/// 
+/// let mut config = AudioConfig::default();
+/// config.latency_mode = LatencyMode::Exclusive;
+/// config.audio_host = Some("asio".to_string());
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyMode {
+    /// Buffer choisi par le pilote, périphérique partagé avec le système
+    #[default]
+    Shared,
+    /// Plus petit buffer annoncé par le périphérique, toujours partagé
+    LowLatency,
+    /// Accès exclusif au périphérique (ASIO, JACK), sans mixeur système
+    Exclusive,
+}
+
+impl std::fmt::Display for LatencyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LatencyMode::Shared => write!(f, "partagé"),
+            LatencyMode::LowLatency => write!(f, "partagé faible latence"),
+            LatencyMode::Exclusive => write!(f, "exclusif"),
+        }
+    }
+}
+
+/// Réglages audio persistés entre deux lancements
+/// 
+/// Regroupe la configuration audio et les périphériques préférés dans un
+/// fichier TOML, pour ne pas avoir à resélectionner son casque à chaque fois.
+/// 
+/// # Example
+/// ```rust,no_run
+/// use audio::AudioSettings;
+/// 
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut settings = AudioSettings::load_or_default("voc_audio.toml");
+/// settings.devices.input_name = Some("Jabra Evolve 20 MS".to_string());
+/// settings.save("voc_audio.toml")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AudioSettings {
+    /// Configuration audio (codec, frames, buffer)
+    #[serde(default)]
+    pub audio: AudioConfig,
+    
+    /// Périphériques préférés de l'utilisateur
+    #[serde(default)]
+    pub devices: AudioDevicePreferences,
+}
+
+impl AudioSettings {
+    /// Charge les réglages depuis un fichier TOML
+    /// 
+    /// # Erreurs
+    /// - `AudioError::ConfigError` si le fichier est illisible ou invalide
+    pub fn load(path: impl AsRef<Path>) -> AudioResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AudioError::ConfigError(format!("Lecture de {} impossible: {}", path.display(), e)))?;
+        let settings: Self = toml::from_str(&content)
+            .map_err(|e| AudioError::ConfigError(format!("Réglages invalides dans {}: {}", path.display(), e)))?;
+        settings.audio.validate().map_err(AudioError::ConfigError)?;
+        Ok(settings)
+    }
+    
+    /// Charge les réglages, ou les valeurs par défaut si le fichier est absent ou invalide
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::load(path).unwrap_or_else(|e| {
+            println!("⚠️  {} - réglages par défaut utilisés", e);
+            Self::default()
+        })
+    }
+    
+    /// Enregistre les réglages dans un fichier TOML
+    /// 
+    /// # Erreurs
+    /// - `AudioError::ConfigError` si le fichier ne peut pas être écrit
+    pub fn save(&self, path: impl AsRef<Path>) -> AudioResult<()> {
+        let path = path.as_ref();
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| AudioError::ConfigError(format!("Sérialisation des réglages impossible: {}", e)))?;
+        std::fs::write(path, content)
+            .map_err(|e| AudioError::ConfigError(format!("Écriture de {} impossible: {}", path.display(), e)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,7 +491,8 @@ mod tests {
         // Test des calculs
         assert_eq!(config.samples_per_frame(), 960); // 48000 * 20 / 1000
         assert_eq!(config.frame_size_bytes(), 3840); // 960 * 1 * 4
-        assert_eq!(config.theoretical_latency_ms(), 80); // 20 * (1 + 3)
+        assert_eq!(config.theoretical_latency_ms(), 60); // 20 + 40 visés
+        assert_eq!((config.target_buffer_frames(), config.max_buffer_frames()), (2, 3));
         
         // Test de validation
         assert!(config.validate().is_ok());
@@ -180,16 +508,78 @@ mod tests {
         config.sample_rate = 48000;
         config.channels = 0; // Invalide
         assert!(config.validate().is_err());
+        
+        config.channels = 1;
+        config.device_buffer_frames = Some(0); // Buffer vide
+        assert!(config.validate().is_err());
+        
+        config.device_buffer_frames = None;
+        config.sidetone_gain = 1.5; // Plus fort que le micro
+        assert!(config.validate().is_err());
+        
+        config.sidetone_gain = 0.15;
+        config.ducking_gain = f32::NAN;
+        assert!(config.validate().is_err());
+        
+        config.ducking_gain = 0.3;
+        config.loudness_target_lufs = 0.0; // Saturerait
+        assert!(config.validate().is_err());
+        
+        config.loudness_target_lufs = -18.0;
+        config.target_delay_ms = 80; // Au-delà du maximum
+        assert!(config.validate().is_err());
+        
+        config.target_delay_ms = 40;
+        config.capture_stall_timeout_ms = 40; // Simple gigue du callback
+        assert!(config.validate().is_err());
+        config.capture_stall_timeout_ms = 0; // Watchdog désactivé
+        assert!(config.validate().is_ok());
     }
     
     #[test]
     fn test_preset_configs() {
         let low_lat = AudioConfig::low_latency();
         assert_eq!(low_lat.frame_duration_ms, 10);
+        assert_eq!((low_lat.target_buffer_frames(), low_lat.max_buffer_frames()), (2, 3));
         assert!(low_lat.validate().is_ok());
         
         let high_qual = AudioConfig::high_quality();
         assert_eq!(high_qual.opus_bitrate, 64000);
         assert!(high_qual.validate().is_ok());
+        
+        let low_power = AudioConfig::low_power();
+        assert_eq!(low_power.samples_per_frame(), 1920);
+        assert_eq!(low_power.theoretical_latency_ms(), 120);
+        assert_eq!((low_power.target_buffer_frames(), low_power.max_buffer_frames()), (2, 3));
+        
+        // Mêmes délais en ms avec des frames de 60ms : une frame visée, une au plus
+        let long_frames = AudioConfig { frame_duration_ms: 60, ..AudioConfig::default() };
+        assert_eq!((long_frames.target_buffer_frames(), long_frames.max_buffer_frames()), (1, 1));
+        assert!(long_frames.validate().is_ok());
+        assert!(low_power.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_settings_roundtrip() {
+        let path = std::env::temp_dir().join(format!("voc_audio_{}.toml", std::process::id()));
+        
+        let mut settings = AudioSettings {
+            audio: AudioConfig::low_latency(),
+            ..Default::default()
+        };
+        settings.devices.input_name = Some("Headset (2- USB Audio)".to_string());
+        settings.save(&path).unwrap();
+        
+        let loaded = AudioSettings::load(&path).unwrap();
+        assert_eq!(loaded.devices, settings.devices);
+        assert_eq!(loaded.audio.frame_duration_ms, 10);
+        
+        // Un fichier ancien sans section [devices] reste lisible
+        std::fs::write(&path, "[audio]\nsample_rate = 48000\nchannels = 1\nframe_duration_ms = 20\n\
+                               opus_bitrate = 32000\nopus_complexity = 5\nreceive_buffer_size = 3\n").unwrap();
+        assert_eq!(AudioSettings::load(&path).unwrap().devices, AudioDevicePreferences::default());
+        
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(AudioSettings::load_or_default(&path).devices, AudioDevicePreferences::default());
     }
 }
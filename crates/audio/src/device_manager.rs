@@ -0,0 +1,372 @@
+//! Ouverture des périphériques audio avec liste de repli
+//!
+//! `AudioDeviceManager` essaie les périphériques dans l'ordre de
+//! `AudioDevicePreferences::input_order` / `output_order` : le casque
+//! préféré, puis la liste de repli (micro intégré...), puis n'importe quel
+//! périphérique. Un candidat introuvable, ou dont le stream refuse de
+//! démarrer, est sauté. Le même parcours reprend quand le périphérique en
+//! cours est débranché en plein appel (`AudioError::requires_device_reopen`).
+//!
+//! Chaque étape est un `DeviceEvent`, à récupérer par `take_event` pour
+//! indiquer à l'utilisateur quel périphérique est utilisé.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+
+use crate::devices;
+use crate::{
+    AudioCapture, AudioConfig, AudioDevicePreferences, AudioError, AudioFrame, AudioPlayback, AudioResult,
+    AudioSettings, CpalCapture, CpalPlayback, DeviceSelection,
+};
+
+/// Sens d'un périphérique audio
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeviceDirection {
+    /// Entrée (microphone)
+    Input,
+    /// Sortie (haut-parleurs, casque)
+    Output,
+}
+
+impl std::fmt::Display for DeviceDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceDirection::Input => write!(f, "entrée"),
+            DeviceDirection::Output => write!(f, "sortie"),
+        }
+    }
+}
+
+/// Étape du parcours de la liste de repli
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceEvent {
+    /// Périphérique ouvert et démarré : c'est celui utilisé désormais
+    Opened {
+        direction: DeviceDirection,
+        /// Nom du périphérique retenu
+        name: String,
+        /// Candidat de la liste qui l'a désigné (`None` : n'importe lequel)
+        candidate: Option<String>,
+    },
+    /// Candidat introuvable ou refusé : le suivant est essayé
+    OpenFailed {
+        direction: DeviceDirection,
+        /// Nom du candidat ("par défaut" pour le périphérique du système)
+        candidate: String,
+        /// Cause de l'échec
+        reason: String,
+    },
+    /// Périphérique en cours perdu (débranché) : la liste est reparcourue
+    Lost {
+        direction: DeviceDirection,
+        /// Nom du périphérique perdu
+        name: String,
+    },
+}
+
+impl std::fmt::Display for DeviceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceEvent::Opened { direction, name, candidate: Some(_) } => {
+                write!(f, "{} : {}", direction, name)
+            }
+            DeviceEvent::Opened { direction, name, candidate: None } => {
+                write!(f, "{} : {} (aucun périphérique de la liste disponible)", direction, name)
+            }
+            DeviceEvent::OpenFailed { direction, candidate, reason } => {
+                write!(f, "{} : \"{}\" écarté ({})", direction, candidate, reason)
+            }
+            DeviceEvent::Lost { direction, name } => write!(f, "{} : {} perdu", direction, name),
+        }
+    }
+}
+
+/// Capture et lecture ouvertes selon la liste de repli
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{AudioDeviceManager, AudioSettings};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut settings = AudioSettings::load_or_default("voc_audio.toml");
+/// settings.devices.input_name = Some("Jabra Evolve 20 MS".to_string());
+/// settings.devices.input_fallbacks = vec!["Microphone (Realtek Audio)".to_string()];
+///
+/// let mut manager = AudioDeviceManager::from_settings(&settings);
+/// manager.open_capture().await?;
+/// while let Some(event) = manager.take_event() {
+///     println!("🎧 {}", event);
+/// }
+///
+/// // Un casque débranché est remplacé sans interrompre la boucle
+/// let frame = manager.next_frame().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AudioDeviceManager {
+    config: AudioConfig,
+    preferences: AudioDevicePreferences,
+    capture: Option<CpalCapture>,
+    playback: Option<CpalPlayback>,
+    /// Événements en attente de `take_event`
+    events: VecDeque<DeviceEvent>,
+}
+
+impl AudioDeviceManager {
+    /// Crée un manager ; aucun périphérique n'est ouvert
+    pub fn new(config: AudioConfig, preferences: AudioDevicePreferences) -> Self {
+        Self { config, preferences, capture: None, playback: None, events: VecDeque::new() }
+    }
+
+    /// Crée un manager depuis les réglages persistés
+    pub fn from_settings(settings: &AudioSettings) -> Self {
+        Self::new(settings.audio.clone(), settings.devices.clone())
+    }
+
+    /// Ouvre et démarre la capture sur le premier microphone utilisable
+    ///
+    /// # Erreurs
+    /// - `AudioError::NoDeviceFound` si aucun candidat ne s'ouvre
+    pub async fn open_capture(&mut self) -> AudioResult<()> {
+        self.capture = None;
+        let order = owned_order(self.preferences.input_order());
+        self.capture = Some(self.open_first(order).await?);
+        Ok(())
+    }
+
+    /// Ouvre et démarre la lecture sur la première sortie utilisable
+    ///
+    /// # Erreurs
+    /// - `AudioError::NoDeviceFound` si aucun candidat ne s'ouvre
+    pub async fn open_playback(&mut self) -> AudioResult<()> {
+        self.playback = None;
+        let order = owned_order(self.preferences.output_order());
+        self.playback = Some(self.open_first(order).await?);
+        Ok(())
+    }
+
+    /// Capture en cours, si ouverte
+    pub fn capture(&self) -> Option<&CpalCapture> {
+        self.capture.as_ref()
+    }
+
+    /// Capture en cours, si ouverte (réglages, pause)
+    pub fn capture_mut(&mut self) -> Option<&mut CpalCapture> {
+        self.capture.as_mut()
+    }
+
+    /// Lecture en cours, si ouverte
+    pub fn playback(&self) -> Option<&CpalPlayback> {
+        self.playback.as_ref()
+    }
+
+    /// Lecture en cours, si ouverte (réglages, pause)
+    pub fn playback_mut(&mut self) -> Option<&mut CpalPlayback> {
+        self.playback.as_mut()
+    }
+
+    /// Récupère le prochain événement de périphérique
+    pub fn take_event(&mut self) -> Option<DeviceEvent> {
+        self.events.pop_front()
+    }
+
+    /// Prochaine frame du microphone, en changeant de micro s'il est débranché
+    ///
+    /// # Erreurs
+    /// - `AudioError::InitializationError` si la capture n'est pas ouverte
+    /// - `AudioError::NoDeviceFound` si plus aucun micro ne s'ouvre
+    /// - les autres erreurs de `AudioCapture::next_frame`
+    pub async fn next_frame(&mut self) -> AudioResult<AudioFrame> {
+        let capture = self.capture.as_mut()
+            .ok_or(AudioError::InitializationError("Capture non ouverte".to_string()))?;
+        match capture.next_frame().await {
+            Err(e) if e.requires_device_reopen() => {
+                self.events.push_back(DeviceEvent::Lost {
+                    direction: DeviceDirection::Input,
+                    name: capture.device_info(),
+                });
+                self.open_capture().await?;
+                match self.capture.as_mut() {
+                    Some(capture) => capture.next_frame().await,
+                    None => Err(AudioError::NoDeviceFound),
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// Joue une frame, en changeant de sortie si elle est débranchée
+    ///
+    /// La frame en cours lors de la perte de la sortie est jetée.
+    ///
+    /// # Erreurs
+    /// - `AudioError::InitializationError` si la lecture n'est pas ouverte
+    /// - `AudioError::NoDeviceFound` si plus aucune sortie ne s'ouvre
+    /// - les autres erreurs de `AudioPlayback::play_frame`
+    pub async fn play_frame(&mut self, frame: AudioFrame) -> AudioResult<usize> {
+        let playback = self.playback.as_mut()
+            .ok_or(AudioError::InitializationError("Lecture non ouverte".to_string()))?;
+        match playback.play_frame(frame).await {
+            Err(e) if e.requires_device_reopen() => {
+                self.events.push_back(DeviceEvent::Lost {
+                    direction: DeviceDirection::Output,
+                    name: playback.device_info(),
+                });
+                self.open_playback().await?;
+                Ok(1)
+            }
+            result => result,
+        }
+    }
+
+    /// Parcourt `order` jusqu'au premier périphérique qui démarre
+    async fn open_first<D: ManagedDevice>(&mut self, order: Vec<Option<String>>) -> AudioResult<D> {
+        // Noms déjà essayés : un candidat approximatif peut désigner le même
+        let mut tried = Vec::new();
+        for candidate in order {
+            if let Some(device) = self.try_open(candidate.as_deref(), &mut tried).await {
+                return Ok(device);
+            }
+            if candidate.is_none() {
+                // N'importe lequel : après celui par défaut, tous les autres
+                for name in D::available_names() {
+                    if !tried.contains(&name)
+                        && let Some(device) = self.try_open(Some(&name), &mut tried).await
+                    {
+                        return Ok(device);
+                    }
+                }
+            }
+        }
+        Err(AudioError::NoDeviceFound)
+    }
+
+    /// Ouvre et démarre un candidat, en notant le résultat dans les événements
+    async fn try_open<D: ManagedDevice>(&mut self, candidate: Option<&str>, tried: &mut Vec<String>) -> Option<D> {
+        let label = candidate.unwrap_or("par défaut").to_string();
+        let failed = |reason: String| DeviceEvent::OpenFailed { direction: D::DIRECTION, candidate: label.clone(), reason };
+
+        let mut device = match D::open(self.config.clone(), candidate) {
+            Ok(device) => device,
+            Err(e) => {
+                self.events.push_back(failed(e.to_string()));
+                return None;
+            }
+        };
+        if matches!(device.selection(), DeviceSelection::Fallback { .. }) {
+            self.events.push_back(failed("introuvable".to_string()));
+            return None;
+        }
+        let name = device.name();
+        if tried.contains(&name) {
+            return None;
+        }
+        tried.push(name.clone());
+
+        match device.start_device().await {
+            Ok(()) => {
+                let candidate = candidate.map(str::to_string);
+                self.events.push_back(DeviceEvent::Opened { direction: D::DIRECTION, name, candidate });
+                Some(device)
+            }
+            Err(e) => {
+                self.events.push_back(failed(e.to_string()));
+                None
+            }
+        }
+    }
+}
+
+/// Ordre d'essai détaché des préférences (le manager est emprunté pendant le parcours)
+fn owned_order(order: Vec<Option<&str>>) -> Vec<Option<String>> {
+    order.into_iter().map(|name| name.map(str::to_string)).collect()
+}
+
+/// Opérations communes à la capture et à la lecture pour le parcours
+#[async_trait]
+trait ManagedDevice: Sized + Send {
+    const DIRECTION: DeviceDirection;
+
+    fn open(config: AudioConfig, preferred: Option<&str>) -> AudioResult<Self>;
+
+    fn available_names() -> Vec<String>;
+
+    fn selection(&self) -> &DeviceSelection;
+
+    fn name(&self) -> String;
+
+    async fn start_device(&mut self) -> AudioResult<()>;
+}
+
+#[async_trait]
+impl ManagedDevice for CpalCapture {
+    const DIRECTION: DeviceDirection = DeviceDirection::Input;
+
+    fn open(config: AudioConfig, preferred: Option<&str>) -> AudioResult<Self> {
+        CpalCapture::with_preferred_device(config, preferred)
+    }
+
+    fn available_names() -> Vec<String> {
+        devices::input_device_names()
+    }
+
+    fn selection(&self) -> &DeviceSelection {
+        self.device_selection()
+    }
+
+    fn name(&self) -> String {
+        self.device_info()
+    }
+
+    async fn start_device(&mut self) -> AudioResult<()> {
+        self.start().await
+    }
+}
+
+#[async_trait]
+impl ManagedDevice for CpalPlayback {
+    const DIRECTION: DeviceDirection = DeviceDirection::Output;
+
+    fn open(config: AudioConfig, preferred: Option<&str>) -> AudioResult<Self> {
+        CpalPlayback::with_preferred_device(config, preferred)
+    }
+
+    fn available_names() -> Vec<String> {
+        devices::output_device_names()
+    }
+
+    fn selection(&self) -> &DeviceSelection {
+        self.device_selection()
+    }
+
+    fn name(&self) -> String {
+        self.device_info()
+    }
+
+    async fn start_device(&mut self) -> AudioResult<()> {
+        self.start().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_display() {
+        let opened = DeviceEvent::Opened {
+            direction: DeviceDirection::Input,
+            name: "Microphone (Realtek Audio)".to_string(),
+            candidate: None,
+        };
+        assert!(opened.to_string().starts_with("entrée : Microphone (Realtek Audio)"));
+
+        let failed = DeviceEvent::OpenFailed {
+            direction: DeviceDirection::Output,
+            candidate: "Jabra Evolve".to_string(),
+            reason: "introuvable".to_string(),
+        };
+        assert_eq!(failed.to_string(), "sortie : \"Jabra Evolve\" écarté (introuvable)");
+    }
+}
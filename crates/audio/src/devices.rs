@@ -0,0 +1,551 @@
+//! Sélection des périphériques audio préférés
+//!
+//! Les noms des périphériques choisis par l'utilisateur sont mémorisés dans
+//! `AudioDevicePreferences`. Au démarrage, ils sont comparés aux
+//! périphériques énumérés par cpal avec une correspondance approximative :
+//! un casque rebranché sur un autre port USB change souvent de numéro
+//! ("Headset (2- USB Audio)" devient "Headset (3- USB Audio)").
+//! Si aucun périphérique ne correspond, le périphérique par défaut est
+//! utilisé et un avertissement est remonté via `DeviceSelection`.
+//!
+//! L'hôte audio (WASAPI, ASIO, ALSA, JACK, CoreAudio...) peut aussi être
+//! choisi via `AudioConfig::audio_host`, et le mode d'accès négocié selon
+//! `AudioConfig::latency_mode` : le mode obtenu est décrit par
+//! `HostModeReport`.
+//!
+//! L'énumération des périphériques nécessite la feature `cpal` ; les
+//! préférences et la correspondance des noms restent toujours disponibles.
+
+#[cfg(feature = "cpal")]
+use cpal::{
+    BufferSize, Device, Host, HostId, SampleFormat, StreamConfig, SupportedBufferSize, SupportedStreamConfig,
+};
+#[cfg(feature = "cpal")]
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::LatencyMode;
+#[cfg(feature = "cpal")]
+use crate::{AudioError, AudioResult, DeviceInfo};
+
+/// Similarité minimale (indice de Jaccard sur les mots) pour accepter un nom
+const MIN_NAME_SIMILARITY: f32 = 0.5;
+
+/// Périphériques audio préférés de l'utilisateur
+///
+/// `None` signifie "périphérique par défaut du système".
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AudioDevicePreferences {
+    /// Nom du périphérique d'entrée (microphone)
+    pub input_name: Option<String>,
+
+    /// Nom du périphérique de sortie (haut-parleurs, casque)
+    pub output_name: Option<String>,
+
+    /// Entrées à essayer, dans l'ordre, si la préférée ne s'ouvre pas
+    /// (ex: micro intégré après le casque)
+    #[serde(default)]
+    pub input_fallbacks: Vec<String>,
+
+    /// Sorties à essayer, dans l'ordre, si la préférée ne s'ouvre pas
+    #[serde(default)]
+    pub output_fallbacks: Vec<String>,
+}
+
+impl AudioDevicePreferences {
+    /// Ordre d'essai des entrées (voir `fallback_order`)
+    pub fn input_order(&self) -> Vec<Option<&str>> {
+        fallback_order(self.input_name.as_deref(), &self.input_fallbacks)
+    }
+
+    /// Ordre d'essai des sorties (voir `fallback_order`)
+    pub fn output_order(&self) -> Vec<Option<&str>> {
+        fallback_order(self.output_name.as_deref(), &self.output_fallbacks)
+    }
+}
+
+/// Ordre d'essai des périphériques : préféré, liste de repli, puis n'importe lequel
+///
+/// `None` termine toujours la liste : le périphérique par défaut, puis tout
+/// autre périphérique disponible. Les doublons (sans tenir compte de la
+/// casse) et les noms vides sont retirés.
+///
+/// # Example
+/// ```rust
+/// use audio::devices::fallback_order;
+///
+/// let fallbacks = vec!["Microphone (Realtek Audio)".to_string()];
+/// assert_eq!(
+///     fallback_order(Some("Jabra Evolve 20 MS"), &fallbacks),
+///     vec![Some("Jabra Evolve 20 MS"), Some("Microphone (Realtek Audio)"), None]
+/// );
+/// ```
+pub fn fallback_order<'a>(preferred: Option<&'a str>, fallbacks: &'a [String]) -> Vec<Option<&'a str>> {
+    let mut order: Vec<Option<&str>> = Vec::with_capacity(fallbacks.len() + 2);
+    for name in preferred.into_iter().chain(fallbacks.iter().map(String::as_str)) {
+        let name = name.trim();
+        let duplicate = order.iter().flatten().any(|known| known.eq_ignore_ascii_case(name));
+        if !name.is_empty() && !duplicate {
+            order.push(Some(name));
+        }
+    }
+    order.push(None);
+    order
+}
+
+/// Résultat de la sélection d'un périphérique
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceSelection {
+    /// Aucune préférence : périphérique par défaut du système
+    Default,
+
+    /// Le périphérique préféré a été trouvé (éventuellement sous un nom proche)
+    Preferred {
+        /// Nom enregistré dans les préférences
+        requested: String,
+        /// Nom du périphérique retenu
+        found: String,
+    },
+
+    /// Le périphérique préféré est introuvable : repli sur le défaut
+    Fallback {
+        /// Nom enregistré dans les préférences
+        requested: String,
+    },
+}
+
+impl DeviceSelection {
+    /// Message d'avertissement à afficher à l'utilisateur, le cas échéant
+    pub fn warning(&self) -> Option<String> {
+        match self {
+            DeviceSelection::Fallback { requested } => Some(format!(
+                "Périphérique \"{}\" introuvable, utilisation du périphérique par défaut",
+                requested
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Mode d'accès obtenu auprès de l'hôte audio
+///
+/// Comparé au mode demandé pour savoir si la latence visée est atteignable :
+/// un mode partagé sous WASAPI ajoute le buffer du mixeur système.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HostModeReport {
+    /// Nom de l'hôte audio utilisé ("WASAPI", "ASIO", "JACK"...)
+    pub host: String,
+
+    /// Mode demandé dans `AudioConfig::latency_mode`
+    pub requested: LatencyMode,
+
+    /// Mode réellement obtenu
+    pub obtained: LatencyMode,
+
+    /// Précisions sur la négociation (raison du repli, buffer ajusté)
+    pub note: Option<String>,
+}
+
+impl HostModeReport {
+    /// Vrai si l'hôte n'a pas accordé le mode demandé
+    pub fn is_degraded(&self) -> bool {
+        self.obtained != self.requested
+    }
+
+    /// Repli sur le mode partagé (buffer refusé à l'ouverture du stream)
+    #[cfg(feature = "cpal")]
+    pub(crate) fn fall_back_to_shared(&mut self, reason: String) {
+        self.obtained = LatencyMode::Shared;
+        self.add_note(reason);
+    }
+
+    #[cfg(feature = "cpal")]
+    fn add_note(&mut self, note: String) {
+        self.note = Some(match self.note.take() {
+            Some(previous) => format!("{} ; {}", previous, note),
+            None => note,
+        });
+    }
+}
+
+impl std::fmt::Display for HostModeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} : mode {}", self.host, self.obtained)?;
+        if self.is_degraded() {
+            write!(f, " ({} demandé)", self.requested)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, " - {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cherche le nom le plus proche de `preferred` parmi `available`
+///
+/// Ordre de préférence :
+/// 1. égalité exacte (sans tenir compte de la casse)
+/// 2. un nom contient l'autre (ex: "Jabra" / "Jabra Evolve 20 MS")
+/// 3. meilleure similarité entre les mots, numéros ignorés
+///
+/// # Example
+/// ```rust
+/// use audio::devices::match_device_name;
+///
+/// let available = vec!["Speakers".to_string(), "Headset (3- USB Audio)".to_string()];
+/// assert_eq!(
+///     match_device_name("Headset (2- USB Audio)", &available),
+///     Some("Headset (3- USB Audio)")
+/// );
+/// ```
+pub fn match_device_name<'a>(preferred: &str, available: &'a [String]) -> Option<&'a str> {
+    let wanted = preferred.trim().to_lowercase();
+    if wanted.is_empty() {
+        return None;
+    }
+
+    if let Some(name) = available.iter().find(|name| name.trim().to_lowercase() == wanted) {
+        return Some(name);
+    }
+
+    if let Some(name) = available.iter().find(|name| {
+        let candidate = name.trim().to_lowercase();
+        !candidate.is_empty() && (candidate.contains(&wanted) || wanted.contains(&candidate))
+    }) {
+        return Some(name);
+    }
+
+    let wanted_words = name_words(preferred);
+    available
+        .iter()
+        .map(|name| (name, similarity(&wanted_words, &name_words(name))))
+        .filter(|(_, score)| *score >= MIN_NAME_SIMILARITY)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(name, _)| name.as_str())
+}
+
+/// Découpe un nom en mots significatifs (minuscules, sans les numéros)
+fn name_words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !word.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Indice de Jaccard entre deux ensembles de mots
+fn similarity(a: &[String], b: &[String]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let common = a.iter().filter(|word| b.contains(word)).count();
+    let union = a.len() + b.len() - common;
+    common as f32 / union as f32
+}
+
+/// Nom lisible d'un périphérique cpal
+#[cfg(feature = "cpal")]
+pub(crate) fn device_name(device: &Device) -> String {
+    device.description()
+        .ok()
+        .map(|desc| desc.name().to_string())
+        .unwrap_or_else(|| "Périphérique inconnu".to_string())
+}
+
+/// Décrit un périphérique cpal et les paramètres de son stream
+#[cfg(feature = "cpal")]
+pub(crate) fn describe_device(
+    host_id: HostId,
+    name: &str,
+    stream: Option<(StreamConfig, SampleFormat)>,
+    buffer_size: Option<u32>,
+) -> DeviceInfo {
+    let (sample_rate, channels, sample_format) = stream
+        .map(|(config, format)| (config.sample_rate, config.channels, format.to_string()))
+        .unwrap_or_default();
+    DeviceInfo {
+        name: name.to_string(),
+        sample_rate,
+        channels,
+        sample_format,
+        buffer_size,
+        host_api: host_id.name().to_string(),
+    }
+}
+
+/// Liste les noms des périphériques d'entrée disponibles
+#[cfg(feature = "cpal")]
+pub fn input_device_names() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.map(|device| device_name(&device)).collect())
+        .unwrap_or_default()
+}
+
+/// Liste les noms des périphériques de sortie disponibles
+#[cfg(feature = "cpal")]
+pub fn output_device_names() -> Vec<String> {
+    cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.map(|device| device_name(&device)).collect())
+        .unwrap_or_default()
+}
+
+/// Liste les hôtes audio disponibles sur cette machine
+///
+/// Seuls les hôtes compilés dans cpal apparaissent (ASIO et JACK
+/// nécessitent les features `asio` / `jack` de cpal).
+#[cfg(feature = "cpal")]
+pub fn available_host_names() -> Vec<String> {
+    cpal::available_hosts().into_iter().map(|id| id.name().to_string()).collect()
+}
+
+/// Choisit l'hôte audio selon la préférence (`AudioConfig::audio_host`)
+///
+/// Le nom est comparé sans tenir compte de la casse. Un hôte introuvable ou
+/// qui ne s'initialise pas (serveur JACK arrêté) est remplacé par l'hôte
+/// par défaut ; le second élément décrit alors le repli.
+#[cfg(feature = "cpal")]
+pub(crate) fn select_host(preferred: Option<&str>) -> (Host, Option<String>) {
+    let Some(requested) = preferred else {
+        return (cpal::default_host(), None);
+    };
+    let default = cpal::default_host();
+    let Some(id) = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(requested.trim()))
+    else {
+        let warning = format!("Hôte audio \"{}\" indisponible, utilisation de {}",
+                              requested, default.id().name());
+        return (default, Some(warning));
+    };
+    match cpal::host_from_id(id) {
+        Ok(host) => (host, None),
+        Err(e) => {
+            let warning = format!("Hôte audio {} inutilisable ({}), utilisation de {}",
+                                  id.name(), e, default.id().name());
+            (default, Some(warning))
+        }
+    }
+}
+
+/// Choisit le périphérique d'entrée selon la préférence
+#[cfg(feature = "cpal")]
+pub(crate) fn select_input_device(host: &Host, preferred: Option<&str>) -> AudioResult<(Device, DeviceSelection)> {
+    let devices = host.input_devices().map(|devices| devices.collect()).unwrap_or_default();
+    select_device(devices, host.default_input_device(), preferred)
+}
+
+/// Choisit le périphérique de sortie selon la préférence
+#[cfg(feature = "cpal")]
+pub(crate) fn select_output_device(host: &Host, preferred: Option<&str>) -> AudioResult<(Device, DeviceSelection)> {
+    let devices = host.output_devices().map(|devices| devices.collect()).unwrap_or_default();
+    select_device(devices, host.default_output_device(), preferred)
+}
+
+/// Taille de buffer à demander à cpal pour `requested` frames par callback
+///
+/// La demande est bornée à la plage annoncée par le périphérique ; le second
+/// élément décrit l'ajustement éventuel, pour l'afficher. Sans demande, cpal
+/// garde la taille choisie par le pilote.
+#[cfg(feature = "cpal")]
+pub(crate) fn requested_buffer_size(
+    requested: Option<u32>,
+    supported: &SupportedBufferSize,
+) -> (BufferSize, Option<String>) {
+    let Some(frames) = requested else {
+        return (BufferSize::Default, None);
+    };
+    match *supported {
+        SupportedBufferSize::Range { min, max } if frames < min || frames > max => {
+            let granted = frames.clamp(min, max);
+            let note = format!("Buffer de {} frames hors de la plage du périphérique ({}-{}), {} demandées",
+                               frames, min, max, granted);
+            (BufferSize::Fixed(granted), Some(note))
+        }
+        _ => (BufferSize::Fixed(frames), None),
+    }
+}
+
+/// Vrai si la configuration native du périphérique ne correspond plus au stream
+///
+/// Un casque Bluetooth passant du profil A2DP au profil HFP change de
+/// fréquence et de nombre de canaux sans changer de nom ; appelé depuis le
+/// callback d'erreur du stream. Un périphérique devenu introuvable n'est pas
+/// un changement de configuration (le watchdog s'en charge).
+#[cfg(feature = "cpal")]
+pub(crate) fn native_config_changed(current: Option<SupportedStreamConfig>, stream: &StreamConfig) -> bool {
+    current.is_some_and(|native| native.sample_rate() != stream.sample_rate || native.channels() != stream.channels)
+}
+
+/// Négocie le mode d'accès demandé et la taille de buffer correspondante
+///
+/// - `Shared` : taille demandée (`device_buffer_frames`) ou celle du pilote
+/// - `LowLatency` : taille demandée, sinon le minimum de la plage annoncée ;
+///   une plage inconnue laisse le pilote choisir (repli sur `Shared`)
+/// - `Exclusive` : seul ASIO donne un accès exclusif via cpal ; le mode
+///   exclusif de WASAPI n'y est pas exposé, d'où un repli sur `LowLatency`
+#[cfg(feature = "cpal")]
+pub(crate) fn negotiate_latency_mode(
+    requested_mode: LatencyMode,
+    requested_frames: Option<u32>,
+    host_id: HostId,
+    supported: &SupportedBufferSize,
+) -> (BufferSize, HostModeReport) {
+    let mut report = HostModeReport {
+        host: host_id.name().to_string(),
+        requested: requested_mode,
+        obtained: requested_mode,
+        note: None,
+    };
+    if requested_mode == LatencyMode::Exclusive && !host_id.name().eq_ignore_ascii_case("asio") {
+        report.obtained = LatencyMode::LowLatency;
+        report.add_note(format!("accès exclusif indisponible avec {}", host_id.name()));
+    }
+
+    let (buffer_size, adjustment) = match (report.obtained, requested_frames, supported) {
+        (LatencyMode::Shared, _, _) | (_, Some(_), _) => requested_buffer_size(requested_frames, supported),
+        (_, None, SupportedBufferSize::Range { min, .. }) => (BufferSize::Fixed(*min), None),
+        (_, None, SupportedBufferSize::Unknown) => {
+            report.obtained = LatencyMode::Shared;
+            (BufferSize::Default, Some("plage de buffer du périphérique inconnue".to_string()))
+        }
+    };
+    if let Some(adjustment) = adjustment {
+        report.add_note(adjustment);
+    }
+    (buffer_size, report)
+}
+
+#[cfg(feature = "cpal")]
+fn select_device(
+    devices: Vec<Device>,
+    default: Option<Device>,
+    preferred: Option<&str>,
+) -> AudioResult<(Device, DeviceSelection)> {
+    let Some(requested) = preferred else {
+        return Ok((default.ok_or(AudioError::NoDeviceFound)?, DeviceSelection::Default));
+    };
+
+    let names: Vec<String> = devices.iter().map(device_name).collect();
+    if let Some(found) = match_device_name(requested, &names)
+        && let Some(index) = names.iter().position(|name| name == found)
+    {
+        let selection = DeviceSelection::Preferred {
+            requested: requested.to_string(),
+            found: found.to_string(),
+        };
+        return Ok((devices[index].clone(), selection));
+    }
+
+    let selection = DeviceSelection::Fallback { requested: requested.to_string() };
+    Ok((default.ok_or(AudioError::NoDeviceFound)?, selection))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_match_device_name() {
+        let available = names(&["Speakers (Realtek Audio)", "Headset (3- Jabra Evolve 20 MS)", "HDMI Output"]);
+
+        // Exact, insensible à la casse
+        assert_eq!(match_device_name("hdmi output", &available), Some("HDMI Output"));
+        // Inclusion
+        assert_eq!(match_device_name("Jabra Evolve", &available), Some("Headset (3- Jabra Evolve 20 MS)"));
+        // Numéro de port changé
+        assert_eq!(
+            match_device_name("Headset (2- Jabra Evolve 20 MS)", &available),
+            Some("Headset (3- Jabra Evolve 20 MS)")
+        );
+        // Rien de ressemblant
+        assert_eq!(match_device_name("Blue Yeti", &available), None);
+        assert_eq!(match_device_name("  ", &available), None);
+    }
+
+    #[test]
+    #[cfg(feature = "cpal")]
+    fn test_requested_buffer_size() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert!(matches!(requested_buffer_size(None, &range), (BufferSize::Default, None)));
+        assert!(matches!(requested_buffer_size(Some(256), &range), (BufferSize::Fixed(256), None)));
+        assert!(matches!(requested_buffer_size(Some(16), &range), (BufferSize::Fixed(64), Some(_))));
+        // Plage inconnue : la demande est transmise telle quelle
+        let unknown = SupportedBufferSize::Unknown;
+        assert!(matches!(requested_buffer_size(Some(16), &unknown), (BufferSize::Fixed(16), None)));
+    }
+
+    #[test]
+    #[cfg(feature = "cpal")]
+    fn test_negotiate_latency_mode() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        let host = cpal::default_host().id();
+
+        let (buffer, report) = negotiate_latency_mode(LatencyMode::Shared, None, host, &range);
+        assert!(matches!(buffer, BufferSize::Default));
+        assert!(!report.is_degraded());
+
+        // Faible latence : plus petit buffer, sauf taille explicite
+        let (buffer, report) = negotiate_latency_mode(LatencyMode::LowLatency, None, host, &range);
+        assert!(matches!(buffer, BufferSize::Fixed(64)));
+        assert_eq!(report.obtained, LatencyMode::LowLatency);
+        let (buffer, _) = negotiate_latency_mode(LatencyMode::LowLatency, Some(256), host, &range);
+        assert!(matches!(buffer, BufferSize::Fixed(256)));
+
+        // Plage inconnue : repli sur le mode partagé
+        let unknown = SupportedBufferSize::Unknown;
+        let (buffer, report) = negotiate_latency_mode(LatencyMode::LowLatency, None, host, &unknown);
+        assert!(matches!(buffer, BufferSize::Default));
+        assert_eq!(report.obtained, LatencyMode::Shared);
+        assert!(report.is_degraded() && report.note.is_some());
+
+        // Pas d'accès exclusif hors ASIO
+        if !host.name().eq_ignore_ascii_case("asio") {
+            let (_, report) = negotiate_latency_mode(LatencyMode::Exclusive, None, host, &range);
+            assert_eq!(report.obtained, LatencyMode::LowLatency);
+            assert!(report.to_string().contains("demandé"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "cpal")]
+    fn test_native_config_changed() {
+        let stream = StreamConfig { channels: 2, sample_rate: 48000, buffer_size: BufferSize::Default };
+        let native = |channels, rate| {
+            Some(SupportedStreamConfig::new(channels, rate, SupportedBufferSize::Unknown, SampleFormat::F32))
+        };
+        assert!(!native_config_changed(native(2, 48000), &stream));
+        // Casque Bluetooth passé en HFP
+        assert!(native_config_changed(native(1, 16000), &stream));
+        // Périphérique débranché : pas un changement de route
+        assert!(!native_config_changed(None, &stream));
+    }
+
+    #[test]
+    fn test_fallback_order() {
+        let fallbacks = names(&["Built-in Microphone", "jabra evolve", " "]);
+        assert_eq!(
+            fallback_order(Some("Jabra Evolve"), &fallbacks),
+            vec![Some("Jabra Evolve"), Some("Built-in Microphone"), None]
+        );
+        assert_eq!(fallback_order(None, &[]), vec![None]);
+
+        let preferences = AudioDevicePreferences {
+            output_fallbacks: names(&["Speakers"]),
+            ..Default::default()
+        };
+        assert_eq!(preferences.output_order(), vec![Some("Speakers"), None]);
+    }
+
+    #[test]
+    fn test_selection_warning() {
+        assert!(DeviceSelection::Default.warning().is_none());
+        let fallback = DeviceSelection::Fallback { requested: "Blue Yeti".to_string() };
+        assert!(fallback.warning().unwrap().contains("Blue Yeti"));
+    }
+}
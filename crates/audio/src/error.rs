@@ -5,11 +5,25 @@
 
 use thiserror::Error;
 
+/// Famille d'une erreur audio, pour choisir la réaction appropriée
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioErrorCategory {
+    /// Périphérique absent, débranché ou refusant le stream
+    Device,
+    /// Encodage ou décodage
+    Codec,
+    /// Débordement ou famine d'un buffer
+    Buffer,
+    /// Paramètres ou initialisation d'un composant
+    Config,
+}
+
 /// Énumération de toutes les erreurs possibles dans le système audio
 /// 
 /// `thiserror::Error` génère automatiquement l'implémentation du trait Error
 /// et nous permet de définir des messages d'erreur avec `#[error("...")]`
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum AudioError {
     /// Aucun périphérique audio (microphone ou haut-parleurs) n'a été trouvé
     #[error("Aucun périphérique audio trouvé")]
@@ -21,6 +35,7 @@ pub enum AudioError {
     
     /// Erreur provenant de la librairie cpal (Cross-Platform Audio Library)
     /// `#[from]` génère automatiquement une conversion depuis l'erreur cpal
+    #[cfg(feature = "cpal")]
     #[error("Erreur cpal: {0}")]
     CpalError(#[from] cpal::PlayStreamError),
     
@@ -47,38 +62,80 @@ pub enum AudioError {
     /// Erreur lors de l'initialisation d'un composant
     #[error("Erreur d'initialisation: {0}")]
     InitializationError(String),
+    
+    /// Le périphérique refuse de créer le stream demandé
+    #[cfg(feature = "cpal")]
+    #[error("Erreur construction stream: {0}")]
+    StreamBuildError(#[from] cpal::BuildStreamError),
+    
+    /// Le stream n'a pas pu être mis en pause
+    #[cfg(feature = "cpal")]
+    #[error("Erreur pause stream: {0}")]
+    StreamPauseError(#[from] cpal::PauseStreamError),
+    
+    /// L'hôte audio n'a pas pu énumérer ses périphériques
+    #[cfg(feature = "cpal")]
+    #[error("Erreur énumération des périphériques: {0}")]
+    DeviceEnumerationError(#[from] cpal::DevicesError),
+}
+
+impl AudioError {
+    /// Famille de l'erreur
+    pub fn category(&self) -> AudioErrorCategory {
+        match self {
+            AudioError::NoDeviceFound | AudioError::DeviceDisconnected | AudioError::Timeout => AudioErrorCategory::Device,
+            #[cfg(feature = "cpal")]
+            AudioError::CpalError(_)
+            | AudioError::StreamBuildError(_)
+            | AudioError::StreamPauseError(_)
+            | AudioError::DeviceEnumerationError(_) => AudioErrorCategory::Device,
+            AudioError::OpusError(_) => AudioErrorCategory::Codec,
+            AudioError::BufferOverflow | AudioError::BufferUnderrun => AudioErrorCategory::Buffer,
+            AudioError::ConfigError(_) | AudioError::InitializationError(_) => AudioErrorCategory::Config,
+        }
+    }
+    
+    /// Vérifie si l'erreur est passagère : la frame suivante a toutes les
+    /// chances de passer sans rien changer (frame perdue, paquet corrompu)
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            AudioError::BufferOverflow | AudioError::BufferUnderrun | AudioError::Timeout | AudioError::OpusError(_)
+        )
+    }
+    
+    /// Vérifie si le périphérique doit être fermé puis rouvert (débranché,
+    /// stream invalidé par le système)
+    pub fn requires_device_reopen(&self) -> bool {
+        match self {
+            AudioError::DeviceDisconnected => true,
+            #[cfg(feature = "cpal")]
+            AudioError::CpalError(_) | AudioError::StreamPauseError(_) => true,
+            #[cfg(feature = "cpal")]
+            AudioError::StreamBuildError(err) => matches!(err, cpal::BuildStreamError::DeviceNotAvailable),
+            _ => false,
+        }
+    }
 }
 
 /// Conversion automatique des erreurs Opus vers AudioError
 /// 
 /// Cela nous permet d'utiliser l'opérateur `?` avec les fonctions Opus
+#[cfg(feature = "opus")]
 impl From<opus::Error> for AudioError {
     fn from(err: opus::Error) -> Self {
         AudioError::OpusError(format!("{:?}", err))
     }
 }
 
-/// Conversion des erreurs cpal::BuildStreamError
-impl From<cpal::BuildStreamError> for AudioError {
-    fn from(err: cpal::BuildStreamError) -> Self {
-        AudioError::ConfigError(format!("Erreur construction stream: {:?}", err))
-    }
-}
-
 /// Conversion des erreurs cpal::DefaultStreamConfigError
+#[cfg(feature = "cpal")]
 impl From<cpal::DefaultStreamConfigError> for AudioError {
     fn from(err: cpal::DefaultStreamConfigError) -> Self {
         AudioError::ConfigError(format!("Erreur config par défaut: {:?}", err))
     }
 }
 
-/// Conversion des erreurs cpal::PauseStreamError
-impl From<cpal::PauseStreamError> for AudioError {
-    fn from(err: cpal::PauseStreamError) -> Self {
-        AudioError::ConfigError(format!("Erreur pause stream: {:?}", err))
-    }
-}
-
 /// Type Result personnalisé pour notre crate
 /// 
 /// Au lieu d'écrire Result<T, AudioError> partout, on peut écrire AudioResult<T>
@@ -97,4 +154,28 @@ mod tests {
         let error = AudioError::ConfigError("Test".to_string());
         assert_eq!(error.to_string(), "Erreur de configuration audio: Test");
     }
+    
+    #[test]
+    fn test_error_recoverability() {
+        // Frame perdue : on continue
+        assert!(AudioError::BufferOverflow.is_recoverable());
+        assert_eq!(AudioError::BufferOverflow.category(), AudioErrorCategory::Buffer);
+        assert!(!AudioError::BufferOverflow.requires_device_reopen());
+        
+        // Micro débranché : rouvrir le périphérique
+        assert!(!AudioError::DeviceDisconnected.is_recoverable());
+        assert!(AudioError::DeviceDisconnected.requires_device_reopen());
+        
+        #[cfg(feature = "cpal")]
+        {
+            let error = AudioError::from(cpal::BuildStreamError::DeviceNotAvailable);
+            assert_eq!(error.category(), AudioErrorCategory::Device);
+            assert!(error.requires_device_reopen());
+            assert!(!AudioError::from(cpal::BuildStreamError::StreamConfigNotSupported).requires_device_reopen());
+        }
+        
+        let error = AudioError::ConfigError("48 kHz refusé".to_string());
+        assert_eq!(error.category(), AudioErrorCategory::Config);
+        assert!(!error.is_recoverable() && !error.requires_device_reopen());
+    }
 }
@@ -0,0 +1,305 @@
+//! Import/export des frames audio sur disque
+//!
+//! - WAV ↔ `AudioFrame` : enregistrements, fixtures de test et capture depuis
+//!   un fichier partagent ce code. L'export se fait en PCM 16 bits, lisible
+//!   partout ; l'import accepte le PCM entier (8 à 32 bits) et le flottant 32 bits.
+//! - `CompressedFrame` → Ogg Opus (`.opus`) : enregistre exactement ce qui
+//!   part sur le réseau, sans ré-encodage.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use crate::{AudioConfig, AudioError, AudioFrame, AudioResult, CompressedFrame, Sample};
+
+/// Écrit une séquence de frames dans un fichier WAV
+///
+/// Les frames sont concaténées dans l'ordre donné, au format de `config`
+/// (sample rate, channels ; échantillons entrelacés en stéréo).
+///
+/// # Arguments
+/// * `path` - Fichier WAV à créer (écrasé s'il existe)
+/// * `frames` - Frames à écrire
+/// * `config` - Format des frames
+///
+/// # Erreurs
+/// - `AudioError::ConfigError` si le fichier ne peut pas être écrit
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{io, AudioConfig, AudioFrame};
+///
+/// # fn example() -> Result<(), audio::AudioError> {
+/// let config = AudioConfig::default();
+/// let frames = vec![AudioFrame::silence(config.samples_per_frame(), 0)];
+/// io::write_wav("silence.wav", &frames, &config)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_wav(path: impl AsRef<Path>, frames: &[AudioFrame], config: &AudioConfig) -> AudioResult<()> {
+    let path = path.as_ref();
+    let spec = WavSpec {
+        channels: config.channels,
+        sample_rate: config.sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let wav_error = |e: hound::Error| AudioError::ConfigError(format!("Écriture de {} impossible: {}", path.display(), e));
+
+    let mut writer = WavWriter::create(path, spec).map_err(wav_error)?;
+    for sample in frames.iter().flat_map(|frame| frame.samples.iter()) {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        writer.write_sample(value).map_err(wav_error)?;
+    }
+    writer.finalize().map_err(wav_error)
+}
+
+/// Lit un fichier WAV et le découpe en frames
+///
+/// Les frames font `config.samples_per_frame()` échantillons par canal et
+/// sont numérotées à partir de 0 ; la dernière est complétée par du silence.
+/// Le fichier doit avoir le sample rate et le nombre de canaux de `config`
+/// (pas de rééchantillonnage).
+///
+/// # Arguments
+/// * `path` - Fichier WAV à lire
+/// * `config` - Format attendu des frames
+///
+/// # Erreurs
+/// - `AudioError::ConfigError` si le fichier est illisible ou d'un autre format
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{io, AudioConfig};
+///
+/// # fn example() -> Result<(), audio::AudioError> {
+/// let config = AudioConfig::default();
+/// let frames = io::read_wav("enregistrement.wav", &config)?;
+/// println!("{} frames de {}ms", frames.len(), config.frame_duration_ms);
+/// # Ok(())
+/// # }
+/// ```
+pub fn read_wav(path: impl AsRef<Path>, config: &AudioConfig) -> AudioResult<Vec<AudioFrame>> {
+    let path = path.as_ref();
+    let wav_error = |e: hound::Error| AudioError::ConfigError(format!("Lecture de {} impossible: {}", path.display(), e));
+
+    let mut reader = WavReader::open(path).map_err(wav_error)?;
+    let spec = reader.spec();
+    if spec.sample_rate != config.sample_rate || spec.channels != config.channels {
+        return Err(AudioError::ConfigError(format!(
+            "{} : {} Hz / {} canal(aux), attendu {} Hz / {}",
+            path.display(), spec.sample_rate, spec.channels, config.sample_rate, config.channels
+        )));
+    }
+
+    let samples: Vec<Sample> = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>().map_err(wav_error)?,
+        SampleFormat::Int => {
+            // Pleine échelle de l'entier signé sur `bits_per_sample` bits
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>()
+                .map(|sample| sample.map(|value| value as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(wav_error)?
+        }
+    };
+
+    let frame_len = config.samples_per_frame() * config.channels as usize;
+    Ok(samples.chunks(frame_len)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut samples = chunk.to_vec();
+            samples.resize(frame_len, 0.0);
+            AudioFrame::new(samples, index as u64)
+        })
+        .collect())
+}
+
+/// Écrit des `CompressedFrame` Opus dans un conteneur Ogg (RFC 7845)
+///
+/// Les paquets Opus sont recopiés tels quels ; les positions (granules) sont
+/// calculées à partir du nombre d'échantillons de chaque frame, ramené à
+/// 48 kHz comme l'impose le format. Le fichier n'est valide qu'après `finish()`.
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{io::OggOpusWriter, AudioCodec, AudioConfig, AudioFrame, OpusCodec};
+///
+/// # fn example() -> Result<(), audio::AudioError> {
+/// let config = AudioConfig::default();
+/// let mut codec = OpusCodec::new(config.clone())?;
+/// let mut recording = OggOpusWriter::create("appel.opus", &config)?;
+///
+/// let frame = AudioFrame::silence(config.samples_per_frame(), 0);
+/// recording.write_frame(&codec.encode(&frame)?)?;
+/// recording.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OggOpusWriter<W: Write> {
+    writer: PacketWriter<W>,
+    serial: u32,
+    channels: u16,
+    sample_rate: u32,
+    /// Position (en échantillons à 48 kHz) à la fin des frames écrites
+    granule: u64,
+    /// Dernier paquet, retenu pour pouvoir le marquer fin de flux
+    pending: Option<(Vec<u8>, u64)>,
+}
+
+impl OggOpusWriter<BufWriter<File>> {
+    /// Crée le fichier `.opus` et écrit ses en-têtes
+    ///
+    /// # Erreurs
+    /// - `AudioError::ConfigError` si le fichier ne peut pas être créé
+    pub fn create(path: impl AsRef<Path>, config: &AudioConfig) -> AudioResult<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .map_err(|e| AudioError::ConfigError(format!("Création de {} impossible: {}", path.display(), e)))?;
+        Self::new(BufWriter::new(file), config)
+    }
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    /// Échantillons (à 48 kHz) à ignorer en début de décodage : retard
+    /// algorithmique de l'encodeur libopus
+    pub const PRE_SKIP: u16 = 312;
+
+    /// Fréquence des granules Ogg Opus, quel que soit le sample rate d'origine
+    const GRANULE_RATE: u64 = 48000;
+
+    /// Démarre un flux Ogg Opus sur `writer` et écrit ses en-têtes
+    ///
+    /// # Erreurs
+    /// - `AudioError::ConfigError` en cas d'échec d'écriture
+    pub fn new(writer: W, config: &AudioConfig) -> AudioResult<Self> {
+        // Numéro de flux arbitraire, distinct d'un enregistrement à l'autre
+        let serial = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or(0);
+
+        let mut ogg = Self {
+            writer: PacketWriter::new(writer),
+            serial,
+            channels: config.channels,
+            sample_rate: config.sample_rate,
+            granule: Self::PRE_SKIP as u64,
+            pending: None,
+        };
+
+        // En-tête d'identification (chaque en-tête occupe sa propre page)
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // version
+        head.push(config.channels as u8);
+        head.extend_from_slice(&Self::PRE_SKIP.to_le_bytes());
+        head.extend_from_slice(&config.sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // gain de sortie
+        head.push(0); // mapping mono/stéréo
+        ogg.write_page(head, PacketWriteEndInfo::EndPage, 0)?;
+
+        // En-tête de commentaires : vendeur seul
+        let vendor = concat!("voc ", env!("CARGO_PKG_VERSION"));
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+        ogg.write_page(tags, PacketWriteEndInfo::EndPage, 0)?;
+
+        Ok(ogg)
+    }
+
+    /// Ajoute une frame compressée à l'enregistrement
+    ///
+    /// # Erreurs
+    /// - `AudioError::ConfigError` en cas d'échec d'écriture
+    pub fn write_frame(&mut self, frame: &CompressedFrame) -> AudioResult<()> {
+        let samples_per_channel = (frame.original_sample_count / self.channels.max(1) as usize) as u64;
+        self.granule += samples_per_channel * Self::GRANULE_RATE / self.sample_rate as u64;
+
+        if let Some((data, granule)) = self.pending.replace((frame.data.clone(), self.granule)) {
+            self.write_page(data, PacketWriteEndInfo::NormalPacket, granule)?;
+        }
+        Ok(())
+    }
+
+    /// Durée enregistrée, en échantillons à 48 kHz (hors pré-skip)
+    pub fn duration_samples(&self) -> u64 {
+        self.granule - Self::PRE_SKIP as u64
+    }
+
+    /// Termine le flux et retourne le writer sous-jacent, vidé
+    ///
+    /// # Erreurs
+    /// - `AudioError::ConfigError` en cas d'échec d'écriture
+    pub fn finish(mut self) -> AudioResult<W> {
+        if let Some((data, granule)) = self.pending.take() {
+            self.write_page(data, PacketWriteEndInfo::EndStream, granule)?;
+        }
+        let mut writer = self.writer.into_inner();
+        writer.flush()
+            .map_err(|e| AudioError::ConfigError(format!("Écriture Ogg impossible: {}", e)))?;
+        Ok(writer)
+    }
+
+    fn write_page(&mut self, data: Vec<u8>, end: PacketWriteEndInfo, granule: u64) -> AudioResult<()> {
+        self.writer.write_packet(data.into_boxed_slice(), self.serial, end, granule)
+            .map_err(|e| AudioError::ConfigError(format!("Écriture Ogg impossible: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_roundtrip() {
+        let path = std::env::temp_dir().join(format!("voc_io_{}.wav", std::process::id()));
+        let config = AudioConfig::default();
+        let frame_len = config.samples_per_frame();
+
+        let ramp: Vec<Sample> = (0..frame_len).map(|i| i as f32 / frame_len as f32 - 0.5).collect();
+        let frames = vec![AudioFrame::new(ramp.clone(), 0), AudioFrame::new(ramp[..100].to_vec(), 1)];
+        write_wav(&path, &frames, &config).unwrap();
+
+        let loaded = read_wav(&path, &config).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].sequence_number, 1);
+        assert!(loaded.iter().all(|frame| frame.samples.len() == frame_len));
+        assert!(loaded[0].samples.iter().zip(&ramp).all(|(a, b)| (a - b).abs() < 1e-3));
+        // Fin de fichier complétée par du silence
+        assert!(loaded[1].samples[100..].iter().all(|&s| s == 0.0));
+
+        // Format différent de la configuration
+        assert!(read_wav(&path, &AudioConfig { sample_rate: 16000, ..config }).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ogg_opus_granules() {
+        let config = AudioConfig::default();
+        let mut writer = OggOpusWriter::new(Vec::new(), &config).unwrap();
+        for sequence in 0..3 {
+            let frame = CompressedFrame::new(vec![sequence as u8; 40], 960, std::time::Instant::now(), sequence);
+            writer.write_frame(&frame).unwrap();
+        }
+        assert_eq!(writer.duration_samples(), 3 * 960);
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = ogg::PacketReader::new(std::io::Cursor::new(bytes));
+        let mut packets = Vec::new();
+        while let Some(packet) = reader.read_packet().unwrap() {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 5);
+        assert!(packets[0].data.starts_with(b"OpusHead"));
+        assert!(packets[1].data.starts_with(b"OpusTags"));
+        assert_eq!(packets[4].data, vec![2; 40]);
+        assert!(packets[4].last_in_stream());
+        assert_eq!(packets[4].absgp_page(), OggOpusWriter::<Vec<u8>>::PRE_SKIP as u64 + 3 * 960);
+    }
+}
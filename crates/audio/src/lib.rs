@@ -5,24 +5,72 @@
 //! - Compression/décompression Opus
 //! - Lecture audio avec cpal
 //! - Pipeline de test complet
+//!
+//! # Features
+//! - `cpal` (par défaut) : capture et lecture sur les périphériques du système
+//! - `opus` (par défaut) : codec Opus
+//! - `jack`, `asio` : hôtes audio JACK (Linux) et ASIO (Windows), à choisir
+//!   via `AudioConfig::audio_host`
+//!
+//! Sans elles, seuls les types, la configuration, les périphériques factices
+//! et l'import/export restent disponibles : de quoi manipuler des trames sans
+//! dépendre des bibliothèques audio du système (serveur sans carte son).
+//!
+//! # Stabilité
+//! `audio::prelude` regroupe la surface stable. Les structures de
+//! configuration et de statistiques et `AudioError` sont `#[non_exhaustive]` :
+//! partir de `Default` puis modifier les champs, et garder un bras `_` dans
+//! les `match`.
 
 pub mod config;      // Configuration audio
 pub mod types;       // Types de données (AudioFrame, etc.)
 pub mod traits;      // Traits abstraits
+#[cfg(feature = "cpal")]
 pub mod capture;     // Implémentation capture avec cpal
+#[cfg(feature = "cpal")]
 pub mod playback;    // Implémentation lecture avec cpal
+#[cfg(feature = "opus")]
 pub mod codec;       // Implémentation Opus
+#[cfg(all(feature = "cpal", feature = "opus"))]
 pub mod pipeline;    // Pipeline de test
 pub mod error;       // Gestion d'erreurs
+pub mod devices;     // Périphériques préférés
+#[cfg(feature = "cpal")]
+pub mod device_manager; // Liste de repli des périphériques
+pub mod mock;        // Périphériques factices (tests, auto-diagnostic)
+pub mod io;          // Import/export WAV et Ogg Opus
+pub mod stretch;     // Étirement temporel (ajustement du délai de lecture)
+pub mod resample;    // Rééchantillonnage vers la fréquence native du périphérique
+pub mod realtime;    // Priorité temps réel des threads audio
+#[cfg(feature = "cpal")]
+pub mod samples;     // Conversion des formats d'échantillons du périphérique
+pub mod mixer;       // Sidetone et ducking des signaux sonores
+pub mod loudness;    // Normalisation de la sonie des pairs (LUFS)
+pub mod session;     // Démarrage à chaud de la chaîne audio d'un appel
+pub mod loopback;    // Mesures du test loopback par signal de test
+pub mod queue;       // Files bornées entre les étapes de la chaîne audio
+pub mod codec_pool;  // Threads de codage pour les appels à plusieurs pairs
+pub mod prelude;     // Surface stable, à importer d'un bloc
 
 // Réexports pour faciliter l'utilisation
 pub use config::*;
 pub use types::*;
 pub use traits::*;
 pub use error::*;
+pub use devices::{AudioDevicePreferences, DeviceSelection, HostModeReport};
 
 // Réexports des implémentations principales
+#[cfg(feature = "cpal")]
 pub use capture::CpalCapture;
+#[cfg(feature = "cpal")]
 pub use playback::CpalPlayback;
+#[cfg(feature = "cpal")]
+pub use device_manager::{AudioDeviceManager, DeviceDirection, DeviceEvent};
+#[cfg(feature = "opus")]
 pub use codec::OpusCodec;
+#[cfg(all(feature = "cpal", feature = "opus"))]
 pub use pipeline::AudioPipelineImpl;
+pub use mock::{MockCapture, MockPlayback};
+pub use session::{CallSession, CallSessionState};
+pub use loopback::{LoopbackAnalyzer, LoopbackReport, TestTone, ToneGenerator};
+pub use codec_pool::{CodecPool, PeerId};
@@ -0,0 +1,385 @@
+//! Mesures objectives du test loopback par signal de test
+//!
+//! Sans signal connu, le test loopback ne compte que des frames : la latence
+//! mesurée est celle du traitement logiciel, pas celle entendue. Avec une
+//! `TestTone`, le pipeline joue des salves de sinusoïde (une par seconde)
+//! à la place du micro, et `LoopbackAnalyzer` les retrouve dans le signal
+//! capturé :
+//! - latence acoustique : décalage entre le début de chaque salve jouée et
+//!   son retour dans le micro (haut-parleurs → air → micro compris) ;
+//! - distorsion harmonique (THD) et rapport signal/bruit (SNR) sur la
+//!   partie stable des salves reçues ;
+//! - décrochages : effondrements brefs de l'enveloppe au milieu d'une salve
+//!   (buffer vide, frame perdue).
+//!
+//! Les salves sont séparées d'assez de silence pour mesurer une latence
+//! jusqu'à `MAX_MEASURABLE_LATENCY_MS` sans confondre deux salves.
+
+use std::time::Duration;
+
+use crate::{AudioConfig, AudioFrame, AudioStats};
+
+/// Durée d'une salve de sinusoïde
+const BURST_MS: u32 = 200;
+
+/// Période des salves : une par seconde
+const BURST_PERIOD_MS: u32 = 1000;
+
+/// Latence au-delà de laquelle une salve retardée se confond avec la suivante
+pub const MAX_MEASURABLE_LATENCY_MS: u32 = BURST_PERIOD_MS - BURST_MS;
+
+/// Durée d'un bloc de l'enveloppe (résolution de la latence)
+const ENVELOPE_BLOCK_MS: u32 = 1;
+
+/// Marge ignorée aux bords d'une salve (attaque, réverbération) pour la
+/// mesure de distorsion
+const STEADY_MARGIN_MS: u32 = 50;
+
+/// Harmoniques prises en compte par la THD (2 à 5)
+const HARMONICS: u32 = 5;
+
+/// Enveloppe relative (au niveau médian de la salve) sous laquelle un bloc
+/// compte comme un décrochage
+const DROPOUT_RATIO: f64 = 0.25;
+
+/// Signal de test injecté à la place du micro
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TestTone {
+    /// Fréquence de la sinusoïde en Hz
+    pub frequency_hz: f32,
+    /// Amplitude crête (0.0 à 1.0)
+    pub amplitude: f32,
+}
+
+impl Default for TestTone {
+    /// 1 kHz à -12 dBFS : audible sans saturer un petit haut-parleur
+    fn default() -> Self {
+        Self { frequency_hz: 1000.0, amplitude: 0.25 }
+    }
+}
+
+/// Génère les frames de salves de `TestTone`, au format de `AudioConfig`
+pub struct ToneGenerator {
+    tone: TestTone,
+    sample_rate: u32,
+    channels: usize,
+    samples_per_frame: usize,
+    /// Position en échantillons par canal depuis la première frame
+    position: u64,
+}
+
+impl ToneGenerator {
+    /// Crée un générateur, la première salve commençant à la première frame
+    pub fn new(tone: TestTone, config: &AudioConfig) -> Self {
+        Self {
+            tone,
+            sample_rate: config.sample_rate.max(1),
+            channels: config.channels.max(1) as usize,
+            samples_per_frame: config.samples_per_frame(),
+            position: 0,
+        }
+    }
+
+    /// Frame suivante (salve ou silence), numérotée `sequence_number`
+    pub fn next_frame(&mut self, sequence_number: u64) -> AudioFrame {
+        let period = samples_for_ms(self.sample_rate, BURST_PERIOD_MS);
+        let burst = samples_for_ms(self.sample_rate, BURST_MS);
+        let step = 2.0 * std::f64::consts::PI * self.tone.frequency_hz as f64 / self.sample_rate as f64;
+        let mut samples = Vec::with_capacity(self.samples_per_frame * self.channels);
+        for _ in 0..self.samples_per_frame {
+            let offset = self.position % period;
+            let sample = if offset < burst {
+                self.tone.amplitude * (step * offset as f64).sin() as f32
+            } else {
+                0.0
+            };
+            samples.extend(std::iter::repeat_n(sample, self.channels));
+            self.position += 1;
+        }
+        let mut frame = AudioFrame::new(samples, sequence_number);
+        frame.media_timestamp = Some(self.position - self.samples_per_frame as u64);
+        frame
+    }
+}
+
+/// Mesures du test loopback
+#[derive(Clone, Debug, Default)]
+pub struct LoopbackReport {
+    /// Compteurs et distributions du pipeline
+    pub stats: AudioStats,
+    /// Salves de test jouées (0 sans `TestTone`)
+    pub bursts_played: u32,
+    /// Salves retrouvées dans le signal capturé
+    pub bursts_detected: u32,
+    /// Latence acoustique médiane des salves retrouvées
+    pub acoustic_latency: Option<Duration>,
+    /// Distorsion harmonique totale du signal reçu, en %
+    pub thd_percent: Option<f32>,
+    /// Rapport signal/bruit du signal reçu, en dB
+    pub snr_db: Option<f32>,
+    /// Décrochages détectés au milieu des salves retrouvées
+    pub dropouts: u32,
+}
+
+impl LoopbackReport {
+    /// Indique si le signal de test a été entendu par le micro
+    pub fn tone_detected(&self) -> bool {
+        self.bursts_detected > 0
+    }
+}
+
+/// Retrouve les salves d'un `ToneGenerator` dans le signal capturé
+///
+/// Les frames capturées sont ajoutées dans l'ordre, la première capturée
+/// en même temps que la première frame de salves était jouée : la latence
+/// se lit alors directement en échantillons. Le signal est gardé en mono
+/// (moyenne des canaux), soit ~200 Ko par seconde de test à 48 kHz.
+///
+/// # Example
+/// ```rust
+/// use audio::{AudioConfig, AudioFrame, LoopbackAnalyzer, TestTone, ToneGenerator};
+///
+/// let config = AudioConfig::default();
+/// let mut generator = ToneGenerator::new(TestTone::default(), &config);
+/// let mut analyzer = LoopbackAnalyzer::new(TestTone::default(), &config);
+/// // Retour parfait, décalé de deux frames (40ms)
+/// analyzer.push_captured(&AudioFrame::silence(config.samples_per_frame(), 0));
+/// analyzer.push_captured(&AudioFrame::silence(config.samples_per_frame(), 1));
+/// for sequence in 0..100 {
+///     analyzer.push_captured(&generator.next_frame(sequence));
+/// }
+/// let report = analyzer.report(Default::default());
+/// assert_eq!(report.acoustic_latency, Some(std::time::Duration::from_millis(40)));
+/// ```
+pub struct LoopbackAnalyzer {
+    tone: TestTone,
+    sample_rate: u32,
+    channels: usize,
+    captured: Vec<f64>,
+}
+
+impl LoopbackAnalyzer {
+    /// Crée un analyseur pour les salves de `tone`
+    pub fn new(tone: TestTone, config: &AudioConfig) -> Self {
+        Self {
+            tone,
+            sample_rate: config.sample_rate.max(1),
+            channels: config.channels.max(1) as usize,
+            captured: Vec::new(),
+        }
+    }
+
+    /// Ajoute une frame capturée
+    pub fn push_captured(&mut self, frame: &AudioFrame) {
+        self.captured.extend(
+            frame.samples
+                .chunks(self.channels)
+                .map(|channels| channels.iter().map(|&s| s as f64).sum::<f64>() / channels.len() as f64),
+        );
+    }
+
+    /// Analyse le signal capturé et complète `stats` des mesures
+    pub fn report(&self, stats: AudioStats) -> LoopbackReport {
+        let period = samples_for_ms(self.sample_rate, BURST_PERIOD_MS) as usize;
+        let burst = samples_for_ms(self.sample_rate, BURST_MS) as usize;
+        let block = samples_for_ms(self.sample_rate, ENVELOPE_BLOCK_MS).max(1) as usize;
+        let envelope: Vec<f64> = self.captured.chunks_exact(block).map(rms).collect();
+
+        let mut report = LoopbackReport { stats, ..LoopbackReport::default() };
+        // Salves entières seulement : une salve tronquée fausserait les mesures
+        report.bursts_played = (self.captured.len() / period) as u32;
+        let Some(threshold) = detection_threshold(&envelope) else {
+            return report;
+        };
+
+        let blocks_per_period = period / block;
+        let blocks_per_burst = burst / block;
+        let margin = samples_for_ms(self.sample_rate, STEADY_MARGIN_MS) as usize;
+        let mut latencies = Vec::new();
+        let (mut fundamental, mut harmonics, mut total) = (0.0, 0.0, 0.0);
+        for index in 0..report.bursts_played as usize {
+            // Premier bloc au-dessus du seuil après le début de la salve jouée
+            let start = index * blocks_per_period;
+            let end = (start + blocks_per_period).min(envelope.len());
+            let Some(onset) = (start..end).find(|&b| envelope[b] >= threshold) else {
+                continue;
+            };
+            if onset + blocks_per_burst > envelope.len() {
+                continue;
+            }
+            report.bursts_detected += 1;
+            latencies.push((onset - start) * block);
+            report.dropouts += count_dropouts(&envelope[onset..onset + blocks_per_burst]);
+
+            let steady = &self.captured[onset * block + margin..onset * block + burst - margin];
+            let powers = self.tone_powers(steady);
+            fundamental += powers.0;
+            harmonics += powers.1;
+            total += powers.2;
+        }
+
+        if latencies.is_empty() {
+            return report;
+        }
+        latencies.sort_unstable();
+        let median = latencies[latencies.len() / 2] as u64;
+        report.acoustic_latency = Some(Duration::from_nanos(median * 1_000_000_000 / self.sample_rate as u64));
+        if fundamental > 0.0 {
+            let noise = (total - fundamental - harmonics).max(f64::MIN_POSITIVE);
+            report.thd_percent = Some((100.0 * (harmonics / fundamental).sqrt()) as f32);
+            report.snr_db = Some((10.0 * (fundamental / noise).log10()) as f32);
+        }
+        report
+    }
+
+    /// Puissances de la fondamentale, des harmoniques et du signal entier
+    ///
+    /// La fenêtre est réduite à un nombre entier de périodes de la
+    /// fondamentale : sans fenêtrage, la fuite spectrale reste négligeable.
+    fn tone_powers(&self, samples: &[f64]) -> (f64, f64, f64) {
+        let cycle = self.sample_rate as f64 / self.tone.frequency_hz as f64;
+        let cycles = (samples.len() as f64 / cycle).floor();
+        let samples = &samples[..((cycles * cycle).round() as usize).min(samples.len())];
+        if samples.is_empty() {
+            return (0.0, 0.0, 0.0);
+        }
+        let nyquist = self.sample_rate as f64 / 2.0;
+        let fundamental = goertzel_power(samples, self.tone.frequency_hz as f64, self.sample_rate);
+        let harmonics = (2..=HARMONICS)
+            .map(|order| self.tone.frequency_hz as f64 * order as f64)
+            .take_while(|&frequency| frequency < nyquist)
+            .map(|frequency| goertzel_power(samples, frequency, self.sample_rate))
+            .sum();
+        let total = samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64;
+        (fundamental, harmonics, total)
+    }
+}
+
+/// Nombre d'échantillons (par canal) de `ms` millisecondes
+fn samples_for_ms(sample_rate: u32, ms: u32) -> u64 {
+    sample_rate as u64 * ms as u64 / 1000
+}
+
+fn rms(samples: &[f64]) -> f64 {
+    (samples.iter().map(|s| s * s).sum::<f64>() / samples.len().max(1) as f64).sqrt()
+}
+
+/// Seuil de détection d'une salve dans l'enveloppe
+///
+/// À mi-chemin (en dB) entre le bruit de fond (10e centile des blocs) et le
+/// niveau des salves (95e centile) ; `None` si les deux se confondent :
+/// le micro n'a pas entendu le signal de test.
+fn detection_threshold(envelope: &[f64]) -> Option<f64> {
+    if envelope.is_empty() {
+        return None;
+    }
+    let mut sorted = envelope.to_vec();
+    sorted.sort_unstable_by(f64::total_cmp);
+    let floor = sorted[sorted.len() / 10].max(1e-6);
+    let peak = sorted[sorted.len() * 95 / 100];
+    (peak >= floor * 4.0).then(|| (floor * peak).sqrt())
+}
+
+/// Décrochages d'une salve : séries de blocs sous `DROPOUT_RATIO` fois le
+/// niveau médian, les bords exclus
+fn count_dropouts(burst: &[f64]) -> u32 {
+    let margin = ((burst.len() / 20).max(1)).min(burst.len() / 2);
+    let inner = &burst[margin..burst.len() - margin];
+    if inner.is_empty() {
+        return 0;
+    }
+    let mut sorted = inner.to_vec();
+    sorted.sort_unstable_by(f64::total_cmp);
+    let floor = sorted[sorted.len() / 2] * DROPOUT_RATIO;
+
+    let mut dropouts = 0;
+    let mut in_dropout = false;
+    for &level in inner {
+        let low = level < floor;
+        if low && !in_dropout {
+            dropouts += 1;
+        }
+        in_dropout = low;
+    }
+    dropouts
+}
+
+/// Puissance moyenne de la composante à `frequency` (algorithme de Goertzel)
+///
+/// Normalisée pour qu'une sinusoïde d'amplitude A donne A²/2, comme la
+/// puissance moyenne du signal entier.
+fn goertzel_power(samples: &[f64], frequency: f64, sample_rate: u32) -> f64 {
+    let coefficient = 2.0 * (2.0 * std::f64::consts::PI * frequency / sample_rate as f64).cos();
+    let (mut previous, mut before) = (0.0, 0.0);
+    for &sample in samples {
+        let current = sample + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+    let magnitude_squared = previous * previous + before * before - coefficient * previous * before;
+    2.0 * magnitude_squared / (samples.len() as f64).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signal reçu : silence pendant `delay_frames`, puis les salves
+    /// atténuées de moitié, avec une harmonique 3 à 1% et un trou d'une frame
+    fn captured_tone(config: &AudioConfig, delay_frames: usize, seconds: usize) -> Vec<AudioFrame> {
+        let mut generator = ToneGenerator::new(TestTone::default(), config);
+        let frames_per_second = 1000 / config.frame_duration_ms as usize;
+        let step = 2.0 * std::f32::consts::PI * 3000.0 / config.sample_rate as f32;
+        let mut position = 0usize;
+        let mut frames: Vec<AudioFrame> = (0..delay_frames)
+            .map(|sequence| AudioFrame::silence(config.samples_per_frame(), sequence as u64))
+            .collect();
+        for sequence in 0..seconds * frames_per_second - delay_frames {
+            let mut frame = generator.next_frame(sequence as u64);
+            for sample in frame.samples.iter_mut() {
+                if *sample != 0.0 {
+                    *sample = 0.5 * *sample + 0.5 * 0.25 * 0.01 * (step * position as f32).sin();
+                }
+                position += 1;
+            }
+            // Frame perdue au début de la deuxième salve (20 à 40ms), hors
+            // de la fenêtre de mesure de la distorsion
+            if sequence == frames_per_second + 1 {
+                frame.samples.fill(0.0);
+            }
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_measures_delayed_tone() {
+        let config = AudioConfig::default();
+        let mut analyzer = LoopbackAnalyzer::new(TestTone::default(), &config);
+        for frame in captured_tone(&config, 3, 4) {
+            analyzer.push_captured(&frame);
+        }
+
+        let report = analyzer.report(AudioStats::default());
+        assert_eq!((report.bursts_played, report.bursts_detected), (4, 4));
+        assert_eq!(report.acoustic_latency, Some(Duration::from_millis(60)));
+        assert_eq!(report.dropouts, 1);
+        let thd = report.thd_percent.unwrap();
+        assert!((thd - 1.0).abs() < 0.1, "THD {}%", thd);
+        assert!(report.snr_db.unwrap() > 20.0);
+    }
+
+    #[test]
+    fn test_silence_detects_nothing() {
+        let config = AudioConfig::default();
+        let mut analyzer = LoopbackAnalyzer::new(TestTone::default(), &config);
+        for sequence in 0..100 {
+            analyzer.push_captured(&AudioFrame::silence(config.samples_per_frame(), sequence));
+        }
+
+        let report = analyzer.report(AudioStats::default());
+        assert_eq!(report.bursts_played, 2);
+        assert!(!report.tone_detected());
+        assert_eq!(report.acoustic_latency, None);
+    }
+}
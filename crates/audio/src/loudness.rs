@@ -0,0 +1,370 @@
+//! Normalisation de la sonie des pairs (LUFS)
+//!
+//! D'un pair à l'autre, le niveau reçu varie de plus de 20 dB (micro, gain
+//! du système, distance à la bouche). `LoudnessNormalizer` mesure la sonie
+//! à long terme du flux reçu et corrige lentement le gain vers une cible
+//! (`AudioConfig::loudness_target_lufs`, -18 LUFS par défaut) : pairs
+//! discrets et pairs trop forts sont nivelés sans réglage.
+//!
+//! La mesure suit la norme ITU-R BS.1770 : filtre de pondération K, blocs
+//! de 400ms avec recouvrement de 75%, gate absolue et relative. La gate
+//! absolue est bien plus haute que celle de la norme (-50 LUFS au lieu de
+//! -70) : seule la voix compte, pas le bruit de fond des silences. La
+//! moyenne est exponentielle (une dizaine de secondes) plutôt qu'intégrée
+//! sur tout l'appel, pour suivre un pair qui change de micro.
+//!
+//! Le gain varie d'au plus `GAIN_SLEW_DB_PER_SEC` : la correction ne
+//! s'entend pas comme un « pompage ». Il est aussi réduit, le temps d'une
+//! frame, si le signal amplifié saturait.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use crate::{AudioConfig, AudioFrame};
+
+/// Durée d'un sous-bloc de mesure (un quart de bloc BS.1770)
+const SUB_BLOCK_MS: u32 = 100;
+
+/// Sous-bloc par bloc de 400ms
+const SUB_BLOCKS_PER_BLOCK: usize = 4;
+
+/// Constante de temps de la moyenne à long terme, en blocs de 100ms
+const LONG_TERM_BLOCKS: f64 = 100.0;
+
+/// Gate absolue : blocs plus faibles ignorés (silence, bruit de fond)
+const ABSOLUTE_GATE_LUFS: f64 = -50.0;
+
+/// Gate relative : blocs plus faibles que la sonie courante moins cet écart ignorés
+const RELATIVE_GATE_LU: f64 = 10.0;
+
+/// Filtre biquad (forme directe II transposée)
+#[derive(Clone, Copy, Debug, Default)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// Filtre de pondération K d'un canal (BS.1770 : plateau aigu puis passe-haut)
+#[derive(Clone, Copy, Debug)]
+struct KWeighting {
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    /// Coefficients recalculés pour `sample_rate` (ceux de la norme sont donnés à 48 kHz)
+    fn new(sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b0: (vh + vb * k / q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Biquad::default()
+        };
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            ..Biquad::default()
+        };
+
+        Self { shelf, highpass }
+    }
+
+    fn process(&mut self, sample: f32) -> f64 {
+        self.highpass.process(self.shelf.process(sample as f64))
+    }
+}
+
+/// Sonie (LUFS) d'une énergie pondérée K
+fn loudness_of(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(1e-12).log10()
+}
+
+/// Normalise la sonie du flux reçu vers une cible
+///
+/// Appliqué frame par frame, hors du callback temps réel
+/// (`CpalPlayback::play_frame`).
+///
+/// # Example
+/// ```rust
+/// use audio::{AudioConfig, AudioFrame};
+/// use audio::loudness::LoudnessNormalizer;
+///
+/// let config = AudioConfig::default();
+/// let mut normalizer = LoudnessNormalizer::new(&config);
+///
+/// // Pair discret : sinus 1 kHz à -32 dBFS, environ -35 LUFS
+/// for sequence in 0..500 {
+///     let samples = (0..config.samples_per_frame())
+///         .map(|i| {
+///             let t = (sequence * 960 + i) as f32 / 48000.0;
+///             0.025 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin()
+///         })
+///         .collect();
+///     normalizer.process(&mut AudioFrame::new(samples, sequence as u64));
+/// }
+/// assert_eq!(normalizer.gain_db(), LoudnessNormalizer::MAX_GAIN_DB);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoudnessNormalizer {
+    /// Sonie visée, en LUFS
+    target_lufs: f64,
+
+    /// Fréquence d'échantillonnage des frames
+    sample_rate: u32,
+
+    /// Canaux entrelacés des frames
+    channels: usize,
+
+    /// Pondération K, un filtre par canal
+    filters: Vec<KWeighting>,
+
+    /// Trames (tous canaux) par sous-bloc
+    sub_block_len: usize,
+
+    /// Énergie pondérée et trames du sous-bloc en cours
+    sub_block_energy: f64,
+    sub_block_frames: usize,
+
+    /// Énergie moyenne des derniers sous-blocs (un bloc de 400ms)
+    recent: VecDeque<f64>,
+
+    /// Énergie moyenne à long terme des blocs retenus par les gates
+    long_term: Option<f64>,
+
+    /// Gain appliqué, en dB
+    gain_db: f64,
+
+    /// Variation maximale du gain par frame, en dB
+    max_step_db: f64,
+}
+
+impl LoudnessNormalizer {
+    /// Sonie visée par défaut (voix, marge pour les pics)
+    pub const DEFAULT_TARGET_LUFS: f32 = -18.0;
+
+    /// Correction maximale, dans un sens comme dans l'autre
+    pub const MAX_GAIN_DB: f32 = 12.0;
+
+    /// Vitesse maximale de la correction
+    pub const GAIN_SLEW_DB_PER_SEC: f32 = 3.0;
+
+    /// Crée un normaliseur vers `AudioConfig::loudness_target_lufs`
+    pub fn new(config: &AudioConfig) -> Self {
+        let channels = config.channels.max(1) as usize;
+        Self {
+            target_lufs: config.loudness_target_lufs as f64,
+            sample_rate: config.sample_rate,
+            channels,
+            filters: vec![KWeighting::new(config.sample_rate); channels],
+            sub_block_len: (config.sample_rate * SUB_BLOCK_MS / 1000).max(1) as usize,
+            sub_block_energy: 0.0,
+            sub_block_frames: 0,
+            recent: VecDeque::with_capacity(SUB_BLOCKS_PER_BLOCK),
+            long_term: None,
+            gain_db: 0.0,
+            max_step_db: (Self::GAIN_SLEW_DB_PER_SEC * config.frame_duration_ms as f32 / 1000.0) as f64,
+        }
+    }
+
+    /// Sonie à long terme mesurée, en LUFS (`None` tant que le pair n'a pas parlé)
+    pub fn loudness(&self) -> Option<f32> {
+        self.long_term.map(|energy| loudness_of(energy) as f32)
+    }
+
+    /// Gain appliqué actuellement, en dB
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db as f32
+    }
+
+    /// Oublie la mesure (nouveau pair) : gain ramené à 0 dB
+    pub fn reset(&mut self) {
+        self.filters = vec![KWeighting::new(self.sample_rate); self.channels];
+        self.sub_block_energy = 0.0;
+        self.sub_block_frames = 0;
+        self.recent.clear();
+        self.long_term = None;
+        self.gain_db = 0.0;
+    }
+
+    /// Mesure la frame puis lui applique le gain de correction
+    ///
+    /// Le gain passe de sa valeur précédente à la nouvelle par une rampe sur
+    /// la frame, et reste sous le seuil de saturation.
+    pub fn process(&mut self, frame: &mut AudioFrame) {
+        self.measure(&frame.samples);
+
+        let previous = self.gain_db;
+        if let Some(loudness) = self.loudness() {
+            let wanted = (self.target_lufs - loudness as f64)
+                .clamp(-Self::MAX_GAIN_DB as f64, Self::MAX_GAIN_DB as f64);
+            self.gain_db += (wanted - self.gain_db).clamp(-self.max_step_db, self.max_step_db);
+        }
+
+        let peak = frame.samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        let ceiling = if peak > 0.0 { 1.0 / peak } else { f32::MAX };
+        let from = (10f64.powf(previous / 20.0) as f32).min(ceiling);
+        let to = (10f64.powf(self.gain_db / 20.0) as f32).min(ceiling);
+        if from == 1.0 && to == 1.0 {
+            return;
+        }
+
+        let frames = (frame.samples.len() / self.channels).max(1) as f32;
+        for (index, chunk) in frame.samples.chunks_mut(self.channels).enumerate() {
+            let gain = from + (to - from) * (index + 1) as f32 / frames;
+            for sample in chunk {
+                *sample *= gain;
+            }
+        }
+    }
+
+    /// Accumule l'énergie pondérée des échantillons, bloc par bloc
+    fn measure(&mut self, samples: &[f32]) {
+        for chunk in samples.chunks_exact(self.channels) {
+            for (filter, &sample) in self.filters.iter_mut().zip(chunk) {
+                let weighted = filter.process(sample);
+                self.sub_block_energy += weighted * weighted;
+            }
+            self.sub_block_frames += 1;
+
+            if self.sub_block_frames == self.sub_block_len {
+                self.close_sub_block();
+            }
+        }
+    }
+
+    /// Termine un sous-bloc : le bloc de 400ms qui s'y achève passe les gates
+    fn close_sub_block(&mut self) {
+        if self.recent.len() == SUB_BLOCKS_PER_BLOCK {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(self.sub_block_energy / self.sub_block_frames as f64);
+        self.sub_block_energy = 0.0;
+        self.sub_block_frames = 0;
+
+        if self.recent.len() < SUB_BLOCKS_PER_BLOCK {
+            return;
+        }
+        let block = self.recent.iter().sum::<f64>() / SUB_BLOCKS_PER_BLOCK as f64;
+        let loudness = loudness_of(block);
+        if loudness < ABSOLUTE_GATE_LUFS {
+            return;
+        }
+
+        self.long_term = match self.long_term {
+            None => Some(block),
+            Some(energy) if loudness < loudness_of(energy) - RELATIVE_GATE_LU => Some(energy),
+            Some(energy) => Some(energy + (block - energy) / LONG_TERM_BLOCKS),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames d'un sinus à 1 kHz d'amplitude `amplitude`
+    fn sine_frames(config: &AudioConfig, amplitude: f32, seconds: u32) -> Vec<AudioFrame> {
+        let frame_len = config.samples_per_frame();
+        let count = seconds * 1000 / config.frame_duration_ms as u32;
+        (0..count as usize)
+            .map(|sequence| {
+                let samples = (0..frame_len)
+                    .map(|i| {
+                        let t = (sequence * frame_len + i) as f32 / config.sample_rate as f32;
+                        amplitude * (2.0 * std::f32::consts::PI * 1000.0 * t).sin()
+                    })
+                    .collect();
+                AudioFrame::new(samples, sequence as u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_measures_reference_sine() {
+        // BS.1770 : sinus 1 kHz à 0 dBFS en mono = -3.01 LUFS
+        for sample_rate in [48000, 16000] {
+            let config = AudioConfig { sample_rate, ..AudioConfig::default() };
+            let mut normalizer = LoudnessNormalizer::new(&config);
+            for frame in &sine_frames(&config, 0.1, 3) {
+                normalizer.measure(&frame.samples);
+            }
+            let loudness = normalizer.loudness().unwrap();
+            assert!((loudness - -23.01).abs() < 0.1, "{} Hz : {} LUFS", sample_rate, loudness);
+        }
+    }
+
+    #[test]
+    fn test_levels_quiet_and_loud_peers() {
+        let config = AudioConfig::default();
+
+        // -23 LUFS : remonté de 5 dB, progressivement
+        let mut normalizer = LoudnessNormalizer::new(&config);
+        let mut gains = Vec::new();
+        for mut frame in sine_frames(&config, 0.1, 10) {
+            normalizer.process(&mut frame);
+            gains.push(normalizer.gain_db());
+        }
+        assert!((normalizer.gain_db() - 5.0).abs() < 0.1);
+        assert!(gains.windows(2).all(|pair| pair[1] - pair[0] <= 0.061));
+
+        // -9 LUFS : baissé de 9 dB
+        normalizer.reset();
+        let mut last = Vec::new();
+        for mut frame in sine_frames(&config, 0.5, 10) {
+            normalizer.process(&mut frame);
+            last = frame.samples;
+        }
+        assert!((normalizer.gain_db() - -9.0).abs() < 0.1);
+        let peak = last.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!((peak - 0.5 * 10f32.powf(-9.0 / 20.0)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_pauses_keep_gain() {
+        let config = AudioConfig::default();
+        let mut normalizer = LoudnessNormalizer::new(&config);
+        for mut frame in sine_frames(&config, 0.1, 10) {
+            normalizer.process(&mut frame);
+        }
+        let gain = normalizer.gain_db();
+
+        // Silence puis bruit de fond : sous les gates, la mesure ne bouge
+        // plus (seuls les blocs à cheval sur la fin de la voix comptent)
+        for amplitude in [0.0, 0.002] {
+            for mut frame in sine_frames(&config, amplitude, 5) {
+                normalizer.process(&mut frame);
+            }
+        }
+        assert!((normalizer.gain_db() - gain).abs() < 0.1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Sons mixés à la voix du pair pendant la lecture
+//!
+//! Deux conforts d'écoute au casque :
+//! - le sidetone, une fraction du micro renvoyée dans le casque : sans lui,
+//!   on s'entend mal et on parle trop fort. Les échantillons passent
+//!   directement du callback de capture à celui de lecture (`SidetoneTap`),
+//!   sans attendre une frame complète ni passer par le codec : la latence
+//!   ajoutée est celle des buffers des périphériques ;
+//! - le ducking des signaux sonores de l'application (bips de connexion,
+//!   notifications) : atténués tant que le pair parle, pour ne pas couvrir
+//!   sa voix.
+//!
+//! Les gains viennent de `AudioConfig::sidetone_gain` et
+//! `AudioConfig::ducking_gain`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::{AudioConfig, AudioFrame};
+
+/// Échantillons du micro transmis de la capture à la lecture
+///
+/// Les clones partagent le même buffer : la capture y pousse ce qu'elle
+/// reçoit (`CpalCapture::set_sidetone_tap`), la lecture en retire ce qu'il
+/// lui faut. Aucun des deux callbacks ne bloque : en cas de contention, les
+/// échantillons sont simplement sautés.
+#[derive(Clone, Debug)]
+pub struct SidetoneTap {
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    capacity: usize,
+}
+
+impl SidetoneTap {
+    /// Crée un tap gardant au plus deux frames de `config`
+    ///
+    /// Au-delà (lecture arrêtée ou en retard), les échantillons les plus
+    /// anciens sont jetés : un sidetone n'a d'intérêt qu'immédiat.
+    pub fn new(config: &AudioConfig) -> Self {
+        let capacity = config.samples_per_frame() * config.channels as usize * 2;
+        Self {
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Ajoute des échantillons du micro (`AudioConfig::channels` entrelacés)
+    pub fn push(&self, samples: &[f32]) {
+        let Ok(mut buffer) = self.samples.try_lock() else {
+            return;
+        };
+        buffer.extend(samples);
+        let excess = buffer.len().saturating_sub(self.capacity);
+        buffer.drain(..excess);
+    }
+
+    /// Échantillons en attente de lecture
+    pub fn pending(&self) -> usize {
+        self.samples.try_lock().map_or(0, |buffer| buffer.len())
+    }
+
+    /// Ajoute les échantillons en attente à `output`, multipliés par `gain`
+    fn mix_into(&self, output: &mut [f32], gain: f32) {
+        let Ok(mut buffer) = self.samples.try_lock() else {
+            return;
+        };
+        let count = output.len().min(buffer.len());
+        for (out, sample) in output.iter_mut().zip(buffer.drain(..count)) {
+            *out += sample * gain;
+        }
+    }
+
+    /// Jette les échantillons en attente (sidetone coupé)
+    fn discard(&self) {
+        if let Ok(mut buffer) = self.samples.try_lock() {
+            buffer.clear();
+        }
+    }
+}
+
+/// Mixe le sidetone et les signaux sonores dans la sortie de lecture
+///
+/// Les clones partagent le tap, le gain du sidetone et la file des signaux :
+/// `CpalPlayback` en garde un pour ses réglages et en confie un au callback.
+///
+/// # Example
+/// ```rust
+/// use std::collections::VecDeque;
+/// use audio::AudioConfig;
+/// use audio::mixer::PlaybackMixer;
+///
+/// let mut config = AudioConfig::default();
+/// config.sidetone_gain = 0.5;
+/// let mut mixer = PlaybackMixer::new(&config);
+/// mixer.sidetone_tap().push(&[0.4, 0.4]);
+///
+/// // Pas de voix du pair (underrun) : le sidetone est joué quand même
+/// let mut samples = VecDeque::new();
+/// mixer.mix(&mut samples, 4);
+/// assert_eq!(samples, [0.2, 0.2, 0.0, 0.0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PlaybackMixer {
+    /// Micro à renvoyer dans le casque
+    sidetone: SidetoneTap,
+
+    /// Gain du sidetone (bits d'un f32), réglable pendant la lecture
+    sidetone_gain: Arc<AtomicU32>,
+
+    /// Échantillons des signaux sonores en attente
+    cues: Arc<Mutex<VecDeque<f32>>>,
+
+    /// Gain des signaux pendant que le pair parle
+    ducking_gain: f32,
+
+    /// Gain courant des signaux, qui rejoint sa cible par une rampe
+    cue_gain: f32,
+
+    /// Variation maximale de `cue_gain` par échantillon
+    ramp_step: f32,
+
+    /// Durée, en échantillons, du ducking après la dernière voix détectée
+    hold_samples: usize,
+
+    /// Échantillons de ducking restants
+    hold_remaining: usize,
+}
+
+impl PlaybackMixer {
+    /// Niveau RMS au-dessus duquel le pair est considéré comme parlant (-40 dBFS)
+    pub const VOICE_THRESHOLD: f32 = 0.01;
+
+    /// Durée de la rampe de gain des signaux (pas de clic au ducking)
+    pub const DUCKING_RAMP_MS: u32 = 10;
+
+    /// Maintien du ducking après la voix, pour ne pas remonter les signaux
+    /// entre deux mots
+    pub const DUCKING_HOLD_MS: u32 = 300;
+
+    /// Crée un mixer réglé selon `config` (sidetone et ducking)
+    pub fn new(config: &AudioConfig) -> Self {
+        let samples_per_ms = (config.sample_rate / 1000) as usize * config.channels as usize;
+        Self {
+            sidetone: SidetoneTap::new(config),
+            sidetone_gain: Arc::new(AtomicU32::new(config.sidetone_gain.clamp(0.0, 1.0).to_bits())),
+            cues: Arc::new(Mutex::new(VecDeque::new())),
+            ducking_gain: config.ducking_gain.clamp(0.0, 1.0),
+            cue_gain: 1.0,
+            ramp_step: 1.0 / (samples_per_ms * Self::DUCKING_RAMP_MS as usize).max(1) as f32,
+            hold_samples: samples_per_ms * Self::DUCKING_HOLD_MS as usize,
+            hold_remaining: 0,
+        }
+    }
+
+    /// Tap à confier à la capture pour alimenter le sidetone
+    pub fn sidetone_tap(&self) -> SidetoneTap {
+        self.sidetone.clone()
+    }
+
+    /// Gain courant du sidetone
+    pub fn sidetone_gain(&self) -> f32 {
+        f32::from_bits(self.sidetone_gain.load(Ordering::Relaxed))
+    }
+
+    /// Change le gain du sidetone, borné à [0.0, 1.0] (0.0 le coupe)
+    pub fn set_sidetone_gain(&self, gain: f32) {
+        let gain = if gain.is_nan() { 0.0 } else { gain.clamp(0.0, 1.0) };
+        self.sidetone_gain.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Ajoute un signal sonore à jouer par-dessus la voix, une seule fois
+    pub fn queue_cue(&self, cue: &AudioFrame) {
+        if let Ok(mut cues) = self.cues.lock() {
+            cues.extend(&cue.samples);
+        }
+    }
+
+    /// Mixe le sidetone et les signaux dans les `needed` premiers échantillons
+    ///
+    /// `samples` contient la voix du pair à jouer ; s'il en manque (underrun),
+    /// il est complété par du silence pour que sidetone et signaux restent
+    /// audibles. Appelé par le callback de lecture : ne bloque pas.
+    pub fn mix(&mut self, samples: &mut VecDeque<f32>, needed: usize) {
+        let voice_len = samples.len().min(needed);
+        self.track_voice(samples.range(..voice_len), needed);
+
+        let sidetone_gain = self.sidetone_gain();
+        let cues_pending = self.cues.try_lock().is_ok_and(|cues| !cues.is_empty());
+        if sidetone_gain == 0.0 {
+            self.sidetone.discard();
+            if !cues_pending {
+                return;
+            }
+        }
+
+        if samples.len() < needed {
+            samples.resize(needed, 0.0);
+        }
+        let output = &mut samples.make_contiguous()[..needed];
+        if sidetone_gain > 0.0 {
+            self.sidetone.mix_into(output, sidetone_gain);
+        }
+        if cues_pending {
+            self.mix_cues(output);
+        }
+    }
+
+    /// Met à jour le maintien du ducking d'après le niveau de la voix
+    fn track_voice<'a>(&mut self, voice: impl ExactSizeIterator<Item = &'a f32>, needed: usize) {
+        let len = voice.len();
+        let energy = voice.map(|sample| sample * sample).sum::<f32>() / len.max(1) as f32;
+        if energy.sqrt() > Self::VOICE_THRESHOLD {
+            self.hold_remaining = self.hold_samples;
+        } else {
+            self.hold_remaining = self.hold_remaining.saturating_sub(needed);
+        }
+    }
+
+    /// Ajoute les signaux en attente à `output`, atténués pendant la voix
+    fn mix_cues(&mut self, output: &mut [f32]) {
+        let Ok(mut cues) = self.cues.try_lock() else {
+            return;
+        };
+        let target = if self.hold_remaining > 0 { self.ducking_gain } else { 1.0 };
+        let count = output.len().min(cues.len());
+        for (out, sample) in output.iter_mut().zip(cues.drain(..count)) {
+            self.cue_gain += (target - self.cue_gain).clamp(-self.ramp_step, self.ramp_step);
+            *out += sample * self.cue_gain;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidetone_tap_keeps_latest_samples() {
+        let config = AudioConfig::default();
+        let tap = SidetoneTap::new(&config);
+        let capacity = config.samples_per_frame() * 2;
+
+        // Lecture arrêtée : seules les deux dernières frames sont gardées
+        for value in 0..3 {
+            tap.push(&vec![value as f32; config.samples_per_frame()]);
+        }
+        assert_eq!(tap.pending(), capacity);
+
+        let mut output = vec![0.0; capacity];
+        tap.mix_into(&mut output, 0.5);
+        assert_eq!(output[0], 0.5);
+        assert_eq!(output[capacity - 1], 1.0);
+        assert_eq!(tap.pending(), 0);
+    }
+
+    #[test]
+    fn test_sidetone_mixed_over_voice() {
+        let config = AudioConfig { sidetone_gain: 0.2, ..AudioConfig::default() };
+        let mut mixer = PlaybackMixer::new(&config);
+        mixer.sidetone_tap().push(&[0.5; 4]);
+
+        let mut samples = VecDeque::from([0.1; 6]);
+        mixer.mix(&mut samples, 4);
+        let mixed: Vec<f32> = samples.iter().map(|sample| (sample * 100.0).round() / 100.0).collect();
+        assert_eq!(mixed, [0.2, 0.2, 0.2, 0.2, 0.1, 0.1]);
+
+        // Coupé : le micro en attente est jeté, la voix passe telle quelle
+        mixer.set_sidetone_gain(0.0);
+        mixer.sidetone_tap().push(&[0.5; 4]);
+        let mut samples = VecDeque::from([0.1; 4]);
+        mixer.mix(&mut samples, 4);
+        assert_eq!(samples, [0.1; 4]);
+        assert_eq!(mixer.sidetone_tap().pending(), 0);
+    }
+
+    #[test]
+    fn test_cues_ducked_while_peer_speaks() {
+        let config = AudioConfig::default();
+        let mut mixer = PlaybackMixer::new(&config);
+        let frame_len = config.samples_per_frame();
+        let cue = AudioFrame::new(vec![0.5; frame_len * 2], 0);
+
+        // Pair silencieux : le signal est joué à plein volume
+        mixer.queue_cue(&cue);
+        let mut samples = VecDeque::new();
+        mixer.mix(&mut samples, frame_len);
+        assert!(samples.iter().all(|&sample| sample == 0.5));
+
+        // Le pair parle : le signal descend vers `ducking_gain` sans saut
+        let mut samples = VecDeque::from(vec![0.0; frame_len]);
+        for sample in samples.iter_mut().step_by(2) {
+            *sample = 0.2;
+        }
+        let voice = samples.clone();
+        mixer.mix(&mut samples, frame_len);
+        let cue_levels: Vec<f32> = samples.iter().zip(&voice).map(|(mixed, voice)| mixed - voice).collect();
+        assert!(cue_levels.windows(2).all(|pair| pair[0] >= pair[1] - 1e-6));
+        let ducked = 0.5 * config.ducking_gain;
+        assert!((cue_levels[frame_len - 1] - ducked).abs() < 1e-4);
+        assert!(cue_levels[0] > ducked);
+    }
+}
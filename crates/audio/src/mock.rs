@@ -0,0 +1,331 @@
+//! Périphériques audio factices
+//!
+//! Implémentations de `AudioCapture` et `AudioPlayback` sans matériel,
+//! pour les tests et l'auto-diagnostic (`network::UdpNetworkManager::self_test`).
+//! La capture produit une sinusoïde (ou des frames fournies) au rythme réel
+//! d'une frame par `frame_duration_ms`, la lecture conserve les frames jouées.
+
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{
+    AudioCapture, AudioPlayback, AudioConfig, AudioError, AudioFrame, AudioResult, DeviceInfo,
+    MockAudioDevice,
+};
+
+/// Fréquence de la sinusoïde générée par défaut (La 440 Hz)
+const TONE_FREQUENCY_HZ: f32 = 440.0;
+
+/// Amplitude de la sinusoïde générée
+const TONE_AMPLITUDE: f32 = 0.5;
+
+/// Microphone factice
+///
+/// Retourne d'abord les frames fournies par `set_test_data`, puis une
+/// sinusoïde continue.
+pub struct MockCapture {
+    config: AudioConfig,
+    test_frames: VecDeque<AudioFrame>,
+    pending_error: Option<AudioError>,
+    latency_ms: u32,
+    sequence_counter: u64,
+    phase: f32,
+    is_recording: bool,
+    is_paused: bool,
+}
+
+impl MockCapture {
+    /// Crée un microphone factice
+    pub fn new(config: AudioConfig) -> Self {
+        Self {
+            config,
+            test_frames: VecDeque::new(),
+            pending_error: None,
+            latency_ms: 0,
+            sequence_counter: 0,
+            phase: 0.0,
+            is_recording: false,
+            is_paused: false,
+        }
+    }
+
+    /// Génère la frame suivante de la sinusoïde
+    fn next_tone_frame(&mut self) -> AudioFrame {
+        let step = 2.0 * std::f32::consts::PI * TONE_FREQUENCY_HZ / self.config.sample_rate as f32;
+        let samples = (0..self.config.samples_per_frame() * self.config.channels as usize)
+            .map(|_| {
+                let sample = TONE_AMPLITUDE * self.phase.sin();
+                self.phase = (self.phase + step) % (2.0 * std::f32::consts::PI);
+                sample
+            })
+            .collect();
+        AudioFrame::new(samples, self.sequence_counter)
+    }
+}
+
+#[async_trait]
+impl AudioCapture for MockCapture {
+    async fn start(&mut self) -> AudioResult<()> {
+        self.is_recording = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> AudioResult<()> {
+        self.is_recording = false;
+        self.is_paused = false;
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> AudioResult<()> {
+        if !self.is_recording {
+            return Err(AudioError::InitializationError("capture factice non démarrée".to_string()));
+        }
+        self.is_paused = true;
+        Ok(())
+    }
+
+    async fn resume(&mut self) -> AudioResult<()> {
+        if !self.is_recording {
+            return Err(AudioError::InitializationError("capture factice non démarrée".to_string()));
+        }
+        self.is_paused = false;
+        Ok(())
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    async fn next_frame(&mut self) -> AudioResult<AudioFrame> {
+        if !self.is_recording {
+            return Err(AudioError::InitializationError("capture factice non démarrée".to_string()));
+        }
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        // Rythme réel : une frame toutes les frame_duration_ms
+        let delay = self.config.frame_duration_ms as u64 + self.latency_ms as u64;
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+
+        self.sequence_counter += 1;
+        let mut frame = match self.test_frames.pop_front() {
+            Some(mut frame) => {
+                frame.sequence_number = self.sequence_counter;
+                frame
+            }
+            None => self.next_tone_frame(),
+        };
+        // Micro coupé : le signal est jeté, le rythme reste le même
+        if self.is_paused {
+            frame.samples.fill(0.0);
+        }
+        // Horloge de capture régulière : une frame complète par appel
+        let frame_samples = self.config.samples_per_frame() as u64;
+        frame.media_timestamp = Some((self.sequence_counter - 1) * frame_samples);
+        Ok(frame)
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    fn device_info(&self) -> String {
+        format!("Microphone factice ({} Hz)", TONE_FREQUENCY_HZ)
+    }
+
+    fn device_details(&self) -> DeviceInfo {
+        DeviceInfo {
+            name: self.device_info(),
+            sample_rate: self.config.sample_rate,
+            channels: self.config.channels,
+            sample_format: "f32".to_string(),
+            buffer_size: Some(self.config.samples_per_frame() as u32),
+            host_api: "Mock".to_string(),
+        }
+    }
+}
+
+impl crate::traits::sealed::Sealed for MockCapture {}
+
+impl MockAudioDevice for MockCapture {
+    fn set_test_data(&mut self, frames: Vec<AudioFrame>) {
+        self.test_frames = frames.into();
+    }
+
+    fn simulate_error(&mut self, error: AudioError) {
+        self.pending_error = Some(error);
+    }
+
+    fn set_simulated_latency(&mut self, latency_ms: u32) {
+        self.latency_ms = latency_ms;
+    }
+}
+
+/// Haut-parleur factice
+///
+/// Conserve les frames jouées pour vérification.
+pub struct MockPlayback {
+    played: Vec<AudioFrame>,
+    pending_error: Option<AudioError>,
+    is_playing: bool,
+    is_paused: bool,
+}
+
+impl MockPlayback {
+    /// Crée un haut-parleur factice
+    pub fn new() -> Self {
+        Self {
+            played: Vec::new(),
+            pending_error: None,
+            is_playing: false,
+            is_paused: false,
+        }
+    }
+
+    /// Frames jouées depuis la création
+    pub fn played_frames(&self) -> &[AudioFrame] {
+        &self.played
+    }
+}
+
+impl Default for MockPlayback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AudioPlayback for MockPlayback {
+    async fn start(&mut self) -> AudioResult<()> {
+        self.is_playing = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> AudioResult<()> {
+        self.is_playing = false;
+        self.is_paused = false;
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> AudioResult<()> {
+        if !self.is_playing {
+            return Err(AudioError::InitializationError("lecture factice non démarrée".to_string()));
+        }
+        self.is_paused = true;
+        Ok(())
+    }
+
+    async fn resume(&mut self) -> AudioResult<()> {
+        if !self.is_playing {
+            return Err(AudioError::InitializationError("lecture factice non démarrée".to_string()));
+        }
+        self.is_paused = false;
+        Ok(())
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    async fn play_frame(&mut self, frame: AudioFrame) -> AudioResult<usize> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+        // Son coupé : la frame est jetée
+        if !self.is_paused {
+            self.played.push(frame);
+        }
+        Ok(0)
+    }
+
+    fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    fn buffer_level(&self) -> usize {
+        0
+    }
+
+    fn device_info(&self) -> String {
+        "Haut-parleur factice".to_string()
+    }
+}
+
+impl crate::traits::sealed::Sealed for MockPlayback {}
+
+impl MockAudioDevice for MockPlayback {
+    fn set_test_data(&mut self, _frames: Vec<AudioFrame>) {}
+
+    fn simulate_error(&mut self, error: AudioError) {
+        self.pending_error = Some(error);
+    }
+
+    fn set_simulated_latency(&mut self, _latency_ms: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_capture_and_playback() {
+        let config = AudioConfig::low_latency();
+        let mut capture = MockCapture::new(config.clone());
+        let mut playback = MockPlayback::new();
+
+        // Pas de frame tant que la capture n'est pas démarrée
+        assert!(capture.next_frame().await.is_err());
+
+        capture.set_test_data(vec![AudioFrame::silence(config.samples_per_frame(), 0)]);
+        capture.start().await.unwrap();
+        playback.start().await.unwrap();
+
+        let first = capture.next_frame().await.unwrap();
+        assert!(first.is_silence(0.001));
+        let second = capture.next_frame().await.unwrap();
+        assert!(!second.is_silence(0.001));
+        assert_eq!(second.samples.len(), config.samples_per_frame());
+        assert_eq!((first.sequence_number, second.sequence_number), (1, 2));
+
+        capture.simulate_error(AudioError::DeviceDisconnected);
+        assert!(matches!(capture.next_frame().await, Err(AudioError::DeviceDisconnected)));
+
+        playback.play_frame(second).await.unwrap();
+        assert_eq!(playback.played_frames().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pause_keeps_streams_open() {
+        let config = AudioConfig::low_latency();
+        let mut capture = MockCapture::new(config.clone());
+        let mut playback = MockPlayback::new();
+        assert!(capture.pause().await.is_err());
+        capture.start().await.unwrap();
+        playback.start().await.unwrap();
+
+        // Micro coupé : des frames silencieuses, au même rythme et sur la même horloge
+        let before = capture.next_frame().await.unwrap();
+        capture.pause().await.unwrap();
+        assert!(capture.is_paused() && capture.is_recording());
+        let muted = capture.next_frame().await.unwrap();
+        assert!(muted.is_silence(0.001));
+        assert_eq!(muted.media_timestamp, Some(before.media_timestamp.unwrap() + config.samples_per_frame() as u64));
+        capture.resume().await.unwrap();
+        assert!(!capture.next_frame().await.unwrap().is_silence(0.001));
+
+        // Son coupé : les frames reçues sont jetées
+        playback.pause().await.unwrap();
+        playback.play_frame(muted).await.unwrap();
+        assert!(playback.played_frames().is_empty());
+        playback.resume().await.unwrap();
+        playback.play_frame(before).await.unwrap();
+        assert_eq!(playback.played_frames().len(), 1);
+
+        // stop() lève la pause
+        capture.pause().await.unwrap();
+        capture.stop().await.unwrap();
+        assert!(!capture.is_paused());
+    }
+}
@@ -7,18 +7,377 @@
 //! 
 //! Il permet de tester tout le système audio sans réseau,
 //! idéal pour valider la latence et la qualité avant de passer au networking.
+//! 
+//! Le test loopback fait tourner chaque étape dans sa propre tâche, reliées
+//! par des files bornées (`queue::bounded`) : comme dans un vrai moteur
+//! d'appel, un encodage lent ne retarde plus la capture, et l'attente dans
+//! chaque file est mesurée. Un superviseur redémarre l'étape en échec.
 
 use async_trait::async_trait;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tokio::time::{sleep, Duration, Instant};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::queue::{self, FrameReceiver, FrameSender};
+use crate::stretch;
 use crate::{
     AudioPipeline, AudioCapture, AudioPlayback, AudioCodec,
     CpalCapture, CpalPlayback, OpusCodec,
-    AudioFrame, AudioConfig, AudioError, AudioResult, AudioStats,
+    AudioFrame, CompressedFrame, FrameOrigin, AudioConfig, AudioError, AudioResult, AudioStats, OverflowPolicy,
+    LoopbackAnalyzer, LoopbackReport, TestTone, ToneGenerator,
 };
 
+/// Frames consécutives hors de la zone visée avant une correction de
+/// cadence (500ms à 20ms par frame) : la gigue du callback de lecture fait
+/// varier le niveau d'une frame ou deux sans dérive d'horloge
+const PACING_PATIENCE_FRAMES: u32 = 25;
+
+/// Capacité des files entre étapes (100ms à 20ms par frame) : au-delà,
+/// l'attente coûterait plus en latence qu'une frame fusionnée
+const STAGE_QUEUE_FRAMES: usize = 5;
+
+/// Redémarrages d'étapes tolérés pendant un test loopback ; au-delà, le
+/// test s'arrête sur l'erreur
+const MAX_STAGE_RESTARTS: u64 = 3;
+
+/// Composants partagés entre le pipeline et les tâches de ses étapes
+type SharedCapture = Arc<Mutex<Box<dyn AudioCapture>>>;
+type SharedCodec = Arc<Mutex<Box<dyn AudioCodec>>>;
+type SharedPlayback = Arc<Mutex<Box<dyn AudioPlayback>>>;
+
+/// Correction de cadence de la boucle, d'après le niveau du buffer de lecture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PacingAdjustment {
+    /// Une frame reçue, une frame jouée
+    None,
+    /// Deux frames fusionnées en une : la lecture est en retard
+    Merge,
+    /// Une frame étirée sur deux : la lecture est en avance
+    Expand,
+}
+
+/// Régulation en boucle fermée de la cadence capture → lecture
+/// 
+/// Les horloges du micro et des haut-parleurs dérivent (0,1% courants) :
+/// sans correction, le buffer de lecture finit toujours par déborder ou se
+/// vider. Une frame est fusionnée ou étirée (WSOLA, inaudible) dès que le
+/// niveau reste hors de la zone visée pendant `PACING_PATIENCE_FRAMES`.
+#[derive(Debug)]
+struct PlaybackPacer {
+    target_frames: usize,
+    frames_above: u32,
+    frames_below: u32,
+}
+
+impl PlaybackPacer {
+    fn new(config: &AudioConfig) -> Self {
+        Self { target_frames: config.target_buffer_frames(), frames_above: 0, frames_below: 0 }
+    }
+    
+    /// Correction à appliquer à la prochaine frame, d'après le niveau actuel
+    fn adjust(&mut self, buffer_level: usize) -> PacingAdjustment {
+        if buffer_level > self.target_frames + 1 {
+            self.frames_above += 1;
+            self.frames_below = 0;
+        } else if buffer_level == 0 {
+            self.frames_below += 1;
+            self.frames_above = 0;
+        } else {
+            self.frames_above = 0;
+            self.frames_below = 0;
+        }
+        
+        if self.frames_above >= PACING_PATIENCE_FRAMES {
+            self.frames_above = 0;
+            PacingAdjustment::Merge
+        } else if self.frames_below >= PACING_PATIENCE_FRAMES {
+            self.frames_below = 0;
+            PacingAdjustment::Expand
+        } else {
+            PacingAdjustment::None
+        }
+    }
+}
+
+/// Met à jour les statistiques avec une nouvelle frame capturée
+async fn record_captured(stats: &Mutex<AudioStats>, frame: &AudioFrame) {
+    let mut stats = stats.lock().await;
+    stats.frames_captured += 1;
+    
+    // Met à jour le niveau RMS moyen
+    let frame_rms = frame.rms_level();
+    if stats.frames_captured == 1 {
+        stats.avg_rms_level = frame_rms;
+    } else {
+        // Moyenne mobile simple
+        stats.avg_rms_level = (stats.avg_rms_level * 0.9) + (frame_rms * 0.1);
+    }
+}
+
+async fn record_played(stats: &Mutex<AudioStats>, latency: Duration, origin: FrameOrigin) {
+    let mut stats = stats.lock().await;
+    stats.frames_played += 1;
+    stats.record_origin(origin);
+    stats.latency.record(latency);
+    
+    // Met à jour la latence moyenne
+    let latency_ms = latency.as_millis() as f32;
+    if stats.frames_played == 1 {
+        stats.avg_latency_ms = latency_ms;
+    } else {
+        stats.avg_latency_ms = (stats.avg_latency_ms * 0.9) + (latency_ms * 0.1);
+    }
+}
+
+/// Ajoute les temps de codage d'une frame aux distributions
+async fn record_codec_times(stats: &Mutex<AudioStats>, encode_time: Duration, decode_time: Duration) {
+    let mut stats = stats.lock().await;
+    stats.encode_time.record(encode_time);
+    stats.decode_time.record(decode_time);
+}
+
+async fn record_compression(stats: &Mutex<AudioStats>, ratio: f32) {
+    let mut stats = stats.lock().await;
+    
+    if stats.frames_captured <= 1 {
+        stats.avg_compression_ratio = ratio;
+    } else {
+        stats.avg_compression_ratio = (stats.avg_compression_ratio * 0.9) + (ratio * 0.1);
+    }
+}
+
+/// Joue une frame décodée, en comptant les frames jetées par le buffer
+/// 
+/// Quelle que soit `AudioConfig::overflow_policy`, chaque frame jetée
+/// compte dans `AudioStats::buffer_overflows`.
+async fn play_counted(playback: &mut dyn AudioPlayback, stats: &Mutex<AudioStats>, frame: AudioFrame) -> AudioResult<()> {
+    let result = playback.play_frame(frame).await;
+    let dropped = match &result {
+        Ok(dropped) => *dropped,
+        Err(AudioError::BufferOverflow) => 1,
+        Err(_) => 0,
+    };
+    if dropped > 0 {
+        stats.lock().await.buffer_overflows += dropped as u64;
+    }
+    result.map(|_| ())
+}
+
+/// Étire une frame décodée sur deux frames de même durée
+fn expand_in_two(decoded: AudioFrame, channels: u16) -> (AudioFrame, AudioFrame) {
+    let mut stretched = stretch::expand_frame(&decoded, channels);
+    let second = stretched.split_off(decoded.samples.len());
+    let mut extra = decoded.clone();
+    extra.samples = second;
+    let mut first = decoded;
+    first.samples = stretched;
+    (first, extra)
+}
+
+/// Envoie une frame à l'étape suivante ; une file pleine n'arrête pas
+/// l'étape, les frames jetées sont comptées
+async fn forward(output: &FrameSender, stats: &Mutex<AudioStats>, frame: AudioFrame) -> AudioResult<()> {
+    let dropped = match output.send(frame) {
+        Ok(dropped) => dropped,
+        Err(AudioError::BufferOverflow) => 1,
+        Err(e) => return Err(e),
+    };
+    if dropped > 0 {
+        stats.lock().await.queue_overflows += dropped as u64;
+    }
+    Ok(())
+}
+
+/// Étape amont arrêtée : la file d'entrée est fermée et vide
+fn upstream_closed(stage: &str) -> AudioError {
+    AudioError::InitializationError(format!("étape {} privée de son entrée", stage))
+}
+
+/// Étape de capture : micro → file du codec
+/// 
+/// Porte aussi le signal de test : la frame envoyée au codec est remplacée
+/// par le générateur, la capture (qui l'entend en retour) va à l'analyseur.
+struct CaptureStage {
+    capture: SharedCapture,
+    output: FrameSender,
+    stats: Arc<Mutex<AudioStats>>,
+    test_tone: Option<(ToneGenerator, LoopbackAnalyzer)>,
+}
+
+impl CaptureStage {
+    async fn step(&mut self) -> AudioResult<()> {
+        let captured = self.capture.lock().await.next_frame().await?;
+        record_captured(&self.stats, &captured).await;
+        
+        let frame = match &mut self.test_tone {
+            Some((generator, analyzer)) => {
+                analyzer.push_captured(&captured);
+                generator.next_frame(captured.sequence_number)
+            }
+            None => captured,
+        };
+        forward(&self.output, &self.stats, frame).await
+    }
+}
+
+/// Étape codec : encode puis décode chaque frame, vers la file de lecture
+struct CodecStage {
+    codec: SharedCodec,
+    input: FrameReceiver,
+    output: FrameSender,
+    stats: Arc<Mutex<AudioStats>>,
+    /// Paquet compressé réutilisé d'une frame à l'autre
+    compressed: CompressedFrame,
+}
+
+impl CodecStage {
+    async fn step(&mut self) -> AudioResult<()> {
+        let (mut frame, waited) = self.input.recv().await.ok_or_else(|| upstream_closed("codec"))?;
+        self.stats.lock().await.encode_queue_time.record(waited);
+        
+        let mut codec = self.codec.lock().await;
+        let encode_start = Instant::now();
+        codec.encode_into(&frame, &mut self.compressed.data)?;
+        let encode_time = encode_start.elapsed();
+        self.compressed.original_sample_count = frame.samples.len();
+        
+        // Décodage dans les échantillons de la frame capturée : aucune
+        // allocation par frame, et l'instant de capture est conservé pour
+        // mesurer la latence de bout en bout, files comprises
+        let decode_start = Instant::now();
+        codec.decode_into(&self.compressed, &mut frame.samples)?;
+        let decode_time = decode_start.elapsed();
+        drop(codec);
+        
+        record_compression(&self.stats, self.compressed.compression_ratio()).await;
+        record_codec_times(&self.stats, encode_time, decode_time).await;
+        forward(&self.output, &self.stats, frame).await
+    }
+}
+
+/// Étape de lecture : file de lecture → haut-parleurs, cadence corrigée
+/// sur le niveau du buffer de lecture (voir `PlaybackPacer`)
+struct PlaybackStage {
+    playback: SharedPlayback,
+    input: FrameReceiver,
+    pacer: PlaybackPacer,
+    channels: u16,
+    stats: Arc<Mutex<AudioStats>>,
+}
+
+impl PlaybackStage {
+    /// Prochaine frame décodée, en mesurant son attente dans la file
+    async fn next_decoded(&mut self) -> AudioResult<AudioFrame> {
+        let (frame, waited) = self.input.recv().await.ok_or_else(|| upstream_closed("lecture"))?;
+        self.stats.lock().await.playback_queue_time.record(waited);
+        Ok(frame)
+    }
+    
+    async fn step(&mut self) -> AudioResult<()> {
+        let adjustment = self.pacer.adjust(self.playback.lock().await.buffer_level());
+        
+        // Deux frames fusionnées si la lecture prend du retard
+        let mut frame = self.next_decoded().await?;
+        if adjustment == PacingAdjustment::Merge {
+            let next = self.next_decoded().await?;
+            frame = stretch::compress_frames(&frame, &next, self.channels);
+            self.stats.lock().await.pacing_merges += 1;
+        }
+        let (captured_at, origin) = (frame.timestamp, frame.origin);
+        
+        let mut playback = self.playback.lock().await;
+        let result = if adjustment == PacingAdjustment::Expand {
+            let (first, extra) = expand_in_two(frame, self.channels);
+            self.stats.lock().await.pacing_expansions += 1;
+            match play_counted(&mut **playback, &self.stats, first).await {
+                Ok(()) => play_counted(&mut **playback, &self.stats, extra).await,
+                Err(e) => Err(e),
+            }
+        } else {
+            play_counted(&mut **playback, &self.stats, frame).await
+        };
+        drop(playback);
+        
+        match result {
+            // Buffer plein : frame déjà comptée, la lecture continue
+            Ok(()) | Err(AudioError::BufferOverflow) => {}
+            Err(e) => return Err(e),
+        }
+        record_played(&self.stats, captured_at.elapsed(), origin).await;
+        Ok(())
+    }
+}
+
+/// Étape du pipeline, exécutée dans sa propre tâche
+enum Stage {
+    Capture(CaptureStage),
+    Codec(CodecStage),
+    Playback(PlaybackStage),
+}
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Capture(_) => "capture",
+            Stage::Codec(_) => "codec",
+            Stage::Playback(_) => "lecture",
+        }
+    }
+    
+    /// Traite les frames jusqu'à l'arrêt demandé ou la première erreur
+    /// 
+    /// L'étape est rendue avec le résultat : le superviseur la redémarre
+    /// sans perdre ses files ni l'analyse du signal de test.
+    async fn run(mut self, mut stop: watch::Receiver<bool>) -> (Stage, AudioResult<()>) {
+        let result = loop {
+            let step = tokio::select! {
+                _ = stop.changed() => break Ok(()),
+                step = self.step() => step,
+            };
+            if let Err(e) = step {
+                break Err(e);
+            }
+        };
+        (self, result)
+    }
+    
+    async fn step(&mut self) -> AudioResult<()> {
+        match self {
+            Stage::Capture(stage) => stage.step().await,
+            Stage::Codec(stage) => stage.step().await,
+            Stage::Playback(stage) => stage.step().await,
+        }
+    }
+    
+    /// Remet le composant de l'étape dans un état sain après une erreur
+    async fn restart(&mut self) -> AudioResult<()> {
+        match self {
+            Stage::Capture(stage) => {
+                let mut capture = stage.capture.lock().await;
+                capture.stop().await?;
+                capture.start().await
+            }
+            Stage::Codec(stage) => stage.codec.lock().await.reset(),
+            Stage::Playback(stage) => {
+                stage.pacer = PlaybackPacer { frames_above: 0, frames_below: 0, ..stage.pacer };
+                let mut playback = stage.playback.lock().await;
+                playback.stop().await?;
+                playback.start().await
+            }
+        }
+    }
+    
+    /// Analyseur du signal de test, porté par l'étape de capture
+    fn into_analyzer(self) -> Option<LoopbackAnalyzer> {
+        match self {
+            Stage::Capture(stage) => stage.test_tone.map(|(_, analyzer)| analyzer),
+            _ => None,
+        }
+    }
+}
+
 /// Pipeline audio complet pour tests
 /// 
 /// Cette structure combine capture, codec et playback pour créer
@@ -32,26 +391,32 @@ use crate::{
 /// # Architecture du pipeline
 /// 
 /// ```text
-/// Microphone → [Capture] → [Encode] → [Decode] → [Playback] → Haut-parleurs
-///     ↑                                                           ↑
-///     └─────────────── Test Loopback ───────────────────────────┘
+/// Microphone → [Capture] ─file─→ [Encode → Decode] ─file─→ [Playback] → Haut-parleurs
+///     ↑          tâche                 tâche                  tâche          ↑
+///     └─────────────────────────── Test Loopback ────────────────────────────┘
 /// ```
 pub struct AudioPipelineImpl {
     /// Module de capture audio
-    capture: Box<dyn AudioCapture>,
+    capture: SharedCapture,
     
     /// Codec pour compression/décompression
-    codec: Box<dyn AudioCodec>,
+    codec: SharedCodec,
     
     /// Module de lecture audio
-    playback: Box<dyn AudioPlayback>,
+    playback: SharedPlayback,
     
     /// Configuration audio
-    _config: AudioConfig,
+    config: AudioConfig,
     
     /// Statistiques du pipeline
     stats: Arc<Mutex<AudioStats>>,
     
+    /// Cadence de la boucle réglée sur le buffer de lecture
+    pacer: PlaybackPacer,
+    
+    /// Paquet compressé réutilisé d'une frame à l'autre
+    compressed: CompressedFrame,
+    
     /// Indicateur si le pipeline est actif
     is_running: bool,
 }
@@ -74,18 +439,33 @@ impl AudioPipelineImpl {
         let playback = Box::new(CpalPlayback::new(config.clone())?) as Box<dyn AudioPlayback>;
         
         println!("✅ Pipeline audio initialisé");
-        println!("   Capture : {}", capture.device_info());
+        println!("   Capture : {}", capture.device_details());
         println!("   Codec : {}", codec.codec_info());
-        println!("   Playback : {}", playback.device_info());
+        println!("   Playback : {}", playback.device_details());
         
-        Ok(Self {
-            capture,
-            codec,
-            playback,
-            _config: config,
+        Ok(Self::with_components(config, capture, codec, playback))
+    }
+    
+    /// Crée un pipeline à partir de composants déjà construits
+    /// 
+    /// Permet de tester le pipeline sans matériel (`MockCapture`,
+    /// `MockPlayback`).
+    pub fn with_components(
+        config: AudioConfig,
+        capture: Box<dyn AudioCapture>,
+        codec: Box<dyn AudioCodec>,
+        playback: Box<dyn AudioPlayback>,
+    ) -> Self {
+        Self {
+            capture: Arc::new(Mutex::new(capture)),
+            codec: Arc::new(Mutex::new(codec)),
+            playback: Arc::new(Mutex::new(playback)),
+            pacer: PlaybackPacer::new(&config),
+            compressed: CompressedFrame::default(),
+            config,
             stats: Arc::new(Mutex::new(AudioStats::default())),
             is_running: false,
-        })
+        }
     }
     
     /// Retourne les statistiques actuelles du pipeline
@@ -99,45 +479,158 @@ impl AudioPipelineImpl {
         stats.reset();
     }
     
-    /// Met à jour les statistiques avec une nouvelle frame
-    async fn update_stats_captured(&self, frame: &AudioFrame) {
-        let mut stats = self.stats.lock().await;
-        stats.frames_captured += 1;
+    /// Joue une frame décodée, en comptant les frames jetées par le buffer
+    async fn play_decoded(&mut self, frame: AudioFrame) -> AudioResult<()> {
+        play_counted(&mut **self.playback.lock().await, &self.stats, frame).await
+    }
+    
+    /// Capture la frame suivante, remplacée par le signal de test s'il y en
+    /// a un : la capture, qui l'entend en retour, va alors à l'analyseur
+    async fn next_source_frame(&mut self, test_tone: Option<&mut (ToneGenerator, LoopbackAnalyzer)>) -> AudioResult<AudioFrame> {
+        let captured = self.capture.lock().await.next_frame().await?;
+        
+        // Met à jour les stats de capture
+        record_captured(&self.stats, &captured).await;
+        Ok(match test_tone {
+            Some((generator, analyzer)) => {
+                analyzer.push_captured(&captured);
+                generator.next_frame(captured.sequence_number)
+            }
+            None => captured,
+        })
+    }
+    
+    /// Traite une frame complète dans la tâche appelante, en corrigeant la
+    /// cadence sur le niveau du buffer de lecture (voir `PlaybackPacer`)
+    async fn process_frame(&mut self, mut test_tone: Option<&mut (ToneGenerator, LoopbackAnalyzer)>) -> AudioResult<()> {
+        let adjustment = self.pacer.adjust(self.playback.lock().await.buffer_level());
+        
+        // 1. Capture une frame (deux si la lecture prend du retard)
+        let frame_start = Instant::now();
+        let mut frame = self.next_source_frame(test_tone.as_deref_mut()).await?;
+        if adjustment == PacingAdjustment::Merge {
+            let next = self.next_source_frame(test_tone).await?;
+            frame = stretch::compress_frames(&frame, &next, self.config.channels);
+            self.stats.lock().await.pacing_merges += 1;
+        }
+        
+        // 2. Encode la frame (paquet réutilisé d'une frame à l'autre)
+        let encode_start = Instant::now();
+        self.codec.lock().await.encode_into(&frame, &mut self.compressed.data)?;
+        let encode_time = encode_start.elapsed();
+        self.compressed.original_sample_count = frame.samples.len();
+        record_compression(&self.stats, self.compressed.compression_ratio()).await;
+        
+        // 3. Décode la frame dans ses propres échantillons
+        let decode_start = Instant::now();
+        let mut decoded = frame;
+        self.codec.lock().await.decode_into(&self.compressed, &mut decoded.samples)?;
+        record_codec_times(&self.stats, encode_time, decode_start.elapsed()).await;
         
-        // Met à jour le niveau RMS moyen
-        let frame_rms = frame.rms_level();
-        if stats.frames_captured == 1 {
-            stats.avg_rms_level = frame_rms;
+        // 4. Joue la frame, étirée sur deux si la lecture est en avance
+        let origin = decoded.origin;
+        if adjustment == PacingAdjustment::Expand {
+            let (first, extra) = expand_in_two(decoded, self.config.channels);
+            self.stats.lock().await.pacing_expansions += 1;
+            self.play_decoded(first).await?;
+            self.play_decoded(extra).await?;
         } else {
-            // Moyenne mobile simple
-            stats.avg_rms_level = (stats.avg_rms_level * 0.9) + (frame_rms * 0.1);
+            self.play_decoded(decoded).await?;
         }
+        
+        // Calcule la latence totale
+        record_played(&self.stats, frame_start.elapsed(), origin).await;
+        
+        Ok(())
     }
     
-    async fn update_stats_played(&self, _frame: &AudioFrame, latency_ms: f32) {
-        let mut stats = self.stats.lock().await;
-        stats.frames_played += 1;
+    /// Lance les trois étapes du test loopback, chacune dans sa tâche
+    fn spawn_stages(&self, test_tone: Option<TestTone>, stop: &watch::Receiver<bool>) -> JoinSet<(Stage, AudioResult<()>)> {
+        let channels = self.config.channels;
+        // Codec en retard : les plus anciennes frames sont fusionnées pour
+        // rattraper le direct ; la file de lecture suit la politique du
+        // buffer de lecture
+        let (captured_tx, captured_rx) = queue::bounded(STAGE_QUEUE_FRAMES, OverflowPolicy::DropOldest, channels);
+        let (decoded_tx, decoded_rx) = queue::bounded(STAGE_QUEUE_FRAMES, self.config.overflow_policy, channels);
         
-        // Met à jour la latence moyenne
-        if stats.frames_played == 1 {
-            stats.avg_latency_ms = latency_ms;
-        } else {
-            stats.avg_latency_ms = (stats.avg_latency_ms * 0.9) + (latency_ms * 0.1);
+        let stages = [
+            Stage::Capture(CaptureStage {
+                capture: Arc::clone(&self.capture),
+                output: captured_tx,
+                stats: Arc::clone(&self.stats),
+                test_tone: test_tone
+                    .map(|tone| (ToneGenerator::new(tone, &self.config), LoopbackAnalyzer::new(tone, &self.config))),
+            }),
+            Stage::Codec(CodecStage {
+                codec: Arc::clone(&self.codec),
+                input: captured_rx,
+                output: decoded_tx,
+                stats: Arc::clone(&self.stats),
+                compressed: CompressedFrame::default(),
+            }),
+            Stage::Playback(PlaybackStage {
+                playback: Arc::clone(&self.playback),
+                input: decoded_rx,
+                pacer: PlaybackPacer::new(&self.config),
+                channels,
+                stats: Arc::clone(&self.stats),
+            }),
+        ];
+        
+        let mut tasks = JoinSet::new();
+        for stage in stages {
+            tasks.spawn(stage.run(stop.clone()));
         }
+        tasks
     }
     
-    async fn update_stats_compression(&self, ratio: f32) {
-        let mut stats = self.stats.lock().await;
+    /// Supervise les étapes jusqu'à la fin du test : une étape en échec est
+    /// redémarrée, au plus `MAX_STAGE_RESTARTS` fois
+    /// 
+    /// Les étapes terminées sont rangées dans `stopped`.
+    async fn supervise(
+        &self,
+        tasks: &mut JoinSet<(Stage, AudioResult<()>)>,
+        stop: &watch::Receiver<bool>,
+        test_duration: Duration,
+        stopped: &mut Vec<Stage>,
+    ) -> AudioResult<()> {
+        let deadline = sleep(test_duration);
+        tokio::pin!(deadline);
         
-        if stats.frames_captured <= 1 {
-            stats.avg_compression_ratio = ratio;
-        } else {
-            stats.avg_compression_ratio = (stats.avg_compression_ratio * 0.9) + (ratio * 0.1);
+        loop {
+            let joined = tokio::select! {
+                _ = &mut deadline => return Ok(()),
+                Some(joined) = tasks.join_next() => joined,
+            };
+            let (mut stage, result) = joined
+                .map_err(|e| AudioError::InitializationError(format!("tâche audio interrompue : {}", e)))?;
+            let error = match result {
+                Ok(()) => {
+                    stopped.push(stage);
+                    continue;
+                }
+                Err(e) => e,
+            };
+            
+            let mut stats = self.stats.lock().await;
+            if stats.stage_restarts >= MAX_STAGE_RESTARTS {
+                stopped.push(stage);
+                return Err(error);
+            }
+            stats.stage_restarts += 1;
+            drop(stats);
+            println!("⚠️  Étape {} en échec ({}), redémarrage", stage.name(), error);
+            if let Err(e) = stage.restart().await {
+                stopped.push(stage);
+                return Err(e);
+            }
+            tasks.spawn(stage.run(stop.clone()));
         }
     }
     
     /// Lance un test de performance détaillé
-    /// 
+    ///  
     /// Ce test mesure :
     /// - Latence de capture
     /// - Temps d'encodage
@@ -158,23 +651,24 @@ impl AudioPipelineImpl {
         while start_time.elapsed() < test_duration {
             // Mesure la capture
             let capture_start = Instant::now();
-            let frame = self.capture.next_frame().await?;
+            let frame = self.capture.lock().await.next_frame().await?;
             let capture_time = capture_start.elapsed();
             
             // Mesure l'encodage
             let encode_start = Instant::now();
-            let compressed = self.codec.encode(&frame)?;
+            let compressed = self.codec.lock().await.encode(&frame)?;
             let encode_time = encode_start.elapsed();
             total_encode_time += encode_time;
             
             // Mesure le décodage
             let decode_start = Instant::now();
-            let decoded = self.codec.decode(&compressed)?;
+            let decoded = self.codec.lock().await.decode(&compressed)?;
             let decode_time = decode_start.elapsed();
             total_decode_time += decode_time;
+            record_codec_times(&self.stats, encode_time, decode_time).await;
             
             // Joue la frame
-            if let Err(AudioError::BufferOverflow) = self.playback.play_frame(decoded).await {
+            if let Err(AudioError::BufferOverflow) = self.play_decoded(decoded).await {
                 // Buffer overflow normal sous charge
             }
             
@@ -204,6 +698,10 @@ impl AudioPipelineImpl {
         println!("   Throughput : {:.1} frames/s", frame_count as f64 / duration_seconds as f64);
         
         let stats = self.get_stats().await;
+        println!("   Encodage p99 : {:?}, max {:?}",
+                 stats.encode_time.p99().unwrap_or_default(), stats.encode_time.max().unwrap_or_default());
+        println!("   Décodage p99 : {:?}, max {:?}",
+                 stats.decode_time.p99().unwrap_or_default(), stats.decode_time.max().unwrap_or_default());
         println!("   Niveau audio moyen : {:.3}", stats.avg_rms_level);
         println!("   Compression moyenne : {:.1}x", stats.avg_compression_ratio);
         
@@ -211,7 +709,7 @@ impl AudioPipelineImpl {
     }
     
     /// Test de stress avec charge CPU artificielle
-    /// 
+    ///  
     /// Simule une charge système pour tester la robustesse
     pub async fn stress_test(&mut self, duration_seconds: u32) -> AudioResult<()> {
         println!("💪 Test de stress du pipeline ({}s)...", duration_seconds);
@@ -220,7 +718,7 @@ impl AudioPipelineImpl {
         
         let start_time = Instant::now();
         let test_duration = Duration::from_secs(duration_seconds as u64);
-        let mut dropped_frames = 0u64;
+        let overflows_before = self.get_stats().await.buffer_overflows;
         let mut processed_frames = 0u64;
         
         while start_time.elapsed() < test_duration {
@@ -230,10 +728,7 @@ impl AudioPipelineImpl {
             // Traite une frame
             match self.process_single_frame().await {
                 Ok(_) => processed_frames += 1,
-                Err(AudioError::BufferOverflow) => {
-                    dropped_frames += 1;
-                    processed_frames += 1;
-                },
+                Err(AudioError::BufferOverflow) => processed_frames += 1,
                 Err(e) => {
                     eprintln!("⚠️  Erreur stress test: {}", e);
                     break;
@@ -246,6 +741,7 @@ impl AudioPipelineImpl {
         
         self.stop().await?;
         
+        let dropped_frames = self.get_stats().await.buffer_overflows - overflows_before;
         let drop_rate = (dropped_frames as f64 / processed_frames as f64) * 100.0;
         
         println!("💪 Test de stress terminé :");
@@ -264,6 +760,8 @@ impl AudioPipelineImpl {
     }
 }
 
+impl crate::traits::sealed::Sealed for AudioPipelineImpl {}
+
 #[async_trait]
 impl AudioPipeline for AudioPipelineImpl {
     async fn start(&mut self) -> AudioResult<()> {
@@ -272,12 +770,13 @@ impl AudioPipeline for AudioPipelineImpl {
         }
         
         println!("🚀 Démarrage du pipeline audio...");
+        self.pacer = PlaybackPacer::new(&self.config);
         
         // Démarre dans l'ordre : playback → capture (pour éviter les premières frames perdues)
-        self.playback.start().await?;
+        self.playback.lock().await.start().await?;
         sleep(Duration::from_millis(100)).await; // Petit délai pour que le playback soit prêt
         
-        self.capture.start().await?;
+        self.capture.lock().await.start().await?;
         
         self.is_running = true;
         println!("✅ Pipeline audio démarré");
@@ -293,12 +792,12 @@ impl AudioPipeline for AudioPipelineImpl {
         println!("🛑 Arrêt du pipeline audio...");
         
         // Arrête dans l'ordre inverse
-        self.capture.stop().await?;
+        self.capture.lock().await.stop().await?;
         
         // Attend un peu pour vider les buffers
         sleep(Duration::from_millis(200)).await;
         
-        self.playback.stop().await?;
+        self.playback.lock().await.stop().await?;
         
         self.is_running = false;
         println!("✅ Pipeline audio arrêté");
@@ -306,9 +805,12 @@ impl AudioPipeline for AudioPipelineImpl {
         Ok(())
     }
     
-    async fn run_loopback_test(&mut self, duration_seconds: u32) -> AudioResult<AudioStats> {
+    async fn run_loopback_test(&mut self, duration_seconds: u32, test_tone: Option<TestTone>) -> AudioResult<LoopbackReport> {
         println!("🔄 Test loopback ({}s) - Micro → Codec → Haut-parleurs", duration_seconds);
-        println!("   ⚠️  Attention : vous allez entendre votre propre voix !");
+        match test_tone {
+            Some(tone) => println!("   🎵 Signal de test : salves de {:.0} Hz, gardez le micro près des haut-parleurs", tone.frequency_hz),
+            None => println!("   ⚠️  Attention : vous allez entendre votre propre voix !"),
+        }
         
         // Reset les statistiques
         self.reset_stats().await;
@@ -316,47 +818,65 @@ impl AudioPipeline for AudioPipelineImpl {
         // Démarre le pipeline
         self.start().await?;
         
-        let start_time = Instant::now();
+        // Une tâche par étape, supervisées jusqu'à la fin du test
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let mut tasks = self.spawn_stages(test_tone, &stop_rx);
+        let mut stopped = Vec::new();
         let test_duration = Duration::from_secs(duration_seconds as u64);
+        if let Err(e) = self.supervise(&mut tasks, &stop_rx, test_duration, &mut stopped).await {
+            eprintln!("❌ Erreur loopback: {}", e);
+        }
         
-        // Boucle principale du test
-        while start_time.elapsed() < test_duration {
-            match self.process_single_frame().await {
-                Ok(_) => {},
-                Err(AudioError::BufferOverflow) => {
-                    // Buffer overflow acceptable pendant le test
-                    let mut stats = self.stats.lock().await;
-                    stats.buffer_overflows += 1;
-                },
-                Err(AudioError::Timeout) => {
-                    println!("⏰ Timeout pendant le test loopback");
-                    break;
-                },
-                Err(e) => {
-                    eprintln!("❌ Erreur loopback: {}", e);
-                    break;
-                }
+        // Arrête les étapes, puis le pipeline
+        let _ = stop_tx.send(true);
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((stage, _)) = joined {
+                stopped.push(stage);
             }
-            
-            // Petite pause pour éviter de saturer le CPU
-            sleep(Duration::from_millis(1)).await;
         }
-        
-        // Arrête le pipeline
         self.stop().await?;
         
         let stats = self.get_stats().await;
+        let report = match stopped.into_iter().find_map(Stage::into_analyzer) {
+            Some(analyzer) => analyzer.report(stats.clone()),
+            None => LoopbackReport { stats: stats.clone(), ..LoopbackReport::default() },
+        };
         
         println!("🏁 Test loopback terminé :");
         println!("   Frames capturées : {}", stats.frames_captured);
         println!("   Frames jouées : {}", stats.frames_played);
         println!("   Latence moyenne : {:.1}ms", stats.avg_latency_ms);
+        println!("   Latence min {:?}, p95 {:?}, p99 {:?}, max {:?}",
+                 stats.latency.min().unwrap_or_default(), stats.latency.p95().unwrap_or_default(),
+                 stats.latency.p99().unwrap_or_default(), stats.latency.max().unwrap_or_default());
+        println!("   Attente avant codec p99 {:?}, avant lecture p99 {:?}",
+                 stats.encode_queue_time.p99().unwrap_or_default(),
+                 stats.playback_queue_time.p99().unwrap_or_default());
         println!("   Niveau audio : {:.3}", stats.avg_rms_level);
         println!("   Compression : {:.1}x", stats.avg_compression_ratio);
         
         if stats.buffer_overflows > 0 {
             println!("   ⚠️  Buffer overflows : {}", stats.buffer_overflows);
         }
+        if stats.queue_overflows > 0 {
+            println!("   ⚠️  Frames jetées entre étapes : {}", stats.queue_overflows);
+        }
+        if stats.stage_restarts > 0 {
+            println!("   ⚠️  Étapes redémarrées : {}", stats.stage_restarts);
+        }
+        if test_tone.is_some() {
+            println!("   Salves retrouvées : {}/{}", report.bursts_detected, report.bursts_played);
+            if let Some(latency) = report.acoustic_latency {
+                println!("   Latence acoustique : {:?}", latency);
+            }
+            if let (Some(thd), Some(snr)) = (report.thd_percent, report.snr_db) {
+                println!("   THD : {:.2}%, SNR : {:.1} dB", thd, snr);
+            }
+            println!("   Décrochages : {}", report.dropouts);
+            if !report.tone_detected() {
+                println!("⚠️  Signal de test inaudible - Vérifiez le volume et le microphone");
+            }
+        }
         
         // Évaluation de la qualité
         if stats.avg_latency_ms < 50.0 && stats.avg_rms_level > 0.001 {
@@ -367,32 +887,11 @@ impl AudioPipeline for AudioPipelineImpl {
             println!("⚠️  Niveau audio très faible - Vérifiez le microphone");
         }
         
-        Ok(stats)
+        Ok(report)
     }
     
     async fn process_single_frame(&mut self) -> AudioResult<()> {
-        // 1. Capture une frame
-        let frame_start = Instant::now();
-        let frame = self.capture.next_frame().await?;
-        
-        // Met à jour les stats de capture
-        self.update_stats_captured(&frame).await;
-        
-        // 2. Encode la frame
-        let compressed = self.codec.encode(&frame)?;
-        self.update_stats_compression(compressed.compression_ratio()).await;
-        
-        // 3. Décode la frame
-        let decoded = self.codec.decode(&compressed)?;
-        
-        // 4. Joue la frame
-        self.playback.play_frame(decoded).await?;
-        
-        // Calcule la latence totale
-        let total_latency = frame_start.elapsed().as_millis() as f32;
-        self.update_stats_played(&frame, total_latency).await;
-        
-        Ok(())
+        self.process_frame(None).await
     }
 }
 
@@ -409,8 +908,57 @@ impl Drop for AudioPipelineImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{MockAudioDevice, MockCapture, MockPlayback};
     use tokio::time::timeout;
     
+    #[test]
+    fn test_pacer_follows_buffer_depth() {
+        let config = AudioConfig::default();
+        let mut pacer = PlaybackPacer::new(&config);
+        let target = config.target_buffer_frames();
+        
+        // La gigue autour de la cible ne déclenche rien
+        for level in [target, target + 1, target.saturating_sub(1).max(1)].repeat(20) {
+            assert_eq!(pacer.adjust(level), PacingAdjustment::None);
+        }
+        
+        // Buffer durablement trop plein : une fusion, puis de nouveau patience
+        for _ in 1..PACING_PATIENCE_FRAMES {
+            assert_eq!(pacer.adjust(target + 2), PacingAdjustment::None);
+        }
+        assert_eq!(pacer.adjust(target + 2), PacingAdjustment::Merge);
+        assert_eq!(pacer.adjust(target + 2), PacingAdjustment::None);
+        
+        // Buffer vide : étirement
+        for _ in 1..PACING_PATIENCE_FRAMES {
+            assert_eq!(pacer.adjust(0), PacingAdjustment::None);
+        }
+        assert_eq!(pacer.adjust(0), PacingAdjustment::Expand);
+    }
+    
+    #[tokio::test]
+    async fn test_loopback_stages_restart_failed_capture() {
+        let config = AudioConfig::default();
+        let mut capture = MockCapture::new(config.clone());
+        capture.simulate_error(AudioError::DeviceDisconnected);
+        let codec = OpusCodec::new(config.clone()).unwrap();
+        let mut pipeline = AudioPipelineImpl::with_components(
+            config, Box::new(capture), Box::new(codec), Box::new(MockPlayback::new()),
+        );
+    
+        let report = timeout(Duration::from_secs(5), pipeline.run_loopback_test(1, None))
+            .await
+            .unwrap()
+            .unwrap();
+    
+        // La capture redémarrée, les frames traversent les trois étapes
+        assert_eq!(report.stats.stage_restarts, 1);
+        assert!(report.stats.frames_played > 10);
+        assert!(report.stats.encode_queue_time.count() > 10);
+        assert!(report.stats.playback_queue_time.count() > 10);
+        assert!(!pipeline.is_running);
+    }
+    
     #[tokio::test]
     async fn test_pipeline_creation() {
         let config = AudioConfig::default();
@@ -478,13 +1026,13 @@ mod tests {
             // Test très court (1 seconde) pour éviter de bloquer les tests
             let result = timeout(
                 Duration::from_secs(5),
-                pipeline.run_loopback_test(1)
+                pipeline.run_loopback_test(1, None)
             ).await;
             
             match result {
-                Ok(Ok(stats)) => {
+                Ok(Ok(report)) => {
                     println!("✅ Test loopback court réussi");
-                    println!("   Frames: {}/{}", stats.frames_captured, stats.frames_played);
+                    println!("   Frames: {}/{}", report.stats.frames_captured, report.stats.frames_played);
                 },
                 Ok(Err(e)) => println!("❌ Erreur loopback: {}", e),
                 Err(_) => println!("⏰ Timeout test loopback"),
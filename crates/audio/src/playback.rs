@@ -7,17 +7,29 @@
 //! - Un buffer pour gérer le jitter réseau
 //! - Une gestion des underruns (pas assez de données)
 //! - Une synchronisation avec l'horloge système
+//!
+//! La sonie du pair est nivelée à la réception de chaque frame (module
+//! `loudness`), si `AudioConfig::loudness_normalization` est activé.
+//! Le sidetone et les signaux sonores de l'application sont mixés à la voix
+//! du pair juste avant la conversion (voir le module `mixer`).
 
 use async_trait::async_trait;
-use cpal::{Device, Stream, SupportedStreamConfig, SampleFormat};
-use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
+use cpal::{BufferSize, Device, FromSample, HostId, Sample, SizedSample, Stream, StreamConfig, SupportedStreamConfig, SampleFormat};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use tokio::sync::Mutex;
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use crate::{
-    AudioPlayback, AudioFrame, AudioConfig, AudioError, AudioResult,
+    AudioPlayback, AudioFrame, AudioConfig, AudioError, AudioResult, DeviceInfo, DeviceSelection,
+    HostModeReport, OverflowPolicy,
 };
+use crate::{devices, samples, stretch};
+use crate::resample::Resampler;
+use crate::mixer::{PlaybackMixer, SidetoneTap};
+use crate::loudness::LoudnessNormalizer;
+use crate::realtime::CallbackPromotion;
 
 /// Implémentation de lecture audio avec cpal
 /// 
@@ -52,11 +64,44 @@ pub struct CpalPlayback {
     /// Nom du périphérique pour debug
     device_name: String,
     
+    /// Résultat de la sélection (préférence trouvée ou repli sur le défaut)
+    selection: DeviceSelection,
+    
+    /// Hôte audio du périphérique (`AudioConfig::audio_host`)
+    host_id: HostId,
+    
+    /// Mode d'accès obtenu à l'ouverture du dernier stream
+    host_mode: Option<HostModeReport>,
+    
     /// Compteur de frames jouées (statistiques)
     frames_played: Arc<Mutex<u64>>,
     
     /// Compteur d'underruns (manque de données)
     underruns: Arc<Mutex<u64>>,
+    
+    /// Frames par callback accordées par le périphérique (0 = pas encore connu)
+    granted_buffer_frames: Arc<AtomicU32>,
+    
+    /// Configuration et format du dernier stream construit
+    stream_params: Option<(StreamConfig, SampleFormat)>,
+    
+    /// Sidetone et signaux sonores (réglages partagés avec le callback)
+    mixer: PlaybackMixer,
+    
+    /// Normalisation de la sonie du pair (None si désactivée)
+    normalizer: Option<LoudnessNormalizer>,
+    
+    /// Son coupé par `pause()` (partagé avec le callback)
+    paused: Arc<AtomicBool>,
+    
+    /// Configuration native changée, signalée par le callback d'erreur
+    route_changed: Arc<AtomicBool>,
+    
+    /// Streams reconstruits après un changement de configuration native
+    route_changes: u32,
+    
+    /// Périphérique débranché, signalé par le callback d'erreur
+    disconnected: Arc<AtomicBool>,
 }
 
 impl CpalPlayback {
@@ -72,27 +117,44 @@ impl CpalPlayback {
     /// - `AudioError::NoDeviceFound` si aucun haut-parleur n'est disponible
     /// - `AudioError::ConfigError` si la configuration n'est pas supportée
     pub fn new(config: AudioConfig) -> AudioResult<Self> {
-        // Obtient l'host audio par défaut du système
-        let host = cpal::default_host();
+        Self::with_preferred_device(config, None)
+    }
+    
+    /// Crée une instance de lecture sur la sortie préférée
+    /// 
+    /// Même logique que `CpalCapture::with_preferred_device` : correspondance
+    /// approximative du nom, repli sur la sortie par défaut avec avertissement.
+    /// 
+    /// # Arguments
+    /// * `config` - Configuration audio à utiliser
+    /// * `preferred_name` - Nom de la sortie préférée (`AudioDevicePreferences::output_name`)
+    /// 
+    /// # Erreurs
+    /// - `AudioError::NoDeviceFound` si aucun haut-parleur n'est disponible
+    pub fn with_preferred_device(config: AudioConfig, preferred_name: Option<&str>) -> AudioResult<Self> {
+        // Trouve le périphérique préféré, ou celui par défaut
+        let (host, host_warning) = devices::select_host(config.audio_host.as_deref());
+        if let Some(warning) = host_warning {
+            println!("⚠️  {}", warning);
+        }
+        let (device, selection) = devices::select_output_device(&host, preferred_name)?;
+        if let Some(warning) = selection.warning() {
+            println!("⚠️  {}", warning);
+        }
         
-        // Trouve le périphérique de sortie par défaut
-        let device = host
-            .default_output_device()
-            .ok_or(AudioError::NoDeviceFound)?;
-            
         // Récupère le nom du périphérique pour debug
-        let device_name = device.description()
-            .ok()
-            .map(|desc| desc.name().to_string())
-            .unwrap_or_else(|| "Périphérique inconnu".to_string());
+        let device_name = devices::device_name(&device);
             
         // Crée le buffer avec la taille configurée
         let frame_buffer = Arc::new(Mutex::new(VecDeque::with_capacity(
-            config.receive_buffer_size * 2 // Un peu plus grand pour éviter les reallocations
+            config.max_buffer_frames() * 2 // Un peu plus grand pour éviter les reallocations
         )));
         
         println!("🔊 Périphérique de lecture trouvé : {}", device_name);
         
+        let mixer = PlaybackMixer::new(&config);
+        let normalizer = config.loudness_normalization.then(|| LoudnessNormalizer::new(&config));
+        
         Ok(Self {
             device,
             config,
@@ -100,12 +162,93 @@ impl CpalPlayback {
             frame_buffer,
             is_playing: false,
             device_name,
+            selection,
+            host_id: host.id(),
+            host_mode: None,
             frames_played: Arc::new(Mutex::new(0)),
             underruns: Arc::new(Mutex::new(0)),
+            granted_buffer_frames: Arc::new(AtomicU32::new(0)),
+            stream_params: None,
+            mixer,
+            normalizer,
+            paused: Arc::new(AtomicBool::new(false)),
+            route_changed: Arc::new(AtomicBool::new(false)),
+            route_changes: 0,
+            disconnected: Arc::new(AtomicBool::new(false)),
         })
     }
     
-    /// Vérifie que la configuration audio est supportée par le périphérique
+    /// Nombre de streams reconstruits après un changement de route
+    /// 
+    /// Voir `CpalCapture::route_changes` : la sortie d'un casque Bluetooth
+    /// passe aussi en HFP quand son micro est ouvert.
+    pub fn route_changes(&self) -> u32 {
+        self.route_changes
+    }
+    
+    /// Résultat de la sélection de la sortie
+    pub fn device_selection(&self) -> &DeviceSelection {
+        &self.selection
+    }
+    
+    /// Mode d'accès obtenu auprès de l'hôte audio (`AudioConfig::latency_mode`)
+    /// 
+    /// Connu après `start()` ; `None` avant.
+    pub fn host_mode(&self) -> Option<&HostModeReport> {
+        self.host_mode.as_ref()
+    }
+    
+    /// Taille de buffer accordée par le périphérique, en frames par callback
+    /// 
+    /// Connue après le premier callback du stream ; `None` avant.
+    pub fn device_buffer_frames(&self) -> Option<u32> {
+        match self.granted_buffer_frames.load(Ordering::Relaxed) {
+            0 => None,
+            frames => Some(frames),
+        }
+    }
+    
+    /// Tap à confier à la capture pour entendre son micro dans le casque
+    /// 
+    /// Voir `CpalCapture::set_sidetone_tap` ; sans effet tant que le gain
+    /// du sidetone est nul.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use audio::{AudioConfig, CpalCapture, CpalPlayback};
+    /// 
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = AudioConfig::default();
+    /// config.sidetone_gain = 0.15;
+    /// let mut capture = CpalCapture::new(config.clone())?;
+    /// let playback = CpalPlayback::new(config)?;
+    /// capture.set_sidetone_tap(Some(playback.sidetone_tap()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sidetone_tap(&self) -> SidetoneTap {
+        self.mixer.sidetone_tap()
+    }
+    
+    /// Change le gain du sidetone pendant la lecture (0.0 le coupe)
+    pub fn set_sidetone_gain(&self, gain: f32) {
+        self.mixer.set_sidetone_gain(gain);
+    }
+    
+    /// Joue un signal sonore (bip, notification) par-dessus la voix du pair
+    /// 
+    /// Le signal est atténué à `AudioConfig::ducking_gain` tant que le pair
+    /// parle. `cue` doit avoir le format de `AudioConfig` (fréquence, canaux).
+    pub fn play_cue(&self, cue: &AudioFrame) {
+        self.mixer.queue_cue(cue);
+    }
+    
+    /// Gain appliqué par la normalisation de la sonie, en dB (None si désactivée)
+    pub fn loudness_gain_db(&self) -> Option<f32> {
+        self.normalizer.as_ref().map(LoudnessNormalizer::gain_db)
+    }
+    
+    /// Lit la configuration native du périphérique de sortie
     fn validate_config(&self) -> AudioResult<SupportedStreamConfig> {
         // Obtient la configuration par défaut du périphérique
         let default_config = self.device
@@ -117,238 +260,308 @@ impl CpalPlayback {
         println!("   Channels: {}", default_config.channels());
         println!("   Sample format: {:?}", default_config.sample_format());
         
-        // Vérifie que le périphérique supporte notre sample rate
-        let supported_rates = self.device
-            .supported_output_configs()
-            .map_err(|e| AudioError::ConfigError(format!("Impossible d'obtenir configs supportées: {}", e)))?;
-        
-        let mut config_found = false;
-        for supported_range in supported_rates {
-            let min_rate = supported_range.min_sample_rate();
-            let max_rate = supported_range.max_sample_rate();
-            
-            if self.config.sample_rate >= min_rate && self.config.sample_rate <= max_rate {
-                config_found = true;
-                break;
-            }
+        // Configuration native gardée : la voix est rééchantillonnée dans le callback
+        if default_config.sample_rate() != self.config.sample_rate {
+            println!("   Rééchantillonné depuis {} Hz", self.config.sample_rate);
         }
         
-        if !config_found {
-            return Err(AudioError::ConfigError(format!(
-                "Sample rate {} Hz non supporté par le périphérique de sortie", 
-                self.config.sample_rate
-            )));
-        }
-        
-        
         Ok(default_config)
     }
     
     /// Construit et configure le stream audio de sortie
+    /// 
+    /// Même repli que la capture : une taille de buffer refusée par le
+    /// périphérique est remplacée par la taille par défaut du pilote.
     fn build_stream(&mut self) -> AudioResult<Stream> {
-        let stream_config = self.validate_config()?;
+        let supported_config = self.validate_config()?;
+        let sample_format = supported_config.sample_format();
+        let mut stream_config = supported_config.config();
+        let (buffer_size, mut host_mode) = devices::negotiate_latency_mode(
+            self.config.latency_mode,
+            self.config.device_buffer_frames,
+            self.host_id,
+            supported_config.buffer_size(),
+        );
+        stream_config.buffer_size = buffer_size;
         
+        println!("🎵 Démarrage lecture :");
+        println!("   Échantillons par frame : {}", self.config.samples_per_frame());
+        println!("   Délai de lecture : {}ms visés, {}ms max ({} / {} frames)",
+                 self.config.target_delay_ms, self.config.max_delay_ms,
+                 self.config.target_buffer_frames(), self.config.max_buffer_frames());
+        println!("   Buffer périphérique : {:?}", stream_config.buffer_size);
+        
+        let stream = match (self.build_stream_with(&stream_config, sample_format), stream_config.buffer_size) {
+            (Err(e), BufferSize::Fixed(frames)) => {
+                println!("⚠️  Buffer de {} frames refusé ({}) - taille par défaut du pilote", frames, e);
+                host_mode.fall_back_to_shared(format!("buffer de {} frames refusé", frames));
+                stream_config.buffer_size = BufferSize::Default;
+                self.build_stream_with(&stream_config, sample_format)?
+            }
+            (result, _) => result?,
+        };
+        if host_mode.is_degraded() {
+            println!("⚠️  Hôte {}", host_mode);
+        } else {
+            println!("   Hôte {}", host_mode);
+        }
+        self.stream_params = Some((stream_config, sample_format));
+        self.host_mode = Some(host_mode);
+        Ok(stream)
+    }
+    
+    /// Construit le stream cpal de sortie avec une configuration donnée
+    fn build_stream_with(&self, stream_config: &StreamConfig, sample_format: SampleFormat) -> AudioResult<Stream> {
+        // Les f32 du buffer sont convertis au format du périphérique dans le callback
+        match sample_format {
+            SampleFormat::I8 => self.build_typed_stream::<i8>(stream_config),
+            SampleFormat::I16 => self.build_typed_stream::<i16>(stream_config),
+            SampleFormat::I24 => self.build_typed_stream::<cpal::I24>(stream_config),
+            SampleFormat::I32 => self.build_typed_stream::<i32>(stream_config),
+            SampleFormat::I64 => self.build_typed_stream::<i64>(stream_config),
+            SampleFormat::U8 => self.build_typed_stream::<u8>(stream_config),
+            SampleFormat::U16 => self.build_typed_stream::<u16>(stream_config),
+            SampleFormat::U24 => self.build_typed_stream::<cpal::U24>(stream_config),
+            SampleFormat::U32 => self.build_typed_stream::<u32>(stream_config),
+            SampleFormat::U64 => self.build_typed_stream::<u64>(stream_config),
+            SampleFormat::F32 => self.build_typed_stream::<f32>(stream_config),
+            SampleFormat::F64 => self.build_typed_stream::<f64>(stream_config),
+            _ => Err(AudioError::ConfigError(format!("Format d'échantillon non supporté : {:?}", sample_format))),
+        }
+    }
+    
+    /// Construit le stream de sortie pour un type d'échantillon donné
+    fn build_typed_stream<T>(&self, stream_config: &StreamConfig) -> AudioResult<Stream>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
         // Clone des variables nécessaires pour le callback
         let frame_buffer = Arc::clone(&self.frame_buffer);
         let samples_per_frame = self.config.samples_per_frame();
+        let channels = self.config.channels;
         let frames_played = Arc::clone(&self.frames_played);
         let underruns = Arc::clone(&self.underruns);
         
-        println!("🎵 Démarrage lecture :");
-        println!("   Échantillons par frame : {}", samples_per_frame);
-        println!("   Taille buffer : {} frames", self.config.receive_buffer_size);
+        // Taille réellement accordée, relevée à chaque callback
+        let granted_buffer_frames = Arc::clone(&self.granted_buffer_frames);
+        granted_buffer_frames.store(0, Ordering::Relaxed);
+        let device_channels = stream_config.channels as usize;
+        if device_channels != channels as usize {
+            println!("   Canaux : {} répartis sur les {} du périphérique", channels, device_channels);
+        }
         
         // Buffer local pour accumuler les échantillons
         let mut output_buffer = VecDeque::with_capacity(samples_per_frame * 4);
         
-        // Détermine le format d'échantillons du périphérique
-        let sample_format = stream_config.sample_format();
-        
-        // Construit le stream selon le format d'échantillons
-        let stream = match sample_format {
-            SampleFormat::F32 => {
-                self.device.build_output_stream(
-                    &stream_config.config(),
-                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                        Self::fill_output_buffer_f32(
-                            data,
-                            &mut output_buffer,
-                            &frame_buffer,
-                            samples_per_frame,
-                            &frames_played,
-                            &underruns,
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio sortie : {}", err);
-                    },
-                    None
-                )?
-            },
-            SampleFormat::I16 => {
-                self.device.build_output_stream(
-                    &stream_config.config(),
-                    move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
-                        Self::fill_output_buffer_i16(
-                            data,
-                            &mut output_buffer,
-                            &frame_buffer,
-                            samples_per_frame,
-                            &frames_played,
-                            &underruns,
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio sortie : {}", err);
-                    },
-                    None
-                )?
+        // Voix rééchantillonnée à la fréquence native, en attente de sortie
+        let mut native = NativeOutput {
+            resampler: Resampler::new(self.config.sample_rate, stream_config.sample_rate, channels),
+            pending: VecDeque::with_capacity(samples_per_frame * 4),
+        };
+        
+        // Priorité temps réel du thread cpal, si activée
+        let mut promotion = CallbackPromotion::new(&self.config);
+        
+        // Sidetone et signaux sonores, mixés à chaque callback
+        let mut mixer = self.mixer.clone();
+        
+        // Délai visé : la lecture (re)démarre une fois cet audio accumulé
+        let target_frames = self.config.target_buffer_frames();
+        let mut primed = false;
+        
+        // Son coupé : silence, puis réamorçage à la reprise
+        let paused = Arc::clone(&self.paused);
+        
+        // Changement de route : comparé à la configuration native courante
+        let route_changed = Arc::clone(&self.route_changed);
+        let disconnected = Arc::clone(&self.disconnected);
+        let device = self.device.clone();
+        let built_config = stream_config.clone();
+        
+        let stream = self.device.build_output_stream(
+            stream_config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                promotion.ensure();
+                granted_buffer_frames.store((data.len() / device_channels) as u32, Ordering::Relaxed);
+                if paused.load(Ordering::Relaxed) {
+                    primed = false;
+                    output_buffer.clear();
+                    native.pending.clear();
+                    samples::fill_from_f32(data, device_channels, &mut native.pending, channels as usize);
+                    return;
+                }
+                let needed = native.samples_needed(data.len() / device_channels, channels);
+                if Self::is_primed(&mut primed, &frame_buffer, target_frames) {
+                    let underrun = Self::refill_samples(
+                        needed,
+                        &mut output_buffer,
+                        &frame_buffer,
+                        channels,
+                        &frames_played,
+                        &underruns,
+                    );
+                    primed = !underrun;
+                }
+                Self::fill_output_buffer(
+                    data,
+                    device_channels,
+                    needed,
+                    &mut output_buffer,
+                    &mut native,
+                    channels,
+                    &mut mixer,
+                );
             },
-            SampleFormat::U16 => {
-                self.device.build_output_stream(
-                    &stream_config.config(),
-                    move |data: &mut [u16], _: &cpal::OutputCallbackInfo| {
-                        Self::fill_output_buffer_u16(
-                            data,
-                            &mut output_buffer,
-                            &frame_buffer,
-                            samples_per_frame,
-                            &frames_played,
-                            &underruns,
-                        );
-                    },
-                    move |err| {
-                        eprintln!("❌ Erreur stream audio sortie : {}", err);
-                    },
-                    None
-                )?
+            move |err| {
+                eprintln!("❌ Erreur stream audio sortie : {}", err);
+                if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                    disconnected.store(true, Ordering::Relaxed);
+                } else if devices::native_config_changed(device.default_output_config().ok(), &built_config) {
+                    route_changed.store(true, Ordering::Relaxed);
+                }
             },
-            _ => return Err(AudioError::ConfigError(format!("Format d'échantillon non supporté : {:?}", sample_format))),
-        };
+            None
+        )?;
         
         Ok(stream)
     }
     
-    /// Remplit le buffer de sortie avec des échantillons f32
+    /// Indique si la lecture peut puiser dans le buffer de frames
     /// 
-    /// Cette fonction est appelée par le callback audio (thread temps réel).
-    /// Elle doit être très rapide et ne jamais bloquer.
-    fn fill_output_buffer_f32(
-        output: &mut [f32],
-        sample_buffer: &mut VecDeque<f32>,
-        frame_buffer: &Arc<Mutex<VecDeque<AudioFrame>>>,
-        _samples_per_frame: usize,
-        frames_played: &Arc<Mutex<u64>>,
-        underruns: &Arc<Mutex<u64>>,
-    ) {
-        // Remplit le buffer d'échantillons si nécessaire
-        while sample_buffer.len() < output.len() {
-            // Essaie de récupérer une frame (non-bloquant)
-            if let Ok(mut buffer_guard) = frame_buffer.try_lock() {
-                if let Some(frame) = buffer_guard.pop_front() {
-                    // Ajoute tous les échantillons de cette frame
-                    for sample in frame.samples {
-                        sample_buffer.push_back(sample);
-                    }
-                    
-                    // Met à jour les statistiques (non-bloquant)
-                    if let Ok(mut count) = frames_played.try_lock() {
-                        *count += 1;
-                    }
-                } else {
-                    // Pas de frame disponible - underrun
-                    if let Ok(mut count) = underruns.try_lock() {
-                        *count += 1;
-                    }
-                    break;
-                }
-            } else {
-                // Impossible d'obtenir le lock - on continue avec ce qu'on a
-                break;
-            }
-        }
-        
-        // Remplit la sortie avec les échantillons disponibles
-        for sample in output.iter_mut() {
-            *sample = sample_buffer.pop_front().unwrap_or(0.0); // Silence si pas de données
+    /// Au démarrage et après un underrun, la lecture attend que
+    /// `target_frames` frames soient en attente (`AudioConfig::target_delay_ms`) :
+    /// le silence joué en attendant évite d'enchaîner les underruns.
+    fn is_primed(primed: &mut bool, frame_buffer: &Arc<Mutex<VecDeque<AudioFrame>>>, target_frames: usize) -> bool {
+        if !*primed {
+            *primed = frame_buffer.try_lock().is_ok_and(|buffer| buffer.len() >= target_frames);
         }
+        *primed
     }
     
-    /// Remplit le buffer de sortie avec des échantillons i16 (conversion depuis f32)
-    fn fill_output_buffer_i16(
-        output: &mut [i16],
+    /// Transfère des frames dans le buffer d'échantillons jusqu'à `needed` échantillons
+    /// 
+    /// Si la frame retirée était la dernière en attente, elle est étirée sur
+    /// la durée de deux frames (WSOLA) : le délai de lecture grandit d'une
+    /// frame sans trou audible, plutôt qu'un underrun au callback suivant.
+    /// Le pendant (rétrécir le délai) est fait par `play_frame` quand le
+    /// buffer déborde.
+    /// 
+    /// Retourne `true` en cas d'underrun (plus aucune frame en attente).
+    fn refill_samples(
+        needed: usize,
         sample_buffer: &mut VecDeque<f32>,
         frame_buffer: &Arc<Mutex<VecDeque<AudioFrame>>>,
-        _samples_per_frame: usize,
+        channels: u16,
         frames_played: &Arc<Mutex<u64>>,
         underruns: &Arc<Mutex<u64>>,
-    ) {
-        // Même logique que f32, mais on convertit en remplissant
-        while sample_buffer.len() < output.len() {
-            if let Ok(mut buffer_guard) = frame_buffer.try_lock() {
-                if let Some(frame) = buffer_guard.pop_front() {
-                    for sample in frame.samples {
-                        sample_buffer.push_back(sample);
-                    }
-                    
-                    if let Ok(mut count) = frames_played.try_lock() {
-                        *count += 1;
-                    }
-                } else {
-                    if let Ok(mut count) = underruns.try_lock() {
-                        *count += 1;
-                    }
-                    break;
+    ) -> bool {
+        while sample_buffer.len() < needed {
+            // Essaie de récupérer une frame (non-bloquant)
+            let Ok(mut buffer_guard) = frame_buffer.try_lock() else {
+                // Impossible d'obtenir le lock - on continue avec ce qu'on a
+                break;
+            };
+            
+            let Some(frame) = buffer_guard.pop_front() else {
+                // Pas de frame disponible - underrun
+                if let Ok(mut count) = underruns.try_lock() {
+                    *count += 1;
                 }
+                return true;
+            };
+            
+            if buffer_guard.is_empty() {
+                sample_buffer.extend(stretch::expand_frame(&frame, channels));
             } else {
-                break;
+                sample_buffer.extend(frame.samples);
+            }
+            
+            // Met à jour les statistiques (non-bloquant)
+            if let Ok(mut count) = frames_played.try_lock() {
+                *count += 1;
             }
         }
-        
-        // Remplit et convertit f32 -> i16
-        for sample in output.iter_mut() {
-            let f32_sample = sample_buffer.pop_front().unwrap_or(0.0);
-            // Convertit f32 [-1.0, 1.0] vers i16
-            *sample = (f32_sample * i16::MAX as f32) as i16;
-        }
+        false
     }
     
-    /// Remplit le buffer de sortie avec des échantillons u16 (conversion depuis f32)
-    fn fill_output_buffer_u16(
-        output: &mut [u16],
+    /// Remplit le buffer de sortie du périphérique (conversion depuis f32)
+    /// 
+    /// Le sidetone et les signaux sonores sont d'abord mixés aux `needed`
+    /// premiers échantillons de la voix, qui sont ensuite rééchantillonnés à
+    /// la fréquence native par `native`.
+    /// Les trames de `channels` canaux sont réparties sur les
+    /// `device_channels` canaux du périphérique (mono dupliqué sur l'avant
+    /// gauche / droit d'une sortie 5.1, par exemple).
+    /// Cette fonction est appelée par le callback audio (thread temps réel).
+    /// Elle doit être très rapide et ne jamais bloquer.
+    fn fill_output_buffer<T>(
+        output: &mut [T],
+        device_channels: usize,
+        needed: usize,
         sample_buffer: &mut VecDeque<f32>,
-        frame_buffer: &Arc<Mutex<VecDeque<AudioFrame>>>,
-        _samples_per_frame: usize,
-        frames_played: &Arc<Mutex<u64>>,
-        underruns: &Arc<Mutex<u64>>,
-    ) {
-        // Même logique que f32, mais on convertit en remplissant
-        while sample_buffer.len() < output.len() {
-            if let Ok(mut buffer_guard) = frame_buffer.try_lock() {
-                if let Some(frame) = buffer_guard.pop_front() {
-                    for sample in frame.samples {
-                        sample_buffer.push_back(sample);
-                    }
-                    
-                    if let Ok(mut count) = frames_played.try_lock() {
-                        *count += 1;
-                    }
-                } else {
-                    if let Ok(mut count) = underruns.try_lock() {
-                        *count += 1;
-                    }
-                    break;
+        native: &mut NativeOutput,
+        channels: u16,
+        mixer: &mut PlaybackMixer,
+    ) where
+        T: Sample + FromSample<f32>,
+    {
+        mixer.mix(sample_buffer, needed);
+        
+        let available = needed.min(sample_buffer.len());
+        let take = available - available % channels as usize;
+        native.resampler.process(&sample_buffer.make_contiguous()[..take], &mut native.pending);
+        sample_buffer.drain(..take);
+        
+        // Silence si pas de données
+        samples::fill_from_f32(output, device_channels, &mut native.pending, channels as usize);
+    }
+    
+    /// Ajoute une frame au buffer de lecture, selon `AudioConfig::overflow_policy` s'il est plein
+    /// 
+    /// Le buffer est plein à `AudioConfig::max_buffer_frames` frames.
+    /// 
+    /// Retourne le nombre de frames jetées.
+    fn enqueue_frame(
+        buffer: &mut VecDeque<AudioFrame>,
+        frame: AudioFrame,
+        config: &AudioConfig,
+    ) -> AudioResult<usize> {
+        if buffer.len() < config.max_buffer_frames() {
+            buffer.push_back(frame);
+            return Ok(0);
+        }
+        
+        match config.overflow_policy {
+            OverflowPolicy::DropOldest => {
+                // Le délai diminue d'une frame : les deux plus anciennes sont
+                // fusionnées par étirement plutôt que jetées (buffer d'une
+                // seule frame : elle est simplement remplacée)
+                if let (Some(first), Some(second)) = (buffer.pop_front(), buffer.pop_front()) {
+                    buffer.push_front(stretch::compress_frames(&first, &second, config.channels));
                 }
-            } else {
-                break;
+                buffer.push_back(frame);
+                Ok(1)
             }
+            OverflowPolicy::DropNewest => Ok(1),
+            OverflowPolicy::Error => Err(AudioError::BufferOverflow),
         }
-        
-        // Remplit et convertit f32 -> u16
-        for sample in output.iter_mut() {
-            let f32_sample = sample_buffer.pop_front().unwrap_or(0.0);
-            // Convertit f32 [-1.0, 1.0] vers u16 [0, 65535]
-            *sample = ((f32_sample + 1.0) * 0.5 * u16::MAX as f32) as u16;
+    }
+    
+    /// Reconstruit le stream avec la nouvelle configuration native de la sortie
+    /// 
+    /// Les frames en attente sont gardées : la lecture reprend après le
+    /// réamorçage. Un échec est seulement affiché ; le changement suivant
+    /// retentera.
+    fn rebuild_after_route_change(&mut self) {
+        println!("🔀 Configuration de la sortie changée - reconstruction du stream");
+        self.route_changes += 1;
+        self.stream = None;
+        match self.build_stream() {
+            Ok(stream) => match stream.play() {
+                Ok(()) => self.stream = Some(stream),
+                Err(e) => eprintln!("❌ Redémarrage du stream de lecture impossible : {}", e),
+            },
+            Err(e) => eprintln!("❌ Reconstruction du stream de lecture impossible : {}", e),
         }
     }
     
@@ -369,8 +582,16 @@ impl AudioPlayback for CpalPlayback {
         
         println!("🚀 Démarrage de la lecture audio...");
         
-        // Construit et démarre le stream
-        let stream = self.build_stream()?;
+        // Nouvelle lecture, peut-être un autre pair : sonie à remesurer
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.reset();
+        }
+        
+        // Stream ouvert par `prepare`, sinon construit maintenant
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => self.build_stream()?,
+        };
         stream.play()?;
         
         self.stream = Some(stream);
@@ -380,8 +601,25 @@ impl AudioPlayback for CpalPlayback {
         Ok(())
     }
     
+    async fn prepare(&mut self) -> AudioResult<()> {
+        if self.is_playing || self.stream.is_some() {
+            return Ok(()); // Déjà démarré ou en veille
+        }
+        
+        // Certains backends démarrent le stream dès sa construction
+        let stream = self.build_stream()?;
+        if let Err(e) = stream.pause() {
+            println!("⚠️  Mise en veille du stream impossible : {}", e);
+        }
+        self.stream = Some(stream);
+        
+        println!("💤 Lecture audio en veille");
+        Ok(())
+    }
+    
     async fn stop(&mut self) -> AudioResult<()> {
         if !self.is_playing {
+            self.stream = None; // Ferme un stream en veille
             return Ok(()); // Déjà arrêté
         }
         
@@ -393,27 +631,56 @@ impl AudioPlayback for CpalPlayback {
         }
         
         self.is_playing = false;
+        self.paused.store(false, Ordering::Relaxed);
         
         println!("✅ Lecture audio arrêtée");
         Ok(())
     }
     
-    async fn play_frame(&mut self, frame: AudioFrame) -> AudioResult<()> {
-        let mut buffer_guard = self.frame_buffer.lock().await;
-        
-        // Vérifie si le buffer est plein
-        if buffer_guard.len() >= self.config.receive_buffer_size {
-            // Buffer plein - on peut soit dropper la frame la plus ancienne,
-            // soit rejeter la nouvelle frame
-            buffer_guard.pop_front(); // Drop la plus ancienne
-            return Err(AudioError::BufferOverflow);
+    async fn pause(&mut self) -> AudioResult<()> {
+        if !self.is_playing {
+            return Err(AudioError::InitializationError("Lecture non démarrée".to_string()));
         }
-        
-        // Ajoute la frame au buffer
-        buffer_guard.push_back(frame);
+        self.paused.store(true, Ordering::Relaxed);
+        self.frame_buffer.lock().await.clear();
+        println!("🔇 Son coupé");
+        Ok(())
+    }
+    
+    async fn resume(&mut self) -> AudioResult<()> {
+        if !self.is_playing {
+            return Err(AudioError::InitializationError("Lecture non démarrée".to_string()));
+        }
+        self.paused.store(false, Ordering::Relaxed);
+        println!("🔊 Son rétabli");
         Ok(())
     }
     
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    
+    async fn play_frame(&mut self, mut frame: AudioFrame) -> AudioResult<usize> {
+        // Sortie débranchée : à rouvrir par l'appelant (voir `AudioDeviceManager`)
+        if self.disconnected.load(Ordering::Relaxed) {
+            return Err(AudioError::DeviceDisconnected);
+        }
+        // Route changée (casque Bluetooth en HFP) : nouveau stream, même buffer de frames
+        if self.is_playing && self.route_changed.swap(false, Ordering::Relaxed) {
+            self.rebuild_after_route_change();
+        }
+        // Son coupé : la frame est jetée, sans fausser la sonie mesurée
+        if self.is_paused() {
+            return Ok(0);
+        }
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.process(&mut frame);
+        }
+        
+        let mut buffer_guard = self.frame_buffer.lock().await;
+        Self::enqueue_frame(&mut buffer_guard, frame, &self.config)
+    }
+    
     fn is_playing(&self) -> bool {
         self.is_playing
     }
@@ -437,6 +704,14 @@ impl AudioPlayback for CpalPlayback {
     fn device_info(&self) -> String {
         self.device_name.clone()
     }
+    
+    /// Paramètres du stream en cours, ou ceux qu'utiliserait `start()`
+    fn device_details(&self) -> DeviceInfo {
+        let stream = self.stream_params.clone().or_else(|| {
+            self.device.default_output_config().ok().map(|config| (config.config(), config.sample_format()))
+        });
+        devices::describe_device(self.host_id, &self.device_name, stream, self.device_buffer_frames())
+    }
 }
 
 // Implémentation de Drop pour nettoyer proprement
@@ -450,6 +725,24 @@ impl Drop for CpalPlayback {
     }
 }
 
+/// Voix convertie à la fréquence native de la sortie, propre au callback
+/// 
+/// Le rééchantillonneur produit plus ou moins de trames qu'il n'en consomme :
+/// le surplus attend le callback suivant dans `pending`.
+struct NativeOutput {
+    resampler: Resampler,
+    pending: VecDeque<f32>,
+}
+
+impl NativeOutput {
+    /// Échantillons de la chaîne nécessaires pour compléter `device_frames`
+    /// trames de sortie, compte tenu de la voix déjà convertie
+    fn samples_needed(&self, device_frames: usize, channels: u16) -> usize {
+        let missing = device_frames.saturating_sub(self.pending.len() / channels as usize);
+        self.resampler.input_frames_for(missing) * channels as usize
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -464,6 +757,7 @@ mod tests {
             Ok(playback) => {
                 assert!(!playback.is_playing());
                 assert!(!playback.device_info().is_empty());
+                assert_eq!(playback.device_details().name, playback.device_info());
                 assert_eq!(playback.buffer_level(), 0);
             },
             Err(AudioError::NoDeviceFound) => {
@@ -519,19 +813,51 @@ mod tests {
         
         if let Ok(mut playback) = CpalPlayback::new(config.clone()) {
             // Remplit le buffer au maximum
-            for i in 0..config.receive_buffer_size {
+            for i in 0..config.max_buffer_frames() {
                 let frame = AudioFrame::silence(config.samples_per_frame(), i as u64);
                 let result = playback.play_frame(frame).await;
                 assert!(result.is_ok());
             }
             
-            // Une frame de plus doit causer un overflow
+            // Une frame de plus doit causer un overflow : la plus ancienne disparaît
             let overflow_frame = AudioFrame::silence(config.samples_per_frame(), 999);
             let result = playback.play_frame(overflow_frame).await;
-            assert!(matches!(result, Err(AudioError::BufferOverflow)));
+            assert!(matches!(result, Ok(1)));
+            assert_eq!(playback.buffer_level(), config.max_buffer_frames());
         }
     }
     
+    #[test]
+    fn test_overflow_policies() {
+        let mut config = AudioConfig::default();
+        let frame_len = config.samples_per_frame();
+        let frame = |sequence| AudioFrame::silence(frame_len, sequence);
+        let sequences = |buffer: &VecDeque<AudioFrame>| {
+            buffer.iter().map(|frame| frame.sequence_number).collect::<Vec<_>>()
+        };
+        let full: VecDeque<AudioFrame> = (0..config.max_buffer_frames() as u64).map(frame).collect();
+        
+        let mut buffer = full.clone();
+        assert!(matches!(CpalPlayback::enqueue_frame(&mut buffer, frame(9), &config), Ok(1)));
+        assert_eq!(sequences(&buffer), [1, 2, 9]); // 0 et 1 fusionnées
+        
+        config.overflow_policy = OverflowPolicy::DropNewest;
+        let mut buffer = full.clone();
+        assert!(matches!(CpalPlayback::enqueue_frame(&mut buffer, frame(9), &config), Ok(1)));
+        assert_eq!(sequences(&buffer), [0, 1, 2]);
+        
+        config.overflow_policy = OverflowPolicy::Error;
+        let mut buffer = full.clone();
+        let result = CpalPlayback::enqueue_frame(&mut buffer, frame(9), &config);
+        assert!(matches!(result, Err(AudioError::BufferOverflow)));
+        assert_eq!(sequences(&buffer), [0, 1, 2]);
+        
+        // Buffer pas plein : rien n'est jeté, quelle que soit la politique
+        buffer.pop_back();
+        assert!(matches!(CpalPlayback::enqueue_frame(&mut buffer, frame(9), &config), Ok(0)));
+        assert_eq!(sequences(&buffer), [0, 1, 9]);
+    }
+    
     // Note: Ce test nécessite de vrais haut-parleurs et peut être audible
     #[tokio::test]
     #[ignore] // Ignore par défaut, lance avec --ignored pour tester
@@ -0,0 +1,28 @@
+//! Surface stable du crate, à importer d'un bloc
+//!
+//! Traits, configuration, erreurs et implémentations principales. Les
+//! éléments absents du prélude (modules `stretch`, `resample`, `samples`,
+//! `realtime`...) restent publics mais peuvent encore changer d'une version
+//! mineure à l'autre.
+//!
+//! # Example
+//! ```rust
+//! use audio::prelude::*;
+//!
+//! let config = AudioConfig::default();
+//! let frame = AudioFrame::silence(config.samples_per_frame(), 0);
+//! assert!(config.validate().is_ok());
+//! assert_eq!(frame.samples.len(), 960);
+//! ```
+
+pub use crate::{AudioCapture, AudioCodec, AudioPipeline, AudioPlayback};
+pub use crate::{AudioConfig, AudioDevicePreferences, AudioSettings, LatencyMode, OverflowPolicy};
+pub use crate::{AudioError, AudioErrorCategory, AudioResult};
+pub use crate::{AudioFrame, AudioStats, CompressedFrame, DeviceInfo, FrameOrigin, Sample};
+pub use crate::{CallSession, CallSessionState, MockCapture, MockPlayback};
+#[cfg(feature = "cpal")]
+pub use crate::{AudioDeviceManager, CpalCapture, CpalPlayback, DeviceEvent};
+#[cfg(feature = "opus")]
+pub use crate::OpusCodec;
+#[cfg(all(feature = "cpal", feature = "opus"))]
+pub use crate::AudioPipelineImpl;
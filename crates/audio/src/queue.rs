@@ -0,0 +1,197 @@
+//! File bornée de frames entre deux étapes de la chaîne audio
+//!
+//! Chaque étape (capture, codec, lecture) tourne dans sa propre tâche : la
+//! file absorbe les à-coups de l'une sans bloquer l'autre. Pleine, elle
+//! applique une `OverflowPolicy` explicite plutôt que de freiner l'étape
+//! amont (une capture freinée perd des échantillons dans le callback).
+//! Chaque frame garde son instant d'entrée : le temps d'attente mesure la
+//! latence ajoutée par la file.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+use crate::{stretch, AudioError, AudioFrame, AudioResult, OverflowPolicy};
+
+/// État partagé entre les deux extrémités
+struct Shared {
+    frames: Mutex<VecDeque<(AudioFrame, Instant)>>,
+    notify: Notify,
+    closed: AtomicBool,
+    capacity: usize,
+    policy: OverflowPolicy,
+    channels: u16,
+}
+
+/// Crée une file de `capacity` frames (au moins une)
+///
+/// `channels` indique l'entrelacement des échantillons, pour la fusion
+/// des frames de `OverflowPolicy::DropOldest`.
+///
+/// # Example
+/// ```rust
+/// use audio::{AudioFrame, OverflowPolicy, queue};
+///
+/// # async fn example() {
+/// let (sender, mut receiver) = queue::bounded(2, OverflowPolicy::DropNewest, 1);
+/// sender.send(AudioFrame::silence(960, 1)).unwrap();
+/// sender.send(AudioFrame::silence(960, 2)).unwrap();
+/// assert_eq!(sender.send(AudioFrame::silence(960, 3)).unwrap(), 1);
+///
+/// let (frame, waited) = receiver.recv().await.unwrap();
+/// assert_eq!(frame.sequence_number, 1);
+/// println!("Attente dans la file : {:?}", waited);
+/// # }
+/// ```
+pub fn bounded(capacity: usize, policy: OverflowPolicy, channels: u16) -> (FrameSender, FrameReceiver) {
+    let shared = Arc::new(Shared {
+        frames: Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+        notify: Notify::new(),
+        closed: AtomicBool::new(false),
+        capacity: capacity.max(1),
+        policy,
+        channels,
+    });
+    (FrameSender { shared: Arc::clone(&shared) }, FrameReceiver { shared })
+}
+
+/// Extrémité d'entrée d'une file (étape amont)
+///
+/// Sa destruction ferme la file : le récepteur lit les frames restantes,
+/// puis `recv` retourne `None`.
+pub struct FrameSender {
+    shared: Arc<Shared>,
+}
+
+impl FrameSender {
+    /// Ajoute une frame, selon la politique de la file si elle est pleine
+    ///
+    /// Retourne le nombre de frames jetées (fusionnées pour `DropOldest`).
+    ///
+    /// # Erreurs
+    /// - `AudioError::BufferOverflow` : file pleine avec `OverflowPolicy::Error`
+    ///   (la frame est jetée)
+    pub fn send(&self, frame: AudioFrame) -> AudioResult<usize> {
+        let mut frames = self.shared.frames.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut dropped = 0;
+        if frames.len() >= self.shared.capacity {
+            match self.shared.policy {
+                OverflowPolicy::DropOldest => {
+                    // Comme le buffer de lecture : les deux plus anciennes
+                    // fusionnées par étirement plutôt qu'un trou audible (file
+                    // d'une seule frame : elle est simplement remplacée)
+                    if let (Some((first, queued_at)), Some((second, _))) = (frames.pop_front(), frames.pop_front()) {
+                        frames.push_front((stretch::compress_frames(&first, &second, self.shared.channels), queued_at));
+                    }
+                    dropped = 1;
+                }
+                OverflowPolicy::DropNewest => return Ok(1),
+                OverflowPolicy::Error => return Err(AudioError::BufferOverflow),
+            }
+        }
+        frames.push_back((frame, Instant::now()));
+        drop(frames);
+        self.shared.notify.notify_one();
+        Ok(dropped)
+    }
+
+    /// Nombre de frames en attente
+    pub fn len(&self) -> usize {
+        self.shared.frames.lock().map(|frames| frames.len()).unwrap_or(0)
+    }
+
+    /// Indique si la file est vide
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for FrameSender {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.notify.notify_one();
+    }
+}
+
+/// Extrémité de sortie d'une file (étape aval)
+pub struct FrameReceiver {
+    shared: Arc<Shared>,
+}
+
+impl FrameReceiver {
+    /// Prochaine frame et son temps d'attente dans la file
+    ///
+    /// Attend qu'une frame arrive ; `None` une fois la file fermée et vide.
+    pub async fn recv(&mut self) -> Option<(AudioFrame, Duration)> {
+        loop {
+            if let Some(received) = self.try_recv() {
+                return Some(received);
+            }
+            if self.shared.closed.load(Ordering::Acquire) {
+                // Une frame a pu arriver juste avant la fermeture
+                return self.try_recv();
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// Prochaine frame si la file n'est pas vide, sans attendre
+    pub fn try_recv(&mut self) -> Option<(AudioFrame, Duration)> {
+        let mut frames = self.shared.frames.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        frames.pop_front().map(|(frame, queued_at)| (frame, queued_at.elapsed()))
+    }
+
+    /// Nombre de frames en attente
+    pub fn len(&self) -> usize {
+        self.shared.frames.lock().map(|frames| frames.len()).unwrap_or(0)
+    }
+
+    /// Indique si la file est vide
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_overflow_policies() {
+        let (sender, mut receiver) = bounded(2, OverflowPolicy::DropOldest, 1);
+        for sequence in 1..=3 {
+            sender.send(AudioFrame::silence(960, sequence)).unwrap();
+        }
+        // Les deux plus anciennes fusionnées en une, à la durée d'une frame
+        let (merged, _) = receiver.recv().await.unwrap();
+        assert_eq!((merged.sequence_number, merged.samples.len()), (2, 960));
+        assert_eq!(receiver.recv().await.unwrap().0.sequence_number, 3);
+
+        let (sender, _receiver) = bounded(1, OverflowPolicy::Error, 1);
+        sender.send(AudioFrame::silence(960, 1)).unwrap();
+        assert!(matches!(sender.send(AudioFrame::silence(960, 2)), Err(AudioError::BufferOverflow)));
+        assert_eq!(sender.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_wakes_and_closes() {
+        let (sender, mut receiver) = bounded(4, OverflowPolicy::DropNewest, 1);
+        let consumer = tokio::spawn(async move {
+            let mut sequences = Vec::new();
+            while let Some((frame, _)) = receiver.recv().await {
+                sequences.push(frame.sequence_number);
+            }
+            sequences
+        });
+
+        for sequence in 1..=3 {
+            sender.send(AudioFrame::silence(960, sequence)).unwrap();
+            tokio::task::yield_now().await;
+        }
+        drop(sender);
+        assert_eq!(consumer.await.unwrap(), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Priorité temps réel des threads audio
+//!
+//! Un callback cpal doit rendre la main en moins d'une frame : sous charge,
+//! un thread à priorité normale se fait préempter et on entend des craquements.
+//! Ce module élève la priorité du thread courant avec le mécanisme natif :
+//! - Linux et autres Unix : `SCHED_FIFO`, puis `SCHED_RR` (nécessite
+//!   `CAP_SYS_NICE` ou une limite `RLIMIT_RTPRIO` suffisante)
+//! - Windows : MMCSS, tâche « Pro Audio »
+//! - macOS : politique `THREAD_TIME_CONSTRAINT_POLICY` calée sur la période
+//!
+//! Sur macOS, le thread d'E/S de CoreAudio est déjà temps réel et rattaché au
+//! workgroup audio du périphérique ; cpal n'exposant pas ce workgroup, les
+//! callbacks n'y sont pas modifiés. `promote_current_thread` reste utile pour
+//! un thread d'ordonnancement de la lecture créé par l'application.
+
+use std::time::Duration;
+
+use crate::{AudioConfig, AudioError, AudioResult};
+
+/// Priorité demandée pour `SCHED_FIFO` / `SCHED_RR`, bornée par le système
+///
+/// Volontairement basse : au-dessus de tous les threads normaux, mais sous
+/// les threads des serveurs son (PipeWire, JACK) qui nous alimentent.
+#[cfg(all(unix, not(target_vendor = "apple")))]
+const REALTIME_PRIORITY: libc::c_int = 10;
+
+/// Élève la priorité du thread courant pour un traitement audio périodique
+///
+/// # Arguments
+/// * `period` - Intervalle entre deux réveils du thread (durée d'une frame
+///   ou d'un buffer cpal) ; utilisé par macOS pour réserver le temps CPU
+///
+/// # Erreurs
+/// - `AudioError::InitializationError` si le système refuse (droits
+///   insuffisants, MMCSS indisponible) ou si la plateforme n'est pas gérée
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// std::thread::spawn(|| {
+///     if let Err(e) = audio::realtime::promote_current_thread(Duration::from_millis(20)) {
+///         eprintln!("⚠️  {}", e);
+///     }
+///     // ... boucle d'ordonnancement de la lecture
+/// });
+/// ```
+pub fn promote_current_thread(period: Duration) -> AudioResult<()> {
+    promote(period)
+}
+
+#[cfg(all(unix, not(target_vendor = "apple")))]
+fn promote(_period: Duration) -> AudioResult<()> {
+    let mut last_error = 0;
+    for policy in [libc::SCHED_FIFO, libc::SCHED_RR] {
+        // SAFETY: appels POSIX sur le thread courant, `param` entièrement initialisé
+        let result = unsafe {
+            let min = libc::sched_get_priority_min(policy);
+            let max = libc::sched_get_priority_max(policy);
+            let mut param: libc::sched_param = std::mem::zeroed();
+            param.sched_priority = REALTIME_PRIORITY.clamp(min, max);
+            libc::pthread_setschedparam(libc::pthread_self(), policy, &param)
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        last_error = result;
+    }
+    Err(AudioError::InitializationError(format!(
+        "Priorité temps réel refusée ({}) - CAP_SYS_NICE ou RLIMIT_RTPRIO requis",
+        std::io::Error::from_raw_os_error(last_error)
+    )))
+}
+
+#[cfg(target_vendor = "apple")]
+#[allow(deprecated)] // `mach_timebase_info` : pas besoin du crate mach2 pour un seul appel
+fn promote(period: Duration) -> AudioResult<()> {
+    let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+    // SAFETY: `timebase` est un pointeur valide vers une structure initialisée
+    if unsafe { libc::mach_timebase_info(&mut timebase) } != 0 || timebase.numer == 0 {
+        return Err(AudioError::InitializationError("mach_timebase_info indisponible".to_string()));
+    }
+    let to_ticks = |duration: Duration| {
+        (duration.as_nanos() * timebase.denom as u128 / timebase.numer as u128).min(u32::MAX as u128) as u32
+    };
+
+    // Le thread peut utiliser jusqu'à la moitié de sa période
+    let mut policy = libc::thread_time_constraint_policy {
+        period: to_ticks(period),
+        computation: to_ticks(period / 2),
+        constraint: to_ticks(period),
+        preemptible: 1,
+    };
+    // SAFETY: `policy` correspond au type attendu par THREAD_TIME_CONSTRAINT_POLICY
+    let result = unsafe {
+        libc::thread_policy_set(
+            libc::pthread_mach_thread_np(libc::pthread_self()),
+            libc::THREAD_TIME_CONSTRAINT_POLICY as libc::thread_policy_flavor_t,
+            &mut policy as *mut libc::thread_time_constraint_policy as libc::thread_policy_t,
+            libc::THREAD_TIME_CONSTRAINT_POLICY_COUNT,
+        )
+    };
+    if result != 0 {
+        return Err(AudioError::InitializationError(format!("thread_policy_set a échoué (code {})", result)));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn promote(_period: Duration) -> AudioResult<()> {
+    use windows_sys::Win32::System::Threading::AvSetMmThreadCharacteristicsW;
+
+    let task: Vec<u16> = "Pro Audio\0".encode_utf16().collect();
+    let mut task_index = 0u32;
+    // SAFETY: `task` est une chaîne UTF-16 terminée par un nul, valide pendant l'appel.
+    // Le handle n'est jamais rendu : l'enregistrement MMCSS vit autant que le thread.
+    let handle = unsafe { AvSetMmThreadCharacteristicsW(task.as_ptr(), &mut task_index) };
+    if handle.is_null() {
+        return Err(AudioError::InitializationError(format!(
+            "Enregistrement MMCSS refusé ({})",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn promote(_period: Duration) -> AudioResult<()> {
+    Err(AudioError::InitializationError("Priorité temps réel non gérée sur cette plateforme".to_string()))
+}
+
+/// Élévation différée au premier appel d'un callback audio
+///
+/// cpal crée lui-même le thread qui exécute les callbacks : la promotion ne
+/// peut se faire que depuis le callback. Cette structure, déplacée dans la
+/// closure, tente l'opération une seule fois et n'affiche l'échec qu'une fois.
+#[derive(Debug)]
+pub struct CallbackPromotion {
+    period: Duration,
+    pending: bool,
+}
+
+impl CallbackPromotion {
+    /// Prépare la promotion selon `AudioConfig::realtime_priority`
+    pub fn new(config: &AudioConfig) -> Self {
+        Self {
+            period: Duration::from_millis(config.frame_duration_ms as u64),
+            // Le thread d'E/S CoreAudio est déjà temps réel (voir la doc du module)
+            pending: config.realtime_priority && !cfg!(target_vendor = "apple"),
+        }
+    }
+
+    /// À appeler en tête de callback ; ne fait rien après le premier appel
+    pub fn ensure(&mut self) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+        if let Err(e) = promote_current_thread(self.period) {
+            eprintln!("⚠️  Thread audio laissé en priorité normale : {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_promotion_disabled_by_default() {
+        let mut promotion = CallbackPromotion::new(&AudioConfig::default());
+        assert!(!promotion.pending);
+        promotion.ensure();
+
+        let config = AudioConfig { realtime_priority: true, ..AudioConfig::default() };
+        let mut promotion = CallbackPromotion::new(&config);
+        assert_eq!(promotion.pending, !cfg!(target_vendor = "apple"));
+        // Réussite ou refus selon les droits du processus : une seule tentative
+        promotion.ensure();
+        assert!(!promotion.pending);
+    }
+}
@@ -0,0 +1,166 @@
+//! Rééchantillonnage entre la fréquence du périphérique et celle de la chaîne
+//!
+//! Un casque Bluetooth qui bascule du profil A2DP (48 kHz stéréo) au profil
+//! HFP (16 kHz, voire 8 kHz mono) en cours d'appel change la fréquence de son
+//! stream. Le reste de la chaîne (codec, mixeur, frames de
+//! `AudioConfig::samples_per_frame`) garde `AudioConfig::sample_rate` : les
+//! échantillons sont convertis dès le callback.
+//!
+//! L'interpolation est linéaire et continue d'un callback à l'autre : pas de
+//! filtre anti-repliement, suffisant pour la voix (l'essentiel de son énergie
+//! est sous les 4 kHz du HFP à 8 kHz).
+
+use crate::Sample;
+
+/// Rééchantillonneur en continu, par trames entrelacées de `channels` canaux
+///
+/// # Example
+/// ```rust
+/// use audio::resample::Resampler;
+///
+/// // Micro HFP à 16 kHz, chaîne à 48 kHz
+/// let mut resampler = Resampler::new(16000, 48000, 1);
+/// let mut output = Vec::new();
+/// resampler.process(&[0.5; 320], &mut output);
+/// assert_eq!(output.len(), 957);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Resampler {
+    from_rate: u32,
+    to_rate: u32,
+    channels: usize,
+    /// Position de la prochaine sortie, en 1/`to_rate` de trame d'entrée
+    /// (entier : pas de dérive sur un long appel) ; 0 = dernière trame du
+    /// bloc précédent
+    position: u64,
+    /// Dernière trame du bloc précédent (vide avant le premier bloc)
+    previous: Vec<Sample>,
+}
+
+impl Resampler {
+    /// Crée un rééchantillonneur de `from_rate` vers `to_rate` Hz
+    pub fn new(from_rate: u32, to_rate: u32, channels: u16) -> Self {
+        Self {
+            from_rate,
+            to_rate,
+            channels: channels.max(1) as usize,
+            position: to_rate as u64,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Vrai si les deux fréquences sont égales (simple copie)
+    pub fn is_passthrough(&self) -> bool {
+        self.from_rate == self.to_rate
+    }
+
+    /// Fréquence d'entrée, en Hz
+    pub fn from_rate(&self) -> u32 {
+        self.from_rate
+    }
+
+    /// Fréquence de sortie, en Hz
+    pub fn to_rate(&self) -> u32 {
+        self.to_rate
+    }
+
+    /// Trames d'entrée à fournir pour obtenir au moins `output_frames` trames
+    pub fn input_frames_for(&self, output_frames: usize) -> usize {
+        if self.is_passthrough() || output_frames == 0 {
+            return output_frames;
+        }
+        let last = self.position + (output_frames as u64 - 1) * self.from_rate as u64;
+        (last / self.to_rate as u64) as usize + 1
+    }
+
+    /// Rééchantillonne `input` et ajoute le résultat à `output`
+    ///
+    /// Les trames incomplètes en fin de bloc sont ignorées.
+    pub fn process(&mut self, input: &[Sample], output: &mut impl Extend<Sample>) {
+        let channels = self.channels;
+        let frames = input.len() / channels;
+        if self.is_passthrough() {
+            output.extend(input[..frames * channels].iter().copied());
+            return;
+        }
+        if frames == 0 {
+            return;
+        }
+        if self.previous.is_empty() {
+            self.previous.extend_from_slice(&input[..channels]);
+        }
+
+        // Trame `index` du bloc étendu : 0 = `previous`, i + 1 = input[i]
+        let frame = |index: usize| -> &[Sample] {
+            match index {
+                0 => &self.previous,
+                i => &input[(i - 1) * channels..i * channels],
+            }
+        };
+        let (from_rate, to_rate) = (self.from_rate as u64, self.to_rate as u64);
+        let end = frames as u64 * to_rate;
+        while self.position < end {
+            let index = (self.position / to_rate) as usize;
+            let fraction = (self.position % to_rate) as f32 / to_rate as f32;
+            let (a, b) = (frame(index), frame(index + 1));
+            output.extend(a.iter().zip(b).map(|(a, b)| a + (b - a) * fraction));
+            self.position += from_rate;
+        }
+
+        self.position -= end;
+        self.previous.clear();
+        self.previous.extend_from_slice(&input[(frames - 1) * channels..frames * channels]);
+    }
+
+    /// Oublie l'état du bloc précédent (nouveau stream)
+    pub fn reset(&mut self) {
+        self.position = self.to_rate as u64;
+        self.previous.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_lengths_across_blocks() {
+        for (from, to) in [(16000, 48000), (48000, 16000), (44100, 48000), (48000, 8000)] {
+            let mut resampler = Resampler::new(from, to, 2);
+            let mut output = Vec::new();
+            let mut input_frames = 0;
+            for block in [441, 100, 7, 960, 1] {
+                let wanted = resampler.input_frames_for(block);
+                let before = output.len();
+                resampler.process(&vec![0.25; wanted * 2], &mut output);
+                assert!((output.len() - before) / 2 >= block, "{} -> {} Hz : bloc de {}", from, to, block);
+                input_frames += wanted;
+            }
+            // Durée conservée (à la trame de démarrage près), trames
+            // entières, valeur constante intacte
+            let ratio = to as f64 / from as f64;
+            let expected = input_frames as f64 * ratio;
+            assert!(((output.len() / 2) as f64 - expected).abs() <= ratio.max(1.0) + 1.0);
+            assert_eq!(output.len() % 2, 0);
+            assert!(output.iter().all(|&s| (s - 0.25).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_resample_is_continuous() {
+        // Rampe coupée en blocs irréguliers : la sortie reste une rampe
+        let ramp: Vec<Sample> = (0..1600).map(|i| i as f32 / 1600.0).collect();
+        let mut resampler = Resampler::new(16000, 48000, 1);
+        let mut output = Vec::new();
+        for block in ramp.chunks(137) {
+            resampler.process(block, &mut output);
+        }
+        let expected_step = 1.0 / 4800.0;
+        assert!(output.windows(2).all(|pair| ((pair[1] - pair[0]) - expected_step).abs() < 1e-4));
+
+        let mut passthrough = Resampler::new(48000, 48000, 1);
+        let mut copy = Vec::new();
+        passthrough.process(&ramp, &mut copy);
+        assert_eq!(copy, ramp);
+    }
+}
@@ -0,0 +1,207 @@
+//! Conversion des échantillons du périphérique vers et depuis f32
+//!
+//! Les cartes son exposent des formats variés : i16 le plus souvent, mais
+//! aussi i24 ou i32 (ASIO, ALSA), u8 sur du vieux matériel, f64... Le reste du
+//! crate ne manipule que des f32 dans [-1.0, 1.0] ; la conversion passe par
+//! les traits `Sample` / `FromSample` de cpal, qui gèrent notamment le
+//! décalage des formats non signés (u8 128 = silence).
+//!
+//! Le nombre de canaux du périphérique peut aussi différer de
+//! `AudioConfig::channels` (sortie 5.1, micro stéréo) : chaque trame est
+//! répartie sur les canaux du périphérique par `map_channels`.
+
+use std::collections::VecDeque;
+
+use cpal::{FromSample, Sample, SampleFormat};
+
+/// Formats d'échantillons gérés par la capture et la lecture
+pub const SUPPORTED_FORMATS: [SampleFormat; 12] = [
+    SampleFormat::I8,
+    SampleFormat::I16,
+    SampleFormat::I24,
+    SampleFormat::I32,
+    SampleFormat::I64,
+    SampleFormat::U8,
+    SampleFormat::U16,
+    SampleFormat::U24,
+    SampleFormat::U32,
+    SampleFormat::U64,
+    SampleFormat::F32,
+    SampleFormat::F64,
+];
+
+/// Nombre de canaux du périphérique pris en compte ; au-delà, silence
+const MAX_CHANNELS: usize = 64;
+
+/// Vérifie qu'un format de périphérique peut être converti
+pub fn is_supported(format: SampleFormat) -> bool {
+    SUPPORTED_FORMATS.contains(&format)
+}
+
+/// Convertit un échantillon du périphérique en f32
+///
+/// # Example
+/// ```rust
+/// use audio::samples::to_f32;
+///
+/// assert_eq!(to_f32(i32::MIN), -1.0);
+/// assert_eq!(to_f32(128u8), 0.0);
+/// ```
+pub fn to_f32<T>(sample: T) -> f32
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    sample.to_sample::<f32>()
+}
+
+/// Répartit une trame (un échantillon par canal) sur un autre nombre de canaux
+///
+/// - même nombre de canaux : copie
+/// - vers mono : moyenne de tous les canaux
+/// - depuis mono : avant gauche et avant droit, autres canaux à 0
+/// - sinon : canaux communs copiés, canaux en trop à 0
+///
+/// # Example
+/// ```rust
+/// use audio::samples::map_channels;
+///
+/// // Mono vers 5.1 : seuls les canaux avant gauche / droit jouent
+/// let mut surround = [1.0; 6];
+/// map_channels(&[0.5], &mut surround);
+/// assert_eq!(surround, [0.5, 0.5, 0.0, 0.0, 0.0, 0.0]);
+/// ```
+pub fn map_channels(input: &[f32], output: &mut [f32]) {
+    match (input.len(), output.len()) {
+        (from, to) if from == to => output.copy_from_slice(input),
+        (from, 1) => output[0] = input.iter().sum::<f32>() / from as f32,
+        (1, _) => {
+            output.fill(0.0);
+            output[..2].fill(input[0]);
+        }
+        (from, to) => {
+            let common = from.min(to);
+            output[..common].copy_from_slice(&input[..common]);
+            output[common..].fill(0.0);
+        }
+    }
+}
+
+/// Ajoute une trame du périphérique à `out`, convertie en f32 sur `channels` canaux
+///
+/// Appelé pour chaque trame dans le callback de capture (sans allocation).
+/// Les canaux au-delà de `MAX_CHANNELS` sont ajoutés en silence.
+pub fn push_frame<T>(device_frame: &[T], channels: usize, out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    let mut source = [0.0f32; MAX_CHANNELS];
+    let mut mapped = [0.0f32; MAX_CHANNELS];
+    let device_channels = device_frame.len().min(MAX_CHANNELS);
+    for (value, &sample) in source.iter_mut().zip(device_frame) {
+        *value = to_f32(sample);
+    }
+    let kept_channels = channels.min(MAX_CHANNELS);
+    map_channels(&source[..device_channels], &mut mapped[..kept_channels]);
+    out.extend_from_slice(&mapped[..kept_channels]);
+    out.resize(out.len() + channels - kept_channels, 0.0);
+}
+
+/// Remplit la sortie du périphérique depuis des trames f32 de `channels` canaux
+///
+/// Chaque trame est répartie sur les canaux du périphérique (`map_channels`).
+/// Quand `samples` est épuisé, le reste de la sortie est mis au silence
+/// (`T::EQUILIBRIUM`, soit 128 pour u8, et non 0), comme les canaux du
+/// périphérique au-delà de `MAX_CHANNELS`.
+pub fn fill_from_f32<T>(output: &mut [T], device_channels: usize, samples: &mut VecDeque<f32>, channels: usize)
+where
+    T: Sample + FromSample<f32>,
+{
+    let mut source = [0.0f32; MAX_CHANNELS];
+    let mut mapped = [0.0f32; MAX_CHANNELS];
+    let mapped_channels = device_channels.min(MAX_CHANNELS);
+    let source_channels = channels.min(MAX_CHANNELS);
+    for device_frame in output.chunks_mut(device_channels) {
+        if samples.len() < channels {
+            device_frame.fill(T::EQUILIBRIUM);
+            continue;
+        }
+        for value in &mut source[..source_channels] {
+            *value = samples.pop_front().unwrap_or(0.0);
+        }
+        samples.drain(..channels - source_channels);
+        map_channels(&source[..source_channels], &mut mapped[..mapped_channels]);
+        for (out, &value) in device_frame.iter_mut().zip(mapped.iter()) {
+            *out = value.to_sample::<T>();
+        }
+        if let Some(unmapped) = device_frame.get_mut(mapped_channels..) {
+            unmapped.fill(T::EQUILIBRIUM);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(to_f32(0.25f64), 0.25);
+        assert_eq!(to_f32(i16::MIN), -1.0);
+        assert!((to_f32(i32::MAX) - 1.0).abs() < 1e-6);
+        assert_eq!(to_f32(u16::MAX / 2 + 1), 0.0);
+        assert!(is_supported(SampleFormat::I32));
+
+        let mut samples = VecDeque::from([1.0, -1.0, 0.5]);
+        let mut output = [0u8; 5];
+        fill_from_f32(&mut output, 1, &mut samples, 1);
+        assert_eq!(output, [255, 0, 192, 128, 128]);
+        assert!(samples.is_empty());
+
+        // Aller-retour sans perte notable en i24
+        let mut samples = VecDeque::from([0.3f32]);
+        let mut output = [cpal::I24::EQUILIBRIUM];
+        fill_from_f32(&mut output, 1, &mut samples, 1);
+        assert!((to_f32(output[0]) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_channel_mapping() {
+        // Lecture mono sur une sortie 5.1 : deux trames complètes, puis silence
+        let mut samples = VecDeque::from([0.5, -0.5]);
+        let mut output = [1.0f32; 18];
+        fill_from_f32(&mut output, 6, &mut samples, 1);
+        assert_eq!(&output[..6], &[0.5, 0.5, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(&output[6..8], &[-0.5, -0.5]);
+        assert!(output[8..].iter().all(|&sample| sample == 0.0));
+
+        // Capture stéréo ramenée en mono
+        let mut captured = Vec::new();
+        push_frame(&[i16::MIN, 0], 1, &mut captured);
+        push_frame(&[0.25f32, 0.75], 2, &mut captured);
+        assert_eq!(captured, vec![-0.5, 0.25, 0.75]);
+
+        // Stéréo vers mono : moyenne
+        let mut mono = [0.0];
+        map_channels(&[0.2, 0.4], &mut mono);
+        assert!((mono[0] - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_channels_beyond_max() {
+        // Canaux du périphérique au-delà de MAX_CHANNELS : silence
+        let mut samples = VecDeque::from([0.5]);
+        let mut output = [1.0f32; MAX_CHANNELS + 2];
+        fill_from_f32(&mut output, MAX_CHANNELS + 2, &mut samples, 1);
+        assert_eq!(&output[..2], &[0.5, 0.5]);
+        assert!(output[2..].iter().all(|&sample| sample == 0.0));
+
+        // Capture sur plus de MAX_CHANNELS canaux : pas de panique, canaux en trop muets
+        let mut captured = Vec::new();
+        push_frame(&[0.5f32; 2], MAX_CHANNELS + 2, &mut captured);
+        assert_eq!(captured.len(), MAX_CHANNELS + 2);
+        assert_eq!(&captured[..2], &[0.5, 0.5]);
+        assert!(captured[MAX_CHANNELS..].iter().all(|&sample| sample == 0.0));
+    }
+}
@@ -0,0 +1,198 @@
+//! Démarrage à chaud de la chaîne audio d'un appel
+//!
+//! Ouvrir les périphériques et créer l'encodeur Opus prend un temps
+//! perceptible : démarrés à l'acceptation de l'appel, ils font perdre la
+//! première seconde d'audio. `CallSession` les prépare à l'avance, en
+//! veille (`prepare`), pour que `activate` n'ait plus qu'à lancer les
+//! streams une fois le handshake terminé.
+
+use std::time::{Duration, Instant};
+
+use crate::{AudioCapture, AudioCodec, AudioConfig, AudioFrame, AudioPlayback, AudioResult, CompressedFrame};
+
+/// Étape du cycle de vie d'une `CallSession`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CallSessionState {
+    /// Composants créés, périphériques fermés
+    Idle,
+    /// Périphériques ouverts et codecs initialisés, streams à l'arrêt
+    Standby,
+    /// Capture et lecture en cours
+    Active,
+}
+
+/// Chaîne audio d'un appel : capture, encodeur, décodeur et lecture
+///
+/// # Example
+/// ```rust,no_run
+/// use audio::{AudioConfig, CallSession};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// // Avant d'accepter l'appel
+/// let mut session = CallSession::prepare(AudioConfig::default()).await?;
+///
+/// // Handshake terminé : seuls les streams restent à démarrer
+/// let setup = session.activate().await?;
+/// println!("Audio actif en {:?}", setup);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CallSession {
+    config: AudioConfig,
+    capture: Box<dyn AudioCapture>,
+    playback: Box<dyn AudioPlayback>,
+    encoder: Box<dyn AudioCodec>,
+    decoder: Box<dyn AudioCodec>,
+    state: CallSessionState,
+}
+
+impl CallSession {
+    /// Assemble une session à partir de composants existants (périphériques
+    /// préférés, factices...), sans les ouvrir
+    pub fn new(
+        config: AudioConfig,
+        capture: Box<dyn AudioCapture>,
+        playback: Box<dyn AudioPlayback>,
+        encoder: Box<dyn AudioCodec>,
+        decoder: Box<dyn AudioCodec>,
+    ) -> Self {
+        Self { config, capture, playback, encoder, decoder, state: CallSessionState::Idle }
+    }
+
+    /// Crée et met en veille la chaîne audio par défaut (cpal et Opus)
+    ///
+    /// # Erreurs
+    /// - `AudioError::NoDeviceFound` : aucun microphone ou haut-parleur
+    /// - `AudioError::ConfigError` : configuration refusée par un périphérique
+    #[cfg(all(feature = "cpal", feature = "opus"))]
+    pub async fn prepare(config: AudioConfig) -> AudioResult<Self> {
+        let mut session = Self::new(
+            config.clone(),
+            Box::new(crate::CpalCapture::new(config.clone())?),
+            Box::new(crate::CpalPlayback::new(config.clone())?),
+            Box::new(crate::OpusCodec::new(config.clone())?),
+            Box::new(crate::OpusCodec::new(config)?),
+        );
+        session.standby().await?;
+        Ok(session)
+    }
+
+    /// Ouvre les périphériques et initialise les codecs, sans démarrer
+    ///
+    /// Une frame de silence traverse l'encodeur et le décodeur (remis à
+    /// zéro ensuite) : leurs allocations sont faites avant l'appel.
+    /// Sans effet si la session est déjà en veille ou active.
+    pub async fn standby(&mut self) -> AudioResult<()> {
+        if self.state != CallSessionState::Idle {
+            return Ok(());
+        }
+        self.capture.prepare().await?;
+        self.playback.prepare().await?;
+
+        let silence = AudioFrame::silence(self.config.samples_per_frame() * self.config.channels as usize, 0);
+        let compressed = self.encoder.encode(&silence)?;
+        self.decoder.decode(&compressed)?;
+        self.encoder.reset()?;
+        self.decoder.reset()?;
+
+        self.state = CallSessionState::Standby;
+        Ok(())
+    }
+
+    /// Démarre la capture et la lecture (handshake terminé)
+    ///
+    /// Une session encore au repos est d'abord mise en veille.
+    ///
+    /// # Returns
+    /// Durée de l'activation, à comparer au démarrage à froid
+    pub async fn activate(&mut self) -> AudioResult<Duration> {
+        let started = Instant::now();
+        self.standby().await?;
+        if self.state == CallSessionState::Standby {
+            self.playback.start().await?;
+            self.capture.start().await?;
+            self.state = CallSessionState::Active;
+        }
+        Ok(started.elapsed())
+    }
+
+    /// Arrête les streams et ferme les périphériques (fin d'appel)
+    pub async fn deactivate(&mut self) -> AudioResult<()> {
+        self.capture.stop().await?;
+        self.playback.stop().await?;
+        self.state = CallSessionState::Idle;
+        Ok(())
+    }
+
+    /// Étape courante
+    pub fn state(&self) -> CallSessionState {
+        self.state
+    }
+
+    /// Capture et encode la frame suivante du microphone
+    pub async fn capture_frame(&mut self) -> AudioResult<CompressedFrame> {
+        let frame = self.capture.next_frame().await?;
+        self.encoder.encode(&frame)
+    }
+
+    /// Décode une frame reçue et la met en file de lecture
+    ///
+    /// # Returns
+    /// Frames en attente de lecture
+    pub async fn play_frame(&mut self, compressed: &CompressedFrame) -> AudioResult<usize> {
+        let frame = self.decoder.decode(compressed)?;
+        self.playback.play_frame(frame).await
+    }
+
+    /// Encodeur (réinitialisation, changement de débit)
+    pub fn encoder_mut(&mut self) -> &mut dyn AudioCodec {
+        self.encoder.as_mut()
+    }
+
+    /// Décodeur (réinitialisation après une reprise de flux)
+    pub fn decoder_mut(&mut self) -> &mut dyn AudioCodec {
+        self.decoder.as_mut()
+    }
+}
+
+#[cfg(all(test, feature = "opus"))]
+mod tests {
+    use super::*;
+    use crate::{MockCapture, MockPlayback, OpusCodec};
+
+    fn mock_session() -> CallSession {
+        let config = AudioConfig::default();
+        CallSession::new(
+            config.clone(),
+            Box::new(MockCapture::new(config.clone())),
+            Box::new(MockPlayback::new()),
+            Box::new(OpusCodec::new(config.clone()).unwrap()),
+            Box::new(OpusCodec::new(config).unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let mut session = mock_session();
+        assert_eq!(session.state(), CallSessionState::Idle);
+
+        session.standby().await.unwrap();
+        assert_eq!(session.state(), CallSessionState::Standby);
+
+        session.activate().await.unwrap();
+        assert_eq!(session.state(), CallSessionState::Active);
+        let compressed = session.capture_frame().await.unwrap();
+        assert!(session.play_frame(&compressed).await.is_ok());
+
+        session.deactivate().await.unwrap();
+        assert_eq!(session.state(), CallSessionState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_activate_from_idle_prepares_first() {
+        let mut session = mock_session();
+        session.activate().await.unwrap();
+        assert_eq!(session.state(), CallSessionState::Active);
+        assert!(session.capture_frame().await.is_ok());
+    }
+}
@@ -0,0 +1,199 @@
+//! Étirement temporel (WSOLA) pour ajuster le délai de lecture
+//!
+//! Quand le buffer de lecture doit grandir ou rétrécir, insérer du silence ou
+//! jeter une frame entière de 20ms s'entend nettement (trou, clic). WSOLA
+//! (Waveform Similarity Overlap-Add) change la durée d'un signal sans changer
+//! sa hauteur : le signal est découpé en segments qui se chevauchent, et
+//! chaque segment est choisi, autour de sa position nominale, là où il
+//! prolonge le mieux le précédent avant d'être recollé par fondu.
+//!
+//! Le début et la fin du signal étiré coïncident avec ceux de l'original : la
+//! frame précédente et la suivante se raccordent donc sans discontinuité.
+
+use crate::{AudioFrame, Sample};
+
+/// Découpage en segments : la fenêtre fait au plus ce quart de la longueur
+const WINDOW_DIVISOR: usize = 4;
+
+/// Étire ou compresse un signal mono à `output_len` échantillons, sans changer sa hauteur
+///
+/// # Example
+/// ```rust
+/// use audio::stretch::time_stretch;
+///
+/// let signal: Vec<f32> = (0..1920).map(|i| (i as f32 * 0.05).sin()).collect();
+/// let shorter = time_stretch(&signal, 960);
+/// assert_eq!(shorter.len(), 960);
+/// ```
+pub fn time_stretch(input: &[Sample], output_len: usize) -> Vec<Sample> {
+    let window = input.len().min(output_len) / WINDOW_DIVISOR;
+    let hop = window / 2;
+    if input.len() == output_len || hop == 0 {
+        return resample_linear(input, output_len);
+    }
+
+    // Fenêtre sin² : deux fenêtres décalées d'un demi-pas se somment à 1
+    let weights: Vec<f32> = (0..window)
+        .map(|i| (std::f32::consts::PI * (i as f32 + 0.5) / window as f32).sin().powi(2))
+        .collect();
+
+    let mut output = vec![0.0; output_len];
+    let mut norm = vec![0.0; output_len];
+    let last_input = input.len() - window;
+    let last_output = output_len - window;
+    let tolerance = hop;
+    let mut previous: Option<usize> = None;
+
+    let mut k = 0;
+    loop {
+        let out_pos = (k * hop).min(last_output);
+        let nominal = (out_pos as u64 * last_input as u64 / last_output.max(1) as u64) as usize;
+
+        // Premier et dernier segments fixés : raccord exact avec les frames voisines
+        let in_pos = match previous {
+            Some(previous) if out_pos < last_output => {
+                let natural = (previous + hop).min(input.len() - hop);
+                best_match(input, natural, nominal, tolerance, hop, last_input)
+            }
+            Some(_) => last_input,
+            None => 0,
+        };
+
+        for (i, weight) in weights.iter().enumerate() {
+            output[out_pos + i] += input[in_pos + i] * weight;
+            norm[out_pos + i] += weight;
+        }
+
+        if out_pos == last_output {
+            break;
+        }
+        previous = Some(in_pos);
+        k += 1;
+    }
+
+    for (sample, weight) in output.iter_mut().zip(&norm) {
+        *sample /= weight;
+    }
+    output
+}
+
+/// Remplace deux frames consécutives par une seule (le délai diminue d'une frame)
+///
+/// `channels` indique l'entrelacement des échantillons.
+pub fn compress_frames(first: &AudioFrame, second: &AudioFrame, channels: u16) -> AudioFrame {
+    let joined: Vec<Sample> = first.samples.iter().chain(&second.samples).copied().collect();
+    let samples = stretch_interleaved(&joined, first.samples.len(), channels);
+    AudioFrame {
+        samples,
+        timestamp: second.timestamp,
+        sequence_number: second.sequence_number,
+        media_timestamp: first.media_timestamp,
+        origin: first.origin,
+    }
+}
+
+/// Étire une frame sur la durée de deux (le délai augmente d'une frame)
+///
+/// `channels` indique l'entrelacement des échantillons.
+pub fn expand_frame(frame: &AudioFrame, channels: u16) -> Vec<Sample> {
+    stretch_interleaved(&frame.samples, frame.samples.len() * 2, channels)
+}
+
+/// Applique `time_stretch` canal par canal
+fn stretch_interleaved(input: &[Sample], output_len: usize, channels: u16) -> Vec<Sample> {
+    let channels = channels.max(1) as usize;
+    if channels == 1 {
+        return time_stretch(input, output_len);
+    }
+
+    let mut output = vec![0.0; output_len];
+    for channel in 0..channels {
+        let mono: Vec<Sample> = input.iter().skip(channel).step_by(channels).copied().collect();
+        let stretched = time_stretch(&mono, output_len / channels);
+        for (i, sample) in stretched.into_iter().enumerate() {
+            output[i * channels + channel] = sample;
+        }
+    }
+    output
+}
+
+/// Position autour de `nominal` dont le début ressemble le plus à `input[natural..]`
+fn best_match(input: &[Sample], natural: usize, nominal: usize, tolerance: usize, len: usize, max_pos: usize) -> usize {
+    let reference = &input[natural..natural + len];
+    let start = nominal.saturating_sub(tolerance);
+    let end = (nominal + tolerance).min(max_pos);
+
+    (start..=end)
+        .map(|pos| {
+            let candidate = &input[pos..pos + len];
+            let dot: f32 = reference.iter().zip(candidate).map(|(a, b)| a * b).sum();
+            let energy: f32 = candidate.iter().map(|s| s * s).sum();
+            (pos, dot / energy.sqrt().max(1e-6))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(pos, _)| pos)
+        .unwrap_or(nominal.min(max_pos))
+}
+
+/// Repli pour les signaux trop courts pour être découpés
+fn resample_linear(input: &[Sample], output_len: usize) -> Vec<Sample> {
+    if input.is_empty() {
+        return vec![0.0; output_len];
+    }
+    let step = (input.len() - 1) as f32 / (output_len.max(2) - 1) as f32;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f32 * step;
+            let index = position as usize;
+            let next = input[(index + 1).min(input.len() - 1)];
+            input[index] + (next - input[index]) * (position - index as f32)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, period: f32) -> Vec<Sample> {
+        (0..len).map(|i| (2.0 * std::f32::consts::PI * i as f32 / period).sin() * 0.5).collect()
+    }
+
+    /// Plus petite amplitude crête sur chaque période du signal
+    fn min_peak(samples: &[Sample], period: usize) -> f32 {
+        samples.chunks_exact(period)
+            .map(|chunk| chunk.iter().fold(0.0f32, |peak, s| peak.max(s.abs())))
+            .fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn test_stretch_keeps_length_ends_and_continuity() {
+        // Période de ~3,6ms à 48 kHz, sans rapport avec le découpage
+        let signal = sine(1920, 173.0);
+
+        for output_len in [960, 3840] {
+            let stretched = time_stretch(&signal, output_len);
+            assert_eq!(stretched.len(), output_len);
+            assert!((stretched[0] - signal[0]).abs() < 1e-4);
+            assert!((stretched[output_len - 1] - signal[1919]).abs() < 1e-4);
+            // Segments recollés en phase : pas de creux d'amplitude aux raccords
+            let peak = min_peak(&stretched, 173);
+            assert!(peak > 0.46, "{} échantillons : crête minimale {}", output_len, peak);
+        }
+    }
+
+    #[test]
+    fn test_frame_helpers() {
+        let first = AudioFrame::new(sine(960, 200.0), 1);
+        let second = AudioFrame::new(sine(960, 200.0), 2);
+
+        let merged = compress_frames(&first, &second, 1);
+        assert_eq!(merged.samples.len(), 960);
+        assert_eq!(merged.sequence_number, 2);
+
+        assert_eq!(expand_frame(&first, 1).len(), 1920);
+        // Stéréo : chaque canal est étiré séparément
+        let stereo = AudioFrame::new(vec![0.25; 1920], 3);
+        assert!(expand_frame(&stereo, 2).iter().all(|s| (s - 0.25).abs() < 1e-4));
+    }
+}
@@ -3,9 +3,18 @@
 //! Ce module définit les interfaces (traits) que doivent implémenter
 //! tous les composants audio. Cela permet d'avoir du code modulaire
 //! et testable avec différentes implémentations.
+//! 
+//! `AudioPipeline` et `MockAudioDevice` sont scellés : le crate en fournit
+//! les seules implémentations, et peut leur ajouter des méthodes sans
+//! casser le code des utilisateurs.
 
 use async_trait::async_trait;
-use crate::{AudioFrame, CompressedFrame, AudioError, AudioResult};
+use crate::{AudioFrame, CompressedFrame, AudioError, AudioResult, DeviceInfo};
+
+/// Supertrait des traits qui ne s'implémentent pas hors du crate
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
 
 /// Trait pour capturer l'audio depuis un périphérique d'entrée
 /// 
@@ -29,12 +38,45 @@ pub trait AudioCapture: Send + Sync {
     /// - `AudioError::InitializationError` : Échec de l'initialisation
     async fn start(&mut self) -> AudioResult<()>;
     
+    /// Ouvre le périphérique sans démarrer la capture (veille)
+    /// 
+    /// Le `start()` suivant n'a plus qu'à lancer le stream : appelé avant
+    /// qu'un appel soit accepté (voir `CallSession`), il évite de perdre le
+    /// début de l'appel. Sans effet par défaut.
+    async fn prepare(&mut self) -> AudioResult<()> {
+        Ok(())
+    }
+    
     /// Arrête la capture audio
     /// 
     /// Libère les ressources et ferme le périphérique.
     /// Après cet appel, `next_frame()` ne doit plus être utilisé.
     async fn stop(&mut self) -> AudioResult<()>;
     
+    /// Coupe le micro sans fermer le périphérique
+    /// 
+    /// Le stream reste ouvert : `next_frame()` continue de livrer des frames
+    /// au même rythme, mais silencieuses (le signal du micro est jeté), et
+    /// `resume()` rétablit le son immédiatement, là où `stop()` + `start()`
+    /// coûtent plusieurs centaines de millisecondes. `stop()` lève la pause.
+    /// 
+    /// Par défaut, équivaut à `stop()`.
+    async fn pause(&mut self) -> AudioResult<()> {
+        self.stop().await
+    }
+    
+    /// Rétablit le micro coupé par `pause()`
+    /// 
+    /// Par défaut, équivaut à `start()`.
+    async fn resume(&mut self) -> AudioResult<()> {
+        self.start().await
+    }
+    
+    /// Vérifie si le micro est coupé par `pause()`
+    fn is_paused(&self) -> bool {
+        false
+    }
+    
     /// Récupère la prochaine frame audio
     /// 
     /// Cette fonction bloque jusqu'à ce qu'une frame soit disponible.
@@ -74,6 +116,13 @@ pub trait AudioCapture: Send + Sync {
     fn device_info(&self) -> String {
         "Périphérique inconnu".to_string()
     }
+    
+    /// Retourne les paramètres négociés avec le périphérique d'entrée
+    /// 
+    /// Par défaut, seul le nom est connu.
+    fn device_details(&self) -> DeviceInfo {
+        DeviceInfo { name: self.device_info(), ..Default::default() }
+    }
 }
 
 /// Trait pour jouer l'audio sur un périphérique de sortie
@@ -87,21 +136,58 @@ pub trait AudioPlayback: Send + Sync {
     /// Initialise le périphérique de sortie et prépare les buffers.
     async fn start(&mut self) -> AudioResult<()>;
     
+    /// Ouvre le périphérique sans démarrer la lecture (veille)
+    /// 
+    /// Voir `AudioCapture::prepare`. Sans effet par défaut.
+    async fn prepare(&mut self) -> AudioResult<()> {
+        Ok(())
+    }
+    
     /// Arrête la lecture audio
     /// 
     /// Vide les buffers et ferme le périphérique.
     async fn stop(&mut self) -> AudioResult<()>;
     
+    /// Coupe le son sans fermer le périphérique
+    /// 
+    /// Le stream reste ouvert et joue du silence ; les frames en attente et
+    /// celles reçues pendant la pause (`play_frame`) sont jetées. `resume()`
+    /// rétablit le son immédiatement (voir `AudioCapture::pause`). `stop()`
+    /// lève la pause.
+    /// 
+    /// Par défaut, équivaut à `stop()`.
+    async fn pause(&mut self) -> AudioResult<()> {
+        self.stop().await
+    }
+    
+    /// Rétablit le son coupé par `pause()`
+    /// 
+    /// Par défaut, équivaut à `start()`.
+    async fn resume(&mut self) -> AudioResult<()> {
+        self.start().await
+    }
+    
+    /// Vérifie si le son est coupé par `pause()`
+    fn is_paused(&self) -> bool {
+        false
+    }
+    
     /// Met une frame en queue pour lecture
     /// 
     /// La frame sera jouée dans l'ordre d'arrivée.
-    /// Si le buffer est plein, la frame peut être rejetée.
+    /// Si le buffer est plein, une frame est jetée selon
+    /// `AudioConfig::overflow_policy`.
     /// 
     /// # Arguments
     /// * `frame` - La frame audio à jouer
     /// 
+    /// # Retour
+    /// Nombre de frames jetées pour faire de la place (0 si le buffer
+    /// n'était pas plein)
+    /// 
     /// # Erreurs
     /// - `AudioError::BufferOverflow` : Buffer plein, frame rejetée
+    ///   (`OverflowPolicy::Error`)
     /// - `AudioError::DeviceDisconnected` : Haut-parleurs débranchés
     /// 
     /// # Example
@@ -119,7 +205,7 @@ pub trait AudioPlayback: Send + Sync {
     /// # Ok(())
     /// # }
     /// ```
-    async fn play_frame(&mut self, frame: AudioFrame) -> AudioResult<()>;
+    async fn play_frame(&mut self, frame: AudioFrame) -> AudioResult<usize>;
     
     /// Vérifie si la lecture est active
     fn is_playing(&self) -> bool;
@@ -143,6 +229,13 @@ pub trait AudioPlayback: Send + Sync {
     fn device_info(&self) -> String {
         "Périphérique de sortie inconnu".to_string()
     }
+    
+    /// Retourne les paramètres négociés avec le périphérique de sortie
+    /// 
+    /// Par défaut, seul le nom est connu.
+    fn device_details(&self) -> DeviceInfo {
+        DeviceInfo { name: self.device_info(), ..Default::default() }
+    }
 }
 
 /// Trait pour encoder/décoder l'audio avec un codec
@@ -218,6 +311,58 @@ pub trait AudioCodec: Send + Sync {
     /// ```
     fn decode(&mut self, compressed: &CompressedFrame) -> AudioResult<AudioFrame>;
     
+    /// Encode une frame dans un buffer fourni par l'appelant
+    /// 
+    /// `output` est remplacé par les données compressées. Réutilisé d'une
+    /// frame à l'autre, il évite l'allocation que fait `encode` toutes les
+    /// 20ms. L'implémentation par défaut délègue à `encode`.
+    /// 
+    /// # Erreurs
+    /// Celles de `encode`
+    fn encode_into(&mut self, frame: &AudioFrame, output: &mut Vec<u8>) -> AudioResult<()> {
+        *output = self.encode(frame)?.data;
+        Ok(())
+    }
+    
+    /// Décode une frame dans un buffer fourni par l'appelant
+    /// 
+    /// `output` est remplacé par les échantillons décodés, voir `encode_into`.
+    /// L'implémentation par défaut délègue à `decode`.
+    /// 
+    /// # Erreurs
+    /// Celles de `decode`
+    fn decode_into(&mut self, compressed: &CompressedFrame, output: &mut Vec<f32>) -> AudioResult<()> {
+        *output = self.decode(compressed)?.samples;
+        Ok(())
+    }
+    
+    /// Produit une frame de remplacement pour une frame perdue (PLC)
+    /// 
+    /// Un codec capable d'extrapoler l'audio à partir de son état retourne
+    /// une frame `FrameOrigin::Concealed`. L'implémentation par défaut
+    /// retourne du silence (`FrameOrigin::Silence`).
+    /// 
+    /// # Arguments
+    /// * `sample_count` - Nombre d'échantillons de la frame manquante
+    /// * `sequence_number` - Numéro de séquence de la frame manquante
+    fn conceal(&mut self, sample_count: usize, sequence_number: u64) -> AudioResult<AudioFrame> {
+        Ok(AudioFrame::silence(sample_count, sequence_number))
+    }
+    
+    /// Reconstruit une frame perdue depuis la redondance (FEC) de la suivante
+    /// 
+    /// `next` est la frame reçue juste après le trou ; elle doit encore être
+    /// décodée normalement ensuite. Si elle ne porte pas de redondance, ou
+    /// si le codec ne sait pas l'exploiter, la frame est masquée comme par
+    /// `conceal` (ce que fait l'implémentation par défaut). L'origine de la
+    /// frame retournée indique ce qui a été fait.
+    /// 
+    /// # Erreurs
+    /// Celles de `decode`
+    fn decode_fec(&mut self, next: &CompressedFrame, sequence_number: u64) -> AudioResult<AudioFrame> {
+        self.conceal(next.original_sample_count, sequence_number)
+    }
+    
     /// Réinitialise l'état interne du codec
     /// 
     /// Utile après une coupure réseau ou pour débuter une nouvelle session.
@@ -234,8 +379,9 @@ pub trait AudioCodec: Send + Sync {
 /// 
 /// Ce trait combine capture, codec et playback pour des tests end-to-end.
 /// Il permet de tester tout le système audio sans réseau.
+/// Trait scellé : seul `AudioPipelineImpl` l'implémente.
 #[async_trait]
-pub trait AudioPipeline: Send + Sync {
+pub trait AudioPipeline: sealed::Sealed + Send + Sync {
     /// Démarre le pipeline complet
     /// 
     /// Initialise capture, codec et playback.
@@ -253,10 +399,17 @@ pub trait AudioPipeline: Send + Sync {
     /// 
     /// # Arguments
     /// * `duration_seconds` - Durée du test
+    /// * `test_tone` - Signal joué à la place du micro, retrouvé dans la
+    ///   capture pour mesurer latence acoustique, distorsion et décrochages
+    ///   (voir `LoopbackAnalyzer`)
     /// 
     /// # Returns
-    /// Statistiques sur le test effectué
-    async fn run_loopback_test(&mut self, duration_seconds: u32) -> AudioResult<crate::AudioStats>;
+    /// Statistiques et mesures du test effectué
+    async fn run_loopback_test(
+        &mut self,
+        duration_seconds: u32,
+        test_tone: Option<crate::TestTone>,
+    ) -> AudioResult<crate::LoopbackReport>;
     
     /// Traite une frame complète (capture → encode → decode → lecture)
     /// 
@@ -286,7 +439,8 @@ pub trait AudioMonitor: Send + Sync {
 /// 
 /// Permet de créer des implémentations de test qui simulent
 /// des périphériques audio sans avoir besoin de hardware.
-pub trait MockAudioDevice: Send + Sync {
+/// Trait scellé : implémenté par `MockCapture` et `MockPlayback`.
+pub trait MockAudioDevice: sealed::Sealed + Send + Sync {
     /// Configure le dispositif factice avec des données de test
     fn set_test_data(&mut self, frames: Vec<AudioFrame>);
     
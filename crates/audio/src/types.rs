@@ -18,6 +18,27 @@ use serde::{Deserialize, Serialize};
 /// - f32 est suffisant pour la qualité audio (24 bits effectifs)
 pub type Sample = f32;
 
+/// Provenance des échantillons d'une frame jouée
+/// 
+/// Distingue l'audio réellement reçu de ce que le récepteur a dû
+/// reconstruire quand une frame manquait, pour que le monitoring puisse
+/// dire quelle part de l'audio joué a été masquée.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FrameOrigin {
+    /// Frame capturée ou décodée normalement
+    #[default]
+    Normal,
+    
+    /// Frame reconstruite depuis la redondance (FEC) portée par la suivante
+    Fec,
+    
+    /// Frame extrapolée par le décodeur (PLC)
+    Concealed,
+    
+    /// Silence inséré faute de mieux
+    Silence,
+}
+
 /// Frame d'audio brute (non compressée)
 /// 
 /// Une frame contient un petit morceau d'audio (typiquement 20ms).
@@ -47,6 +68,17 @@ pub struct AudioFrame {
     /// Incrémenté pour chaque frame envoyée.
     /// Permet de détecter si des frames sont perdues sur le réseau.
     pub sequence_number: u64,
+    
+    /// Position du premier échantillon sur l'horloge de capture, en
+    /// échantillons par canal depuis le démarrage du stream (None si inconnue)
+    /// 
+    /// Transmise de bout en bout (voir `CompressedFrame::media_timestamp`),
+    /// elle place la frame sur la ligne de temps de l'émetteur : une vidéo
+    /// ou un rendu externe s'y synchronise, quelle que soit la latence.
+    pub media_timestamp: Option<u64>,
+    
+    /// Provenance des échantillons (reçus, reconstruits ou silence)
+    pub origin: FrameOrigin,
 }
 
 impl AudioFrame {
@@ -68,6 +100,8 @@ impl AudioFrame {
             samples,
             timestamp: Instant::now(),
             sequence_number,
+            media_timestamp: None,
+            origin: FrameOrigin::Normal,
         }
     }
     
@@ -75,7 +109,30 @@ impl AudioFrame {
     /// 
     /// Utile pour combler les trous quand on perd des frames réseau
     pub fn silence(sample_count: usize, sequence_number: u64) -> Self {
-        Self::new(vec![0.0; sample_count], sequence_number)
+        Self {
+            origin: FrameOrigin::Silence,
+            ..Self::new(vec![0.0; sample_count], sequence_number)
+        }
+    }
+    
+    /// Vrai si la frame n'a pas été reçue mais reconstruite (FEC, PLC ou silence)
+    pub fn is_reconstructed(&self) -> bool {
+        self.origin != FrameOrigin::Normal
+    }
+    
+    /// Position de la frame sur la ligne de temps de l'émetteur
+    /// 
+    /// # Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use audio::AudioFrame;
+    /// 
+    /// let mut frame = AudioFrame::silence(960, 3);
+    /// frame.media_timestamp = Some(96_000);
+    /// assert_eq!(frame.media_time(48000), Some(Duration::from_secs(2)));
+    /// ```
+    pub fn media_time(&self, sample_rate: u32) -> Option<std::time::Duration> {
+        media_time(self.media_timestamp, sample_rate)
     }
     
     /// Calcule la durée de cette frame en millisecondes
@@ -143,6 +200,53 @@ impl AudioFrame {
     }
 }
 
+/// Métadonnées d'activité vocale, calculées à l'émission sur la frame brute
+/// 
+/// Permettent au récepteur (ou à un mixeur) d'afficher qui parle, de
+/// privilégier les locuteurs actifs et de ne pas décoder le silence.
+/// Le niveau suit la convention RTP (RFC 6464) : en -dBov, de 0 (pleine
+/// échelle) à 127 (silence).
+/// 
+/// # Example
+/// ```rust
+/// use audio::{AudioFrame, FrameMetadata};
+/// 
+/// let metadata = FrameMetadata::from_frame(&AudioFrame::new(vec![0.5, -0.5], 1));
+/// assert!(metadata.is_speech);
+/// assert_eq!(metadata.level_dbov, 6);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FrameMetadata {
+    /// La frame contient de la parole (détection d'activité vocale)
+    pub is_speech: bool,
+    
+    /// Niveau RMS en -dBov (0 = pleine échelle, 127 = silence)
+    pub level_dbov: u8,
+}
+
+impl FrameMetadata {
+    /// Niveau le plus faible représentable (silence)
+    pub const SILENCE_DBOV: u8 = 127;
+    
+    /// En dessous de -45 dBov, la frame est considérée comme du silence
+    pub const SPEECH_THRESHOLD_DBOV: u8 = 45;
+    
+    /// Calcule le niveau et l'activité vocale d'une frame brute
+    pub fn from_frame(frame: &AudioFrame) -> Self {
+        let rms = frame.rms_level();
+        let level_dbov = if rms > 0.0 {
+            (-20.0 * rms.log10()).clamp(0.0, Self::SILENCE_DBOV as f32).round() as u8
+        } else {
+            Self::SILENCE_DBOV
+        };
+        
+        Self {
+            is_speech: level_dbov < Self::SPEECH_THRESHOLD_DBOV,
+            level_dbov,
+        }
+    }
+}
+
 /// Frame d'audio compressée avec Opus
 /// 
 /// Après compression, l'audio prend beaucoup moins de place :
@@ -167,6 +271,19 @@ pub struct CompressedFrame {
     
     /// Numéro de séquence de la frame originale
     pub sequence_number: u64,
+    
+    /// Activité vocale de la frame d'origine (None si inconnue)
+    /// 
+    /// Transportée dans l'en-tête réseau, pas dans la sérialisation
+    #[serde(skip)]
+    pub metadata: Option<FrameMetadata>,
+    
+    /// Position de la frame d'origine sur l'horloge de capture (voir
+    /// `AudioFrame::media_timestamp`)
+    /// 
+    /// Transportée à la suite du payload réseau, pas dans la sérialisation
+    #[serde(skip)]
+    pub media_timestamp: Option<u64>,
 }
 
 impl Default for CompressedFrame {
@@ -176,6 +293,8 @@ impl Default for CompressedFrame {
             original_sample_count: 0,
             timestamp: Instant::now(),
             sequence_number: 0,
+            metadata: None,
+            media_timestamp: None,
         }
     }
 }
@@ -193,9 +312,24 @@ impl CompressedFrame {
             original_sample_count,
             timestamp,
             sequence_number,
+            metadata: None,
+            media_timestamp: None,
         }
     }
     
+    /// Position de la frame sur la ligne de temps de l'émetteur
+    pub fn media_time(&self, sample_rate: u32) -> Option<std::time::Duration> {
+        media_time(self.media_timestamp, sample_rate)
+    }
+    
+    /// Vrai si la frame contient de la parole, ou si on ne le sait pas
+    /// 
+    /// Un récepteur peut se dispenser de décoder les frames pour lesquelles
+    /// l'émetteur a signalé du silence.
+    pub fn is_speech(&self) -> bool {
+        self.metadata.is_none_or(|metadata| metadata.is_speech)
+    }
+    
     /// Calcule le ratio de compression obtenu
     /// 
     /// Exemple : ratio de 20.0 = la frame compressée fait 20x moins que l'originale
@@ -220,10 +354,20 @@ impl CompressedFrame {
     }
 }
 
+/// Convertit une position en échantillons en durée depuis le début du stream
+fn media_time(media_timestamp: Option<u64>, sample_rate: u32) -> Option<std::time::Duration> {
+    let sample_rate = sample_rate.max(1) as u64;
+    media_timestamp.map(|samples| {
+        std::time::Duration::from_secs(samples / sample_rate)
+            + std::time::Duration::from_nanos((samples % sample_rate) * 1_000_000_000 / sample_rate)
+    })
+}
+
 /// Statistiques audio pour le monitoring
 /// 
 /// Permet de surveiller la qualité et les performances du système audio
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct AudioStats {
     /// Nombre de frames capturées
     pub frames_captured: u64,
@@ -240,12 +384,52 @@ pub struct AudioStats {
     /// Latence moyenne mesurée (ms)
     pub avg_latency_ms: f32,
     
+    /// Distribution de la latence de bout en bout (capture → lecture)
+    /// 
+    /// Les coupures audibles viennent des pics, que la moyenne lisse :
+    /// `latency.p99()` et `latency.max()` les révèlent.
+    pub latency: LatencyHistogram,
+    
+    /// Distribution des temps d'encodage et de décodage d'une frame
+    pub encode_time: LatencyHistogram,
+    pub decode_time: LatencyHistogram,
+    
     /// Ratio de compression moyen
     pub avg_compression_ratio: f32,
     
     /// Nombre de buffer overflows/underruns
+    /// 
+    /// `buffer_overflows` compte les frames jetées par le buffer de lecture
+    /// (voir `OverflowPolicy`), une par frame.
     pub buffer_overflows: u64,
     pub buffer_underruns: u64,
+    
+    /// Corrections de cadence du pipeline : deux frames capturées fusionnées
+    /// en une (capture en avance sur la lecture), ou une frame étirée sur
+    /// deux (capture en retard)
+    pub pacing_merges: u64,
+    pub pacing_expansions: u64,
+
+    /// Temps d'attente des frames dans les files entre étapes : avant le
+    /// codec, puis avant la lecture (pipeline en tâches séparées)
+    pub encode_queue_time: LatencyHistogram,
+    pub playback_queue_time: LatencyHistogram,
+
+    /// Frames jetées par les files entre étapes (voir `queue::bounded`)
+    pub queue_overflows: u64,
+
+    /// Étapes du pipeline redémarrées après une erreur
+    pub stage_restarts: u64,
+    
+    /// Frames jouées qui n'ont pas été reçues : reconstruites par FEC,
+    /// extrapolées par le décodeur, ou remplacées par du silence
+    /// (voir `FrameOrigin`)
+    #[serde(default)]
+    pub frames_fec: u64,
+    #[serde(default)]
+    pub frames_concealed: u64,
+    #[serde(default)]
+    pub frames_silence: u64,
 }
 
 impl AudioStats {
@@ -261,6 +445,173 @@ impl AudioStats {
         }
         (self.frames_lost as f32 / self.frames_captured as f32) * 100.0
     }
+    
+    /// Comptabilise la provenance d'une frame jouée
+    pub fn record_origin(&mut self, origin: FrameOrigin) {
+        match origin {
+            FrameOrigin::Normal => {}
+            FrameOrigin::Fec => self.frames_fec += 1,
+            FrameOrigin::Concealed => self.frames_concealed += 1,
+            FrameOrigin::Silence => self.frames_silence += 1,
+        }
+    }
+    
+    /// Pourcentage de l'audio joué qui a été masqué (PLC ou silence)
+    /// 
+    /// Les frames reconstruites par FEC n'en font pas partie : elles
+    /// restituent l'audio d'origine, à une qualité dégradée.
+    pub fn concealed_percentage(&self) -> f32 {
+        self.percentage_of_played(self.frames_concealed + self.frames_silence)
+    }
+    
+    /// Pourcentage de l'audio joué reconstruit par FEC
+    pub fn fec_percentage(&self) -> f32 {
+        self.percentage_of_played(self.frames_fec)
+    }
+    
+    fn percentage_of_played(&self, frames: u64) -> f32 {
+        if self.frames_played == 0 {
+            return 0.0;
+        }
+        (frames as f32 / self.frames_played as f32) * 100.0
+    }
+}
+
+/// Nombre de classes de `LatencyHistogram` : la dernière couvre 2^23µs (~8s) et plus
+pub const LATENCY_BUCKETS: usize = 24;
+
+/// Histogramme de durées à classes logarithmiques (puissances de 2 en µs)
+/// 
+/// Taille fixe et insertion en temps constant, pour un enregistrement à
+/// chaque frame ; le minimum et le maximum sont exacts, les centiles
+/// précis à un facteur 2 près.
+/// 
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use audio::LatencyHistogram;
+/// 
+/// let mut latency = LatencyHistogram::default();
+/// for _ in 0..99 {
+///     latency.record(Duration::from_millis(20));
+/// }
+/// latency.record(Duration::from_millis(150));
+/// assert_eq!(latency.min(), Some(Duration::from_millis(20)));
+/// assert!(latency.p95().unwrap() < Duration::from_millis(50));
+/// assert_eq!(latency.max(), Some(Duration::from_millis(150)));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// Nombre de mesures par classe (voir `bucket_upper_bound`)
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+    min_us: u64,
+    max_us: u64,
+}
+
+impl LatencyHistogram {
+    /// Ajoute une mesure
+    pub fn record(&mut self, duration: std::time::Duration) {
+        let micros = duration.as_micros().min(u64::MAX as u128) as u64;
+        let index = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[index.min(LATENCY_BUCKETS - 1)] += 1;
+        self.min_us = if self.count == 0 { micros } else { self.min_us.min(micros) };
+        self.max_us = self.max_us.max(micros);
+        self.count += 1;
+    }
+    
+    /// Nombre de mesures
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    
+    /// Plus courte durée mesurée, `None` sans mesure
+    pub fn min(&self) -> Option<std::time::Duration> {
+        (self.count > 0).then(|| std::time::Duration::from_micros(self.min_us))
+    }
+    
+    /// Plus longue durée mesurée, `None` sans mesure
+    pub fn max(&self) -> Option<std::time::Duration> {
+        (self.count > 0).then(|| std::time::Duration::from_micros(self.max_us))
+    }
+    
+    /// Borne haute de la classe contenant le centile `percentile` (0-100),
+    /// bornée par le maximum ; `None` sans mesure
+    pub fn percentile(&self, percentile: f32) -> Option<std::time::Duration> {
+        let max = self.max()?;
+        let rank = ((self.count as f64 * percentile.clamp(0.0, 100.0) as f64 / 100.0).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank && index + 1 < LATENCY_BUCKETS {
+                return Some(Self::bucket_upper_bound(index).min(max));
+            }
+        }
+        Some(max)
+    }
+    
+    /// 95e centile, `None` sans mesure
+    pub fn p95(&self) -> Option<std::time::Duration> {
+        self.percentile(95.0)
+    }
+    
+    /// 99e centile, `None` sans mesure
+    pub fn p99(&self) -> Option<std::time::Duration> {
+        self.percentile(99.0)
+    }
+    
+    /// Borne haute (exclue) de la classe `index` : la classe 0 ne contient
+    /// que les mesures nulles, la classe `i` les mesures de 2^(i-1) à 2^i µs
+    pub fn bucket_upper_bound(index: usize) -> std::time::Duration {
+        std::time::Duration::from_micros(1 << index)
+    }
+}
+
+/// Paramètres d'un périphérique audio tels que négociés avec le matériel
+/// 
+/// Ce que fait réellement la carte son, pour l'affichage et les logs :
+/// le format ou le nombre de canaux du périphérique peuvent différer de
+/// `AudioConfig`. Une valeur numérique à 0 signifie « inconnue ».
+/// 
+/// # Example
+/// ```rust
+/// use audio::{AudioCapture, MockCapture};
+/// 
+/// let capture = MockCapture::new(audio::AudioConfig::default());
+/// let info = capture.device_details();
+/// assert_eq!(info.sample_rate, 48000);
+/// println!("{}", info);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    /// Nom du périphérique
+    pub name: String,
+    
+    /// Fréquence d'échantillonnage du stream, en Hz
+    pub sample_rate: u32,
+    
+    /// Nombre de canaux du stream
+    pub channels: u16,
+    
+    /// Format des échantillons côté périphérique ("f32", "i16"...)
+    pub sample_format: String,
+    
+    /// Frames par callback accordées (`None` avant le premier callback)
+    pub buffer_size: Option<u32>,
+    
+    /// API audio de l'hôte ("ALSA", "WASAPI", "CoreAudio"...)
+    pub host_api: String,
+}
+
+impl std::fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}, {} Hz, {} canal(aux), {}",
+               self.name, self.host_api, self.sample_rate, self.channels, self.sample_format)?;
+        match self.buffer_size {
+            Some(frames) => write!(f, ", buffer {} frames)", frames),
+            None => write!(f, ")"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -286,6 +637,22 @@ mod tests {
         assert!(!noisy.is_silence(0.01));
     }
     
+    #[test]
+    fn test_frame_metadata() {
+        let silent = FrameMetadata::from_frame(&AudioFrame::silence(960, 1));
+        assert_eq!(silent, FrameMetadata { is_speech: false, level_dbov: FrameMetadata::SILENCE_DBOV });
+        
+        // Bruit de fond à -60 dBov : pas de la parole
+        let noise = FrameMetadata::from_frame(&AudioFrame::new(vec![0.001, -0.001], 2));
+        assert_eq!(noise.level_dbov, 60);
+        assert!(!noise.is_speech);
+        
+        let mut frame = CompressedFrame::new(vec![1], 960, Instant::now(), 3);
+        assert!(frame.is_speech());
+        frame.metadata = Some(noise);
+        assert!(!frame.is_speech());
+    }
+    
     #[test]
     fn test_rms_calculation() {
         // Frame avec échantillons connus
@@ -327,4 +694,40 @@ mod tests {
         
         assert_eq!(stats.loss_percentage(), 5.0);
     }
+    
+    #[test]
+    fn test_stats_frame_origins() {
+        assert_eq!(AudioFrame::new(vec![0.1], 1).origin, FrameOrigin::Normal);
+        assert!(AudioFrame::silence(960, 2).is_reconstructed());
+        
+        let mut stats = AudioStats::default();
+        assert_eq!(stats.concealed_percentage(), 0.0);
+        stats.frames_played = 200;
+        for origin in [FrameOrigin::Normal, FrameOrigin::Fec, FrameOrigin::Concealed, FrameOrigin::Concealed, FrameOrigin::Silence] {
+            stats.record_origin(origin);
+        }
+        
+        assert_eq!(stats.frames_fec, 1);
+        assert_eq!(stats.concealed_percentage(), 1.5);
+        assert_eq!(stats.fec_percentage(), 0.5);
+    }
+    
+    #[test]
+    fn test_latency_histogram_tails() {
+        let mut latency = LatencyHistogram::default();
+        assert_eq!(latency.p99(), None);
+        
+        for _ in 0..98 {
+            latency.record(std::time::Duration::from_micros(3));
+        }
+        latency.record(std::time::Duration::from_micros(900));
+        latency.record(std::time::Duration::from_secs(60));
+        
+        assert_eq!(latency.count(), 100);
+        assert_eq!(latency.min(), Some(std::time::Duration::from_micros(3)));
+        assert_eq!(latency.p95(), Some(std::time::Duration::from_micros(4)));
+        assert_eq!(latency.percentile(99.0), Some(std::time::Duration::from_micros(1024)));
+        // La dernière classe est ouverte : le centile est le maximum exact
+        assert_eq!(latency.percentile(100.0), Some(std::time::Duration::from_secs(60)));
+    }
 }
@@ -0,0 +1,264 @@
+//! Tests d'intégration de la pile complète
+//!
+//! `run_call` passe un appel simulé de bout en bout, avec les vrais
+//! composants de chaque crate : `MockCapture` → `OpusCodec` →
+//! `UdpNetworkManager` (paire de `SimulatedTransport`) → `OpusCodec` →
+//! `MockPlayback`. Contrairement à l'auto-diagnostic
+//! (`UdpNetworkManager::self_test`), l'appel est soumis à la gigue et le
+//! rapport détaille la latence de chaque frame jouée.
+//!
+//! Les tests (`tests/`) déroulent l'appel sous une horloge tokio suspendue
+//! (`#[tokio::test(start_paused = true)]`) : dix secondes d'appel
+//! s'exécutent en une fraction de seconde, indépendamment de la charge de
+//! la machine de CI, et les latences sont mesurées en temps virtuel.
+//!
+//! Avec `CallParams::conceal_losses`, l'appelé comble les frames perdues
+//! comme à la lecture (FEC ou extrapolation par le décodeur) : le rapport
+//! compte alors les frames reconstruites selon leur `FrameOrigin`.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use audio::{AudioCapture, AudioCodec, AudioConfig, AudioPlayback, FrameOrigin, MockCapture, MockPlayback, OpusCodec};
+use network::{
+    NetworkConfig, NetworkManager, NetworkResult, NetworkStats, SimulatedTransport, StreamDescription, UdpNetworkManager, utils,
+};
+
+/// Attente des derniers paquets après la fin de l'émission
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Conditions de l'appel simulé
+#[derive(Clone, Debug)]
+pub struct CallParams {
+    /// Durée d'émission
+    pub duration: Duration,
+
+    /// Taux de perte sur le trajet appelant → appelé (0.0 à 1.0)
+    pub loss_rate: f32,
+
+    /// Latence de base du trajet
+    pub latency_ms: u32,
+
+    /// Gigue ajoutée à chaque paquet, tirée entre 0 et `jitter_ms`
+    pub jitter_ms: u32,
+
+    /// Configuration réseau des deux pairs
+    pub network: NetworkConfig,
+
+    /// Configuration audio (capture et codecs)
+    pub audio: AudioConfig,
+
+    /// Joue une frame reconstruite à la place de chaque frame perdue
+    ///
+    /// Suppose une livraison dans l'ordre (appel sans gigue) : une frame
+    /// arrivée après une plus récente n'est pas retirée de la lecture.
+    pub conceal_losses: bool,
+}
+
+impl Default for CallParams {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(10),
+            loss_rate: 0.0,
+            latency_ms: 20,
+            jitter_ms: 0,
+            network: NetworkConfig::wan_optimized(),
+            audio: AudioConfig::default(),
+            conceal_losses: false,
+        }
+    }
+}
+
+/// Résultat de l'appel simulé
+#[derive(Clone, Debug)]
+pub struct CallReport {
+    /// Frames capturées, encodées et envoyées par l'appelant
+    pub frames_sent: u64,
+
+    /// Frames jouées par l'appelé, reçues ou reconstruites
+    pub frames_played: u64,
+
+    /// Frames perdues reconstruites depuis la redondance de la suivante
+    pub frames_fec: u64,
+
+    /// Frames perdues extrapolées par le décodeur ou remplacées par du silence
+    pub frames_concealed: u64,
+
+    /// Numéros de séquence reçus, dans l'ordre de livraison
+    pub sequences: Vec<u64>,
+
+    /// Délai entre la capture et la lecture de chaque frame jouée
+    pub latencies: Vec<Duration>,
+
+    /// Erreurs de décodage ou frames décodées de taille incorrecte
+    pub codec_errors: Vec<String>,
+
+    /// Statistiques réseau de l'appelant en fin d'appel
+    pub caller_stats: NetworkStats,
+
+    /// Statistiques réseau de l'appelé en fin d'appel
+    pub callee_stats: NetworkStats,
+}
+
+impl CallReport {
+    /// Latence moyenne des frames jouées
+    pub fn mean_latency(&self) -> Duration {
+        let total: Duration = self.latencies.iter().sum();
+        total / self.latencies.len().max(1) as u32
+    }
+
+    /// Latence maximale des frames jouées
+    pub fn max_latency(&self) -> Duration {
+        self.latencies.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Frames envoyées jamais reçues (numéros de séquence absents)
+    pub fn frames_lost(&self) -> u64 {
+        let mut received = self.sequences.clone();
+        received.sort_unstable();
+        received.dedup();
+        self.frames_sent.saturating_sub(received.len() as u64)
+    }
+
+    /// Vrai si les frames ont été livrées dans l'ordre, sans doublon
+    ///
+    /// La gigue réordonne les paquets et le manager les livre dans leur
+    /// ordre d'arrivée : seul un appel sans gigue est garanti dans l'ordre.
+    pub fn in_order(&self) -> bool {
+        self.sequences.windows(2).all(|pair| pair[0] < pair[1])
+    }
+}
+
+/// Déroule un appel simulé entre deux managers selon `params`
+///
+/// L'appelant envoie une frame de sinusoïde toutes les
+/// `AudioConfig::frame_duration_ms` pendant `params.duration` ; l'appelé
+/// reçoit, décode et joue jusqu'à ce que plus rien n'arrive.
+///
+/// # Erreurs
+/// Les erreurs de connexion, d'envoi ou de réception. Les erreurs de
+/// décodage sont seulement relevées dans `CallReport::codec_errors`.
+pub async fn run_call(params: &CallParams) -> NetworkResult<CallReport> {
+    let mut encoder = OpusCodec::new(params.audio.clone())?;
+    let mut decoder = OpusCodec::new(params.audio.clone())?;
+
+    let (mut caller_transport, callee_transport) = SimulatedTransport::pair(params.network.clone())?;
+    caller_transport.set_simulation_params(params.latency_ms, params.loss_rate, params.jitter_ms);
+    let mut caller = UdpNetworkManager::with_transport(params.network.clone(), Box::new(caller_transport))?;
+    let mut callee = UdpNetworkManager::with_transport(params.network.clone(), Box::new(callee_transport))?;
+
+    let (caller_port, callee_port) = (9001, 9002);
+    let (dialed, accepted) = tokio::join!(
+        caller.open(caller_port, Some(utils::localhost(callee_port))),
+        callee.open(callee_port, None),
+    );
+    dialed?;
+    accepted?;
+    caller.set_stream_description(StreamDescription::from_config(&params.audio));
+
+    let mut capture = MockCapture::new(params.audio.clone());
+    let mut playback = MockPlayback::new();
+    capture.start().await?;
+    playback.start().await?;
+
+    let mut expected_samples = params.audio.samples_per_frame() * params.audio.channels as usize;
+    // Instant de capture de chaque frame : le manager numérote les frames
+    // envoyées à partir de 1, dans l'ordre d'envoi
+    let mut captured_at = Vec::new();
+    let mut sequences = Vec::new();
+    let mut played_at = Vec::new();
+    let mut codec_errors = Vec::new();
+    let mut reconstructed = Vec::new();
+
+    let deadline = Instant::now() + params.duration;
+    let send_side = async {
+        while Instant::now() < deadline {
+            let frame = capture.next_frame().await?;
+            captured_at.push(Instant::now());
+            caller.send_audio(encoder.encode(&frame)?).await?;
+        }
+        NetworkResult::Ok(())
+    };
+    let receive_side = async {
+        let mut next_sequence = 1;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now()) + DRAIN_TIMEOUT;
+            let compressed = match tokio::time::timeout(wait, callee.receive_audio()).await {
+                Ok(result) => result?,
+                Err(_) => break,
+            };
+            sequences.push(compressed.sequence_number);
+            if callee.take_stream_resync().is_some() {
+                decoder.reset()?;
+            }
+            // Le pair a décrit son flux après des échecs de décodage répétés
+            if let Some(description) = callee.take_decoder_reconfigure() {
+                let mut config = params.audio.clone();
+                description.apply_to(&mut config);
+                expected_samples = config.samples_per_frame() * config.channels as usize;
+                decoder = OpusCodec::new(config)?;
+            }
+            // Frames manquantes : extrapolées, sauf la dernière, reconstruite
+            // depuis la redondance du paquet reçu
+            if params.conceal_losses {
+                for missing in next_sequence..compressed.sequence_number {
+                    let frame = if missing + 1 == compressed.sequence_number {
+                        decoder.decode_fec(&compressed, missing)
+                    } else {
+                        decoder.conceal(expected_samples, missing)
+                    };
+                    match frame {
+                        Ok(frame) => {
+                            reconstructed.push(frame.origin);
+                            playback.play_frame(frame).await?;
+                        }
+                        Err(e) => codec_errors.push(format!("Masquage de la frame {} : {}", missing, e)),
+                    }
+                }
+                next_sequence = next_sequence.max(compressed.sequence_number + 1);
+            }
+            let decoded = decoder.decode(&compressed);
+            let playable = matches!(&decoded, Ok(frame) if frame.samples.len() == expected_samples);
+            callee.report_decode_result(playable).await?;
+            match decoded {
+                Ok(frame) if frame.samples.len() == expected_samples => {
+                    playback.play_frame(frame).await?;
+                    played_at.push((compressed.sequence_number, Instant::now()));
+                }
+                Ok(frame) => codec_errors.push(format!(
+                    "Frame {} décodée avec {} échantillons au lieu de {}",
+                    compressed.sequence_number, frame.samples.len(), expected_samples
+                )),
+                Err(e) => codec_errors.push(format!("Décodage de la frame {} : {}", compressed.sequence_number, e)),
+            }
+        }
+        NetworkResult::Ok(())
+    };
+    let (sent, received) = tokio::join!(send_side, receive_side);
+    sent?;
+    received?;
+
+    let latencies = played_at.into_iter()
+        .filter_map(|(sequence, played)| {
+            let captured = captured_at.get((sequence as usize).checked_sub(1)?)?;
+            Some(played.duration_since(*captured))
+        })
+        .collect();
+    let count = |origins: &[FrameOrigin]| reconstructed.iter().filter(|origin| origins.contains(origin)).count() as u64;
+    let report = CallReport {
+        frames_sent: captured_at.len() as u64,
+        frames_played: playback.played_frames().len() as u64,
+        frames_fec: count(&[FrameOrigin::Fec]),
+        frames_concealed: count(&[FrameOrigin::Concealed, FrameOrigin::Silence]),
+        sequences,
+        latencies,
+        codec_errors,
+        caller_stats: caller.network_stats(),
+        callee_stats: callee.network_stats(),
+    };
+
+    caller.disconnect().await?;
+    callee.disconnect().await?;
+    Ok(report)
+}
@@ -0,0 +1,83 @@
+//! Appels complets simulés : capture → Opus → réseau → Opus → lecture
+//!
+//! Horloge tokio suspendue : dix secondes d'appel en temps virtuel, même
+//! nombre de frames quelle que soit la charge de la machine.
+
+use std::time::Duration;
+
+use integration::{CallParams, CallReport, run_call};
+
+/// Frames émises pendant `params.duration`
+fn expected_frames(params: &CallParams) -> u64 {
+    params.duration.as_millis() as u64 / params.audio.frame_duration_ms as u64
+}
+
+/// Vérifications communes : rien de perdu en route entre réseau, codec et lecture
+fn assert_consistent(report: &CallReport, params: &CallParams) {
+    assert_eq!(report.frames_sent, expected_frames(params));
+    assert!(report.codec_errors.is_empty(), "{:?}", report.codec_errors);
+
+    // Chaque frame reçue est décodée puis jouée, une seule fois
+    let mut unique = report.sequences.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), report.sequences.len());
+    // (plus les frames reconstruites à la place des perdues)
+    let reconstructed = report.frames_fec + report.frames_concealed;
+    assert_eq!(report.frames_played, report.sequences.len() as u64 + reconstructed);
+    assert_eq!(report.latencies.len(), report.sequences.len());
+    assert!(unique.last().is_some_and(|&last| last <= report.frames_sent));
+
+    // Les compteurs des managers correspondent à ce qui a été vu de l'appel
+    assert_eq!(report.caller_stats.packets_sent, report.frames_sent);
+    assert_eq!(report.callee_stats.packets_received, report.frames_played - reconstructed);
+    assert_eq!(report.frames_sent, report.frames_played - reconstructed + report.frames_lost());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_call_on_clean_network() {
+    let params = CallParams::default();
+    let report = run_call(&params).await.unwrap();
+
+    assert_consistent(&report, &params);
+    assert_eq!(report.frames_lost(), 0);
+    assert!(report.in_order());
+    assert!(report.max_latency() <= Duration::from_millis(params.latency_ms as u64 + 10));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_call_under_loss_and_jitter() {
+    let params = CallParams { loss_rate: 0.05, jitter_ms: 80, ..CallParams::default() };
+    let report = run_call(&params).await.unwrap();
+
+    assert_consistent(&report, &params);
+
+    // 5% de 500 frames : 25 perdues en moyenne, bornes à plus de 4 écarts-types
+    let lost = report.frames_lost();
+    assert!((5..=50).contains(&lost), "{} frames perdues", lost);
+
+    // Latence de base + gigue uniforme : 60ms en moyenne, 100ms au plus
+    // (à la période de scrutation du transport simulé près)
+    let mean = report.mean_latency();
+    assert!(mean >= Duration::from_millis(45) && mean <= Duration::from_millis(75), "latence moyenne {:?}", mean);
+    assert!(report.max_latency() <= Duration::from_millis(110), "latence max {:?}", report.max_latency());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_call_conceals_losses() {
+    let mut params = CallParams { loss_rate: 0.05, conceal_losses: true, ..CallParams::default() };
+    params.audio.opus_fec_loss_percent = 5;
+    let report = run_call(&params).await.unwrap();
+
+    assert_consistent(&report, &params);
+
+    // Chaque perte est comblée, sauf celles en fin d'appel (rien ne les suit)
+    let lost = report.frames_lost();
+    assert!((5..=50).contains(&lost), "{} frames perdues", lost);
+    let reconstructed = report.frames_fec + report.frames_concealed;
+    assert!(reconstructed <= lost && lost - reconstructed <= 5, "{} reconstruites sur {} perdues", reconstructed, lost);
+
+    // Une perte isolée est reconstruite par FEC, une rafale extrapolée
+    assert!(report.frames_fec > 0);
+    assert!(report.frames_fec >= report.frames_concealed);
+}
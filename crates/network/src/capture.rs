@@ -0,0 +1,444 @@
+//! Capture des datagrammes pour le diagnostic protocolaire
+//!
+//! Chaque transport accepte un « tap » (`NetworkTransport::set_tap`) appelé
+//! pour chaque datagramme envoyé ou reçu, avant validation. `CaptureWriter`
+//! fournit un tap qui enregistre ces datagrammes au format pcapng :
+//! - le fichier s'ouvre dans Wireshark (en-têtes IP/UDP synthétisés)
+//! - le sens (envoyé/reçu) est stocké dans l'option `epb_flags`
+//! - `CapturedDatagram::read_all` relit la capture pour la rejouer dans
+//!   `SimulatedTransport::replay_capture`
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{NetworkError, NetworkResult};
+
+/// Sens d'un datagramme capturé
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TapDirection {
+    /// Datagramme émis par ce transport
+    Sent,
+    /// Datagramme reçu par ce transport
+    Received,
+}
+
+/// Datagramme observé par un tap
+#[derive(Debug)]
+pub struct TapEvent<'a> {
+    /// Sens du datagramme
+    pub direction: TapDirection,
+
+    /// Instant de l'envoi / de la réception
+    pub timestamp: SystemTime,
+
+    /// Adresse locale du transport (si bindé)
+    pub local_addr: Option<SocketAddr>,
+
+    /// Adresse du pair distant (destination ou source)
+    pub remote_addr: SocketAddr,
+
+    /// Contenu brut du datagramme (paquet sérialisé)
+    pub datagram: &'a [u8],
+}
+
+/// Callback appelé pour chaque datagramme envoyé ou reçu
+pub type PacketTap = Box<dyn Fn(&TapEvent<'_>) + Send + Sync>;
+
+/// Appelle le tap s'il est configuré (utilisé par les transports)
+#[cfg(any(feature = "udp", feature = "simulator"))]
+pub(crate) fn emit(
+    tap: &Option<PacketTap>,
+    direction: TapDirection,
+    local_addr: Option<SocketAddr>,
+    remote_addr: SocketAddr,
+    datagram: &[u8],
+) {
+    if let Some(tap) = tap {
+        tap(&TapEvent {
+            direction,
+            timestamp: SystemTime::now(),
+            local_addr,
+            remote_addr,
+            datagram,
+        });
+    }
+}
+
+/// Types de blocs pcapng utilisés
+const BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const BLOCK_INTERFACE_DESCRIPTION: u32 = 1;
+const BLOCK_ENHANCED_PACKET: u32 = 6;
+
+/// Marqueur d'ordre des octets pcapng
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// LINKTYPE_RAW : paquets IPv4/IPv6 sans en-tête de liaison
+const LINKTYPE_RAW: u16 = 101;
+
+/// Option `epb_flags` et valeurs de direction
+const OPTION_EPB_FLAGS: u16 = 2;
+const EPB_FLAGS_INBOUND: u32 = 0b01;
+const EPB_FLAGS_OUTBOUND: u32 = 0b10;
+
+/// Enregistre les datagrammes d'un transport dans un fichier pcapng
+///
+/// # Example
+/// ```rust,no_run
+/// use network::{CaptureWriter, NetworkConfig, NetworkTransport, UdpTransport};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut transport = UdpTransport::new(NetworkConfig::default())?;
+/// let writer = CaptureWriter::create("voc.pcapng")?;
+/// transport.set_tap(Some(writer.into_tap()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct CaptureWriter {
+    /// Fichier de capture (partagé entre envoi et réception)
+    output: Mutex<BufWriter<File>>,
+}
+
+impl CaptureWriter {
+    /// Crée le fichier de capture et écrit les en-têtes pcapng
+    ///
+    /// # Erreurs
+    /// - `NetworkError::IoError` : Impossible de créer le fichier
+    pub fn create(path: impl AsRef<Path>) -> NetworkResult<Self> {
+        let mut output = BufWriter::new(File::create(path)?);
+
+        // Section Header Block (sans options, longueur de section inconnue)
+        let mut section = Vec::with_capacity(16);
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        section.extend_from_slice(&1u16.to_le_bytes());
+        section.extend_from_slice(&0u16.to_le_bytes());
+        section.extend_from_slice(&(-1i64).to_le_bytes());
+        write_block(&mut output, BLOCK_SECTION_HEADER, &section)?;
+
+        // Interface Description Block (résolution par défaut : microseconde)
+        let mut interface = Vec::with_capacity(8);
+        interface.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        interface.extend_from_slice(&0u16.to_le_bytes());
+        interface.extend_from_slice(&65535u32.to_le_bytes());
+        write_block(&mut output, BLOCK_INTERFACE_DESCRIPTION, &interface)?;
+
+        output.flush()?;
+        Ok(Self { output: Mutex::new(output) })
+    }
+
+    /// Ajoute un datagramme à la capture
+    ///
+    /// Le fichier est vidé après chaque datagramme pour rester exploitable
+    /// même si l'application s'arrête brutalement.
+    pub fn record(&self, event: &TapEvent<'_>) -> NetworkResult<()> {
+        let local_addr = event.local_addr.unwrap_or_else(|| unspecified_addr(event.remote_addr));
+        let (source, destination, flags) = match event.direction {
+            TapDirection::Sent => (local_addr, event.remote_addr, EPB_FLAGS_OUTBOUND),
+            TapDirection::Received => (event.remote_addr, local_addr, EPB_FLAGS_INBOUND),
+        };
+        let frame = build_ip_udp_frame(source, destination, event.datagram);
+
+        let micros = event.timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        // Enhanced Packet Block
+        let mut body = Vec::with_capacity(frame.len() + 36);
+        body.extend_from_slice(&0u32.to_le_bytes()); // interface 0
+        body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(micros as u32).to_le_bytes());
+        body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        body.extend_from_slice(&frame);
+        body.resize(body.len().next_multiple_of(4), 0);
+        body.extend_from_slice(&OPTION_EPB_FLAGS.to_le_bytes());
+        body.extend_from_slice(&4u16.to_le_bytes());
+        body.extend_from_slice(&flags.to_le_bytes());
+        body.extend_from_slice(&[0, 0, 0, 0]); // opt_endofopt
+
+        let mut output = self.output.lock()
+            .map_err(|_| NetworkError::InitializationError("capture verrouillée".to_string()))?;
+        write_block(&mut *output, BLOCK_ENHANCED_PACKET, &body)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Convertit l'enregistreur en tap à installer sur un transport
+    ///
+    /// Les erreurs d'écriture sont affichées sans interrompre le transport.
+    pub fn into_tap(self) -> PacketTap {
+        Box::new(move |event| {
+            if let Err(e) = self.record(event) {
+                println!("Capture : écriture impossible ({})", e);
+            }
+        })
+    }
+}
+
+/// Datagramme relu depuis une capture pcapng
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedDatagram {
+    /// Sens du datagramme lors de la capture
+    pub direction: TapDirection,
+
+    /// Instant de capture
+    pub timestamp: SystemTime,
+
+    /// Adresse locale du transport capturé
+    pub local_addr: SocketAddr,
+
+    /// Adresse du pair distant
+    pub remote_addr: SocketAddr,
+
+    /// Contenu brut du datagramme
+    pub datagram: Vec<u8>,
+}
+
+impl CapturedDatagram {
+    /// Relit tous les datagrammes d'une capture écrite par `CaptureWriter`
+    ///
+    /// # Erreurs
+    /// - `NetworkError::IoError` : Fichier illisible ou format inattendu
+    pub fn read_all(path: impl AsRef<Path>) -> NetworkResult<Vec<Self>> {
+        let mut content = Vec::new();
+        File::open(path)?.read_to_end(&mut content)?;
+
+        let mut datagrams = Vec::new();
+        let mut offset = 0;
+
+        while offset + 12 <= content.len() {
+            let block_type = read_u32(&content, offset);
+            let block_len = read_u32(&content, offset + 4) as usize;
+            if block_len < 12 || offset + block_len > content.len() {
+                return Err(invalid_capture("bloc tronqué"));
+            }
+
+            let body = &content[offset + 8..offset + block_len - 4];
+            match block_type {
+                BLOCK_SECTION_HEADER if read_u32(body, 0) != BYTE_ORDER_MAGIC => {
+                    return Err(invalid_capture("ordre des octets non supporté"));
+                }
+                BLOCK_ENHANCED_PACKET => {
+                    if let Some(datagram) = parse_enhanced_packet(body) {
+                        datagrams.push(datagram);
+                    }
+                }
+                _ => {}
+            }
+
+            offset += block_len;
+        }
+
+        Ok(datagrams)
+    }
+}
+
+/// Écrit un bloc pcapng (type, longueur, corps aligné, longueur)
+fn write_block(output: &mut impl Write, block_type: u32, body: &[u8]) -> std::io::Result<()> {
+    let padded_len = body.len().next_multiple_of(4);
+    let block_len = (padded_len + 12) as u32;
+
+    output.write_all(&block_type.to_le_bytes())?;
+    output.write_all(&block_len.to_le_bytes())?;
+    output.write_all(body)?;
+    output.write_all(&[0u8; 3][..padded_len - body.len()])?;
+    output.write_all(&block_len.to_le_bytes())
+}
+
+/// Analyse un Enhanced Packet Block écrit par `CaptureWriter`
+fn parse_enhanced_packet(body: &[u8]) -> Option<CapturedDatagram> {
+    if body.len() < 20 {
+        return None;
+    }
+
+    let micros = ((read_u32(body, 4) as u64) << 32) | read_u32(body, 8) as u64;
+    let captured_len = read_u32(body, 12) as usize;
+    let frame = body.get(20..20 + captured_len)?;
+
+    // Options : recherche de epb_flags pour connaître le sens
+    let mut option_offset = 20 + captured_len.next_multiple_of(4);
+    let mut flags = 0;
+    while option_offset + 4 <= body.len() {
+        let code = u16::from_le_bytes([body[option_offset], body[option_offset + 1]]);
+        let len = u16::from_le_bytes([body[option_offset + 2], body[option_offset + 3]]) as usize;
+        if code == 0 {
+            break;
+        }
+        if code == OPTION_EPB_FLAGS && len == 4 && option_offset + 8 <= body.len() {
+            flags = read_u32(body, option_offset + 4);
+        }
+        option_offset += 4 + len.next_multiple_of(4);
+    }
+
+    let (source, destination, datagram) = parse_ip_udp_frame(frame)?;
+    let (direction, local_addr, remote_addr) = if flags & 0b11 == EPB_FLAGS_INBOUND {
+        (TapDirection::Received, destination, source)
+    } else {
+        (TapDirection::Sent, source, destination)
+    };
+
+    Some(CapturedDatagram {
+        direction,
+        timestamp: UNIX_EPOCH + Duration::from_micros(micros),
+        local_addr,
+        remote_addr,
+        datagram: datagram.to_vec(),
+    })
+}
+
+/// Construit une trame IP + UDP autour du datagramme (pour Wireshark)
+fn build_ip_udp_frame(source: SocketAddr, destination: SocketAddr, datagram: &[u8]) -> Vec<u8> {
+    let udp_len = (8 + datagram.len()) as u16;
+    let mut frame = Vec::with_capacity(48 + datagram.len());
+
+    match (source.ip(), destination.ip()) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            let total_len = 20 + udp_len;
+            frame.extend_from_slice(&[0x45, 0]);
+            frame.extend_from_slice(&total_len.to_be_bytes());
+            frame.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]); // DF, TTL 64, UDP
+            frame.extend_from_slice(&src.octets());
+            frame.extend_from_slice(&dst.octets());
+            let checksum = ipv4_header_checksum(&frame);
+            frame[10..12].copy_from_slice(&checksum.to_be_bytes());
+        }
+        (src, dst) => {
+            frame.extend_from_slice(&[0x60, 0, 0, 0]);
+            frame.extend_from_slice(&udp_len.to_be_bytes());
+            frame.extend_from_slice(&[17, 64]); // UDP, hop limit 64
+            frame.extend_from_slice(&to_ipv6(src).octets());
+            frame.extend_from_slice(&to_ipv6(dst).octets());
+        }
+    }
+
+    frame.extend_from_slice(&source.port().to_be_bytes());
+    frame.extend_from_slice(&destination.port().to_be_bytes());
+    frame.extend_from_slice(&udp_len.to_be_bytes());
+    frame.extend_from_slice(&[0, 0]); // checksum UDP non calculé
+    frame.extend_from_slice(datagram);
+    frame
+}
+
+/// Extrait adresses et contenu d'une trame IP + UDP
+fn parse_ip_udp_frame(frame: &[u8]) -> Option<(SocketAddr, SocketAddr, &[u8])> {
+    let (source_ip, destination_ip, udp) = match frame.first()? >> 4 {
+        4 => {
+            let header_len = ((frame[0] & 0x0F) as usize) * 4;
+            let src: [u8; 4] = frame.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = frame.get(16..20)?.try_into().ok()?;
+            (IpAddr::from(src), IpAddr::from(dst), frame.get(header_len..)?)
+        }
+        6 => {
+            let src: [u8; 16] = frame.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = frame.get(24..40)?.try_into().ok()?;
+            (IpAddr::from(src), IpAddr::from(dst), frame.get(40..)?)
+        }
+        _ => return None,
+    };
+
+    let source_port = u16::from_be_bytes([*udp.first()?, *udp.get(1)?]);
+    let destination_port = u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]);
+    Some((
+        SocketAddr::new(source_ip, source_port),
+        SocketAddr::new(destination_ip, destination_port),
+        udp.get(8..)?,
+    ))
+}
+
+/// Adresse locale par défaut quand le transport n'est pas bindé
+fn unspecified_addr(remote_addr: SocketAddr) -> SocketAddr {
+    match remote_addr {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    }
+}
+
+/// Adresse IPv6 équivalente (IPv4 mappée si besoin)
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+/// Checksum de l'en-tête IPv4 (complément à un)
+fn ipv4_header_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]) as u32)
+        .sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Lit un u32 little-endian (l'appelant garantit la taille)
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Erreur de format de capture
+fn invalid_capture(reason: &str) -> NetworkError {
+    NetworkError::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("capture pcapng invalide : {}", reason),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_roundtrip() {
+        let path = std::env::temp_dir().join(format!("voc-capture-{}.pcapng", std::process::id()));
+        let local: SocketAddr = "192.168.1.10:9001".parse().unwrap();
+        let remote_v4: SocketAddr = "192.168.1.20:40000".parse().unwrap();
+        let remote_v6: SocketAddr = "[fe80::2]:40000".parse().unwrap();
+
+        let writer = CaptureWriter::create(&path).unwrap();
+        for (direction, remote, payload) in [
+            (TapDirection::Sent, remote_v4, &b"hello"[..]),
+            (TapDirection::Received, remote_v4, &b"world!!!"[..]),
+            (TapDirection::Received, remote_v6, &b"v6"[..]),
+        ] {
+            writer.record(&TapEvent {
+                direction,
+                timestamp: SystemTime::now(),
+                local_addr: Some(local),
+                remote_addr: remote,
+                datagram: payload,
+            }).unwrap();
+        }
+        drop(writer);
+
+        let datagrams = CapturedDatagram::read_all(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(datagrams.len(), 3);
+        assert_eq!(datagrams[0].direction, TapDirection::Sent);
+        assert_eq!(datagrams[0].local_addr, local);
+        assert_eq!(datagrams[0].remote_addr, remote_v4);
+        assert_eq!(datagrams[0].datagram, b"hello");
+        assert_eq!(datagrams[1].direction, TapDirection::Received);
+        assert_eq!(datagrams[1].datagram, b"world!!!");
+        assert_eq!(datagrams[2].remote_addr, remote_v6);
+        assert_eq!(datagrams[2].datagram, b"v6");
+    }
+
+    #[test]
+    fn test_ipv4_header_checksum() {
+        let frame = build_ip_udp_frame(
+            "10.0.0.1:1".parse().unwrap(),
+            "10.0.0.2:2".parse().unwrap(),
+            b"x",
+        );
+        // Un en-tête valide a une somme de contrôle nulle une fois recalculée
+        assert_eq!(ipv4_header_checksum(&frame[..20]), 0);
+    }
+}
@@ -0,0 +1,456 @@
+//! Contrôle de congestion pour l'envoi audio
+//!
+//! Ce module définit une interface de contrôle de congestion pluggable
+//! (`CongestionController`) et une implémentation par défaut basée sur le
+//! délai et la perte, inspirée de GCC (Google Congestion Control).
+//!
+//! Le contrôleur observe les paquets envoyés, les mesures de RTT reçues via
+//! les heartbeats et les pertes signalées, et en déduit un débit de pacing.
+//! Le `Pacer` applique ce débit sur le chemin d'envoi du manager.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Interface d'un algorithme de contrôle de congestion
+///
+/// Le manager notifie le contrôleur à chaque événement réseau pertinent
+/// et lit le débit de pacing résultant avant chaque envoi audio.
+///
+/// Permet de remplacer l'algorithme par défaut (ex: LEDBAT, débit fixe)
+/// sans modifier le manager.
+pub trait CongestionController: Send + Sync {
+    /// Notifie l'envoi d'un paquet de `bytes` octets
+    fn on_packet_sent(&mut self, bytes: usize, now: Instant);
+
+    /// Notifie la réception d'un heartbeat, avec la mesure de RTT si disponible
+    fn on_heartbeat(&mut self, rtt: Option<Duration>, now: Instant);
+
+    /// Notifie des pertes de paquets détectées
+    fn on_loss(&mut self, lost_packets: u64, now: Instant);
+
+    /// Débit de pacing courant en bits par seconde
+    fn pacing_rate_bps(&self) -> u32;
+
+    /// État courant du contrôleur (pour les statistiques)
+    fn state(&self) -> CongestionState;
+
+    /// Nom de l'algorithme pour le debug
+    fn name(&self) -> &'static str {
+        "Contrôleur de congestion"
+    }
+}
+
+/// Phase du contrôleur de congestion
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CongestionPhase {
+    /// Le réseau absorbe le débit, on augmente
+    #[default]
+    Increase,
+    /// Situation incertaine, on maintient le débit
+    Hold,
+    /// Surcharge détectée (délai ou perte), on réduit
+    Decrease,
+}
+
+/// État exporté du contrôleur de congestion
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CongestionState {
+    /// Débit de pacing courant (bits/sec)
+    pub pacing_rate_bps: u32,
+
+    /// Phase courante de l'algorithme
+    pub phase: CongestionPhase,
+
+    /// RTT lissé en millisecondes
+    pub smoothed_rtt_ms: f32,
+
+    /// RTT minimum observé en millisecondes (référence sans file d'attente)
+    pub min_rtt_ms: f32,
+
+    /// Fraction de paquets perdus sur la dernière fenêtre (0.0 à 1.0)
+    pub loss_fraction: f32,
+}
+
+/// Contrôleur de congestion par défaut basé sur le délai et la perte
+///
+/// Fonctionnement (simplifié de GCC) :
+/// - Délai de file d'attente = RTT lissé - RTT minimum
+/// - Délai > seuil ou perte > 10% : réduction multiplicative du débit
+/// - Délai faible et perte < 2% : augmentation multiplicative douce
+/// - Sinon : maintien du débit
+pub struct DelayBasedController {
+    /// Débit courant (bits/sec)
+    rate_bps: f64,
+
+    /// Bornes du débit (bits/sec)
+    min_rate_bps: u32,
+    max_rate_bps: u32,
+
+    /// RTT lissé et minimum observés
+    smoothed_rtt: Option<Duration>,
+    min_rtt: Option<Duration>,
+
+    /// Compteurs de la fenêtre courante pour le calcul de perte
+    window_sent: u64,
+    window_lost: u64,
+
+    /// Dernière fraction de perte calculée
+    loss_fraction: f32,
+
+    /// Phase courante
+    phase: CongestionPhase,
+
+    /// Dernière adaptation du débit (évite d'adapter trop souvent)
+    last_update: Option<Instant>,
+}
+
+impl DelayBasedController {
+    /// Débit initial par défaut (bits/sec, en-têtes inclus)
+    pub const DEFAULT_START_RATE_BPS: u32 = 64_000;
+
+    /// Débit minimum par défaut (bits/sec)
+    pub const DEFAULT_MIN_RATE_BPS: u32 = 16_000;
+
+    /// Débit maximum par défaut (bits/sec)
+    pub const DEFAULT_MAX_RATE_BPS: u32 = 256_000;
+
+    /// Délai de file d'attente au-delà duquel on considère le lien surchargé
+    const QUEUING_DELAY_THRESHOLD: Duration = Duration::from_millis(25);
+
+    /// Intervalle minimum entre deux adaptations du débit
+    const UPDATE_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// Crée un contrôleur avec les bornes par défaut
+    pub fn new() -> Self {
+        Self::with_bounds(
+            Self::DEFAULT_START_RATE_BPS,
+            Self::DEFAULT_MIN_RATE_BPS,
+            Self::DEFAULT_MAX_RATE_BPS,
+        )
+    }
+
+    /// Crée un contrôleur avec des bornes personnalisées
+    ///
+    /// # Arguments
+    /// * `start_rate_bps` - Débit initial
+    /// * `min_rate_bps` - Débit plancher
+    /// * `max_rate_bps` - Débit plafond
+    pub fn with_bounds(start_rate_bps: u32, min_rate_bps: u32, max_rate_bps: u32) -> Self {
+        Self {
+            rate_bps: start_rate_bps.clamp(min_rate_bps, max_rate_bps) as f64,
+            min_rate_bps,
+            max_rate_bps,
+            smoothed_rtt: None,
+            min_rtt: None,
+            window_sent: 0,
+            window_lost: 0,
+            loss_fraction: 0.0,
+            phase: CongestionPhase::Increase,
+            last_update: None,
+        }
+    }
+
+    /// Délai de file d'attente estimé (RTT lissé - RTT minimum)
+    fn queuing_delay(&self) -> Duration {
+        match (self.smoothed_rtt, self.min_rtt) {
+            (Some(srtt), Some(min)) => srtt.saturating_sub(min),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Recalcule la phase et le débit à partir des mesures courantes
+    fn update_rate(&mut self, now: Instant) {
+        if let Some(last) = self.last_update
+            && now.duration_since(last) < Self::UPDATE_INTERVAL
+        {
+            return;
+        }
+        self.last_update = Some(now);
+
+        // Fraction de perte sur la fenêtre écoulée
+        if self.window_sent > 0 {
+            self.loss_fraction = (self.window_lost as f32 / self.window_sent as f32).min(1.0);
+        }
+        self.window_sent = 0;
+        self.window_lost = 0;
+
+        let queuing_delay = self.queuing_delay();
+
+        self.phase = if self.loss_fraction > 0.10 || queuing_delay > Self::QUEUING_DELAY_THRESHOLD {
+            CongestionPhase::Decrease
+        } else if self.loss_fraction < 0.02 && queuing_delay < Self::QUEUING_DELAY_THRESHOLD / 2 {
+            CongestionPhase::Increase
+        } else {
+            CongestionPhase::Hold
+        };
+
+        match self.phase {
+            CongestionPhase::Decrease => {
+                // Réduction proportionnelle à la perte (comme GCC), au moins 15%
+                let factor = (1.0 - 0.5 * self.loss_fraction as f64).min(0.85);
+                self.rate_bps *= factor;
+            }
+            CongestionPhase::Increase => {
+                self.rate_bps *= 1.08;
+            }
+            CongestionPhase::Hold => {}
+        }
+
+        self.rate_bps = self.rate_bps.clamp(self.min_rate_bps as f64, self.max_rate_bps as f64);
+    }
+}
+
+impl Default for DelayBasedController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CongestionController for DelayBasedController {
+    fn on_packet_sent(&mut self, _bytes: usize, _now: Instant) {
+        self.window_sent += 1;
+    }
+
+    fn on_heartbeat(&mut self, rtt: Option<Duration>, now: Instant) {
+        if let Some(rtt) = rtt {
+            // Moyenne mobile du RTT (même pondération que les stats transport)
+            self.smoothed_rtt = Some(match self.smoothed_rtt {
+                Some(srtt) => srtt.mul_f32(0.8) + rtt.mul_f32(0.2),
+                None => rtt,
+            });
+            self.min_rtt = Some(match self.min_rtt {
+                Some(min) => min.min(rtt),
+                None => rtt,
+            });
+        }
+
+        self.update_rate(now);
+    }
+
+    fn on_loss(&mut self, lost_packets: u64, now: Instant) {
+        self.window_lost += lost_packets;
+        self.update_rate(now);
+    }
+
+    fn pacing_rate_bps(&self) -> u32 {
+        self.rate_bps as u32
+    }
+
+    fn state(&self) -> CongestionState {
+        CongestionState {
+            pacing_rate_bps: self.pacing_rate_bps(),
+            phase: self.phase,
+            smoothed_rtt_ms: self.smoothed_rtt.map(|d| d.as_secs_f32() * 1000.0).unwrap_or(0.0),
+            min_rtt_ms: self.min_rtt.map(|d| d.as_secs_f32() * 1000.0).unwrap_or(0.0),
+            loss_fraction: self.loss_fraction,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Délai/perte (GCC simplifié)"
+    }
+}
+
+/// Pacer à seau de jetons
+///
+/// Lisse les envois pour respecter le débit donné par le contrôleur de
+/// congestion, dans la limite d'un plafond éventuel
+/// (`NetworkConfig::max_bandwidth_bps`). Autorise une petite rafale (burst)
+/// pour ne pas retarder inutilement les frames audio isolées.
+pub struct Pacer {
+    /// Débit cible (bits/sec)
+    rate_bps: u32,
+
+    /// Plafond de débit (bits/sec), prioritaire sur le débit cible
+    cap_bps: Option<u32>,
+
+    /// Jetons disponibles en octets (peut devenir négatif en cas de dette)
+    tokens_bytes: f64,
+
+    /// Taille maximale de la rafale autorisée en octets
+    burst_bytes: f64,
+
+    /// Dernier remplissage du seau
+    last_refill: Option<Instant>,
+}
+
+impl Pacer {
+    /// Rafale par défaut : ~2 paquets audio typiques
+    const DEFAULT_BURST_BYTES: f64 = 500.0;
+
+    /// Crée un pacer au débit donné
+    pub fn new(rate_bps: u32) -> Self {
+        Self {
+            rate_bps,
+            cap_bps: None,
+            tokens_bytes: Self::DEFAULT_BURST_BYTES,
+            burst_bytes: Self::DEFAULT_BURST_BYTES,
+            last_refill: None,
+        }
+    }
+
+    /// Met à jour le débit cible
+    pub fn set_rate(&mut self, rate_bps: u32) {
+        self.rate_bps = rate_bps;
+    }
+
+    /// Plafonne le débit, quel que soit le débit cible (`None` : aucun plafond)
+    pub fn set_cap(&mut self, cap_bps: Option<u32>) {
+        self.cap_bps = cap_bps;
+    }
+
+    /// Débit appliqué (bits/sec) : le débit cible, dans la limite du plafond
+    pub fn rate_bps(&self) -> u32 {
+        self.cap_bps.map_or(self.rate_bps, |cap| cap.min(self.rate_bps))
+    }
+
+    /// Le plafond limite actuellement le débit cible
+    pub fn is_capped(&self) -> bool {
+        self.cap_bps.is_some_and(|cap| cap < self.rate_bps)
+    }
+
+    /// Réserve `bytes` octets et retourne le délai à attendre avant l'envoi
+    ///
+    /// Retourne `Duration::ZERO` si l'envoi peut partir immédiatement.
+    pub fn delay_for(&mut self, bytes: usize, now: Instant) -> Duration {
+        let bytes_per_sec = self.refill(now);
+        self.tokens_bytes -= bytes as f64;
+
+        if self.tokens_bytes >= 0.0 || bytes_per_sec <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens_bytes / bytes_per_sec)
+        }
+    }
+
+    /// Réserve `bytes` octets seulement si l'envoi peut partir immédiatement
+    ///
+    /// Pour les ordonnanceurs qui servent une autre file en attendant
+    /// (`RelayServer`). Un envoi est accepté tant que le seau n'est pas en
+    /// dette : un datagramme plus grand que la rafale finit par passer.
+    pub fn try_reserve(&mut self, bytes: usize, now: Instant) -> bool {
+        let bytes_per_sec = self.refill(now);
+        if self.tokens_bytes < 0.0 && bytes_per_sec > 0.0 {
+            return false;
+        }
+        self.tokens_bytes -= bytes as f64;
+        true
+    }
+
+    /// Remplit le seau depuis le dernier appel et retourne le débit en octets/sec
+    fn refill(&mut self, now: Instant) -> f64 {
+        let bytes_per_sec = self.rate_bps() as f64 / 8.0;
+        if let Some(last) = self.last_refill {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            self.tokens_bytes = (self.tokens_bytes + elapsed * bytes_per_sec).min(self.burst_bytes);
+        }
+        self.last_refill = Some(now);
+        bytes_per_sec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_controller_increases_on_clean_link() {
+        let mut controller = DelayBasedController::new();
+        let start = Instant::now();
+        let initial_rate = controller.pacing_rate_bps();
+
+        for i in 1..=5 {
+            let now = start + Duration::from_millis(300 * i);
+            controller.on_packet_sent(200, now);
+            controller.on_heartbeat(Some(Duration::from_millis(10)), now);
+        }
+
+        assert!(controller.pacing_rate_bps() > initial_rate);
+        assert_eq!(controller.state().phase, CongestionPhase::Increase);
+    }
+
+    #[test]
+    fn test_controller_decreases_on_queuing_delay() {
+        let mut controller = DelayBasedController::new();
+        let start = Instant::now();
+
+        // RTT de référence bas, puis RTT qui grimpe (file d'attente)
+        controller.on_heartbeat(Some(Duration::from_millis(10)), start);
+        let rate_before = controller.pacing_rate_bps();
+
+        for i in 1..=5 {
+            let now = start + Duration::from_millis(300 * i);
+            controller.on_heartbeat(Some(Duration::from_millis(150)), now);
+        }
+
+        assert!(controller.pacing_rate_bps() < rate_before);
+        assert_eq!(controller.state().phase, CongestionPhase::Decrease);
+    }
+
+    #[test]
+    fn test_controller_decreases_on_loss() {
+        let mut controller = DelayBasedController::new();
+        let start = Instant::now();
+        controller.on_heartbeat(None, start);
+        let rate_before = controller.pacing_rate_bps();
+
+        for _ in 0..10 {
+            controller.on_packet_sent(200, start);
+        }
+        controller.on_loss(5, start + Duration::from_millis(300));
+
+        assert!(controller.pacing_rate_bps() < rate_before);
+        assert!((controller.state().loss_fraction - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_controller_respects_bounds() {
+        let mut controller = DelayBasedController::with_bounds(20_000, 16_000, 24_000);
+        let start = Instant::now();
+
+        for i in 1..=20 {
+            let now = start + Duration::from_millis(300 * i);
+            controller.on_heartbeat(Some(Duration::from_millis(5)), now);
+        }
+        assert_eq!(controller.pacing_rate_bps(), 24_000);
+    }
+
+    #[test]
+    fn test_pacer_allows_burst_then_delays() {
+        let mut pacer = Pacer::new(80_000); // 10 000 octets/sec
+        let now = Instant::now();
+
+        // La rafale initiale passe sans attente
+        assert_eq!(pacer.delay_for(200, now), Duration::ZERO);
+        assert_eq!(pacer.delay_for(200, now), Duration::ZERO);
+
+        // Le paquet suivant doit attendre ~10ms (100 octets de dette)
+        let delay = pacer.delay_for(200, now);
+        assert!(delay > Duration::from_millis(5) && delay < Duration::from_millis(15));
+
+        // Après un temps suffisant, les jetons sont revenus
+        let later = now + Duration::from_millis(100);
+        assert_eq!(pacer.delay_for(200, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pacer_cap_overrides_controller_rate() {
+        let mut pacer = Pacer::new(80_000);
+        pacer.set_cap(Some(40_000)); // 5 000 octets/sec
+        assert_eq!(pacer.rate_bps(), 40_000);
+        assert!(pacer.is_capped());
+
+        // Dette de 100 octets remboursée au débit plafonné : ~20ms
+        let now = Instant::now();
+        pacer.delay_for(300, now);
+        let delay = pacer.delay_for(300, now);
+        assert!(delay > Duration::from_millis(15) && delay < Duration::from_millis(25));
+        assert!(!pacer.try_reserve(200, now));
+        assert!(pacer.try_reserve(200, now + Duration::from_millis(30)));
+
+        // Plafond au-dessus du débit du contrôleur : sans effet
+        pacer.set_cap(Some(100_000));
+        assert_eq!(pacer.rate_bps(), 80_000);
+        assert!(!pacer.is_capped());
+    }
+}
@@ -0,0 +1,182 @@
+//! Échelle de dégradation progressive de l'appel
+//!
+//! Quand la connexion reste mauvaise plusieurs périodes d'échantillonnage
+//! d'affilée (`QualitySample`), l'appel descend d'un échelon : débit réduit,
+//! redondance, frames plus longues, buffer plus profond, avertissement de
+//! l'utilisateur, puis suspension des extras. Il remonte d'un échelon après
+//! une période plus longue de bonne qualité. Chaque changement d'échelon est
+//! un `DegradationEvent`, à appliquer par l'application (codec, interface) ;
+//! le manager applique lui-même la redondance et la profondeur du buffer.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DegradationConfig, QualitySample};
+
+/// Échelon de l'échelle de dégradation, du moins au plus intrusif
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DegradationStep {
+    /// Débit du codec réduit (`CodecParams::bitrate_bps`, voir `renegotiate`)
+    ReduceBitrate,
+    /// Audio envoyé en double (`RedundancyMode::Duplicate`)
+    EnableRedundancy,
+    /// Frames plus longues : moins de paquets, moins d'en-têtes
+    IncreaseFrameSize,
+    /// Attente des paquets en retard doublée (`NetworkConfig::target_delay_ms`)
+    IncreaseJitterBuffer,
+    /// L'utilisateur est prévenu que la connexion est mauvaise
+    WarnUser,
+    /// Flux annexes suspendus (partage, métadonnées...) au profit de la voix
+    SuspendExtras,
+}
+
+impl DegradationStep {
+    /// Échelons dans l'ordre de descente
+    pub const LADDER: [DegradationStep; 6] = [
+        DegradationStep::ReduceBitrate,
+        DegradationStep::EnableRedundancy,
+        DegradationStep::IncreaseFrameSize,
+        DegradationStep::IncreaseJitterBuffer,
+        DegradationStep::WarnUser,
+        DegradationStep::SuspendExtras,
+    ];
+}
+
+/// Changement d'échelon
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DegradationEvent {
+    /// Conditions mauvaises persistantes : l'échelon est activé
+    Degrade(DegradationStep),
+    /// Conditions rétablies : l'échelon est annulé
+    Recover(DegradationStep),
+}
+
+/// Position sur l'échelle de dégradation, mise à jour à chaque échantillon
+///
+/// # Example
+/// ```rust
+/// use std::time::Instant;
+/// use network::{DegradationConfig, DegradationEvent, DegradationLadder, DegradationStep, QualitySample};
+///
+/// let mut config = DegradationConfig::default();
+/// config.degrade_after = 1;
+/// let mut ladder = DegradationLadder::new(config);
+/// let lossy = QualitySample { at: Instant::now(), rtt_ms: 40.0, loss_percent: 12.0, jitter_ms: 5.0, bitrate_bps: 32_000 };
+/// assert_eq!(ladder.on_sample(&lossy), Some(DegradationEvent::Degrade(DegradationStep::ReduceBitrate)));
+/// assert_eq!(ladder.active_steps(), &[DegradationStep::ReduceBitrate]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DegradationLadder {
+    config: DegradationConfig,
+    /// Nombre d'échelons activés
+    level: usize,
+    /// Échantillons mauvais (ou bons) consécutifs depuis le dernier changement
+    bad_streak: u32,
+    good_streak: u32,
+}
+
+impl DegradationLadder {
+    /// Part des seuils sous laquelle un échantillon compte pour la remontée
+    ///
+    /// Entre les deux, l'échantillon ne fait ni descendre ni remonter :
+    /// l'échelle n'oscille pas autour d'un seuil.
+    pub const RECOVERY_MARGIN: f32 = 0.5;
+
+    /// Crée une échelle au premier échelon (aucune dégradation)
+    pub fn new(config: DegradationConfig) -> Self {
+        Self { config, level: 0, bad_streak: 0, good_streak: 0 }
+    }
+
+    /// Nombre d'échelons activés (0 : aucune dégradation)
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Échelons activés, dans l'ordre de descente
+    pub fn active_steps(&self) -> &[DegradationStep] {
+        &DegradationStep::LADDER[..self.level]
+    }
+
+    /// Prend en compte un échantillon de qualité
+    ///
+    /// Retourne le changement d'échelon éventuel : au plus un par
+    /// échantillon, et chaque changement remet les compteurs à zéro pour que
+    /// l'échelon suivant attende une nouvelle période complète.
+    pub fn on_sample(&mut self, sample: &QualitySample) -> Option<DegradationEvent> {
+        if self.exceeds(sample, 1.0) {
+            self.good_streak = 0;
+            self.bad_streak += 1;
+        } else if !self.exceeds(sample, Self::RECOVERY_MARGIN) {
+            self.bad_streak = 0;
+            self.good_streak += 1;
+        } else {
+            self.bad_streak = 0;
+            self.good_streak = 0;
+        }
+
+        if self.bad_streak >= self.config.degrade_after && self.level < DegradationStep::LADDER.len() {
+            self.bad_streak = 0;
+            self.level += 1;
+            return Some(DegradationEvent::Degrade(DegradationStep::LADDER[self.level - 1]));
+        }
+        if self.good_streak >= self.config.recover_after && self.level > 0 {
+            self.good_streak = 0;
+            self.level -= 1;
+            return Some(DegradationEvent::Recover(DegradationStep::LADDER[self.level]));
+        }
+        None
+    }
+
+    /// Revient au premier échelon (nouvel appel)
+    pub fn reset(&mut self) {
+        self.level = 0;
+        self.bad_streak = 0;
+        self.good_streak = 0;
+    }
+
+    /// Un des seuils, multiplié par `factor`, est dépassé
+    fn exceeds(&self, sample: &QualitySample, factor: f32) -> bool {
+        sample.loss_percent > self.config.max_loss_percent * factor
+            || sample.rtt_ms > self.config.max_rtt_ms * factor
+            || sample.jitter_ms > self.config.max_jitter_ms * factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn sample(loss_percent: f32) -> QualitySample {
+        QualitySample { at: Instant::now(), rtt_ms: 30.0, loss_percent, jitter_ms: 5.0, bitrate_bps: 32_000 }
+    }
+
+    #[test]
+    fn test_ladder_walks_down_and_back_up() {
+        let config = DegradationConfig { degrade_after: 2, recover_after: 3, ..Default::default() };
+        let mut ladder = DegradationLadder::new(config);
+
+        // Deux échantillons mauvais par échelon, jusqu'au bas de l'échelle
+        let events: Vec<_> = (0..20).filter_map(|_| ladder.on_sample(&sample(20.0))).collect();
+        let expected: Vec<_> = DegradationStep::LADDER.iter().copied().map(DegradationEvent::Degrade).collect();
+        assert_eq!(events, expected);
+        assert_eq!(ladder.level(), DegradationStep::LADDER.len());
+
+        // Zone intermédiaire : ni descente ni remontée
+        assert!((0..10).all(|_| ladder.on_sample(&sample(4.0)).is_none()));
+
+        // Trois bons échantillons par échelon, dans l'ordre inverse
+        let events: Vec<_> = (0..3).filter_map(|_| ladder.on_sample(&sample(0.0))).collect();
+        assert_eq!(events, vec![DegradationEvent::Recover(DegradationStep::SuspendExtras)]);
+        assert_eq!(ladder.active_steps().last(), Some(&DegradationStep::WarnUser));
+    }
+
+    #[test]
+    fn test_isolated_bad_sample_is_ignored() {
+        let mut ladder = DegradationLadder::new(DegradationConfig::default());
+        for _ in 0..10 {
+            assert!(ladder.on_sample(&sample(20.0)).is_none());
+            assert!(ladder.on_sample(&sample(0.0)).is_none());
+        }
+        assert_eq!(ladder.level(), 0);
+    }
+}
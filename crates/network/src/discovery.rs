@@ -0,0 +1,486 @@
+//! Découverte de pairs Voc sur le réseau local
+//!
+//! Une instance envoie une sonde `PacketType::Discovery` en broadcast UDP sur
+//! le port d'écoute visé ; chaque instance en écoute répond directement à
+//! l'expéditeur. Sert de solution de repli à `connect_with_code` quand
+//! l'adresse encodée n'est plus valide (ex: IP changée par le DHCP).
+//!
+//! Les mêmes paquets servent à sonder un pair précis avant l'appel (`ping`) :
+//! toute instance en écoute ou en appel répond, sans handshake. Envoyées au
+//! rythme de l'audio (`measure_jitter`), ces sondes mesurent la gigue du
+//! chemin, d'après la variation de leur temps de réponse.
+//!
+//! Pour traverser un NAT, les deux pairs sondent en même temps toutes les
+//! adresses candidates de l'autre (privée et publique, échangées par la
+//! signalisation) : les premières sondes ouvrent les NAT de part et d'autre,
+//! et le premier chemin qui répond est retenu (`punch_candidates`).
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+use crate::{NetworkPacket, PacketType, NetworkTransport, NetworkResult, NetworkError, PresenceCapabilities};
+
+/// Message transporté dans la frame d'un paquet de découverte
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DiscoveryMessage {
+    /// Sonde envoyée en broadcast : "qui écoute sur ce port ?"
+    Probe,
+    /// Réponse d'une instance en écoute
+    Reply,
+    /// Sonde d'accessibilité adressée à un pair précis
+    Ping {
+        /// Numéro de la sonde, renvoyé dans la réponse
+        seq: u32,
+    },
+    /// Réponse à une sonde `Ping`
+    Pong {
+        /// Numéro de la sonde à laquelle on répond
+        seq: u32,
+    },
+    /// Annonce périodique de présence, envoyée en broadcast (voir `presence`)
+    Announce {
+        /// Nom affiché de l'instance
+        display_name: String,
+        /// Ce que l'instance sait faire
+        capabilities: PresenceCapabilities,
+    },
+    /// Annonce périodique d'un relais, envoyée en broadcast (voir `relay`)
+    RelayAnnounce,
+}
+
+/// Pair Voc découvert sur le réseau local
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveredPeer {
+    /// Adresse à utiliser pour se connecter
+    pub addr: SocketAddr,
+
+    /// ID de l'instance qui a répondu
+    pub sender_id: u64,
+
+    /// Temps de réponse à la sonde
+    pub response_time: Duration,
+}
+
+/// Résultat d'un `UdpNetworkManager::ping`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PingReport {
+    /// Adresse sondée
+    pub addr: SocketAddr,
+
+    /// ID de l'instance qui a répondu (si elle a répondu)
+    pub sender_id: Option<u64>,
+
+    /// Nombre de sondes envoyées
+    pub sent: u32,
+
+    /// Temps de réponse de chaque sonde reçue, dans l'ordre d'envoi
+    pub rtts: Vec<Duration>,
+}
+
+impl PingReport {
+    /// Nombre de réponses reçues
+    pub fn received(&self) -> u32 {
+        self.rtts.len() as u32
+    }
+
+    /// Le pair a répondu au moins une fois
+    pub fn is_reachable(&self) -> bool {
+        !self.rtts.is_empty()
+    }
+
+    /// Pourcentage de sondes sans réponse
+    pub fn loss_percent(&self) -> f32 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        (self.sent - self.received()) as f32 / self.sent as f32 * 100.0
+    }
+
+    /// Plus petit temps de réponse
+    pub fn min_rtt(&self) -> Option<Duration> {
+        self.rtts.iter().min().copied()
+    }
+
+    /// Temps de réponse moyen
+    pub fn avg_rtt(&self) -> Option<Duration> {
+        let total: Duration = self.rtts.iter().sum();
+        (!self.rtts.is_empty()).then(|| total / self.rtts.len() as u32)
+    }
+
+    /// Plus grand temps de réponse
+    pub fn max_rtt(&self) -> Option<Duration> {
+        self.rtts.iter().max().copied()
+    }
+}
+
+/// Résultat d'un `UdpNetworkManager::measure_jitter`
+///
+/// La gigue est mesurée sur l'aller-retour : chaque échantillon est l'écart
+/// entre les temps de réponse de deux sondes consécutives. Elle majore donc
+/// la gigue du seul trajet pair → nous, ce qui va dans le sens de la sécurité
+/// pour dimensionner le buffer anti-jitter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JitterReport {
+    /// Adresse sondée
+    pub addr: SocketAddr,
+
+    /// Intervalle entre deux sondes
+    pub probe_interval: Duration,
+
+    /// Temps de réponse de chaque sonde, dans l'ordre d'envoi (`None` : sans réponse)
+    pub rtts: Vec<Option<Duration>>,
+}
+
+impl JitterReport {
+    /// Nombre de sondes envoyées
+    pub fn sent(&self) -> u32 {
+        self.rtts.len() as u32
+    }
+
+    /// Nombre de réponses reçues
+    pub fn received(&self) -> u32 {
+        self.rtts.iter().flatten().count() as u32
+    }
+
+    /// Pourcentage de sondes sans réponse
+    pub fn loss_percent(&self) -> f32 {
+        if self.rtts.is_empty() {
+            return 0.0;
+        }
+        (self.sent() - self.received()) as f32 / self.sent() as f32 * 100.0
+    }
+
+    /// Échantillons de gigue, triés par ordre croissant
+    ///
+    /// Une sonde perdue interrompt la série : seules les paires de sondes
+    /// consécutives ayant toutes deux reçu une réponse comptent.
+    pub fn jitter_samples(&self) -> Vec<Duration> {
+        let mut samples: Vec<Duration> = self.rtts.windows(2)
+            .filter_map(|pair| match pair {
+                [Some(previous), Some(current)] => Some(current.abs_diff(*previous)),
+                _ => None,
+            })
+            .collect();
+        samples.sort();
+        samples
+    }
+
+    /// Gigue au centile `percentile` (0-100), `None` sans échantillon
+    pub fn percentile(&self, percentile: f32) -> Option<Duration> {
+        let samples = self.jitter_samples();
+        let rank = (samples.len() as f32 * percentile.clamp(0.0, 100.0) / 100.0).ceil() as usize;
+        samples.get(rank.clamp(1, samples.len().max(1)) - 1).copied()
+    }
+
+    /// Gigue médiane
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// Gigue dépassée par 5% des sondes
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    /// Gigue dépassée par 1% des sondes
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    /// Fenêtre de réordonnancement conseillée, en frames de `frame_duration`
+    ///
+    /// Couvre la gigue p99, avec au moins une frame ; `None` sans échantillon
+    /// (pair injoignable).
+    ///
+    /// # Example
+    /// ```rust
+    /// use network::JitterReport;
+    /// use std::time::Duration;
+    ///
+    /// let report = JitterReport {
+    ///     addr: "192.168.1.20:9001".parse().unwrap(),
+    ///     probe_interval: Duration::from_millis(20),
+    ///     rtts: [12, 30, 14, 13].map(|ms| Some(Duration::from_millis(ms))).to_vec(),
+    /// };
+    /// assert_eq!(report.p99(), Some(Duration::from_millis(18)));
+    /// assert_eq!(report.recommended_late_window(Duration::from_millis(20)), Some(1));
+    /// ```
+    pub fn recommended_late_window(&self, frame_duration: Duration) -> Option<u64> {
+        let p99 = self.p99()?;
+        let frames = p99.as_nanos().div_ceil(frame_duration.as_nanos().max(1));
+        Some((frames as u64).max(1))
+    }
+}
+
+/// Envoie `count` sondes `Ping` à `target`, une à la fois
+///
+/// Chaque sonde attend sa réponse au plus `wait` ; une réponse tardive à
+/// une sonde précédente est ignorée, comme tout autre paquet reçu.
+pub(crate) async fn ping(
+    transport: &mut (dyn NetworkTransport + Send + Sync),
+    target: SocketAddr,
+    count: u32,
+    sender_id: u64,
+    session_id: u64,
+    wait: Duration,
+) -> NetworkResult<PingReport> {
+    let mut report = PingReport { addr: target, sender_id: None, sent: 0, rtts: Vec::new() };
+
+    for seq in 0..count {
+        let probe = NetworkPacket::new_discovery(&DiscoveryMessage::Ping { seq }, sender_id, session_id);
+        let started_at = Instant::now();
+        transport.send_packet(&probe, target).await?;
+        report.sent += 1;
+
+        while let Some(remaining) = wait.checked_sub(started_at.elapsed()) {
+            let (packet, source) = match timeout(remaining, transport.receive_packet()).await {
+                Ok(Ok(received)) => received,
+                Err(_) => break,
+                Ok(Err(e)) if e.is_packet_level() || matches!(e, NetworkError::Timeout) => continue,
+                Ok(Err(e)) => return Err(e),
+            };
+            if source == target && packet.discovery_message() == Some(DiscoveryMessage::Pong { seq }) {
+                report.rtts.push(started_at.elapsed());
+                report.sender_id = Some(packet.sender_id);
+                break;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Envoie une sonde `Ping` toutes les `interval` pendant `duration`
+///
+/// Les réponses sont collectées entre deux envois, puis au plus `grace`
+/// après la dernière sonde ; une réponse en double est ignorée.
+pub(crate) async fn measure_jitter(
+    transport: &mut (dyn NetworkTransport + Send + Sync),
+    target: SocketAddr,
+    duration: Duration,
+    interval: Duration,
+    sender_id: u64,
+    session_id: u64,
+    grace: Duration,
+) -> NetworkResult<JitterReport> {
+    let count = (duration.as_nanos() / interval.as_nanos().max(1)).max(1) as u32;
+    let mut report = JitterReport { addr: target, probe_interval: interval, rtts: vec![None; count as usize] };
+    let mut sent_at = Vec::with_capacity(count as usize);
+    let started_at = Instant::now();
+
+    for seq in 0..=count {
+        // Sonde suivante, ou fin de la collecte après la dernière
+        let next_at = if seq < count {
+            started_at + interval * seq
+        } else {
+            started_at + interval * count.saturating_sub(1) + grace
+        };
+        while let Some(remaining) = next_at.checked_duration_since(Instant::now()) {
+            let (packet, source) = match timeout(remaining, transport.receive_packet()).await {
+                Ok(Ok(received)) => received,
+                Err(_) => break,
+                Ok(Err(e)) if e.is_packet_level() || matches!(e, NetworkError::Timeout) => continue,
+                Ok(Err(e)) => return Err(e),
+            };
+            if source != target {
+                continue;
+            }
+            if let Some(DiscoveryMessage::Pong { seq: answered }) = packet.discovery_message()
+                && let Some(slot @ None) = report.rtts.get_mut(answered as usize)
+                && let Some(sent) = sent_at.get(answered as usize)
+            {
+                *slot = Some(Instant::now().saturating_duration_since(*sent));
+            }
+            // Toutes les sondes ont reçu leur réponse : inutile d'attendre
+            if sent_at.len() == report.rtts.len() && report.rtts.iter().all(Option::is_some) {
+                break;
+            }
+        }
+
+        if seq < count {
+            let probe = NetworkPacket::new_discovery(&DiscoveryMessage::Ping { seq }, sender_id, session_id);
+            sent_at.push(Instant::now());
+            transport.send_packet(&probe, target).await?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Sonde tous les candidats à la fois et retourne le premier qui répond
+///
+/// Une rafale de `burst` sondes `Ping` part vers chaque candidat toutes les
+/// `interval`, pendant au plus `wait`. Le pair fait de même de son côté :
+/// ses sondes reçues entre-temps obtiennent leur réponse, le moteur de
+/// protocole ne tournant pas pendant la sonde. Un chemin est retenu à la
+/// première réponse `Pong`, ou au premier Hello du pair (qui a alors retenu
+/// ce chemin de son côté et retransmettra son Hello).
+pub(crate) async fn punch_candidates(
+    transport: &mut (dyn NetworkTransport + Send + Sync),
+    candidates: &[SocketAddr],
+    burst: u32,
+    interval: Duration,
+    sender_id: u64,
+    session_id: u64,
+    wait: Duration,
+) -> NetworkResult<Option<SocketAddr>> {
+    let started_at = Instant::now();
+    let deadline = started_at + wait;
+    let mut next_burst = started_at;
+    let mut seq = 0;
+
+    while Instant::now() < deadline {
+        if Instant::now() >= next_burst {
+            let probe = NetworkPacket::new_discovery(&DiscoveryMessage::Ping { seq }, sender_id, session_id);
+            for _ in 0..burst {
+                for &candidate in candidates {
+                    // Un candidat injoignable (adresse privée d'un autre
+                    // réseau) ne doit pas empêcher de sonder les autres
+                    let _ = transport.send_packet(&probe, candidate).await;
+                }
+            }
+            seq += 1;
+            next_burst += interval;
+        }
+
+        let remaining = next_burst.min(deadline).saturating_duration_since(Instant::now());
+        let (packet, source) = match timeout(remaining, transport.receive_packet()).await {
+            Ok(Ok(received)) => received,
+            Err(_) => continue,
+            Ok(Err(e)) if e.is_packet_level() || matches!(e, NetworkError::Timeout) => continue,
+            // Port fermé sur un candidat, signalé par ICMP sur certains systèmes
+            Ok(Err(NetworkError::IoError(e)))
+                if matches!(e.kind(), std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset) => continue,
+            Ok(Err(e)) => return Err(e),
+        };
+        if packet.sender_id == sender_id {
+            continue;
+        }
+
+        match packet.discovery_message() {
+            Some(DiscoveryMessage::Ping { seq: asked }) => {
+                let pong = NetworkPacket::new_discovery(&DiscoveryMessage::Pong { seq: asked }, sender_id, session_id);
+                transport.send_packet(&pong, source).await?;
+            }
+            Some(DiscoveryMessage::Pong { .. }) if candidates.contains(&source) => return Ok(Some(source)),
+            _ if packet.packet_type == PacketType::Handshake && candidates.contains(&source) => return Ok(Some(source)),
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
+
+/// Envoie une sonde et collecte les réponses pendant `wait`
+///
+/// Le transport doit être bindé. Les réponses en double (même adresse)
+/// et les paquets non liés à la découverte sont ignorés.
+pub(crate) async fn discover_peers(
+    transport: &mut (dyn NetworkTransport + Send + Sync),
+    target: SocketAddr,
+    sender_id: u64,
+    session_id: u64,
+    wait: Duration,
+) -> NetworkResult<Vec<DiscoveredPeer>> {
+    let probe = NetworkPacket::new_discovery(&DiscoveryMessage::Probe, sender_id, session_id);
+    let started_at = Instant::now();
+    transport.send_packet(&probe, target).await?;
+
+    let mut peers: Vec<DiscoveredPeer> = Vec::new();
+
+    while let Some(remaining) = wait.checked_sub(started_at.elapsed()) {
+        let (packet, source) = match timeout(remaining, transport.receive_packet()).await {
+            Ok(Ok(received)) => received,
+            Err(_) => break,
+            // Timeout du transport ou paquets invalides d'autres applications :
+            // on continue d'écouter jusqu'à la fin du délai
+            Ok(Err(NetworkError::Timeout))
+            | Ok(Err(NetworkError::InvalidPacketFormat { .. }))
+            | Ok(Err(NetworkError::CorruptedPacket { .. }))
+            | Ok(Err(NetworkError::PacketTooOld { .. })) => continue,
+            Ok(Err(e)) => return Err(e),
+        };
+
+        let is_reply = packet.packet_type == PacketType::Discovery
+            && packet.sender_id != sender_id
+            && packet.discovery_message() == Some(DiscoveryMessage::Reply);
+
+        if is_reply && !peers.iter().any(|peer| peer.addr == source) {
+            peers.push(DiscoveredPeer {
+                addr: source,
+                sender_id: packet.sender_id,
+                response_time: started_at.elapsed(),
+            });
+        }
+    }
+
+    Ok(peers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_packet_roundtrip() {
+        let probe = NetworkPacket::new_discovery(&DiscoveryMessage::Probe, 1, 2);
+        assert_eq!(probe.packet_type, PacketType::Discovery);
+        assert!(probe.verify_checksum());
+        assert_eq!(probe.discovery_message(), Some(DiscoveryMessage::Probe));
+
+        let reply = NetworkPacket::new_discovery(&DiscoveryMessage::Reply, 1, 2);
+        assert_eq!(reply.discovery_message(), Some(DiscoveryMessage::Reply));
+
+        let pong = NetworkPacket::new_discovery(&DiscoveryMessage::Pong { seq: 7 }, 1, 2);
+        assert_eq!(pong.discovery_message(), Some(DiscoveryMessage::Pong { seq: 7 }));
+
+        // Un paquet audio ne porte pas de message de découverte
+        let heartbeat = NetworkPacket::new_heartbeat(1, 2);
+        assert_eq!(heartbeat.discovery_message(), None);
+    }
+
+    #[test]
+    fn test_ping_report() {
+        let mut report = PingReport {
+            addr: "127.0.0.1:9001".parse().unwrap(),
+            sender_id: Some(3),
+            sent: 4,
+            rtts: vec![Duration::from_millis(10), Duration::from_millis(30)],
+        };
+        assert!(report.is_reachable());
+        assert_eq!(report.received(), 2);
+        assert_eq!(report.loss_percent(), 50.0);
+        assert_eq!(report.min_rtt(), Some(Duration::from_millis(10)));
+        assert_eq!(report.avg_rtt(), Some(Duration::from_millis(20)));
+        assert_eq!(report.max_rtt(), Some(Duration::from_millis(30)));
+
+        report.rtts.clear();
+        assert!(!report.is_reachable());
+        assert_eq!(report.avg_rtt(), None);
+        assert_eq!(report.loss_percent(), 100.0);
+    }
+
+    #[test]
+    fn test_jitter_report() {
+        let ms = |ms| Some(Duration::from_millis(ms));
+        let report = JitterReport {
+            addr: "127.0.0.1:9001".parse().unwrap(),
+            probe_interval: Duration::from_millis(20),
+            rtts: vec![ms(10), ms(12), None, ms(40), ms(11), ms(10), ms(10)],
+        };
+        assert_eq!((report.sent(), report.received()), (7, 6));
+        // La sonde perdue interrompt la série : 2, 29, 1, 0
+        let samples: Vec<_> = report.jitter_samples().iter().map(Duration::as_millis).collect();
+        assert_eq!(samples, vec![0, 1, 2, 29]);
+        assert_eq!(report.p50(), Some(Duration::from_millis(1)));
+        assert_eq!(report.p95(), Some(Duration::from_millis(29)));
+        assert_eq!(report.recommended_late_window(Duration::from_millis(20)), Some(2));
+
+        let unreachable = JitterReport { rtts: vec![None; 3], ..report };
+        assert_eq!(unreachable.p99(), None);
+        assert_eq!(unreachable.recommended_late_window(Duration::from_millis(20)), None);
+        assert_eq!(unreachable.loss_percent(), 100.0);
+    }
+}
@@ -0,0 +1,2538 @@
+//! Cœur du protocole, sans entrées-sorties
+//!
+//! `ProtocolEngine` regroupe la logique de session : handshake (retransmissions
+//! avec backoff, départage des Hello croisés, collisions d'ID), reprise des
+//! sessions interrompues, suivi des heartbeats, validation de la source et de
+//! la session, buffer anti-jitter et détection des discontinuités du flux. Il
+//! ne touche ni au réseau ni à l'horloge : il consomme des paquets datés
+//! (`handle_packet(packet, source, now)`) et le passage du temps
+//! (`poll(now)`), et retourne des `ProtocolAction` à exécuter.
+//!
+//! `UdpNetworkManager` n'est qu'un pilote : il envoie et reçoit via le
+//! transport, attend jusqu'à `next_deadline`, et répercute les actions sur
+//! l'état de connexion public. Les scénarios du protocole se testent donc sans
+//! socket ni timer, avec des instants choisis.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use audio::CompressedFrame;
+
+use crate::{
+    BufferStats, CodecParams, ControlMessage, DiscoveryMessage, FlowControlHint, HandshakeInfo, HandshakeMessage, Identity, Liveness, NetworkConfig, NetworkError, NetworkResult,
+    NetworkPacket, PacketPayload, PacketType, PeerIdentity, PeerStatsReport, ProofChallenge, ProtocolErrorCode, StreamDescription, StreamResync, utils
+};
+use crate::types::delay_frames;
+
+/// Action demandée par le moteur au pilote
+#[derive(Debug)]
+pub enum ProtocolAction {
+    /// Paquet à envoyer
+    Send { packet: NetworkPacket, target: SocketAddr },
+
+    /// Handshake terminé : session établie avec le pair
+    Connected { peer_addr: SocketAddr, session_id: u64 },
+
+    /// Session interrompue reprise, éventuellement depuis une nouvelle
+    /// adresse du pair : séquences et codec continuent
+    Resumed { peer_addr: SocketAddr, session_id: u64 },
+
+    /// Nouvelle frame audio du pair, à livrer au moteur d'appel
+    Deliver(CompressedFrame),
+
+    /// Frame sortie dans l'ordre du buffer anti-jitter
+    Buffered(CompressedFrame),
+
+    /// Heartbeat reçu du pair (occasion de mettre à jour le contrôle de congestion)
+    HeartbeatReceived,
+
+    /// Identité annoncée par le pair dans son handshake, fixée après la
+    /// session : prouvée sur notre défi, ou `None` faute de preuve avant
+    /// l'échéance (pair anonyme)
+    PeerIdentified { peer_addr: SocketAddr, identity: Option<PeerIdentity> },
+
+    /// Paquet ignoré : reçu de `addr` alors que la session (ou le handshake)
+    /// est avec `expected`
+    UnexpectedSource { addr: SocketAddr, expected: SocketAddr },
+
+    /// Le pair a mis fin à l'appel (déconnexion ou erreur protocolaire)
+    PeerClosed { reason: String },
+
+    /// Handshake refusé ou expiré, ou pair muet au-delà de `heartbeat_timeout`
+    Failed(NetworkError),
+}
+
+/// Étape de la session vue par le moteur
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// Pas de pair : seuls les Hello et les sondes de découverte sont traités
+    Idle,
+
+    /// Hello envoyé, en attente de la réponse du pair
+    Handshaking {
+        peer_addr: SocketAddr,
+        deadline: Instant,
+        next_send: Instant,
+        retry_interval: Duration,
+    },
+
+    /// Session établie
+    Connected {
+        peer_addr: SocketAddr,
+        last_heartbeat: Instant,
+    },
+}
+
+/// Session interrompue sans déconnexion explicite (silence du pair, erreur
+/// de transport), reprenable pendant `NetworkConfig::resume_grace`
+#[derive(Debug, Clone, Copy)]
+struct SuspendedSession {
+    peer_addr: SocketAddr,
+    /// Dernier signe de vie du pair, point de départ du délai de reprise
+    last_seen: Instant,
+}
+
+/// Logique du protocole Voc, pilotée par paquets et par instants
+///
+/// # Example
+/// ```rust
+/// use std::time::Instant;
+/// use network::{NetworkConfig, ProtocolAction, ProtocolEngine, utils};
+///
+/// let config = NetworkConfig::test_config();
+/// let (caller_addr, callee_addr) = (utils::localhost(9001), utils::localhost(9002));
+/// let mut caller = ProtocolEngine::with_ids(&config, 1, 10);
+/// let mut callee = ProtocolEngine::with_ids(&config, 2, 20);
+/// let now = Instant::now();
+///
+/// // Hello de l'appelant, Accept de l'appelé : l'appelant adopte sa session
+/// let Some(ProtocolAction::Send { packet: hello, .. }) = caller.connect(callee_addr, now).pop() else { panic!() };
+/// let accept = match callee.handle_packet(hello, caller_addr, now).remove(0) {
+///     ProtocolAction::Send { packet, .. } => packet,
+///     other => panic!("{:?}", other),
+/// };
+/// caller.handle_packet(accept, callee_addr, now);
+/// assert!(caller.is_connected() && callee.is_connected());
+/// assert_eq!(caller.session_id(), 20);
+/// ```
+#[derive(Debug)]
+pub struct ProtocolEngine {
+    /// Période des heartbeats (et des rapports de réception)
+    heartbeat_interval: Duration,
+
+    /// Silence du pair au-delà duquel la session est perdue
+    heartbeat_timeout: Duration,
+
+    /// Premier délai de retransmission du Hello
+    handshake_retry_interval: Duration,
+
+    /// Durée maximum du handshake
+    connection_timeout: Duration,
+
+    /// ID local unique
+    sender_id: u64,
+
+    /// ID de session unique
+    session_id: u64,
+
+    /// Numéro de séquence de la dernière frame audio envoyée
+    sequence_counter: u64,
+
+    /// Version de protocole négociée avec le pair (celle de son handshake)
+    peer_protocol_version: u8,
+
+    /// Étape courante de la session
+    phase: Phase,
+
+    /// Buffer anti-jitter pour réception
+    receive_buffer: JitterBuffer,
+
+    /// Délais du buffer anti-jitter en ms, convertis en frames de `frame_duration`
+    target_delay_ms: u32,
+    max_delay_ms: u32,
+
+    /// Dernier rapport de réception reçu du pair
+    remote_stats: Option<PeerStatsReport>,
+
+    /// Dernière indication de contrôle de flux reçue du pair
+    remote_flow: Option<FlowControlHint>,
+
+    /// Frames audio à ne pas envoyer, le buffer du pair débordant
+    pending_skips: u64,
+
+    /// Remplissage du buffer de lecture de l'application, en pourcentage
+    playback_fill_percent: u8,
+
+    /// Envoi du dernier heartbeat portant notre rapport de réception
+    last_stats_sent: Option<Instant>,
+
+    /// Changements de codec annoncés par le pair : (première frame, paramètres)
+    decoder_switches: VecDeque<(u64, CodecParams)>,
+
+    /// Durée d'une frame du flux reçu (suit les changements de codec du pair)
+    frame_duration: Duration,
+
+    /// Discontinuité du flux à signaler au moteur d'appel (`take_stream_resync`)
+    stream_resync: Option<StreamResync>,
+
+    /// Une session a déjà été établie (la suivante est une reprise)
+    has_connected: bool,
+
+    /// Séquence de la dernière frame audio livrée dans la session courante
+    last_delivered_sequence: Option<u64>,
+
+    /// Notre adresse locale, annoncée dans nos handshakes
+    local_addr: Option<SocketAddr>,
+
+    /// Adresse locale annoncée par le pair dans son handshake
+    peer_local_addr: Option<SocketAddr>,
+
+    /// Notre identité, prouvée dans nos handshakes
+    identity: Option<Identity>,
+
+    /// Identité prouvée par le pair sur notre défi
+    peer_identity: Option<PeerIdentity>,
+
+    /// Défi joint à nos handshakes, nouveau à chaque handshake
+    handshake_challenge: u64,
+
+    /// Défi joint au dernier handshake du pair : nos `Accept` y prouvent
+    /// notre identité
+    peer_challenge: Option<ProofChallenge>,
+
+    /// Défi de l'`Accept` du pair, sur lequel notre identité reste à
+    /// prouver (`identity_proof`) jusqu'à acquittement
+    owed_proof: Option<ProofChallenge>,
+
+    /// Preuves d'identité envoyées sans acquittement, et la dernière
+    identity_proofs: u32,
+    last_identity_proof: Option<Instant>,
+
+    /// Échéance de la preuve d'identité annoncée par le pair dans son Hello
+    identity_deadline: Option<Instant>,
+
+    /// Délai de reprise d'une session interrompue (0 : reprise désactivée)
+    resume_grace: Duration,
+
+    /// Jeton de reprise annoncé au pair dans nos handshakes
+    resume_token: u64,
+
+    /// Jeton de reprise annoncé par le pair, à présenter pour reprendre
+    peer_resume_token: Option<u64>,
+
+    /// Dernière session interrompue
+    suspended: Option<SuspendedSession>,
+
+    /// Notre handshake en cours est une reprise (`Resume` au lieu de `Hello`)
+    resuming: bool,
+
+    /// Mode padding demandé (`NetworkConfig::padding`)
+    padding: bool,
+
+    /// Le pair a acquitté notre offre de padding
+    padding_accepted: bool,
+
+    /// Offres de padding envoyées sans acquittement, et la dernière
+    padding_offers: u32,
+    last_padding_offer: Option<Instant>,
+
+    /// Horodatage média demandé au pair (`NetworkConfig::media_timestamps`)
+    media_timestamps: bool,
+
+    /// Le pair a acquitté notre demande d'horodatage
+    media_timestamps_acked: bool,
+
+    /// Demandes d'horodatage envoyées sans acquittement, et la dernière
+    media_timestamp_requests: u32,
+    last_media_timestamp_request: Option<Instant>,
+
+    /// Le pair demande l'horodatage média de nos frames
+    send_media_timestamps: bool,
+
+    /// Données de l'application jointes à nos heartbeats de rapport (une
+    /// fois définies, même vides : le pair voit qu'elles ont été effacées)
+    heartbeat_payload: Option<Vec<u8>>,
+
+    /// Dernières données de l'application du pair, et leur changement pas
+    /// encore lu (`take_peer_heartbeat_payload`)
+    peer_heartbeat_payload: Option<Vec<u8>>,
+    peer_heartbeat_payload_changed: bool,
+
+    /// Description de notre flux, envoyée au pair qui la demande
+    local_stream: StreamDescription,
+
+    /// Échecs consécutifs de décodage du flux du pair
+    decode_failures: u32,
+
+    /// Demandes de description envoyées depuis le dernier décodage réussi,
+    /// et la dernière
+    stream_description_requests: u32,
+    last_stream_description_request: Option<Instant>,
+
+    /// Description reçue du pair, à appliquer au décodeur
+    decoder_reconfigure: Option<StreamDescription>,
+}
+
+impl ProtocolEngine {
+    /// Délai max entre deux retransmissions du handshake (plafond du backoff)
+    pub const HANDSHAKE_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Frames manquantes au-delà desquelles le décodeur est réinitialisé
+    ///
+    /// En deçà, le masquage de pertes d'Opus (PLC) suit ; au-delà (200ms à
+    /// 20ms par frame), son état prédictif ne correspond plus au flux.
+    pub const RESYNC_GAP_FRAMES: u64 = 10;
+
+    /// Frames sautées au plus par indication de saturation du pair (100ms à
+    /// 20ms par frame) : au-delà, le trou serait plus audible que le retard
+    pub const MAX_SKIPS_PER_HINT: u64 = 5;
+
+    /// Offres de padding sans réponse au-delà desquelles le pair est supposé
+    /// ne pas comprendre les paquets complétés
+    pub const MAX_PADDING_OFFERS: u32 = 5;
+
+    /// Demandes d'horodatage sans réponse au-delà desquelles le pair est
+    /// supposé ne pas savoir horodater ses frames
+    pub const MAX_MEDIA_TIMESTAMP_REQUESTS: u32 = 5;
+
+    /// Preuves d'identité sans acquittement au-delà desquelles le pair est
+    /// supposé ne pas les comprendre
+    pub const MAX_IDENTITY_PROOFS: u32 = 5;
+
+    /// Échecs de décodage consécutifs à partir desquels les paramètres du
+    /// flux du pair sont supposés différents des nôtres (200ms à 20ms par
+    /// frame : au-delà d'une rafale de paquets corrompus)
+    pub const DECODE_FAILURES_BEFORE_DESCRIBE: u32 = 10;
+
+    /// Demandes de description sans effet au-delà desquelles le pair est
+    /// supposé ne pas les comprendre (ou les échecs avoir une autre cause)
+    pub const MAX_STREAM_DESCRIPTION_REQUESTS: u32 = 5;
+
+    /// Crée un moteur avec des identifiants aléatoires
+    ///
+    /// # Errors
+    /// - `NetworkError::InitializationError` : source d'aléa du système
+    ///   indisponible (voir `utils::random_id`)
+    pub fn new(config: &NetworkConfig) -> NetworkResult<Self> {
+        Ok(Self::with_ids(config, utils::random_id()?, utils::random_id()?))
+    }
+
+    /// Crée un moteur avec des identifiants choisis (tests reproductibles)
+    ///
+    /// # Arguments
+    /// * `config` - Configuration réseau (délais, taille du buffer anti-jitter)
+    /// * `sender_id` - ID local, non nul
+    /// * `session_id` - ID de session proposé au pair
+    pub fn with_ids(config: &NetworkConfig, sender_id: u64, session_id: u64) -> Self {
+        let frame_duration = Duration::from_millis(CodecParams::voice().frame_duration_ms as u64);
+        let (late_window, max_size) = config.delay_frames(frame_duration);
+        Self {
+            heartbeat_interval: config.heartbeat_interval,
+            heartbeat_timeout: config.heartbeat_timeout,
+            handshake_retry_interval: config.handshake_retry_interval,
+            connection_timeout: config.connection_timeout,
+            sender_id,
+            session_id,
+            sequence_counter: 0,
+            peer_protocol_version: NetworkPacket::CURRENT_PROTOCOL_VERSION,
+            phase: Phase::Idle,
+            receive_buffer: JitterBuffer::new(max_size, late_window)
+                .with_byte_budget(config.max_buffer_bytes, config.max_buffer_bytes_per_sender),
+            target_delay_ms: config.target_delay_ms,
+            max_delay_ms: config.max_delay_ms,
+            remote_stats: None,
+            remote_flow: None,
+            pending_skips: 0,
+            playback_fill_percent: 0,
+            last_stats_sent: None,
+            decoder_switches: VecDeque::new(),
+            frame_duration,
+            stream_resync: None,
+            has_connected: false,
+            last_delivered_sequence: None,
+            local_addr: None,
+            peer_local_addr: None,
+            identity: None,
+            peer_identity: None,
+            handshake_challenge: new_handshake_challenge(),
+            peer_challenge: None,
+            owed_proof: None,
+            identity_proofs: 0,
+            last_identity_proof: None,
+            identity_deadline: None,
+            resume_grace: config.resume_grace,
+            resume_token: new_resume_token(),
+            peer_resume_token: None,
+            suspended: None,
+            resuming: false,
+            padding: config.padding.is_some(),
+            padding_accepted: false,
+            padding_offers: 0,
+            last_padding_offer: None,
+            media_timestamps: config.media_timestamps,
+            media_timestamps_acked: false,
+            media_timestamp_requests: 0,
+            last_media_timestamp_request: None,
+            send_media_timestamps: false,
+            heartbeat_payload: None,
+            peer_heartbeat_payload: None,
+            peer_heartbeat_payload_changed: false,
+            local_stream: StreamDescription::default(),
+            decode_failures: 0,
+            stream_description_requests: 0,
+            last_stream_description_request: None,
+            decoder_reconfigure: None,
+        }
+    }
+
+    /// ID local
+    pub fn sender_id(&self) -> u64 {
+        self.sender_id
+    }
+
+    /// ID de la session courante (adopté du pair si c'est lui qui a accepté)
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    /// Version de protocole utilisée avec le pair
+    pub fn peer_protocol_version(&self) -> u8 {
+        self.peer_protocol_version
+    }
+
+    /// Définit l'adresse locale annoncée dans nos handshakes (connue après le bind)
+    pub fn set_local_addr(&mut self, local_addr: Option<SocketAddr>) {
+        self.local_addr = local_addr;
+    }
+
+    /// Adresse locale annoncée par le pair dans son handshake, si elle l'a été
+    pub fn peer_local_addr(&self) -> Option<SocketAddr> {
+        self.peer_local_addr
+    }
+
+    /// Définit l'identité prouvée dans nos handshakes (voir `Identity`)
+    pub fn set_identity(&mut self, identity: Option<Identity>) {
+        self.identity = identity;
+    }
+
+    /// Notre identité, si elle a été définie
+    pub fn identity(&self) -> Option<PeerIdentity> {
+        self.identity.as_ref().map(Identity::public)
+    }
+
+    /// Identité prouvée par le pair sur notre défi
+    ///
+    /// `None` si le pair n'en annonce pas (versions précédentes), si sa
+    /// preuve est invalide ou rejouée, ou tant qu'elle est attendue
+    /// (`identity_pending`) : il est alors traité comme anonyme.
+    pub fn peer_identity(&self) -> Option<PeerIdentity> {
+        self.peer_identity
+    }
+
+    /// Le pair a annoncé une identité qu'il doit encore prouver : elle sera
+    /// fixée par `ProtocolAction::PeerIdentified`
+    pub fn identity_pending(&self) -> bool {
+        self.identity_deadline.is_some()
+    }
+
+    /// Numéro de séquence de la prochaine frame audio envoyée
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence_counter + 1
+    }
+
+    /// Impose l'ID local (tests de collision)
+    #[cfg(all(test, feature = "udp", feature = "simulator"))]
+    pub(crate) fn set_sender_id(&mut self, sender_id: u64) {
+        self.sender_id = sender_id;
+    }
+
+    /// Session établie
+    pub fn is_connected(&self) -> bool {
+        matches!(self.phase, Phase::Connected { .. })
+    }
+
+    /// Pair de la session (établie ou en cours de handshake)
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        match self.phase {
+            Phase::Idle => None,
+            Phase::Handshaking { peer_addr, .. } | Phase::Connected { peer_addr, .. } => Some(peer_addr),
+        }
+    }
+
+    /// Signes de vie du pair à l'instant `now`
+    ///
+    /// Le pair envoie un heartbeat par `heartbeat_interval` : chaque période
+    /// entière de silence au-delà de la première compte un heartbeat manqué.
+    pub fn liveness(&self, now: Instant) -> Liveness {
+        let Phase::Connected { last_heartbeat, .. } = self.phase else {
+            return Liveness { last_seen: None, missed: 0, healthy: false };
+        };
+        let silence = now.saturating_duration_since(last_heartbeat);
+        let periods = silence.as_millis() / self.heartbeat_interval.as_millis().max(1);
+        let missed = periods.saturating_sub(1).min(u32::MAX as u128) as u32;
+        Liveness { last_seen: Some(last_heartbeat), missed, healthy: missed == 0 }
+    }
+
+    /// Dernier rapport de réception reçu du pair
+    pub fn remote_stats(&self) -> Option<PeerStatsReport> {
+        self.remote_stats
+    }
+
+    /// Nombre de paquets audio en attente dans le buffer anti-jitter
+    pub fn buffered_packets(&self) -> usize {
+        self.receive_buffer.packets.len()
+    }
+
+    /// Durée d'une frame du flux reçu
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    /// Attente des paquets en retard demandée, en ms (avant conversion en frames)
+    pub fn target_delay_ms(&self) -> u32 {
+        self.target_delay_ms
+    }
+
+    /// Change l'attente des paquets en retard (`NetworkConfig::target_delay_ms`)
+    ///
+    /// Convertie en frames du flux reçu et bornée à la capacité du buffer
+    /// anti-jitter moins une frame. Retourne l'attente appliquée, en ms.
+    pub fn set_target_delay_ms(&mut self, delay_ms: u32) -> u32 {
+        self.target_delay_ms = delay_ms;
+        self.resize_receive_buffer();
+        self.receive_buffer.late_window as u32 * self.frame_duration.as_millis() as u32
+    }
+
+    /// Reprend les délais de `config` sans toucher à la session en cours
+    ///
+    /// Heartbeats, handshake, reprise de session et tailles du buffer
+    /// anti-jitter suivent la nouvelle configuration ; les options négociées
+    /// avec le pair (padding, horodatage média) restent celles de la création.
+    pub fn reconfigure(&mut self, config: &NetworkConfig) {
+        self.heartbeat_interval = config.heartbeat_interval;
+        self.heartbeat_timeout = config.heartbeat_timeout;
+        self.handshake_retry_interval = config.handshake_retry_interval;
+        self.connection_timeout = config.connection_timeout;
+        self.resume_grace = config.resume_grace;
+        self.max_delay_ms = config.max_delay_ms;
+        self.set_target_delay_ms(config.target_delay_ms);
+    }
+
+    /// Recalcule les tailles du buffer anti-jitter pour la durée de frame courante
+    fn resize_receive_buffer(&mut self) {
+        let (late_window, max_size) = delay_frames(self.target_delay_ms, self.max_delay_ms, self.frame_duration);
+        self.receive_buffer.late_window = late_window;
+        self.receive_buffer.max_size = max_size;
+    }
+
+    /// Paquets audio reçus (hors doublons)
+    pub fn received_packets(&self) -> u64 {
+        self.receive_buffer.received_packets
+    }
+
+    /// Paquets audio déclarés perdus
+    pub fn lost_packets(&self) -> u64 {
+        self.receive_buffer.lost_packets
+    }
+
+    /// Paquets audio arrivés après que leur créneau a été sauté
+    pub fn late_packets(&self) -> u64 {
+        self.receive_buffer.late_packets
+    }
+
+    /// État du buffer anti-jitter
+    ///
+    /// Les délais sont estimés en frames du flux reçu : `target_delay_ms`
+    /// correspond à l'attente des retardataires, `avg_delay_ms` aux paquets en
+    /// attente. `jitter_ms` est repris tel quel (mesuré par le transport).
+    pub fn buffer_stats(&self, jitter_ms: f32) -> BufferStats {
+        let buffer = &self.receive_buffer;
+        let frame_ms = self.frame_duration.as_secs_f32() * 1000.0;
+        BufferStats {
+            packets_buffered: buffer.packets.len(),
+            packets_dropped: buffer.overflow_drops,
+            duplicates_dropped: buffer.duplicates,
+            fill_level: buffer.packets.len() as f32 / buffer.max_size.max(1) as f32,
+            jitter_ms,
+            avg_delay_ms: buffer.packets.len() as f32 * frame_ms,
+            target_delay_ms: buffer.late_window as f32 * frame_ms,
+            bytes_buffered: buffer.bytes,
+            byte_budget_drops: buffer.byte_budget_drops,
+            sender_cap_drops: buffer.sender_cap_drops,
+            packets_lost: buffer.lost_packets,
+            late_discarded: buffer.late_packets,
+            // Chaque créneau sauté a été masqué, même si le paquet est arrivé ensuite
+            concealments: buffer.lost_packets + buffer.late_packets,
+        }
+    }
+
+    /// Prochain instant où `poll` a quelque chose à faire
+    ///
+    /// Retransmission ou expiration du handshake, expiration des heartbeats ;
+    /// `None` sans session.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        match self.phase {
+            Phase::Idle => None,
+            Phase::Handshaking { deadline, next_send, .. } => Some(next_send.min(deadline)),
+            Phase::Connected { last_heartbeat, .. } => {
+                let timeout = last_heartbeat + self.heartbeat_timeout;
+                Some(self.identity_deadline.map_or(timeout, |deadline| deadline.min(timeout)))
+            }
+        }
+    }
+
+    /// Commence le handshake avec `peer_addr` (le Hello part immédiatement)
+    ///
+    /// Si la session précédente avec `peer_addr` a été interrompue depuis
+    /// moins de `resume_grace`, le Hello est remplacé par un `Resume` : le
+    /// pair qui a gardé la session la reprend, les autres ouvrent une
+    /// nouvelle session comme pour un Hello.
+    pub fn connect(&mut self, peer_addr: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        self.resuming = self.peer_resume_token.is_some()
+            && self.suspended_at(now).is_some_and(|suspended| suspended.peer_addr == peer_addr);
+        if !self.resuming {
+            // Nouvelle session : les jetons des précédentes ne valent plus
+            self.resume_token = new_resume_token();
+        }
+        self.handshake_challenge = new_handshake_challenge();
+        self.phase = Phase::Handshaking {
+            peer_addr,
+            deadline: now + self.connection_timeout,
+            next_send: now,
+            retry_interval: self.handshake_retry_interval,
+        };
+        self.poll(now)
+    }
+
+    /// Fait avancer les timers : retransmission et expiration du handshake,
+    /// silence du pair
+    pub fn poll(&mut self, now: Instant) -> Vec<ProtocolAction> {
+        match self.phase {
+            Phase::Idle => Vec::new(),
+
+            Phase::Handshaking { peer_addr, deadline, .. } if now >= deadline => {
+                self.phase = Phase::Idle;
+                let timeout_ms = self.connection_timeout.as_millis() as u32;
+                vec![ProtocolAction::Failed(NetworkError::connection_timeout(peer_addr, timeout_ms))]
+            }
+
+            Phase::Handshaking { peer_addr, deadline, next_send, retry_interval } => {
+                if now < next_send {
+                    return Vec::new();
+                }
+                self.phase = Phase::Handshaking {
+                    peer_addr,
+                    deadline,
+                    next_send: now + retry_interval,
+                    retry_interval: (retry_interval * 2).min(Self::HANDSHAKE_MAX_RETRY_INTERVAL),
+                };
+                vec![self.send(self.handshake_packet(self.opening_message()), peer_addr)]
+            }
+
+            Phase::Connected { peer_addr, last_heartbeat } => {
+                let mut actions = Vec::new();
+                if self.identity_deadline.is_some_and(|deadline| now >= deadline) {
+                    // Identité annoncée, jamais prouvée : pair anonyme
+                    self.identity_deadline = None;
+                    actions.push(ProtocolAction::PeerIdentified { peer_addr, identity: None });
+                }
+                if now.saturating_duration_since(last_heartbeat) > self.heartbeat_timeout {
+                    self.suspend();
+                    actions.push(ProtocolAction::Failed(NetworkError::PeerDisconnected { addr: peer_addr }));
+                }
+                actions
+            }
+        }
+    }
+
+    /// Traite un paquet reçu de `source` à l'instant `now`
+    ///
+    /// Sans session, seuls les Hello (acceptés) et les sondes de découverte
+    /// sont traités. Pendant le handshake et la session, les paquets d'une
+    /// autre source sont ignorés, sauf les Hello refusés (`ServerFull`) une
+    /// fois la session établie. Une reprise portant notre jeton est acceptée
+    /// de toute source : le pair a pu changer d'adresse.
+    pub fn handle_packet(&mut self, packet: NetworkPacket, source: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        if let Some(HandshakeMessage::Resume { token }) = packet.handshake_message()
+            && self.accepts_resume(token, packet.session_id, now)
+        {
+            return self.accept_resume(&packet, source, now);
+        }
+
+        match self.phase {
+            Phase::Idle => self.handle_idle(packet, source, now),
+            Phase::Handshaking { peer_addr, .. } if source == peer_addr => self.handle_handshake_reply(packet, source, now),
+            Phase::Connected { peer_addr, .. } if source == peer_addr => self.handle_session(packet, source, now),
+            Phase::Connected { .. } if packet.packet_type == PacketType::Handshake => {
+                // Un seul appel à la fois : refuse explicitement les autres clients
+                vec![self.send(self.error_packet(ProtocolErrorCode::ServerFull), source)]
+            }
+            // Les sondes de découverte du LAN sont normales, le reste trahit
+            // une erreur de configuration (port, NAT qui réécrit l'adresse)
+            Phase::Handshaking { peer_addr, .. } | Phase::Connected { peer_addr, .. }
+                if packet.packet_type != PacketType::Discovery =>
+            {
+                vec![ProtocolAction::UnexpectedSource { addr: source, expected: peer_addr }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Sans session : accepte les Hello, répond aux sondes de découverte
+    fn handle_idle(&mut self, packet: NetworkPacket, source: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        match packet.packet_type {
+            // Hello, ou reprise d'une session que nous n'avons plus : nouvelle session
+            PacketType::Handshake if !is_acceptance(packet.handshake_message()) => {
+                // Répond dans la version du pair (qui peut être plus ancienne)
+                self.learn_peer(&packet, now);
+                if let Err(error) = self.resolve_sender_collision(packet.sender_id) {
+                    return self.fail(error);
+                }
+                self.resume_token = new_resume_token();
+                self.handshake_challenge = new_handshake_challenge();
+                let accept = self.send(self.handshake_packet(HandshakeMessage::Accept), source);
+                vec![accept, self.enter_connected(source, now)]
+            }
+            PacketType::Discovery => self.discovery_reply(&packet, source).into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Réponse du pair pendant notre handshake
+    fn handle_handshake_reply(&mut self, packet: NetworkPacket, source: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        // Refus explicite du pair : inutile d'attendre le timeout
+        if let Some(error) = packet.error_message() {
+            self.phase = Phase::Idle;
+            return vec![ProtocolAction::Failed(NetworkError::remote_error(source, error.code, error.description))];
+        }
+
+        match (packet.packet_type, packet.handshake_message()) {
+            // Hello croisé (connexion simultanée) : départage par sender_id
+            (PacketType::Handshake, Some(HandshakeMessage::Hello | HandshakeMessage::Resume { .. })) => {
+                self.learn_peer(&packet, now);
+                if self.wins_tie_break(&packet) {
+                    let accept = self.send(self.handshake_packet(HandshakeMessage::Accept), source);
+                    return vec![accept, self.enter_connected(source, now)];
+                }
+                // Sinon le pair va accepter notre Hello (le perdant change d'ID en cas de collision)
+                match self.resolve_sender_collision(packet.sender_id) {
+                    Ok(()) => Vec::new(),
+                    Err(error) => self.fail(error),
+                }
+            }
+
+            // Reprise acceptée : la session continue
+            (PacketType::Handshake, Some(HandshakeMessage::ResumeAccept)) if self.resumes(&packet) => {
+                self.learn_peer(&packet, now);
+                let mut actions = vec![self.enter_resumed(source, now)];
+                actions.extend(self.identity_proof(now));
+                actions
+            }
+
+            // Accept, ou réponse d'un pair legacy sans message : session du pair adoptée
+            (PacketType::Handshake, _) => {
+                self.learn_peer(&packet, now);
+                if let Err(error) = self.resolve_sender_collision(packet.sender_id) {
+                    return self.fail(error);
+                }
+                self.session_id = packet.session_id;
+                let mut actions = vec![self.enter_connected(source, now)];
+                actions.extend(self.identity_proof(now));
+                actions
+            }
+
+            // Le pair nous considère déjà connectés (son Accept a été perdu)
+            (PacketType::Audio | PacketType::Heartbeat, _) if self.resumes(&packet) => vec![self.enter_resumed(source, now)],
+            (PacketType::Audio | PacketType::Heartbeat, _) => {
+                self.session_id = packet.session_id;
+                vec![self.enter_connected(source, now)]
+            }
+
+            _ => Vec::new(),
+        }
+    }
+
+    /// Paquet du pair pendant la session
+    fn handle_session(&mut self, packet: NetworkPacket, source: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        // Reste d'une session précédente (ou d'une autre instance sur le même port)
+        let in_session = matches!(
+            packet.packet_type,
+            PacketType::Audio | PacketType::Heartbeat | PacketType::Control | PacketType::Padding
+        );
+        if in_session && packet.session_id != self.session_id {
+            return Vec::new();
+        }
+
+        match packet.packet_type {
+            PacketType::Audio => {
+                let Some(frame) = packet.audio_frame().cloned() else {
+                    return Vec::new();
+                };
+                let received_before = self.receive_buffer.received_packets;
+                let accepted = self.receive_buffer.push_packet(packet);
+
+                let mut actions = Vec::new();
+                if accepted {
+                    while let Some(buffered) = self.receive_buffer.pop_packet() {
+                        actions.extend(buffered.into_audio_frame().map(ProtocolAction::Buffered));
+                    }
+                }
+                // Livrée sauf doublon (ex: copie redondante)
+                if self.receive_buffer.received_packets > received_before {
+                    actions.push(ProtocolAction::Deliver(self.track_delivery(frame)));
+                }
+                actions
+            }
+
+            PacketType::Heartbeat => {
+                self.phase = Phase::Connected { peer_addr: source, last_heartbeat: now };
+                if let Some(report) = packet.peer_stats() {
+                    self.remote_stats = Some(report);
+                }
+                if let Some(hint) = packet.flow_control_hint() {
+                    self.record_flow_control(hint);
+                }
+                if let Some(payload) = packet.heartbeat_payload() {
+                    self.record_peer_heartbeat_payload(payload);
+                }
+                vec![ProtocolAction::HeartbeatReceived]
+            }
+
+            PacketType::Handshake => {
+                // Accepte les retransmissions (notre Accept a pu être perdu) ; un
+                // Accept ne reçoit pas de réponse (pas de ping-pong)
+                if is_acceptance(packet.handshake_message()) {
+                    return Vec::new();
+                }
+                self.peer_protocol_version = packet.protocol_version;
+                if let Err(error) = self.resolve_sender_collision(packet.sender_id) {
+                    return self.fail(error);
+                }
+                vec![self.send(self.handshake_packet(HandshakeMessage::Accept), source)]
+            }
+
+            PacketType::Disconnect => {
+                self.phase = Phase::Idle;
+                vec![ProtocolAction::PeerClosed { reason: "déconnexion du pair".to_string() }]
+            }
+
+            PacketType::Error => {
+                // Le pair met fin à la communication (ex: expulsion)
+                self.phase = Phase::Idle;
+                let reason = match packet.error_message() {
+                    Some(error) => format!("erreur du pair ({:?}) : {}", error.code, error.description),
+                    None => "erreur du pair".to_string(),
+                };
+                vec![ProtocolAction::PeerClosed { reason }]
+            }
+
+            PacketType::Control => match packet.control_message() {
+                // Le pair change de codec : acquitte (y compris les retransmissions)
+                Some(ControlMessage::Renegotiate { params, switch_at }) => {
+                    if !self.decoder_switches.iter().any(|(sequence, _)| *sequence == switch_at) {
+                        self.decoder_switches.push_back((switch_at, params));
+                    }
+                    let ack = NetworkPacket::new_control(
+                        &ControlMessage::RenegotiateAck { switch_at },
+                        self.sender_id,
+                        self.session_id,
+                    );
+                    vec![self.send(ack, source)]
+                }
+                // Les paquets complétés et factices du pair sont compris
+                Some(ControlMessage::Padding) => {
+                    let ack = NetworkPacket::new_control(&ControlMessage::PaddingAck, self.sender_id, self.session_id);
+                    vec![self.send(ack, source)]
+                }
+                Some(ControlMessage::PaddingAck) => {
+                    self.padding_accepted = self.padding;
+                    Vec::new()
+                }
+                // Le pair sait lire l'horodatage : nos frames le portent désormais
+                Some(ControlMessage::MediaTimestamps) => {
+                    self.send_media_timestamps = true;
+                    let ack = NetworkPacket::new_control(&ControlMessage::MediaTimestampsAck, self.sender_id, self.session_id);
+                    vec![self.send(ack, source)]
+                }
+                Some(ControlMessage::MediaTimestampsAck) => {
+                    self.media_timestamps_acked = true;
+                    Vec::new()
+                }
+                // Le pair ne décode pas nos frames : il recevra nos paramètres
+                Some(ControlMessage::DescribeStream) => {
+                    let description = NetworkPacket::new_control(
+                        &ControlMessage::StreamDescribed(self.local_stream),
+                        self.sender_id,
+                        self.session_id,
+                    );
+                    vec![self.send(description, source)]
+                }
+                Some(ControlMessage::StreamDescribed(description)) => {
+                    self.decoder_reconfigure = Some(description);
+                    self.decode_failures = 0;
+                    Vec::new()
+                }
+                // Preuve sur notre défi : acquittée (y compris les retransmissions)
+                Some(ControlMessage::IdentityProof(proof)) => {
+                    let challenge = ProofChallenge {
+                        sender_id: self.sender_id,
+                        session_id: self.session_id,
+                        nonce: self.handshake_challenge,
+                    };
+                    let Some(identity) = proof.verify(packet.sender_id, packet.session_id, &challenge) else {
+                        return Vec::new();
+                    };
+                    let ack = NetworkPacket::new_control(&ControlMessage::IdentityProofAck, self.sender_id, self.session_id);
+                    let mut actions = vec![self.send(ack, source)];
+                    if self.identity_deadline.take().is_some() {
+                        self.peer_identity = Some(identity);
+                        actions.push(ProtocolAction::PeerIdentified { peer_addr: source, identity: Some(identity) });
+                    }
+                    actions
+                }
+                Some(ControlMessage::IdentityProofAck) => {
+                    self.owed_proof = None;
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            },
+
+            // Paquet factice : rien à livrer
+            PacketType::Padding => Vec::new(),
+
+            PacketType::Discovery => self.discovery_reply(&packet, source).into_iter().collect(),
+        }
+    }
+
+    /// Entre en session avec `peer_addr`
+    fn enter_connected(&mut self, peer_addr: SocketAddr, now: Instant) -> ProtocolAction {
+        self.phase = Phase::Connected { peer_addr, last_heartbeat: now };
+
+        // Le pair repart d'un codec neuf : le nôtre doit en faire autant
+        if self.has_connected {
+            self.stream_resync = Some(StreamResync::SessionRestart { session_id: self.session_id });
+        }
+        self.has_connected = true;
+        self.last_delivered_sequence = None;
+        self.suspended = None;
+        self.resuming = false;
+
+        ProtocolAction::Connected { peer_addr, session_id: self.session_id }
+    }
+
+    /// Reprend la session interrompue, avec le pair à `peer_addr`
+    ///
+    /// Séquences, buffer anti-jitter et codecs continuent : pas de
+    /// `StreamResync::SessionRestart`, le trou dans le flux reçu est traité
+    /// comme des pertes (`SequenceJump` au-delà de `RESYNC_GAP_FRAMES`).
+    fn enter_resumed(&mut self, peer_addr: SocketAddr, now: Instant) -> ProtocolAction {
+        self.phase = Phase::Connected { peer_addr, last_heartbeat: now };
+        self.suspended = None;
+        self.resuming = false;
+        ProtocolAction::Resumed { peer_addr, session_id: self.session_id }
+    }
+
+    /// Quitte la session en la gardant reprenable pendant `resume_grace`
+    fn suspend(&mut self) {
+        if let Phase::Connected { peer_addr, last_heartbeat } = self.phase {
+            self.suspended = Some(SuspendedSession { peer_addr, last_seen: last_heartbeat });
+        }
+        self.phase = Phase::Idle;
+    }
+
+    /// Session interrompue encore reprenable à l'instant `now`
+    fn suspended_at(&self, now: Instant) -> Option<SuspendedSession> {
+        self.suspended.filter(|suspended| now <= suspended.last_seen + self.resume_grace)
+    }
+
+    /// Le `Resume` du pair désigne-t-il notre session, encore reprenable ?
+    ///
+    /// La session peut être interrompue de notre côté, ou toujours en cours :
+    /// le pair a changé d'adresse avant que son silence soit constaté.
+    fn accepts_resume(&self, token: u64, session_id: u64, now: Instant) -> bool {
+        if self.resume_grace.is_zero() || token != self.resume_token || session_id != self.session_id {
+            return false;
+        }
+        matches!(self.phase, Phase::Connected { .. }) || self.suspended_at(now).is_some()
+    }
+
+    /// Accepte la reprise de notre session par le pair, depuis `source`
+    fn accept_resume(&mut self, packet: &NetworkPacket, source: SocketAddr, now: Instant) -> Vec<ProtocolAction> {
+        self.learn_peer(packet, now);
+        self.handshake_challenge = new_handshake_challenge();
+        let accept = self.send(self.handshake_packet(HandshakeMessage::ResumeAccept), source);
+        // Retransmission (notre réponse a été perdue) : la session a déjà repris
+        if let Phase::Connected { peer_addr, .. } = self.phase
+            && peer_addr == source
+        {
+            return vec![accept];
+        }
+        vec![accept, self.enter_resumed(source, now)]
+    }
+
+    /// Réponse du pair à notre `Resume`, pour la session interrompue ?
+    fn resumes(&self, packet: &NetworkPacket) -> bool {
+        self.resuming && packet.session_id == self.session_id
+    }
+
+    /// Message qui ouvre notre handshake : reprise si possible, sinon Hello
+    fn opening_message(&self) -> HandshakeMessage {
+        match self.peer_resume_token {
+            Some(token) if self.resuming => HandshakeMessage::Resume { token },
+            _ => HandshakeMessage::Hello,
+        }
+    }
+
+    /// Retient ce qu'annonce le handshake du pair (version, adresse locale,
+    /// jeton de reprise, défi, identité)
+    ///
+    /// Une réponse à notre handshake prouve l'identité du pair sur notre
+    /// défi, et nous doit la preuve de la nôtre sur le sien. Dans une
+    /// ouverture (Hello, Resume), l'identité n'est qu'annoncée : rejouable,
+    /// elle attend la preuve du pair jusqu'à `connection_timeout`.
+    fn learn_peer(&mut self, packet: &NetworkPacket, now: Instant) {
+        self.peer_protocol_version = packet.protocol_version;
+        // Un pair d'une version précédente ne voit que 32 bits de nos identifiants
+        self.sender_id = NetworkPacket::wire_id(self.sender_id, packet.protocol_version);
+        self.session_id = NetworkPacket::wire_id(self.session_id, packet.protocol_version);
+        self.peer_local_addr = packet.handshake_info().map(|info| info.local_addr);
+        self.peer_resume_token = packet.resume_token();
+        self.peer_challenge = packet.handshake_challenge();
+        let identity = packet.handshake_identity().filter(|_| self.peer_challenge.is_some());
+
+        if is_acceptance(packet.handshake_message()) {
+            let challenge = ProofChallenge {
+                sender_id: self.sender_id,
+                session_id: self.session_id,
+                nonce: self.handshake_challenge,
+            };
+            self.peer_identity = identity.and_then(|identity| identity.verify(packet.sender_id, packet.session_id, &challenge));
+            self.owed_proof = self.peer_challenge.filter(|_| self.identity.is_some());
+            self.identity_proofs = 0;
+            self.last_identity_proof = None;
+            self.identity_deadline = None;
+        } else {
+            self.peer_identity = None;
+            self.identity_deadline = identity.map(|_| now + self.connection_timeout);
+        }
+    }
+
+    /// Met fin à la session côté local
+    ///
+    /// Retourne le paquet de déconnexion à envoyer au pair s'il y en avait un.
+    /// Les paramètres propres au pair (version, rapport, changements de codec)
+    /// sont oubliés, et la session n'est plus reprenable.
+    pub fn disconnect(&mut self) -> Option<ProtocolAction> {
+        let peer_addr = self.peer_addr();
+        let goodbye = peer_addr.map(|addr| self.send(self.disconnect_packet(), addr));
+        self.close();
+        self.suspended = None;
+        self.peer_resume_token = None;
+        goodbye
+    }
+
+    /// Abandonne la session sans prévenir le pair (erreur de transport, fin d'appel)
+    ///
+    /// Une session établie reste reprenable pendant `resume_grace` (voir
+    /// `connect`) ; `disconnect` y met fin.
+    pub fn close(&mut self) {
+        self.suspend();
+        self.peer_protocol_version = NetworkPacket::CURRENT_PROTOCOL_VERSION;
+        self.remote_stats = None;
+        self.last_stats_sent = None;
+        self.remote_flow = None;
+        self.pending_skips = 0;
+        self.playback_fill_percent = 0;
+        self.decoder_switches.clear();
+        self.peer_local_addr = None;
+        self.padding_accepted = false;
+        self.padding_offers = 0;
+        self.last_padding_offer = None;
+        self.media_timestamps_acked = false;
+        self.media_timestamp_requests = 0;
+        self.last_media_timestamp_request = None;
+        self.send_media_timestamps = false;
+        self.peer_heartbeat_payload = None;
+        self.peer_heartbeat_payload_changed = false;
+        self.owed_proof = None;
+        self.identity_deadline = None;
+        self.decode_failures = 0;
+        self.stream_description_requests = 0;
+        self.last_stream_description_request = None;
+        self.decoder_reconfigure = None;
+    }
+
+    /// Offre de padding au pair, au plus une par `heartbeat_interval`
+    ///
+    /// En mode padding, l'offre est renouvelée jusqu'à son acquittement
+    /// (`padding_accepted`), dans la limite de `MAX_PADDING_OFFERS` : un pair
+    /// d'une version précédente l'ignore, et ne reçoit alors jamais de
+    /// paquets complétés ou factices qu'il ne saurait pas lire.
+    pub fn padding_offer(&mut self, now: Instant) -> Option<ProtocolAction> {
+        let Phase::Connected { peer_addr, .. } = self.phase else {
+            return None;
+        };
+        if !self.padding || self.padding_accepted || self.padding_offers >= Self::MAX_PADDING_OFFERS {
+            return None;
+        }
+        let due = self.last_padding_offer
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.heartbeat_interval);
+        if !due {
+            return None;
+        }
+
+        self.padding_offers += 1;
+        self.last_padding_offer = Some(now);
+        let offer = NetworkPacket::new_control(&ControlMessage::Padding, self.sender_id, self.session_id);
+        Some(self.send(offer, peer_addr))
+    }
+
+    /// Demande d'horodatage média au pair, au plus une par `heartbeat_interval`
+    ///
+    /// Renouvelée comme l'offre de padding (voir `padding_offer`), dans la
+    /// limite de `MAX_MEDIA_TIMESTAMP_REQUESTS` : un pair d'une version
+    /// précédente l'ignore, et ses frames restent sans horodatage.
+    pub fn media_timestamps_request(&mut self, now: Instant) -> Option<ProtocolAction> {
+        let Phase::Connected { peer_addr, .. } = self.phase else {
+            return None;
+        };
+        if !self.media_timestamps
+            || self.media_timestamps_acked
+            || self.media_timestamp_requests >= Self::MAX_MEDIA_TIMESTAMP_REQUESTS
+        {
+            return None;
+        }
+        let due = self.last_media_timestamp_request
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.heartbeat_interval);
+        if !due {
+            return None;
+        }
+
+        self.media_timestamp_requests += 1;
+        self.last_media_timestamp_request = Some(now);
+        let request = NetworkPacket::new_control(&ControlMessage::MediaTimestamps, self.sender_id, self.session_id);
+        Some(self.send(request, peer_addr))
+    }
+
+    /// Preuve de notre identité sur le défi de l'`Accept` du pair, au plus
+    /// une par `heartbeat_interval`
+    ///
+    /// La première part avec la session ; renouvelée jusqu'à son
+    /// acquittement, dans la limite de `MAX_IDENTITY_PROOFS` comme l'offre
+    /// de padding (voir `padding_offer`).
+    pub fn identity_proof(&mut self, now: Instant) -> Option<ProtocolAction> {
+        let Phase::Connected { peer_addr, .. } = self.phase else {
+            return None;
+        };
+        let (Some(identity), Some(challenge)) = (&self.identity, self.owed_proof) else {
+            return None;
+        };
+        if self.identity_proofs >= Self::MAX_IDENTITY_PROOFS {
+            return None;
+        }
+        let due = self.last_identity_proof
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.heartbeat_interval);
+        if !due {
+            return None;
+        }
+
+        self.identity_proofs += 1;
+        self.last_identity_proof = Some(now);
+        let proof = identity.challenge_proof(self.sender_id, self.session_id, &challenge);
+        let packet = NetworkPacket::new_control(&ControlMessage::IdentityProof(proof), self.sender_id, self.session_id);
+        Some(self.send(packet, peer_addr))
+    }
+
+    /// Le pair a accepté le padding : nos paquets audio doivent être
+    /// complétés, et les créneaux sans audio comblés (`padding_packet`)
+    pub fn padding_accepted(&self) -> bool {
+        self.padding_accepted
+    }
+
+    /// Paquet factice à envoyer à la place d'une frame audio
+    ///
+    /// `None` hors session ou tant que le pair n'a pas accepté le padding.
+    pub fn padding_packet(&self) -> Option<ProtocolAction> {
+        match self.phase {
+            Phase::Connected { peer_addr, .. } if self.padding_accepted => {
+                Some(self.send(NetworkPacket::new_padding(self.sender_id, self.session_id), peer_addr))
+            }
+            _ => None,
+        }
+    }
+
+    /// Crée le paquet de la prochaine frame audio (numéro de séquence attribué)
+    pub fn prepare_audio(&mut self, frame: CompressedFrame) -> NetworkPacket {
+        self.sequence_counter += 1;
+        let mut frame_with_sequence = frame;
+        frame_with_sequence.sequence_number = self.sequence_counter;
+        // Horodatage seulement pour un pair qui l'a demandé (il sait le lire)
+        if !self.send_media_timestamps {
+            frame_with_sequence.media_timestamp = None;
+        }
+
+        let mut packet = NetworkPacket::new_audio(frame_with_sequence, self.sender_id, self.session_id);
+        packet.protocol_version = self.peer_protocol_version;
+        packet
+    }
+
+    /// Heartbeat portant notre rapport de réception, au plus une fois par
+    /// `heartbeat_interval`
+    ///
+    /// # Arguments
+    /// * `now` - Instant courant
+    /// * `jitter_ms` - Gigue mesurée par le transport, incluse dans le rapport
+    pub fn stats_heartbeat(&mut self, now: Instant, jitter_ms: f32) -> Option<ProtocolAction> {
+        let Phase::Connected { peer_addr, .. } = self.phase else {
+            return None;
+        };
+        let due = self.last_stats_sent
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.heartbeat_interval);
+        if !due {
+            return None;
+        }
+
+        // Les pairs des versions précédentes reçoivent le rapport seul
+        let report = self.receive_report(jitter_ms);
+        let mut packet = if self.peer_protocol_version != NetworkPacket::CURRENT_PROTOCOL_VERSION {
+            NetworkPacket::new_heartbeat_with_stats(&report, self.sender_id, self.session_id)
+        } else {
+            let payload = self.heartbeat_payload.as_deref().unwrap_or_default();
+            NetworkPacket::new_heartbeat_with_losses(&report, self.flow_control_hint(), payload, self.sender_id, self.session_id)
+        };
+        packet.protocol_version = self.peer_protocol_version;
+        packet.checksum = packet.calculate_checksum();
+
+        self.last_stats_sent = Some(now);
+        Some(self.send(packet, peer_addr))
+    }
+
+    /// Rapport de réception local, à destination du pair
+    pub fn receive_report(&self, jitter_ms: f32) -> PeerStatsReport {
+        let received = self.receive_buffer.received_packets;
+        let lost = self.receive_buffer.lost_packets;
+        let loss_percent = if received + lost == 0 {
+            0.0
+        } else {
+            lost as f32 / (received + lost) as f32 * 100.0
+        };
+
+        PeerStatsReport {
+            packets_received: received,
+            packets_lost: lost,
+            loss_percent,
+            jitter_ms,
+        }
+    }
+
+    /// Relève l'occupation du buffer de lecture de l'application
+    ///
+    /// # Arguments
+    /// * `buffered_frames` - Frames en attente de lecture
+    /// * `capacity` - Capacité du buffer de lecture, en frames
+    pub fn set_playback_level(&mut self, buffered_frames: usize, capacity: usize) {
+        let fill = buffered_frames * 100 / capacity.max(1);
+        self.playback_fill_percent = fill.min(100) as u8;
+    }
+
+    /// Occupation locale des buffers de réception, à destination du pair
+    ///
+    /// Le remplissage annoncé est le plus élevé du buffer anti-jitter et du
+    /// buffer de lecture (voir `set_playback_level`).
+    pub fn flow_control_hint(&self) -> FlowControlHint {
+        let buffer = &self.receive_buffer;
+        let jitter_fill = (buffer.packets.len() * 100 / buffer.max_size.max(1)).min(100) as u8;
+        FlowControlHint {
+            buffer_fill_percent: jitter_fill.max(self.playback_fill_percent),
+            overflow_drops: buffer.overflow_drops,
+        }
+    }
+
+    /// Prend en compte l'indication de contrôle de flux du pair
+    ///
+    /// Une frame est sautée par indication de saturation ; si le pair a
+    /// éjecté des paquets depuis l'indication précédente, autant de frames
+    /// (au plus `MAX_SKIPS_PER_HINT`) pour ramener son buffer à niveau.
+    fn record_flow_control(&mut self, hint: FlowControlHint) {
+        if hint.is_congested(self.remote_flow.as_ref()) {
+            let new_drops = hint.overflow_drops.saturating_sub(self.remote_flow.map_or(0, |previous| previous.overflow_drops));
+            self.pending_skips = new_drops.clamp(1, Self::MAX_SKIPS_PER_HINT);
+        }
+        self.remote_flow = Some(hint);
+    }
+
+    /// Retient les données de l'application du pair (signalées si elles ont changé)
+    ///
+    /// Des données vides n'effacent que des données déjà reçues.
+    fn record_peer_heartbeat_payload(&mut self, payload: Vec<u8>) {
+        let unchanged = match &self.peer_heartbeat_payload {
+            Some(previous) => *previous == payload,
+            None => payload.is_empty(),
+        };
+        if !unchanged {
+            self.peer_heartbeat_payload = Some(payload);
+            self.peer_heartbeat_payload_changed = true;
+        }
+    }
+
+    /// La prochaine frame audio doit-elle être sautée (buffer du pair saturé) ?
+    ///
+    /// À appeler avant `prepare_audio` : une frame sautée ne consomme pas de
+    /// numéro de séquence, le pair ne la compte donc pas comme perdue.
+    pub fn take_flow_control_skip(&mut self) -> bool {
+        if self.pending_skips == 0 {
+            return false;
+        }
+        self.pending_skips -= 1;
+        true
+    }
+
+    /// Prochaine frame du buffer anti-jitter, livrée au moteur d'appel
+    pub fn pop_buffered(&mut self) -> Option<CompressedFrame> {
+        let frame = self.receive_buffer.pop_packet()?.into_audio_frame()?;
+        Some(self.track_delivery(frame))
+    }
+
+    /// Livre une frame reçue au moteur d'appel en repérant les sauts de séquence
+    fn track_delivery(&mut self, frame: CompressedFrame) -> CompressedFrame {
+        let sequence = frame.sequence_number;
+        if let Some(last) = self.last_delivered_sequence
+            && sequence > last + Self::RESYNC_GAP_FRAMES
+            && !matches!(self.stream_resync, Some(StreamResync::SessionRestart { .. }))
+        {
+            self.stream_resync = Some(StreamResync::SequenceJump { from: last, to: sequence });
+        }
+        // Une frame en retard (attente `target_delay_ms`) ne recule pas la référence
+        self.last_delivered_sequence = Some(self.last_delivered_sequence.map_or(sequence, |last| last.max(sequence)));
+        frame
+    }
+
+    /// Paramètres à appliquer au décodeur avant de décoder la frame `sequence`
+    /// (voir `UdpNetworkManager::take_decoder_switch`)
+    pub fn take_decoder_switch(&mut self, sequence: u64) -> Option<CodecParams> {
+        let mut params = None;
+        while let Some(&(switch_at, next)) = self.decoder_switches.front() {
+            if switch_at > sequence {
+                break;
+            }
+            params = Some(next);
+            self.frame_duration = Duration::from_millis(next.frame_duration_ms as u64);
+            self.decoder_switches.pop_front();
+        }
+        // Mêmes délais en ms, quelle que soit la durée des nouvelles frames
+        if params.is_some() {
+            self.resize_receive_buffer();
+        }
+        params
+    }
+
+    /// Données jointes à nos prochains heartbeats de rapport (voir
+    /// `UdpNetworkManager::set_heartbeat_payload`)
+    pub fn set_heartbeat_payload(&mut self, payload: Vec<u8>) {
+        self.heartbeat_payload = Some(payload);
+    }
+
+    /// Nouvelles données de l'application du pair depuis le dernier appel
+    /// (voir `UdpNetworkManager::take_peer_heartbeat_payload`)
+    pub fn take_peer_heartbeat_payload(&mut self) -> Option<Vec<u8>> {
+        if !std::mem::take(&mut self.peer_heartbeat_payload_changed) {
+            return None;
+        }
+        self.peer_heartbeat_payload.clone()
+    }
+
+    /// Description de notre flux, envoyée au pair qui la demande
+    pub fn stream_description(&self) -> StreamDescription {
+        self.local_stream
+    }
+
+    /// Change la description de notre flux (nouvel encodeur)
+    pub fn set_stream_description(&mut self, description: StreamDescription) {
+        self.local_stream = description;
+    }
+
+    /// Prend en compte le décodage d'une frame du pair
+    ///
+    /// Au-delà de `DECODE_FAILURES_BEFORE_DESCRIBE` échecs consécutifs, la
+    /// description du flux est demandée au pair, au plus une fois par
+    /// `heartbeat_interval` et `MAX_STREAM_DESCRIPTION_REQUESTS` fois tant
+    /// qu'aucun décodage ne réussit (voir `take_decoder_reconfigure`).
+    pub fn record_decode_result(&mut self, success: bool, now: Instant) -> Option<ProtocolAction> {
+        if success {
+            self.decode_failures = 0;
+            self.stream_description_requests = 0;
+            self.last_stream_description_request = None;
+            return None;
+        }
+        self.decode_failures = self.decode_failures.saturating_add(1);
+
+        let Phase::Connected { peer_addr, .. } = self.phase else {
+            return None;
+        };
+        if self.decode_failures < Self::DECODE_FAILURES_BEFORE_DESCRIBE
+            || self.peer_protocol_version != NetworkPacket::CURRENT_PROTOCOL_VERSION
+            || self.stream_description_requests >= Self::MAX_STREAM_DESCRIPTION_REQUESTS
+        {
+            return None;
+        }
+        let due = self.last_stream_description_request
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.heartbeat_interval);
+        if !due {
+            return None;
+        }
+
+        self.stream_description_requests += 1;
+        self.last_stream_description_request = Some(now);
+        let request = NetworkPacket::new_control(&ControlMessage::DescribeStream, self.sender_id, self.session_id);
+        Some(self.send(request, peer_addr))
+    }
+
+    /// Description du flux du pair reçue depuis le dernier appel
+    /// (voir `UdpNetworkManager::take_decoder_reconfigure`)
+    pub fn take_decoder_reconfigure(&mut self) -> Option<StreamDescription> {
+        self.decoder_reconfigure.take()
+    }
+
+    /// Discontinuité du flux reçu depuis le dernier appel
+    /// (voir `UdpNetworkManager::take_stream_resync`)
+    pub fn take_stream_resync(&mut self) -> Option<StreamResync> {
+        self.stream_resync.take()
+    }
+
+    /// Départage une connexion simultanée : le plus petit (sender_id, session_id)
+    /// prend le rôle de serveur
+    fn wins_tie_break(&self, peer_packet: &NetworkPacket) -> bool {
+        (self.sender_id, self.session_id) < (peer_packet.sender_id, peer_packet.session_id)
+    }
+
+    /// Change de `sender_id` s'il est identique à celui du pair
+    ///
+    /// Deux pairs de même ID ne se distinguent plus (sondes de découverte
+    /// ignorées, départage des Hello croisés) : celui qui détecte la collision
+    /// pendant le handshake tire un nouvel ID avant de répondre.
+    ///
+    /// Le nouvel ID est réduit à ce qu'en voit un pair d'une version
+    /// précédente. Échoue si la source d'aléa du système est indisponible.
+    fn resolve_sender_collision(&mut self, peer_sender_id: u64) -> NetworkResult<()> {
+        if self.sender_id != peer_sender_id {
+            return Ok(());
+        }
+        let previous = self.sender_id;
+        while self.sender_id == peer_sender_id {
+            self.sender_id = NetworkPacket::wire_id(utils::random_id()?, self.peer_protocol_version);
+        }
+        println!("Collision d'ID émetteur avec le pair ({:016x}) : nouvel ID {:016x}", previous, self.sender_id);
+        Ok(())
+    }
+
+    /// Abandonne le handshake ou la session sur une erreur locale
+    fn fail(&mut self, error: NetworkError) -> Vec<ProtocolAction> {
+        self.phase = Phase::Idle;
+        vec![ProtocolAction::Failed(error)]
+    }
+
+    /// Réponse à une sonde de découverte LAN ou à un ping (pas à nos propres sondes)
+    fn discovery_reply(&self, packet: &NetworkPacket, source: SocketAddr) -> Option<ProtocolAction> {
+        if packet.sender_id == self.sender_id {
+            return None;
+        }
+        let reply = match packet.discovery_message()? {
+            DiscoveryMessage::Probe => DiscoveryMessage::Reply,
+            DiscoveryMessage::Ping { seq } => DiscoveryMessage::Pong { seq },
+            DiscoveryMessage::Reply
+            | DiscoveryMessage::Pong { .. }
+            | DiscoveryMessage::Announce { .. }
+            | DiscoveryMessage::RelayAnnounce => return None,
+        };
+        let reply = NetworkPacket::new_discovery(&reply, self.sender_id, self.session_id);
+        Some(self.send(reply, source))
+    }
+
+    fn send(&self, packet: NetworkPacket, target: SocketAddr) -> ProtocolAction {
+        ProtocolAction::Send { packet, target }
+    }
+
+    /// Crée un paquet handshake dans la version du pair, avec checksum correct
+    ///
+    /// Notre adresse locale y est jointe si elle est connue et que le pair
+    /// parle la version courante, suivie de notre jeton de reprise si la
+    /// reprise est activée, puis de notre identité et de notre défi si
+    /// l'identité est définie. L'identité d'une réponse est prouvée sur le
+    /// défi du pair ; celle d'une ouverture n'est qu'annoncée.
+    pub fn handshake_packet(&self, message: HandshakeMessage) -> NetworkPacket {
+        let mut packet = match self.local_addr {
+            Some(local_addr) if self.peer_protocol_version == NetworkPacket::CURRENT_PROTOCOL_VERSION => {
+                let info = HandshakeInfo { local_addr };
+                if let Some(identity) = &self.identity {
+                    let token = if self.resume_grace.is_zero() { 0 } else { self.resume_token };
+                    let proof = match self.peer_challenge.filter(|_| is_acceptance(Some(message))) {
+                        Some(challenge) => identity.challenge_proof(self.sender_id, self.session_id, &challenge),
+                        None => identity.handshake_proof(self.sender_id, self.session_id),
+                    };
+                    NetworkPacket::new_handshake_with_challenge(
+                        message, info, token, &proof, self.handshake_challenge, self.sender_id, self.session_id,
+                    )
+                } else if self.resume_grace.is_zero() {
+                    NetworkPacket::new_handshake_with_info(message, info, self.sender_id, self.session_id)
+                } else {
+                    NetworkPacket::new_handshake_with_token(message, info, self.resume_token, self.sender_id, self.session_id)
+                }
+            }
+            _ => NetworkPacket::new_handshake(message, self.sender_id, self.session_id),
+        };
+        packet.protocol_version = self.peer_protocol_version;
+        packet.checksum = packet.calculate_checksum();
+        packet
+    }
+
+    /// Crée un heartbeat simple dans la version du pair
+    pub fn heartbeat_packet(&self) -> NetworkPacket {
+        let mut packet = NetworkPacket::new_heartbeat(self.sender_id, self.session_id);
+        packet.protocol_version = self.peer_protocol_version;
+        packet.checksum = packet.calculate_checksum();
+        packet
+    }
+
+    /// Crée une erreur protocolaire (description par défaut du code)
+    pub fn error_packet(&self, code: ProtocolErrorCode) -> NetworkPacket {
+        NetworkPacket::new_error(code, code.default_description(), self.sender_id, self.session_id)
+    }
+
+    /// Crée un paquet disconnect dans la version du pair, avec checksum correct
+    pub fn disconnect_packet(&self) -> NetworkPacket {
+        let mut packet = NetworkPacket::new(PacketType::Disconnect, PacketPayload::None, self.sender_id, self.session_id);
+        packet.protocol_version = self.peer_protocol_version;
+        packet.checksum = packet.calculate_checksum();
+        packet
+    }
+}
+
+/// Message d'acceptation (qui n'appelle pas de réponse)
+fn is_acceptance(message: Option<HandshakeMessage>) -> bool {
+    matches!(message, Some(HandshakeMessage::Accept | HandshakeMessage::ResumeAccept))
+}
+
+/// Nouveau défi de handshake, imprévisible pour un tiers
+fn new_handshake_challenge() -> u64 {
+    getrandom::u64().unwrap_or_else(|_| fastrand::u64(..))
+}
+
+/// Nouveau jeton de reprise, imprévisible pour un tiers
+fn new_resume_token() -> u64 {
+    // 0 annonce une reprise désactivée (voir `NetworkPacket::resume_token`)
+    getrandom::u64().unwrap_or_else(|_| fastrand::u64(..)).max(1)
+}
+
+/// Buffer anti-jitter simple pour les paquets réseau
+/// 
+/// Compense les variations de latence réseau en buffering intelligemment
+/// les paquets avant de les livrer à l'application.
+///
+/// La mémoire est bornée en paquets (`max_size`) et en octets : un pair qui
+/// envoie des frames de taille maximale (mode musique, ou malveillant) ne
+/// peut pas dépasser `max_bytes`, ni `max_bytes_per_sender` pour un même
+/// `sender_id`.
+#[derive(Debug)]
+struct JitterBuffer {
+    /// Paquets en attente, triés par numéro de séquence
+    packets: std::collections::BTreeMap<u64, NetworkPacket>,
+
+    /// Taille maximum du buffer
+    max_size: usize,
+
+    /// Octets maximum en attente, tous expéditeurs confondus
+    max_bytes: usize,
+
+    /// Octets maximum en attente pour un même expéditeur
+    max_bytes_per_sender: usize,
+
+    /// Octets en attente (voir `buffered_size`)
+    bytes: usize,
+
+    /// Octets en attente par expéditeur
+    sender_bytes: std::collections::HashMap<u64, usize>,
+
+    /// Paquets éjectés pour tenir le budget global en octets
+    byte_budget_drops: u64,
+
+    /// Paquets éjectés pour tenir le plafond d'un expéditeur
+    sender_cap_drops: u64,
+
+    /// Numéro de séquence attendu
+    expected_sequence: u64,
+
+    /// Paquets perdus détectés
+    lost_packets: u64,
+
+    /// Paquets éjectés faute de place (buffer plein, en paquets ou en octets)
+    overflow_drops: u64,
+
+    /// Nombre de paquets plus récents attendus avant de déclarer un trou perdu
+    late_window: u64,
+
+    /// Numéros sautés récemment, pour reconnaître un retardataire d'un doublon
+    skipped: std::collections::BTreeSet<u64>,
+
+    /// Paquets arrivés après que leur créneau a été sauté
+    late_packets: u64,
+
+    /// Paquets audio reçus (hors doublons)
+    received_packets: u64,
+
+    /// Doublons écartés (copies redondantes, retransmissions)
+    duplicates: u64,
+}
+
+impl JitterBuffer {
+    /// Crée un nouveau buffer anti-jitter
+    /// 
+    /// # Arguments
+    /// * `max_size` - Nombre maximum de paquets en attente
+    /// * `late_window` - Paquets plus récents attendus avant de sauter un trou
+    fn new(max_size: usize, late_window: u64) -> Self {
+        Self {
+            packets: std::collections::BTreeMap::new(),
+            max_size,
+            max_bytes: usize::MAX,
+            max_bytes_per_sender: usize::MAX,
+            bytes: 0,
+            sender_bytes: std::collections::HashMap::new(),
+            byte_budget_drops: 0,
+            sender_cap_drops: 0,
+            expected_sequence: 1,
+            lost_packets: 0,
+            overflow_drops: 0,
+            late_window,
+            skipped: std::collections::BTreeSet::new(),
+            late_packets: 0,
+            received_packets: 0,
+            duplicates: 0,
+        }
+    }
+
+    /// Borne la mémoire du buffer en octets (illimitée par défaut)
+    ///
+    /// # Arguments
+    /// * `max_bytes` - Octets maximum en attente
+    /// * `max_bytes_per_sender` - Octets maximum en attente par `sender_id`
+    fn with_byte_budget(mut self, max_bytes: usize, max_bytes_per_sender: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.max_bytes_per_sender = max_bytes_per_sender;
+        self
+    }
+
+    /// Octets comptés pour un paquet en attente : en-tête et frame compressée
+    fn buffered_size(packet: &NetworkPacket) -> usize {
+        NetworkPacket::HEADER_SIZE + packet.audio_frame().map_or(0, |frame| frame.data.len())
+    }
+
+    /// Range un paquet et compte ses octets
+    fn store(&mut self, sequence: u64, packet: NetworkPacket) {
+        let size = Self::buffered_size(&packet);
+        self.bytes += size;
+        *self.sender_bytes.entry(packet.sender_id).or_default() += size;
+        self.packets.insert(sequence, packet);
+    }
+
+    /// Retire un paquet et décompte ses octets
+    fn take(&mut self, sequence: u64) -> Option<NetworkPacket> {
+        let packet = self.packets.remove(&sequence)?;
+        let size = Self::buffered_size(&packet);
+        self.bytes -= size;
+        if let std::collections::hash_map::Entry::Occupied(mut entry) = self.sender_bytes.entry(packet.sender_id) {
+            *entry.get_mut() -= size;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+        Some(packet)
+    }
+
+    /// Éjecte le paquet `sequence` faute de place
+    ///
+    /// Le plus ancien fait sauter la lecture juste après lui ; un paquet
+    /// plus récent laisse un trou, traité comme une perte à la lecture.
+    fn evict(&mut self, sequence: u64) {
+        let oldest = self.packets.first_key_value().map(|(oldest, _)| *oldest);
+        if self.take(sequence).is_some() {
+            if oldest == Some(sequence) {
+                self.skip_to(sequence + 1);
+            }
+            self.overflow_drops += 1;
+        }
+    }
+
+    /// Ajoute un paquet au buffer
+    /// 
+    /// Retourne true si le paquet a été accepté
+    /// 
+    /// Quand le buffer déborde, l'audio le plus récent est prioritaire : les
+    /// paquets les plus anciens sont éjectés et la lecture saute directement
+    /// après eux, pour ne pas accumuler de latence. Un expéditeur au-delà de
+    /// son plafond perd d'abord ses propres paquets les plus anciens.
+    fn push_packet(&mut self, packet: NetworkPacket) -> bool {
+        let sequence = packet.sequence_number();
+        let sender_id = packet.sender_id;
+
+        // Créneau déjà sauté : le paquet n'était pas perdu mais en retard
+        if self.skipped.remove(&sequence) {
+            self.lost_packets = self.lost_packets.saturating_sub(1);
+            self.late_packets += 1;
+            self.received_packets += 1;
+            return false;
+        }
+
+        // Rejette les paquets trop anciens ou en double
+        if sequence < self.expected_sequence || self.packets.contains_key(&sequence) {
+            self.duplicates += 1;
+            return false;
+        }
+        self.received_packets += 1;
+
+        // Ajoute le paquet puis éjecte les plus anciens si le buffer déborde
+        // (y compris le nouveau paquet s'il est lui-même le plus ancien)
+        self.store(sequence, packet);
+        while self.sender_bytes.get(&sender_id).is_some_and(|bytes| *bytes > self.max_bytes_per_sender) {
+            let oldest_from_sender = self.packets.iter()
+                .find(|(_, buffered)| buffered.sender_id == sender_id)
+                .map(|(oldest, _)| *oldest);
+            let Some(oldest) = oldest_from_sender else { break };
+            self.evict(oldest);
+            self.sender_cap_drops += 1;
+        }
+        while self.packets.len() > self.max_size || self.bytes > self.max_bytes {
+            let Some(oldest) = self.packets.first_key_value().map(|(oldest, _)| *oldest) else { break };
+            if self.packets.len() <= self.max_size {
+                self.byte_budget_drops += 1;
+            }
+            self.evict(oldest);
+        }
+
+        self.packets.contains_key(&sequence)
+    }
+
+    /// Avance la lecture jusqu'à `sequence`, en comptant comme perdus les
+    /// numéros sautés qui n'ont jamais été reçus
+    fn skip_to(&mut self, sequence: u64) {
+        let skipped = sequence.saturating_sub(self.expected_sequence);
+        // Le paquet éjecté lui-même a été reçu : il n'est pas compté perdu
+        self.lost_packets += skipped.saturating_sub(1);
+        let missing_end = sequence.saturating_sub(1);
+        let history_start = missing_end.saturating_sub(self.max_size as u64);
+        for missing in self.expected_sequence.max(history_start)..missing_end {
+            self.mark_skipped(missing);
+        }
+        self.expected_sequence = self.expected_sequence.max(sequence);
+    }
+
+    /// Mémorise un numéro sauté (historique borné à la taille du buffer)
+    fn mark_skipped(&mut self, sequence: u64) {
+        self.skipped.insert(sequence);
+        while self.skipped.len() > self.max_size {
+            self.skipped.pop_first();
+        }
+    }
+
+    /// Récupère le prochain paquet dans l'ordre
+    fn pop_packet(&mut self) -> Option<NetworkPacket> {
+        // Cherche le paquet avec le numéro de séquence attendu
+        if let Some(packet) = self.take(self.expected_sequence) {
+            self.expected_sequence += 1;
+            return Some(packet);
+        }
+
+        // Si pas trouvé, le paquet attendu n'est déclaré perdu qu'une fois
+        // assez de paquets plus récents reçus (fenêtre de retard dépassée) ;
+        // tous les paquets en attente sont alors plus récents que lui
+        if self.packets.len() as u64 > self.late_window {
+            self.lost_packets += 1;
+            let missing = self.expected_sequence;
+            self.mark_skipped(missing);
+            self.expected_sequence += 1;
+
+            // Réessaie avec le nouveau numéro attendu
+            return self.pop_packet();
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALLER: SocketAddr = SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 9001);
+    const CALLEE: SocketAddr = SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 9002);
+
+    /// Paquets à envoyer parmi les actions
+    fn sent(actions: Vec<ProtocolAction>) -> Vec<NetworkPacket> {
+        actions.into_iter()
+            .filter_map(|action| match action {
+                ProtocolAction::Send { packet, .. } => Some(packet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Appelant (1, session 10) connecté à l'appelé (2, session 20) à `now`
+    fn connected_pair(config: &NetworkConfig, now: Instant) -> (ProtocolEngine, ProtocolEngine) {
+        let mut caller = ProtocolEngine::with_ids(config, 1, 10);
+        let mut callee = ProtocolEngine::with_ids(config, 2, 20);
+        for hello in sent(caller.connect(CALLEE, now)) {
+            for accept in sent(callee.handle_packet(hello, CALLER, now)) {
+                caller.handle_packet(accept, CALLEE, now);
+            }
+        }
+        assert!(caller.is_connected() && callee.is_connected());
+        (caller, callee)
+    }
+
+    fn audio(engine: &mut ProtocolEngine, sequence: u64) -> NetworkPacket {
+        let mut packet = engine.prepare_audio(CompressedFrame::new(vec![0; 20], 960, Instant::now(), 0));
+        if let PacketPayload::Audio(frame) = &mut packet.payload {
+            frame.sequence_number = sequence;
+        }
+        packet
+    }
+
+    #[test]
+    fn test_handshake_backoff_and_timeout() {
+        let config = NetworkConfig::test_config();
+        let retry = config.handshake_retry_interval;
+        let t0 = Instant::now();
+        let mut engine = ProtocolEngine::with_ids(&config, 1, 10);
+
+        assert_eq!(sent(engine.connect(CALLEE, t0)).len(), 1);
+        assert_eq!(engine.next_deadline(), Some(t0 + retry));
+        assert!(engine.poll(t0 + retry / 2).is_empty());
+
+        // Retransmissions à intervalle doublé
+        assert_eq!(sent(engine.poll(t0 + retry)).len(), 1);
+        assert_eq!(engine.next_deadline(), Some(t0 + retry * 3));
+        assert!(engine.poll(t0 + retry * 2).is_empty());
+        assert_eq!(sent(engine.poll(t0 + retry * 3)).len(), 1);
+
+        // Expiration du handshake
+        match engine.poll(t0 + config.connection_timeout).pop() {
+            Some(ProtocolAction::Failed(NetworkError::ConnectionTimeout { addr, .. })) => assert_eq!(addr, CALLEE),
+            other => panic!("Timeout attendu, obtenu {:?}", other),
+        }
+        assert_eq!(engine.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_crossed_hellos_converge() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let mut low = ProtocolEngine::with_ids(&config, 1, 10);
+        let mut high = ProtocolEngine::with_ids(&config, 2, 20);
+
+        let hello_low = sent(low.connect(CALLEE, now)).remove(0);
+        let hello_high = sent(high.connect(CALLER, now)).remove(0);
+
+        // Le plus petit ID accepte, l'autre attend son Accept
+        let actions = low.handle_packet(hello_high, CALLEE, now);
+        assert!(matches!(actions.last(), Some(ProtocolAction::Connected { session_id: 10, .. })));
+        assert!(high.handle_packet(hello_low, CALLER, now).is_empty());
+
+        for accept in sent(actions) {
+            high.handle_packet(accept, CALLER, now);
+        }
+        assert!(high.is_connected());
+        assert_eq!(high.session_id(), low.session_id());
+    }
+
+    #[test]
+    fn test_legacy_peer_sees_narrowed_ids() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let mut caller = ProtocolEngine::with_ids(&config, 1, 10);
+        let mut callee = ProtocolEngine::with_ids(&config, 2 | 7 << 32, 20 | 7 << 32);
+
+        // Hello d'un pair v3 : notre session se réduit à ses 32 bits de poids faible
+        let mut hello = sent(caller.connect(CALLEE, now)).remove(0);
+        hello.protocol_version = 3;
+        hello.checksum = hello.calculate_checksum();
+        let actions = callee.handle_packet(hello, CALLER, now);
+        assert!(matches!(actions.last(), Some(ProtocolAction::Connected { session_id: 20, .. })));
+        let accept = sent(actions).remove(0);
+        assert_eq!((accept.protocol_version, accept.sender_id, accept.session_id), (3, 2, 20));
+
+        // Les paquets du pair portent la session telle qu'il l'a reçue
+        caller.handle_packet(accept, CALLEE, now);
+        let packet = audio(&mut caller, 1);
+        assert_eq!((packet.protocol_version, packet.session_id), (3, 20));
+        assert!(callee.handle_packet(packet, CALLER, now).iter().any(|action| matches!(action, ProtocolAction::Deliver(_))));
+    }
+
+    #[test]
+    fn test_handshake_announces_local_addr() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let mut caller = ProtocolEngine::with_ids(&config, 1, 10);
+        let mut callee = ProtocolEngine::with_ids(&config, 2, 20);
+        // Appelant derrière un NAT : son adresse locale diffère de CALLER
+        let behind_nat: SocketAddr = "192.168.1.10:41234".parse().unwrap();
+        caller.set_local_addr(Some(behind_nat));
+        callee.set_local_addr(Some(CALLEE));
+
+        for hello in sent(caller.connect(CALLEE, now)) {
+            for accept in sent(callee.handle_packet(hello, CALLER, now)) {
+                caller.handle_packet(accept, CALLEE, now);
+            }
+        }
+        assert!(caller.is_connected());
+        assert_eq!(callee.peer_local_addr(), Some(behind_nat));
+        assert_eq!(caller.peer_local_addr(), Some(CALLEE));
+
+        // Sans adresse connue, rien n'est annoncé
+        let (caller, callee) = connected_pair(&config, now);
+        assert_eq!(caller.peer_local_addr(), None);
+        assert_eq!(callee.peer_local_addr(), None);
+    }
+
+    #[test]
+    fn test_handshake_proves_identity() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let caller_identity = Identity::from_secret_bytes([1; 32]);
+        let callee_identity = Identity::from_secret_bytes([2; 32]);
+        let mut caller = ProtocolEngine::with_ids(&config, 1, 10);
+        let mut callee = ProtocolEngine::with_ids(&config, 2, 20);
+        caller.set_local_addr(Some(CALLER));
+        caller.set_identity(Some(caller_identity.clone()));
+        callee.set_local_addr(Some(CALLEE));
+        callee.set_identity(Some(callee_identity.clone()));
+
+        let hello = sent(caller.connect(CALLEE, now)).remove(0);
+        assert!(hello.resume_token().is_some());
+        let accept = sent(callee.handle_packet(hello.clone(), CALLER, now)).remove(0);
+        assert!(callee.is_connected() && callee.identity_pending());
+
+        // L'Accept prouve l'identité de l'appelé sur le défi du Hello, l'appelant
+        // prouve ensuite la sienne sur le défi de l'Accept
+        let proof = sent(caller.handle_packet(accept, CALLEE, now)).remove(0);
+        assert_eq!(caller.peer_identity(), Some(callee_identity.public()));
+        assert!(matches!(proof.control_message(), Some(ControlMessage::IdentityProof(_))));
+        assert_eq!(callee.peer_identity(), None);
+        let actions = callee.handle_packet(proof.clone(), CALLER, now);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            ProtocolAction::PeerIdentified { identity: Some(identity), .. } if *identity == caller_identity.public()
+        )));
+        assert_eq!(callee.peer_identity(), Some(caller_identity.public()));
+        assert!(!callee.identity_pending());
+
+        // Preuve acquittée : plus renouvelée
+        for ack in sent(actions) {
+            caller.handle_packet(ack, CALLEE, now);
+        }
+        assert!(caller.identity_proof(now + config.heartbeat_interval).is_none());
+
+        // Hello et preuve capturés, rejoués à l'appelé relancé : son nouveau
+        // défi n'est pas couvert, le pair reste anonyme
+        let mut replayed_to = ProtocolEngine::with_ids(&config, 2, 20);
+        replayed_to.set_identity(Some(callee_identity));
+        replayed_to.handle_packet(hello, CALLER, now);
+        assert!(replayed_to.is_connected() && replayed_to.identity_pending());
+        assert!(replayed_to.handle_packet(proof, CALLER, now).is_empty());
+        assert_eq!(replayed_to.peer_identity(), None);
+        let expired = replayed_to.poll(now + config.connection_timeout);
+        assert!(matches!(expired.first(), Some(ProtocolAction::PeerIdentified { identity: None, .. })));
+        assert_eq!(replayed_to.peer_identity(), None);
+    }
+
+    #[test]
+    fn test_session_resumption() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let mut caller = ProtocolEngine::with_ids(&config, 1, 10);
+        let mut callee = ProtocolEngine::with_ids(&config, 2, 20);
+        caller.set_local_addr(Some(CALLER));
+        callee.set_local_addr(Some(CALLEE));
+        for hello in sent(caller.connect(CALLEE, t0)) {
+            for accept in sent(callee.handle_packet(hello, CALLER, t0)) {
+                caller.handle_packet(accept, CALLEE, t0);
+            }
+        }
+        let first = audio(&mut caller, 1);
+        callee.handle_packet(first, CALLER, t0);
+
+        // Changement de réseau côté appelant : un seul Resume, depuis sa nouvelle adresse
+        let moved: SocketAddr = "127.0.0.1:9003".parse().unwrap();
+        caller.close();
+        let resume = sent(caller.connect(CALLEE, t0)).remove(0);
+        assert!(matches!(resume.handshake_message(), Some(HandshakeMessage::Resume { .. })));
+        let actions = callee.handle_packet(resume, moved, t0);
+        assert!(matches!(actions[1], ProtocolAction::Resumed { peer_addr, session_id: 20 } if peer_addr == moved));
+        for reply in sent(actions) {
+            assert!(matches!(caller.handle_packet(reply, CALLEE, t0)[..], [ProtocolAction::Resumed { session_id: 20, .. }]));
+        }
+
+        // Même session : les séquences continuent, le codec n'est pas réinitialisé
+        let second = audio(&mut caller, 2);
+        assert_eq!(second.session_id, 20);
+        assert!(matches!(callee.handle_packet(second, moved, t0)[..], [.., ProtocolAction::Deliver(_)]));
+        assert_eq!(callee.take_stream_resync(), None);
+        assert_eq!(caller.take_stream_resync(), None);
+
+        // Au-delà du délai de reprise, le Resume ouvre une nouvelle session
+        let token = caller.peer_resume_token.unwrap();
+        callee.poll(t0 + config.heartbeat_timeout * 2);
+        let late = t0 + config.resume_grace + Duration::from_millis(1);
+        let resume = caller.handshake_packet(HandshakeMessage::Resume { token });
+        let replies = sent(callee.handle_packet(resume, moved, late));
+        assert_eq!(replies[0].handshake_message(), Some(HandshakeMessage::Accept));
+        assert_eq!(callee.take_stream_resync(), Some(StreamResync::SessionRestart { session_id: 20 }));
+        // Le délai est aussi passé pour l'appelant : il ne tente plus de reprise
+        caller.close();
+        assert_eq!(sent(caller.connect(CALLEE, late))[0].handshake_message(), Some(HandshakeMessage::Hello));
+
+        // Après une déconnexion explicite, plus de reprise
+        caller.handle_packet(replies[0].clone(), CALLEE, late);
+        assert!(caller.is_connected());
+        caller.disconnect();
+        assert_eq!(sent(caller.connect(CALLEE, late))[0].handshake_message(), Some(HandshakeMessage::Hello));
+    }
+
+    #[test]
+    fn test_heartbeat_timeout() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let (caller, mut callee) = connected_pair(&config, t0);
+
+        assert!(callee.poll(t0 + config.heartbeat_timeout).is_empty());
+
+        // Un heartbeat repousse l'expiration
+        let t1 = t0 + config.heartbeat_timeout;
+        let heartbeat = caller.heartbeat_packet();
+        assert!(matches!(callee.handle_packet(heartbeat, CALLER, t1)[..], [ProtocolAction::HeartbeatReceived]));
+        assert_eq!(callee.next_deadline(), Some(t1 + config.heartbeat_timeout));
+        assert!(callee.poll(t1 + config.heartbeat_timeout).is_empty());
+
+        match callee.poll(t1 + config.heartbeat_timeout + Duration::from_millis(1)).pop() {
+            Some(ProtocolAction::Failed(NetworkError::PeerDisconnected { addr })) => assert_eq!(addr, CALLER),
+            other => panic!("Pair perdu attendu, obtenu {:?}", other),
+        }
+        assert!(!callee.is_connected());
+    }
+
+    #[test]
+    fn test_liveness_counts_missed_heartbeats() {
+        let config = NetworkConfig::test_config();
+        let interval = config.heartbeat_interval;
+        let t0 = Instant::now();
+        let (caller, mut callee) = connected_pair(&config, t0);
+
+        // Un heartbeat en retard n'est pas encore manqué
+        let liveness = callee.liveness(t0 + interval * 3 / 2);
+        assert_eq!((liveness.last_seen, liveness.missed, liveness.healthy), (Some(t0), 0, true));
+
+        let liveness = callee.liveness(t0 + interval * 3);
+        assert_eq!((liveness.missed, liveness.healthy), (2, false));
+        assert_eq!(liveness.unresponsive_for(t0 + interval * 3), Some(interval * 3));
+
+        // Le heartbeat suivant remet le compteur à zéro
+        let t1 = t0 + interval * 3;
+        callee.handle_packet(caller.heartbeat_packet(), CALLER, t1);
+        let liveness = callee.liveness(t1);
+        assert_eq!((liveness.last_seen, liveness.missed, liveness.healthy), (Some(t1), 0, true));
+
+        callee.close();
+        assert_eq!(callee.liveness(t1), Liveness { last_seen: None, missed: 0, healthy: false });
+    }
+
+    #[test]
+    fn test_session_and_source_validation() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, now);
+        let intruder: SocketAddr = "127.0.0.1:9003".parse().unwrap();
+
+        // Audio d'une autre source ou d'une autre session : ignoré (la
+        // source inattendue est signalée)
+        assert!(matches!(
+            callee.handle_packet(audio(&mut caller, 1), intruder, now)[..],
+            [ProtocolAction::UnexpectedSource { addr, expected: CALLER }] if addr == intruder
+        ));
+        let mut stale = audio(&mut caller, 1);
+        stale.session_id = 99;
+        assert!(callee.handle_packet(stale, CALLER, now).is_empty());
+
+        // Audio valide livré une seule fois
+        let packet = audio(&mut caller, 1);
+        assert!(callee.handle_packet(packet.clone(), CALLER, now).iter().any(|action| matches!(action, ProtocolAction::Deliver(_))));
+        assert!(!callee.handle_packet(packet, CALLER, now).iter().any(|action| matches!(action, ProtocolAction::Deliver(_))));
+        assert_eq!(callee.received_packets(), 1);
+
+        // Un second client est refusé pendant l'appel
+        let mut other = ProtocolEngine::with_ids(&config, 3, 30);
+        let hello = sent(other.connect(CALLEE, now)).remove(0);
+        let refusal = sent(callee.handle_packet(hello, intruder, now)).remove(0);
+        assert_eq!(refusal.error_message().map(|error| error.code), Some(ProtocolErrorCode::ServerFull));
+
+        // Déconnexion du pair
+        let goodbye = match caller.disconnect() {
+            Some(ProtocolAction::Send { packet, target }) => { assert_eq!(target, CALLEE); packet }
+            other => panic!("Paquet de déconnexion attendu, obtenu {:?}", other),
+        };
+        assert!(matches!(callee.handle_packet(goodbye, CALLER, now)[..], [ProtocolAction::PeerClosed { .. }]));
+        assert!(!callee.is_connected());
+    }
+
+    #[test]
+    fn test_stats_heartbeat_once_per_interval() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+
+        let report = match callee.stats_heartbeat(t0, 4.0) {
+            Some(ProtocolAction::Send { packet, .. }) => packet,
+            other => panic!("Heartbeat attendu, obtenu {:?}", other),
+        };
+        assert!(callee.stats_heartbeat(t0 + config.heartbeat_interval / 2, 4.0).is_none());
+        assert!(callee.stats_heartbeat(t0 + config.heartbeat_interval, 4.0).is_some());
+
+        caller.handle_packet(report, CALLEE, t0);
+        assert_eq!(caller.remote_stats().map(|stats| stats.jitter_ms), Some(4.0));
+    }
+
+    #[test]
+    fn test_overflowing_peer_makes_sender_skip_frames() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+        let frame = || CompressedFrame::new(vec![0; 20], 960, Instant::now(), 0);
+        let heartbeat = |callee: &mut ProtocolEngine, at| match callee.stats_heartbeat(at, 0.0) {
+            Some(ProtocolAction::Send { packet, .. }) => packet,
+            other => panic!("Heartbeat attendu, obtenu {:?}", other),
+        };
+
+        // Lecture à jour : l'émetteur continue normalement
+        callee.set_playback_level(2, 10);
+        caller.handle_packet(heartbeat(&mut callee, t0), CALLEE, t0);
+        assert!(!caller.take_flow_control_skip());
+
+        // Lecture en retard : une frame sautée par indication
+        callee.set_playback_level(9, 10);
+        assert_eq!(callee.flow_control_hint(), FlowControlHint { buffer_fill_percent: 90, overflow_drops: 0 });
+        let t1 = t0 + config.heartbeat_interval;
+        caller.handle_packet(heartbeat(&mut callee, t1), CALLEE, t1);
+        assert!(caller.take_flow_control_skip());
+        assert!(!caller.take_flow_control_skip());
+
+        // Les frames sautées ne consomment pas de numéro de séquence
+        assert_eq!(caller.prepare_audio(frame()).sequence_number(), 1);
+
+        // Paquets éjectés par le pair : autant de frames sautées, dans la limite
+        let report = callee.receive_report(0.0);
+        let hint = FlowControlHint { buffer_fill_percent: 50, overflow_drops: 20 };
+        caller.handle_packet(NetworkPacket::new_heartbeat_with_feedback(&report, hint, 2, caller.session_id()), CALLEE, t1);
+        let skipped = std::iter::from_fn(|| caller.take_flow_control_skip().then_some(())).count() as u64;
+        assert_eq!(skipped, ProtocolEngine::MAX_SKIPS_PER_HINT);
+
+        // Un pair d'une version précédente reçoit le rapport seul
+        callee.peer_protocol_version = NetworkPacket::MIN_SUPPORTED_PROTOCOL_VERSION;
+        let legacy = heartbeat(&mut callee, t1 + config.heartbeat_interval);
+        assert!(legacy.peer_stats().is_some());
+        assert_eq!(legacy.flow_control_hint(), None);
+    }
+
+    #[test]
+    fn test_heartbeat_payload_event() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+        let heartbeat = |caller: &mut ProtocolEngine, at| match caller.stats_heartbeat(at, 0.0) {
+            Some(ProtocolAction::Send { packet, .. }) => packet,
+            other => panic!("Heartbeat attendu, obtenu {:?}", other),
+        };
+
+        // Sans données : heartbeat de rapport habituel
+        callee.handle_packet(heartbeat(&mut caller, t0), CALLER, t0);
+        assert_eq!(callee.take_peer_heartbeat_payload(), None);
+
+        // Signalées une fois, puis seulement quand elles changent
+        caller.set_heartbeat_payload(b"batterie=80".to_vec());
+        let t1 = t0 + config.heartbeat_interval;
+        callee.handle_packet(heartbeat(&mut caller, t1), CALLER, t1);
+        assert_eq!(callee.take_peer_heartbeat_payload(), Some(b"batterie=80".to_vec()));
+        let t2 = t1 + config.heartbeat_interval;
+        callee.handle_packet(heartbeat(&mut caller, t2), CALLER, t2);
+        assert_eq!(callee.take_peer_heartbeat_payload(), None);
+
+        // Effacées
+        caller.set_heartbeat_payload(Vec::new());
+        let t3 = t2 + config.heartbeat_interval;
+        callee.handle_packet(heartbeat(&mut caller, t3), CALLER, t3);
+        assert_eq!(callee.take_peer_heartbeat_payload(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_stream_description_after_decode_failures() {
+        let config = NetworkConfig::test_config();
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+        let description = StreamDescription { sample_rate: 16000, ..StreamDescription::default() };
+        caller.set_stream_description(description);
+
+        // Une rafale d'échecs isolés ne déclenche rien
+        for _ in 1..ProtocolEngine::DECODE_FAILURES_BEFORE_DESCRIBE {
+            assert!(callee.record_decode_result(false, t0).is_none());
+        }
+        assert!(callee.record_decode_result(true, t0).is_none());
+        for _ in 1..ProtocolEngine::DECODE_FAILURES_BEFORE_DESCRIBE {
+            assert!(callee.record_decode_result(false, t0).is_none());
+        }
+
+        let request = sent(callee.record_decode_result(false, t0).into_iter().collect());
+        assert_eq!(request[0].control_message(), Some(ControlMessage::DescribeStream));
+        assert!(callee.record_decode_result(false, t0).is_none());
+
+        let reply = sent(caller.handle_packet(request[0].clone(), CALLEE, t0));
+        assert_eq!(reply[0].control_message(), Some(ControlMessage::StreamDescribed(description)));
+        callee.handle_packet(reply[0].clone(), CALLER, t0);
+        assert_eq!(callee.take_decoder_reconfigure(), Some(description));
+        assert_eq!(callee.take_decoder_reconfigure(), None);
+    }
+
+    #[test]
+    fn test_padding_negotiation() {
+        let mut config = NetworkConfig::test_config();
+        config.padding = Some(crate::PaddingConfig::default());
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+
+        // Pas de paquet factice avant l'acquittement du pair
+        assert!(caller.padding_packet().is_none());
+        let offer = sent(caller.padding_offer(t0).into_iter().collect());
+        assert_eq!(offer[0].control_message(), Some(ControlMessage::Padding));
+        assert!(caller.padding_offer(t0 + config.heartbeat_interval / 2).is_none());
+
+        let ack = sent(callee.handle_packet(offer[0].clone(), CALLER, t0));
+        assert_eq!(ack[0].control_message(), Some(ControlMessage::PaddingAck));
+        caller.handle_packet(ack[0].clone(), CALLEE, t0);
+        assert!(caller.padding_accepted());
+        assert!(caller.padding_offer(t0 + config.heartbeat_interval).is_none());
+
+        // Les paquets factices sont acceptés sans rien livrer
+        let dummy = sent(caller.padding_packet().into_iter().collect());
+        assert_eq!(dummy[0].packet_type, PacketType::Padding);
+        assert!(callee.handle_packet(dummy[0].clone(), CALLER, t0).is_empty());
+
+        // Fin de session : le pair suivant devra acquitter à nouveau
+        caller.close();
+        assert!(!caller.padding_accepted());
+    }
+
+    #[test]
+    fn test_padding_offers_stop_without_answer() {
+        let mut config = NetworkConfig::test_config();
+        config.padding = Some(crate::PaddingConfig::default());
+        let t0 = Instant::now();
+        let (mut caller, _callee) = connected_pair(&config, t0);
+
+        // Pair d'une version précédente : l'offre reste sans réponse
+        let offers = (0..10)
+            .filter_map(|round| caller.padding_offer(t0 + config.heartbeat_interval * round))
+            .count();
+        assert_eq!(offers as u32, ProtocolEngine::MAX_PADDING_OFFERS);
+        assert!(caller.padding_packet().is_none());
+
+        // Sans mode padding, aucune offre
+        let (mut plain, _) = connected_pair(&NetworkConfig::test_config(), t0);
+        assert!(plain.padding_offer(t0).is_none());
+    }
+
+    #[test]
+    fn test_media_timestamps_negotiation() {
+        let mut config = NetworkConfig::test_config();
+        config.media_timestamps = true;
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+        let frame = |media_timestamp| {
+            let mut frame = CompressedFrame::new(vec![0; 20], 960, Instant::now(), 0);
+            frame.media_timestamp = Some(media_timestamp);
+            frame
+        };
+
+        // Tant que le pair ne l'a pas demandé, l'horodatage ne part pas
+        assert_eq!(caller.prepare_audio(frame(0)).media_timestamp(), None);
+
+        let request = sent(callee.media_timestamps_request(t0).into_iter().collect());
+        assert_eq!(request[0].control_message(), Some(ControlMessage::MediaTimestamps));
+        assert!(callee.media_timestamps_request(t0 + config.heartbeat_interval / 2).is_none());
+        let ack = sent(caller.handle_packet(request[0].clone(), CALLEE, t0));
+        assert_eq!(ack[0].control_message(), Some(ControlMessage::MediaTimestampsAck));
+        callee.handle_packet(ack[0].clone(), CALLER, t0);
+        assert!(callee.media_timestamps_request(t0 + config.heartbeat_interval).is_none());
+
+        // Horodatage transmis jusqu'à la frame livrée
+        let packet = caller.prepare_audio(frame(960));
+        let mut encoded = Vec::new();
+        packet.encode_into(&mut encoded);
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        let delivered = callee.handle_packet(decoded, CALLER, t0).into_iter().find_map(|action| match action {
+            ProtocolAction::Deliver(frame) => Some(frame),
+            _ => None,
+        });
+        assert_eq!(delivered.unwrap().media_timestamp, Some(960));
+
+        // Fin de session : le pair suivant devra le demander à nouveau
+        caller.close();
+        assert_eq!(caller.prepare_audio(frame(1920)).media_timestamp(), None);
+    }
+
+    #[test]
+    fn test_stream_resync_events() {
+        let config = NetworkConfig::test_config();
+        let now = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, now);
+        let frame = |sequence| CompressedFrame::new(vec![0; 20], 960, Instant::now(), sequence);
+
+        // Première session : pas de reprise à signaler
+        assert_eq!(callee.take_stream_resync(), None);
+
+        // Petits trous masqués par le PLC, pas les rafales
+        for sequence in [1, 2, 5] {
+            callee.track_delivery(frame(sequence));
+        }
+        assert_eq!(callee.take_stream_resync(), None);
+        callee.track_delivery(frame(5 + ProtocolEngine::RESYNC_GAP_FRAMES + 1));
+        let jump = callee.take_stream_resync().unwrap();
+        assert_eq!(jump, StreamResync::SequenceJump { from: 5, to: 16 });
+        assert!(!jump.resets_encoder());
+        // Une frame en retard ne provoque pas de faux saut ensuite
+        callee.track_delivery(frame(3));
+        callee.track_delivery(frame(17));
+        assert_eq!(callee.take_stream_resync(), None);
+
+        // Reconnexion : encodeur et décodeur à réinitialiser
+        callee.close();
+        for hello in sent(caller.connect(CALLEE, now)) {
+            callee.handle_packet(hello, CALLER, now);
+        }
+        callee.track_delivery(frame(500));
+        let restart = callee.take_stream_resync().unwrap();
+        assert_eq!(restart, StreamResync::SessionRestart { session_id: 20 });
+        assert!(restart.resets_encoder());
+    }
+
+    #[test]
+    fn test_jitter_buffer() {
+        let mut buffer = JitterBuffer::new(10, 0);
+
+        // Test ajout de paquets dans l'ordre
+        let frame1 = CompressedFrame::new(vec![1], 960, Instant::now(), 1);
+        let packet1 = NetworkPacket::new_audio(frame1, 123, 456);
+
+        assert!(buffer.push_packet(packet1.clone()));
+
+        // Test récupération
+        let received = buffer.pop_packet().unwrap();
+        assert_eq!(received.sequence_number(), 1);
+
+        // Test paquet en retard (rejeté)
+        let frame_old = CompressedFrame::new(vec![0], 960, Instant::now(), 1);
+        let packet_old = NetworkPacket::new_audio(frame_old, 123, 456);
+        assert!(!buffer.push_packet(packet_old));
+    }
+
+    #[test]
+    fn test_jitter_buffer_out_of_order() {
+        let mut buffer = JitterBuffer::new(10, 0);
+
+        // Ajoute des paquets dans le désordre
+        let frame3 = CompressedFrame::new(vec![3], 960, Instant::now(), 3);
+        let packet3 = NetworkPacket::new_audio(frame3, 123, 456);
+        assert!(buffer.push_packet(packet3));
+
+        let frame1 = CompressedFrame::new(vec![1], 960, Instant::now(), 1);
+        let packet1 = NetworkPacket::new_audio(frame1, 123, 456);
+        assert!(buffer.push_packet(packet1));
+
+        // Le paquet 1 doit sortir en premier
+        let received = buffer.pop_packet().unwrap();
+        assert_eq!(received.sequence_number(), 1);
+
+        // Le paquet 2 est manquant, doit être marqué comme perdu
+        // et le paquet 3 doit sortir
+        let received = buffer.pop_packet().unwrap();
+        assert_eq!(received.sequence_number(), 3);
+        assert_eq!(buffer.lost_packets, 1);
+    }
+
+    #[test]
+    fn test_jitter_buffer_overflow_skips_forward() {
+        let mut buffer = JitterBuffer::new(3, 0);
+        let push = |buffer: &mut JitterBuffer, sequence: u64| {
+            let frame = CompressedFrame::new(vec![sequence as u8], 960, Instant::now(), sequence);
+            buffer.push_packet(NetworkPacket::new_audio(frame, 123, 456))
+        };
+
+        // 1 et 2 n'arrivent pas à temps ; 3..=5 remplissent le buffer
+        for sequence in 3..=5 {
+            assert!(push(&mut buffer, sequence));
+        }
+
+        // 2 arrive buffer plein : plus ancien que tout le reste, c'est lui qui
+        // est éjecté, 1 est perdu et la lecture saute à 3
+        assert!(!push(&mut buffer, 2));
+        assert_eq!((buffer.overflow_drops, buffer.lost_packets, buffer.expected_sequence), (1, 1, 3));
+
+        // 6 fait déborder : 3 est éjecté sans être compté perdu
+        assert!(push(&mut buffer, 6));
+        assert_eq!((buffer.overflow_drops, buffer.lost_packets, buffer.expected_sequence), (2, 1, 4));
+
+        // La lecture reprend sans trou ni paquet déclaré perdu à tort
+        let played: Vec<u64> = std::iter::from_fn(|| buffer.pop_packet())
+            .map(|packet| packet.sequence_number())
+            .collect();
+        assert_eq!(played, vec![4, 5, 6]);
+        assert_eq!(buffer.lost_packets, 1);
+    }
+
+    #[test]
+    fn test_jitter_buffer_late_window() {
+        let mut buffer = JitterBuffer::new(10, 2);
+        let push = |buffer: &mut JitterBuffer, sequence: u64| {
+            let frame = CompressedFrame::new(vec![sequence as u8], 960, Instant::now(), sequence);
+            buffer.push_packet(NetworkPacket::new_audio(frame, 123, 456))
+        };
+
+        // 1 est en retard : le créneau est conservé tant que la fenêtre n'est pas dépassée
+        assert!(push(&mut buffer, 2));
+        assert!(push(&mut buffer, 3));
+        assert!(buffer.pop_packet().is_none());
+
+        // Arrivé dans la fenêtre, il est réinséré dans l'ordre
+        assert!(push(&mut buffer, 1));
+        let played: Vec<u64> = std::iter::from_fn(|| buffer.pop_packet())
+            .map(|packet| packet.sequence_number())
+            .collect();
+        assert_eq!(played, vec![1, 2, 3]);
+        assert_eq!((buffer.lost_packets, buffer.late_packets), (0, 0));
+
+        // 4 manque encore après 3 paquets plus récents : créneau sauté
+        for sequence in 5..=7 {
+            assert!(push(&mut buffer, sequence));
+        }
+        assert_eq!(buffer.pop_packet().unwrap().sequence_number(), 5);
+        assert_eq!(buffer.lost_packets, 1);
+
+        // Trop tard pour être joué, il est compté en retard et non plus perdu
+        assert!(!push(&mut buffer, 4));
+        assert_eq!((buffer.lost_packets, buffer.late_packets), (0, 1));
+
+        // Un doublon d'un paquet déjà joué n'est pas un retardataire
+        assert!(!push(&mut buffer, 2));
+        assert_eq!(buffer.late_packets, 1);
+        assert_eq!(buffer.duplicates, 1);
+    }
+
+    #[test]
+    fn test_jitter_buffer_byte_budget() {
+        // Budget de 3 frames de 1000 octets, quelle que soit la capacité en paquets
+        let frame_bytes = NetworkPacket::HEADER_SIZE + 1000;
+        let mut buffer = JitterBuffer::new(100, 0).with_byte_budget(3 * frame_bytes, usize::MAX);
+        let push = |buffer: &mut JitterBuffer, sequence: u64, size: usize| {
+            let frame = CompressedFrame::new(vec![0; size], 960, Instant::now(), sequence);
+            buffer.push_packet(NetworkPacket::new_audio(frame, 123, 456))
+        };
+
+        // 1 manque : 2..=4 remplissent le budget, 5 fait éjecter 2
+        for sequence in 2..=5 {
+            assert!(push(&mut buffer, sequence, 1000));
+        }
+        assert_eq!((buffer.packets.len(), buffer.bytes), (3, 3 * frame_bytes));
+        assert_eq!((buffer.overflow_drops, buffer.byte_budget_drops, buffer.expected_sequence), (1, 1, 3));
+
+        // De petites frames tiennent à plusieurs dans la place d'une grande
+        for sequence in 6..=9 {
+            assert!(push(&mut buffer, sequence, 10));
+        }
+        assert_eq!(buffer.packets.len(), 6);
+        assert!(buffer.bytes <= 3 * frame_bytes);
+
+        // Tout est décompté à la lecture
+        let played: Vec<u64> = std::iter::from_fn(|| buffer.pop_packet())
+            .map(|packet| packet.sequence_number())
+            .collect();
+        assert_eq!(played, vec![4, 5, 6, 7, 8, 9]);
+        assert_eq!(buffer.bytes, 0);
+        assert!(buffer.sender_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_jitter_buffer_sender_cap() {
+        let frame_bytes = NetworkPacket::HEADER_SIZE + 100;
+        let mut buffer = JitterBuffer::new(100, 100).with_byte_budget(10 * frame_bytes, 4 * frame_bytes);
+        let push = |buffer: &mut JitterBuffer, sequence: u64, sender_id: u64| {
+            let frame = CompressedFrame::new(vec![0; 100], 960, Instant::now(), sequence);
+            buffer.push_packet(NetworkPacket::new_audio(frame, sender_id, 456))
+        };
+
+        // 1 attend 2..=9 (fenêtre de retard) ; l'expéditeur 7 inonde le buffer
+        assert!(push(&mut buffer, 2, 123));
+        for sequence in 3..=9 {
+            push(&mut buffer, sequence, 7);
+        }
+
+        // Seuls ses paquets les plus anciens sont éjectés, pas ceux du pair
+        let senders: Vec<(u64, u64)> = buffer.packets.iter().map(|(sequence, packet)| (*sequence, packet.sender_id)).collect();
+        assert_eq!(senders, vec![(2, 123), (6, 7), (7, 7), (8, 7), (9, 7)]);
+        assert_eq!((buffer.sender_cap_drops, buffer.overflow_drops, buffer.byte_budget_drops), (3, 3, 0));
+        assert_eq!(buffer.sender_bytes[&7], 4 * frame_bytes);
+        assert_eq!(buffer.expected_sequence, 1);
+    }
+
+    #[test]
+    fn test_buffer_stats() {
+        let mut config = NetworkConfig::test_config();
+        config.target_delay_ms = 40;
+        let t0 = Instant::now();
+        let (mut caller, mut callee) = connected_pair(&config, t0);
+
+        // 2 manque, 3 et 4 attendent dans la fenêtre de retard
+        let packets: Vec<_> = (1..=4).map(|sequence| audio(&mut caller, sequence)).collect();
+        for (index, packet) in packets.iter().enumerate() {
+            if index != 1 {
+                callee.handle_packet(packet.clone(), CALLER, t0);
+            }
+        }
+        let stats = callee.buffer_stats(3.0);
+        assert_eq!(stats.packets_buffered, 2);
+        assert_eq!(stats.target_delay_ms, 40.0);
+        assert_eq!(stats.avg_delay_ms, 40.0);
+        assert_eq!(stats.jitter_ms, 3.0);
+
+        // 5 dépasse la fenêtre : 2 est masqué, puis arrive trop tard
+        callee.handle_packet(audio(&mut caller, 5), CALLER, t0);
+        callee.handle_packet(packets[1].clone(), CALLER, t0);
+        let stats = callee.buffer_stats(3.0);
+        assert_eq!((stats.packets_buffered, stats.packets_lost), (0, 0));
+        assert_eq!((stats.late_discarded, stats.concealments), (1, 1));
+    }
+
+    #[test]
+    fn test_buffer_delays_follow_frame_duration() {
+        let mut config = NetworkConfig::test_config();
+        config.target_delay_ms = 120;
+        config.max_delay_ms = 600;
+        let mut engine = ProtocolEngine::with_ids(&config, 1, 10);
+        let frames = |engine: &ProtocolEngine| (engine.receive_buffer.late_window, engine.receive_buffer.max_size);
+        assert_eq!(frames(&engine), (6, 30));
+
+        // Le pair passe à des frames de 60ms : mêmes délais en ms
+        engine.frame_duration = Duration::from_millis(60);
+        engine.resize_receive_buffer();
+        assert_eq!(frames(&engine), (2, 10));
+        assert_eq!(engine.buffer_stats(0.0).target_delay_ms, 120.0);
+
+        // Attente bornée à la capacité moins une frame
+        assert_eq!(engine.set_target_delay_ms(90), 120);
+        assert_eq!(engine.set_target_delay_ms(5000), 540);
+    }
+}
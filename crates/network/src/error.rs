@@ -5,16 +5,21 @@
 
 use thiserror::Error;
 use std::net::SocketAddr;
+use crate::types::ProtocolErrorCode;
+use crate::identity::PeerIdentity;
 
 /// Énumération de toutes les erreurs possibles dans le système réseau
 /// 
 /// `thiserror::Error` génère automatiquement l'implémentation du trait Error
 /// avec des messages d'erreur descriptifs en français.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum NetworkError {
     /// Impossible de créer ou bind le socket UDP sur le port demandé
+    /// 
+    /// `failure` classe la cause (voir `bind_diagnostic` pour la remédiation).
     #[error("Impossible de bind le socket sur le port {port}: {reason}")]
-    BindError { port: u16, reason: String },
+    BindError { port: u16, reason: String, failure: BindFailure },
     
     /// Timeout lors de la tentative de connexion vers un peer
     #[error("Timeout de connexion vers {addr} après {timeout_ms}ms")]
@@ -32,13 +37,22 @@ pub enum NetworkError {
     #[error("Paquet trop volumineux: {size} bytes (max autorisé: {max} bytes)")]
     PacketTooLarge { size: usize, max: usize },
     
-    /// Paquet reçu avec un format invalide ou version incompatible
+    /// Paquet reçu avec un format invalide
     #[error("Format de paquet invalide reçu de {addr}")]
     InvalidPacketFormat { addr: SocketAddr },
     
+    /// Paquet reçu dans une version de protocole non supportée
+    #[error("Version de protocole {version} non supportée reçue de {addr}")]
+    UnsupportedVersion { addr: SocketAddr, version: u8 },
+    
     /// Session ID mismatch - paquet d'une ancienne session
     #[error("Session ID invalide: reçu {received}, attendu {expected}")]
-    InvalidSessionId { received: u32, expected: u32 },
+    InvalidSessionId { received: u64, expected: u64 },
+    
+    /// Paquet reçu d'une autre adresse que celle du pair connecté (mauvais
+    /// port, NAT qui réécrit l'adresse...) : ignoré
+    #[error("Paquet de {addr} ignoré : le pair connecté est {expected}")]
+    UnexpectedSource { addr: SocketAddr, expected: SocketAddr },
     
     /// Numéro de séquence trop ancien (paquet en retard)
     #[error("Paquet en retard: séquence {sequence}, retard de {age_ms}ms")]
@@ -68,6 +82,10 @@ pub enum NetworkError {
     #[error("Erreur IO réseau: {0}")]
     IoError(#[from] std::io::Error),
     
+    /// Erreur de la chaîne audio (auto-diagnostic)
+    #[error("Erreur audio: {0}")]
+    AudioError(#[from] audio::AudioError),
+    
     /// Erreur lors de l'initialisation des composants réseau
     #[error("Erreur d'initialisation réseau: {0}")]
     InitializationError(String),
@@ -79,6 +97,142 @@ pub enum NetworkError {
     /// Erreur de configuration réseau
     #[error("Configuration réseau invalide: {0}")]
     ConfigError(String),
+    
+    /// Échec de la redirection de port sur le routeur (UPnP / NAT-PMP)
+    #[error("Redirection de port impossible: {0}")]
+    PortMappingError(String),
+    
+    /// Erreur protocolaire signalée par le pair distant (paquet `Error`)
+    #[error("Refus du pair {addr} ({code:?}): {description}")]
+    RemoteError { addr: SocketAddr, code: ProtocolErrorCode, description: String },
+    
+    /// Identité du pair refusée (voir `TrustDecision`), ou absente alors
+    /// qu'une décision est demandée à l'application
+    #[error("Pair {addr} non approuvé")]
+    UntrustedPeer { addr: SocketAddr, identity: Option<PeerIdentity> },
+    
+    /// Un pair de confiance se présente avec une autre identité : clé
+    /// régénérée, ou usurpation (voir `KnownPeers::forget`)
+    #[error("Identité du pair {addr} modifiée : empreinte {} au lieu de {}", .actual.fingerprint(), .expected.fingerprint())]
+    FingerprintChanged { addr: SocketAddr, expected: PeerIdentity, actual: PeerIdentity },
+}
+
+/// Cause probable d'un échec de bind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindFailure {
+    /// Le port est déjà utilisé par une autre application
+    PortInUse,
+    /// Port privilégié (< 1024) sans les droits nécessaires
+    PermissionDenied,
+    /// Accès refusé sur un port ordinaire : pare-feu, politique de sécurité
+    /// (SELinux, AppArmor) ou plage de ports réservée par Windows
+    FirewallLikely,
+    /// L'adresse locale demandée n'appartient pas à cette machine
+    AddressUnavailable,
+    /// Autre erreur du système
+    Other,
+}
+
+impl BindFailure {
+    /// Classe l'erreur renvoyée par le système lors du bind de `port`
+    pub fn classify(error: &std::io::Error, port: u16) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::AddrInUse => BindFailure::PortInUse,
+            std::io::ErrorKind::AddrNotAvailable => BindFailure::AddressUnavailable,
+            // Sous Windows, aucun port n'est privilégié : un refus vient
+            // d'une exclusion de ports ou d'un logiciel de sécurité
+            std::io::ErrorKind::PermissionDenied if cfg!(unix) && port < 1024 => BindFailure::PermissionDenied,
+            std::io::ErrorKind::PermissionDenied => BindFailure::FirewallLikely,
+            _ => BindFailure::Other,
+        }
+    }
+    
+    /// Description courte de la cause, affichable à l'utilisateur
+    pub fn description(&self) -> &'static str {
+        match self {
+            BindFailure::PortInUse => "port déjà utilisé",
+            BindFailure::PermissionDenied => "port privilégié",
+            BindFailure::FirewallLikely => "accès refusé par le système",
+            BindFailure::AddressUnavailable => "adresse locale indisponible",
+            BindFailure::Other => "erreur système",
+        }
+    }
+    
+    /// Remède suggéré pour un échec sur `port`
+    pub fn remedy(&self, port: u16) -> String {
+        match self {
+            BindFailure::PortInUse => format!(
+                "fermez l'application qui utilise le port {} (une autre instance de Voc ?) ou choisissez un autre port", port),
+            BindFailure::PermissionDenied => format!(
+                "les ports inférieurs à 1024 sont réservés à l'administrateur : choisissez un port ≥ 1024 \
+                 (ex: 9001) ou accordez CAP_NET_BIND_SERVICE au programme (port {} demandé)", port),
+            BindFailure::FirewallLikely => format!(
+                "autorisez Voc dans le pare-feu ou la politique de sécurité, ou choisissez un autre port \
+                 (sous Windows, `netsh int ipv4 show excludedportrange protocol=udp` liste les ports réservés, dont peut-être le {})", port),
+            BindFailure::AddressUnavailable => 
+                "vérifiez bind_ip / bind_interface : l'adresse n'existe pas (ou plus) sur cette machine".to_string(),
+            BindFailure::Other => "vérifiez la configuration réseau de la machine".to_string(),
+        }
+    }
+}
+
+/// Diagnostic d'un échec de bind : cause, remède et port de repli éventuel
+/// 
+/// Obtenu par `NetworkError::bind_diagnostic` ou `utils::diagnose_bind`.
+/// 
+/// # Example
+/// ```rust
+/// use network::{BindFailure, NetworkError};
+/// 
+/// let error = NetworkError::bind_failed(9001, std::io::ErrorKind::AddrInUse.into());
+/// let diagnostic = error.bind_diagnostic().unwrap().probe_alternatives(100);
+/// assert_eq!(diagnostic.failure, BindFailure::PortInUse);
+/// println!("{}", diagnostic);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindDiagnostic {
+    /// Port demandé
+    pub port: u16,
+    
+    /// Cause probable
+    pub failure: BindFailure,
+    
+    /// Message du système
+    pub reason: String,
+    
+    /// Port libre trouvé par `probe_alternatives`
+    pub alternative_port: Option<u16>,
+}
+
+impl BindDiagnostic {
+    /// Remède suggéré à l'utilisateur
+    pub fn remedy(&self) -> String {
+        self.failure.remedy(self.port)
+    }
+    
+    /// Cherche un port libre parmi les `attempts` ports suivants
+    /// 
+    /// Seulement si un autre port a des chances de fonctionner : port occupé,
+    /// privilégié (la recherche commence alors à 1024) ou refusé par le système.
+    pub fn probe_alternatives(mut self, attempts: u16) -> Self {
+        let first = match self.failure {
+            BindFailure::PortInUse | BindFailure::FirewallLikely => self.port.saturating_add(1),
+            BindFailure::PermissionDenied => self.port.max(1023).saturating_add(1),
+            BindFailure::AddressUnavailable | BindFailure::Other => return self,
+        };
+        self.alternative_port = crate::utils::find_free_udp_port(first..=first.saturating_add(attempts.saturating_sub(1)));
+        self
+    }
+}
+
+impl std::fmt::Display for BindDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "port {} : {} ({}) - {}", self.port, self.failure.description(), self.reason, self.remedy())?;
+        if let Some(port) = self.alternative_port {
+            write!(f, " ; port libre suggéré : {}", port)?;
+        }
+        Ok(())
+    }
 }
 
 /// Conversion automatique des erreurs de parsing d'adresses
@@ -90,6 +244,17 @@ impl From<std::net::AddrParseError> for NetworkError {
     }
 }
 
+/// Erreur de bind correspondant à un diagnostic (port suggéré non conservé)
+impl From<BindDiagnostic> for NetworkError {
+    fn from(diagnostic: BindDiagnostic) -> Self {
+        NetworkError::BindError {
+            port: diagnostic.port,
+            reason: diagnostic.reason,
+            failure: diagnostic.failure,
+        }
+    }
+}
+
 /// Type Result personnalisé pour notre crate network
 /// 
 /// Au lieu d'écrire Result<T, NetworkError> partout, on peut écrire NetworkResult<T>
@@ -102,6 +267,7 @@ impl NetworkError {
         Self::BindError {
             port,
             reason: cause.to_string(),
+            failure: BindFailure::classify(&cause, port),
         }
     }
     
@@ -120,6 +286,29 @@ impl NetworkError {
         Self::PacketTooLarge { size, max }
     }
     
+    /// Crée une erreur de version de protocole non supportée
+    pub fn unsupported_version(addr: SocketAddr, version: u8) -> Self {
+        Self::UnsupportedVersion { addr, version }
+    }
+    
+    /// Crée une erreur à partir d'un refus envoyé par le pair distant
+    pub fn remote_error(addr: SocketAddr, code: ProtocolErrorCode, description: String) -> Self {
+        Self::RemoteError { addr, code, description }
+    }
+    
+    /// Diagnostic d'une erreur de bind (`None` pour les autres erreurs)
+    pub fn bind_diagnostic(&self) -> Option<BindDiagnostic> {
+        match self {
+            NetworkError::BindError { port, reason, failure } => Some(BindDiagnostic {
+                port: *port,
+                failure: *failure,
+                reason: reason.clone(),
+                alternative_port: None,
+            }),
+            _ => None,
+        }
+    }
+    
     /// Vérifie si l'erreur est récupérable (worth retrying)
     pub fn is_recoverable(&self) -> bool {
         match self {
@@ -128,10 +317,67 @@ impl NetworkError {
             NetworkError::BufferUnderflow => true,
             NetworkError::PacketTooOld { .. } => true,
             NetworkError::CorruptedPacket { .. } => true,
+            NetworkError::AudioError(err) => err.is_recoverable(),
             _ => false,
         }
     }
     
+    /// Vérifie si l'erreur ne concerne qu'un datagramme reçu
+    ///
+    /// Ces erreurs (corruption, format ou version inconnus, paquet trop vieux
+    /// ou d'une source inattendue)
+    /// n'affectent pas la connexion : le paquet est ignoré et la réception continue.
+    pub fn is_packet_level(&self) -> bool {
+        matches!(
+            self,
+            NetworkError::CorruptedPacket { .. }
+                | NetworkError::InvalidPacketFormat { .. }
+                | NetworkError::UnsupportedVersion { .. }
+                | NetworkError::PacketTooOld { .. }
+                | NetworkError::UnexpectedSource { .. }
+        )
+    }
+
+    /// Nom du type d'erreur, sans ses détails (adresse, tailles...)
+    ///
+    /// Sert de clé pour regrouper les occurrences d'une même erreur.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetworkError::BindError { .. } => "BindError",
+            NetworkError::ConnectionTimeout { .. } => "ConnectionTimeout",
+            NetworkError::PeerDisconnected { .. } => "PeerDisconnected",
+            NetworkError::CorruptedPacket { .. } => "CorruptedPacket",
+            NetworkError::PacketTooLarge { .. } => "PacketTooLarge",
+            NetworkError::InvalidPacketFormat { .. } => "InvalidPacketFormat",
+            NetworkError::UnsupportedVersion { .. } => "UnsupportedVersion",
+            NetworkError::InvalidSessionId { .. } => "InvalidSessionId",
+            NetworkError::PacketTooOld { .. } => "PacketTooOld",
+            NetworkError::UnexpectedSource { .. } => "UnexpectedSource",
+            NetworkError::BufferOverflow { .. } => "BufferOverflow",
+            NetworkError::BufferUnderflow => "BufferUnderflow",
+            NetworkError::Timeout => "Timeout",
+            NetworkError::InvalidAddress { .. } => "InvalidAddress",
+            NetworkError::SerializationError(_) => "SerializationError",
+            NetworkError::IoError(_) => "IoError",
+            NetworkError::AudioError(_) => "AudioError",
+            NetworkError::InitializationError(_) => "InitializationError",
+            NetworkError::InvalidState { .. } => "InvalidState",
+            NetworkError::ConfigError(_) => "ConfigError",
+            NetworkError::PortMappingError(_) => "PortMappingError",
+            NetworkError::RemoteError { .. } => "RemoteError",
+            NetworkError::UntrustedPeer { .. } => "UntrustedPeer",
+            NetworkError::FingerprintChanged { .. } => "FingerprintChanged",
+        }
+    }
+
+    /// Vérifie si une nouvelle tentative de connexion a des chances d'aboutir
+    /// 
+    /// Vrai pour les erreurs passagères (timeout, pair perdu, erreur de
+    /// socket) ; faux notamment pour un refus explicite du pair.
+    pub fn can_retry_connection(&self) -> bool {
+        matches!(self, NetworkError::IoError(_)) || self.is_recoverable() || self.requires_reconnection()
+    }
+    
     /// Vérifie si l'erreur nécessite une reconnexion
     pub fn requires_reconnection(&self) -> bool {
         match self {
@@ -151,7 +397,8 @@ mod tests {
     fn test_error_display() {
         let error = NetworkError::BindError { 
             port: 9001, 
-            reason: "Port déjà utilisé".to_string() 
+            reason: "Port déjà utilisé".to_string(),
+            failure: BindFailure::PortInUse,
         };
         assert!(error.to_string().contains("9001"));
         assert!(error.to_string().contains("Port déjà utilisé"));
@@ -167,7 +414,8 @@ mod tests {
         
         let bind_error = NetworkError::BindError { 
             port: 9001, 
-            reason: "Permission refusée".to_string() 
+            reason: "Permission refusée".to_string(),
+            failure: BindFailure::FirewallLikely,
         };
         assert!(!bind_error.is_recoverable());
     }
@@ -181,6 +429,18 @@ mod tests {
         
         let buffer_overflow = NetworkError::BufferOverflow { capacity: 100 };
         assert!(!buffer_overflow.requires_reconnection());
+        
+        // Un refus explicite du pair ne doit pas déclencher de reconnexion automatique
+        let server_full = NetworkError::remote_error(
+            "127.0.0.1:9001".parse().unwrap(),
+            ProtocolErrorCode::ServerFull,
+            "appel en cours".to_string(),
+        );
+        assert!(!server_full.requires_reconnection());
+        assert!(!server_full.is_recoverable());
+        assert!(!server_full.can_retry_connection());
+        assert!(disconnected.can_retry_connection());
+        assert!(server_full.to_string().contains("appel en cours"));
     }
     
     #[test]
@@ -189,11 +449,37 @@ mod tests {
         let error = NetworkError::bind_failed(8080, io_err);
         
         match error {
-            NetworkError::BindError { port, reason } => {
+            NetworkError::BindError { port, reason, failure } => {
                 assert_eq!(port, 8080);
                 assert!(reason.contains("test"));
+                assert_eq!(failure, BindFailure::FirewallLikely);
             }
             _ => panic!("Wrong error type"),
         }
     }
+    
+    #[test]
+    fn test_bind_failure_classification() {
+        use std::io::{Error, ErrorKind};
+        
+        assert_eq!(BindFailure::classify(&Error::from(ErrorKind::AddrInUse), 9001), BindFailure::PortInUse);
+        assert_eq!(BindFailure::classify(&Error::from(ErrorKind::AddrNotAvailable), 9001), BindFailure::AddressUnavailable);
+        assert_eq!(BindFailure::classify(&Error::from(ErrorKind::PermissionDenied), 9001), BindFailure::FirewallLikely);
+        assert_eq!(BindFailure::classify(&Error::from(ErrorKind::Other), 9001), BindFailure::Other);
+        
+        // Le diagnostic d'un port privilégié propose un port ordinaire
+        #[cfg(unix)]
+        {
+            let privileged = NetworkError::bind_failed(80, Error::from(ErrorKind::PermissionDenied));
+            let diagnostic = privileged.bind_diagnostic().unwrap().probe_alternatives(200);
+            assert_eq!(diagnostic.failure, BindFailure::PermissionDenied);
+            assert!(diagnostic.alternative_port.is_some_and(|port| port >= 1024));
+            assert!(diagnostic.to_string().contains("1024"), "{}", diagnostic);
+        }
+        
+        // Changer de port ne corrige pas une adresse absente
+        let unavailable = NetworkError::bind_failed(9001, Error::from(ErrorKind::AddrNotAvailable));
+        assert_eq!(unavailable.bind_diagnostic().unwrap().probe_alternatives(100).alternative_port, None);
+        assert!(NetworkError::Timeout.bind_diagnostic().is_none());
+    }
 }
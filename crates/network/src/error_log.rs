@@ -0,0 +1,119 @@
+//! Journalisation limitée des erreurs réseau répétées
+//!
+//! Un lien instable peut produire des milliers de « paquet corrompu » par
+//! minute. Chaque type d'erreur est affiché à sa première occurrence, puis les
+//! répétitions sont seulement comptées et résumées une fois par intervalle.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::NetworkError;
+
+/// Répétitions d'un type d'erreur dans l'intervalle courant
+#[derive(Debug)]
+struct ErrorWindow {
+    /// Début de l'intervalle (dernier message affiché)
+    started_at: Instant,
+    /// Occurrences non affichées depuis
+    suppressed: u64,
+}
+
+/// Déduplique les erreurs par type et agrège les répétitions
+#[derive(Debug)]
+pub(crate) struct ErrorLog {
+    interval: Duration,
+    windows: HashMap<&'static str, ErrorWindow>,
+    suppressed_total: u64,
+}
+
+impl ErrorLog {
+    /// Intervalle par défaut entre deux messages d'un même type d'erreur
+    pub(crate) const SUMMARY_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// Crée un journal qui affiche au plus un message par type et par `interval`
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            windows: HashMap::new(),
+            suppressed_total: 0,
+        }
+    }
+
+    /// Enregistre une erreur et retourne le message à afficher, s'il y en a un
+    ///
+    /// Le message reprend l'erreur, suivi du nombre de répétitions masquées
+    /// depuis le précédent message du même type.
+    pub(crate) fn report(&mut self, error: &NetworkError, now: Instant) -> Option<String> {
+        let Some(window) = self.windows.get_mut(error.kind()) else {
+            self.windows.insert(error.kind(), ErrorWindow { started_at: now, suppressed: 0 });
+            return Some(error.to_string());
+        };
+
+        if now.duration_since(window.started_at) < self.interval {
+            window.suppressed += 1;
+            self.suppressed_total += 1;
+            return None;
+        }
+
+        let message = match window.suppressed {
+            0 => error.to_string(),
+            repeats => format!("{} (+{} similaires en {:.0}s)",
+                               error, repeats, now.duration_since(window.started_at).as_secs_f32()),
+        };
+        window.started_at = now;
+        window.suppressed = 0;
+        Some(message)
+    }
+
+    /// Résume les répétitions encore en attente et vide le journal
+    ///
+    /// À appeler en fin d'appel pour ne pas perdre les derniers comptes.
+    pub(crate) fn flush(&mut self) -> Vec<String> {
+        let mut summaries: Vec<String> = self.windows.drain()
+            .filter(|(_, window)| window.suppressed > 0)
+            .map(|(kind, window)| format!("{} : {} erreurs similaires non affichées", kind, window.suppressed))
+            .collect();
+        summaries.sort();
+        summaries
+    }
+
+    /// Nombre total d'erreurs masquées depuis la création
+    pub(crate) fn suppressed_total(&self) -> u64 {
+        self.suppressed_total
+    }
+}
+
+impl Default for ErrorLog {
+    fn default() -> Self {
+        Self::new(Self::SUMMARY_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_aggregated() {
+        let mut log = ErrorLog::new(Duration::from_secs(10));
+        let addr = "127.0.0.1:9001".parse().unwrap();
+        let corrupted = NetworkError::corrupted_packet(addr);
+        let start = Instant::now();
+
+        assert!(log.report(&corrupted, start).is_some());
+        for i in 1..=999 {
+            assert!(log.report(&corrupted, start + Duration::from_millis(i)).is_none());
+        }
+        // Un autre type d'erreur n'est pas masqué
+        assert!(log.report(&NetworkError::InvalidPacketFormat { addr }, start).is_some());
+        assert_eq!(log.suppressed_total(), 999);
+
+        // L'intervalle écoulé, le message suivant résume les répétitions
+        let summary = log.report(&corrupted, start + Duration::from_secs(10)).unwrap();
+        assert!(summary.contains("+999"), "{}", summary);
+
+        assert!(log.report(&corrupted, start + Duration::from_secs(11)).is_none());
+        assert_eq!(log.flush(), vec!["CorruptedPacket : 1 erreurs similaires non affichées"]);
+        assert!(log.flush().is_empty());
+    }
+}
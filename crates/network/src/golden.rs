@@ -0,0 +1,326 @@
+//! Tests du format réseau contre des fixtures figées (`golden/`)
+//!
+//! Chaque paquet représentatif est encodé puis comparé octet par octet au
+//! fichier `golden/v<version>/<nom>.hex` versionné avec le code. Un champ
+//! réordonné dans une structure sérialisée par bincode, un flag déplacé ou un
+//! type renuméroté change ces octets sans casser aucun autre test, alors
+//! qu'un pair d'une version précédente ne comprendrait plus nos paquets.
+//!
+//! Les fixtures sont aussi relues : les paquets déjà émis par les versions
+//! publiées doivent rester lisibles.
+//!
+//! Changement voulu du format : voir la procédure de `docs/FORMAT_RESEAU.md`
+//! (fixtures régénérées avec `VOC_UPDATE_GOLDEN=1`).
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use audio::{CompressedFrame, FrameMetadata};
+
+use crate::identity::{CHALLENGE_CONTEXT, HANDSHAKE_CONTEXT};
+use crate::{
+    CodecKind, CodecParams, ControlMessage, DiscoveryMessage, FlowControlHint, HandshakeInfo,
+    HandshakeMessage, Identity, NetworkPacket, PacketPayload, PacketType, PeerStatsReport, PresenceCapabilities,
+    ProofChallenge, ProtocolErrorCode, StreamDescription,
+};
+
+/// Variable d'environnement qui réécrit les fixtures au lieu de les comparer
+const UPDATE_ENV: &str = "VOC_UPDATE_GOLDEN";
+
+/// Octets par ligne dans les fichiers `.hex`
+const BYTES_PER_LINE: usize = 16;
+
+/// Identifiants des cas v3 et précédents (32 bits sur le réseau)
+const SENDER_ID: u64 = 0x0102_0304;
+const SESSION_ID: u64 = 0x0A0B_0C0D;
+
+/// Identifiants des cas v4, aux 32 bits de poids fort non nuls
+const WIDE_SENDER_ID: u64 = 0x1112_1314_0102_0304;
+const WIDE_SESSION_ID: u64 = 0x2526_2728_0A0B_0C0D;
+
+/// Paquet représentatif et son fichier de référence
+struct GoldenCase {
+    name: &'static str,
+    description: &'static str,
+    packet: NetworkPacket,
+}
+
+impl GoldenCase {
+    /// Paquet figé dans la version `version` (checksum recalculé)
+    ///
+    /// La version est explicite : après un changement de version, les cas
+    /// des versions précédentes passent par les convertisseurs legacy.
+    fn new(version: u8, name: &'static str, description: &'static str, mut packet: NetworkPacket) -> Self {
+        packet.protocol_version = version;
+        packet.checksum = packet.calculate_checksum();
+        Self { name, description, packet }
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(format!("v{}", self.packet.protocol_version))
+            .join(format!("{}.hex", self.name))
+    }
+}
+
+fn audio_frame(with_metadata: bool) -> CompressedFrame {
+    let mut frame = CompressedFrame::new((0u8..24).collect(), 960, Instant::now(), 42);
+    if with_metadata {
+        frame.metadata = Some(FrameMetadata { is_speech: true, level_dbov: 23 });
+    }
+    frame
+}
+
+fn local_addr() -> SocketAddr {
+    "192.168.1.20:9001".parse().unwrap()
+}
+
+/// Données d'identité telles que signées par les builds v3 (identifiants sur 32 bits)
+fn v3_signed_ids(context: &[u8], ids: &[u64]) -> Vec<u8> {
+    let mut message = context.to_vec();
+    for id in ids {
+        message.extend_from_slice(&(*id as u32).to_be_bytes());
+    }
+    message
+}
+
+/// Paquets figés de la version `version` (v3 et suivantes), au moins un par
+/// type et par variante de payload
+fn header_cases(version: u8) -> Vec<GoldenCase> {
+    let (sender_id, session_id) = if version >= 4 { (WIDE_SENDER_ID, WIDE_SESSION_ID) } else { (SENDER_ID, SESSION_ID) };
+    let report = PeerStatsReport { packets_received: 1500, packets_lost: 38, loss_percent: 2.5, jitter_ms: 4.25 };
+    let hint = FlowControlHint { buffer_fill_percent: 85, overflow_drops: 3 };
+    let info = HandshakeInfo { local_addr: local_addr() };
+    let key = Identity::from_secret_bytes([7; 32]);
+    let challenge = ProofChallenge { sender_id: session_id, session_id: sender_id, nonce: 0x8877_6655_4433_2211 };
+    let (identity, proof) = if version >= 4 {
+        (key.handshake_proof(sender_id, session_id), key.challenge_proof(sender_id, session_id, &challenge))
+    } else {
+        let mut challenged = v3_signed_ids(CHALLENGE_CONTEXT, &[sender_id, session_id, challenge.sender_id, challenge.session_id]);
+        challenged.extend_from_slice(&challenge.nonce.to_be_bytes());
+        (key.sign_raw(&v3_signed_ids(HANDSHAKE_CONTEXT, &[sender_id, session_id])), key.sign_raw(&challenged))
+    };
+    let params = CodecParams { codec: CodecKind::Opus, bitrate_bps: 64000, frame_duration_ms: 20 };
+    let description = StreamDescription { params, sample_rate: 16000, channels: 1 };
+    let mut timestamped = audio_frame(true);
+    timestamped.media_timestamp = Some(96_000);
+
+    vec![
+        GoldenCase::new(version, "audio", "frame audio avec métadonnées (parole, -23 dBov)",
+            NetworkPacket::new_audio(audio_frame(true), sender_id, session_id)),
+        GoldenCase::new(version, "audio_no_metadata", "frame audio sans métadonnées",
+            NetworkPacket::new_audio(audio_frame(false), sender_id, session_id)),
+        GoldenCase::new(version, "audio_media_timestamp", "frame audio avec métadonnées et horodatage média",
+            NetworkPacket::new_audio(timestamped, sender_id, session_id)),
+        GoldenCase::new(version, "heartbeat", "heartbeat simple",
+            NetworkPacket::new_heartbeat(sender_id, session_id)),
+        GoldenCase::new(version, "heartbeat_stats", "heartbeat avec rapport de réception",
+            NetworkPacket::new_heartbeat_with_stats(&report, sender_id, session_id)),
+        GoldenCase::new(version, "heartbeat_feedback", "heartbeat avec rapport et contrôle de flux",
+            NetworkPacket::new_heartbeat_with_feedback(&report, hint, sender_id, session_id)),
+        GoldenCase::new(version, "heartbeat_payload", "heartbeat avec rapport, contrôle de flux et données de l'application",
+            NetworkPacket::new_heartbeat_with_payload(&report, hint, b"batterie=80", sender_id, session_id)),
+        GoldenCase::new(version, "heartbeat_losses", "heartbeat avec rapport, contrôle de flux, données de l'application et compte des pertes",
+            NetworkPacket::new_heartbeat_with_losses(&report, hint, b"batterie=80", sender_id, session_id)),
+        GoldenCase::new(version, "handshake_hello", "handshake Hello avec adresse locale et jeton de reprise",
+            NetworkPacket::new_handshake_with_token(HandshakeMessage::Hello, info, 0x1122_3344_5566_7788, sender_id, session_id)),
+        GoldenCase::new(version, "handshake_identity", "handshake Hello avec adresse locale, jeton de reprise et identité",
+            NetworkPacket::new_handshake_with_identity(HandshakeMessage::Hello, info, 0x1122_3344_5566_7788, &identity, sender_id, session_id)),
+        GoldenCase::new(version, "handshake_challenge", "handshake Accept avec adresse locale, jeton de reprise, identité prouvée et défi",
+            NetworkPacket::new_handshake_with_challenge(HandshakeMessage::Accept, info, 0x1122_3344_5566_7788, &proof, 0x1234_5678_9abc_def0, sender_id, session_id)),
+        GoldenCase::new(version, "handshake_accept", "handshake Accept avec adresse locale",
+            NetworkPacket::new_handshake_with_info(HandshakeMessage::Accept, info, sender_id, session_id)),
+        GoldenCase::new(version, "handshake_resume", "demande de reprise de session",
+            NetworkPacket::new_handshake(HandshakeMessage::Resume { token: 0x1122_3344_5566_7788 }, sender_id, session_id)),
+        GoldenCase::new(version, "disconnect", "déconnexion",
+            NetworkPacket::new(PacketType::Disconnect, PacketPayload::None, sender_id, session_id)),
+        GoldenCase::new(version, "discovery_ping", "sonde d'accessibilité",
+            NetworkPacket::new_discovery(&DiscoveryMessage::Ping { seq: 7 }, sender_id, session_id)),
+        GoldenCase::new(version, "discovery_announce", "annonce de présence",
+            NetworkPacket::new_discovery(&DiscoveryMessage::Announce {
+                display_name: "Salon".to_string(),
+                capabilities: PresenceCapabilities { protocol_version: version, codecs: vec![CodecKind::Opus] },
+            }, sender_id, session_id)),
+        GoldenCase::new(version, "error", "erreur protocolaire (serveur plein)",
+            NetworkPacket::new_error(ProtocolErrorCode::ServerFull, "appel en cours", sender_id, session_id)),
+        GoldenCase::new(version, "control_renegotiate", "renégociation du codec",
+            NetworkPacket::new_control(&ControlMessage::Renegotiate { params, switch_at: 120 }, sender_id, session_id)),
+        GoldenCase::new(version, "control_ack", "acquittement de renégociation",
+            NetworkPacket::new_control(&ControlMessage::RenegotiateAck { switch_at: 120 }, sender_id, session_id)),
+        GoldenCase::new(version, "control_padding", "offre de padding",
+            NetworkPacket::new_control(&ControlMessage::Padding, sender_id, session_id)),
+        GoldenCase::new(version, "control_padding_ack", "acquittement de padding",
+            NetworkPacket::new_control(&ControlMessage::PaddingAck, sender_id, session_id)),
+        GoldenCase::new(version, "control_media_timestamps", "demande d'horodatage média",
+            NetworkPacket::new_control(&ControlMessage::MediaTimestamps, sender_id, session_id)),
+        GoldenCase::new(version, "control_media_timestamps_ack", "acquittement d'horodatage média",
+            NetworkPacket::new_control(&ControlMessage::MediaTimestampsAck, sender_id, session_id)),
+        GoldenCase::new(version, "control_describe_stream", "demande de description du flux",
+            NetworkPacket::new_control(&ControlMessage::DescribeStream, sender_id, session_id)),
+        GoldenCase::new(version, "control_stream_described", "description du flux",
+            NetworkPacket::new_control(&ControlMessage::StreamDescribed(description), sender_id, session_id)),
+        GoldenCase::new(version, "control_identity_proof", "preuve d'identité sur le défi du pair",
+            NetworkPacket::new_control(&ControlMessage::IdentityProof(proof), sender_id, session_id)),
+        GoldenCase::new(version, "control_identity_proof_ack", "acquittement de preuve d'identité",
+            NetworkPacket::new_control(&ControlMessage::IdentityProofAck, sender_id, session_id)),
+        GoldenCase::new(version, "padding", "paquet factice du mode padding",
+            NetworkPacket::new_padding(sender_id, session_id)),
+    ]
+}
+
+/// Paquets figés, au moins un par type et par variante de payload
+fn cases() -> Vec<GoldenCase> {
+    let mut cases = header_cases(NetworkPacket::CURRENT_PROTOCOL_VERSION);
+
+    // En-tête v3 (identifiants sur 32 bits) et formats bincode des versions 1 et 2
+    if cfg!(feature = "legacy-protocol") {
+        cases.extend(header_cases(3));
+        cases.extend([
+            GoldenCase::new(2, "audio", "frame audio",
+                NetworkPacket::new_audio(audio_frame(false), SENDER_ID, SESSION_ID)),
+            GoldenCase::new(2, "error", "erreur protocolaire (serveur plein)",
+                NetworkPacket::new_error(ProtocolErrorCode::ServerFull, "appel en cours", SENDER_ID, SESSION_ID)),
+            GoldenCase::new(1, "audio", "frame audio",
+                NetworkPacket::new_audio(audio_frame(false), SENDER_ID, SESSION_ID)),
+            GoldenCase::new(1, "heartbeat", "heartbeat simple",
+                NetworkPacket::new_heartbeat(SENDER_ID, SESSION_ID)),
+        ]);
+    }
+    cases
+}
+
+/// Encode comme à l'envoi : format courant, ou celui d'une version précédente
+fn encode(packet: &NetworkPacket) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if packet.protocol_version == NetworkPacket::CURRENT_PROTOCOL_VERSION {
+        packet.encode_into(&mut buffer);
+    } else {
+        #[cfg(feature = "legacy-protocol")]
+        crate::legacy::encode_legacy(packet, &mut buffer).unwrap();
+    }
+    buffer
+}
+
+/// Décode comme à la réception
+fn decode(data: &[u8]) -> Option<NetworkPacket> {
+    match NetworkPacket::peek_version(data)? {
+        NetworkPacket::CURRENT_PROTOCOL_VERSION => NetworkPacket::decode_from(data),
+        #[cfg(feature = "legacy-protocol")]
+        version => crate::legacy::decode_legacy(version, data, local_addr()).ok(),
+        #[cfg(not(feature = "legacy-protocol"))]
+        _ => None,
+    }
+}
+
+fn to_hex(description: &str, bytes: &[u8]) -> String {
+    let mut text = format!("# {}\n", description);
+    for line in bytes.chunks(BYTES_PER_LINE) {
+        let hex: Vec<String> = line.iter().map(|byte| format!("{:02x}", byte)).collect();
+        text.push_str(&hex.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+fn from_hex(text: &str) -> Vec<u8> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+        .collect()
+}
+
+/// Lit la fixture du cas (ou la réécrit si `VOC_UPDATE_GOLDEN` est défini)
+fn golden_bytes(case: &GoldenCase) -> Vec<u8> {
+    let path = case.path();
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, to_hex(case.description, &encode(&case.packet))).unwrap();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(text) => from_hex(&text),
+        Err(e) => panic!("{} : {} (créer avec {}=1 cargo test -p network golden)", path.display(), e, UPDATE_ENV),
+    }
+}
+
+/// Décrit la première différence (champ de l'en-tête courant concerné, sinon payload)
+fn first_difference(expected: &[u8], actual: &[u8]) -> String {
+    let offset = expected.iter().zip(actual).position(|(a, b)| a != b)
+        .unwrap_or(expected.len().min(actual.len()));
+    let field = NetworkPacket::WIRE_LAYOUT.iter()
+        .find(|field| (field.offset..field.offset + field.size).contains(&offset))
+        .map_or("payload", |field| field.name);
+    format!("{} octets au lieu de {}, premier écart à l'offset {} ({})", actual.len(), expected.len(), offset, field)
+}
+
+#[test]
+fn test_encoding_matches_golden_files() {
+    let changed: Vec<String> = cases().iter()
+        .filter_map(|case| {
+            let expected = golden_bytes(case);
+            let actual = encode(&case.packet);
+            (actual != expected).then(|| format!("{} : {}", case.path().display(), first_difference(&expected, &actual)))
+        })
+        .collect();
+
+    assert!(
+        changed.is_empty(),
+        "Format réseau modifié (voir docs/FORMAT_RESEAU.md avant de régénérer les fixtures) :\n{}",
+        changed.join("\n")
+    );
+}
+
+#[test]
+fn test_golden_files_decode() {
+    for case in cases() {
+        let data = golden_bytes(&case);
+        let decoded = decode(&data).unwrap_or_else(|| panic!("{} illisible", case.path().display()));
+        let expected = &case.packet;
+
+        assert!(decoded.verify_checksum(), "{}", case.name);
+        assert_eq!(decoded.protocol_version, expected.protocol_version);
+        assert_eq!(decoded.packet_type, expected.packet_type);
+        assert_eq!((decoded.sender_id, decoded.session_id), (expected.sender_id, expected.session_id));
+        assert_eq!(decoded.sequence_number(), expected.sequence_number());
+        assert_eq!(decoded.payload.to_bytes(), expected.payload.to_bytes());
+        assert_eq!(decoded.payload.original_sample_count(), expected.payload.original_sample_count());
+        assert_eq!(decoded.media_timestamp(), expected.media_timestamp());
+
+        // Les messages se relisent à l'identique
+        assert_eq!(decoded.handshake_message(), expected.handshake_message());
+        assert_eq!(decoded.handshake_info(), expected.handshake_info());
+        assert_eq!(decoded.resume_token(), expected.resume_token());
+        assert_eq!(decoded.handshake_identity(), expected.handshake_identity());
+        assert_eq!(decoded.handshake_challenge(), expected.handshake_challenge());
+        assert_eq!(decoded.peer_stats(), expected.peer_stats());
+        assert_eq!(decoded.flow_control_hint(), expected.flow_control_hint());
+        assert_eq!(decoded.heartbeat_payload(), expected.heartbeat_payload());
+        assert_eq!(decoded.discovery_message(), expected.discovery_message());
+        assert_eq!(decoded.error_message(), expected.error_message());
+        assert_eq!(decoded.control_message(), expected.control_message());
+        if expected.protocol_version >= 3 {
+            assert_eq!(decoded.flags(), expected.flags());
+        }
+    }
+}
+
+#[test]
+fn test_every_golden_file_has_a_case() {
+    // Une fixture sans cas : paquet retiré des tests, le format n'est plus figé
+    let known: Vec<PathBuf> = cases().iter().map(GoldenCase::path).collect();
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("golden");
+    for version in std::fs::read_dir(&root).unwrap() {
+        let version = version.unwrap().path();
+        // Sans legacy-protocol, seules les fixtures de la version courante sont testées
+        let current = format!("v{}", NetworkPacket::CURRENT_PROTOCOL_VERSION);
+        if !cfg!(feature = "legacy-protocol") && !version.ends_with(&current) {
+            continue;
+        }
+        for file in std::fs::read_dir(version).unwrap() {
+            let path = file.unwrap().path();
+            assert!(known.contains(&path), "{} ne correspond à aucun cas", path.display());
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Identité cryptographique persistante des pairs
+//!
+//! `sender_id` est tiré au hasard à chaque processus : il ne permet pas de
+//! reconnaître un pair d'une session à l'autre. Chaque instance possède donc
+//! une paire de clés ed25519 (`Identity`), conservée sur disque
+//! (`NetworkConfig::identity_file`). La clé publique (`PeerIdentity`) est
+//! jointe aux handshakes avec une signature qui prouve la possession de la
+//! clé privée (`HandshakeIdentity`) : l'application peut s'en servir pour ses
+//! contacts connus et ses décisions d'autorisation, puis pour l'accord de
+//! clés de la couche de chiffrement.
+//!
+//! Une signature des seuls identifiants de l'expéditeur se rejoue telle quelle
+//! (`handshake_proof`) : elle ne fait qu'annoncer la clé. La preuve couvre donc
+//! aussi un défi tiré par le pair pour ce handshake (`ProofChallenge`) :
+//! l'`Accept` prouve l'identité de l'appelé sur le défi du `Hello`, l'appelant
+//! prouve ensuite la sienne sur le défi de l'`Accept`
+//! (`ControlMessage::IdentityProof`).
+
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{NetworkError, NetworkResult};
+
+/// Préfixe des données signées, propre au handshake (une signature ne peut
+/// pas être réutilisée pour un autre usage de la clé)
+pub(crate) const HANDSHAKE_CONTEXT: &[u8] = b"voc-handshake-identity-v1";
+
+/// Préfixe des preuves liées au défi du pair (distinct des annonces)
+pub(crate) const CHALLENGE_CONTEXT: &[u8] = b"voc-handshake-identity-v2";
+
+/// Défi d'un pair : ses identifiants et une valeur tirée au hasard pour le
+/// handshake, que la preuve d'identité doit couvrir
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProofChallenge {
+    /// `sender_id` du pair qui a tiré le défi
+    pub sender_id: u64,
+    /// `session_id` de son handshake
+    pub session_id: u64,
+    /// Valeur aléatoire, nouvelle à chaque handshake
+    pub nonce: u64,
+}
+
+/// Clé publique d'un pair : son identité stable d'une session à l'autre
+///
+/// S'affiche et se lit en hexadécimal (64 caractères), pour les listes de
+/// contacts.
+///
+/// # Example
+/// ```rust
+/// use network::PeerIdentity;
+///
+/// let text = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+/// let identity: PeerIdentity = text.parse().unwrap();
+/// assert_eq!(identity.to_string(), text);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeerIdentity([u8; 32]);
+
+impl PeerIdentity {
+    /// Octets de la clé publique
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Empreinte courte, à comparer de vive voix : SHA-256 de la clé tronqué
+    /// à 128 bits, en 8 groupes de 4 chiffres hexadécimaux
+    pub fn fingerprint(&self) -> String {
+        let digest = Sha256::digest(self.0);
+        digest[..16].chunks(2).map(to_hex).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl From<[u8; 32]> for PeerIdentity {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for PeerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl fmt::Debug for PeerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PeerIdentity({})", self)
+    }
+}
+
+impl FromStr for PeerIdentity {
+    type Err = NetworkError;
+
+    fn from_str(text: &str) -> NetworkResult<Self> {
+        parse_key_hex(text).map(Self)
+    }
+}
+
+/// Paire de clés ed25519 de l'instance locale
+///
+/// La clé privée n'apparaît ni dans `Debug` ni dans les handshakes.
+///
+/// # Example
+/// ```rust,no_run
+/// use network::Identity;
+///
+/// // Créée au premier lancement, relue ensuite
+/// let identity = Identity::load_or_create("identity.key").unwrap();
+/// println!("Mon identité : {}", identity.public());
+/// ```
+#[derive(Clone)]
+pub struct Identity {
+    signing_key: SigningKey,
+}
+
+impl Identity {
+    /// Génère une nouvelle paire de clés
+    ///
+    /// # Erreurs
+    /// - `NetworkError::InitializationError` : source d'aléa du système indisponible
+    pub fn generate() -> NetworkResult<Self> {
+        let mut secret = [0u8; 32];
+        getrandom::fill(&mut secret)
+            .map_err(|e| NetworkError::InitializationError(format!("génération de l'identité: {}", e)))?;
+        Ok(Self::from_secret_bytes(secret))
+    }
+
+    /// Recrée une paire de clés à partir de sa clé privée (tests, import)
+    pub fn from_secret_bytes(secret: [u8; 32]) -> Self {
+        Self { signing_key: SigningKey::from_bytes(&secret) }
+    }
+
+    /// Lit la clé privée enregistrée par `save`
+    ///
+    /// # Erreurs
+    /// - `NetworkError::IoError` : fichier illisible
+    /// - `NetworkError::InitializationError` : contenu invalide
+    pub fn load(path: impl AsRef<Path>) -> NetworkResult<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let secret = parse_key_hex(&text).map_err(|_| {
+            NetworkError::InitializationError(format!("identité {} : clé privée invalide", path.display()))
+        })?;
+        Ok(Self::from_secret_bytes(secret))
+    }
+
+    /// Enregistre la clé privée en hexadécimal, lisible du seul utilisateur
+    /// (Unix) ; les répertoires parents sont créés au besoin
+    pub fn save(&self, path: impl AsRef<Path>) -> NetworkResult<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        writeln!(file, "{}", to_hex(&self.signing_key.to_bytes()))?;
+        Ok(())
+    }
+
+    /// Lit l'identité enregistrée dans `path`, ou en crée une au premier lancement
+    pub fn load_or_create(path: impl AsRef<Path>) -> NetworkResult<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Self::load(path);
+        }
+        let identity = Self::generate()?;
+        identity.save(path)?;
+        println!("🔑 Nouvelle identité {} enregistrée dans {}", identity.public(), path.display());
+        Ok(identity)
+    }
+
+    /// Clé publique, communiquée aux pairs
+    pub fn public(&self) -> PeerIdentity {
+        PeerIdentity(self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Annonce de l'identité dans un handshake de `sender_id` pour `session_id`
+    ///
+    /// Rejouable par qui l'a capturée : ne prouve rien (voir `challenge_proof`).
+    pub fn handshake_proof(&self, sender_id: u64, session_id: u64) -> HandshakeIdentity {
+        self.sign(&handshake_message(sender_id, session_id))
+    }
+
+    /// Preuve d'identité de `sender_id` pour `session_id`, liée au défi du pair
+    pub fn challenge_proof(&self, sender_id: u64, session_id: u64, challenge: &ProofChallenge) -> HandshakeIdentity {
+        self.sign(&challenge_message(sender_id, session_id, challenge))
+    }
+
+    /// Signe des données arbitraires (fixtures des versions précédentes)
+    #[cfg(test)]
+    pub(crate) fn sign_raw(&self, message: &[u8]) -> HandshakeIdentity {
+        self.sign(message)
+    }
+
+    fn sign(&self, message: &[u8]) -> HandshakeIdentity {
+        let signature = self.signing_key.sign(message);
+        HandshakeIdentity { public_key: self.public(), signature: signature.to_bytes().to_vec() }
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Identity").field("public", &self.public()).finish_non_exhaustive()
+    }
+}
+
+/// Identité jointe au handshake : clé publique et signature de l'en-tête
+///
+/// Voir `NetworkPacket::new_handshake_with_identity`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeIdentity {
+    /// Clé publique de l'expéditeur
+    pub public_key: PeerIdentity,
+    /// Signature ed25519 de `sender_id` et `session_id`, et du défi du pair
+    /// pour une preuve (64 octets)
+    pub signature: Vec<u8>,
+}
+
+impl HandshakeIdentity {
+    /// Identité prouvée par `sender_id` pour `session_id` sur notre défi
+    ///
+    /// `None` si la signature ne correspond pas : l'expéditeur ne possède
+    /// pas la clé privée, le paquet a été modifié, ou la preuve a été
+    /// capturée dans un autre handshake (autre défi) et rejouée.
+    ///
+    /// # Example
+    /// ```rust
+    /// use network::{Identity, ProofChallenge};
+    ///
+    /// let identity = Identity::from_secret_bytes([7; 32]);
+    /// let challenge = ProofChallenge { sender_id: 3, session_id: 4, nonce: 42 };
+    /// let proof = identity.challenge_proof(1, 2, &challenge);
+    /// assert_eq!(proof.verify(1, 2, &challenge), Some(identity.public()));
+    ///
+    /// // Une simple annonce ne prouve rien
+    /// assert_eq!(identity.handshake_proof(1, 2).verify(1, 2, &challenge), None);
+    /// ```
+    pub fn verify(&self, sender_id: u64, session_id: u64, challenge: &ProofChallenge) -> Option<PeerIdentity> {
+        let key = VerifyingKey::from_bytes(self.public_key.as_bytes()).ok()?;
+        let signature = Signature::from_slice(&self.signature).ok()?;
+        key.verify_strict(&challenge_message(sender_id, session_id, challenge), &signature).ok()?;
+        Some(self.public_key)
+    }
+}
+
+/// Données signées dans une annonce
+fn handshake_message(sender_id: u64, session_id: u64) -> Vec<u8> {
+    let mut message = HANDSHAKE_CONTEXT.to_vec();
+    message.extend_from_slice(&sender_id.to_be_bytes());
+    message.extend_from_slice(&session_id.to_be_bytes());
+    message
+}
+
+/// Données signées dans une preuve : nos identifiants, puis le défi du pair
+fn challenge_message(sender_id: u64, session_id: u64, challenge: &ProofChallenge) -> Vec<u8> {
+    let mut message = CHALLENGE_CONTEXT.to_vec();
+    message.extend_from_slice(&sender_id.to_be_bytes());
+    message.extend_from_slice(&session_id.to_be_bytes());
+    message.extend_from_slice(&challenge.sender_id.to_be_bytes());
+    message.extend_from_slice(&challenge.session_id.to_be_bytes());
+    message.extend_from_slice(&challenge.nonce.to_be_bytes());
+    message
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Clé de 32 octets en hexadécimal (espaces autour ignorés)
+fn parse_key_hex(text: &str) -> NetworkResult<[u8; 32]> {
+    let text = text.trim();
+    let invalid = || NetworkError::ConfigError(format!("clé invalide (64 caractères hexadécimaux attendus) : {:?}", text));
+    if text.len() != 64 || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 32];
+    for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks_exact(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_survives_restart() {
+        let path = std::env::temp_dir().join(format!("voc-identity-{}.key", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let created = Identity::load_or_create(&path).unwrap();
+        let reloaded = Identity::load_or_create(&path).unwrap();
+        assert_eq!(created.public(), reloaded.public());
+        assert_ne!(Identity::generate().unwrap().public(), created.public());
+
+        fs::write(&path, "pas une clé").unwrap();
+        assert!(Identity::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_challenge_proof() {
+        let identity = Identity::from_secret_bytes([7; 32]);
+        let challenge = ProofChallenge { sender_id: 3, session_id: 4, nonce: 42 };
+        let proof = identity.challenge_proof(1, 2, &challenge);
+        assert_eq!(proof.verify(1, 2, &challenge), Some(identity.public()));
+
+        // Rejouée pour un autre en-tête, sur un nouveau défi, ou avec une autre clé
+        assert_eq!(proof.verify(1, 3, &challenge), None);
+        assert_eq!(proof.verify(1 | 1 << 32, 2, &challenge), None);
+        assert_eq!(proof.verify(1, 2, &ProofChallenge { nonce: 43, ..challenge }), None);
+        let forged = HandshakeIdentity { public_key: Identity::from_secret_bytes([8; 32]).public(), ..proof };
+        assert_eq!(forged.verify(1, 2, &challenge), None);
+    }
+}
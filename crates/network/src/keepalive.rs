@@ -0,0 +1,313 @@
+//! Heartbeats émis hors du runtime async
+//!
+//! Le pair coupe la connexion s'il ne reçoit aucun heartbeat pendant
+//! `heartbeat_timeout`. Si le runtime tokio de l'application est bloqué
+//! quelques instants (pause du ramasse-miettes d'un hôte, déplacement de
+//! fenêtre sous Windows), les heartbeats envoyés depuis une tâche async
+//! s'arrêtent aussi. Ils partent donc d'un thread dédié, qui n'utilise que des
+//! appels bloquants sur une copie du socket : il ne dépend d'aucun runtime.
+//!
+//! Un second thread (`LivenessWatchdog`) surveille les arrivées du pair,
+//! pour qu'un client qui ne lit pas le réseau constate lui aussi sa
+//! disparition.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{NetworkPacket, NetworkResult};
+
+/// Envoi synchrone de paquets, utilisable depuis n'importe quel thread
+///
+/// Fourni par les transports qui le permettent (`NetworkTransport::keepalive_sink`).
+/// Les datagrammes envoyés par ce chemin ne passent ni par les statistiques
+/// ni par le tap de capture du transport.
+pub trait KeepaliveSink: Send + Sync {
+    /// Envoie un paquet sans attendre (un envoi impossible est une erreur, pas une attente)
+    fn send_now(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()>;
+}
+
+/// Observation des arrivées, utilisable depuis n'importe quel thread
+///
+/// Fournie par les transports qui le permettent (`NetworkTransport::keepalive_source`).
+/// Les datagrammes repérés ne sont pas consommés : ils restent dus à la
+/// prochaine `NetworkTransport::receive_packet`, qui les traite comme les
+/// autres (statistiques, tap de capture, durées d'étapes).
+pub trait KeepaliveSource: Send + Sync {
+    /// Instant de la dernière arrivée d'un datagramme de `peer_addr`, sans attendre
+    ///
+    /// Compte aussi bien les datagrammes déjà reçus par le transport que
+    /// ceux arrivés depuis et pas encore lus. Les arrivées antérieures au
+    /// premier appel pour `peer_addr` peuvent être ignorées.
+    fn last_arrival(&self, peer_addr: SocketAddr) -> Option<Instant>;
+}
+
+/// Dernière arrivée du pair surveillé, notée par les réceptions du
+/// transport comme par son `KeepaliveSource`
+#[cfg(any(feature = "udp", feature = "simulator"))]
+#[derive(Debug, Default)]
+pub(crate) struct ArrivalMonitor {
+    watched: Mutex<Option<(SocketAddr, Option<Instant>)>>,
+}
+
+#[cfg(any(feature = "udp", feature = "simulator"))]
+impl ArrivalMonitor {
+    /// Note l'arrivée d'un datagramme de `source` (ignorée hors du pair surveillé)
+    pub(crate) fn record(&self, source: SocketAddr, at: Instant) {
+        if let Some((watched, last)) = self.watched.lock().unwrap().as_mut()
+            && *watched == source
+        {
+            *last = Some(last.map_or(at, |last| last.max(at)));
+        }
+    }
+
+    /// Surveille désormais `peer_addr` et retourne sa dernière arrivée notée
+    pub(crate) fn watch(&self, peer_addr: SocketAddr) -> Option<Instant> {
+        let mut watched = self.watched.lock().unwrap();
+        match *watched {
+            Some((addr, last)) if addr == peer_addr => last,
+            _ => {
+                *watched = Some((peer_addr, None));
+                None
+            }
+        }
+    }
+}
+
+/// Thread d'envoi périodique des heartbeats (et des annonces de présence)
+///
+/// Le thread s'arrête quand la structure est détruite (ou via `stop`).
+#[derive(Debug)]
+pub(crate) struct KeepaliveThread {
+    /// Fermé pour réveiller et arrêter le thread
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+    /// Instant du dernier heartbeat parti
+    last_sent: Arc<Mutex<Option<Instant>>>,
+}
+
+impl KeepaliveThread {
+    /// Démarre l'envoi d'un heartbeat toutes les `interval`, le premier immédiatement
+    ///
+    /// # Arguments
+    /// * `sink` - Chemin d'envoi synchrone fourni par le transport
+    /// * `heartbeat` - Paquet à envoyer (checksum et timestamp refaits à chaque envoi)
+    /// * `peer_addr` - Adresse du pair
+    /// * `interval` - Période d'envoi (`NetworkConfig::heartbeat_interval`)
+    pub(crate) fn spawn(
+        sink: Arc<dyn KeepaliveSink>,
+        heartbeat: NetworkPacket,
+        peer_addr: SocketAddr,
+        interval: Duration,
+    ) -> std::io::Result<Self> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let last_sent = Arc::new(Mutex::new(None));
+        let sent = last_sent.clone();
+        let handle = std::thread::Builder::new()
+            .name("voc-keepalive".to_string())
+            .spawn(move || loop {
+                match sink.send_now(&heartbeat, peer_addr) {
+                    Ok(()) => *sent.lock().unwrap() = Some(Instant::now()),
+                    Err(e) => println!("{:?} vers {} non envoyé : {}", heartbeat.packet_type, peer_addr, e),
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            })?;
+
+        Ok(Self { stop: Some(stop), handle: Some(handle), last_sent })
+    }
+
+    /// Instant du dernier heartbeat envoyé avec succès
+    pub(crate) fn last_sent(&self) -> Option<Instant> {
+        *self.last_sent.lock().unwrap()
+    }
+
+    /// Arrête le thread et attend sa fin (immédiate : il est réveillé)
+    pub(crate) fn stop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for KeepaliveThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Surveillance du silence du pair, hors des lectures du manager
+///
+/// Les paquets entrants ne sont traités que pendant `receive_audio` : sans
+/// ce thread, un client qui n'attend pas d'audio ne verrait jamais le pair
+/// disparaître. Le thread consulte les arrivées du transport (sans rien
+/// consommer) et appelle `on_silence` si le pair reste muet plus de
+/// `timeout`. Il s'arrête alors, ou quand la structure est détruite.
+#[derive(Debug)]
+pub(crate) struct LivenessWatchdog {
+    /// Fermé pour réveiller et arrêter le thread
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+    /// Le pair a été déclaré muet
+    expired: Arc<AtomicBool>,
+}
+
+impl LivenessWatchdog {
+    /// Période de consultation des arrivées
+    pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+    /// Démarre la surveillance de `peer_addr`
+    ///
+    /// # Arguments
+    /// * `source` - Observation des arrivées fournie par le transport
+    /// * `peer_addr` - Adresse du pair
+    /// * `timeout` - Silence toléré (`NetworkConfig::heartbeat_timeout`)
+    /// * `on_silence` - Appelé depuis le thread quand le silence dépasse `timeout`
+    pub(crate) fn spawn(
+        source: Arc<dyn KeepaliveSource>,
+        peer_addr: SocketAddr,
+        timeout: Duration,
+        on_silence: impl FnOnce() + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let expired = Arc::new(AtomicBool::new(false));
+        let silenced = expired.clone();
+        let started_at = Instant::now();
+        let handle = std::thread::Builder::new()
+            .name("voc-watchdog".to_string())
+            .spawn(move || loop {
+                match stopped.recv_timeout(Self::POLL_INTERVAL) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => return,
+                }
+                let heard = source.last_arrival(peer_addr).map_or(started_at, |at| at.max(started_at));
+                if heard.elapsed() > timeout {
+                    silenced.store(true, Ordering::Release);
+                    on_silence();
+                    return;
+                }
+            })?;
+
+        Ok(Self { stop: Some(stop), handle: Some(handle), expired })
+    }
+
+    /// Le pair a-t-il été déclaré muet ?
+    pub(crate) fn expired(&self) -> bool {
+        self.expired.load(Ordering::Acquire)
+    }
+
+    /// Arrête le thread et attend sa fin (immédiate : il est réveillé)
+    pub(crate) fn stop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for LivenessWatchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    /// Compte les heartbeats envoyés
+    #[derive(Default)]
+    struct CountingSink(Mutex<Vec<Instant>>);
+
+    impl KeepaliveSink for CountingSink {
+        fn send_now(&self, _packet: &NetworkPacket, _target_addr: SocketAddr) -> NetworkResult<()> {
+            self.0.lock().unwrap().push(Instant::now());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_keepalive_thread_runs_until_stopped() {
+        let sink = Arc::new(CountingSink::default());
+        let peer: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let mut thread = KeepaliveThread::spawn(
+            sink.clone(),
+            NetworkPacket::new_heartbeat(1, 2),
+            peer,
+            Duration::from_millis(10),
+        ).unwrap();
+
+        // Le thread avance même si l'appelant est bloqué
+        std::thread::sleep(Duration::from_millis(55));
+        thread.stop();
+        let sent = sink.0.lock().unwrap().len();
+        let last = *sink.0.lock().unwrap().last().unwrap();
+        assert!(thread.last_sent().is_some_and(|at| at >= last));
+        assert!(sent >= 3, "{} heartbeats seulement", sent);
+
+        // Plus rien après l'arrêt
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(sink.0.lock().unwrap().len(), sent);
+    }
+
+    /// Arrivées du pair simulées par le test
+    #[derive(Default)]
+    struct ScriptedSource(Mutex<Option<Instant>>);
+
+    impl KeepaliveSource for ScriptedSource {
+        fn last_arrival(&self, _peer_addr: SocketAddr) -> Option<Instant> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_watchdog_detects_silence() {
+        let source = Arc::new(ScriptedSource::default());
+        let peer: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let (silenced, on_silence) = mpsc::channel();
+        let watchdog = LivenessWatchdog::spawn(
+            source.clone(),
+            peer,
+            Duration::from_millis(60),
+            move || silenced.send(()).unwrap(),
+        ).unwrap();
+
+        // Pair vivant
+        for _ in 0..5 {
+            *source.0.lock().unwrap() = Some(Instant::now());
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        assert!(!watchdog.expired());
+
+        // Pair muet
+        on_silence.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(watchdog.expired());
+    }
+
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    #[test]
+    fn test_arrival_monitor_tracks_watched_peer() {
+        let monitor = ArrivalMonitor::default();
+        let peer: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let other: SocketAddr = "127.0.0.1:9002".parse().unwrap();
+        let now = Instant::now();
+
+        monitor.record(peer, now);
+        assert_eq!(monitor.watch(peer), None);
+        monitor.record(peer, now);
+        monitor.record(other, now + Duration::from_millis(10));
+        assert_eq!(monitor.watch(peer), Some(now));
+        // Une arrivée plus ancienne notée en retard ne recule pas
+        monitor.record(peer, now - Duration::from_millis(10));
+        assert_eq!(monitor.watch(peer), Some(now));
+        assert_eq!(monitor.watch(other), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Convertisseurs pour les anciennes versions du protocole
+//!
+//! Disponible avec la feature `legacy-protocol` (activée par défaut).
+//! Permet de dialoguer avec un pair qui parle une version comprise entre
+//! `NetworkPacket::MIN_SUPPORTED_PROTOCOL_VERSION` et la version courante :
+//! - à la réception, le paquet legacy est converti en `NetworkPacket`
+//!   (le champ `protocol_version` conserve la version du pair)
+//! - à l'envoi, un paquet dont `protocol_version` est ancien est réécrit
+//!   dans le format de cette version
+//!
+//! Versions connues :
+//! - v1 : sérialisation bincode, sans les types `Discovery` et `Error`
+//! - v2 : sérialisation bincode de `NetworkPacket` (avant l'en-tête explicite v3),
+//!   quand chaque paquet portait une frame audio (`PacketV2`)
+//! - v3 : en-tête explicite de 32 octets, identifiants sur 32 bits (avant la v4)
+//!
+//! Les versions 1 à 3 ne portent que les 32 bits de poids faible des
+//! identifiants (`NetworkPacket::wire_id`).
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::Instant;
+use audio::CompressedFrame;
+
+use crate::{NetworkPacket, PacketPayload, PacketType, NetworkResult, NetworkError};
+
+/// Taille de l'en-tête v3 : celui de la v4 avec des identifiants sur 32 bits
+const HEADER_SIZE_V3: usize = 32;
+
+/// Types de paquets du protocole v1
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum PacketTypeV1 {
+    Audio,
+    Heartbeat,
+    Handshake,
+    Disconnect,
+}
+
+/// Format d'un paquet du protocole v1
+#[derive(Serialize, Deserialize)]
+struct PacketV1 {
+    protocol_version: u8,
+    packet_type: PacketTypeV1,
+    sender_id: u32,
+    session_id: u32,
+    compressed_frame: CompressedFrame,
+    checksum: u32,
+}
+
+impl From<PacketV1> for NetworkPacket {
+    fn from(packet: PacketV1) -> Self {
+        let packet_type = match packet.packet_type {
+            PacketTypeV1::Audio => PacketType::Audio,
+            PacketTypeV1::Heartbeat => PacketType::Heartbeat,
+            PacketTypeV1::Handshake => PacketType::Handshake,
+            PacketTypeV1::Disconnect => PacketType::Disconnect,
+        };
+
+        PacketV2 {
+            protocol_version: packet.protocol_version,
+            packet_type,
+            sender_id: packet.sender_id,
+            session_id: packet.session_id,
+            compressed_frame: packet.compressed_frame,
+            checksum: packet.checksum,
+        }.into()
+    }
+}
+
+/// Format d'un paquet du protocole v2
+#[derive(Serialize, Deserialize)]
+struct PacketV2 {
+    protocol_version: u8,
+    packet_type: PacketType,
+    sender_id: u32,
+    session_id: u32,
+    compressed_frame: CompressedFrame,
+    checksum: u32,
+}
+
+impl From<PacketV2> for NetworkPacket {
+    fn from(packet: PacketV2) -> Self {
+        NetworkPacket {
+            protocol_version: packet.protocol_version,
+            packet_type: packet.packet_type,
+            sender_id: u64::from(packet.sender_id),
+            session_id: u64::from(packet.session_id),
+            payload: PacketPayload::from_frame(packet.packet_type, packet.compressed_frame),
+            send_timestamp: Instant::now(),
+            checksum: packet.checksum,
+        }
+    }
+}
+
+impl From<&NetworkPacket> for PacketV2 {
+    fn from(packet: &NetworkPacket) -> Self {
+        PacketV2 {
+            protocol_version: packet.protocol_version,
+            packet_type: packet.packet_type,
+            sender_id: packet.sender_id as u32,
+            session_id: packet.session_id as u32,
+            compressed_frame: packet.payload.to_frame(),
+            checksum: packet.checksum,
+        }
+    }
+}
+
+/// Désérialise un paquet d'une ancienne version du protocole
+///
+/// Le checksum est vérifié ensuite par l'appelant, comme pour un paquet courant.
+pub(crate) fn decode_legacy(version: u8, data: &[u8], source_addr: SocketAddr) -> NetworkResult<NetworkPacket> {
+    match version {
+        1 => {
+            let packet: PacketV1 = bincode::deserialize(data)
+                .map_err(|_| NetworkError::InvalidPacketFormat { addr: source_addr })?;
+            Ok(packet.into())
+        }
+        2 => {
+            let packet: PacketV2 = bincode::deserialize(data)
+                .map_err(|_| NetworkError::InvalidPacketFormat { addr: source_addr })?;
+            Ok(packet.into())
+        }
+        3 => {
+            // En-tête v4 reconstitué : identifiants complétés de 32 bits nuls
+            if data.len() < HEADER_SIZE_V3 {
+                return Err(NetworkError::InvalidPacketFormat { addr: source_addr });
+            }
+            let mut wide = Vec::with_capacity(data.len() + NetworkPacket::HEADER_SIZE - HEADER_SIZE_V3);
+            wide.extend_from_slice(&data[..8]);
+            wide.extend_from_slice(&[0; 4]);
+            wide.extend_from_slice(&data[8..12]);
+            wide.extend_from_slice(&[0; 4]);
+            wide.extend_from_slice(&data[12..]);
+            NetworkPacket::decode_from(&wide)
+                .ok_or(NetworkError::InvalidPacketFormat { addr: source_addr })
+        }
+        _ => Err(NetworkError::unsupported_version(source_addr, version)),
+    }
+}
+
+/// Sérialise un paquet dans le format de `packet.protocol_version`
+///
+/// Échoue si le type de paquet n'existe pas dans cette version.
+pub(crate) fn encode_legacy(packet: &NetworkPacket, buffer: &mut Vec<u8>) -> NetworkResult<()> {
+    match packet.protocol_version {
+        1 => {
+            let packet_type = match packet.packet_type {
+                PacketType::Audio => PacketTypeV1::Audio,
+                PacketType::Heartbeat => PacketTypeV1::Heartbeat,
+                PacketType::Handshake => PacketTypeV1::Handshake,
+                PacketType::Disconnect => PacketTypeV1::Disconnect,
+                other => return Err(NetworkError::ConfigError(
+                    format!("paquet {:?} inexistant en protocole v1", other)
+                )),
+            };
+
+            let legacy = PacketV1 {
+                protocol_version: packet.protocol_version,
+                packet_type,
+                sender_id: packet.sender_id as u32,
+                session_id: packet.session_id as u32,
+                compressed_frame: packet.payload.to_frame(),
+                checksum: packet.checksum,
+            };
+
+            buffer.clear();
+            bincode::serialize_into(&mut *buffer, &legacy)?;
+            Ok(())
+        }
+        2 => {
+            buffer.clear();
+            bincode::serialize_into(&mut *buffer, &PacketV2::from(packet))?;
+            Ok(())
+        }
+        3 => {
+            // En-tête v4 dont on retire les 32 bits de poids fort des identifiants
+            packet.encode_into(buffer);
+            buffer.drain(16..20);
+            buffer.drain(8..12);
+            Ok(())
+        }
+        version => Err(NetworkError::ConfigError(
+            format!("encodage en protocole v{} non supporté", version)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_roundtrip() {
+        let addr: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let frame = CompressedFrame::new(vec![1, 2, 3], 960, Instant::now(), 7);
+        let mut packet = NetworkPacket::new_audio(frame, 11, 22);
+        packet.protocol_version = 1;
+        packet.checksum = packet.calculate_checksum();
+
+        let mut buffer = Vec::new();
+        encode_legacy(&packet, &mut buffer).unwrap();
+        assert_eq!(NetworkPacket::peek_version(&buffer), Some(1));
+
+        let decoded = decode_legacy(1, &buffer, addr).unwrap();
+        assert_eq!(decoded.protocol_version, 1);
+        assert_eq!(decoded.packet_type, PacketType::Audio);
+        assert_eq!(decoded.audio_frame().unwrap().data, vec![1, 2, 3]);
+        assert!(decoded.verify_checksum());
+
+        // v2 : bincode de l'ancien NetworkPacket, frame toujours présente
+        packet.protocol_version = 2;
+        packet.checksum = packet.calculate_checksum();
+        encode_legacy(&packet, &mut buffer).unwrap();
+        assert_eq!(NetworkPacket::peek_version(&buffer), Some(2));
+        let decoded = decode_legacy(2, &buffer, addr).unwrap();
+        assert_eq!(decoded.sender_id, 11);
+        assert!(decoded.verify_checksum());
+
+        // Les messages passent par la frame et retrouvent leur payload
+        let mut error = NetworkPacket::new_error(crate::ProtocolErrorCode::ServerFull, "plein", 11, 22);
+        error.protocol_version = 2;
+        error.checksum = error.calculate_checksum();
+        encode_legacy(&error, &mut buffer).unwrap();
+        let decoded = decode_legacy(2, &buffer, addr).unwrap();
+        assert_eq!(decoded.payload, error.payload);
+        assert!(decoded.verify_checksum());
+
+        // v3 : en-tête explicite, seuls les 32 bits de poids faible des identifiants
+        let frame = CompressedFrame::new(vec![4, 5, 6], 960, Instant::now(), 8);
+        let mut packet = NetworkPacket::new_audio(frame, 0x0000_0009_0000_000B, 0x0000_0009_0000_0016);
+        packet.protocol_version = 3;
+        packet.checksum = packet.calculate_checksum();
+        encode_legacy(&packet, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), HEADER_SIZE_V3 + 3);
+        assert_eq!(NetworkPacket::peek_version(&buffer), Some(3));
+        let decoded = decode_legacy(3, &buffer, addr).unwrap();
+        assert_eq!((decoded.sender_id, decoded.session_id), (11, 22));
+        assert_eq!(decoded.audio_frame().unwrap().data, vec![4, 5, 6]);
+        assert_eq!(decoded.sequence_number(), 8);
+        assert!(decoded.verify_checksum());
+        assert!(decode_legacy(3, &buffer[..HEADER_SIZE_V3 - 1], addr).is_err());
+
+        // Bourrage du mode padding : flags et taille à la même place qu'en v4
+        assert!(NetworkPacket::pad_datagram(&mut buffer, 64));
+        let decoded = decode_legacy(3, &buffer, addr).unwrap();
+        assert_eq!(decoded.audio_frame().unwrap().data, vec![4, 5, 6]);
+        assert!(decoded.verify_checksum());
+
+        // Les paquets apparus en v2 ne peuvent pas être envoyés à un pair v1
+        let mut discovery = NetworkPacket::new_heartbeat(11, 22);
+        discovery.packet_type = PacketType::Discovery;
+        discovery.protocol_version = 1;
+        assert!(encode_legacy(&discovery, &mut buffer).is_err());
+    }
+}
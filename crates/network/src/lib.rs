@@ -10,8 +10,53 @@
 //! - `error` : Gestion d'erreurs avec types spécialisés réseau
 //! - `types` : Types de données (paquets, états, configurations, statistiques)
 //! - `traits` : Traits abstraits pour transport, manager, monitoring
-//! - `transport` : Implémentations UDP (réel et simulé)
-//! - `manager` : Manager haut niveau P2P avec logique métier
+//! - `transport` : Implémentations UDP (réel et simulé, features `udp` et `simulator`)
+//! - `engine` : Cœur du protocole sans entrées-sorties (handshake, heartbeats, buffer anti-jitter)
+//! - `manager` : Manager haut niveau P2P, pilote du moteur de protocole sur le transport
+//! - `keepalive` : Heartbeats et surveillance du pair depuis des threads dédiés, hors runtime async
+//! - `priority` : Priorité des paquets de contrôle sur l'audio quand le socket sature
+//! - `runtime` : Exécution dans un runtime tokio injecté par l'application
+//! - `sync_sender` : Envoi audio sans verrou depuis le thread de capture temps réel
+//! - `timing` : Durée des étapes du chemin de réception (désérialisation, checksum, buffer)
+//! - `state` : Machine à états de la connexion et historique des transitions
+//! - `error_log` : Journalisation limitée des erreurs réseau répétées
+//! - `quality` : Historique de la qualité de connexion (RTT, perte, gigue, débit)
+//! - `identity` : Identité cryptographique persistante (ed25519) prouvée dans les handshakes
+//! - `trust` : Confiance au premier usage dans l'identité des pairs (pairs connus)
+//! - `degradation` : Échelle de dégradation progressive quand la qualité reste mauvaise
+//! - `congestion` : Contrôle de congestion pluggable et pacing des envois
+//! - `discovery` : Découverte de pairs sur le réseau local (broadcast UDP)
+//! - `presence` : Annonces périodiques de présence sur le LAN (« qui est en ligne »)
+//! - `relay` : Relais audio pour les pairs injoignables en direct, et son en-tête
+//! - `port_mapping` : Redirection de port UPnP / NAT-PMP (feature `upnp`)
+//! - `proxy` : Transport UDP via proxy SOCKS5 (UDP ASSOCIATE, feature `udp`)
+//! - `capture` : Tap de capture des datagrammes et enregistrement pcapng
+//! - `selftest` : Auto-diagnostic de bout en bout sans pair distant (features `simulator` et `audio-reexports`)
+//! - `legacy` : Convertisseurs des anciennes versions du protocole (feature `legacy-protocol`)
+//! - `golden` : Tests du format réseau contre les fixtures figées de `golden/` (tests uniquement)
+//! - `prelude` : Surface stable (traits, configuration, erreurs, manager), à importer d'un bloc
+//! 
+//! Les structures de configuration et de statistiques, `NetworkError` et
+//! `TrySendError` sont `#[non_exhaustive]` : partir de `Default` puis
+//! modifier les champs, et garder un bras `_` dans les `match`.
+//! 
+//! # Features
+//! 
+//! - `udp` (par défaut) : `UdpTransport`, `Socks5UdpTransport` et `UdpNetworkManager::new`
+//! - `simulator` (par défaut) : `SimulatedTransport`, `UdpNetworkManager::new_simulated`
+//! - `audio-reexports` (par défaut) : capture, lecture et codec Opus du crate
+//!   audio (cpal, libopus) ; avec `simulator`, active `UdpNetworkManager::self_test`
+//! - `legacy-protocol` (par défaut) : lecture des anciennes versions du protocole
+//! - `upnp` : redirection de port automatique
+//! 
+//! Sans les features par défaut, il reste le protocole (`ProtocolEngine`,
+//! paquets, configuration) et le manager sur un transport fourni via
+//! `UdpNetworkManager::with_transport` : de quoi construire un serveur de
+//! signalisation sans dépendre des bibliothèques audio du système.
+//! 
+//! ```toml
+//! network = { path = "../network", default-features = false }
+//! ```
 //! 
 //! # Examples
 //! 
@@ -77,15 +122,46 @@
 mod error;
 mod types;
 mod traits;
+#[cfg(any(feature = "udp", feature = "simulator"))]
 mod transport;
 mod manager;
+mod engine;
+mod state;
+mod error_log;
+mod quality;
+mod degradation;
+mod identity;
+mod trust;
+mod congestion;
+mod discovery;
+mod presence;
+mod relay;
+mod capture;
+mod keepalive;
+mod priority;
+mod runtime;
+mod sync_sender;
+mod timing;
+#[cfg(all(feature = "simulator", feature = "audio-reexports"))]
+mod selftest;
+#[cfg(feature = "legacy-protocol")]
+mod legacy;
+#[cfg(test)]
+mod golden;
+#[cfg(feature = "udp")]
+mod proxy;
+#[cfg(feature = "upnp")]
+mod port_mapping;
+pub mod prelude;
 
 // Re-exports publics
-pub use error::{NetworkError, NetworkResult};
+pub use error::{BindDiagnostic, BindFailure, NetworkError, NetworkResult};
 
 pub use types::{
-    NetworkPacket, PacketType, ConnectionState, ConnectionQuality,
-    NetworkConfig, NetworkStats
+    NetworkPacket, PacketPayload, PacketType, ConnectionState, ConnectionQuality, NetworkEvent,
+    NetworkConfig, NetworkStats, NetworkStatsInterval, Liveness, ProxyConfig, PresenceConfig, PaddingConfig, DegradationConfig, RedundancyMode, NetworkInterface,
+    ProtocolErrorCode, ProtocolErrorMessage, HandshakeMessage, HandshakeInfo, PeerStatsReport, FlowControlHint, WireField, SessionRoute,
+    CodecKind, CodecParams, ControlMessage, SendOutcome, StreamDescription, StreamResync
 };
 
 pub use traits::{
@@ -93,9 +169,44 @@ pub use traits::{
     BufferStats, NetworkSimulator, NetworkTestMode, SimulationParams, PerformanceReport
 };
 
-pub use transport::{UdpTransport, SimulatedTransport};
+#[cfg(feature = "udp")]
+pub use transport::UdpTransport;
+#[cfg(feature = "simulator")]
+pub use transport::SimulatedTransport;
+
+#[cfg(feature = "udp")]
+pub use proxy::Socks5UdpTransport;
+
+pub use keepalive::{KeepaliveSink, KeepaliveSource};
+pub use sync_sender::{SyncAudioSender, TrySendError};
+pub use timing::{ReceiveStage, ReceiveTimings, StageTiming, TimingStats, TIMING_BUCKETS};
+
+pub use capture::{
+    TapDirection, TapEvent, PacketTap, CaptureWriter, CapturedDatagram
+};
 
 pub use manager::UdpNetworkManager;
+pub use engine::{ProtocolEngine, ProtocolAction};
+pub use state::StateTransition;
+pub use quality::QualitySample;
+pub use degradation::{DegradationEvent, DegradationLadder, DegradationStep};
+pub use identity::{HandshakeIdentity, Identity, PeerIdentity, ProofChallenge};
+pub use trust::{KnownPeer, KnownPeers, TrustCallback, TrustDecision, TrustRequest, TrustStatus};
+
+pub use congestion::{
+    CongestionController, CongestionState, CongestionPhase, DelayBasedController, Pacer
+};
+
+pub use discovery::{DiscoveredPeer, DiscoveryMessage, JitterReport, PingReport};
+pub use presence::{PresenceCapabilities, PresencePeer, PRESENCE_PORT};
+pub use relay::{RelayStats, RELAY_MAGIC};
+#[cfg(feature = "udp")]
+pub use relay::RelayServer;
+#[cfg(all(feature = "simulator", feature = "audio-reexports"))]
+pub use selftest::{SelfTestReport, SELF_TEST_DURATION, SELF_TEST_LOSS_RATE};
+
+#[cfg(feature = "upnp")]
+pub use port_mapping::{PortMapper, PortMapping, PortMappingMethod};
 
 // Re-exports depuis le crate audio (pour simplicité d'utilisation)
 pub use audio::CompressedFrame;
@@ -106,7 +217,10 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Fonctions utilitaires pour l'utilisateur final
 pub mod utils {
     use super::*;
-    use std::net::{SocketAddr, IpAddr, Ipv4Addr};
+    use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr};
+    
+    /// Alphabet Crockford base32 des codes de connexion (sans I, L, O, U)
+    const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
     
     /// Parse une adresse IP:PORT depuis une string
     /// 
@@ -146,6 +260,8 @@ pub mod utils {
     /// Détecte l'adresse IP locale principale
     /// 
     /// Utile pour afficher l'adresse à laquelle les autres peuvent se connecter.
+    /// Première de `local_ip_candidates(None)` : fonctionne aussi sur un LAN
+    /// sans accès à Internet.
     /// 
     /// # Example
     /// ```rust
@@ -156,20 +272,453 @@ pub mod utils {
     /// }
     /// ```
     pub fn get_local_ip() -> NetworkResult<IpAddr> {
-        // Méthode simple : se connecte à un serveur externe pour déduire l'IP locale
-        use std::net::UdpSocket;
-        
-        let socket = UdpSocket::bind("0.0.0.0:0")
-            .map_err(|e| NetworkError::IoError(e))?;
-            
-        // Se "connecte" à 8.8.8.8:80 (ne fait que configurer le routage)
-        socket.connect("8.8.8.8:80")
-            .map_err(|e| NetworkError::IoError(e))?;
-            
-        let local_addr = socket.local_addr()
-            .map_err(|e| NetworkError::IoError(e))?;
-            
-        Ok(local_addr.ip())
+        local_ip_candidates(None)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| NetworkError::InitializationError("aucune adresse IP locale hors bouclage".to_string()))
+    }
+    
+    /// Adresses IP locales joignables par un pair, de la plus probable à la moins probable
+    /// 
+    /// Énumère les interfaces sans envoyer de trafic, hors bouclage et IPv6
+    /// lien-local : adresses privées (RFC 1918) d'abord, puis IPv4 publiques,
+    /// IPv6 globales et enfin IPv4 lien-local (169.254/16, réseau sans DHCP).
+    /// 
+    /// # Arguments
+    /// * `target_hint` - Adresse du pair ou de la passerelle visée, si connue :
+    ///   l'adresse locale que le système utiliserait pour la joindre passe en tête
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// let candidates = utils::local_ip_candidates(None).unwrap();
+    /// assert!(candidates.iter().all(|ip| !ip.is_loopback()));
+    /// ```
+    pub fn local_ip_candidates(target_hint: Option<IpAddr>) -> NetworkResult<Vec<IpAddr>> {
+        let mut candidates: Vec<IpAddr> = list_interfaces()?
+            .into_iter()
+            .filter(|interface| !interface.is_loopback)
+            .flat_map(|interface| interface.ips)
+            .filter(|ip| local_ip_rank(ip).is_some())
+            .collect();
+        candidates.sort_by_key(local_ip_rank);
+        candidates.dedup();
+        
+        // Route vers la cible : `connect` UDP ne fait que choisir l'interface, rien n'est envoyé
+        let routed = target_hint.and_then(|target| {
+            let unspecified: IpAddr = if target.is_ipv4() { Ipv4Addr::UNSPECIFIED.into() } else { Ipv6Addr::UNSPECIFIED.into() };
+            let socket = std::net::UdpSocket::bind(SocketAddr::new(unspecified, 0)).ok()?;
+            socket.connect(SocketAddr::new(target, 9)).ok()?;
+            socket.local_addr().ok().map(|addr| addr.ip())
+        });
+        if let Some(position) = routed.and_then(|ip| candidates.iter().position(|candidate| *candidate == ip)) {
+            let preferred = candidates.remove(position);
+            candidates.insert(0, preferred);
+        }
+        
+        Ok(candidates)
+    }
+    
+    /// Ordre de préférence d'une adresse locale (`None` : à exclure)
+    pub(crate) fn local_ip_rank(ip: &IpAddr) -> Option<u8> {
+        match ip {
+            _ if ip.is_loopback() || ip.is_unspecified() => None,
+            IpAddr::V4(v4) if v4.is_private() => Some(0),
+            IpAddr::V4(v4) if v4.is_link_local() => Some(3),
+            IpAddr::V4(_) => Some(1),
+            IpAddr::V6(v6) if v6.is_unicast_link_local() => None,
+            IpAddr::V6(_) => Some(2),
+        }
+    }
+    
+    /// Liste les interfaces réseau de la machine
+    /// 
+    /// Une entrée par interface, avec toutes ses adresses (IPv4 d'abord),
+    /// triées par nom : de quoi proposer un choix à l'utilisateur avant de
+    /// renseigner `NetworkConfig::bind_interface`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// for interface in utils::list_interfaces().unwrap() {
+    ///     println!("{} : {:?}", interface.name, interface.ips);
+    /// }
+    /// ```
+    pub fn list_interfaces() -> NetworkResult<Vec<NetworkInterface>> {
+        let mut interfaces: Vec<NetworkInterface> = Vec::new();
+        
+        for address in if_addrs::get_if_addrs()? {
+            let broadcast = matches!(&address.addr, if_addrs::IfAddr::V4(v4) if v4.broadcast.is_some());
+            let index = match interfaces.iter().position(|interface| interface.name == address.name) {
+                Some(index) => index,
+                None => {
+                    interfaces.push(NetworkInterface {
+                        name: address.name.clone(),
+                        ips: Vec::new(),
+                        is_loopback: address.is_loopback(),
+                        supports_broadcast: false,
+                    });
+                    interfaces.len() - 1
+                }
+            };
+            let interface = &mut interfaces[index];
+            interface.ips.push(address.ip());
+            interface.supports_broadcast |= broadcast;
+        }
+        
+        for interface in &mut interfaces {
+            interface.ips.sort_by_key(|ip| ip.is_ipv6());
+        }
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(interfaces)
+    }
+    
+    /// Vérifie si un port UDP est libre sur toutes les interfaces
+    /// 
+    /// Teste réellement un bind UDP sur `0.0.0.0:port` (comme le transport),
+    /// le socket de test est libéré immédiatement.
+    /// 
+    /// # Arguments
+    /// * `port` - Port UDP à tester
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// let socket = std::net::UdpSocket::bind("0.0.0.0:0").unwrap();
+    /// let used_port = socket.local_addr().unwrap().port();
+    /// assert!(!utils::is_port_available(used_port));
+    /// ```
+    pub fn is_port_available(port: u16) -> bool {
+        std::net::UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port)).is_ok()
+    }
+    
+    /// Diagnostique l'impossibilité de lier un socket UDP sur `port`
+    /// 
+    /// Tente le bind (toutes interfaces) et, en cas d'échec, classe la cause
+    /// (port occupé, privilégié, refusé par un pare-feu...) avec un remède
+    /// à proposer à l'utilisateur. `None` si le port est utilisable.
+    /// 
+    /// # Arguments
+    /// * `port` - Port à tester
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{utils, BindFailure};
+    /// 
+    /// let socket = std::net::UdpSocket::bind("0.0.0.0:0").unwrap();
+    /// let used_port = socket.local_addr().unwrap().port();
+    /// 
+    /// let diagnostic = utils::diagnose_bind(used_port).unwrap().probe_alternatives(100);
+    /// assert_eq!(diagnostic.failure, BindFailure::PortInUse);
+    /// assert!(diagnostic.alternative_port.is_some());
+    /// ```
+    pub fn diagnose_bind(port: u16) -> Option<BindDiagnostic> {
+        std::net::UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port))
+            .err()
+            .and_then(|e| NetworkError::bind_failed(port, e).bind_diagnostic())
+    }
+    
+    /// Vérifie qu'un port UDP est utilisable avant d'y écouter
+    /// 
+    /// Test rapide avec `is_port_available` ; sinon, le diagnostic de
+    /// `diagnose_bind` porte un port libre suggéré parmi les `attempts`
+    /// suivants (voir `BindDiagnostic::probe_alternatives`).
+    /// 
+    /// # Arguments
+    /// * `port` - Port à tester
+    /// * `attempts` - Nombre de ports alternatifs à essayer
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{utils, NetworkError};
+    /// 
+    /// let socket = std::net::UdpSocket::bind("0.0.0.0:0").unwrap();
+    /// let used_port = socket.local_addr().unwrap().port();
+    /// 
+    /// let diagnostic = utils::check_port_available(used_port, 100).unwrap_err();
+    /// assert!(diagnostic.alternative_port.is_some());
+    /// assert!(matches!(NetworkError::from(diagnostic), NetworkError::BindError { .. }));
+    /// ```
+    pub fn check_port_available(port: u16, attempts: u16) -> Result<(), BindDiagnostic> {
+        if is_port_available(port) {
+            return Ok(());
+        }
+        // Le port a pu se libérer entre les deux tentatives
+        match diagnose_bind(port) {
+            Some(diagnostic) => Err(diagnostic.probe_alternatives(attempts)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Cherche le premier port UDP libre dans une plage
+    /// 
+    /// Utile pour proposer un port alternatif quand le port par défaut est occupé.
+    /// Le port 0 (attribution automatique par l'OS) est ignoré.
+    /// 
+    /// # Arguments
+    /// * `range` - Plage de ports à parcourir dans l'ordre
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// if let Some(port) = utils::find_free_udp_port(9001..=9100) {
+    ///     println!("Port libre : {}", port);
+    /// }
+    /// ```
+    pub fn find_free_udp_port(range: std::ops::RangeInclusive<u16>) -> Option<u16> {
+        range
+            .filter(|&port| port != 0)
+            .find(|&port| is_port_available(port))
+    }
+    
+    /// Adresse de broadcast LAN pour un port donné
+    /// 
+    /// Utilisée pour la découverte de pairs sur le réseau local.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// assert_eq!(utils::broadcast_addr(9001).to_string(), "255.255.255.255:9001");
+    /// ```
+    pub fn broadcast_addr(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), port)
+    }
+    
+    /// Liste les instances Voc en ligne sur le réseau local
+    /// 
+    /// Écoute pendant `duration` les annonces de présence envoyées sur
+    /// `PRESENCE_PORT` (voir `NetworkConfig::presence`). Choisir une durée
+    /// supérieure à l'intervalle des annonces (5s par défaut) pour voir
+    /// toutes les instances.
+    /// 
+    /// # Arguments
+    /// * `duration` - Durée d'écoute
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::BindError` si le port des annonces n'est pas utilisable
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::utils;
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// for peer in utils::collect_presence(Duration::from_secs(6)).await? {
+    ///     println!("{} en ligne sur {}", peer.display_name, peer.addr);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "udp")]
+    pub async fn collect_presence(duration: std::time::Duration) -> NetworkResult<Vec<PresencePeer>> {
+        collect_presence_on(PRESENCE_PORT, duration).await
+    }
+    
+    /// Comme `collect_presence`, pour des annonces envoyées sur `port`
+    /// (`PresenceConfig::port`)
+    #[cfg(feature = "udp")]
+    pub async fn collect_presence_on(port: u16, duration: std::time::Duration) -> NetworkResult<Vec<PresencePeer>> {
+        let socket = presence::bind_listener(port)
+            .map_err(|e| NetworkError::bind_failed(port, e))?;
+        Ok(presence::collect(&socket, duration).await)
+    }
+    
+    /// Écoute pendant `duration` les annonces des relais du LAN
+    /// 
+    /// Les relais s'annoncent sur `PRESENCE_PORT` toutes les
+    /// `RelayServer::ANNOUNCE_INTERVAL` ; les adresses retournées peuvent
+    /// compléter `NetworkConfig::relays`.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{NetworkConfig, utils};
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = NetworkConfig::default();
+    /// config.relays.extend(utils::collect_relays(Duration::from_secs(6)).await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "udp")]
+    pub async fn collect_relays(duration: std::time::Duration) -> NetworkResult<Vec<SocketAddr>> {
+        let socket = presence::bind_listener(PRESENCE_PORT)
+            .map_err(|e| NetworkError::bind_failed(PRESENCE_PORT, e))?;
+        let mut relays = Vec::new();
+        presence::listen(&socket, duration, |data, source| relay::record_announcement(&mut relays, data, source)).await;
+        Ok(relays)
+    }
+    
+    /// Encode une adresse IP:PORT en code de connexion court
+    /// 
+    /// Le code est en base32 Crockford (pas de caractères ambigus), groupé
+    /// par 5 caractères pour être dicté facilement : 11 caractères pour une
+    /// adresse IPv4, 30 pour une IPv6. Les bits qui complètent le dernier
+    /// caractère (7 en IPv4, 6 en IPv6) portent un CRC-8 tronqué de
+    /// l'adresse. En IPv4, un caractère erroné ou deux caractères voisins
+    /// inversés sont toujours détectés ; une saisie aléatoire passe une fois
+    /// sur 128 (une sur 64 en IPv6).
+    /// 
+    /// # Arguments
+    /// * `addr` - Adresse à encoder
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// let addr = utils::parse_address("192.168.1.100:9001").unwrap();
+    /// let code = utils::encode_connection_code(addr);
+    /// assert_eq!(code, "R2M02-S1355-3");
+    /// assert_eq!(utils::decode_connection_code(&code).unwrap(), addr);
+    /// ```
+    pub fn encode_connection_code(addr: SocketAddr) -> String {
+        let mut bytes = match addr.ip() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        bytes.extend_from_slice(&addr.port().to_be_bytes());
+        
+        // Flux de bits : adresse, puis bits de contrôle jusqu'au dernier caractère
+        let data_bits = bytes.len() * 8;
+        let check_len = code_check_len(data_bits);
+        let check = code_check_bits(&bytes, check_len);
+        let bit_at = |i: usize| -> u8 {
+            if i < data_bits {
+                (bytes[i / 8] >> (7 - i % 8)) & 1
+            } else {
+                (check >> (check_len - 1 - (i - data_bits))) & 1
+            }
+        };
+        
+        let chars: Vec<char> = (0..(data_bits + check_len) / 5)
+            .map(|c| {
+                let value = (0..5).fold(0u8, |acc, b| (acc << 1) | bit_at(c * 5 + b));
+                CODE_ALPHABET[value as usize] as char
+            })
+            .collect();
+        
+        chars
+            .chunks(5)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+    
+    /// Décode un code de connexion en adresse IP:PORT
+    /// 
+    /// Tolérant à la saisie : insensible à la casse, tirets et espaces
+    /// ignorés, `O` lu comme `0` et `I`/`L` comme `1`.
+    /// 
+    /// # Arguments
+    /// * `code` - Code produit par `encode_connection_code`
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidAddress` : Code mal formé ou bits de contrôle invalides
+    pub fn decode_connection_code(code: &str) -> NetworkResult<SocketAddr> {
+        let invalid = || NetworkError::InvalidAddress { addr: code.to_string() };
+        
+        let values = code
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' '))
+            .map(|c| decode_code_char(c).ok_or_else(invalid))
+            .collect::<NetworkResult<Vec<u8>>>()?;
+        
+        // 11 caractères = IPv4 + port, 30 caractères = IPv6 + port
+        let byte_len = match values.len() {
+            11 => 6,
+            30 => 18,
+            _ => return Err(invalid()),
+        };
+        
+        let bit_at = |i: usize| -> u8 { (values[i / 5] >> (4 - i % 5)) & 1 };
+        let bytes: Vec<u8> = (0..byte_len)
+            .map(|b| (0..8).fold(0u8, |acc, k| (acc << 1) | bit_at(b * 8 + k)))
+            .collect();
+        
+        let check_len = code_check_len(byte_len * 8);
+        let check = (0..check_len).fold(0u8, |acc, k| (acc << 1) | bit_at(byte_len * 8 + k));
+        if check != code_check_bits(&bytes, check_len) {
+            return Err(invalid());
+        }
+        
+        let (ip_bytes, port_bytes) = bytes.split_at(byte_len - 2);
+        let ip = match <[u8; 4]>::try_from(ip_bytes) {
+            Ok(octets) => IpAddr::V4(Ipv4Addr::from(octets)),
+            Err(_) => {
+                let octets = <[u8; 16]>::try_from(ip_bytes).map_err(|_| invalid())?;
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        };
+        let port = u16::from_be_bytes([port_bytes[0], port_bytes[1]]);
+        
+        Ok(SocketAddr::new(ip, port))
+    }
+    
+    /// Nombre de bits de contrôle d'un code : au moins 5, de quoi compléter
+    /// le dernier caractère (7 en IPv4, 6 en IPv6)
+    fn code_check_len(data_bits: usize) -> usize {
+        (data_bits + 5).div_ceil(5) * 5 - data_bits
+    }
+    
+    /// Bits de contrôle d'un code : les `len` bits de poids fort du CRC-8
+    /// (polynôme 0x07) de l'adresse
+    fn code_check_bits(bytes: &[u8], len: usize) -> u8 {
+        let crc = bytes.iter().fold(0u8, |crc, &byte| {
+            (0..8).fold(crc ^ byte, |crc, _| {
+                if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 }
+            })
+        });
+        crc >> (8 - len)
+    }
+    
+    /// Valeur d'un caractère de code (avec les substitutions Crockford)
+    fn decode_code_char(c: char) -> Option<u8> {
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            other => other,
+        };
+        CODE_ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .map(|pos| pos as u8)
+    }
+    
+    /// Tire un identifiant non nul (session, émetteur) d'un générateur cryptographique
+    /// 
+    /// Les identifiants ne doivent pas être prévisibles : un tiers qui devine
+    /// le `session_id` d'un appel pourrait y injecter des paquets. Sans source
+    /// d'entropie du système, aucun identifiant n'est tiré.
+    /// 
+    /// Les identifiants sont sur 64 bits depuis l'en-tête v4 ; leurs 32 bits de
+    /// poids faible, seuls transmis aux pairs des versions précédentes
+    /// (`NetworkPacket::wire_id`), ne sont pas nuls non plus. Les collisions
+    /// restent détectées pendant le handshake et le pair concerné tire un
+    /// nouvel identifiant (voir `docs/FORMAT_RESEAU.md`).
+    /// 
+    /// # Errors
+    /// - `NetworkError::InitializationError` : source d'aléa du système indisponible
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::utils;
+    /// 
+    /// let id = utils::random_id().unwrap();
+    /// assert_ne!(id as u32, 0);
+    /// ```
+    pub fn random_id() -> NetworkResult<u64> {
+        loop {
+            let id = getrandom::u64()
+                .map_err(|e| NetworkError::InitializationError(format!("tirage d'un identifiant: {}", e)))?;
+            if id as u32 != 0 {
+                return Ok(id);
+            }
+        }
     }
     
     /// Formate une durée en millisecondes de façon lisible
@@ -222,12 +771,85 @@ pub mod utils {
             format!("{:.1} {}", size, UNITS[unit_index])
         }
     }
+    
+    /// Génère le dissecteur Wireshark (Lua) à partir de `NetworkPacket::WIRE_LAYOUT`
+    /// 
+    /// Le résultat est versionné dans `tools/voc_dissector.lua` ; à régénérer avec
+    /// `cargo run --bin voc-client dump-packet-layout --lua > tools/voc_dissector.lua`
+    /// après toute modification du format.
+    pub fn wireshark_dissector_lua() -> String {
+        use std::fmt::Write;
+        
+        let mut lua = String::new();
+        let header_size = NetworkPacket::HEADER_SIZE;
+        let magic_hex: String = NetworkPacket::MAGIC.iter().map(|b| format!("{:02X}", b)).collect();
+        
+        // writeln! sur une String ne peut pas échouer
+        let _ = writeln!(lua, "-- Dissecteur Wireshark pour le protocole Voc v{}", NetworkPacket::CURRENT_PROTOCOL_VERSION);
+        let _ = writeln!(lua, "-- Généré depuis NetworkPacket::WIRE_LAYOUT : ne pas modifier à la main");
+        let _ = writeln!(lua, "-- Installation : copier dans le dossier des plugins Lua de Wireshark");
+        let _ = writeln!(lua);
+        let _ = writeln!(lua, "local voc = Proto(\"voc\", \"Voc voice chat\")");
+        let _ = writeln!(lua);
+        let _ = writeln!(lua, "local packet_types = {{");
+        for packet_type in PacketType::ALL {
+            let _ = writeln!(lua, "    [{}] = \"{:?}\",", packet_type as u8, packet_type);
+        }
+        let _ = writeln!(lua, "}}");
+        let _ = writeln!(lua);
+        
+        for field in NetworkPacket::WIRE_LAYOUT {
+            let abbrev = format!("voc.{}", field.name);
+            let declaration = match (field.name, field.size) {
+                ("magic", _) => format!("ProtoField.bytes({:?}, {:?})", abbrev, field.description),
+                ("packet_type", _) => format!(
+                    "ProtoField.uint8({:?}, {:?}, base.DEC, packet_types)", abbrev, field.description
+                ),
+                (_, size) => format!("ProtoField.uint{}({:?}, {:?})", size * 8, abbrev, field.description),
+            };
+            let _ = writeln!(lua, "local f_{} = {}", field.name, declaration);
+        }
+        let _ = writeln!(lua, "local f_payload = ProtoField.bytes(\"voc.payload\", \"Payload\")");
+        let _ = writeln!(lua);
+        
+        let field_names: Vec<String> = NetworkPacket::WIRE_LAYOUT.iter()
+            .map(|field| format!("f_{}", field.name))
+            .collect();
+        let _ = writeln!(lua, "voc.fields = {{ {}, f_payload }}", field_names.join(", "));
+        let _ = writeln!(lua);
+        
+        let _ = writeln!(lua, "local function dissect(buffer, pinfo, tree)");
+        let _ = writeln!(lua, "    if buffer:len() < {} then return false end", header_size);
+        let _ = writeln!(lua, "    if buffer(0, {}):bytes():tohex() ~= \"{}\" then return false end",
+                         NetworkPacket::MAGIC.len(), magic_hex);
+        let _ = writeln!(lua);
+        let _ = writeln!(lua, "    pinfo.cols.protocol = \"VOC\"");
+        let _ = writeln!(lua, "    local type_name = packet_types[buffer(3, 1):uint()] or \"Inconnu\"");
+        let _ = writeln!(lua, "    pinfo.cols.info = \"Voc \" .. type_name");
+        let _ = writeln!(lua);
+        let _ = writeln!(lua, "    local subtree = tree:add(voc, buffer(), \"Voc \" .. type_name)");
+        for field in NetworkPacket::WIRE_LAYOUT {
+            let _ = writeln!(lua, "    subtree:add(f_{}, buffer({}, {}))", field.name, field.offset, field.size);
+        }
+        let _ = writeln!(lua, "    if buffer:len() > {} then", header_size);
+        let _ = writeln!(lua, "        subtree:add(f_payload, buffer({}))", header_size);
+        let _ = writeln!(lua, "    end");
+        let _ = writeln!(lua, "    return true");
+        let _ = writeln!(lua, "end");
+        let _ = writeln!(lua);
+        let _ = writeln!(lua, "voc.dissector = function(buffer, pinfo, tree) dissect(buffer, pinfo, tree) end");
+        let _ = writeln!(lua, "voc:register_heuristic(\"udp\", dissect)");
+        let _ = writeln!(lua, "DissectorTable.get(\"udp.port\"):add(9001, voc)");
+        
+        lua
+    }
 }
 
 /// Tests d'intégration du crate complet
-#[cfg(test)]
+#[cfg(all(test, feature = "udp", feature = "simulator"))]
 mod integration_tests {
     use super::*;
+    use std::net::IpAddr;
     use std::time::Duration;
     
     #[tokio::test]
@@ -271,6 +893,79 @@ mod integration_tests {
         assert_eq!(utils::format_bytes(2048), "2.0 KB");
     }
     
+    #[test]
+    fn test_local_ip_candidates() {
+        // Privées, puis publiques, IPv6 globales et lien-local ; bouclage exclu
+        let mut ips: Vec<IpAddr> = ["169.254.3.4", "2001:db8::1", "8.8.4.4", "10.0.0.2", "127.0.0.1", "fe80::1"]
+            .iter()
+            .map(|ip| ip.parse().unwrap())
+            .filter(|ip| utils::local_ip_rank(ip).is_some())
+            .collect();
+        ips.sort_by_key(utils::local_ip_rank);
+        let ordered: Vec<String> = ips.iter().map(|ip| ip.to_string()).collect();
+        assert_eq!(ordered, vec!["10.0.0.2", "8.8.4.4", "2001:db8::1", "169.254.3.4"]);
+        
+        // Sans accès externe : énumération seule, jamais d'erreur de routage
+        let candidates = utils::local_ip_candidates(Some("127.0.0.1".parse().unwrap())).unwrap();
+        assert!(candidates.iter().all(|ip| !ip.is_loopback()));
+    }
+    
+    #[test]
+    fn test_connection_code_roundtrip() {
+        let v4 = utils::parse_address("192.168.1.100:9001").unwrap();
+        let code = utils::encode_connection_code(v4);
+        assert_eq!(utils::decode_connection_code(&code).unwrap(), v4);
+        
+        // Saisie tolérante : minuscules et sans tiret
+        let typed = code.to_lowercase().replace('-', "");
+        assert_eq!(utils::decode_connection_code(&typed).unwrap(), v4);
+        
+        let v6 = utils::parse_address("[fe80::1]:9001").unwrap();
+        let code_v6 = utils::encode_connection_code(v6);
+        assert_eq!(utils::decode_connection_code(&code_v6).unwrap(), v6);
+        
+        // Codes invalides
+        assert!(utils::decode_connection_code("ABC").is_err());
+        assert!(utils::decode_connection_code("UUUUU-UUUUU-U").is_err());
+        
+        // Toute faute sur un caractère est détectée
+        let chars: Vec<char> = code.chars().filter(|&c| c != '-').collect();
+        for position in 0..chars.len() {
+            for typo in "0123456789ABCDEFGHJKMNPQRSTVWXYZ".chars().filter(|&c| c != chars[position]) {
+                let mut mistyped = chars.clone();
+                mistyped[position] = typo;
+                let mistyped: String = mistyped.into_iter().collect();
+                assert!(utils::decode_connection_code(&mistyped).is_err(), "{}", mistyped);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_port_availability() {
+        // Occupe un port UDP puis vérifie qu'il est bien détecté comme pris
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0").unwrap();
+        let used_port = socket.local_addr().unwrap().port();
+        assert!(!utils::is_port_available(used_port));
+        
+        // La recherche doit sauter le port occupé
+        let free = utils::find_free_udp_port(used_port..=used_port.saturating_add(50));
+        assert!(free.is_some_and(|port| port != used_port));
+        
+        // Une plage ne contenant que le port occupé ne donne rien
+        assert_eq!(utils::find_free_udp_port(used_port..=used_port), None);
+    }
+    
+    #[test]
+    fn test_wireshark_dissector_up_to_date() {
+        // Le dissecteur versionné doit correspondre au format courant
+        let committed = include_str!("../../../tools/voc_dissector.lua");
+        assert_eq!(
+            committed,
+            utils::wireshark_dissector_lua(),
+            "tools/voc_dissector.lua obsolète : cargo run --bin voc-client dump-packet-layout --lua > tools/voc_dissector.lua"
+        );
+    }
+    
     #[test]
     fn test_config_presets() {
         let default_config = NetworkConfig::default();
@@ -329,7 +1024,7 @@ mod integration_tests {
 }
 
 /// Exemples d'utilisation pour la documentation
-#[cfg(test)]
+#[cfg(all(test, feature = "udp", feature = "simulator"))]
 mod examples {
     use super::*;
     use std::time::Duration;
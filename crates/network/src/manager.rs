@@ -5,18 +5,44 @@
 //! Il orchestre le transport bas niveau et fournit une API simple pour l'audio.
 
 use async_trait::async_trait;
-use tokio::time::{Duration, sleep};
+use tokio::runtime::Handle;
+use tokio::time::Duration;
 use std::time::Instant;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{Mutex, mpsc};
 
 use crate::{
-    NetworkManager, NetworkTransport, UdpTransport, SimulatedTransport,
-    NetworkPacket, PacketType, ConnectionState, NetworkConfig, NetworkStats,
-    NetworkResult, NetworkError
+    NetworkManager, NetworkTransport, NetworkPacket, PacketType, ConnectionState, NetworkConfig, NetworkStats,
+    NetworkStatsInterval, NetworkEvent, NetworkResult, NetworkError, CongestionController, CongestionState, SyncAudioSender,
+    PacketTap, StreamResync, ProtocolEngine, ProtocolAction, DegradationEvent, DegradationLadder, DegradationStep,
+    DelayBasedController, Pacer, DiscoveredPeer, JitterReport, PingReport, ProtocolErrorCode,
+    PeerStatsReport, PeerIdentity, Identity, KnownPeers, TrustCallback, TrustDecision, TrustRequest, TrustStatus, RedundancyMode, SessionRoute, CodecParams, ControlMessage, SendOutcome, StreamDescription, Liveness, BufferStats, ReceiveStage, utils
 };
+use crate::discovery;
+use crate::presence;
+#[cfg(feature = "udp")]
+use crate::{UdpTransport, Socks5UdpTransport};
+#[cfg(feature = "simulator")]
+use crate::SimulatedTransport;
+#[cfg(all(feature = "simulator", feature = "audio-reexports"))]
+use crate::{selftest, SelfTestReport, SELF_TEST_DURATION, SELF_TEST_LOSS_RATE};
+use crate::state::{ConnectionStateMachine, StateTransition};
+use crate::error_log::ErrorLog;
+use crate::keepalive::{KeepaliveThread, LivenessWatchdog};
+use crate::priority::{ControlQueue, PrioritySink};
+use crate::runtime::{RuntimeContext, RuntimeTransport};
+use crate::sync_sender::SyncAudioReceiver;
+use crate::timing;
+use crate::quality::{QualityCounters, QualityHistory, QualitySample};
+#[cfg(feature = "upnp")]
+use crate::{PortMapper, PortMapping};
 use audio::CompressedFrame;
+#[cfg(all(feature = "simulator", feature = "audio-reexports"))]
+use audio::{AudioCodec, AudioConfig, OpusCodec};
+
+/// Recrée le transport d'après la configuration (voir `apply_config`)
+type TransportFactory = fn(&NetworkConfig) -> NetworkResult<Box<dyn NetworkTransport + Send + Sync>>;
 
 /// Manager réseau P2P pour communication audio
 /// 
@@ -27,8 +53,8 @@ use audio::CompressedFrame;
 /// # Architecture
 /// - Transport UDP abstrait (réel ou simulé)
 /// - Machine à états pour la connexion
-/// - Threads séparés pour heartbeat et réception
-/// - Buffer anti-jitter intégré
+/// - Logique du protocole dans `ProtocolEngine` (sans E/S), pilotée ici
+/// - Thread dédié aux heartbeats
 /// - Statistiques temps réel
 /// 
 /// # Example
@@ -55,19 +81,26 @@ pub struct UdpNetworkManager {
     transport: Box<dyn NetworkTransport + Send + Sync>,
     
     /// État de connexion actuel
-    connection_state: Arc<Mutex<ConnectionState>>,
+    connection_state: Arc<Mutex<ConnectionStateMachine>>,
+    
+    /// Logique du protocole (handshake, heartbeats, buffer anti-jitter)
+    engine: ProtocolEngine,
     
-    /// ID de session unique
-    session_id: u32,
+    /// Thread dédié à l'envoi des heartbeats (hors runtime tokio)
+    heartbeat_handle: Option<KeepaliveThread>,
     
-    /// ID local unique
-    sender_id: u32,
+    /// Surveillance du pair pendant que l'application ne lit pas le réseau
+    liveness_watchdog: Option<LivenessWatchdog>,
     
-    /// Numéro de séquence pour les paquets envoyés
-    sequence_counter: u64,
+    /// Thread d'annonce de présence sur le LAN (`NetworkConfig::presence`)
+    presence_handle: Option<KeepaliveThread>,
     
-    /// Handle pour le thread de heartbeat
-    heartbeat_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Heartbeats refusés par le socket plein, envoyés avant la prochaine
+    /// frame audio (`NetworkConfig::control_priority`)
+    control_queue: Arc<ControlQueue>,
+    
+    /// Frames audio dont l'envoi a expiré, renvoyées au prochain `send_audio`
+    outgoing_backlog: std::collections::VecDeque<NetworkPacket>,
     
     /// Canal pour recevoir les frames audio
     _audio_receiver: Option<mpsc::Receiver<CompressedFrame>>,
@@ -75,16 +108,98 @@ pub struct UdpNetworkManager {
     /// Canal pour envoyer les frames audio
     audio_sender: Option<mpsc::Sender<CompressedFrame>>,
     
-    /// Buffer anti-jitter pour réception
-    receive_buffer: JitterBuffer,
+    /// File sans verrou alimentée par les `SyncAudioSender` (créée à la demande)
+    sync_audio: Option<SyncAudioReceiver>,
     
     /// Statistiques combinées
     stats: Arc<Mutex<NetworkStats>>,
+    
+    /// Journal des erreurs de réception, limité pour les liens instables
+    error_log: ErrorLog,
+    
+    /// Dernier pair contacté (conservé en cas d'erreur de connexion)
+    last_peer_addr: Option<SocketAddr>,
+    
+    /// Échantillons de qualité des dernières secondes (pour les graphes)
+    quality: QualityHistory,
+    
+    /// Échelle de dégradation (si `config.degradation`)
+    degradation: Option<DegradationLadder>,
+    
+    /// Changements d'échelon pas encore lus par l'application
+    degradation_events: std::collections::VecDeque<DegradationEvent>,
+    
+    /// Événements réseau pas encore lus par l'application
+    network_events: std::collections::VecDeque<NetworkEvent>,
+    
+    /// Attente des paquets en retard avant l'échelon `IncreaseJitterBuffer`
+    target_delay_before_degradation: Option<u32>,
+    
+    /// Octets audio émis depuis la création (copies redondantes comprises)
+    bytes_sent: u64,
+    
+    /// Référence du dernier snapshot d'intervalle (instant, stats)
+    stats_baseline: Mutex<(Instant, NetworkStats)>,
+    
+    /// Contrôleur de congestion (calcule le débit de pacing)
+    congestion: Box<dyn CongestionController>,
+    
+    /// Pacer appliquant le débit du contrôleur sur le chemin d'envoi
+    pacer: Pacer,
+    
+    /// Dernier rapport du pair pris en compte, avec sa session : ses pertes
+    /// sont déjà signalées au contrôleur de congestion
+    remote_losses_seen: Option<(u64, PeerStatsReport)>,
+    
+    /// Décisions de confiance dans l'identité des pairs
+    known_peers: KnownPeers,
+    
+    /// Question posée à l'application pour une identité inconnue
+    trust_callback: Option<TrustCallback>,
+    
+    /// Runtime tokio des sockets et timers (celui de l'appelant par défaut)
+    runtime: RuntimeContext,
+    
+    /// Construction du transport, si le manager l'a créé lui-même (un
+    /// transport fourni par l'application ne peut pas être recréé)
+    transport_factory: Option<TransportFactory>,
+    
+    /// Redirection de port active sur le routeur (mode écoute)
+    #[cfg(feature = "upnp")]
+    port_mapper: Option<PortMapper>,
 }
 
 impl UdpNetworkManager {
+    /// Frames manquantes au-delà desquelles le décodeur est réinitialisé
+    /// (voir `ProtocolEngine::RESYNC_GAP_FRAMES`)
+    pub const RESYNC_GAP_FRAMES: u64 = ProtocolEngine::RESYNC_GAP_FRAMES;
+    
+    /// Frames audio gardées quand l'envoi expire (500ms à 20ms par frame)
+    /// 
+    /// Couvre une saturation passagère du socket sans perdre l'audio ; au-delà,
+    /// les frames les plus anciennes arriveraient trop tard pour le pair.
+    pub const OUTGOING_BACKLOG_FRAMES: usize = 25;
+    
+    /// Attente maximale de la réponse à chaque sonde de `ping`
+    pub const PING_TIMEOUT: Duration = Duration::from_secs(1);
+    
+    /// Intervalle entre les sondes de `measure_jitter` (rythme d'une frame audio)
+    pub const JITTER_PROBE_INTERVAL: Duration = Duration::from_millis(20);
+    
+    /// Sondes envoyées à chaque relais candidat par `select_relay`
+    pub const RELAY_PING_COUNT: u32 = 3;
+    
+    /// Sondes envoyées à chaque candidat, par rafale, par `open_candidates`
+    pub const PUNCH_BURST_SIZE: u32 = 3;
+    
+    /// Intervalle entre deux rafales de `open_candidates`
+    pub const PUNCH_BURST_INTERVAL: Duration = Duration::from_millis(50);
+    
     /// Crée un nouveau manager avec transport UDP réel
     /// 
+    /// Si `config.proxy` est renseigné, le trafic passe par le proxy SOCKS5
+    /// (`Socks5UdpTransport`) au lieu d'un socket UDP direct.
+    /// 
     /// # Arguments
     /// * `config` - Configuration réseau
     /// 
@@ -95,9 +210,53 @@ impl UdpNetworkManager {
     /// let config = NetworkConfig::default();
     /// let manager = UdpNetworkManager::new(config).unwrap();
     /// ```
+    #[cfg(feature = "udp")]
     pub fn new(config: NetworkConfig) -> NetworkResult<Self> {
-        let transport = Box::new(UdpTransport::new(config.clone())?);
-        Self::with_transport(config, transport)
+        let transport = Self::udp_transport(&config)?;
+        let mut manager = Self::with_transport(config, transport)?;
+        manager.transport_factory = Some(Self::udp_transport);
+        Ok(manager)
+    }
+    
+    /// Socket UDP direct, ou via le proxy SOCKS5 de `config.proxy`
+    #[cfg(feature = "udp")]
+    fn udp_transport(config: &NetworkConfig) -> NetworkResult<Box<dyn NetworkTransport + Send + Sync>> {
+        Ok(if config.proxy.is_some() {
+            Box::new(Socks5UdpTransport::new(config.clone())?)
+        } else {
+            Box::new(UdpTransport::new(config.clone())?)
+        })
+    }
+    
+    /// Crée un manager UDP dont les sockets et timers vivent dans `runtime`
+    /// 
+    /// Pour les applications qui possèdent leur propre runtime ou pilotent
+    /// le manager hors de tout runtime (boucle d'interface egui, commandes
+    /// tauri) : chaque opération entre dans `runtime` le temps d'un poll, et
+    /// peut donc être attendue depuis n'importe quel exécuteur ou thread.
+    /// Le runtime doit continuer à tourner (multi-thread, ou `block_on` en
+    /// cours sur un autre thread) tant que le manager est utilisé.
+    /// 
+    /// Comme les autres constructeurs, il ne bloque pas et n'exige pas
+    /// d'être appelé depuis un runtime : le socket n'est créé qu'au bind.
+    /// 
+    /// # Arguments
+    /// * `config` - Configuration réseau
+    /// * `runtime` - Handle du runtime de l'application
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// let runtime = tokio::runtime::Runtime::new().unwrap();
+    /// let manager = UdpNetworkManager::new_with_runtime(
+    ///     NetworkConfig::default(),
+    ///     runtime.handle().clone(),
+    /// ).unwrap();
+    /// ```
+    #[cfg(feature = "udp")]
+    pub fn new_with_runtime(config: NetworkConfig, runtime: Handle) -> NetworkResult<Self> {
+        Ok(Self::new(config)?.with_runtime(runtime))
     }
     
     /// Crée un nouveau manager avec transport simulé pour tests
@@ -112,611 +271,3650 @@ impl UdpNetworkManager {
     /// let config = NetworkConfig::test_config();
     /// let manager = UdpNetworkManager::new_simulated(config).unwrap();
     /// ```
+    #[cfg(feature = "simulator")]
     pub fn new_simulated(config: NetworkConfig) -> NetworkResult<Self> {
-        let transport = Box::new(SimulatedTransport::new(config.clone())?);
-        Self::with_transport(config, transport)
+        let transport = Self::simulated_transport(&config)?;
+        let mut manager = Self::with_transport(config, transport)?;
+        manager.transport_factory = Some(Self::simulated_transport);
+        Ok(manager)
+    }
+    
+    /// Transport simulé isolé (voir `new_simulated`)
+    #[cfg(feature = "simulator")]
+    fn simulated_transport(config: &NetworkConfig) -> NetworkResult<Box<dyn NetworkTransport + Send + Sync>> {
+        Ok(Box::new(SimulatedTransport::new(config.clone())?))
     }
     
     /// Crée un manager avec un transport personnalisé
-    fn with_transport(
+    /// 
+    /// Permet notamment de relier deux managers par `SimulatedTransport::pair`
+    /// pour simuler un appel complet dans un seul processus.
+    pub fn with_transport(
         config: NetworkConfig, 
         transport: Box<dyn NetworkTransport + Send + Sync>
     ) -> NetworkResult<Self> {
-        let session_id = fastrand::u32(1..=u32::MAX);
-        let sender_id = fastrand::u32(1..=u32::MAX);
+        let voice_frame = Duration::from_millis(CodecParams::voice().frame_duration_ms as u64);
+        let (audio_tx, audio_rx) = mpsc::channel(config.delay_frames(voice_frame).1);
+        
+        let congestion = Box::new(DelayBasedController::new());
+        let mut pacer = Pacer::new(congestion.pacing_rate_bps());
+        pacer.set_cap(config.max_bandwidth_bps);
         
-        let (audio_tx, audio_rx) = mpsc::channel(config.receive_buffer_size);
+        let identity = match &config.identity_file {
+            Some(path) => Identity::load_or_create(path)?,
+            None => Identity::generate()?,
+        };
+        let mut engine = ProtocolEngine::new(&config)?;
+        engine.set_identity(Some(identity));
+        let known_peers = match &config.known_peers_file {
+            Some(path) => KnownPeers::load(path)?,
+            None => KnownPeers::new(),
+        };
         
         Ok(Self {
             config: config.clone(),
             transport,
-            connection_state: Arc::new(Mutex::new(ConnectionState::Disconnected)),
-            session_id,
-            sender_id,
-            sequence_counter: 0,
+            connection_state: Arc::new(Mutex::new(ConnectionStateMachine::new())),
+            engine,
             heartbeat_handle: None,
+            liveness_watchdog: None,
+            presence_handle: None,
+            control_queue: Arc::new(ControlQueue::default()),
+            outgoing_backlog: std::collections::VecDeque::new(),
             _audio_receiver: Some(audio_rx),
             audio_sender: Some(audio_tx),
-            receive_buffer: JitterBuffer::new(config.receive_buffer_size),
+            sync_audio: None,
             stats: Arc::new(Mutex::new(NetworkStats::new())),
+            error_log: ErrorLog::default(),
+            last_peer_addr: None,
+            quality: QualityHistory::new(config.stats_sample_period),
+            degradation: config.degradation.clone().map(DegradationLadder::new),
+            degradation_events: std::collections::VecDeque::new(),
+            network_events: std::collections::VecDeque::new(),
+            target_delay_before_degradation: None,
+            bytes_sent: 0,
+            stats_baseline: Mutex::new((Instant::now(), NetworkStats::new())),
+            congestion,
+            pacer,
+            remote_losses_seen: None,
+            known_peers,
+            trust_callback: None,
+            runtime: RuntimeContext::default(),
+            transport_factory: None,
+            #[cfg(feature = "upnp")]
+            port_mapper: None,
         })
     }
     
-    /// Démarre le thread de heartbeat
+    /// Exécute les sockets et timers du manager dans `runtime`
     /// 
-    /// Envoie des paquets keep-alive périodiques pour maintenir la connexion.
-    async fn start_heartbeat(&mut self, _peer_addr: SocketAddr) -> NetworkResult<()> {
-        if self.heartbeat_handle.is_some() {
-            return Ok(()); // Déjà démarré
+    /// Voir `new_with_runtime` ; s'applique aussi aux autres constructeurs,
+    /// par exemple `with_transport` avec un transport personnalisé.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// let runtime = tokio::runtime::Runtime::new().unwrap();
+    /// let manager = UdpNetworkManager::new_simulated(NetworkConfig::test_config())
+    ///     .unwrap()
+    ///     .with_runtime(runtime.handle().clone());
+    /// ```
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = RuntimeContext::new(runtime);
+        self.transport = Box::new(RuntimeTransport::new(self.transport, self.runtime.clone()));
+        self
+    }
+    
+    /// Handle d'envoi audio pour un thread synchrone (callback de capture)
+    /// 
+    /// Les frames déposées par `SyncAudioSender::try_send_frame` partent à
+    /// chaque tour de `receive_audio`, ou lors d'un appel explicite à
+    /// `send_queued_audio`. La file (partagée par tous les handles) garde
+    /// `OUTGOING_BACKLOG_FRAMES` frames ; elle est fermée avec le manager.
+    pub fn sync_audio_sender(&mut self) -> SyncAudioSender {
+        self.sync_audio
+            .get_or_insert_with(|| SyncAudioReceiver::new(Self::OUTGOING_BACKLOG_FRAMES))
+            .sender()
+    }
+    
+    /// Envoie les frames déposées par les `SyncAudioSender`, dans l'ordre
+    /// 
+    /// # Returns
+    /// Nombre de frames envoyées (0 sans handle synchrone)
+    pub async fn send_queued_audio(&mut self) -> NetworkResult<usize> {
+        let mut sent = 0;
+        while let Some(frame) = self.sync_audio.as_ref().and_then(SyncAudioReceiver::try_recv) {
+            self.send_audio(frame).await?;
+            sent += 1;
         }
+        Ok(sent)
+    }
+    
+    /// Comble un créneau sans audio par un paquet factice (mode padding)
+    /// 
+    /// À appeler à la cadence des frames quand la capture n'en produit pas
+    /// (silence, DTX) : le pair reçoit un paquet de même taille que l'audio,
+    /// au même rythme. Sans effet sans `NetworkConfig::padding`, hors session
+    /// ou tant que le pair n'a pas acquitté l'offre de padding. Les paquets
+    /// factices comptent dans `packets_sent`.
+    /// 
+    /// # Returns
+    /// `true` si un paquet factice est parti
+    pub async fn send_padding(&mut self) -> NetworkResult<bool> {
+        self.send_control_offers().await?;
+        let Some(ProtocolAction::Send { packet, target }) = self.engine.padding_packet() else {
+            return Ok(false);
+        };
+        self.send_audio_packet(&packet, target).await?;
+        Ok(true)
+    }
+    
+    /// Remplace l'algorithme de contrôle de congestion
+    /// 
+    /// # Arguments
+    /// * `controller` - Nouvel algorithme (ex: débit fixe, LEDBAT)
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{UdpNetworkManager, NetworkConfig, DelayBasedController};
+    /// 
+    /// let mut manager = UdpNetworkManager::new_simulated(NetworkConfig::test_config()).unwrap();
+    /// manager.set_congestion_controller(Box::new(
+    ///     DelayBasedController::with_bounds(32_000, 16_000, 64_000)
+    /// ));
+    /// assert_eq!(manager.congestion_state().pacing_rate_bps, 32_000);
+    /// ```
+    pub fn set_congestion_controller(&mut self, controller: Box<dyn CongestionController>) {
+        self.pacer.set_rate(controller.pacing_rate_bps());
+        self.congestion = controller;
+    }
+    
+    /// Retourne l'état courant du contrôle de congestion
+    pub fn congestion_state(&self) -> CongestionState {
+        self.congestion.state()
+    }
+    
+    /// Installe un tap de capture sur le transport sous-jacent
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{CaptureWriter, NetworkConfig, UdpNetworkManager};
+    /// 
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// manager.set_tap(Some(CaptureWriter::create("voc.pcapng")?.into_tap()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_tap(&mut self, tap: Option<PacketTap>) {
+        self.transport.set_tap(tap);
+    }
+    
+    /// Retourne les statistiques accumulées depuis le snapshot précédent
+    /// 
+    /// Chaque appel démarre un nouvel intervalle : appelé une fois par
+    /// seconde, on obtient directement des débits par seconde sans avoir
+    /// à calculer les différences côté dashboard.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example() {
+    /// let manager = UdpNetworkManager::new_simulated(NetworkConfig::test_config()).unwrap();
+    /// let interval = manager.stats_interval_snapshot().await;
+    /// println!("{:.1} paquets/s", interval.packets_sent_per_sec());
+    /// # }
+    /// ```
+    pub async fn stats_interval_snapshot(&self) -> NetworkStatsInterval {
+        // Ordre des locks : stats puis baseline (identique à reset_stats)
+        let mut current = self.stats.lock().await.clone();
+        self.connection_state.lock().await.record_uptime(&mut current, self.runtime.now());
+        current.merge_traffic(&self.transport.stats());
+        let mut baseline = self.stats_baseline.lock().await;
+        
+        let now = self.runtime.now();
+        let interval = NetworkStatsInterval {
+            interval_ms: now.duration_since(baseline.0).as_millis() as u64,
+            delta: current.delta_since(&baseline.1),
+        };
         
-        // Pour l'instant, on simplifie en ne gérant pas les heartbeats automatiques
-        // Dans une version complète, on créerait un thread dédié
+        *baseline = (now, current);
+        interval
+    }
+    
+    /// Historique des transitions d'état de la connexion
+    /// 
+    /// Les `StateTransition` les plus récentes (au plus 64), de la plus
+    /// ancienne à la plus récente, avec la raison de chaque changement :
+    /// utile pour comprendre après coup pourquoi un appel a coupé.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example(manager: &UdpNetworkManager) {
+    /// for transition in manager.state_history().await {
+    ///     println!("{}", transition);
+    /// }
+    /// # }
+    /// ```
+    pub async fn state_history(&self) -> Vec<StateTransition> {
+        self.connection_state.lock().await.history()
+    }
+    
+    /// Qualité de la connexion seconde par seconde
+    /// 
+    /// Les `QualitySample` des dernières secondes (au plus 60), du plus ancien
+    /// au plus récent : de quoi tracer RTT, perte, gigue et débit en direct.
+    /// L'échantillonnage se fait au fil des envois et réceptions audio.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # fn example(manager: &UdpNetworkManager) {
+    /// let rtt: Vec<f32> = manager.quality_history().iter().map(|sample| sample.rtt_ms).collect();
+    /// println!("RTT : {:?}", rtt);
+    /// # }
+    /// ```
+    pub fn quality_history(&self) -> Vec<QualitySample> {
+        self.quality.samples()
+    }
+    
+    /// Nombre de paquets audio en attente dans le buffer anti-jitter
+    /// 
+    /// Borné par `NetworkConfig::max_delay_ms` ; utile pour surveiller
+    /// le manager sur de longues sessions.
+    pub fn buffered_packets(&self) -> usize {
+        self.engine.buffered_packets()
+    }
+    
+    /// Signes de vie du pair : dernier heartbeat reçu, heartbeats manqués
+    /// 
+    /// Se dégrade dès le premier heartbeat manqué, bien avant que
+    /// `heartbeat_timeout` ne coupe la connexion.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// use std::time::Instant;
+    /// 
+    /// # fn example(manager: &UdpNetworkManager) {
+    /// if let Some(silence) = manager.liveness().unresponsive_for(Instant::now()) {
+    ///     println!("Pair injoignable depuis {} s", silence.as_secs());
+    /// }
+    /// # }
+    /// ```
+    pub fn liveness(&self) -> Liveness {
+        self.engine.liveness(self.runtime.now())
+    }
+    
+    /// Remet à zéro les statistiques du manager et du transport
+    /// 
+    /// Le temps connecté et les reconnexions repartent aussi de zéro ; une
+    /// session en cours est comptée depuis le reset.
+    /// 
+    /// Le lock des stats du manager est conservé pendant tout le reset
+    /// pour qu'aucun envoi/réception concurrent ne soit compté à moitié,
+    /// et la référence d'intervalle est réinitialisée en même temps.
+    pub async fn reset_stats(&mut self) {
+        let mut stats = self.stats.lock().await;
+        stats.reset();
+        self.connection_state.lock().await.reset_uptime(self.runtime.now());
+        self.transport.reset_stats().await;
         
-        // TODO: Implémenter le thread de heartbeat complet
-        // let state_clone = self.connection_state.clone();
-        // let interval_duration = self.config.heartbeat_interval;
+        let mut baseline = self.stats_baseline.lock().await;
+        *baseline = (self.runtime.now(), stats.clone());
+    }
+    
+    /// Recherche les instances Voc en écoute sur le réseau local
+    /// 
+    /// Envoie une sonde de découverte vers `target` (en général
+    /// `utils::broadcast_addr(port)`) et collecte les réponses pendant `wait`.
+    /// Le transport est bindé sur un port aléatoire si nécessaire.
+    /// 
+    /// # Arguments
+    /// * `target` - Adresse de broadcast (ou unicast) à sonder
+    /// * `wait` - Durée de collecte des réponses
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig, utils};
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let peers = manager.discover_peers(utils::broadcast_addr(9001), Duration::from_secs(1)).await?;
+    /// for peer in peers {
+    ///     println!("Pair trouvé : {} ({:?})", peer.addr, peer.response_time);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn discover_peers(
+        &mut self,
+        target: SocketAddr,
+        wait: Duration,
+    ) -> NetworkResult<Vec<DiscoveredPeer>> {
+        self.ensure_bound().await?;
+        self.runtime.scope(discovery::discover_peers(
+            self.transport.as_mut(),
+            target,
+            self.engine.sender_id(),
+            self.engine.session_id(),
+            wait,
+        )).await
+    }
+    
+    /// Mesure l'accessibilité et le RTT d'un pair, sans établir d'appel
+    /// 
+    /// Envoie `count` sondes une à une, chacune attendant sa réponse au plus
+    /// `PING_TIMEOUT`. Toute instance Voc en écoute ou en appel répond, sans
+    /// handshake : l'interface peut ainsi vérifier quels pairs découverts
+    /// sont joignables avant d'appeler. Le transport est bindé sur un port
+    /// aléatoire si nécessaire.
+    /// 
+    /// # Arguments
+    /// * `addr` - Adresse du pair à sonder
+    /// * `count` - Nombre de sondes
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : appel en cours (les paquets reçus
+    ///   pendant la mesure ne seraient pas traités)
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let report = manager.ping("192.168.1.20:9001".parse()?, 3).await?;
+    /// if let Some(rtt) = report.avg_rtt() {
+    ///     println!("Joignable, RTT moyen {:?} ({:.0}% de perte)", rtt, report.loss_percent());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ping(&mut self, addr: SocketAddr, count: u32) -> NetworkResult<PingReport> {
+        if self.engine.is_connected() {
+            return Err(NetworkError::InvalidState {
+                operation: "ping".to_string(),
+                current_state: "appel en cours".to_string(),
+            });
+        }
         
-        println!("Heartbeat thread started (placeholder)");
-        Ok(())
+        self.ensure_bound().await?;
+        self.runtime.scope(discovery::ping(
+            self.transport.as_mut(),
+            addr,
+            count,
+            self.engine.sender_id(),
+            self.engine.session_id(),
+            Self::PING_TIMEOUT,
+        )).await
     }
     
-    /// Arrête le thread de heartbeat
-    async fn stop_heartbeat(&mut self) {
-        if let Some(handle) = self.heartbeat_handle.take() {
-            handle.abort();
+    /// Mesure la gigue du chemin vers un pair, sans établir d'appel
+    /// 
+    /// Envoie une sonde toutes les `JITTER_PROBE_INTERVAL` pendant `duration`,
+    /// comme le ferait un flux audio, et relève la variation de leurs temps
+    /// de réponse (voir `JitterReport`). Les réponses sont attendues jusqu'à
+    /// `PING_TIMEOUT` après la dernière sonde.
+    /// 
+    /// # Arguments
+    /// * `peer` - Adresse du pair à sonder
+    /// * `duration` - Durée du train de sondes
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : appel en cours (les paquets reçus
+    ///   pendant la mesure ne seraient pas traités)
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let report = manager.measure_jitter("192.168.1.20:9001".parse()?, Duration::from_secs(2)).await?;
+    /// println!("Gigue p50 {:?}, p95 {:?}, p99 {:?}", report.p50(), report.p95(), report.p99());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn measure_jitter(&mut self, peer: SocketAddr, duration: Duration) -> NetworkResult<JitterReport> {
+        if self.engine.is_connected() {
+            return Err(NetworkError::InvalidState {
+                operation: "measure_jitter".to_string(),
+                current_state: "appel en cours".to_string(),
+            });
         }
+        
+        self.ensure_bound().await?;
+        self.runtime.scope(discovery::measure_jitter(
+            self.transport.as_mut(),
+            peer,
+            duration,
+            Self::JITTER_PROBE_INTERVAL,
+            self.engine.sender_id(),
+            self.engine.session_id(),
+            Self::PING_TIMEOUT,
+        )).await
     }
     
-    /// Effectue le handshake initial avec un peer
-    async fn perform_handshake(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
-        // Crée un paquet handshake en utilisant les méthodes helper
-        let handshake = self.create_handshake_packet();
+    /// Se connecte à un pair à partir d'un code de connexion
+    /// 
+    /// Le code est décodé avec `utils::decode_connection_code`. Si l'adresse
+    /// encodée ne répond pas (IP changée par le DHCP par exemple), une
+    /// découverte LAN est lancée sur le port du code et la connexion est
+    /// tentée vers l'instance qui répond.
+    /// 
+    /// # Arguments
+    /// * `code` - Code de connexion (ex: "R2M02-S1355-3")
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// manager.connect_with_code("R2M02-S1355-3").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_with_code(&mut self, code: &str) -> NetworkResult<()> {
+        let addr = utils::decode_connection_code(code)?;
+        
+        let direct_error = match self.connect_to_peer(addr).await {
+            Ok(()) => return Ok(()),
+            Err(e @ NetworkError::ConnectionTimeout { .. }) => e,
+            Err(e) => return Err(e),
+        };
         
-        // Envoie le handshake
-        self.transport.send_packet(&handshake, peer_addr).await?;
+        println!("{} injoignable - recherche sur le réseau local...", addr);
+        let peers = self
+            .discover_peers(utils::broadcast_addr(addr.port()), self.config.connection_timeout)
+            .await?;
         
-        // Attend la réponse (timeout configurable)
-        let timeout_duration = self.config.connection_timeout;
-        let start_time = Instant::now();
+        // Préfère l'adresse du code si elle a répondu, sinon la première réponse
+        let candidate = peers
+            .iter()
+            .find(|peer| peer.addr.ip() == addr.ip())
+            .or(peers.first());
         
-        while start_time.elapsed() < timeout_duration {
-            match self.transport.receive_packet().await {
-                Ok((packet, source)) if source == peer_addr => {
-                    if packet.packet_type == PacketType::Handshake {
-                        // Handshake réussi
-                        return Ok(());
-                    }
-                }
-                Ok(_) => continue, // Paquet d'une autre source
-                Err(NetworkError::Timeout) => {
-                    // Continue à essayer
-                    sleep(Duration::from_millis(100)).await;
-                    continue;
-                }
-                Err(e) => return Err(e),
+        match candidate {
+            Some(peer) => {
+                let peer_addr = peer.addr;
+                println!("Pair trouvé sur le réseau local : {}", peer_addr);
+                self.connect_to_peer(peer_addr).await
             }
+            None => Err(direct_error),
         }
-        
-        Err(NetworkError::connection_timeout(peer_addr, timeout_duration.as_millis() as u32))
     }
     
-    /// Met à jour l'état de connexion
-    async fn set_connection_state(&self, new_state: ConnectionState) {
-        let mut state = self.connection_state.lock().await;
-        *state = new_state;
+    /// Ouvre une connexion sans distinction client/serveur
+    ///
+    /// Bind le transport sur `local_port`, demande la redirection de port si
+    /// configurée, puis :
+    /// - avec `peer_addr`, appelle le pair (Hello retransmis) tout en acceptant
+    ///   son propre Hello : si les deux côtés appellent en même temps, le
+    ///   départage par `sender_id` les fait converger vers une seule session
+    /// - sans `peer_addr`, attend le premier pair qui se présente
+    ///
+    /// Les deux pairs peuvent ainsi exécuter exactement le même code, ce qui
+    /// est nécessaire pour le hole punching où chacun doit émettre en premier.
+    ///
+    /// # Arguments
+    /// * `local_port` - Port UDP local (0 pour un port choisi par le système)
+    /// * `peer_addr` - Adresse du pair à appeler, si connue
+    ///
+    /// # Retour
+    /// Adresse du pair connecté
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Même appel des deux côtés, chacun avec l'adresse de l'autre
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let peer = manager.open(9001, Some("192.168.1.20:9001".parse()?)).await?;
+    /// println!("Connecté à {}", peer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn open(&mut self, local_port: u16, peer_addr: Option<SocketAddr>) -> NetworkResult<SocketAddr> {
+        self.bind_for_call(local_port).await?;
+
+        match peer_addr {
+            Some(peer_addr) => {
+                self.connect_to_peer(peer_addr).await?;
+                Ok(peer_addr)
+            }
+            None => {
+                self.set_connection_state(ConnectionState::Disconnected, "attente d'un pair").await?;
+                println!("En attente d'un pair sur le port {}...", local_port);
+                self.accept_incoming().await
+            }
+        }
+    }
+
+    /// Ouvre une connexion à travers les NAT, à partir des adresses
+    /// candidates du pair
+    /// 
+    /// Les candidats (en général l'adresse privée du pair et son adresse
+    /// publique, échangées par la signalisation) sont sondés tous à la fois,
+    /// par rafales de `PUNCH_BURST_SIZE` toutes les `PUNCH_BURST_INTERVAL`,
+    /// pendant au plus `connection_timeout`. Le premier qui répond est
+    /// retenu, puis l'appel est établi comme par `open`. Les deux pairs
+    /// doivent appeler cette méthode en même temps, chacun avec les
+    /// candidats de l'autre : leurs sondes ouvrent les NAT de part et
+    /// d'autre.
+    /// 
+    /// # Arguments
+    /// * `local_port` - Port UDP local (0 pour un port choisi par le système)
+    /// * `candidates` - Adresses candidates du pair
+    /// 
+    /// # Retour
+    /// Adresse du pair connecté (le candidat retenu)
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidAddress` : aucun candidat
+    /// - `NetworkError::InvalidState` : appel en cours
+    /// - `NetworkError::ConnectionTimeout` : aucun candidat n'a répondu
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Candidats reçus par la signalisation : adresse privée, puis publique
+    /// let candidates = ["192.168.1.20:9001".parse()?, "203.0.113.7:41234".parse()?];
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let peer = manager.open_candidates(9001, &candidates).await?;
+    /// println!("Connecté à {}", peer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn open_candidates(&mut self, local_port: u16, candidates: &[SocketAddr]) -> NetworkResult<SocketAddr> {
+        if candidates.is_empty() {
+            return Err(NetworkError::InvalidAddress { addr: "aucun candidat".to_string() });
+        }
+        if self.engine.is_connected() {
+            return Err(NetworkError::InvalidState {
+                operation: "open_candidates".to_string(),
+                current_state: "appel en cours".to_string(),
+            });
+        }
+        
+        self.bind_for_call(local_port).await?;
+        let selected = self.runtime.scope(discovery::punch_candidates(
+            self.transport.as_mut(),
+            candidates,
+            Self::PUNCH_BURST_SIZE,
+            Self::PUNCH_BURST_INTERVAL,
+            self.engine.sender_id(),
+            self.engine.session_id(),
+            self.config.connection_timeout,
+        )).await?;
+        
+        let Some(peer_addr) = selected else {
+            return Err(NetworkError::connection_timeout(candidates[0], self.config.connection_timeout.as_millis() as u32));
+        };
+        println!("Chemin retenu vers le pair : {}", peer_addr);
+        self.open(local_port, Some(peer_addr)).await
     }
     
-    /// Traite un paquet reçu selon son type
-    async fn handle_received_packet(&mut self, packet: NetworkPacket, source: SocketAddr) -> NetworkResult<()> {
-        match packet.packet_type {
-            PacketType::Audio => {
-                // Ajoute au buffer anti-jitter
-                if self.receive_buffer.push_packet(packet) {
-                    // Essaie de sortir des paquets du buffer
-                    while let Some(buffered_packet) = self.receive_buffer.pop_packet() {
-                        if let Some(ref sender) = self.audio_sender {
-                            let _ = sender.send(buffered_packet.compressed_frame).await;
-                        }
-                    }
-                }
+    /// Renégocie les paramètres du codec de notre flux audio en cours d'appel
+    /// 
+    /// Le pair est prévenu (retransmissions jusqu'à acquittement, au plus
+    /// `connection_timeout`) que nos frames à partir de la séquence retournée
+    /// utilisent `params`. Au retour, l'appelant recrée donc son encodeur
+    /// avant la prochaine frame ; le pair réinitialise son décodeur à la même
+    /// frame (voir `take_decoder_switch`).
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : pas connecté, ou pair en protocole < v3
+    /// - `NetworkError::ConnectionTimeout` : pas d'acquittement du pair
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use audio::{AudioConfig, OpusCodec};
+    /// use network::{CodecParams, UdpNetworkManager};
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// // Passage en mode musique
+    /// let params = CodecParams::music();
+    /// manager.renegotiate(params).await?;
+    /// 
+    /// let mut config = AudioConfig::default();
+    /// params.apply_to(&mut config);
+    /// let encoder = OpusCodec::new(config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn renegotiate(&mut self, params: CodecParams) -> NetworkResult<u64> {
+        let peer_addr = match self.connection_state.lock().await.current() {
+            ConnectionState::Connected { peer_addr, .. } => *peer_addr,
+            other => {
+                return Err(NetworkError::InvalidState {
+                    operation: "renegotiate".to_string(),
+                    current_state: other.description(),
+                });
+            }
+        };
+        if self.engine.peer_protocol_version() < NetworkPacket::CURRENT_PROTOCOL_VERSION {
+            return Err(NetworkError::InvalidState {
+                operation: "renegotiate".to_string(),
+                current_state: format!("pair en protocole v{}", self.engine.peer_protocol_version()),
+            });
+        }
+        
+        // Nos frames sont bloquées pendant l'échange : la prochaine est la frontière
+        let switch_at = self.engine.next_sequence();
+        let request = NetworkPacket::new_control(
+            &ControlMessage::Renegotiate { params, switch_at },
+            self.engine.sender_id(),
+            self.engine.session_id(),
+        );
+        
+        let timeout_duration = self.config.connection_timeout;
+        let start_time = self.runtime.now();
+        let mut retry_interval = self.config.handshake_retry_interval;
+        let mut next_send = start_time;
+        
+        while let Some(remaining) = timeout_duration.checked_sub(self.runtime.now().saturating_duration_since(start_time)) {
+            if self.runtime.now() >= next_send {
+                self.transport.send_packet(&request, peer_addr).await?;
+                next_send = self.runtime.now() + retry_interval;
+                retry_interval = (retry_interval * 2).min(ProtocolEngine::HANDSHAKE_MAX_RETRY_INTERVAL);
             }
             
-            PacketType::Heartbeat => {
-                // Met à jour le timestamp du dernier heartbeat
-                self.update_last_heartbeat().await;
+            let wait = next_send.saturating_duration_since(self.runtime.now()).min(remaining);
+            let (packet, source) = match self.runtime.timeout(wait, self.transport.receive_packet()).await {
+                Ok(Ok(received)) => received,
+                Ok(Err(NetworkError::Timeout)) | Err(_) => continue,
+                Ok(Err(e)) => return Err(e),
+            };
+            if source != peer_addr {
+                continue;
             }
             
-            PacketType::Handshake => {
-                // Répond au handshake
-                let response = self.create_handshake_packet();
-                self.transport.send_packet(&response, source).await?;
+            if packet.control_message() == Some(ControlMessage::RenegotiateAck { switch_at }) {
+                let description = StreamDescription { params, ..self.engine.stream_description() };
+                self.engine.set_stream_description(description);
+                return Ok(switch_at);
             }
             
-            PacketType::Disconnect => {
-                // Pair se déconnecte proprement
-                self.set_connection_state(ConnectionState::Disconnected).await;
-                self.stop_heartbeat().await;
+            // Le reste du trafic (audio, heartbeats, déconnexion) est traité normalement
+            self.handle_received_packet(packet, source).await?;
+            if !self.connection_state().is_connected() {
+                return Err(NetworkError::PeerDisconnected { addr: peer_addr });
             }
         }
         
-        Ok(())
+        Err(NetworkError::connection_timeout(peer_addr, timeout_duration.as_millis() as u32))
     }
     
-    /// Met à jour le timestamp du dernier heartbeat
-    async fn update_last_heartbeat(&self) {
-        let mut state = self.connection_state.lock().await;
-        if let ConnectionState::Connected { ref mut last_heartbeat, .. } = *state {
-            *last_heartbeat = Instant::now();
-        }
+    /// Paramètres à appliquer au décodeur avant de décoder la frame `sequence`
+    /// 
+    /// Retourne les paramètres annoncés par le pair (`renegotiate`) dès que
+    /// `sequence` atteint la frontière convenue, une seule fois : le décodeur
+    /// doit alors être recréé avec ces paramètres.
+    pub fn take_decoder_switch(&mut self, sequence: u64) -> Option<CodecParams> {
+        self.engine.take_decoder_switch(sequence)
     }
     
-    /// Vérifie si la connexion a timeout (pas de heartbeat reçu)
-    async fn check_heartbeat_timeout(&self) -> bool {
-        let state = self.connection_state.lock().await;
-        if let ConnectionState::Connected { last_heartbeat, .. } = *state {
-            last_heartbeat.elapsed() > self.config.heartbeat_timeout
-        } else {
-            false
-        }
+    /// Discontinuité du flux reçu depuis le dernier appel, s'il y en a une
+    /// 
+    /// À appeler pour chaque frame reçue, avant de la décoder : le codec est
+    /// alors réinitialisé à une frontière de frame (`AudioCodec::reset`),
+    /// l'encodeur aussi si `StreamResync::resets_encoder`. Une reprise de
+    /// session l'emporte sur un saut de séquence survenu entre-temps.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use audio::{AudioCodec, AudioConfig, OpusCodec};
+    /// use network::{NetworkManager, UdpNetworkManager};
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut encoder = OpusCodec::new(AudioConfig::default())?;
+    /// let mut decoder = OpusCodec::new(AudioConfig::default())?;
+    /// let compressed = manager.receive_audio().await?;
+    /// if let Some(resync) = manager.take_stream_resync() {
+    ///     decoder.reset()?;
+    ///     if resync.resets_encoder() {
+    ///         encoder.reset()?;
+    ///     }
+    /// }
+    /// let frame = decoder.decode(&compressed)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_stream_resync(&mut self) -> Option<StreamResync> {
+        self.engine.take_stream_resync()
     }
     
-    /// Crée un paquet handshake avec checksum correct
-    fn create_handshake_packet(&self) -> NetworkPacket {
-        let empty_frame = CompressedFrame::new(vec![], 0, Instant::now(), 0);
-        let mut packet = NetworkPacket {
-            protocol_version: NetworkPacket::CURRENT_PROTOCOL_VERSION,
-            packet_type: PacketType::Handshake,
-            sender_id: self.sender_id,
-            session_id: self.session_id,
-            compressed_frame: empty_frame,
-            send_timestamp: Instant::now(),
-            checksum: 0,
-        };
-        
-        // CORRECTION: Calcule le checksum du paquet réel (avec le bon packet_type)
-        packet.checksum = packet.calculate_checksum();
-        packet
-    }
-    
-    /// Crée un paquet disconnect avec checksum correct  
-    fn create_disconnect_packet(&self) -> NetworkPacket {
-        let empty_frame = CompressedFrame::new(vec![], 0, Instant::now(), 0);
-        let mut packet = NetworkPacket {
-            protocol_version: NetworkPacket::CURRENT_PROTOCOL_VERSION,
-            packet_type: PacketType::Disconnect,
-            sender_id: self.sender_id,
-            session_id: self.session_id,
-            compressed_frame: empty_frame,
-            send_timestamp: Instant::now(),
-            checksum: 0,
-        };
-        
-        // CORRECTION: Calcule le checksum du paquet réel (avec le bon packet_type)
-        packet.checksum = packet.calculate_checksum();
-        packet
+    /// Paramètres de notre encodeur, annoncés au pair qui ne décode pas nos
+    /// frames (par défaut ceux d'`AudioConfig::default()`)
+    /// 
+    /// À appeler à la création de l'encodeur si sa configuration diffère de
+    /// celle par défaut ; `renegotiate` met les paramètres du codec à jour.
+    pub fn set_stream_description(&mut self, description: StreamDescription) {
+        self.engine.set_stream_description(description);
     }
-}
-
-#[async_trait]
-impl NetworkManager for UdpNetworkManager {
-    /// Démarre l'écoute en mode serveur
-    async fn start_listening(&mut self, port: u16) -> NetworkResult<()> {
+    
+    /// Signale le résultat du décodage d'une frame du pair
+    /// 
+    /// Après `ProtocolEngine::DECODE_FAILURES_BEFORE_DESCRIBE` échecs
+    /// consécutifs, les paramètres du pair (fréquence, canaux, codec) sont
+    /// supposés différents des nôtres : le manager lui demande de les
+    /// décrire, et `take_decoder_reconfigure` les livre à réception.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use audio::{AudioCodec, AudioConfig, OpusCodec};
+    /// use network::{NetworkManager, UdpNetworkManager};
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = AudioConfig::default();
+    /// let mut decoder = OpusCodec::new(config.clone())?;
+    /// let compressed = manager.receive_audio().await?;
+    /// if let Some(description) = manager.take_decoder_reconfigure() {
+    ///     description.apply_to(&mut config);
+    ///     decoder = OpusCodec::new(config.clone())?;
+    /// }
+    /// let decoded = decoder.decode(&compressed);
+    /// manager.report_decode_result(decoded.is_ok()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn report_decode_result(&mut self, success: bool) -> NetworkResult<()> {
+        let now = self.runtime.now();
+        if let Some(ProtocolAction::Send { packet, target }) = self.engine.record_decode_result(success, now) {
+            self.transport.send_packet(&packet, target).await?;
+        }
+        Ok(())
+    }
+    
+    /// Paramètres du flux du pair reçus depuis le dernier appel (voir
+    /// `report_decode_result`) : le décodeur doit être recréé avec eux
+    pub fn take_decoder_reconfigure(&mut self) -> Option<StreamDescription> {
+        self.engine.take_decoder_reconfigure()
+    }
+    
+    /// Joint des données de l'application (présence : batterie, version...)
+    /// à nos heartbeats de rapport, au plus `NetworkPacket::MAX_HEARTBEAT_PAYLOAD`
+    /// octets
+    /// 
+    /// Les heartbeats de rapport partent avec le trafic audio, au plus un par
+    /// `heartbeat_interval` : le pair reçoit donc les données dans la seconde
+    /// (par défaut), puis à chaque heartbeat tant qu'elles ne changent pas.
+    /// Des données vides effacent les précédentes. Ignorées des pairs des
+    /// versions précédentes.
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::PacketTooLarge` : données trop longues (inchangées)
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::UdpNetworkManager;
+    /// 
+    /// # fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// manager.set_heartbeat_payload(b"batterie=42")?;
+    /// if let Some(payload) = manager.take_peer_heartbeat_payload() {
+    ///     println!("Pair : {}", String::from_utf8_lossy(&payload));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_heartbeat_payload(&mut self, payload: &[u8]) -> NetworkResult<()> {
+        if payload.len() > NetworkPacket::MAX_HEARTBEAT_PAYLOAD {
+            return Err(NetworkError::PacketTooLarge { size: payload.len(), max: NetworkPacket::MAX_HEARTBEAT_PAYLOAD });
+        }
+        self.engine.set_heartbeat_payload(payload.to_vec());
+        Ok(())
+    }
+    
+    /// Nouvelles données de l'application du pair (voir
+    /// `set_heartbeat_payload`), si elles ont changé depuis le dernier appel
+    pub fn take_peer_heartbeat_payload(&mut self) -> Option<Vec<u8>> {
+        self.engine.take_peer_heartbeat_payload()
+    }
+    
+    /// Prochain changement d'échelon de dégradation (`NetworkConfig::degradation`)
+    /// 
+    /// Le manager applique lui-même la redondance et la profondeur du
+    /// buffer ; les autres échelons reviennent à l'application : débit et
+    /// durée de frame via `renegotiate`, avertissement de l'utilisateur,
+    /// flux annexes.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{CodecParams, DegradationEvent, DegradationStep, UdpNetworkManager};
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// while let Some(event) = manager.take_degradation_event() {
+    ///     match event {
+    ///         DegradationEvent::Degrade(DegradationStep::ReduceBitrate) => {
+    ///             manager.renegotiate(CodecParams { bitrate_bps: 16_000, ..CodecParams::voice() }).await?;
+    ///         }
+    ///         DegradationEvent::Recover(DegradationStep::ReduceBitrate) => {
+    ///             manager.renegotiate(CodecParams::voice()).await?;
+    ///         }
+    ///         DegradationEvent::Degrade(DegradationStep::WarnUser) => println!("Connexion dégradée"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_degradation_event(&mut self) -> Option<DegradationEvent> {
+        self.degradation_events.pop_front()
+    }
+    
+    /// Prochain événement réseau pas encore lu (`NetworkEvent`)
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{NetworkEvent, UdpNetworkManager};
+    /// 
+    /// # fn example(manager: &mut UdpNetworkManager) {
+    /// while let Some(event) = manager.take_network_event() {
+    ///     match event {
+    ///         NetworkEvent::UnexpectedSource(addr) => println!("Paquets inattendus de {} : vérifier le port ou le NAT", addr),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn take_network_event(&mut self) -> Option<NetworkEvent> {
+        self.reap_silent_peer();
+        self.network_events.pop_front()
+    }
+    
+    /// Échelons de dégradation actifs, dans l'ordre de descente
+    pub fn degradation_steps(&self) -> &[DegradationStep] {
+        self.degradation.as_ref().map(DegradationLadder::active_steps).unwrap_or_default()
+    }
+    
+    /// Auto-diagnostic de la pile complète, sans pair distant
+    ///
+    /// Passe un appel de `SELF_TEST_DURATION` entre deux managers reliés par
+    /// des transports simulés (perte de `SELF_TEST_LOSS_RATE`), avec un
+    /// microphone et un haut-parleur factices et le codec Opus. N'affecte pas
+    /// la connexion de ce manager ; seule sa configuration est réutilisée.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// let report = manager.self_test().await?;
+    /// println!("{}", report.summary());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(all(feature = "simulator", feature = "audio-reexports"))]
+    pub async fn self_test(&self) -> NetworkResult<SelfTestReport> {
+        let audio_config = AudioConfig::default();
+        self.runtime.scope(selftest::run_self_test(
+            self.config.clone(),
+            audio_config.clone(),
+            SELF_TEST_DURATION,
+            SELF_TEST_LOSS_RATE,
+            || Ok(Box::new(OpusCodec::new(audio_config.clone())?) as Box<dyn AudioCodec>),
+        )).await
+    }
+
+    /// Adresse locale annoncée par le pair dans son handshake
+    /// 
+    /// Comparée à `peer_addr` (l'adresse d'où viennent ses paquets), elle
+    /// révèle une traduction d'adresse (NAT) entre les pairs. `None` hors
+    /// appel ou si le pair ne l'annonce pas (versions précédentes).
+    pub fn peer_local_addr(&self) -> Option<SocketAddr> {
+        self.engine.peer_local_addr()
+    }
+    
+    /// Notre identité, prouvée au pair dans chaque handshake
+    /// 
+    /// Stable d'un lancement à l'autre avec `NetworkConfig::identity_file`.
+    pub fn identity(&self) -> Option<PeerIdentity> {
+        self.engine.identity()
+    }
+    
+    /// Identité prouvée par le pair sur notre défi, lors du dernier handshake
+    /// 
+    /// Permet de reconnaître un contact d'un appel à l'autre (son `sender_id`
+    /// change à chaque lancement). `None` si le pair n'en annonce pas
+    /// (versions précédentes) ou si sa preuve est invalide ou rejouée.
+    pub fn peer_identity(&self) -> Option<PeerIdentity> {
+        self.engine.peer_identity()
+    }
+    
+    /// Installe la question posée pour chaque identité inconnue (confiance
+    /// au premier usage)
+    /// 
+    /// Avec une question installée, les pairs sans identité prouvée sur
+    /// notre défi sont refusés, y compris ceux qui rejouent l'annonce d'un
+    /// autre. Sans question, les identités inconnues sont acceptées ; les
+    /// décisions retenues (`known_peers`) s'appliquent dans tous les cas.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{NetworkConfig, TrustDecision, UdpNetworkManager};
+    /// 
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = NetworkConfig::default();
+    /// config.known_peers_file = Some("known_peers.toml".into());
+    /// let mut manager = UdpNetworkManager::new(config)?;
+    /// manager.set_trust_callback(Some(Box::new(|request| {
+    ///     println!("Nouveau pair {} : empreinte {}", request.addr, request.fingerprint());
+    ///     TrustDecision::Remember
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_trust_callback(&mut self, callback: Option<TrustCallback>) {
+        self.trust_callback = callback;
+    }
+    
+    /// Décisions de confiance retenues
+    pub fn known_peers(&self) -> &KnownPeers {
+        &self.known_peers
+    }
+    
+    /// Décisions de confiance retenues, à modifier (`forget` après un
+    /// changement d'identité légitime)
+    pub fn known_peers_mut(&mut self) -> &mut KnownPeers {
+        &mut self.known_peers
+    }
+    
+    /// Heartbeats refusés par le socket plein et envoyés avant l'audio suivant
+    /// 
+    /// Toujours 0 sans `NetworkConfig::control_priority` ; une valeur qui
+    /// croît signale un lien saturé par l'audio (baisser le débit, la redondance).
+    pub fn deferred_control_packets(&self) -> u64 {
+        self.control_queue.deferred_count()
+    }
+    
+    /// Relève l'occupation du buffer de lecture, annoncée au pair
+    /// 
+    /// À appeler après chaque frame jouée : au-delà de
+    /// `FlowControlHint::HIGH_WATER_PERCENT`, le pair saute des frames
+    /// jusqu'à ce que la lecture rattrape son retard.
+    /// 
+    /// # Arguments
+    /// * `buffered_frames` - Frames en attente de lecture (ex: `AudioPlayback::buffer_level`)
+    /// * `capacity` - Capacité du buffer de lecture, en frames
+    pub fn set_playback_level(&mut self, buffered_frames: usize, capacity: usize) {
+        self.engine.set_playback_level(buffered_frames, capacity);
+    }
+    
+    /// Redirection de port active sur le routeur, si demandée et accordée
+    #[cfg(feature = "upnp")]
+    pub fn port_mapping(&self) -> Option<PortMapping> {
+        self.port_mapper.as_ref().map(|mapper| mapper.mapping())
+    }
+    
+    /// Arrête complètement le manager
+    /// 
+    /// Déconnecte le pair éventuel, supprime la redirection de port
+    /// sur le routeur (feature `upnp`) et libère le transport.
+    pub async fn shutdown(&mut self) -> NetworkResult<()> {
+        self.disconnect().await?;
+        self.stop_background_tasks().await;
+        self.transport.shutdown().await
+    }
+    
+    /// Change de port local sans recréer le manager
+    /// 
+    /// Les tâches liées au socket (heartbeats, annonces de présence,
+    /// redirection de port) sont arrêtées, le socket est fermé puis rebindé
+    /// sur `new_port`, et les tâches redémarrées. Un appel en cours est
+    /// suspendu puis repris depuis la nouvelle adresse (voir
+    /// `NetworkConfig::resume_grace` ; sans reprise, le pair est prévenu et
+    /// une nouvelle session est établie). Statistiques, identité et
+    /// historique sont conservés.
+    /// 
+    /// # Arguments
+    /// * `new_port` - Nouveau port UDP local (0 pour un port choisi par le système)
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{NetworkManager, UdpNetworkManager};
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// // Port bloqué par le réseau de l'hôtel : on en essaie un autre
+    /// manager.rebind(0).await?;
+    /// println!("Nouvelle adresse locale : {:?}", manager.local_addr());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rebind(&mut self, new_port: u16) -> NetworkResult<()> {
+        self.restart_transport(new_port, None).await
+    }
+    
+    /// Applique une nouvelle configuration sans recréer le manager
+    /// 
+    /// Délais, buffer anti-jitter, plafond de débit, échelle de dégradation
+    /// et période d'échantillonnage de la qualité s'appliquent tout de suite.
+    /// Si les réglages du transport changent (proxy, adresse de bind, âge
+    /// maximal des paquets, mesures de réception), il est recréé et rebindé
+    /// sur le même port comme par `rebind` ; sinon seules les tâches de fond
+    /// (heartbeats, présence, redirection de port) sont redémarrées si leurs
+    /// réglages ont changé. L'identité et les pairs de confiance restent ceux
+    /// de la création. Un tap de capture est à réinstaller après un
+    /// changement de transport.
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` : configuration invalide, ou réglages du
+    ///   transport modifiés sur un transport fourni par l'application
+    ///   (`with_transport`) ; rien n'est alors appliqué
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::UdpNetworkManager;
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example(manager: &mut UdpNetworkManager) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = manager.config().clone();
+    /// config.heartbeat_interval = Duration::from_millis(500);
+    /// config.max_bandwidth_bps = Some(48_000);
+    /// manager.apply_config(config).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_config(&mut self, config: NetworkConfig) -> NetworkResult<()> {
+        config.validate()?;
+        let new_transport = match (self.transport_settings_changed(&config), self.transport_factory) {
+            (false, _) => None,
+            (true, Some(factory)) => Some(self.in_runtime(factory(&config)?)),
+            (true, None) => {
+                return Err(NetworkError::ConfigError(
+                    "transport fourni par l'application : proxy, adresse de bind et réglages de réception ne peuvent pas changer".to_string(),
+                ));
+            }
+        };
+        let background_changed = config.heartbeat_interval != self.config.heartbeat_interval
+            || config.control_priority != self.config.control_priority
+            || config.presence != self.config.presence
+            || config.port_mapping != self.config.port_mapping;
+        
+        // Une nouvelle échelle repart du premier échelon : l'application
+        // annule d'abord ceux de l'ancienne
+        if config.degradation != self.config.degradation {
+            let active = self.degradation_steps().to_vec();
+            for step in active.into_iter().rev() {
+                self.apply_degradation(DegradationEvent::Recover(step));
+            }
+            self.degradation = config.degradation.clone().map(DegradationLadder::new);
+        }
+        if config.stats_sample_period != self.config.stats_sample_period {
+            self.quality = QualityHistory::new(config.stats_sample_period);
+        }
+        self.pacer.set_cap(config.max_bandwidth_bps);
+        self.config = config;
+        
+        self.engine.reconfigure(&self.config);
+        if let Some(before) = self.target_delay_before_degradation.as_mut() {
+            // Échelon `IncreaseJitterBuffer` toujours actif : doublé depuis la nouvelle attente
+            *before = self.engine.target_delay_ms();
+            let frame_ms = self.engine.frame_duration().as_millis() as u32;
+            self.engine.set_target_delay_ms((*before * 2).max(frame_ms));
+        }
+        self.sync_padding();
+        
+        let local_port = self.transport.local_addr().map(|addr| addr.port());
+        match (new_transport, local_port) {
+            (Some(transport), Some(port)) => self.restart_transport(port, Some(transport)).await?,
+            (Some(transport), None) => self.transport = transport,
+            (None, Some(port)) if background_changed => {
+                self.stop_background_tasks().await;
+                self.start_presence();
+                self.setup_port_mapping(port).await;
+                if let Some(peer_addr) = self.connected_peer().await {
+                    self.start_heartbeat(peer_addr).await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// Configuration courante
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+    
+    /// Réglages de `config` qui n'agissent qu'à la création du transport
+    fn transport_settings_changed(&self, config: &NetworkConfig) -> bool {
+        config.proxy != self.config.proxy
+            || config.bind_ip != self.config.bind_ip
+            || config.bind_interface != self.config.bind_interface
+            || config.max_packet_age != self.config.max_packet_age
+            || config.timing_stats != self.config.timing_stats
+            || config.connection_timeout != self.config.connection_timeout
+    }
+    
+    /// Fait passer un transport neuf par le runtime injecté, comme `with_runtime`
+    fn in_runtime(&self, transport: Box<dyn NetworkTransport + Send + Sync>) -> Box<dyn NetworkTransport + Send + Sync> {
+        if self.runtime.is_injected() {
+            Box::new(RuntimeTransport::new(transport, self.runtime.clone()))
+        } else {
+            transport
+        }
+    }
+    
+    /// Pair de l'appel en cours, s'il y en a un
+    async fn connected_peer(&self) -> Option<SocketAddr> {
+        match self.connection_state.lock().await.current() {
+            ConnectionState::Connected { peer_addr, .. } => Some(*peer_addr),
+            _ => None,
+        }
+    }
+    
+    /// Ferme le socket et rebinde sur `local_port` (avec `transport` à la
+    /// place de l'actuel s'il est fourni), en suspendant l'appel en cours
+    /// pour le reprendre ensuite
+    async fn restart_transport(
+        &mut self,
+        local_port: u16,
+        transport: Option<Box<dyn NetworkTransport + Send + Sync>>,
+    ) -> NetworkResult<()> {
+        let peer_addr = self.connected_peer().await;
+        if peer_addr.is_some() {
+            // Avec la reprise, le pair suit notre nouvelle adresse dans la
+            // même session ; sinon il doit la fermer pour accepter notre Hello
+            if self.config.resume_grace.is_zero() {
+                if let Some(ProtocolAction::Send { packet, target }) = self.engine.disconnect() {
+                    let _ = self.transport.send_packet(&packet, target).await;
+                }
+            } else {
+                self.engine.close();
+            }
+            self.set_connection_state(ConnectionState::Disconnected, "changement de socket local").await?;
+        }
+        
+        self.stop_background_tasks().await;
+        // Le transport remet ses compteurs à zéro : les octets déjà comptés
+        // restent dans les stats du manager
+        let transport_stats = self.transport.stats();
+        self.stats.lock().await.merge_traffic(&transport_stats);
+        self.transport.shutdown().await?;
+        if let Some(transport) = transport {
+            self.transport = transport;
+        }
+        self.bind_for_call(local_port).await?;
+        self.sync_padding();
+        println!("Socket local rebindé sur {:?}", self.transport.local_addr());
+        
+        match peer_addr {
+            Some(peer_addr) => self.connect_with_deadline(peer_addr, None).await,
+            None => Ok(()),
+        }
+    }
+    
+    /// Arrête les tâches liées au socket : heartbeats, annonces de présence
+    /// et redirection de port
+    async fn stop_background_tasks(&mut self) {
+        self.stop_heartbeat().await;
+        if let Some(mut thread) = self.presence_handle.take() {
+            thread.stop();
+        }
+        
+        #[cfg(feature = "upnp")]
+        if let Some(mapper) = self.port_mapper.take()
+            && let Err(e) = self.runtime.scope(mapper.remove()).await
+        {
+            println!("Suppression de la redirection de port échouée : {}", e);
+        }
+    }
+    
+    /// Demande la redirection du port d'écoute au routeur si configurée
+    async fn setup_port_mapping(&mut self, port: u16) {
+        if !self.config.port_mapping {
+            return;
+        }
+        
+        #[cfg(feature = "upnp")]
+        match self.runtime.scope(PortMapper::map(port, PortMapper::DEFAULT_LEASE)).await {
+            Ok(mapper) => {
+                println!("Accessible depuis Internet via {}", mapper.mapping().external_addr);
+                self.port_mapper = Some(mapper);
+            }
+            Err(e) => println!("Redirection de port indisponible : {}", e),
+        }
+        
+        #[cfg(not(feature = "upnp"))]
+        println!("Redirection de port demandée pour le port {} mais la feature `upnp` n'est pas activée", port);
+    }
+    
+    /// Attend le Hello d'un pair et établit la connexion
+    ///
+    /// Répond aussi aux sondes de découverte et refuse les versions de
+    /// protocole incompatibles pendant l'attente.
+    async fn accept_incoming(&mut self) -> NetworkResult<SocketAddr> {
+        loop {
+            match self.transport.receive_packet().await {
+                Ok((packet, source_addr)) => {
+                    // Le moteur accepte les Hello et répond aux sondes de découverte
+                    self.handle_received_packet(packet, source_addr).await?;
+                }
+                Err(NetworkError::Timeout) => {
+                    // Échéance de la preuve d'identité attendue du pair
+                    let actions = self.engine.poll(self.runtime.now());
+                    self.apply_actions(actions).await?;
+                }
+                Err(NetworkError::UnsupportedVersion { addr, version }) => {
+                    // Version incompatible : le client est prévenu au lieu d'attendre
+                    println!("Version de protocole {} refusée pour {}", version, addr);
+                    self.send_protocol_error(ProtocolErrorCode::VersionMismatch, addr).await?;
+                }
+                Err(e) if e.is_packet_level() => self.report_packet_error(&e).await,
+                Err(e) => return Err(e),
+            }
+            
+            // Session établie, et l'identité annoncée par le pair jugée
+            if let Some(peer_addr) = self.engine.peer_addr()
+                && self.engine.is_connected()
+                && !self.engine.identity_pending()
+            {
+                println!("Connexion établie avec {}", peer_addr);
+                return Ok(peer_addr);
+            }
+        }
+    }
+
+    /// Bind le transport sur un port choisi par le système s'il ne l'est pas déjà
+    /// 
+    /// Le port 0 laisse le système attribuer un port libre : pas de conflit
+    /// possible avec une autre application, contrairement à un port tiré au hasard.
+    async fn ensure_bound(&mut self) -> NetworkResult<()> {
+        if !self.transport.is_active() {
+            self.transport.bind(0).await?;
+            self.on_transport_bound();
+        }
+        Ok(())
+    }
+    
+    /// Binde le transport sur `local_port` pour un appel, s'il ne l'est pas
+    /// encore, et demande la redirection de port si configurée
+    async fn bind_for_call(&mut self, local_port: u16) -> NetworkResult<()> {
+        if !self.transport.is_active() {
+            self.transport.bind(local_port).await?;
+            self.on_transport_bound();
+            self.setup_port_mapping(local_port).await;
+        }
+        Ok(())
+    }
+    
+    /// Prépare ce qui dépend de l'adresse locale, une fois le transport bindé
+    fn on_transport_bound(&mut self) {
+        self.engine.set_local_addr(self.announced_local_addr());
+        self.start_presence();
+    }
+    
+    /// Adresse locale annoncée au pair dans le handshake
+    /// 
+    /// Un socket lié à toutes les interfaces (`0.0.0.0`) annonce l'adresse
+    /// IP locale principale, avec le port réellement attribué.
+    fn announced_local_addr(&self) -> Option<SocketAddr> {
+        let local_addr = self.transport.local_addr()?;
+        if !local_addr.ip().is_unspecified() {
+            return Some(local_addr);
+        }
+        utils::get_local_ip().ok()
+            .map(|ip| SocketAddr::new(ip, local_addr.port()))
+            .or(Some(local_addr))
+    }
+    
+    /// Démarre le thread de heartbeat
+    /// 
+    /// Envoie un heartbeat toutes les `heartbeat_interval` depuis un thread
+    /// dédié, pour que le pair ne coupe pas la connexion quand le runtime de
+    /// l'application est bloqué. Sans chemin d'envoi synchrone fourni par le
+    /// transport (proxy SOCKS5), les heartbeats accompagnent seulement l'audio.
+    /// La surveillance du pair (`start_liveness_watchdog`) démarre avec eux.
+    async fn start_heartbeat(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
+        if self.heartbeat_handle.is_some() {
+            return Ok(()); // Déjà démarré
+        }
+        let Some(mut sink) = self.transport.keepalive_sink() else {
+            return Ok(());
+        };
+        if self.config.control_priority {
+            sink = Arc::new(PrioritySink::new(sink, self.control_queue.clone()));
+        }
+        
+        let heartbeat = self.engine.heartbeat_packet();
+        let thread = KeepaliveThread::spawn(sink, heartbeat, peer_addr, self.config.heartbeat_interval)?;
+        self.heartbeat_handle = Some(thread);
+        self.start_liveness_watchdog(peer_addr)?;
+        Ok(())
+    }
+    
+    /// Démarre la surveillance du pair hors des lectures du manager
+    /// 
+    /// Si aucun datagramme du pair n'arrive pendant `heartbeat_timeout`,
+    /// même quand l'application ne lit pas le réseau (client sans audio à
+    /// recevoir), l'état passe en erreur depuis le thread du watchdog. La session est
+    /// interrompue (et l'événement `NetworkEvent::PeerTimedOut` émis) au
+    /// prochain appel du manager, voir `reap_silent_peer`.
+    fn start_liveness_watchdog(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
+        let Some(source) = self.transport.keepalive_source() else {
+            return Ok(());
+        };
+        
+        let connection_state = self.connection_state.clone();
+        let runtime = self.runtime.clone();
+        let timeout = self.config.heartbeat_timeout;
+        let watchdog = LivenessWatchdog::spawn(source, peer_addr, timeout, move || {
+            // Thread hors runtime : le verrou tokio peut être pris en bloquant
+            let mut machine = connection_state.blocking_lock();
+            if !machine.current().is_connected() || machine.current().peer_addr() != Some(peer_addr) {
+                return;
+            }
+            let error = NetworkError::PeerDisconnected { addr: peer_addr };
+            let state = ConnectionState::Error {
+                last_error: error.to_string(),
+                failed_at: runtime.now(),
+                can_retry: error.can_retry_connection(),
+            };
+            let _ = machine.transition(state, &format!("pair muet depuis {:?} (watchdog)", timeout), runtime.now());
+        })?;
+        self.liveness_watchdog = Some(watchdog);
+        Ok(())
+    }
+    
+    /// Prend acte d'un pair déclaré muet par le watchdog
+    /// 
+    /// L'état est déjà en erreur : la session est interrompue (reprenable
+    /// pendant `resume_grace`, comme après `fail_connection`), les threads
+    /// arrêtés et l'événement mis en file pour l'application.
+    fn reap_silent_peer(&mut self) {
+        if !self.liveness_watchdog.as_ref().is_some_and(LivenessWatchdog::expired) {
+            return;
+        }
+        self.liveness_watchdog = None;
+        self.heartbeat_handle = None;
+        if let Some(peer_addr) = self.engine.peer_addr() {
+            self.network_events.push_back(NetworkEvent::PeerTimedOut(peer_addr));
+        }
+        self.engine.close();
+    }
+    
+    /// Démarre les annonces de présence si elles sont configurées
+    /// 
+    /// Appelé dès que le transport est bindé : les annonces partent du socket
+    /// d'écoute vers le broadcast, depuis un thread comme les heartbeats.
+    fn start_presence(&mut self) {
+        let Some(presence) = &self.config.presence else {
+            return;
+        };
+        let Some(sink) = self.transport.keepalive_sink() else {
+            println!("Annonces de présence indisponibles sur ce transport");
+            return;
+        };
+        
+        let announce = presence::announcement_packet(presence, self.engine.sender_id(), self.engine.session_id());
+        let target = utils::broadcast_addr(presence.port);
+        match KeepaliveThread::spawn(sink, announce, target, presence.interval) {
+            Ok(thread) => self.presence_handle = Some(thread),
+            Err(e) => println!("Annonces de présence non démarrées : {}", e),
+        }
+    }
+    
+    /// Arrête le thread de heartbeat et la surveillance du pair
+    async fn stop_heartbeat(&mut self) {
+        if let Some(mut thread) = self.heartbeat_handle.take() {
+            thread.stop();
+        }
+        if let Some(mut watchdog) = self.liveness_watchdog.take() {
+            watchdog.stop();
+        }
+    }
+    
+    /// Effectue le handshake initial avec un peer
+    /// 
+    /// Pilote le handshake de `ProtocolEngine` : le `Hello` est retransmis
+    /// avec un backoff exponentiel jusqu'à réponse. Si les deux pairs se
+    /// connectent simultanément (Hello croisés), le pair de plus petit
+    /// `sender_id` prend le rôle de serveur et accepte : les deux côtés
+    /// convergent vers sa session.
+    async fn perform_handshake(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
+        let actions = self.engine.connect(peer_addr, self.runtime.now());
+        self.apply_actions(actions).await?;
+        
+        while let Some(deadline) = self.engine.next_deadline() {
+            // Hello croisés gagnés : l'identité du pair reste à prouver
+            if self.engine.is_connected() && !self.engine.identity_pending() {
+                return Ok(());
+            }
+            
+            // Attend une réponse jusqu'à la prochaine retransmission (ou l'expiration)
+            let wait = deadline.saturating_duration_since(self.runtime.now());
+            let mut actions = match self.runtime.timeout(wait, self.transport.receive_packet()).await {
+                Ok(Ok((packet, source))) => self.engine.handle_packet(packet, source, self.runtime.now()),
+                Ok(Err(NetworkError::Timeout)) | Err(_) => Vec::new(),
+                Ok(Err(e)) => return Err(e),
+            };
+            actions.extend(self.engine.poll(self.runtime.now()));
+            self.apply_actions(actions).await?;
+        }
+        
+        // Le moteur n'abandonne qu'en signalant l'échec : on n'arrive ici qu'en cas de course
+        Err(NetworkError::connection_timeout(peer_addr, self.config.connection_timeout.as_millis() as u32))
+    }
+    
+    /// Compte un datagramme invalide et le signale sans inonder la sortie
+    /// 
+    /// Les répétitions d'une même erreur sont regroupées par `ErrorLog` et
+    /// comptées dans `NetworkStats::errors_suppressed` ; une source
+    /// inattendue devient aussi un `NetworkEvent`, au même rythme que les
+    /// messages.
+    async fn report_packet_error(&mut self, error: &NetworkError) {
+        let message = self.error_log.report(error, self.runtime.now());
+        
+        let mut stats = self.stats.lock().await;
+        match error {
+            NetworkError::CorruptedPacket { .. } => stats.packets_corrupted += 1,
+            NetworkError::PacketTooOld { .. } => stats.packets_rejected += 1,
+            NetworkError::UnexpectedSource { addr, .. } => {
+                stats.packets_from_unknown_peer += 1;
+                if message.is_some() {
+                    self.network_events.push_back(NetworkEvent::UnexpectedSource(*addr));
+                }
+            }
+            _ => {}
+        }
+        stats.errors_suppressed = self.error_log.suppressed_total();
+        
+        if let Some(message) = message.filter(|_| self.config.log_packet_errors) {
+            println!("Paquet ignoré : {}", message);
+        }
+    }
+    
+    /// Passe en état d'erreur après un échec de connexion ou de transport
+    /// 
+    /// L'adresse du pair reste connue (`peer_addr()`) pour `reconnect`.
+    async fn fail_connection(&mut self, error: &NetworkError, reason: &str) {
+        let state = ConnectionState::Error {
+            last_error: error.to_string(),
+            failed_at: self.runtime.now(),
+            can_retry: error.can_retry_connection(),
+        };
+        // Transition toujours autorisée, quel que soit l'état courant
+        let _ = self.set_connection_state(state, reason).await;
+        self.engine.close();
+        self.stop_heartbeat().await;
+    }
+    
+    /// Se connecte au pair en réessayant les échecs passagers
+    /// 
+    /// Comme `connect_to_peer` : jusqu'à `max_retry_attempts` handshakes,
+    /// espacés de `retry_delay` doublé à chaque échec (borné par
+    /// `connection_timeout`). L'état `Connecting` porte le numéro de la
+    /// tentative en cours. Un refus explicite du pair n'est pas réessayé.
+    /// Si toutes les tentatives directes échouent et que des relais sont
+    /// configurés (`NetworkConfig::relays`), une dernière tentative passe
+    /// par le plus proche d'entre eux (voir `connect_via_relay`).
+    /// 
+    /// # Arguments
+    /// * `peer_addr` - Adresse du pair
+    /// * `deadline` - Durée maximale de l'ensemble des tentatives, attentes
+    ///   comprises (aucune limite autre que le nombre de tentatives si `None`)
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConnectionTimeout` : aucune réponse, ou `deadline` dépassée
+    /// - `NetworkError::RemoteError` : le pair a refusé l'appel
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// use std::time::Duration;
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// manager.connect_with_deadline("192.168.1.20:9001".parse()?, Some(Duration::from_secs(10))).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_with_deadline(&mut self, peer_addr: SocketAddr, deadline: Option<Duration>) -> NetworkResult<()> {
+        self.reap_silent_peer();
+        let started_at = self.runtime.now();
+        
+        // Bind sur un port local aléatoire (sauf si déjà bindé, ex: après une découverte)
+        if let Err(e) = self.ensure_bound().await {
+            self.fail_connection(&e, "bind impossible").await;
+            return Err(e);
+        }
+        if let Some(probe) = self.config.jitter_probe {
+            self.tune_target_delay(peer_addr, probe).await;
+        }
+        
+        let mut attempt = 1;
+        loop {
+            let result = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_sub(self.runtime.now().saturating_duration_since(started_at));
+                    let runtime = self.runtime.clone();
+                    runtime.timeout(remaining, self.connect_attempt(peer_addr, attempt)).await
+                        .unwrap_or_else(|_| Err(NetworkError::connection_timeout(peer_addr, deadline.as_millis() as u32)))
+                }
+                None => self.connect_attempt(peer_addr, attempt).await,
+            };
+            
+            let e = match result {
+                Ok(()) => {
+                    println!("Connecté à {}", peer_addr);
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+            let delay = self.retry_backoff(attempt);
+            let out_of_time = deadline.is_some_and(|deadline| self.runtime.now().saturating_duration_since(started_at) + delay >= deadline);
+            if attempt >= self.config.max_retry_attempts || !e.can_retry_connection() || out_of_time {
+                // Pair injoignable en direct : dernier recours, un relais
+                let time_left = deadline.is_none_or(|deadline| self.runtime.now().saturating_duration_since(started_at) < deadline);
+                if e.can_retry_connection() && time_left && self.try_relays(peer_addr).await {
+                    return Ok(());
+                }
+                self.fail_connection(&e, "échec du handshake").await;
+                return Err(e);
+            }
+            
+            println!("Tentative {} vers {} échouée ({}), nouvel essai dans {:?}", attempt, peer_addr, e, delay);
+            self.engine.close();
+            self.runtime.sleep(delay).await;
+            attempt += 1;
+        }
+    }
+    
+    /// Relais candidat le plus proche, d'après son temps de réponse
+    /// 
+    /// Sonde chaque relais de `NetworkConfig::relays` (`RELAY_PING_COUNT`
+    /// sondes) et retient celui dont le RTT moyen est le plus faible.
+    /// 
+    /// # Returns
+    /// Le relais retenu et son RTT moyen, `None` si aucun ne répond
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : appel en cours (voir `ping`)
+    pub async fn select_relay(&mut self) -> NetworkResult<Option<(SocketAddr, Duration)>> {
+        let mut best: Option<(SocketAddr, Duration)> = None;
+        for relay in self.config.relays.clone() {
+            let report = self.ping(relay, Self::RELAY_PING_COUNT).await?;
+            if let Some(rtt) = report.avg_rtt()
+                && best.is_none_or(|(_, best_rtt)| rtt < best_rtt)
+            {
+                best = Some((relay, rtt));
+            }
+        }
+        Ok(best)
+    }
+    
+    /// Se connecte à un pair à travers un relais
+    /// 
+    /// Tout le trafic vers le pair (audio, heartbeats) est encapsulé et
+    /// envoyé au relais, qui le transmet ; `connection_state()` indique
+    /// ensuite `SessionRoute::Relayed`. `connect_to_peer` y a recours de
+    /// lui-même, avec le relais retenu par `select_relay`, quand le pair est
+    /// injoignable en direct.
+    /// 
+    /// # Arguments
+    /// * `peer_addr` - Adresse du pair, telle que le relais la joint
+    /// * `relay` - Adresse du relais (voir `RelayServer`)
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : transport sans support des relais
+    ///   (`SimulatedTransport`, proxy SOCKS5)
+    /// - Erreurs du handshake, comme `connect_to_peer`
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use network::{UdpNetworkManager, NetworkConfig};
+    /// 
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut manager = UdpNetworkManager::new(NetworkConfig::default())?;
+    /// manager.connect_via_relay("203.0.113.7:9001".parse()?, "198.51.100.2:9100".parse()?).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_via_relay(&mut self, peer_addr: SocketAddr, relay: SocketAddr) -> NetworkResult<()> {
+        let result = match self.ensure_bound().await {
+            Ok(()) => self.relayed_attempt(peer_addr, relay).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            self.fail_connection(e, "échec de la connexion via le relais").await;
+        }
+        result
+    }
+    
+    /// Tente la connexion via le relais le plus proche, s'il y en a un
+    /// 
+    /// Retourne true si la connexion est établie ; sinon l'échec est
+    /// seulement affiché, l'erreur de la connexion directe prévalant.
+    async fn try_relays(&mut self, peer_addr: SocketAddr) -> bool {
+        if self.config.relays.is_empty() {
+            return false;
+        }
+        self.engine.close();
+        let relay = match self.select_relay().await {
+            Ok(Some((relay, rtt))) => {
+                println!("Pair {} injoignable en direct, essai via le relais {} (RTT {:?})", peer_addr, relay, rtt);
+                relay
+            }
+            Ok(None) => {
+                println!("Pair {} injoignable en direct, et aucun relais ne répond", peer_addr);
+                return false;
+            }
+            Err(e) => {
+                println!("Sélection d'un relais impossible : {}", e);
+                return false;
+            }
+        };
+        match self.relayed_attempt(peer_addr, relay).await {
+            Ok(()) => true,
+            Err(e) => {
+                println!("Connexion via le relais {} échouée : {}", relay, e);
+                false
+            }
+        }
+    }
+    
+    /// Handshake avec le pair, le trafic passant par `relay`
+    async fn relayed_attempt(&mut self, peer_addr: SocketAddr, relay: SocketAddr) -> NetworkResult<()> {
+        self.transport.set_relay_route(peer_addr, Some(relay))?;
+        let result = self.connect_attempt(peer_addr, 1).await;
+        match &result {
+            Ok(()) => println!("Connecté à {} via le relais {}", peer_addr, relay),
+            Err(_) => {
+                let _ = self.transport.set_relay_route(peer_addr, None);
+                self.engine.close();
+            }
+        }
+        result
+    }
+    
+    /// Chemin du trafic vers `peer_addr`
+    fn session_route(&self, peer_addr: SocketAddr) -> SessionRoute {
+        match self.transport.relay_route(peer_addr) {
+            Some(relay) => SessionRoute::Relayed { relay },
+            None => SessionRoute::Direct,
+        }
+    }
+    
+    /// Cale l'attente des paquets en retard sur la gigue mesurée vers le pair
+    /// 
+    /// Sans mesure exploitable (pair muet, erreur), `target_delay_ms` reste
+    /// celui de la configuration.
+    async fn tune_target_delay(&mut self, peer_addr: SocketAddr, probe: Duration) {
+        self.engine.set_target_delay_ms(self.config.target_delay_ms);
+        let report = match self.measure_jitter(peer_addr, probe).await {
+            Ok(report) => report,
+            Err(e) => {
+                println!("Mesure de gigue vers {} impossible : {}", peer_addr, e);
+                return;
+            }
+        };
+        let frame_duration = self.engine.frame_duration();
+        if let Some(window) = report.recommended_late_window(frame_duration) {
+            let delay_ms = (frame_duration.as_millis() as u64 * window) as u32;
+            let applied = self.engine.set_target_delay_ms(delay_ms);
+            println!("Gigue p99 vers {} : {:?}, réordonnancement toléré {}ms",
+                     peer_addr, report.p99().unwrap_or_default(), applied);
+        }
+    }
+    
+    /// Attente avant la tentative suivant la tentative `attempt` (à partir de 1)
+    fn retry_backoff(&self, attempt: u32) -> Duration {
+        self.config.retry_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.config.connection_timeout.max(self.config.retry_delay))
+    }
+    
+    /// Tentative de handshake avec le pair (`attempt` à partir de 1), transport bindé
+    async fn connect_attempt(&mut self, peer_addr: SocketAddr, attempt: u32) -> NetworkResult<()> {
+        self.set_connection_state(ConnectionState::Connecting {
+            target_addr: peer_addr,
+            started_at: self.runtime.now(),
+            attempt_count: attempt,
+        }, "appel du pair").await?;
+        
+        // Effectue le handshake (l'état passe à Connected à sa réussite)
+        self.perform_handshake(peer_addr).await
+    }
+    
+    /// Met à jour l'état de connexion via la machine à états
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : transition interdite depuis l'état courant
+    async fn set_connection_state(&mut self, new_state: ConnectionState, reason: &str) -> NetworkResult<()> {
+        self.connection_state.lock().await.transition(new_state.clone(), reason, self.runtime.now())?;
+        
+        if new_state.is_connected() {
+            self.outgoing_backlog.clear();
+        }
+        
+        match new_state {
+            ConnectionState::Connecting { .. } | ConnectionState::Connected { .. } => {
+                self.last_peer_addr = new_state.peer_addr();
+            }
+            ConnectionState::Disconnected => {
+                // Fin de l'appel : le prochain passera d'abord en direct
+                if let Some(peer_addr) = self.last_peer_addr.take() {
+                    let _ = self.transport.set_relay_route(peer_addr, None);
+                }
+            }
+            ConnectionState::Error { .. } => {}
+        }
+        Ok(())
+    }
+    
+    /// Traite un paquet reçu : `ProtocolEngine` décide, le manager exécute
+    /// 
+    /// Retourne la frame audio à livrer au moteur d'appel s'il y en a une.
+    async fn handle_received_packet(&mut self, packet: NetworkPacket, source: SocketAddr) -> NetworkResult<Option<CompressedFrame>> {
+        let is_audio = packet.packet_type == PacketType::Audio;
+        
+        // Étapes chronométrées pour l'audio seulement (NetworkConfig::timing_stats)
+        let timings = self.transport.receive_timings().filter(|_| is_audio).cloned();
+        let actions = timing::measure(timings.as_ref(), ReceiveStage::BufferInsert, || {
+            self.engine.handle_packet(packet, source, self.runtime.now())
+        });
+        let inserted_at = Instant::now();
+        let frame = self.apply_actions(actions).await?;
+        self.sync_padding();
+        
+        if is_audio && self.engine.is_connected() {
+            self.stats.lock().await.packets_late = self.engine.late_packets();
+            self.send_stats_heartbeat_if_due().await?;
+            self.send_control_offers().await?;
+        }
+        self.refresh_heartbeat_stats().await;
+        
+        if let (Some(timings), Some(_)) = (&timings, &frame) {
+            timings.record(ReceiveStage::DecodeHandoff, inserted_at.elapsed());
+        }
+        Ok(frame)
+    }
+    
+    /// Exécute les actions du moteur de protocole
+    /// 
+    /// Retourne la frame à livrer (`ProtocolAction::Deliver`) s'il y en a une,
+    /// ou l'erreur d'un `ProtocolAction::Failed` (l'appelant choisit alors la
+    /// raison du passage en erreur).
+    async fn apply_actions(&mut self, actions: Vec<ProtocolAction>) -> NetworkResult<Option<CompressedFrame>> {
+        let mut delivered = None;
+        
+        for action in actions {
+            match action {
+                ProtocolAction::Send { packet, target } => {
+                    self.transport.send_packet(&packet, target).await?;
+                    if packet.packet_type == PacketType::Heartbeat {
+                        self.stats.lock().await.last_heartbeat_sent = Some(self.runtime.now());
+                    }
+                }
+                
+                ProtocolAction::Connected { peer_addr, session_id } => {
+                    // Identité annoncée dans le Hello : jugée une fois prouvée (PeerIdentified)
+                    if !self.engine.identity_pending() {
+                        self.enforce_peer_trust(peer_addr).await?;
+                    }
+                    
+                    // Côté appelé, la demande de connexion est enregistrée d'abord
+                    let reason = if matches!(self.connection_state(), ConnectionState::Connecting { .. }) {
+                        "handshake réussi"
+                    } else {
+                        self.set_connection_state(ConnectionState::Connecting {
+                            target_addr: peer_addr,
+                            started_at: self.runtime.now(),
+                            attempt_count: 1,
+                        }, "handshake reçu").await?;
+                        "handshake accepté"
+                    };
+                    self.set_connection_state(ConnectionState::Connected {
+                        peer_addr,
+                        session_id,
+                        connected_at: self.runtime.now(),
+                        last_heartbeat: self.runtime.now(),
+                        route: self.session_route(peer_addr),
+                    }, reason).await?;
+                    
+                    if let Some(announced) = self.engine.peer_local_addr().filter(|announced| *announced != peer_addr) {
+                        println!("Pair {} : adresse locale annoncée {} (traduction d'adresse probable)", peer_addr, announced);
+                    }
+                    
+                    // Démarre le heartbeat
+                    self.start_heartbeat(peer_addr).await?;
+                }
+                
+                ProtocolAction::Resumed { peer_addr, session_id } => {
+                    // Reprise en cours d'appel (nouvelle adresse du pair) : l'appel continue
+                    let connected_at = match self.connection_state() {
+                        ConnectionState::Connected { connected_at, .. } => connected_at,
+                        ConnectionState::Connecting { .. } => self.runtime.now(),
+                        _ => {
+                            self.set_connection_state(ConnectionState::Connecting {
+                                target_addr: peer_addr,
+                                started_at: self.runtime.now(),
+                                attempt_count: 1,
+                            }, "reprise reçue").await?;
+                            self.runtime.now()
+                        }
+                    };
+                    self.set_connection_state(ConnectionState::Connected {
+                        peer_addr,
+                        session_id,
+                        connected_at,
+                        last_heartbeat: self.runtime.now(),
+                        route: self.session_route(peer_addr),
+                    }, "session reprise").await?;
+                    self.stats.lock().await.sessions_resumed += 1;
+                    println!("Session {:016x} reprise avec {}", session_id, peer_addr);
+                    
+                    // Les heartbeats suivent le pair à sa nouvelle adresse
+                    self.stop_heartbeat().await;
+                    self.start_heartbeat(peer_addr).await?;
+                }
+                
+                ProtocolAction::Deliver(frame) => delivered = Some(frame),
+                
+                ProtocolAction::Buffered(frame) => {
+                    // Sans consommateur, le canal plein ne doit pas bloquer la réception
+                    if let Some(ref sender) = self.audio_sender {
+                        let _ = sender.try_send(frame);
+                    }
+                }
+                
+                ProtocolAction::HeartbeatReceived => {
+                    // Met à jour le timestamp du dernier heartbeat
+                    self.update_last_heartbeat().await;
+                    
+                    // Transmet la mesure de RTT au contrôleur de congestion
+                    let avg_rtt_ms = self.transport.stats().avg_rtt_ms;
+                    self.stats.lock().await.record_heartbeat(self.runtime.now(), avg_rtt_ms);
+                    let rtt = (avg_rtt_ms > 0.0).then(|| Duration::from_secs_f32(avg_rtt_ms / 1000.0));
+                    self.congestion.on_heartbeat(rtt, self.runtime.now());
+                    self.pacer.set_rate(self.congestion.pacing_rate_bps());
+                    
+                    // Les pertes en aval, vues par le pair, concernent notre envoi
+                    if let Some(report) = self.engine.remote_stats() {
+                        self.report_remote_losses(&report);
+                    }
+                }
+                
+                ProtocolAction::PeerIdentified { peer_addr, identity } => {
+                    if let Some(identity) = identity {
+                        println!("Pair {} : identité {} prouvée", peer_addr, identity);
+                    }
+                    self.enforce_peer_trust(peer_addr).await?;
+                }
+                
+                ProtocolAction::UnexpectedSource { addr, expected } => {
+                    self.report_packet_error(&NetworkError::UnexpectedSource { addr, expected }).await;
+                }
+                
+                ProtocolAction::PeerClosed { reason } => {
+                    println!("Appel terminé : {}", reason);
+                    self.set_connection_state(ConnectionState::Disconnected, &reason).await?;
+                    self.stop_heartbeat().await;
+                }
+                
+                ProtocolAction::Failed(error) => return Err(error),
+            }
+        }
+        
+        Ok(delivered)
+    }
+    
+    /// Signale au contrôleur de congestion les pertes du rapport du pair
+    /// apparues depuis le rapport précédent
+    /// 
+    /// Le rapport ne repart de zéro qu'avec une nouvelle session, ou quand
+    /// le nombre de paquets reçus par le pair diminue.
+    fn report_remote_losses(&mut self, report: &PeerStatsReport) {
+        let session_id = self.engine.session_id();
+        let already_reported = match self.remote_losses_seen {
+            Some((seen_session, seen)) if seen_session == session_id
+                && report.packets_received >= seen.packets_received => seen.packets_lost,
+            _ => 0,
+        };
+        // Un compte en baisse ne doit pas faire signaler à nouveau les pertes
+        let mut seen = *report;
+        seen.packets_lost = seen.packets_lost.max(already_reported);
+        self.remote_losses_seen = Some((session_id, seen));
+        self.report_losses(report.packets_lost.saturating_sub(already_reported));
+    }
+    
+    /// Transmet des pertes au contrôleur de congestion et recale le pacer
+    fn report_losses(&mut self, lost_packets: u64) {
+        if lost_packets == 0 {
+            return;
+        }
+        self.congestion.on_loss(lost_packets, self.runtime.now());
+        self.pacer.set_rate(self.congestion.pacing_rate_bps());
+    }
+    
+    /// Ajoute un échantillon à l'historique de qualité si une seconde s'est écoulée
+    fn record_quality_sample(&mut self) {
+        // Les stats du transport sont copiées sous verrou : seulement si un échantillon est dû
+        if !self.quality.is_due(self.runtime.now()) {
+            return;
+        }
+        let transport_stats = self.transport.stats();
+        let counters = QualityCounters {
+            received: self.engine.received_packets(),
+            lost: self.engine.lost_packets(),
+            bytes_sent: self.bytes_sent,
+        };
+        let sample = self.quality.record(self.runtime.now(), counters, transport_stats.avg_rtt_ms, transport_stats.avg_jitter_ms);
+        
+        let event = match (&mut self.degradation, sample) {
+            (Some(ladder), Some(sample)) => ladder.on_sample(&sample),
+            _ => None,
+        };
+        if let Some(event) = event {
+            self.apply_degradation(event);
+        }
+    }
+    
+    /// Applique les échelons qui relèvent du transport, puis garde
+    /// l'événement pour l'application (`take_degradation_event`)
+    fn apply_degradation(&mut self, event: DegradationEvent) {
+        match event {
+            DegradationEvent::Degrade(DegradationStep::IncreaseJitterBuffer) => {
+                let current = self.engine.target_delay_ms();
+                let frame_ms = self.engine.frame_duration().as_millis() as u32;
+                self.target_delay_before_degradation = Some(current);
+                self.engine.set_target_delay_ms((current * 2).max(frame_ms));
+            }
+            DegradationEvent::Recover(DegradationStep::IncreaseJitterBuffer) => {
+                if let Some(delay_ms) = self.target_delay_before_degradation.take() {
+                    self.engine.set_target_delay_ms(delay_ms);
+                }
+            }
+            // Redondance : voir `redundancy_copies` ; le reste revient à l'application
+            _ => {}
+        }
+        println!("Dégradation : {:?}", event);
+        self.degradation_events.push_back(event);
+    }
+    
+    /// Remonte l'échelle de dégradation (fin d'appel)
+    fn reset_degradation(&mut self) {
+        if let Some(ladder) = &mut self.degradation {
+            ladder.reset();
+        }
+        if let Some(delay_ms) = self.target_delay_before_degradation.take() {
+            self.engine.set_target_delay_ms(delay_ms);
+        }
+    }
+    
+    /// Envois par paquet audio : la redondance configurée, au moins doublée
+    /// à l'échelon `EnableRedundancy`
+    fn redundancy_copies(&self) -> u8 {
+        let degraded = self.degradation.as_ref()
+            .is_some_and(|ladder| ladder.active_steps().contains(&DegradationStep::EnableRedundancy));
+        let copies = self.config.redundancy.copies();
+        if degraded { copies.max(2) } else { copies }
+    }
+    
+    /// Met à jour le timestamp du dernier heartbeat
+    async fn update_last_heartbeat(&self) {
+        self.connection_state.lock().await.touch_heartbeat();
+    }
+    
+    /// Reporte dans les stats les heartbeats manqués et le dernier envoi du
+    /// thread de heartbeat
+    async fn refresh_heartbeat_stats(&self) {
+        let liveness = self.engine.liveness(self.runtime.now());
+        let sent = self.heartbeat_handle.as_ref().and_then(KeepaliveThread::last_sent);
+        
+        let mut stats = self.stats.lock().await;
+        stats.missed_heartbeats = liveness.missed;
+        stats.last_heartbeat_sent = stats.last_heartbeat_sent.max(sent);
+    }
+    
+    /// Envoie un heartbeat portant notre rapport de réception, au plus une
+    /// fois par `heartbeat_interval`
+    async fn send_stats_heartbeat_if_due(&mut self) -> NetworkResult<()> {
+        let jitter_ms = self.transport.stats().avg_jitter_ms;
+        if let Some(heartbeat) = self.engine.stats_heartbeat(self.runtime.now(), jitter_ms) {
+            self.apply_actions(vec![heartbeat]).await?;
+        }
+        Ok(())
+    }
+    
+    /// Envoie un paquet audio avec pacing, puis ses copies éventuelles (mode redondant)
+    /// 
+    /// Retourne le nombre d'octets envoyés. Une erreur de socket fait passer
+    /// la connexion en erreur.
+    async fn send_audio_packet(&mut self, packet: &NetworkPacket, peer_addr: SocketAddr) -> NetworkResult<usize> {
+        self.flush_control_queue().await?;
+        
+        // Pacing selon le débit autorisé par le contrôleur de congestion
+        let packet_size = packet.estimated_size();
+        let pacing_delay = self.pacer.delay_for(packet_size, self.runtime.now());
+        if !pacing_delay.is_zero() {
+            if self.pacer.is_capped() {
+                self.stats.lock().await.packets_throttled += 1;
+            }
+            self.runtime.sleep(pacing_delay).await;
+        }
+        
+        let mut sent_bytes = 0;
+        for copy in 0..self.redundancy_copies() {
+            if copy > 0 {
+                self.runtime.sleep(RedundancyMode::DUPLICATE_SPACING).await;
+                self.flush_control_queue().await?;
+            }
+            if let Err(e) = self.transport.send_packet(packet, peer_addr).await {
+                if matches!(e, NetworkError::IoError(_)) {
+                    self.fail_connection(&e, "erreur de socket").await;
+                }
+                return Err(e);
+            }
+            self.congestion.on_packet_sent(packet_size, self.runtime.now());
+            self.bytes_sent += packet_size as u64;
+            sent_bytes += packet_size;
+        }
+        
+        self.stats.lock().await.packets_sent += 1;
+        Ok(sent_bytes)
+    }
+    
+    /// Renouvelle l'offre de padding, la demande d'horodatage média et la
+    /// preuve de notre identité au pair si elles sont dues
+    async fn send_control_offers(&mut self) -> NetworkResult<()> {
+        let now = self.runtime.now();
+        let offers = [
+            self.engine.padding_offer(now),
+            self.engine.media_timestamps_request(now),
+            self.engine.identity_proof(now),
+        ];
+        for offer in offers.into_iter().flatten() {
+            if let ProtocolAction::Send { packet, target } = offer {
+                self.transport.send_packet(&packet, target).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Aligne le bourrage du transport sur l'acquittement du pair
+    /// 
+    /// Les paquets audio ne sont complétés qu'une fois l'offre acceptée : un
+    /// pair d'une version précédente ne saurait pas les lire.
+    fn sync_padding(&mut self) {
+        let packet_size = self.config.padding.as_ref()
+            .filter(|_| self.engine.padding_accepted())
+            .map(|padding| padding.packet_size);
+        self.transport.set_padding(packet_size);
+    }
+    
+    /// Envoie les paquets de contrôle mis de côté faute de place dans le socket
+    /// 
+    /// Appelé avant chaque datagramme audio : l'envoi async attend la place
+    /// nécessaire, le contrôle part donc avant l'audio qui suit.
+    async fn flush_control_queue(&mut self) -> NetworkResult<()> {
+        for (packet, target) in self.control_queue.take_all() {
+            self.transport.send_packet(&packet, target).await?;
+            if packet.packet_type == PacketType::Heartbeat {
+                self.stats.lock().await.last_heartbeat_sent = Some(self.runtime.now());
+            }
+        }
+        Ok(())
+    }
+    
+    /// Confiance au premier usage dans l'identité du pair, une fois prouvée
+    /// sur notre défi ou faute de preuve (voir `set_trust_callback`)
+    fn verify_peer_trust(&mut self, addr: SocketAddr) -> NetworkResult<()> {
+        let Some(identity) = self.engine.peer_identity() else {
+            return match self.trust_callback {
+                Some(_) => Err(NetworkError::UntrustedPeer { addr, identity: None }),
+                None => Ok(()),
+            };
+        };
+        match self.known_peers.check(addr, identity) {
+            TrustStatus::Trusted => Ok(()),
+            TrustStatus::Rejected => Err(NetworkError::UntrustedPeer { addr, identity: Some(identity) }),
+            TrustStatus::Changed { expected } => Err(NetworkError::FingerprintChanged { addr, expected, actual: identity }),
+            TrustStatus::Unknown => {
+                let Some(callback) = &self.trust_callback else {
+                    return Ok(());
+                };
+                match callback(&TrustRequest { addr, identity }) {
+                    TrustDecision::Accept => Ok(()),
+                    TrustDecision::Remember => self.known_peers.remember(addr, identity, true),
+                    TrustDecision::Reject => {
+                        self.known_peers.remember(addr, identity, false)?;
+                        Err(NetworkError::UntrustedPeer { addr, identity: Some(identity) })
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Refuse la session si l'identité du pair n'est pas de confiance
+    /// (`verify_peer_trust`) : le pair est prévenu (`AuthFailed`)
+    async fn enforce_peer_trust(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
+        if let Err(e) = self.verify_peer_trust(peer_addr) {
+            println!("Pair {} refusé : {}", peer_addr, e);
+            self.send_protocol_error(ProtocolErrorCode::AuthFailed, peer_addr).await?;
+            self.fail_connection(&e, "identité refusée").await;
+            return Err(e);
+        }
+        Ok(())
+    }
+    
+    /// Envoie une erreur protocolaire au pair (description par défaut du code)
+    async fn send_protocol_error(&mut self, code: ProtocolErrorCode, target: SocketAddr) -> NetworkResult<()> {
+        let packet = self.engine.error_packet(code);
+        self.transport.send_packet(&packet, target).await
+    }
+}
+
+impl crate::traits::sealed::Sealed for UdpNetworkManager {}
+
+#[async_trait]
+impl NetworkManager for UdpNetworkManager {
+    /// Démarre l'écoute en mode serveur
+    async fn start_listening(&mut self, port: u16) -> NetworkResult<()> {
         // Bind le transport
         self.transport.bind(port).await?;
+        self.on_transport_bound();
+        
+        // Redirection de port sur le routeur (UPnP / NAT-PMP) si demandée
+        self.setup_port_mapping(port).await;
+        
+        // Met à jour l'état
+        self.set_connection_state(ConnectionState::Disconnected, "mise en écoute").await?;
+        
+        println!("En écoute sur le port {} - En attente de connexions...", port);
+        
+        // Boucle principale d'écoute - continue indéfiniment
+        loop {
+            // Attend une nouvelle connexion
+            self.accept_incoming().await?;
+            
+            // Maintenant connecté - écoute les paquets jusqu'à déconnexion
+            loop {
+                match self.transport.receive_packet().await {
+                    Ok((packet, source_addr)) => {
+                        // Les Hello d'autres clients sont refusés (un seul appel à la fois)
+                        self.handle_received_packet(packet, source_addr).await?;
+                        
+                        // Si le pair s'est déconnecté, sort de la boucle de connexion
+                        if !self.engine.is_connected() {
+                            println!("Client {} déconnecté", source_addr);
+                            break; // Sort de la boucle de connexion active
+                        }
+                    }
+                    Err(NetworkError::Timeout) => {
+                        // Vérifie si la connexion a timeout
+                        let actions = self.engine.poll(self.runtime.now());
+                        if let Err(e) = self.apply_actions(actions).await {
+                            println!("Timeout de connexion - retour en écoute");
+                            self.fail_connection(&e, "timeout heartbeat").await;
+                            break; // Sort de la boucle de connexion active
+                        }
+                        continue;
+                    }
+                    Err(e) if e.is_packet_level() => self.report_packet_error(&e).await,
+                    Err(e) => {
+                        self.fail_connection(&e, "erreur de socket").await;
+                        return Err(e);
+                    }
+                }
+            }
+            
+            // Connexion terminée - remet l'état à disconnected et continue à écouter
+            self.set_connection_state(ConnectionState::Disconnected, "fin de l'appel").await?;
+            self.engine.close();
+            self.sync_padding();
+            self.reset_degradation();
+            self.stop_heartbeat().await;
+            println!("Prêt pour une nouvelle connexion...");
+        }
+    }
+    
+    /// Se connecte à un peer distant
+    /// 
+    /// Réessaie jusqu'à `max_retry_attempts` fois (voir `connect_with_deadline`).
+    async fn connect_to_peer(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
+        self.connect_with_deadline(peer_addr, None).await
+    }
+    
+    /// Envoie une frame audio au peer connecté
+    async fn send_audio(&mut self, frame: CompressedFrame) -> NetworkResult<SendOutcome> {
+        self.reap_silent_peer();
+        let peer_addr = {
+            let state = self.connection_state.lock().await;
+            match *state.current() {
+                ConnectionState::Connected { peer_addr, .. } => peer_addr,
+                _ => return Err(NetworkError::InvalidState {
+                    operation: "send_audio".to_string(),
+                    current_state: "not connected".to_string(),
+                }),
+            }
+        };
+        
+        // Buffer du pair saturé : la frame est sautée avant d'être numérotée
+        if self.engine.take_flow_control_skip() {
+            self.stats.lock().await.frames_skipped += 1;
+            return Ok(SendOutcome::DroppedDueToBackpressure);
+        }
+        
+        // Crée le paquet avec un nouveau numéro de séquence
+        let packet = self.engine.prepare_audio(frame);
+        self.send_control_offers().await?;
+        
+        // Les frames en attente partent d'abord, dans l'ordre ; au-delà de la
+        // capacité, les plus anciennes sont abandonnées (trop tard pour le pair)
+        self.outgoing_backlog.push_back(packet);
+        while self.outgoing_backlog.len() > Self::OUTGOING_BACKLOG_FRAMES {
+            self.outgoing_backlog.pop_front();
+        }
+        let mut sent_bytes = 0;
+        while let Some(packet) = self.outgoing_backlog.pop_front() {
+            match self.send_audio_packet(&packet, peer_addr).await {
+                Ok(bytes) => sent_bytes += bytes,
+                Err(NetworkError::Timeout) => {
+                    // Envoi bloqué un instant : la frame est gardée pour le prochain appel
+                    self.outgoing_backlog.push_front(packet);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        self.send_stats_heartbeat_if_due().await?;
+        self.record_quality_sample();
+        
+        self.pacer.set_rate(self.congestion.pacing_rate_bps());
+        self.stats.lock().await.congestion = self.congestion.state();
+        self.refresh_heartbeat_stats().await;
+        
+        // La frame est la dernière de la file : partie seulement si la file est vide
+        if self.outgoing_backlog.is_empty() {
+            Ok(SendOutcome::Sent { bytes: sent_bytes })
+        } else {
+            Ok(SendOutcome::Queued { queue_len: self.outgoing_backlog.len() })
+        }
+    }
+    
+    /// Reçoit une frame audio du peer distant
+    async fn receive_audio(&mut self) -> NetworkResult<CompressedFrame> {
+        self.reap_silent_peer();
+        
+        // Vérifie qu'on est connecté
+        {
+            let state = self.connection_state.lock().await;
+            if !state.current().is_connected() {
+                return Err(NetworkError::InvalidState {
+                    operation: "receive_audio".to_string(),
+                    current_state: "not connected".to_string(),
+                });
+            }
+        }
+        
+        // Essaie d'abord le buffer local
+        if let Some(frame) = self.engine.pop_buffered() {
+            return Ok(frame);
+        }
+        
+        // Sinon, reçoit du réseau (en envoyant au passage l'audio capturé en synchrone)
+        loop {
+            self.send_queued_audio().await?;
+            match self.transport.receive_packet().await {
+                Ok((packet, source)) => {
+                    // Le moteur ignore les paquets d'autres sources et les doublons
+                    // (ex: copie redondante)
+                    if let Some(frame) = self.handle_received_packet(packet, source).await? {
+                        self.record_quality_sample();
+                        self.stats.lock().await.packets_received += 1;
+                        return Ok(frame);
+                    }
+                    
+                    // Déconnexion ou erreur du pair pendant l'attente
+                    if !self.engine.is_connected() {
+                        return Err(NetworkError::PeerDisconnected { addr: source });
+                    }
+                }
+                Err(NetworkError::Timeout) => {
+                    // Vérifie si la connexion a timeout
+                    let actions = self.engine.poll(self.runtime.now());
+                    if let Err(error) = self.apply_actions(actions).await {
+                        self.fail_connection(&error, "timeout heartbeat").await;
+                        return Err(error);
+                    }
+                    self.refresh_heartbeat_stats().await;
+                    continue;
+                }
+                Err(e @ NetworkError::IoError(_)) => {
+                    self.fail_connection(&e, "erreur de socket").await;
+                    return Err(e);
+                }
+                Err(e) if e.is_packet_level() => self.report_packet_error(&e).await,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    
+    /// Déconnecte proprement du peer
+    async fn disconnect(&mut self) -> NetworkResult<()> {
+        // Envoie un paquet de déconnexion au pair éventuel
+        if let Some(ProtocolAction::Send { packet, target }) = self.engine.disconnect() {
+            let _ = self.transport.send_packet(&packet, target).await;
+        }
+        
+        self.sync_padding();
+        
+        // Arrête le heartbeat
+        self.stop_heartbeat().await;
         
         // Met à jour l'état
-        self.set_connection_state(ConnectionState::Disconnected).await;
+        self.set_connection_state(ConnectionState::Disconnected, "déconnexion locale").await?;
+        self.quality.clear();
+        self.reset_degradation();
+        for summary in self.error_log.flush() {
+            println!("{}", summary);
+        }
+        
+        println!("Déconnexion terminée");
+        Ok(())
+    }
+    
+    /// Retourne l'état de connexion actuel
+    fn connection_state(&self) -> ConnectionState {
+        // Version synchrone pour éviter de bloquer
+        match self.connection_state.try_lock() {
+            Ok(state) => state.current().clone(),
+            Err(_) => ConnectionState::Disconnected,
+        }
+    }
+    
+    /// Retourne les statistiques réseau combinées
+    fn network_stats(&self) -> NetworkStats {
+        let mut stats = match self.stats.try_lock() {
+            Ok(stats) => stats.clone(),
+            Err(_) => return NetworkStats::default(),
+        };
+        if let Ok(state) = self.connection_state.try_lock() {
+            state.record_uptime(&mut stats, self.runtime.now());
+        }
+        stats.merge_traffic(&self.transport.stats());
+        stats
+    }
+    
+    /// Retourne le dernier rapport de réception du pair
+    fn remote_stats(&self) -> Option<PeerStatsReport> {
+        self.engine.remote_stats()
+    }
+    
+    /// Retourne l'état du buffer anti-jitter
+    fn buffer_stats(&self) -> BufferStats {
+        self.engine.buffer_stats(self.transport.stats().avg_jitter_ms)
+    }
+    
+    /// Retourne l'adresse du pair (connecté, en cours de connexion ou en erreur)
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.last_peer_addr
+    }
+    
+    /// Retourne l'ID de la session courante
+    fn session_id(&self) -> u64 {
+        self.engine.session_id()
+    }
+    
+    /// Retourne l'adresse locale du transport
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.transport.local_addr()
+    }
+    
+    /// Retourne la durée de la connexion en cours
+    fn connected_duration(&self) -> Option<Duration> {
+        match self.connection_state.try_lock() {
+            Ok(state) => match *state.current() {
+                ConnectionState::Connected { connected_at, .. } => Some(self.runtime.now().saturating_duration_since(connected_at)),
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
+    
+    /// Force une reconnexion si possible
+    /// 
+    /// Après `retry_delay`, jusqu'à `max_retry_attempts` tentatives comme
+    /// `connect_to_peer` ; refusé si la dernière erreur est définitive
+    /// (`can_retry: false`). Après une coupure, la session est reprise sans
+    /// nouveau handshake si le pair l'a gardée (`NetworkConfig::resume_grace`).
+    async fn reconnect(&mut self) -> NetworkResult<()> {
+        // Récupère l'adresse du peer précédent (conservée en cas d'erreur)
+        let current = self.connection_state();
+        let Some(addr) = self.last_peer_addr else {
+            return Err(NetworkError::InvalidState {
+                operation: "reconnect".to_string(),
+                current_state: "no previous peer".to_string(),
+            });
+        };
+        if let ConnectionState::Error { can_retry: false, .. } = current {
+            return Err(NetworkError::InvalidState {
+                operation: "reconnect".to_string(),
+                current_state: current.description(),
+            });
+        }
+        
+        // Déconnecte proprement d'abord
+        if current.is_connected() {
+            self.disconnect().await?;
+        }
+        
+        // Attend un peu avant de reconnecter
+        self.runtime.sleep(self.config.retry_delay).await;
+        
+        // La reconnexion est comptée par la machine à états
+        self.connect_with_deadline(addr, None).await
+    }
+}
+
+#[cfg(all(test, feature = "udp", feature = "simulator"))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tokio::time::{sleep, timeout};
+    use crate::{HandshakeMessage, PresenceConfig};
+    
+    #[tokio::test]
+    async fn test_manager_creation() {
+        let config = NetworkConfig::test_config();
+        let manager = UdpNetworkManager::new_simulated(config).unwrap();
+        
+        assert!(!manager.connection_state().is_connected());
+        assert_eq!(manager.network_stats().packets_sent, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_congestion_controller_replacement() {
+        let config = NetworkConfig::test_config();
+        let mut manager = UdpNetworkManager::new_simulated(config).unwrap();
+        
+        assert_eq!(
+            manager.congestion_state().pacing_rate_bps,
+            DelayBasedController::DEFAULT_START_RATE_BPS
+        );
+        
+        manager.set_congestion_controller(Box::new(
+            DelayBasedController::with_bounds(32_000, 16_000, 64_000)
+        ));
+        assert_eq!(manager.congestion_state().pacing_rate_bps, 32_000);
+        assert_eq!(manager.pacer.rate_bps(), 32_000);
+    }
+    
+    /// Contrôleur qui compte les pertes signalées par le manager
+    struct LossRecorder(Arc<std::sync::atomic::AtomicU64>);
+    
+    impl CongestionController for LossRecorder {
+        fn on_packet_sent(&mut self, _bytes: usize, _now: Instant) {}
+        
+        fn on_heartbeat(&mut self, _rtt: Option<Duration>, _now: Instant) {}
+        
+        fn on_loss(&mut self, lost_packets: u64, _now: Instant) {
+            self.0.fetch_add(lost_packets, std::sync::atomic::Ordering::Relaxed);
+        }
+        
+        fn pacing_rate_bps(&self) -> u32 {
+            DelayBasedController::DEFAULT_START_RATE_BPS
+        }
+        
+        fn state(&self) -> CongestionState {
+            CongestionState::default()
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_receive_losses_leave_pacing_alone() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        let reported = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        callee.set_congestion_controller(Box::new(LossRecorder(reported.clone())));
+        
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        // La première frame n'est jamais émise : l'appelé la déclare perdue
+        // une fois la fenêtre de retard dépassée par les suivantes
+        caller.engine.prepare_audio(CompressedFrame::new(vec![0], 960, Instant::now(), 0));
+        for _ in 0..20 {
+            caller.send_audio(CompressedFrame::new(vec![1, 2, 3], 960, Instant::now(), 0)).await.unwrap();
+        }
+        while timeout(Duration::from_millis(50), callee.receive_audio()).await.is_ok() {}
+        
+        // Pertes sur le chemin pair → nous : notre débit d'envoi n'y est pour rien
+        assert_eq!(callee.engine.lost_packets(), 1);
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 0);
+        assert_eq!(callee.pacer.rate_bps(), DelayBasedController::DEFAULT_START_RATE_BPS);
+    }
+    
+    #[tokio::test]
+    async fn test_remote_losses_reach_congestion_controller() {
+        let config = NetworkConfig::test_config();
+        let mut manager = UdpNetworkManager::new_simulated(config).unwrap();
+        let reported = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        manager.set_congestion_controller(Box::new(LossRecorder(reported.clone())));
+        let report = |packets_received, packets_lost| PeerStatsReport {
+            packets_received, packets_lost, loss_percent: 5.0, jitter_ms: 0.0,
+        };
+        
+        // Seules les nouvelles pertes sont signalées, un rapport répété n'ajoute rien
+        manager.report_remote_losses(&report(95, 5));
+        manager.report_remote_losses(&report(95, 5));
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 5);
+        manager.report_remote_losses(&report(190, 10));
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 10);
+        
+        // Un compte qui baisse dans la même session n'est pas une remise à zéro
+        manager.report_remote_losses(&report(200, 9));
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 10);
+        manager.report_remote_losses(&report(210, 11));
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 11);
+        
+        // Nouvel appel : le rapport du pair repart de zéro
+        manager.report_remote_losses(&report(19, 1));
+        assert_eq!(reported.load(std::sync::atomic::Ordering::Relaxed), 12);
+    }
+    
+    #[tokio::test]
+    async fn test_degradation_steps_applied_by_manager() {
+        let mut config = NetworkConfig::test_config();
+        config.degradation = Some(crate::DegradationConfig { degrade_after: 1, ..Default::default() });
+        let mut manager = UdpNetworkManager::new_simulated(config).unwrap();
+        let target_delay = manager.engine.target_delay_ms();
+        let lossy = QualitySample { at: Instant::now(), rtt_ms: 20.0, loss_percent: 30.0, jitter_ms: 2.0, bitrate_bps: 32_000 };
+        
+        // Jusqu'à l'échelon du buffer : redondance puis attente doublée
+        for _ in 0..4 {
+            let event = manager.degradation.as_mut().unwrap().on_sample(&lossy).unwrap();
+            manager.apply_degradation(event);
+        }
+        assert_eq!(manager.redundancy_copies(), 2);
+        assert!(manager.engine.target_delay_ms() > target_delay);
+        assert_eq!(manager.take_degradation_event(), Some(DegradationEvent::Degrade(DegradationStep::ReduceBitrate)));
+        assert_eq!(manager.degradation_steps().len(), 4);
+        
+        // Fin d'appel : tout est rétabli
+        manager.reset_degradation();
+        assert_eq!(manager.redundancy_copies(), 1);
+        assert_eq!(manager.engine.target_delay_ms(), target_delay);
+        assert!(manager.degradation_steps().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_bandwidth_cap_limits_pacer() {
+        let mut config = NetworkConfig::test_config();
+        config.max_bandwidth_bps = Some(24_000);
+        let mut manager = UdpNetworkManager::new_simulated(config).unwrap();
+        
+        // Le contrôleur garde son débit, le pacer applique le plafond
+        manager.set_congestion_controller(Box::new(
+            DelayBasedController::with_bounds(32_000, 16_000, 64_000)
+        ));
+        assert_eq!(manager.congestion_state().pacing_rate_bps, 32_000);
+        assert_eq!(manager.pacer.rate_bps(), 24_000);
+        assert!(manager.pacer.is_capped());
+    }
+    
+    #[tokio::test]
+    async fn test_stats_interval_snapshot() {
+        let config = NetworkConfig::test_config();
+        let mut manager = UdpNetworkManager::new_simulated(config).unwrap();
+        
+        manager.stats.lock().await.packets_sent = 10;
+        let first = manager.stats_interval_snapshot().await;
+        assert_eq!(first.delta.packets_sent, 10);
+        
+        manager.stats.lock().await.packets_sent = 15;
+        let second = manager.stats_interval_snapshot().await;
+        assert_eq!(second.delta.packets_sent, 5);
+        
+        // Après un reset, l'intervalle suivant repart de zéro
+        manager.reset_stats().await;
+        assert_eq!(manager.network_stats().packets_sent, 0);
+        let third = manager.stats_interval_snapshot().await;
+        assert_eq!(third.delta.packets_sent, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_traffic_counters_and_bitrates() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        let (dialed, accepted) = tokio::join!(caller.connect_to_peer(utils::localhost(9002)), callee.open(9002, None));
+        dialed.unwrap();
+        accepted.unwrap();
+        let before = caller.stats_interval_snapshot().await;
+        assert!(before.delta.bytes_sent > 0); // Handshake
+        
+        for sequence in 0..5 {
+            caller.send_audio(CompressedFrame::new(vec![7; 100], 960, Instant::now(), sequence)).await.unwrap();
+        }
+        for _ in 0..5 {
+            timeout(Duration::from_millis(500), callee.receive_audio()).await.unwrap().unwrap();
+        }
+        
+        // En-tête compris : plus que les 500 octets de payload
+        let sent = caller.stats_interval_snapshot().await;
+        assert!(sent.delta.bytes_sent > 500);
+        assert!(sent.delta.send_bitrate_kbps > 0.0);
+        let received = callee.network_stats();
+        assert!(received.bytes_received > 500);
+        assert!(received.recv_bitrate_kbps > 0.0);
+        
+        caller.disconnect().await.unwrap();
+        caller.reset_stats().await;
+        let reset = caller.network_stats();
+        assert_eq!((reset.bytes_sent, reset.send_bitrate_kbps), (0, 0.0));
+    }
+    
+    #[tokio::test]
+    async fn test_discovery_and_connect_with_code() {
+        let port = utils::find_free_udp_port(40000..=40100).unwrap();
+        
+        let mut listener = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let listener_task = tokio::spawn(async move {
+            let _ = listener.start_listening(port).await;
+        });
+        sleep(Duration::from_millis(100)).await;
+        
+        // La sonde unicast vers le listener doit obtenir une réponse
+        let mut client = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let peers = client
+            .discover_peers(utils::localhost(port), Duration::from_millis(300))
+            .await
+            .unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].addr.port(), port);
+        
+        // Connexion via le code (réutilise le socket déjà bindé)
+        let code = utils::encode_connection_code(utils::localhost(port));
+        client.connect_with_code(&code).await.unwrap();
+        assert!(client.connection_state().is_connected());
+        
+        listener_task.abort();
+    }
+    
+    #[tokio::test]
+    async fn test_listener_version_negotiation() {
+        let port = utils::find_free_udp_port(40201..=40300).unwrap();
+        
+        let mut listener = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let listener_task = tokio::spawn(async move {
+            let _ = listener.start_listening(port).await;
+        });
+        sleep(Duration::from_millis(100)).await;
+        
+        let config = NetworkConfig::test_config();
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut buffer = [0u8; 2048];
+        
+        // Version future : refus structuré VersionMismatch
+        let mut future = Vec::new();
+        NetworkPacket::new_heartbeat(5, 6).encode_into(&mut future);
+        future[2] = NetworkPacket::CURRENT_PROTOCOL_VERSION + 1;
+        socket.send_to(&future, utils::localhost(port)).await.unwrap();
+        
+        let (len, source) = socket.recv_from(&mut buffer).await.unwrap();
+        let reply = crate::transport::decode_packet(&buffer[..len], source, &config, None).unwrap();
+        assert_eq!(reply.error_message().unwrap().code, ProtocolErrorCode::VersionMismatch);
+        
+        // Pair v1 : le handshake est lu et la réponse est envoyée au format v1
+        #[cfg(feature = "legacy-protocol")]
+        {
+            let mut handshake = NetworkPacket::new_heartbeat(5, 6);
+            handshake.packet_type = PacketType::Handshake;
+            handshake.protocol_version = 1;
+            handshake.checksum = handshake.calculate_checksum();
+            let mut encoded = Vec::new();
+            crate::legacy::encode_legacy(&handshake, &mut encoded).unwrap();
+            socket.send_to(&encoded, utils::localhost(port)).await.unwrap();
+            
+            let (len, source) = socket.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(NetworkPacket::peek_version(&buffer[..len]), Some(1));
+            let reply = crate::transport::decode_packet(&buffer[..len], source, &config, None).unwrap();
+            assert_eq!(reply.packet_type, PacketType::Handshake);
+        }
+        
+        listener_task.abort();
+    }
+    
+    #[tokio::test]
+    async fn test_simultaneous_connect_converges() {
+        let port_a = utils::find_free_udp_port(40301..=40400).unwrap();
+        let mut a = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        a.transport.bind(port_a).await.unwrap();
+        let port_b = utils::find_free_udp_port(40401..=40500).unwrap();
+        let mut b = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        b.transport.bind(port_b).await.unwrap();
+        
+        // Les deux pairs s'appellent en même temps : les Hello se croisent
+        let (result_a, result_b) = tokio::join!(
+            a.connect_to_peer(utils::localhost(port_b)),
+            b.connect_to_peer(utils::localhost(port_a)),
+        );
+        result_a.unwrap();
+        result_b.unwrap();
+        
+        // Une seule session : celle du pair de plus petit sender_id
+        let winner_session = if a.engine.sender_id() < b.engine.sender_id() { a.engine.session_id() } else { b.engine.session_id() };
+        assert_eq!(a.engine.session_id(), b.engine.session_id());
+        assert_eq!(a.engine.session_id(), winner_session);
+    }
+    
+    #[tokio::test]
+    async fn test_open_candidates_selects_answering_path() {
+        let port_a = utils::find_free_udp_port(41121..=41140).unwrap();
+        let port_b = utils::find_free_udp_port(41141..=41160).unwrap();
+        let silent = utils::localhost(utils::find_free_udp_port(41161..=41200).unwrap());
+        let mut a = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut b = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        
+        // Le candidat muet est sondé en même temps que le bon, sans le retarder
+        let candidates_of_a = [silent, utils::localhost(port_b)];
+        let candidates_of_b = [utils::localhost(port_a)];
+        let (peer_of_a, peer_of_b) = tokio::join!(
+            a.open_candidates(port_a, &candidates_of_a),
+            b.open_candidates(port_b, &candidates_of_b),
+        );
+        assert_eq!(peer_of_a.unwrap(), utils::localhost(port_b));
+        assert_eq!(peer_of_b.unwrap(), utils::localhost(port_a));
+        assert_eq!(a.engine.session_id(), b.engine.session_id());
+        
+        assert!(matches!(a.open_candidates(port_a, &[]).await, Err(NetworkError::InvalidAddress { .. })));
+        a.shutdown().await.unwrap();
+        b.shutdown().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_ping_before_connecting() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        
+        // Personne n'écoute encore : la sonde reste sans réponse
+        let unanswered = caller.ping(utils::localhost(9002), 1).await.unwrap();
+        assert!(!unanswered.is_reachable());
+        assert_eq!(unanswered.loss_percent(), 100.0);
+        
+        // Le pair en écoute répond sans handshake, puis l'appel se fait normalement
+        let (report, accepted) = tokio::join!(
+            async {
+                let report = caller.ping(utils::localhost(9002), 3).await.unwrap();
+                caller.connect_to_peer(utils::localhost(9002)).await.unwrap();
+                report
+            },
+            callee.open(9002, None),
+        );
+        accepted.unwrap();
+        assert_eq!((report.sent, report.received()), (3, 3));
+        assert_eq!(report.sender_id, Some(callee.engine.sender_id()));
+        assert!(report.max_rtt().unwrap() < UdpNetworkManager::PING_TIMEOUT);
+        
+        // Pendant un appel, le ping volerait les paquets du pair
+        assert!(matches!(
+            caller.ping(utils::localhost(9002), 1).await,
+            Err(NetworkError::InvalidState { .. })
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_jitter_probe_tunes_target_delay() {
+        let mut config = NetworkConfig::test_config();
+        config.target_delay_ms = 160;
+        config.jitter_probe = Some(UdpNetworkManager::JITTER_PROBE_INTERVAL * 10);
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        
+        let (report, accepted) = tokio::join!(
+            async {
+                let report = caller.measure_jitter(utils::localhost(9002), UdpNetworkManager::JITTER_PROBE_INTERVAL * 5).await.unwrap();
+                caller.connect_to_peer(utils::localhost(9002)).await.unwrap();
+                report
+            },
+            callee.open(9002, None),
+        );
+        accepted.unwrap();
+        assert_eq!((report.sent(), report.received()), (5, 5));
+        assert!(report.p50().is_some());
+        
+        // Lien local sans gigue : l'attente de 160ms est réduite
+        assert!(caller.buffer_stats().target_delay_ms < 160.0);
+        assert!(matches!(
+            caller.measure_jitter(utils::localhost(9002), Duration::from_millis(20)).await,
+            Err(NetworkError::InvalidState { .. })
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_open_symmetric() {
+        let port_a = utils::find_free_udp_port(40501..=40600).unwrap();
+        let port_b = utils::find_free_udp_port(40601..=40700).unwrap();
+        let mut a = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut b = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+
+        // Même code des deux côtés, chacun avec l'adresse de l'autre
+        let (peer_a, peer_b) = tokio::join!(
+            a.open(port_a, Some(utils::localhost(port_b))),
+            b.open(port_b, Some(utils::localhost(port_a))),
+        );
+        assert_eq!(peer_a.unwrap(), utils::localhost(port_b));
+        assert_eq!(peer_b.unwrap(), utils::localhost(port_a));
+        assert_eq!(a.engine.session_id(), b.engine.session_id());
+        assert!(a.connection_state().is_connected());
+
+        // Sans adresse, open attend le premier pair qui appelle
+        a.disconnect().await.unwrap();
+        let mut c = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let (accepted, dialed) = tokio::join!(
+            a.open(port_a, None),
+            c.connect_to_peer(utils::localhost(port_a)),
+        );
+        dialed.unwrap();
+        assert_eq!(Some(accepted.unwrap()), c.transport.local_addr().map(|addr| utils::localhost(addr.port())));
+    }
+
+    #[tokio::test]
+    async fn test_busy_server_refuses_second_client() {
+        let port = utils::find_free_udp_port(40101..=40200).unwrap();
+        
+        let mut listener = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let listener_task = tokio::spawn(async move {
+            let _ = listener.start_listening(port).await;
+        });
+        sleep(Duration::from_millis(100)).await;
+        
+        let mut first = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        first.connect_to_peer(utils::localhost(port)).await.unwrap();
+        
+        // Le second client reçoit un refus explicite au lieu d'un timeout
+        let mut second = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        match second.connect_to_peer(utils::localhost(port)).await {
+            Err(NetworkError::RemoteError { code, .. }) => assert_eq!(code, ProtocolErrorCode::ServerFull),
+            other => panic!("Refus ServerFull attendu, obtenu {:?}", other),
+        }
+        
+        listener_task.abort();
+    }
+    
+    #[tokio::test]
+    async fn test_remote_stats_from_heartbeats() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
         
-        println!("En écoute sur le port {} - En attente de connexions...", port);
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        assert!(caller.remote_stats().is_none());
         
-        // Boucle principale d'écoute - continue indéfiniment
-        loop {
-            // Attend une nouvelle connexion
-            loop {
-                match self.transport.receive_packet().await {
-                    Ok((packet, source_addr)) => {
-                        if packet.packet_type == PacketType::Handshake {
-                            // Tentative de connexion détectée
-                            self.set_connection_state(ConnectionState::Connecting {
-                                target_addr: source_addr,
-                                started_at: Instant::now(),
-                                attempt_count: 1,
-                            }).await;
-                            
-                            // Traite le handshake
-                            self.handle_received_packet(packet, source_addr).await?;
-                            
-                            // Connexion établie
-                            self.set_connection_state(ConnectionState::Connected {
-                                peer_addr: source_addr,
-                                session_id: self.session_id,
-                                connected_at: Instant::now(),
-                                last_heartbeat: Instant::now(),
-                            }).await;
-                            
-                            // Démarre le heartbeat
-                            self.start_heartbeat(source_addr).await?;
-                            
-                            println!("Connexion établie avec {}", source_addr);
-                            break; // Sort de la boucle d'attente de connexion
-                        }
-                    }
-                    Err(NetworkError::Timeout) => continue, // Continue à attendre
-                    Err(e) => return Err(e),
-                }
-            }
-            
-            // Maintenant connecté - écoute les paquets jusqu'à déconnexion
-            loop {
-                match self.transport.receive_packet().await {
-                    Ok((packet, source_addr)) => {
-                        // Vérifie que c'est du bon peer
-                        let current_peer = {
-                            let state = self.connection_state.lock().await;
-                            state.peer_addr()
-                        };
-                        
-                        if Some(source_addr) == current_peer {
-                            // Vérifie le type avant de traiter le paquet
-                            let is_disconnect = packet.packet_type == PacketType::Disconnect;
-                            
-                            self.handle_received_packet(packet, source_addr).await?;
-                            
-                            // Si c'est un disconnect, sort de la boucle de connexion
-                            if is_disconnect {
-                                println!("Client {} déconnecté", source_addr);
-                                break; // Sort de la boucle de connexion active
-                            }
-                        }
-                    }
-                    Err(NetworkError::Timeout) => {
-                        // Vérifie si la connexion a timeout
-                        if self.check_heartbeat_timeout().await {
-                            println!("Timeout de connexion - retour en écoute");
-                            self.set_connection_state(ConnectionState::Disconnected).await;
-                            break; // Sort de la boucle de connexion active
-                        }
-                        continue;
-                    }
-                    Err(e) => return Err(e),
-                }
-            }
-            
-            // Connexion terminée - remet l'état à disconnected et continue à écouter
-            self.set_connection_state(ConnectionState::Disconnected).await;
-            self.stop_heartbeat().await;
-            println!("Prêt pour une nouvelle connexion...");
+        // L'appelé reçoit l'audio et renvoie son rapport par heartbeat
+        for _ in 0..5 {
+            let frame = CompressedFrame::new(vec![1, 2, 3], 960, Instant::now(), 0);
+            caller.send_audio(frame).await.unwrap();
+            callee.receive_audio().await.unwrap();
         }
+        
+        // L'appelant traite le heartbeat en lisant ses paquets entrants
+        let _ = timeout(Duration::from_millis(100), caller.receive_audio()).await;
+        let report = caller.remote_stats().expect("rapport du pair attendu");
+        assert!(report.packets_received >= 1);
+        assert_eq!(report.loss_percent, 0.0);
+        
+        caller.disconnect().await.unwrap();
+        assert!(caller.remote_stats().is_none());
     }
     
-    /// Se connecte à un peer distant
-    async fn connect_to_peer(&mut self, peer_addr: SocketAddr) -> NetworkResult<()> {
-        // Bind sur un port local aléatoire
-        let local_port = fastrand::u16(10000..=60000);
-        self.transport.bind(local_port).await?;
+    #[tokio::test]
+    async fn test_liveness_and_heartbeat_stats() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config.clone(), Box::new(callee_transport)).unwrap();
+        assert!(!caller.liveness().healthy);
         
-        // Met à jour l'état
-        self.set_connection_state(ConnectionState::Connecting {
-            target_addr: peer_addr,
-            started_at: Instant::now(),
-            attempt_count: 1,
-        }).await;
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        assert!(callee.liveness().healthy);
+        
+        // Les heartbeats du thread dédié arrivent pendant l'attente
+        let _ = timeout(config.heartbeat_interval * 3, callee.receive_audio()).await;
+        let stats = callee.network_stats();
+        assert!(stats.last_heartbeat_sent.is_some());
+        assert!(stats.last_heartbeat_received.is_some());
+        assert!(!stats.heartbeat_rtt_ms.is_empty());
+        assert_eq!(stats.missed_heartbeats, 0);
         
-        // Effectue le handshake
-        self.perform_handshake(peer_addr).await?;
+        // Pair muet : la santé se dégrade avant l'expiration
+        caller.stop_heartbeat().await;
+        tokio::time::sleep(config.heartbeat_interval * 3).await;
+        let liveness = callee.liveness();
+        assert!(!liveness.healthy && liveness.missed >= 1);
+        assert!(callee.connection_state().is_connected());
+    }
+    
+    #[tokio::test]
+    async fn test_heartbeats_survive_blocked_runtime() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config.clone(), Box::new(callee_transport)).unwrap();
         
-        // Connexion réussie
-        self.set_connection_state(ConnectionState::Connected {
-            peer_addr,
-            session_id: self.session_id,
-            connected_at: Instant::now(),
-            last_heartbeat: Instant::now(),
-        }).await;
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
         
-        // Démarre le heartbeat
-        self.start_heartbeat(peer_addr).await?;
+        // Bloque le runtime (mono-thread) plus longtemps que heartbeat_timeout
+        std::thread::sleep(config.heartbeat_timeout + Duration::from_millis(300));
         
-        println!("Connecté à {}", peer_addr);
-        Ok(())
+        // L'appelé a reçu les heartbeats du thread dédié : pas de timeout
+        let waited = timeout(config.connection_timeout + Duration::from_millis(500), callee.receive_audio()).await;
+        assert!(waited.is_err(), "aucune frame attendue : {:?}", waited);
+        assert!(callee.connection_state().is_connected());
+        
+        caller.disconnect().await.unwrap();
+        assert!(caller.heartbeat_handle.is_none());
     }
     
-    /// Envoie une frame audio au peer connecté
-    async fn send_audio(&mut self, frame: CompressedFrame) -> NetworkResult<()> {
-        let peer_addr = {
-            let state = self.connection_state.lock().await;
-            match *state {
-                ConnectionState::Connected { peer_addr, .. } => peer_addr,
-                _ => return Err(NetworkError::InvalidState {
-                    operation: "send_audio".to_string(),
-                    current_state: "not connected".to_string(),
-                }),
-            }
-        };
+    #[tokio::test]
+    async fn test_idle_client_detects_silent_peer() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config.clone(), Box::new(callee_transport)).unwrap();
         
-        // Crée le paquet avec un nouveau numéro de séquence
-        self.sequence_counter += 1;
-        let mut frame_with_sequence = frame;
-        frame_with_sequence.sequence_number = self.sequence_counter;
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        // Aucun des deux ne lit le réseau : les heartbeats suffisent
+        tokio::time::sleep(config.heartbeat_timeout + Duration::from_millis(300)).await;
+        assert!(caller.connection_state().is_connected());
+        assert_eq!(caller.take_network_event(), None);
         
-        let packet = NetworkPacket::new_audio(
-            frame_with_sequence,
-            self.sender_id,
-            self.session_id,
+        // Le pair disparaît sans prévenir : constaté sans appel à receive_audio
+        callee.stop_heartbeat().await;
+        tokio::time::sleep(config.heartbeat_timeout + Duration::from_millis(300)).await;
+        assert!(matches!(caller.connection_state(), ConnectionState::Error { can_retry: true, .. }));
+        assert_eq!(caller.take_network_event(), Some(NetworkEvent::PeerTimedOut(utils::localhost(9002))));
+        assert!(caller.heartbeat_handle.is_none() && caller.liveness_watchdog.is_none());
+        assert!(caller.receive_audio().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_session_metadata_getters() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        assert_eq!((caller.peer_addr(), caller.local_addr(), caller.connected_duration()), (None, None, None));
+        
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
         );
+        dialed.unwrap();
+        accepted.unwrap();
         
-        // Envoie le paquet
-        self.transport.send_packet(&packet, peer_addr).await?;
+        assert_eq!(caller.peer_addr(), Some(utils::localhost(9002)));
+        assert_eq!(callee.peer_addr(), Some(utils::localhost(9001)));
+        assert_eq!(caller.local_addr(), Some(utils::localhost(9001)));
+        assert_eq!(caller.session_id(), callee.session_id());
+        assert!(caller.connected_duration().is_some());
         
-        // Met à jour les statistiques
-        let mut stats = self.stats.lock().await;
-        stats.packets_sent += 1;
+        caller.disconnect().await.unwrap();
+        assert_eq!((caller.peer_addr(), caller.connected_duration()), (None, None));
         
-        Ok(())
+        // Les transitions sont journalisées avec leur raison
+        let reasons: Vec<String> = caller.state_history().await
+            .into_iter()
+            .map(|transition| transition.reason)
+            .collect();
+        assert_eq!(reasons, vec!["appel du pair", "handshake réussi", "déconnexion locale"]);
+        assert_eq!(callee.state_history().await.len(), 2);
     }
     
-    /// Reçoit une frame audio du peer distant
-    async fn receive_audio(&mut self) -> NetworkResult<CompressedFrame> {
-        // Vérifie qu'on est connecté
-        {
-            let state = self.connection_state.lock().await;
-            if !state.is_connected() {
-                return Err(NetworkError::InvalidState {
-                    operation: "receive_audio".to_string(),
-                    current_state: "not connected".to_string(),
-                });
-            }
-        }
+    #[tokio::test]
+    async fn test_sender_id_collision_renumbers() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        assert_ne!(caller.engine.session_id(), 0);
         
-        // Essaie d'abord le buffer local
-        if let Some(packet) = self.receive_buffer.pop_packet() {
-            return Ok(packet.compressed_frame);
-        }
+        // Même ID des deux côtés : l'appelé en change en acceptant
+        callee.engine.set_sender_id(caller.engine.sender_id());
+        let original = caller.engine.sender_id();
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
         
-        // Sinon, reçoit du réseau
-        loop {
-            match self.transport.receive_packet().await {
-                Ok((packet, source)) => {
-                    // Vérifie que c'est du bon peer
-                    let expected_peer = {
-                        let state = self.connection_state.lock().await;
-                        state.peer_addr()
-                    };
-                    
-                    if Some(source) != expected_peer {
-                        continue; // Paquet d'un autre peer, ignore
-                    }
-                    
-                    // Traite le paquet
-                    self.handle_received_packet(packet.clone(), source).await?;
-                    
-                    // Si c'est de l'audio, le retourne
-                    if packet.packet_type == PacketType::Audio {
-                        let mut stats = self.stats.lock().await;
-                        stats.packets_received += 1;
-                        return Ok(packet.compressed_frame);
-                    }
-                    
-                    // Sinon continue à écouter
-                }
-                Err(NetworkError::Timeout) => {
-                    // Vérifie si la connexion a timeout
-                    if self.check_heartbeat_timeout().await {
-                        let addr = self.connection_state.lock().await.peer_addr()
-                            .unwrap_or_else(|| "0.0.0.0:0".parse().unwrap());
-                        return Err(NetworkError::PeerDisconnected { addr });
-                    }
-                    continue;
-                }
-                Err(e) => return Err(e),
+        assert_eq!(caller.engine.sender_id(), original);
+        assert_ne!(callee.engine.sender_id(), original);
+        assert_eq!(caller.engine.session_id(), callee.engine.session_id());
+    }
+    
+    #[tokio::test]
+    async fn test_handshake_failure_sets_error_state() {
+        let mut config = NetworkConfig::test_config();
+        config.connection_timeout = Duration::from_millis(150);
+        config.retry_delay = Duration::from_millis(10);
+        // Le pair simulé ne répond jamais
+        let (caller_transport, _silent_peer) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config, Box::new(caller_transport)).unwrap();
+        caller.transport.bind(9001).await.unwrap();
+        
+        let peer = utils::localhost(9002);
+        assert!(caller.connect_to_peer(peer).await.is_err());
+        match caller.connection_state() {
+            ConnectionState::Error { last_error, can_retry, .. } => {
+                assert!(can_retry);
+                assert!(!last_error.is_empty());
             }
+            other => panic!("État Error attendu, obtenu {:?}", other),
         }
+        assert_eq!(caller.peer_addr(), Some(peer));
+        
+        // Chaque connexion réessaie jusqu'à max_retry_attempts (2) puis abandonne
+        assert!(caller.reconnect().await.is_err());
+        let attempts: Vec<u32> = caller.state_history().await
+            .into_iter()
+            .filter_map(|transition| match transition.to {
+                ConnectionState::Connecting { attempt_count, .. } => Some(attempt_count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(attempts, vec![1, 2, 1, 2]);
+        assert!(matches!(caller.connection_state(), ConnectionState::Error { .. }));
     }
     
-    /// Déconnecte proprement du peer
-    async fn disconnect(&mut self) -> NetworkResult<()> {
-        let peer_addr = {
-            let state = self.connection_state.lock().await;
-            state.peer_addr()
-        };
+    #[tokio::test]
+    async fn test_connect_retries_until_peer_answers() {
+        let mut config = NetworkConfig::test_config();
+        config.connection_timeout = Duration::from_millis(150);
+        config.handshake_retry_interval = Duration::from_millis(50);
+        config.retry_delay = Duration::from_millis(10);
+        config.max_retry_attempts = 3;
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
         
-        if let Some(addr) = peer_addr {
-            // Envoie un paquet de déconnexion
-            let disconnect_packet = self.create_disconnect_packet();
-            let _ = self.transport.send_packet(&disconnect_packet, addr).await;
-        }
+        // Le pair ne répond qu'après l'expiration de la première tentative
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            async {
+                sleep(Duration::from_millis(200)).await;
+                callee.open(9002, None).await
+            },
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        let attempts: Vec<u32> = caller.state_history().await
+            .into_iter()
+            .filter_map(|transition| match transition.to {
+                ConnectionState::Connecting { attempt_count, .. } => Some(attempt_count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(attempts, vec![1, 2]);
+        assert!(caller.connection_state().is_connected());
+    }
+    
+    #[tokio::test]
+    async fn test_reconnect_resumes_session() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        let (dialed, accepted) = tokio::join!(caller.connect_to_peer(utils::localhost(9002)), callee.open(9002, None));
+        dialed.unwrap();
+        accepted.unwrap();
+        let session_id = caller.session_id();
         
-        // Arrête le heartbeat
-        self.stop_heartbeat().await;
+        // Coupure côté appelant : la reconnexion reprend la session en un échange
+        let lost = NetworkError::PeerDisconnected { addr: utils::localhost(9002) };
+        caller.fail_connection(&lost, "coupure réseau").await;
+        let (resumed, _) = tokio::join!(
+            caller.reconnect(),
+            timeout(Duration::from_millis(300), callee.receive_audio()),
+        );
+        resumed.unwrap();
+        assert!(caller.connection_state().is_connected());
+        assert_eq!(caller.session_id(), session_id);
+        assert_eq!(caller.network_stats().sessions_resumed, 1);
+        // Pas de nouvelle session : le codec n'est pas réinitialisé
+        assert_eq!(caller.take_stream_resync(), None);
+        assert!(callee.connection_state().is_connected());
         
-        // Met à jour l'état
-        self.set_connection_state(ConnectionState::Disconnected).await;
+        // Côté appelant, la coupure termine une période connectée ; l'appelé
+        // n'a jamais quitté la sienne
+        let stats = caller.network_stats();
+        assert_eq!(stats.reconnection_count, 1);
+        assert!(stats.total_uptime() >= stats.current_session_uptime());
+        assert_eq!(callee.network_stats().reconnection_count, 0);
         
-        println!("Déconnexion terminée");
-        Ok(())
+        caller.reset_stats().await;
+        assert_eq!(caller.network_stats().reconnection_count, 0);
     }
     
-    /// Retourne l'état de connexion actuel
-    fn connection_state(&self) -> ConnectionState {
-        // Version synchrone pour éviter de bloquer
-        match self.connection_state.try_lock() {
-            Ok(state) => state.clone(),
-            Err(_) => ConnectionState::Disconnected,
-        }
+    #[tokio::test]
+    async fn test_rebind_resumes_session() {
+        let port = utils::find_free_udp_port(41201..=41240).unwrap();
+        let mut callee = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut caller = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let (dialed, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                caller.connect_to_peer(utils::localhost(port)).await
+            },
+            callee.open(port, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        caller.send_audio(CompressedFrame::new(vec![5; 20], 960, Instant::now(), 0)).await.unwrap();
+        let (session_id, old_port) = (caller.session_id(), caller.local_addr().unwrap().port());
+        let bytes_sent = caller.network_stats().bytes_sent;
+        assert!(bytes_sent > 0);
+        
+        // Nouveau socket : l'appel reprend depuis la nouvelle adresse, stats conservées
+        let (rebound, _) = tokio::join!(
+            caller.rebind(0),
+            timeout(Duration::from_millis(300), async {
+                while callee.receive_audio().await.is_ok() {}
+            }),
+        );
+        rebound.unwrap();
+        let new_port = caller.local_addr().unwrap().port();
+        assert_ne!(new_port, old_port);
+        assert!(caller.connection_state().is_connected());
+        assert_eq!(caller.session_id(), session_id);
+        assert_eq!(caller.network_stats().packets_sent, 1);
+        assert_eq!(caller.network_stats().sessions_resumed, 1);
+        assert!(caller.network_stats().bytes_sent > bytes_sent);
+        assert_eq!(callee.peer_addr().map(|addr| addr.port()), Some(new_port));
+        caller.shutdown().await.unwrap();
+        callee.shutdown().await.unwrap();
     }
     
-    /// Retourne les statistiques réseau combinées
-    fn network_stats(&self) -> NetworkStats {
-        match self.stats.try_lock() {
-            Ok(stats) => stats.clone(),
-            Err(_) => NetworkStats::default(),
-        }
+    #[tokio::test]
+    async fn test_apply_config_in_place() {
+        let mut manager = UdpNetworkManager::new_simulated(NetworkConfig::test_config()).unwrap();
+        let sender_id = manager.engine.sender_id();
+        
+        let mut config = NetworkConfig::test_config();
+        config.target_delay_ms = 100;
+        manager.apply_config(config.clone()).await.unwrap();
+        assert_eq!(manager.engine.target_delay_ms(), 100);
+        
+        // Réglage du transport : recréé, le reste du manager est conservé
+        config.timing_stats = true;
+        manager.apply_config(config.clone()).await.unwrap();
+        assert!(manager.transport.receive_timings().is_some());
+        assert_eq!(manager.engine.sender_id(), sender_id);
+        
+        config.heartbeat_interval = Duration::ZERO;
+        assert!(matches!(manager.apply_config(config).await, Err(NetworkError::ConfigError(_))));
+        assert_eq!(manager.config().target_delay_ms, 100);
+        
+        // Transport fourni par l'application : ne peut pas être recréé
+        let (transport, _peer) = SimulatedTransport::pair(NetworkConfig::test_config()).unwrap();
+        let mut custom = UdpNetworkManager::with_transport(NetworkConfig::test_config(), Box::new(transport)).unwrap();
+        let mut config = NetworkConfig::test_config();
+        config.timing_stats = !config.timing_stats;
+        assert!(matches!(custom.apply_config(config).await, Err(NetworkError::ConfigError(_))));
     }
     
-    /// Force une reconnexion si possible
-    async fn reconnect(&mut self) -> NetworkResult<()> {
-        // Récupère l'adresse du peer précédent
-        let peer_addr = {
-            let state = self.connection_state.lock().await;
-            state.peer_addr()
-        };
+    #[tokio::test]
+    async fn test_client_binds_system_port() {
+        let port = utils::find_free_udp_port(40961..=41000).unwrap();
+        let mut callee = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut caller = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
         
-        if let Some(addr) = peer_addr {
-            // Déconnecte proprement d'abord
-            self.disconnect().await?;
-            
-            // Attend un peu avant de reconnecter
-            sleep(Duration::from_millis(500)).await;
-            
-            // Tente de reconnecter
-            self.connect_to_peer(addr).await
-        } else {
-            Err(NetworkError::InvalidState {
-                operation: "reconnect".to_string(),
-                current_state: "no previous peer".to_string(),
-            })
+        let (dialed, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                caller.connect_to_peer(utils::localhost(port)).await
+            },
+            callee.open(port, None),
+        );
+        dialed.unwrap();
+        let caller_addr = accepted.unwrap();
+        
+        // Port attribué par le système, annoncé au pair dans le Hello
+        let caller_port = caller.local_addr().unwrap().port();
+        assert_ne!(caller_port, 0);
+        assert_eq!(caller_addr.port(), caller_port);
+        assert_eq!(callee.peer_local_addr().map(|addr| addr.port()), Some(caller_port));
+        assert_eq!(caller.peer_local_addr().map(|addr| addr.port()), Some(port));
+        // Identités prouvées dans le même handshake
+        assert_eq!(callee.peer_identity(), caller.identity());
+        assert_eq!(caller.peer_identity(), callee.identity());
+        assert!(caller.identity().is_some());
+        caller.shutdown().await.unwrap();
+        callee.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_source_reported() {
+        let port = utils::find_free_udp_port(41081..=41120).unwrap();
+        let mut callee = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut caller = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        
+        let (dialed, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                caller.connect_to_peer(utils::localhost(port)).await
+            },
+            callee.open(port, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        // Audio envoyé depuis un autre port que celui du pair
+        let intruder = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        for _ in 0..3 {
+            let frame = CompressedFrame::new(vec![1; 20], 960, Instant::now(), 0);
+            let packet = NetworkPacket::new_audio(frame, 77, 1);
+            let mut datagram = Vec::new();
+            packet.encode_into(&mut datagram);
+            intruder.send_to(&datagram, utils::localhost(port)).unwrap();
         }
+        assert!(timeout(Duration::from_millis(200), callee.receive_audio()).await.is_err());
+        
+        // Tous comptés, un seul événement dans l'intervalle de journalisation
+        assert_eq!(callee.network_stats().packets_from_unknown_peer, 3);
+        assert_eq!(callee.take_network_event(), Some(NetworkEvent::UnexpectedSource(intruder.local_addr().unwrap())));
+        assert_eq!(callee.take_network_event(), None);
+        caller.shutdown().await.unwrap();
+        callee.shutdown().await.unwrap();
     }
-}
+    
+    #[tokio::test]
+    async fn test_trust_on_first_use() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
 
-/// Buffer anti-jitter simple pour les paquets réseau
-/// 
-/// Compense les variations de latence réseau en buffering intelligemment
-/// les paquets avant de les livrer à l'application.
-struct JitterBuffer {
-    /// Paquets en attente, triés par numéro de séquence
-    packets: std::collections::BTreeMap<u64, NetworkPacket>,
+        let port = utils::find_free_udp_port(41041..=41080).unwrap();
+        let mut callee = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut caller = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        callee.set_trust_callback(Some(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            TrustDecision::Reject
+        })));
+
+        let (_, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                caller.connect_to_peer(utils::localhost(port)).await
+            },
+            callee.open(port, None),
+        );
+        let caller_identity = caller.identity().unwrap();
+        assert!(
+            matches!(accepted, Err(NetworkError::UntrustedPeer { identity: Some(identity), .. }) if identity == caller_identity),
+            "{:?}", accepted
+        );
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        assert!(!callee.engine.is_connected());
+
+        // Décision retenue : la question n'est plus posée
+        let caller_addr = caller.local_addr().unwrap();
+        assert_eq!(callee.known_peers().check(caller_addr, caller_identity), TrustStatus::Rejected);
+
+        // Autre identité à l'adresse d'un pair de confiance
+        let other = Identity::from_secret_bytes([9; 32]).public();
+        callee.known_peers_mut().remember(caller_addr, other, true).unwrap();
+        callee.known_peers_mut().forget(caller_identity).unwrap();
+        assert!(matches!(
+            callee.verify_peer_trust(caller_addr),
+            Err(NetworkError::FingerprintChanged { expected, actual, .. }) if expected == other && actual == caller_identity
+        ));
+        caller.shutdown().await.unwrap();
+        callee.shutdown().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_replayed_identity_not_trusted() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        let port = utils::find_free_udp_port(41121..=41160).unwrap();
+        let mut config = NetworkConfig::test_config();
+        config.heartbeat_timeout = Duration::from_secs(5);
+        let mut callee = UdpNetworkManager::new(config).unwrap();
+        let mut caller = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        callee.set_trust_callback(Some(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            TrustDecision::Remember
+        })));
+        
+        // Hello d'un pair de confiance, capturé puis rejoué depuis une autre adresse
+        let intruder = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let intruder_addr = intruder.local_addr().unwrap();
+        let caller_identity = caller.identity().unwrap();
+        callee.known_peers_mut().remember(intruder_addr, caller_identity, true).unwrap();
+        caller.engine.set_local_addr(Some(intruder_addr));
+        let mut hello = Vec::new();
+        caller.engine.handshake_packet(HandshakeMessage::Hello).encode_into(&mut hello);
+        
+        let (_, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                intruder.send_to(&hello, utils::localhost(port)).unwrap();
+            },
+            callee.open(port, None),
+        );
+        
+        // Sans preuve sur le défi de l'appelé : anonyme, refusé sans question
+        assert!(
+            matches!(accepted, Err(NetworkError::UntrustedPeer { identity: None, .. })),
+            "{:?}", accepted
+        );
+        assert_eq!(asked.load(Ordering::SeqCst), 0);
+        assert_eq!(callee.peer_identity(), None);
+        callee.shutdown().await.unwrap();
+    }
     
-    /// Taille maximum du buffer
-    max_size: usize,
+    #[tokio::test]
+    async fn test_connect_via_relay() {
+        let mut relay = crate::RelayServer::bind(0).await.unwrap();
+        let relay_addr = utils::localhost(relay.local_addr().unwrap().port());
+        let relay_task = tokio::spawn(async move { relay.run(None).await });
+        
+        let port = utils::find_free_udp_port(41001..=41040).unwrap();
+        let mut config = NetworkConfig::test_config();
+        config.relays = vec![relay_addr];
+        let mut callee = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        let mut caller = UdpNetworkManager::new(config).unwrap();
+        
+        let (dialed, accepted) = tokio::join!(
+            async {
+                sleep(Duration::from_millis(50)).await;
+                let (selected, _) = caller.select_relay().await.unwrap().unwrap();
+                assert_eq!(selected, relay_addr);
+                caller.connect_via_relay(utils::localhost(port), selected).await
+            },
+            callee.open(port, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        // Chaque côté voit l'autre derrière le relais
+        let relayed = Some(SessionRoute::Relayed { relay: relay_addr });
+        assert_eq!(caller.connection_state().route(), relayed);
+        assert_eq!(callee.connection_state().route(), relayed);
+        
+        caller.disconnect().await.unwrap();
+        assert_eq!(caller.transport.relay_route(utils::localhost(port)), None);
+        caller.shutdown().await.unwrap();
+        callee.shutdown().await.unwrap();
+        relay_task.abort();
+    }
     
-    /// Numéro de séquence attendu
-    expected_sequence: u64,
+    #[tokio::test]
+    async fn test_deferred_control_sent_before_audio() {
+        let config = NetworkConfig { control_priority: true, ..NetworkConfig::test_config() };
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        // Sans les heartbeats du thread dédié, seuls nos paquets arrivent à l'appelé
+        caller.stop_heartbeat().await;
+        while timeout(Duration::from_millis(20), callee.transport.receive_packet()).await.is_ok() {}
+        
+        // Heartbeat refusé par un socket plein : il part avant la frame suivante
+        caller.control_queue.defer(caller.engine.heartbeat_packet(), utils::localhost(9002));
+        let outcome = caller.send_audio(CompressedFrame::new(vec![1; 20], 960, Instant::now(), 0)).await.unwrap();
+        
+        let (first, _) = callee.transport.receive_packet().await.unwrap();
+        let (second, _) = callee.transport.receive_packet().await.unwrap();
+        assert_eq!((first.packet_type, second.packet_type), (PacketType::Heartbeat, PacketType::Audio));
+        assert_eq!(outcome, SendOutcome::Sent { bytes: second.estimated_size() });
+        assert_eq!(caller.deferred_control_packets(), 1);
+    }
     
-    /// Paquets perdus détectés
-    lost_packets: u64,
-}
-
-impl JitterBuffer {
-    /// Crée un nouveau buffer anti-jitter
-    fn new(max_size: usize) -> Self {
-        Self {
-            packets: std::collections::BTreeMap::new(),
-            max_size,
-            expected_sequence: 1,
-            lost_packets: 0,
-        }
+    #[tokio::test]
+    async fn test_connect_deadline() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, _silent_peer) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config, Box::new(caller_transport)).unwrap();
+        
+        // La limite globale coupe le handshake en cours (connection_timeout : 1s)
+        let started_at = Instant::now();
+        let result = caller.connect_with_deadline(utils::localhost(9002), Some(Duration::from_millis(100))).await;
+        assert!(matches!(result, Err(NetworkError::ConnectionTimeout { timeout_ms: 100, .. })), "{:?}", result);
+        assert!(started_at.elapsed() < Duration::from_millis(500));
+        assert!(matches!(caller.connection_state(), ConnectionState::Error { .. }));
     }
     
-    /// Ajoute un paquet au buffer
-    /// 
-    /// Retourne true si le paquet a été accepté
-    fn push_packet(&mut self, packet: NetworkPacket) -> bool {
-        let sequence = packet.compressed_frame.sequence_number;
+    #[tokio::test]
+    async fn test_duplicate_redundancy_collapsed() {
+        let mut config = NetworkConfig::test_config();
+        config.redundancy = RedundancyMode::Duplicate(3);
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
         
-        // Rejette les paquets trop anciens ou en double
-        if sequence < self.expected_sequence || self.packets.contains_key(&sequence) {
-            return false;
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        for _ in 0..3 {
+            let frame = CompressedFrame::new(vec![7; 20], 960, Instant::now(), 0);
+            caller.send_audio(frame).await.unwrap();
         }
         
-        // Vérifie la capacité du buffer
-        if self.packets.len() >= self.max_size {
-            // Supprime le plus ancien paquet
-            if let Some((&oldest_seq, _)) = self.packets.iter().next() {
-                self.packets.remove(&oldest_seq);
-            }
+        // Chaque frame est reçue une seule fois malgré ses trois copies
+        let mut sequences = Vec::new();
+        while let Ok(Ok(frame)) = timeout(Duration::from_millis(100), callee.receive_audio()).await {
+            sequences.push(frame.sequence_number);
         }
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert_eq!(caller.network_stats().packets_sent, 3);
         
-        // Ajoute le paquet
-        self.packets.insert(sequence, packet);
-        true
+        let buffer = callee.buffer_stats();
+        assert_eq!(buffer.duplicates_dropped, 6);
+        assert_eq!((buffer.packets_buffered, buffer.packets_lost, buffer.concealments), (0, 0, 0));
     }
     
-    /// Récupère le prochain paquet dans l'ordre
-    fn pop_packet(&mut self) -> Option<NetworkPacket> {
-        // Cherche le paquet avec le numéro de séquence attendu
-        if let Some(packet) = self.packets.remove(&self.expected_sequence) {
-            self.expected_sequence += 1;
-            return Some(packet);
-        }
+    #[tokio::test]
+    async fn test_sync_audio_sender() {
+        let config = NetworkConfig::test_config();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        assert_eq!(caller.send_queued_audio().await.unwrap(), 0);
+        
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
         
-        // Si pas trouvé, vérifie s'il faut déclarer des paquets perdus
-        let mut found_higher = false;
-        for &seq in self.packets.keys() {
-            if seq > self.expected_sequence {
-                found_higher = true;
-                break;
+        // Le thread de capture dépose ses frames sans runtime ni verrou
+        let sender = caller.sync_audio_sender();
+        std::thread::spawn(move || {
+            for _ in 0..3 {
+                let frame = CompressedFrame::new(vec![3; 20], 960, Instant::now(), 0);
+                sender.try_send_frame(&frame).unwrap();
             }
+        }).join().unwrap();
+        assert_eq!(caller.send_queued_audio().await.unwrap(), 3);
+        
+        for sequence in 1..=3 {
+            assert_eq!(callee.receive_audio().await.unwrap().sequence_number, sequence);
         }
         
-        if found_higher {
-            // Il y a des paquets plus récents, donc celui attendu est perdu
-            self.lost_packets += 1;
-            self.expected_sequence += 1;
-            
-            // Réessaie avec le nouveau numéro attendu
-            return self.pop_packet();
+        let sender = caller.sync_audio_sender();
+        drop(caller);
+        assert!(sender.is_closed());
+    }
+    
+    #[tokio::test]
+    async fn test_receive_timing_stats() {
+        let mut config = NetworkConfig::test_config();
+        config.timing_stats = true;
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
+        
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        for _ in 0..3 {
+            let frame = CompressedFrame::new(vec![5; 20], 960, Instant::now(), 0);
+            caller.send_audio(frame).await.unwrap();
+            callee.receive_audio().await.unwrap();
         }
         
-        None
+        // Seuls les paquets audio sont chronométrés côté manager
+        let stats = callee.transport.timing_stats().unwrap();
+        assert_eq!(stats.stage(ReceiveStage::BufferInsert).count, 3);
+        assert_eq!(stats.stage(ReceiveStage::DecodeHandoff).count, 3);
+        assert_eq!(stats.stage(ReceiveStage::Deserialize).count, 0);
+        assert_eq!(stats.stages().len(), 4);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::Instant;
     
     #[tokio::test]
-    async fn test_manager_creation() {
+    async fn test_renegotiate_switches_decoder_at_boundary() {
         let config = NetworkConfig::test_config();
-        let manager = UdpNetworkManager::new_simulated(config).unwrap();
+        let (caller_transport, callee_transport) = SimulatedTransport::pair(config.clone()).unwrap();
+        let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport)).unwrap();
+        let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport)).unwrap();
         
-        assert!(!manager.connection_state().is_connected());
-        assert_eq!(manager.network_stats().packets_sent, 0);
+        // Pas de renégociation hors appel
+        assert!(caller.renegotiate(CodecParams::music()).await.is_err());
+        
+        let (dialed, accepted) = tokio::join!(
+            caller.open(9001, Some(utils::localhost(9002))),
+            callee.open(9002, None),
+        );
+        dialed.unwrap();
+        accepted.unwrap();
+        
+        let frame = CompressedFrame::new(vec![1; 20], 960, Instant::now(), 0);
+        caller.send_audio(frame).await.unwrap();
+        assert_eq!(callee.receive_audio().await.unwrap().sequence_number, 1);
+        
+        // Le pair acquitte pendant qu'il attend la frame suivante
+        let (switch_at, received) = tokio::join!(
+            async {
+                let switch_at = caller.renegotiate(CodecParams::music()).await.unwrap();
+                let frame = CompressedFrame::new(vec![2; 40], 960, Instant::now(), 0);
+                caller.send_audio(frame).await.unwrap();
+                switch_at
+            },
+            callee.receive_audio(),
+        );
+        assert_eq!(switch_at, 2);
+        assert_eq!(received.unwrap().sequence_number, 2);
+        
+        assert_eq!(callee.take_decoder_switch(1), None);
+        assert_eq!(callee.take_decoder_switch(2), Some(CodecParams::music()));
+        assert_eq!(callee.take_decoder_switch(3), None);
     }
     
     #[test]
-    fn test_jitter_buffer() {
-        let mut buffer = JitterBuffer::new(10);
+    fn test_injected_runtime() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
         
-        // Test ajout de paquets dans l'ordre
-        let frame1 = CompressedFrame::new(vec![1], 960, Instant::now(), 1);
-        let packet1 = NetworkPacket::new_audio(frame1, 123, 456);
-        
-        assert!(buffer.push_packet(packet1.clone()));
+        // Créés et pilotés hors de tout runtime, comme depuis une boucle d'interface
+        let config = NetworkConfig::test_config();
+        let mut caller = UdpNetworkManager::new_with_runtime(config.clone(), runtime.handle().clone()).unwrap();
+        let mut callee = UdpNetworkManager::new_with_runtime(config, runtime.handle().clone()).unwrap();
+        let port_a = utils::find_free_udp_port(40701..=40800).unwrap();
+        let port_b = utils::find_free_udp_port(40801..=40900).unwrap();
         
-        // Test récupération
-        let received = buffer.pop_packet().unwrap();
-        assert_eq!(received.compressed_frame.sequence_number, 1);
+        let (dialed, accepted) = crate::runtime::block_on(async {
+            tokio::join!(
+                caller.open(port_a, Some(utils::localhost(port_b))),
+                callee.open(port_b, None),
+            )
+        });
+        dialed.unwrap();
+        accepted.unwrap();
         
-        // Test paquet en retard (rejeté)
-        let frame_old = CompressedFrame::new(vec![0], 960, Instant::now(), 1);
-        let packet_old = NetworkPacket::new_audio(frame_old, 123, 456);
-        assert!(!buffer.push_packet(packet_old));
+        let frame = CompressedFrame::new(vec![1; 20], 960, Instant::now(), 0);
+        crate::runtime::block_on(caller.send_audio(frame)).unwrap();
+        let received = crate::runtime::block_on(callee.receive_audio()).unwrap();
+        assert_eq!(received.sequence_number, 1);
+        crate::runtime::block_on(caller.shutdown()).unwrap();
     }
     
-    #[test]
-    fn test_jitter_buffer_out_of_order() {
-        let mut buffer = JitterBuffer::new(10);
-        
-        // Ajoute des paquets dans le désordre
-        let frame3 = CompressedFrame::new(vec![3], 960, Instant::now(), 3);
-        let packet3 = NetworkPacket::new_audio(frame3, 123, 456);
-        assert!(buffer.push_packet(packet3));
-        
-        let frame1 = CompressedFrame::new(vec![1], 960, Instant::now(), 1);
-        let packet1 = NetworkPacket::new_audio(frame1, 123, 456);
-        assert!(buffer.push_packet(packet1));
-        
-        // Le paquet 1 doit sortir en premier
-        let received = buffer.pop_packet().unwrap();
-        assert_eq!(received.compressed_frame.sequence_number, 1);
-        
-        // Le paquet 2 est manquant, doit être marqué comme perdu
-        // et le paquet 3 doit sortir
-        let received = buffer.pop_packet().unwrap();
-        assert_eq!(received.compressed_frame.sequence_number, 3);
-        assert_eq!(buffer.lost_packets, 1);
+    #[cfg(feature = "udp")]
+    #[tokio::test]
+    async fn test_presence_announcements_follow_binding() {
+        let mut config = NetworkConfig::test_config();
+        config.presence = Some(PresenceConfig::new("Salon"));
+        let mut manager = UdpNetworkManager::new(config).unwrap();
+        assert!(manager.presence_handle.is_none());
+        
+        // Les annonces démarrent avec le bind et s'arrêtent avec le manager
+        manager.ensure_bound().await.unwrap();
+        assert!(manager.presence_handle.is_some());
+        manager.shutdown().await.unwrap();
+        assert!(manager.presence_handle.is_none());
+        
+        // Sans configuration, aucune annonce
+        let mut silent = UdpNetworkManager::new(NetworkConfig::test_config()).unwrap();
+        silent.ensure_bound().await.unwrap();
+        assert!(silent.presence_handle.is_none());
     }
 }
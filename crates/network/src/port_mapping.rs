@@ -0,0 +1,448 @@
+//! Redirection de port automatique sur le routeur (UPnP IGD / NAT-PMP)
+//!
+//! Disponible avec la feature `upnp`. Demande au routeur domestique de
+//! rediriger le port UDP d'écoute vers cette machine, pour héberger un appel
+//! sans configuration manuelle du routeur :
+//! - UPnP IGD en priorité (découverte SSDP de la passerelle)
+//! - NAT-PMP en repli (passerelle par défaut de la table de routage IPv4)
+//!
+//! Le bail est renouvelé périodiquement par une tâche de fond, qui suit le
+//! port externe si le routeur en accorde un autre, et la redirection est
+//! supprimée explicitement par `PortMapper::remove()`.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use igd_next::aio::Gateway;
+use igd_next::aio::tokio::{Tokio, search_gateway};
+use igd_next::{PortMappingProtocol, SearchOptions};
+use tokio::net::UdpSocket;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+
+use crate::{NetworkError, NetworkResult, utils};
+
+/// Méthode utilisée pour obtenir la redirection
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PortMappingMethod {
+    /// UPnP Internet Gateway Device
+    Igd,
+    /// NAT Port Mapping Protocol (RFC 6886)
+    NatPmp,
+}
+
+/// Redirection active sur le routeur
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortMapping {
+    /// Adresse publique à communiquer au pair distant
+    pub external_addr: SocketAddr,
+
+    /// Port UDP local redirigé
+    pub local_port: u16,
+
+    /// Méthode ayant accordé la redirection
+    pub method: PortMappingMethod,
+
+    /// Durée du bail accordé par le routeur
+    pub lease: Duration,
+}
+
+/// Passerelle ayant accordé la redirection
+#[derive(Clone)]
+enum MappingGateway {
+    Igd { gateway: Gateway<Tokio>, local_addr: SocketAddr },
+    NatPmp { gateway: SocketAddr },
+}
+
+impl MappingGateway {
+    /// Méthode correspondant à la passerelle
+    fn method(&self) -> PortMappingMethod {
+        match self {
+            MappingGateway::Igd { .. } => PortMappingMethod::Igd,
+            MappingGateway::NatPmp { .. } => PortMappingMethod::NatPmp,
+        }
+    }
+
+    /// Demande (ou renouvelle) la redirection, retourne le port externe accordé
+    async fn request(&self, local_port: u16, external_port: u16, lease: Duration) -> NetworkResult<u16> {
+        match self {
+            MappingGateway::Igd { gateway, local_addr } => {
+                gateway
+                    .add_port(
+                        PortMappingProtocol::UDP,
+                        external_port,
+                        *local_addr,
+                        lease.as_secs() as u32,
+                        "Voc",
+                    )
+                    .await
+                    .map_err(|e| NetworkError::PortMappingError(e.to_string()))?;
+                Ok(external_port)
+            }
+            MappingGateway::NatPmp { gateway } => {
+                let request = nat_pmp_map_request(local_port, external_port, lease.as_secs() as u32);
+                let response = nat_pmp_exchange(*gateway, &request, 16).await?;
+                Ok(u16::from_be_bytes([response[10], response[11]]))
+            }
+        }
+    }
+
+    /// Adresse IP publique vue par le routeur
+    async fn external_ip(&self) -> NetworkResult<IpAddr> {
+        match self {
+            MappingGateway::Igd { gateway, .. } => gateway
+                .get_external_ip()
+                .await
+                .map_err(|e| NetworkError::PortMappingError(e.to_string())),
+            MappingGateway::NatPmp { gateway } => {
+                let response = nat_pmp_exchange(*gateway, &[0, 0], 12).await?;
+                Ok(IpAddr::V4(Ipv4Addr::new(response[8], response[9], response[10], response[11])))
+            }
+        }
+    }
+
+    /// Supprime la redirection
+    async fn release(&self, local_port: u16, external_port: u16) -> NetworkResult<()> {
+        match self {
+            MappingGateway::Igd { gateway, .. } => gateway
+                .remove_port(PortMappingProtocol::UDP, external_port)
+                .await
+                .map_err(|e| NetworkError::PortMappingError(e.to_string())),
+            MappingGateway::NatPmp { gateway } => {
+                // Durée 0 et port externe 0 = suppression (RFC 6886 §3.4)
+                let request = nat_pmp_map_request(local_port, 0, 0);
+                nat_pmp_exchange(*gateway, &request, 16).await.map(|_| ())
+            }
+        }
+    }
+}
+
+/// Gestionnaire d'une redirection de port avec renouvellement automatique
+///
+/// # Example
+/// ```rust,no_run
+/// use network::PortMapper;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mapper = PortMapper::map(9001, PortMapper::DEFAULT_LEASE).await?;
+/// println!("Adresse publique : {}", mapper.mapping().external_addr);
+///
+/// // Port externe changé par le routeur lors d'un renouvellement
+/// let mut changes = mapper.subscribe();
+/// # tokio::spawn(async move {
+/// while changes.changed().await.is_ok() {
+///     println!("Nouvelle adresse publique : {}", changes.borrow().external_addr);
+/// }
+/// # });
+///
+/// // ... appel en cours ...
+///
+/// mapper.remove().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PortMapper {
+    /// Redirection courante, mise à jour par la tâche de renouvellement
+    mapping: watch::Receiver<PortMapping>,
+
+    /// Passerelle utilisée (pour le renouvellement et la suppression)
+    gateway: MappingGateway,
+
+    /// Tâche de renouvellement du bail
+    refresh_handle: Option<JoinHandle<()>>,
+}
+
+impl PortMapper {
+    /// Durée de bail demandée par défaut
+    pub const DEFAULT_LEASE: Duration = Duration::from_secs(600);
+
+    /// Durée max de recherche d'une passerelle UPnP
+    const IGD_SEARCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+    /// Délai entre deux tentatives de renouvellement échouées
+    const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+    /// Demande au routeur de rediriger `local_port` (UDP) vers cette machine
+    ///
+    /// Essaie UPnP IGD puis NAT-PMP. Le même port est demandé côté externe ;
+    /// le routeur peut en accorder un autre (voir `mapping().external_addr`).
+    ///
+    /// # Arguments
+    /// * `local_port` - Port UDP local à exposer
+    /// * `lease` - Durée de bail demandée (renouvelé à mi-parcours)
+    ///
+    /// # Erreurs
+    /// - `NetworkError::PortMappingError` : Aucun routeur compatible ou refus
+    pub async fn map(local_port: u16, lease: Duration) -> NetworkResult<Self> {
+        let gateway = match Self::find_igd_gateway(local_port).await {
+            Ok(gateway) => gateway,
+            Err(igd_error) => {
+                println!("UPnP IGD indisponible ({}) - essai NAT-PMP...", igd_error);
+                Self::find_nat_pmp_gateway().await?
+            }
+        };
+
+        let external_port = gateway.request(local_port, local_port, lease).await?;
+        let external_ip = gateway.external_ip().await?;
+
+        let mapping = PortMapping {
+            external_addr: SocketAddr::new(external_ip, external_port),
+            local_port,
+            method: gateway.method(),
+            lease,
+        };
+
+        println!("Redirection {:?} active : {} -> port local {}",
+                 mapping.method, mapping.external_addr, local_port);
+
+        let (mapping_tx, mapping) = watch::channel(mapping);
+        let refresh_handle = tokio::spawn(Self::refresh_loop(gateway.clone(), mapping_tx));
+
+        Ok(Self {
+            mapping,
+            gateway,
+            refresh_handle: Some(refresh_handle),
+        })
+    }
+
+    /// Redirection actuellement active
+    pub fn mapping(&self) -> PortMapping {
+        self.mapping.borrow().clone()
+    }
+
+    /// Suit la redirection : signalée à chaque changement de port externe
+    /// lors d'un renouvellement, à réannoncer au pair
+    pub fn subscribe(&self) -> watch::Receiver<PortMapping> {
+        self.mapping.clone()
+    }
+
+    /// Arrête le renouvellement et supprime la redirection sur le routeur
+    pub async fn remove(mut self) -> NetworkResult<()> {
+        if let Some(handle) = self.refresh_handle.take() {
+            handle.abort();
+        }
+
+        let mapping = self.mapping();
+        self.gateway
+            .release(mapping.local_port, mapping.external_addr.port())
+            .await?;
+
+        println!("Redirection {} supprimée", mapping.external_addr);
+        Ok(())
+    }
+
+    /// Cherche une passerelle UPnP IGD sur le réseau local
+    async fn find_igd_gateway(local_port: u16) -> NetworkResult<MappingGateway> {
+        let options = SearchOptions {
+            timeout: Some(Self::IGD_SEARCH_TIMEOUT),
+            ..Default::default()
+        };
+
+        let gateway = search_gateway(options)
+            .await
+            .map_err(|e| NetworkError::PortMappingError(e.to_string()))?;
+
+        // Adresse locale sur le réseau de la passerelle (hôte multi-interfaces, VPN)
+        let local_ip = utils::local_ip_candidates(Some(gateway.addr.ip()))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| NetworkError::PortMappingError("aucune adresse IP locale".to_string()))?;
+        let local_addr = SocketAddr::new(local_ip, local_port);
+        Ok(MappingGateway::Igd { gateway, local_addr })
+    }
+
+    /// Passerelle NAT-PMP : passerelle par défaut de la table de routage IPv4
+    async fn find_nat_pmp_gateway() -> NetworkResult<MappingGateway> {
+        let gateway = default_ipv4_gateway().await?;
+        Ok(MappingGateway::NatPmp {
+            gateway: SocketAddr::new(IpAddr::V4(gateway), NAT_PMP_PORT),
+        })
+    }
+
+    /// Renouvelle le bail à mi-parcours, avec retry en cas d'échec
+    ///
+    /// Le routeur peut accorder un autre port externe (redémarrage, conflit) :
+    /// la redirection publiée est alors mise à jour.
+    async fn refresh_loop(gateway: MappingGateway, mapping: watch::Sender<PortMapping>) {
+        loop {
+            let PortMapping { external_addr, local_port, lease, .. } = mapping.borrow().clone();
+            sleep(lease / 2).await;
+
+            let granted = loop {
+                match gateway.request(local_port, external_addr.port(), lease).await {
+                    Ok(port) => break port,
+                    Err(e) => {
+                        println!("Renouvellement de la redirection échoué : {} - nouvel essai dans {:?}",
+                                 e, Self::REFRESH_RETRY_DELAY);
+                        sleep(Self::REFRESH_RETRY_DELAY).await;
+                    }
+                }
+            };
+
+            if granted != external_addr.port() {
+                let external_addr = SocketAddr::new(external_addr.ip(), granted);
+                println!("Port externe changé par le routeur : {} -> port local {}", external_addr, local_port);
+                mapping.send_modify(|mapping| mapping.external_addr = external_addr);
+            }
+        }
+    }
+}
+
+impl Drop for PortMapper {
+    fn drop(&mut self) {
+        // Sans appel à remove(), la redirection expire d'elle-même à la fin du bail
+        if let Some(handle) = self.refresh_handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Port du service NAT-PMP sur la passerelle
+const NAT_PMP_PORT: u16 = 5351;
+
+/// Passerelle IPv4 par défaut de la table de routage
+///
+/// `/proc/net/route` sous Linux, sinon la table affichée par `netstat -rn`
+/// (macOS, BSD, Windows).
+async fn default_ipv4_gateway() -> NetworkResult<Ipv4Addr> {
+    let gateway = match tokio::fs::read_to_string("/proc/net/route").await {
+        Ok(table) => parse_proc_net_route(&table),
+        Err(_) => {
+            let output = Command::new("netstat").arg("-rn").output().await?;
+            parse_netstat_routes(&String::from_utf8_lossy(&output.stdout))
+        }
+    };
+    gateway.ok_or_else(|| NetworkError::PortMappingError(
+        "aucune passerelle IPv4 par défaut dans la table de routage".to_string(),
+    ))
+}
+
+/// Passerelle de la route par défaut de plus faible métrique dans `/proc/net/route`
+fn parse_proc_net_route(table: &str) -> Option<Ipv4Addr> {
+    /// Route passant par une passerelle (`RTF_GATEWAY`)
+    const RTF_GATEWAY: u16 = 0x2;
+
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // Iface Destination Gateway Flags RefCnt Use Metric ...
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_, destination, gateway, flags, _, _, metric, ..] = fields[..] else {
+                return None;
+            };
+            let flags = u16::from_str_radix(flags, 16).ok()?;
+            if destination != "00000000" || flags & RTF_GATEWAY == 0 {
+                return None;
+            }
+            // Adresse écrite en hexadécimal dans l'ordre des octets de la machine
+            let gateway = Ipv4Addr::from(u32::from_str_radix(gateway, 16).ok()?.to_ne_bytes());
+            Some((metric.parse::<u32>().ok()?, gateway))
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, gateway)| gateway)
+}
+
+/// Passerelle de la première route par défaut IPv4 affichée par `netstat -rn`
+///
+/// `default 192.168.1.1 ...` (macOS, BSD) ou `0.0.0.0 0.0.0.0 192.168.1.1 ...`
+/// (Windows, destination puis masque) ; les routes `On-link` sont ignorées.
+fn parse_netstat_routes(output: &str) -> Option<Ipv4Addr> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let gateway = match fields.next()? {
+            "default" => fields.next()?,
+            "0.0.0.0" if fields.next()? == "0.0.0.0" => fields.next()?,
+            _ => return None,
+        };
+        gateway.parse::<Ipv4Addr>().ok().filter(|gateway| !gateway.is_unspecified())
+    })
+}
+
+/// Construit une requête NAT-PMP de redirection UDP (opcode 1)
+fn nat_pmp_map_request(local_port: u16, external_port: u16, lifetime_secs: u32) -> [u8; 12] {
+    let mut request = [0u8; 12];
+    request[1] = 1; // version 0, opcode 1 = UDP
+    request[4..6].copy_from_slice(&local_port.to_be_bytes());
+    request[6..8].copy_from_slice(&external_port.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime_secs.to_be_bytes());
+    request
+}
+
+/// Envoie une requête NAT-PMP et attend la réponse (retry exponentiel)
+async fn nat_pmp_exchange(gateway: SocketAddr, request: &[u8], response_len: usize) -> NetworkResult<Vec<u8>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    let mut buffer = [0u8; 16];
+    let mut wait = Duration::from_millis(250);
+
+    for _ in 0..4 {
+        socket.send_to(request, gateway).await?;
+
+        if let Ok(Ok((len, source))) = timeout(wait, socket.recv_from(&mut buffer)).await
+            && source.ip() == gateway.ip()
+            && len >= response_len
+        {
+            let result_code = u16::from_be_bytes([buffer[2], buffer[3]]);
+            if result_code != 0 {
+                return Err(NetworkError::PortMappingError(
+                    format!("NAT-PMP a refusé la requête (code {})", result_code),
+                ));
+            }
+            return Ok(buffer[..len].to_vec());
+        }
+
+        wait *= 2;
+    }
+
+    Err(NetworkError::PortMappingError("aucune réponse NAT-PMP de la passerelle".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nat_pmp_map_request_layout() {
+        let request = nat_pmp_map_request(9001, 9002, 600);
+        assert_eq!(request[0], 0); // version
+        assert_eq!(request[1], 1); // opcode UDP
+        assert_eq!(u16::from_be_bytes([request[4], request[5]]), 9001);
+        assert_eq!(u16::from_be_bytes([request[6], request[7]]), 9002);
+        assert_eq!(u32::from_be_bytes([request[8], request[9], request[10], request[11]]), 600);
+    }
+
+    #[test]
+    fn test_default_gateway_from_proc_net_route() {
+        let gateway = |ip: Ipv4Addr| format!("{:08X}", u32::from_ne_bytes(ip.octets()));
+        let table = format!(
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+             wlan0\t00000000\t{}\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+             eth0\t00000000\t{}\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+             eth0\t0000A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n",
+            gateway(Ipv4Addr::new(192, 168, 1, 254)),
+            gateway(Ipv4Addr::new(10, 0, 0, 138)),
+        );
+        // Route par défaut de plus faible métrique, pas forcément en x.y.z.1
+        assert_eq!(parse_proc_net_route(&table), Some(Ipv4Addr::new(10, 0, 0, 138)));
+        assert_eq!(parse_proc_net_route("Iface\tDestination\tGateway\n"), None);
+    }
+
+    #[test]
+    fn test_default_gateway_from_netstat() {
+        let macos = "Routing tables\n\nInternet:\n\
+            Destination        Gateway            Flags           Netif Expire\n\
+            default            192.168.0.254      UGScg             en0\n\
+            127                127.0.0.1          UCS               lo0\n\n\
+            Internet6:\n\
+            default            fe80::1%en0        UGcg              en0\n";
+        assert_eq!(parse_netstat_routes(macos), Some(Ipv4Addr::new(192, 168, 0, 254)));
+
+        let windows = "IPv4 Route Table\n\
+            Network Destination        Netmask          Gateway       Interface  Metric\n\
+                      0.0.0.0          0.0.0.0         On-link       10.8.0.2     35\n\
+                      0.0.0.0          0.0.0.0      172.16.0.1    172.16.0.20     25\n";
+        assert_eq!(parse_netstat_routes(windows), Some(Ipv4Addr::new(172, 16, 0, 1)));
+    }
+}
@@ -0,0 +1,23 @@
+//! Surface stable du crate, à importer d'un bloc
+//!
+//! Traits, configuration, erreurs et manager P2P. Le reste des réexports de
+//! la racine (moteur de protocole, tap de capture, découverte...) reste
+//! public mais peut encore changer d'une version mineure à l'autre.
+//!
+//! # Example
+//! ```rust
+//! use network::prelude::*;
+//!
+//! let config = NetworkConfig::default();
+//! assert!(config.validate().is_ok());
+//! ```
+
+pub use crate::{CongestionController, KeepaliveSink, KeepaliveSource, NetworkManager, NetworkTransport};
+pub use crate::{DegradationConfig, NetworkConfig, PaddingConfig, PresenceConfig, ProxyConfig};
+pub use crate::{NetworkError, NetworkResult};
+pub use crate::{CompressedFrame, ConnectionQuality, ConnectionState, NetworkEvent, NetworkPacket, NetworkStats, PeerStatsReport};
+pub use crate::{Identity, KnownPeers, SyncAudioSender, UdpNetworkManager};
+#[cfg(feature = "udp")]
+pub use crate::UdpTransport;
+#[cfg(feature = "simulator")]
+pub use crate::SimulatedTransport;
@@ -0,0 +1,188 @@
+//! Présence des instances Voc sur le réseau local (« qui est en ligne »)
+//!
+//! Là où `discovery` sonde le réseau une fois, la présence est continue :
+//! une instance configurée avec `NetworkConfig::presence` diffuse en
+//! broadcast, tant que son transport est bindé, une annonce portant son nom
+//! affiché et ses capacités. Les annonces partent du socket d'écoute (depuis
+//! le thread de heartbeat, hors runtime) : l'adresse source est donc celle à
+//! laquelle appeler l'instance.
+//!
+//! `utils::collect_presence` écoute ces annonces pendant une durée donnée et
+//! en tire la liste des instances en ligne. Le port des annonces est partagé
+//! (`SO_REUSEADDR`) : plusieurs applications d'une même machine peuvent
+//! écouter en même temps.
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use crate::{CodecKind, DiscoveryMessage, NetworkPacket, PresenceConfig};
+
+/// Port par défaut des annonces de présence
+pub const PRESENCE_PORT: u16 = 9000;
+
+/// Capacités annoncées par une instance
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceCapabilities {
+    /// Version du protocole parlée par l'instance
+    pub protocol_version: u8,
+
+    /// Codecs audio que l'instance sait décoder
+    pub codecs: Vec<CodecKind>,
+}
+
+impl PresenceCapabilities {
+    /// Capacités de cette version de Voc
+    pub fn current() -> Self {
+        Self {
+            protocol_version: NetworkPacket::CURRENT_PROTOCOL_VERSION,
+            codecs: vec![CodecKind::Opus],
+        }
+    }
+}
+
+/// Instance en ligne, d'après sa dernière annonce
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresencePeer {
+    /// Adresse d'où vient l'annonce, à utiliser pour appeler l'instance
+    pub addr: SocketAddr,
+
+    /// ID de l'instance
+    pub sender_id: u64,
+
+    /// Nom affiché
+    pub display_name: String,
+
+    /// Capacités annoncées
+    pub capabilities: PresenceCapabilities,
+
+    /// Réception de la dernière annonce
+    pub last_seen: Instant,
+}
+
+/// Paquet d'annonce diffusé par le thread de présence
+pub(crate) fn announcement_packet(config: &PresenceConfig, sender_id: u64, session_id: u64) -> NetworkPacket {
+    let announce = DiscoveryMessage::Announce {
+        display_name: config.display_name.clone(),
+        capabilities: PresenceCapabilities::current(),
+    };
+    NetworkPacket::new_discovery(&announce, sender_id, session_id)
+}
+
+/// Ajoute au roster l'instance annoncée par le datagramme `data`
+///
+/// Une instance déjà connue (même `sender_id`) est mise à jour : nom et
+/// adresse peuvent changer d'une annonce à l'autre. Les datagrammes qui ne
+/// sont pas des annonces valides sont ignorés.
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) fn record_announcement(roster: &mut Vec<PresencePeer>, data: &[u8], source: SocketAddr, now: Instant) {
+    let Some(packet) = NetworkPacket::decode_from(data).filter(NetworkPacket::verify_checksum) else {
+        return;
+    };
+    let Some(DiscoveryMessage::Announce { display_name, capabilities }) = packet.discovery_message() else {
+        return;
+    };
+
+    let peer = PresencePeer { addr: source, sender_id: packet.sender_id, display_name, capabilities, last_seen: now };
+    match roster.iter_mut().find(|known| known.sender_id == peer.sender_id) {
+        Some(known) => *known = peer,
+        None => roster.push(peer),
+    }
+}
+
+/// Socket d'écoute des annonces, partageable avec d'autres processus
+#[cfg(feature = "udp")]
+pub(crate) fn bind_listener(port: u16) -> std::io::Result<tokio::net::UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from(([0, 0, 0, 0], port)).into())?;
+    tokio::net::UdpSocket::from_std(socket.into())
+}
+
+/// Écoute les annonces sur `socket` pendant `duration`
+#[cfg(feature = "udp")]
+pub(crate) async fn collect(socket: &tokio::net::UdpSocket, duration: std::time::Duration) -> Vec<PresencePeer> {
+    let mut roster = Vec::new();
+    listen(socket, duration, |data, source| record_announcement(&mut roster, data, source, Instant::now())).await;
+    roster
+}
+
+/// Passe à `on_datagram` chaque datagramme reçu sur `socket` pendant `duration`
+///
+/// Partagé avec la collecte des annonces de relais (`relay`).
+#[cfg(feature = "udp")]
+pub(crate) async fn listen(
+    socket: &tokio::net::UdpSocket,
+    duration: std::time::Duration,
+    mut on_datagram: impl FnMut(&[u8], SocketAddr),
+) {
+    let started_at = Instant::now();
+    let mut buffer = vec![0u8; 2048];
+
+    while let Some(remaining) = duration.checked_sub(started_at.elapsed()) {
+        match tokio::time::timeout(remaining, socket.recv_from(&mut buffer)).await {
+            Ok(Ok((len, source))) => on_datagram(&buffer[..len], source),
+            // Erreur ICMP remontée par le système : on continue d'écouter
+            Ok(Err(_)) => continue,
+            Err(_) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(packet: &NetworkPacket) -> Vec<u8> {
+        let mut data = Vec::new();
+        packet.encode_into(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_roster_keeps_latest_announcement() {
+        let now = Instant::now();
+        let salon: SocketAddr = "192.168.1.10:9001".parse().unwrap();
+        let bureau: SocketAddr = "192.168.1.11:9001".parse().unwrap();
+        let mut roster = Vec::new();
+
+        record_announcement(&mut roster, &encoded(&announcement_packet(&PresenceConfig::new("Salon"), 1, 0)), salon, now);
+        record_announcement(&mut roster, &encoded(&announcement_packet(&PresenceConfig::new("Bureau"), 2, 0)), bureau, now);
+        // Renommée, et changement d'adresse (DHCP)
+        let moved: SocketAddr = "192.168.1.20:9001".parse().unwrap();
+        record_announcement(&mut roster, &encoded(&announcement_packet(&PresenceConfig::new("Cuisine"), 1, 0)), moved, now);
+        // Ignorés : sonde de découverte, octets quelconques, checksum faux
+        record_announcement(&mut roster, &encoded(&NetworkPacket::new_discovery(&DiscoveryMessage::Probe, 3, 0)), salon, now);
+        record_announcement(&mut roster, b"hello", salon, now);
+        let mut corrupted = announcement_packet(&PresenceConfig::new("Cave"), 4, 0);
+        corrupted.checksum ^= 1;
+        record_announcement(&mut roster, &encoded(&corrupted), salon, now);
+
+        let names: Vec<_> = roster.iter().map(|peer| (peer.sender_id, peer.display_name.as_str(), peer.addr)).collect();
+        assert_eq!(names, vec![(1, "Cuisine", moved), (2, "Bureau", bureau)]);
+        assert_eq!(roster[0].capabilities, PresenceCapabilities::current());
+    }
+
+    #[cfg(feature = "udp")]
+    #[tokio::test]
+    async fn test_collect_presence() {
+        let port = 40951;
+        let listener = bind_listener(port).unwrap();
+        // Le port peut être partagé avec d'autres applications
+        drop(bind_listener(port).unwrap());
+
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let announce = encoded(&announcement_packet(&PresenceConfig::new("Salon"), 7, 0));
+        for _ in 0..2 {
+            sender.send_to(&announce, ("127.0.0.1", port)).unwrap();
+        }
+
+        let roster = collect(&listener, std::time::Duration::from_millis(100)).await;
+        assert_eq!(roster.len(), 1);
+        assert_eq!(roster[0].display_name, "Salon");
+        assert_eq!(roster[0].addr, sender.local_addr().unwrap());
+    }
+}
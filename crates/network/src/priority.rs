@@ -0,0 +1,111 @@
+//! Priorité des paquets de contrôle sur l'audio
+//!
+//! Sous forte charge audio, le buffer d'envoi du socket se remplit : un
+//! heartbeat émis par le thread dédié (socket non bloquant) serait perdu, et
+//! le pair finirait par couper une connexion pourtant vivante. Avec
+//! `NetworkConfig::control_priority`, un paquet de contrôle refusé faute de
+//! place est mis de côté, et le manager l'envoie avant la prochaine frame
+//! audio : le contrôle passe toujours devant.
+//!
+//! Un second socket réservé au contrôle n'est pas envisageable : le pair
+//! reconnaît la session à l'adresse source de ses paquets.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::{KeepaliveSink, NetworkError, NetworkPacket, NetworkResult};
+
+/// Paquets de contrôle en attente de place dans le buffer d'envoi
+#[derive(Debug, Default)]
+pub(crate) struct ControlQueue {
+    pending: Mutex<VecDeque<(NetworkPacket, SocketAddr)>>,
+    deferred: AtomicU64,
+}
+
+impl ControlQueue {
+    /// Paquets gardés au plus (les plus anciens sont abandonnés)
+    pub(crate) const CAPACITY: usize = 8;
+
+    /// Met un paquet de côté
+    ///
+    /// Un paquet du même type vers la même adresse est remplacé : seul le
+    /// heartbeat le plus récent a un intérêt.
+    pub(crate) fn defer(&self, packet: NetworkPacket, target: SocketAddr) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|(queued, queued_target)| queued.packet_type != packet.packet_type || *queued_target != target);
+        pending.push_back((packet, target));
+        while pending.len() > Self::CAPACITY {
+            pending.pop_front();
+        }
+        self.deferred.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Retire tous les paquets en attente, dans l'ordre
+    pub(crate) fn take_all(&self) -> Vec<(NetworkPacket, SocketAddr)> {
+        self.pending.lock().unwrap().drain(..).collect()
+    }
+
+    /// Paquets mis de côté depuis la création
+    pub(crate) fn deferred_count(&self) -> u64 {
+        self.deferred.load(Ordering::Relaxed)
+    }
+}
+
+/// Chemin d'envoi synchrone qui met de côté les paquets refusés faute de place
+pub(crate) struct PrioritySink {
+    inner: Arc<dyn KeepaliveSink>,
+    queue: Arc<ControlQueue>,
+}
+
+impl PrioritySink {
+    pub(crate) fn new(inner: Arc<dyn KeepaliveSink>, queue: Arc<ControlQueue>) -> Self {
+        Self { inner, queue }
+    }
+}
+
+impl KeepaliveSink for PrioritySink {
+    fn send_now(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+        match self.inner.send_now(packet, target_addr) {
+            Err(NetworkError::IoError(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                self.queue.defer(packet.clone(), target_addr);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Buffer d'envoi toujours plein
+    struct FullSink;
+
+    impl KeepaliveSink for FullSink {
+        fn send_now(&self, _packet: &NetworkPacket, _target_addr: SocketAddr) -> NetworkResult<()> {
+            Err(std::io::Error::from(std::io::ErrorKind::WouldBlock).into())
+        }
+    }
+
+    #[test]
+    fn test_full_buffer_defers_latest_control_packet() {
+        let queue = Arc::new(ControlQueue::default());
+        let sink = PrioritySink::new(Arc::new(FullSink), queue.clone());
+        let peer: SocketAddr = "127.0.0.1:9002".parse().unwrap();
+
+        sink.send_now(&NetworkPacket::new_heartbeat(1, 10), peer).unwrap();
+        sink.send_now(&NetworkPacket::new_heartbeat(1, 11), peer).unwrap();
+        let disconnect = NetworkPacket::new(crate::PacketType::Disconnect, crate::PacketPayload::None, 1, 11);
+        sink.send_now(&disconnect, peer).unwrap();
+        assert_eq!(queue.deferred_count(), 3);
+
+        // Seul le dernier heartbeat reste, suivi de la déconnexion
+        let pending = queue.take_all();
+        let sessions: Vec<_> = pending.iter().map(|(packet, _)| (packet.packet_type, packet.session_id)).collect();
+        assert_eq!(sessions, vec![(crate::PacketType::Heartbeat, 11), (crate::PacketType::Disconnect, 11)]);
+        assert!(queue.take_all().is_empty());
+    }
+}
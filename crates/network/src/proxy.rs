@@ -0,0 +1,477 @@
+//! Transport UDP via un proxy SOCKS5 (UDP ASSOCIATE, RFC 1928)
+//!
+//! Certains réseaux imposent de passer par un proxy SOCKS5. Ce transport
+//! ouvre une connexion de contrôle TCP vers le proxy, négocie une association
+//! UDP, puis encapsule chaque datagramme avec l'en-tête SOCKS5 avant de
+//! l'envoyer au relais UDP du proxy.
+//!
+//! L'association reste valide tant que la connexion TCP de contrôle est ouverte.
+
+use async_trait::async_trait;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+
+use crate::capture;
+use crate::transport::{decode_packet, encode_packet, pad_datagram};
+use crate::{
+    NetworkTransport, NetworkPacket, NetworkStats, NetworkConfig, ProxyConfig,
+    NetworkResult, NetworkError, PacketTap, TapDirection, ReceiveTimings
+};
+
+/// Version du protocole SOCKS
+const SOCKS_VERSION: u8 = 5;
+
+/// Types d'adresse SOCKS5
+const ATYP_IPV4: u8 = 1;
+const ATYP_IPV6: u8 = 4;
+
+/// Transport UDP encapsulé dans un proxy SOCKS5
+///
+/// Sélectionné automatiquement par `UdpNetworkManager::new` quand
+/// `NetworkConfig::proxy` est renseigné.
+///
+/// # Example
+/// ```rust,no_run
+/// use network::{Socks5UdpTransport, NetworkConfig, NetworkTransport, ProxyConfig};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut config = NetworkConfig::default();
+/// config.proxy = Some(ProxyConfig::socks5("10.0.0.1:1080".parse()?));
+///
+/// let mut transport = Socks5UdpTransport::new(config)?;
+/// transport.bind(0).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Socks5UdpTransport {
+    /// Configuration réseau
+    config: NetworkConfig,
+
+    /// Configuration du proxy
+    proxy: ProxyConfig,
+
+    /// Connexion TCP de contrôle (maintient l'association UDP)
+    control: Option<TcpStream>,
+
+    /// Socket UDP local
+    socket: Option<Arc<UdpSocket>>,
+
+    /// Adresse du relais UDP annoncée par le proxy
+    relay_addr: Option<SocketAddr>,
+
+    /// Statistiques réseau (partagées entre envois et réceptions concurrents)
+    stats: Mutex<NetworkStats>,
+
+    /// Adresse locale d'écoute
+    local_addr: Option<SocketAddr>,
+
+    /// Tap de capture (datagrammes sans l'en-tête SOCKS5)
+    tap: Option<PacketTap>,
+
+    /// Durées des étapes de réception (si `config.timing_stats`)
+    timings: Option<ReceiveTimings>,
+
+    /// Taille des paquets audio et factices en mode padding
+    padding: Option<usize>,
+}
+
+impl Socks5UdpTransport {
+    /// Crée un transport SOCKS5 à partir de `config.proxy`
+    ///
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` : Aucun proxy configuré, ou identifiants
+    ///   trop longs (`ProxyConfig::MAX_CREDENTIAL_LEN`)
+    pub fn new(config: NetworkConfig) -> NetworkResult<Self> {
+        let proxy = config.proxy.clone().ok_or_else(|| {
+            NetworkError::ConfigError("aucun proxy SOCKS5 configuré".to_string())
+        })?;
+        proxy.validate()?;
+
+        Ok(Self {
+            timings: config.timing_stats.then(ReceiveTimings::new),
+            config,
+            proxy,
+            control: None,
+            socket: None,
+            relay_addr: None,
+            stats: Mutex::new(NetworkStats::new()),
+            local_addr: None,
+            tap: None,
+            padding: None,
+        })
+    }
+
+    /// Adresse du relais UDP négociée avec le proxy
+    pub fn relay_addr(&self) -> Option<SocketAddr> {
+        self.relay_addr
+    }
+
+    /// Négocie l'authentification puis l'association UDP
+    ///
+    /// Retourne l'adresse du relais UDP à utiliser.
+    async fn negotiate(&self, stream: &mut TcpStream) -> NetworkResult<SocketAddr> {
+        let credentials = self.proxy.username.as_deref().zip(self.proxy.password.as_deref());
+
+        // Salutation : méthodes proposées (0 = sans auth, 2 = username/password)
+        let greeting: &[u8] = if credentials.is_some() {
+            &[SOCKS_VERSION, 2, 0x00, 0x02]
+        } else {
+            &[SOCKS_VERSION, 1, 0x00]
+        };
+        stream.write_all(greeting).await?;
+
+        let mut choice = [0u8; 2];
+        stream.read_exact(&mut choice).await?;
+        if choice[0] != SOCKS_VERSION {
+            return Err(proxy_error("version SOCKS inattendue"));
+        }
+
+        match (choice[1], credentials) {
+            (0x00, _) => {}
+            (0x02, Some((username, password))) => {
+                // Sous-négociation username/password (RFC 1929)
+                let mut auth = vec![0x01, username.len() as u8];
+                auth.extend_from_slice(username.as_bytes());
+                auth.push(password.len() as u8);
+                auth.extend_from_slice(password.as_bytes());
+                stream.write_all(&auth).await?;
+
+                let mut status = [0u8; 2];
+                stream.read_exact(&mut status).await?;
+                if status[1] != 0 {
+                    return Err(proxy_error("authentification refusée"));
+                }
+            }
+            _ => return Err(proxy_error("aucune méthode d'authentification acceptée")),
+        }
+
+        // Requête UDP ASSOCIATE (adresse client inconnue : 0.0.0.0:0)
+        let mut request = vec![SOCKS_VERSION, 0x03, 0x00];
+        write_socks_addr(&mut request, SocketAddr::from(([0, 0, 0, 0], 0)));
+        stream.write_all(&request).await?;
+
+        let mut reply_header = [0u8; 4];
+        stream.read_exact(&mut reply_header).await?;
+        if reply_header[1] != 0x00 {
+            return Err(proxy_error(&format!("UDP ASSOCIATE refusé (code {})", reply_header[1])));
+        }
+
+        let relay_addr = match reply_header[3] {
+            ATYP_IPV4 => {
+                let mut addr = [0u8; 6];
+                stream.read_exact(&mut addr).await?;
+                SocketAddr::new(
+                    IpAddr::V4(Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3])),
+                    u16::from_be_bytes([addr[4], addr[5]]),
+                )
+            }
+            ATYP_IPV6 => {
+                let mut addr = [0u8; 18];
+                stream.read_exact(&mut addr).await?;
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&addr[..16]);
+                SocketAddr::new(
+                    IpAddr::V6(Ipv6Addr::from(octets)),
+                    u16::from_be_bytes([addr[16], addr[17]]),
+                )
+            }
+            _ => return Err(proxy_error("type d'adresse de relais non supporté")),
+        };
+
+        // Un relais en 0.0.0.0 signifie "même hôte que le proxy"
+        if relay_addr.ip().is_unspecified() {
+            Ok(SocketAddr::new(self.proxy.addr.ip(), relay_addr.port()))
+        } else {
+            Ok(relay_addr)
+        }
+    }
+}
+
+#[async_trait]
+impl NetworkTransport for Socks5UdpTransport {
+    /// Ouvre le socket UDP local et négocie l'association avec le proxy
+    async fn bind(&mut self, local_port: u16) -> NetworkResult<()> {
+        if self.socket.is_some() {
+            return Err(NetworkError::InvalidState {
+                operation: "bind".to_string(),
+                current_state: "already bound".to_string(),
+            });
+        }
+
+        let socket = UdpSocket::bind(self.config.bind_addr(local_port)?).await
+            .map_err(|e| NetworkError::bind_failed(local_port, e))?;
+
+        let mut control = timeout(self.config.connection_timeout, TcpStream::connect(self.proxy.addr))
+            .await
+            .map_err(|_| NetworkError::connection_timeout(
+                self.proxy.addr,
+                self.config.connection_timeout.as_millis() as u32,
+            ))??;
+
+        let relay_addr = timeout(self.config.connection_timeout, self.negotiate(&mut control))
+            .await
+            .map_err(|_| NetworkError::connection_timeout(
+                self.proxy.addr,
+                self.config.connection_timeout.as_millis() as u32,
+            ))??;
+
+        self.local_addr = socket.local_addr().ok();
+        self.socket = Some(Arc::new(socket));
+        self.control = Some(control);
+        self.relay_addr = Some(relay_addr);
+
+        println!("Transport SOCKS5 actif via {} (relais UDP {})", self.proxy.addr, relay_addr);
+        Ok(())
+    }
+
+    /// Encapsule le paquet avec l'en-tête SOCKS5 et l'envoie au relais
+    async fn send_packet(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+        let (socket, relay_addr) = match (&self.socket, self.relay_addr) {
+            (Some(socket), Some(relay)) => (socket, relay),
+            _ => return Err(NetworkError::InvalidState {
+                operation: "send_packet".to_string(),
+                current_state: "not bound".to_string(),
+            }),
+        };
+
+        let mut packet_to_send = packet.clone();
+        let mut payload = Vec::with_capacity(2048);
+        encode_packet(&mut packet_to_send, &mut payload)?;
+        if let Some(packet_size) = self.padding {
+            pad_datagram(&packet_to_send, &mut payload, packet_size);
+        }
+
+        let mut datagram = Vec::with_capacity(payload.len() + 22);
+        write_udp_header(&mut datagram, target_addr);
+        datagram.extend_from_slice(&payload);
+
+        socket.send_to(&datagram, relay_addr).await?;
+        capture::emit(&self.tap, TapDirection::Sent, self.local_addr, target_addr, &payload);
+        let mut stats = self.stats.lock().unwrap();
+        stats.packets_sent += 1;
+        stats.record_bytes_sent(datagram.len(), Instant::now());
+        Ok(())
+    }
+
+    /// Reçoit un datagramme du relais et retire l'en-tête SOCKS5
+    async fn receive_packet(&self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
+        let socket = self.socket.as_ref()
+            .ok_or_else(|| NetworkError::InvalidState {
+                operation: "receive_packet".to_string(),
+                current_state: "not bound".to_string(),
+            })?;
+
+        let mut receive_buffer = vec![0u8; 2048];
+        let (bytes_received, source) = timeout(
+            self.config.connection_timeout,
+            socket.recv_from(&mut receive_buffer)
+        ).await.map_err(|_| NetworkError::Timeout)??;
+
+        // Seul le relais du proxy est autorisé à nous envoyer des datagrammes
+        if Some(source) != self.relay_addr {
+            return Err(NetworkError::InvalidPacketFormat { addr: source });
+        }
+
+        let (peer_addr, header_len) = parse_udp_header(&receive_buffer[..bytes_received])
+            .ok_or(NetworkError::InvalidPacketFormat { addr: source })?;
+
+        capture::emit(
+            &self.tap,
+            TapDirection::Received,
+            self.local_addr,
+            peer_addr,
+            &receive_buffer[header_len..bytes_received],
+        );
+
+        let packet = decode_packet(
+            &receive_buffer[header_len..bytes_received],
+            peer_addr,
+            &self.config,
+            self.timings.as_ref(),
+        )?;
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.packets_received += 1;
+        stats.record_bytes_received(bytes_received, Instant::now());
+        Ok((packet, peer_addr))
+    }
+
+    /// Ferme l'association (connexion de contrôle) et le socket UDP
+    async fn shutdown(&mut self) -> NetworkResult<()> {
+        if let Some(mut control) = self.control.take() {
+            let _ = control.shutdown().await;
+        }
+        self.socket = None;
+        self.relay_addr = None;
+        self.local_addr = None;
+        self.stats.lock().unwrap().reset();
+
+        println!("Transport SOCKS5 arrêté");
+        Ok(())
+    }
+
+    fn stats(&self) -> NetworkStats {
+        let mut stats = self.stats.lock().unwrap().clone();
+        stats.refresh_bitrates(Instant::now());
+        stats
+    }
+
+    async fn reset_stats(&self) {
+        self.stats.lock().unwrap().reset();
+        if let Some(timings) = &self.timings {
+            timings.reset();
+        }
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn is_active(&self) -> bool {
+        self.socket.is_some() && self.control.is_some()
+    }
+
+    fn set_tap(&mut self, tap: Option<PacketTap>) {
+        self.tap = tap;
+    }
+
+    fn set_padding(&mut self, packet_size: Option<usize>) {
+        self.padding = packet_size;
+    }
+
+    fn receive_timings(&self) -> Option<&ReceiveTimings> {
+        self.timings.as_ref()
+    }
+}
+
+/// Erreur de négociation avec le proxy
+fn proxy_error(reason: &str) -> NetworkError {
+    NetworkError::InitializationError(format!("Proxy SOCKS5 : {}", reason))
+}
+
+/// Écrit ATYP + adresse + port au format SOCKS5
+fn write_socks_addr(buffer: &mut Vec<u8>, addr: SocketAddr) {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            buffer.push(ATYP_IPV4);
+            buffer.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buffer.push(ATYP_IPV6);
+            buffer.extend_from_slice(&ip.octets());
+        }
+    }
+    buffer.extend_from_slice(&addr.port().to_be_bytes());
+}
+
+/// Écrit l'en-tête d'un datagramme UDP SOCKS5 (RSV, FRAG, adresse cible)
+fn write_udp_header(buffer: &mut Vec<u8>, target_addr: SocketAddr) {
+    buffer.extend_from_slice(&[0x00, 0x00, 0x00]); // RSV (2) + FRAG = 0
+    write_socks_addr(buffer, target_addr);
+}
+
+/// Analyse l'en-tête d'un datagramme UDP SOCKS5
+///
+/// Retourne l'adresse du pair d'origine et la taille de l'en-tête.
+/// Les datagrammes fragmentés (FRAG != 0) ne sont pas supportés.
+fn parse_udp_header(data: &[u8]) -> Option<(SocketAddr, usize)> {
+    if data.len() < 4 || data[2] != 0 {
+        return None;
+    }
+
+    match data[3] {
+        ATYP_IPV4 if data.len() >= 10 => {
+            let ip = Ipv4Addr::new(data[4], data[5], data[6], data[7]);
+            let port = u16::from_be_bytes([data[8], data[9]]);
+            Some((SocketAddr::new(IpAddr::V4(ip), port), 10))
+        }
+        ATYP_IPV6 if data.len() >= 22 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&data[4..20]);
+            let port = u16::from_be_bytes([data[20], data[21]]);
+            Some((SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port), 22))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NetworkConfig;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_udp_header_roundtrip() {
+        for addr in ["192.168.1.10:9001", "[fe80::1]:9001"] {
+            let addr: SocketAddr = addr.parse().unwrap();
+            let mut buffer = Vec::new();
+            write_udp_header(&mut buffer, addr);
+            buffer.extend_from_slice(b"payload");
+
+            let (parsed, header_len) = parse_udp_header(&buffer).unwrap();
+            assert_eq!(parsed, addr);
+            assert_eq!(&buffer[header_len..], b"payload");
+        }
+
+        // Datagramme fragmenté rejeté
+        assert!(parse_udp_header(&[0, 0, 1, ATYP_IPV4, 1, 2, 3, 4, 0, 80]).is_none());
+    }
+
+    #[test]
+    fn test_rejects_long_credentials() {
+        let long = "x".repeat(ProxyConfig::MAX_CREDENTIAL_LEN + 1);
+        let mut config = NetworkConfig::test_config();
+        config.proxy = Some(ProxyConfig::socks5("127.0.0.1:1080".parse().unwrap()).with_credentials("alice", &long));
+        assert!(config.validate().unwrap_err().to_string().contains("proxy.password"));
+        assert!(matches!(Socks5UdpTransport::new(config), Err(NetworkError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_udp_associate_echo() {
+        // Faux proxy : accepte l'association et renvoie chaque datagramme tel quel
+        let control_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = control_listener.local_addr().unwrap();
+        let relay = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let relay_port = relay.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = control_listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&[SOCKS_VERSION, 0x00]).await.unwrap();
+
+            let mut request = [0u8; 10];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[1], 0x03); // UDP ASSOCIATE
+            let mut reply = vec![SOCKS_VERSION, 0x00, 0x00];
+            write_socks_addr(&mut reply, SocketAddr::from(([0, 0, 0, 0], relay_port)));
+            stream.write_all(&reply).await.unwrap();
+
+            let mut buffer = [0u8; 2048];
+            let (len, client) = relay.recv_from(&mut buffer).await.unwrap();
+            relay.send_to(&buffer[..len], client).await.unwrap();
+
+            // Garde la connexion de contrôle ouverte le temps du test
+            let _ = stream.read(&mut [0u8; 1]).await;
+        });
+
+        let mut config = NetworkConfig::test_config();
+        config.proxy = Some(ProxyConfig::socks5(proxy_addr));
+        let mut transport = Socks5UdpTransport::new(config).unwrap();
+        transport.bind(0).await.unwrap();
+        assert_eq!(transport.relay_addr().unwrap().port(), relay_port);
+
+        let peer: SocketAddr = "10.1.2.3:9001".parse().unwrap();
+        let packet = NetworkPacket::new_heartbeat(7, 8);
+        transport.send_packet(&packet, peer).await.unwrap();
+
+        let (received, source) = transport.receive_packet().await.unwrap();
+        assert_eq!(source, peer);
+        assert_eq!(received.sender_id, 7);
+    }
+}
@@ -0,0 +1,222 @@
+//! Historique de la qualité de connexion
+//!
+//! Un échantillon par période (RTT, perte, gigue, débit ; une seconde par
+//! défaut, `NetworkConfig::stats_sample_period`) est conservé dans un
+//! buffer circulaire : les interfaces peuvent tracer des sparklines sans
+//! monter leur propre échantillonnage.
+//!
+//! `RateWindow` mesure en continu les débits émis et reçus d'un transport,
+//! sur une fenêtre glissante (voir `NetworkStats::send_bitrate_kbps`).
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Mesures de la connexion sur une période d'échantillonnage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualitySample {
+    /// Fin de la période mesurée
+    pub at: Instant,
+    /// RTT moyen en millisecondes
+    pub rtt_ms: f32,
+    /// Pourcentage de paquets perdus en réception sur la période
+    pub loss_percent: f32,
+    /// Gigue moyenne en millisecondes
+    pub jitter_ms: f32,
+    /// Débit émis sur la période, en bits par seconde
+    pub bitrate_bps: u32,
+}
+
+/// Compteurs cumulés au moment d'un échantillon
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct QualityCounters {
+    pub(crate) received: u64,
+    pub(crate) lost: u64,
+    pub(crate) bytes_sent: u64,
+}
+
+/// Buffer circulaire des derniers échantillons de qualité
+#[derive(Debug)]
+pub(crate) struct QualityHistory {
+    samples: VecDeque<QualitySample>,
+    /// Début de la période en cours et compteurs à ce moment
+    period_start: Option<(Instant, QualityCounters)>,
+    /// Durée d'une période d'échantillonnage
+    sample_period: Duration,
+}
+
+impl QualityHistory {
+    /// Nombre d'échantillons conservés (une minute à un échantillon par seconde)
+    pub(crate) const CAPACITY: usize = 60;
+
+    /// Crée un historique vide, échantillonné toutes les `sample_period`
+    pub(crate) fn new(sample_period: Duration) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::CAPACITY),
+            period_start: None,
+            sample_period,
+        }
+    }
+
+    /// Indique si `record` produirait un échantillon à `now`
+    ///
+    /// Permet à l'appelant de ne rassembler les mesures qu'à ce moment.
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.period_start.is_none_or(|(start, _)| now.duration_since(start) >= self.sample_period)
+    }
+
+    /// Ajoute un échantillon si la période en cours est écoulée
+    ///
+    /// Appelé à chaque envoi ou réception : la perte et le débit sont
+    /// calculés sur la différence des compteurs depuis l'échantillon précédent.
+    /// Retourne l'échantillon ajouté, s'il y en a un.
+    pub(crate) fn record(&mut self, now: Instant, counters: QualityCounters, rtt_ms: f32, jitter_ms: f32) -> Option<QualitySample> {
+        let Some((start, previous)) = self.period_start else {
+            self.period_start = Some((now, counters));
+            return None;
+        };
+        let elapsed = now.duration_since(start);
+        if elapsed < self.sample_period {
+            return None;
+        }
+
+        let received = counters.received.saturating_sub(previous.received);
+        let lost = counters.lost.saturating_sub(previous.lost);
+        let loss_percent = if received + lost == 0 {
+            0.0
+        } else {
+            lost as f32 / (received + lost) as f32 * 100.0
+        };
+        let bytes_sent = counters.bytes_sent.saturating_sub(previous.bytes_sent);
+        let bitrate_bps = (bytes_sent as f64 * 8.0 / elapsed.as_secs_f64()) as u32;
+
+        if self.samples.len() == Self::CAPACITY {
+            self.samples.pop_front();
+        }
+        let sample = QualitySample { at: now, rtt_ms, loss_percent, jitter_ms, bitrate_bps };
+        self.samples.push_back(sample);
+        self.period_start = Some((now, counters));
+        Some(sample)
+    }
+
+    /// Échantillons du plus ancien au plus récent
+    pub(crate) fn samples(&self) -> Vec<QualitySample> {
+        self.samples.iter().copied().collect()
+    }
+
+    /// Vide l'historique (nouvelle connexion)
+    pub(crate) fn clear(&mut self) {
+        self.samples.clear();
+        self.period_start = None;
+    }
+}
+
+/// Octets vus sur une fenêtre glissante de `RateWindow::WINDOW`
+///
+/// Tenue par les transports : absente sans transport intégré.
+#[cfg(any(feature = "udp", feature = "simulator"))]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RateWindow {
+    /// Datagrammes encore dans la fenêtre (instant, taille)
+    events: VecDeque<(Instant, usize)>,
+    /// Somme des tailles de `events`
+    bytes: u64,
+}
+
+#[cfg(any(feature = "udp", feature = "simulator"))]
+impl RateWindow {
+    /// Durée sur laquelle le débit est moyenné
+    pub(crate) const WINDOW: Duration = crate::NetworkStats::BITRATE_WINDOW;
+
+    /// Compte un datagramme de `bytes` octets vu à `now`
+    pub(crate) fn record(&mut self, now: Instant, bytes: usize) {
+        self.prune(now);
+        self.events.push_back((now, bytes));
+        self.bytes += bytes as u64;
+    }
+
+    /// Débit moyen sur la fenêtre se terminant à `now`, en kbit/s
+    pub(crate) fn kbps(&mut self, now: Instant) -> f32 {
+        self.prune(now);
+        self.bytes as f32 * 8.0 / 1000.0 / Self::WINDOW.as_secs_f32()
+    }
+
+    /// Retire les datagrammes sortis de la fenêtre
+    fn prune(&mut self, now: Instant) {
+        while let Some(&(at, bytes)) = self.events.front() {
+            if now.saturating_duration_since(at) < Self::WINDOW {
+                break;
+            }
+            self.events.pop_front();
+            self.bytes -= bytes as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_samples_each_second_and_wraps() {
+        let mut history = QualityHistory::new(Duration::from_secs(1));
+        let start = Instant::now();
+        let mut counters = QualityCounters::default();
+        history.record(start, counters, 0.0, 0.0);
+
+        // 50 paquets reçus, 50 perdus et 4000 octets envoyés par seconde
+        for second in 1..=(QualityHistory::CAPACITY as u64 + 5) {
+            counters.received += 50;
+            counters.lost += 50;
+            counters.bytes_sent += 4000;
+            // Les appels intermédiaires ne produisent pas d'échantillon
+            history.record(start + Duration::from_millis(second * 1000 - 500), counters, 20.0, 5.0);
+            history.record(start + Duration::from_secs(second), counters, 20.0, 5.0);
+        }
+
+        let samples = history.samples();
+        assert_eq!(samples.len(), QualityHistory::CAPACITY);
+        assert_eq!(samples[0].at, start + Duration::from_secs(6));
+        let last = samples[QualityHistory::CAPACITY - 1];
+        assert_eq!(last.bitrate_bps, 32_000);
+        assert!((last.loss_percent - 50.0).abs() < 0.01);
+        assert_eq!((last.rtt_ms, last.jitter_ms), (20.0, 5.0));
+
+        history.clear();
+        assert!(history.samples().is_empty());
+        assert!(history.is_due(start));
+    }
+
+    #[test]
+    fn test_custom_sample_period() {
+        let mut history = QualityHistory::new(Duration::from_secs(5));
+        let start = Instant::now();
+        history.record(start, QualityCounters::default(), 0.0, 0.0);
+
+        assert!(!history.is_due(start + Duration::from_secs(3)));
+        history.record(start + Duration::from_secs(3), QualityCounters::default(), 0.0, 0.0);
+        assert!(history.samples().is_empty());
+
+        assert!(history.is_due(start + Duration::from_secs(5)));
+        history.record(start + Duration::from_secs(5), QualityCounters::default(), 0.0, 0.0);
+        assert_eq!(history.samples().len(), 1);
+    }
+
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    #[test]
+    fn test_rate_window_slides() {
+        let mut window = RateWindow::default();
+        let start = Instant::now();
+        assert_eq!(window.kbps(start), 0.0);
+
+        // 50 datagrammes de 100 octets par seconde : 40 kbit/s
+        for packet in 0..100u64 {
+            window.record(start + Duration::from_millis(packet * 20), 100);
+        }
+        let end = start + Duration::from_millis(1990);
+        assert!((window.kbps(end) - 40.0).abs() < 0.01);
+
+        // Une seconde sans trafic : la moitié de la fenêtre est vide
+        assert!((window.kbps(end + Duration::from_secs(1)) - 20.0).abs() < 1.0);
+        assert_eq!(window.kbps(end + RateWindow::WINDOW), 0.0);
+    }
+}
@@ -0,0 +1,515 @@
+//! Relais audio pour les pairs injoignables en direct
+//!
+//! Quand aucun chemin direct n'existe entre deux pairs (NAT symétriques,
+//! pare-feu), l'audio peut transiter par un relais exploité par un
+//! utilisateur (`RelayServer`). Chaque datagramme envoyé au relais est
+//! précédé d'un en-tête portant l'adresse du destinataire ; le relais le
+//! transmet en remplaçant cette adresse par celle de l'expéditeur. Le
+//! destinataire reconnaît l'en-tête et répond par le même chemin.
+//!
+//! Les relais annoncent leur présence en broadcast sur le port des annonces
+//! de présence (`utils::collect_relays`) et répondent aux sondes `ping` : le
+//! manager choisit ainsi le relais le plus proche parmi
+//! `NetworkConfig::relays` quand la connexion directe échoue.
+//!
+//! Les datagrammes à relayer sont mis en file par expéditeur et servis à
+//! tour de rôle (`FairScheduler`) : la rafale d'un pair ne retarde pas
+//! l'audio des autres, et chaque pair peut être plafonné en débit.
+//!
+//! Le relais ne vérifie pas les en-têtes : à réserver à des pairs de
+//! confiance, comme un proxy ouvert.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::{DiscoveryMessage, NetworkPacket, Pacer};
+
+/// Octets magiques en tête des datagrammes relayés
+pub const RELAY_MAGIC: [u8; 4] = *b"VOCR";
+
+/// Familles d'adresse de l'en-tête
+const FAMILY_IPV4: u8 = 4;
+const FAMILY_IPV6: u8 = 6;
+
+/// Relais à emprunter pour chaque pair, partagé entre le transport et son
+/// chemin d'envoi synchrone (heartbeats)
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) type RelayRoutes = Arc<Mutex<HashMap<SocketAddr, SocketAddr>>>;
+
+/// Écrit dans `buffer` l'en-tête de relais suivi de `payload`
+///
+/// `addr` est le destinataire à l'aller (vers le relais), l'expéditeur au
+/// retour (depuis le relais).
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) fn encapsulate(buffer: &mut Vec<u8>, addr: SocketAddr, payload: &[u8]) {
+    buffer.clear();
+    buffer.extend_from_slice(&RELAY_MAGIC);
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            buffer.push(FAMILY_IPV4);
+            buffer.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buffer.push(FAMILY_IPV6);
+            buffer.extend_from_slice(&ip.octets());
+        }
+    }
+    buffer.extend_from_slice(&addr.port().to_be_bytes());
+    buffer.extend_from_slice(payload);
+}
+
+/// Retire l'en-tête de relais d'un datagramme
+///
+/// Retourne l'adresse de l'en-tête et le datagramme d'origine, ou `None`
+/// si le datagramme n'a pas été relayé.
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) fn decapsulate(data: &[u8]) -> Option<(SocketAddr, &[u8])> {
+    let rest = data.strip_prefix(&RELAY_MAGIC)?;
+    let (&family, rest) = rest.split_first()?;
+    let (ip, rest) = match family {
+        FAMILY_IPV4 if rest.len() >= 4 => {
+            let (octets, rest) = rest.split_at(4);
+            (IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3])), rest)
+        }
+        FAMILY_IPV6 if rest.len() >= 16 => {
+            let (octets, rest) = rest.split_at(16);
+            (IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets).ok()?)), rest)
+        }
+        _ => return None,
+    };
+    if rest.len() < 2 {
+        return None;
+    }
+    let (port, payload) = rest.split_at(2);
+    Some((SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]])), payload))
+}
+
+/// Ajoute à `relays` le relais annoncé par le datagramme `data`
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) fn record_announcement(relays: &mut Vec<SocketAddr>, data: &[u8], source: SocketAddr) {
+    let is_announce = NetworkPacket::decode_from(data)
+        .filter(NetworkPacket::verify_checksum)
+        .and_then(|packet| packet.discovery_message())
+        == Some(DiscoveryMessage::RelayAnnounce);
+    if is_announce && !relays.contains(&source) {
+        relays.push(source);
+    }
+}
+
+/// Compteurs d'un `RelayServer`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RelayStats {
+    /// Datagrammes transmis
+    pub forwarded: u64,
+    /// Datagrammes retardés par le plafond de débit de leur expéditeur
+    pub throttled: u64,
+    /// Datagrammes abandonnés, la file de leur expéditeur étant pleine
+    /// ou trop d'expéditeurs ayant des datagrammes en attente
+    pub dropped: u64,
+    /// Expéditeurs inactifs oubliés (avec l'état de leur plafond de débit)
+    /// pour rester sous `MAX_SENDERS`
+    pub evicted: u64,
+}
+
+/// Datagramme relayé en attente d'envoi
+struct Queued {
+    target: SocketAddr,
+    datagram: Vec<u8>,
+    /// Déjà retardé par le plafond (compté une seule fois)
+    held: bool,
+}
+
+/// File d'un expéditeur
+struct SenderQueue {
+    datagrams: VecDeque<Queued>,
+    /// Octets que l'expéditeur peut encore envoyer dans ce tour
+    deficit: usize,
+    pacer: Option<Pacer>,
+}
+
+/// Ordonnanceur équitable des datagrammes relayés (deficit round robin)
+///
+/// Chaque expéditeur a sa file ; les files sont servies à tour de rôle,
+/// au plus `QUANTUM` octets par tour, si bien qu'un pair qui envoie en
+/// rafale ne fait attendre les autres que d'un tour. Un plafond de débit
+/// optionnel s'applique à chaque expéditeur ; au-delà de
+/// `MAX_QUEUED_PER_SENDER`, les datagrammes les plus anciens sont
+/// abandonnés (trop tard pour être joués).
+///
+/// Une file vidée est oubliée, sauf si son expéditeur est plafonné (le seau
+/// du plafond doit survivre entre deux datagrammes). Au-delà de
+/// `MAX_SENDERS` expéditeurs connus, les expéditeurs inactifs sont oubliés :
+/// des adresses sources usurpées ou changeantes ne font pas grossir le
+/// relais indéfiniment.
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+pub(crate) struct FairScheduler {
+    senders: HashMap<SocketAddr, SenderQueue>,
+    /// Ordre de service des expéditeurs ayant des datagrammes en attente
+    order: VecDeque<SocketAddr>,
+    max_bandwidth_bps: Option<u32>,
+    stats: RelayStats,
+}
+
+#[cfg_attr(not(feature = "udp"), allow(dead_code))]
+impl FairScheduler {
+    /// Octets crédités à chaque tour (au moins un datagramme de taille maximale)
+    pub(crate) const QUANTUM: usize = 2048;
+
+    /// Datagrammes en attente par expéditeur (~1s d'audio en frames de 20ms)
+    pub(crate) const MAX_QUEUED_PER_SENDER: usize = 50;
+
+    /// Expéditeurs suivis au plus (files et plafonds de débit)
+    pub(crate) const MAX_SENDERS: usize = 1024;
+
+    pub(crate) fn new(max_bandwidth_bps: Option<u32>) -> Self {
+        Self {
+            senders: HashMap::new(),
+            order: VecDeque::new(),
+            max_bandwidth_bps,
+            stats: RelayStats::default(),
+        }
+    }
+
+    /// Met en file un datagramme de `source` à transmettre à `target`
+    pub(crate) fn push(&mut self, source: SocketAddr, target: SocketAddr, datagram: Vec<u8>) {
+        if !self.senders.contains_key(&source) && self.senders.len() >= Self::MAX_SENDERS {
+            let known = self.senders.len();
+            self.senders.retain(|_, queue| !queue.datagrams.is_empty());
+            self.stats.evicted += (known - self.senders.len()) as u64;
+            if self.senders.len() >= Self::MAX_SENDERS {
+                self.stats.dropped += 1;
+                return;
+            }
+        }
+        let max_bandwidth_bps = self.max_bandwidth_bps;
+        let queue = self.senders.entry(source).or_insert_with(|| SenderQueue {
+            datagrams: VecDeque::new(),
+            deficit: 0,
+            pacer: max_bandwidth_bps.map(Pacer::new),
+        });
+        if queue.datagrams.is_empty() {
+            self.order.push_back(source);
+        }
+        if queue.datagrams.len() == Self::MAX_QUEUED_PER_SENDER {
+            queue.datagrams.pop_front();
+            self.stats.dropped += 1;
+        }
+        queue.datagrams.push_back(Queued { target, datagram, held: false });
+    }
+
+    /// Prochain datagramme à envoyer, avec son destinataire
+    ///
+    /// Retourne `None` si aucune file n'a de datagramme envoyable
+    /// maintenant (files vides, ou expéditeurs au plafond).
+    pub(crate) fn pop(&mut self, now: Instant) -> Option<(SocketAddr, Vec<u8>)> {
+        // Deux passes : la première peut seulement recréditer les expéditeurs
+        for _ in 0..2 * self.order.len() {
+            let source = *self.order.front()?;
+            let queue = self.senders.get_mut(&source)?;
+            let size = queue.datagrams.front()?.datagram.len();
+
+            if queue.deficit < size {
+                queue.deficit += Self::QUANTUM;
+                self.order.rotate_left(1);
+                continue;
+            }
+            if let Some(pacer) = &mut queue.pacer
+                && !pacer.try_reserve(size, now)
+            {
+                let head = queue.datagrams.front_mut()?;
+                if !head.held {
+                    head.held = true;
+                    self.stats.throttled += 1;
+                }
+                self.order.rotate_left(1);
+                continue;
+            }
+
+            queue.deficit -= size;
+            let queued = queue.datagrams.pop_front()?;
+            if queue.datagrams.is_empty() {
+                // Une file vide ne garde pas son crédit pour le tour suivant
+                queue.deficit = 0;
+                if queue.pacer.is_none() {
+                    self.senders.remove(&source);
+                }
+                self.order.pop_front();
+            }
+            self.stats.forwarded += 1;
+            return Some((queued.target, queued.datagram));
+        }
+        None
+    }
+
+    /// Des datagrammes attendent (plafond de débit atteint)
+    pub(crate) fn has_pending(&self) -> bool {
+        !self.order.is_empty()
+    }
+
+    pub(crate) fn stats(&self) -> RelayStats {
+        self.stats
+    }
+}
+
+/// Relais exploité par un utilisateur
+///
+/// Transmet les datagrammes relayés entre pairs, répond aux sondes `ping`
+/// (mesure du RTT par les clients) et peut s'annoncer sur le LAN.
+///
+/// # Example
+/// ```rust,no_run
+/// use network::{RelayServer, PRESENCE_PORT};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// // 128 kbps au plus par pair
+/// let mut relay = RelayServer::bind(9100).await?.with_max_bandwidth(Some(128_000));
+/// println!("Relais actif sur {}", relay.local_addr()?);
+/// relay.run(Some(PRESENCE_PORT)).await;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "udp")]
+pub struct RelayServer {
+    socket: tokio::net::UdpSocket,
+    sender_id: u64,
+    scheduler: FairScheduler,
+    buffer: Vec<u8>,
+}
+
+/// Réveil de la boucle du relais
+#[cfg(feature = "udp")]
+enum Wake {
+    Announce,
+    Datagram(std::io::Result<(usize, SocketAddr)>),
+    Schedule,
+}
+
+#[cfg(feature = "udp")]
+impl RelayServer {
+    /// Intervalle entre deux annonces du relais
+    pub const ANNOUNCE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+    /// Nouvel essai des files retenues par le plafond de débit
+    const SCHEDULE_TICK: std::time::Duration = std::time::Duration::from_millis(5);
+
+    /// Ouvre le relais sur `port` (0 : port choisi par le système)
+    ///
+    /// # Erreurs
+    /// - `NetworkError::BindError` : port indisponible
+    /// - `NetworkError::InitializationError` : source d'aléa du système indisponible
+    pub async fn bind(port: u16) -> crate::NetworkResult<Self> {
+        let socket = tokio::net::UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port))).await
+            .map_err(|e| crate::NetworkError::bind_failed(port, e))?;
+        if let Err(e) = socket.set_broadcast(true) {
+            println!("Broadcast UDP indisponible, relais non annoncé : {}", e);
+        }
+        Ok(Self {
+            socket,
+            sender_id: crate::utils::random_id()?,
+            scheduler: FairScheduler::new(None),
+            buffer: Vec::with_capacity(2048),
+        })
+    }
+
+    /// Plafonne le débit relayé pour chaque expéditeur, en bits/sec
+    /// (`None` : aucun plafond)
+    pub fn with_max_bandwidth(mut self, max_bandwidth_bps: Option<u32>) -> Self {
+        self.scheduler = FairScheduler::new(max_bandwidth_bps);
+        self
+    }
+
+    /// Adresse locale du relais
+    pub fn local_addr(&self) -> crate::NetworkResult<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Datagrammes transmis depuis l'ouverture
+    pub fn forwarded(&self) -> u64 {
+        self.scheduler.stats().forwarded
+    }
+
+    /// Compteurs de transmission et d'application des plafonds
+    pub fn stats(&self) -> RelayStats {
+        self.scheduler.stats()
+    }
+
+    /// Sert les pairs indéfiniment (s'arrête quand la tâche est abandonnée)
+    ///
+    /// # Arguments
+    /// * `announce_port` - Port des annonces en broadcast (`PRESENCE_PORT`),
+    ///   ou `None` pour un relais connu seulement par configuration
+    pub async fn run(&mut self, announce_port: Option<u16>) {
+        let mut announce = tokio::time::interval(Self::ANNOUNCE_INTERVAL);
+        let mut datagram = vec![0u8; 2048];
+
+        loop {
+            let pending = self.scheduler.has_pending();
+            let wake = tokio::select! {
+                _ = announce.tick(), if announce_port.is_some() => Wake::Announce,
+                received = self.socket.recv_from(&mut datagram) => Wake::Datagram(received),
+                _ = tokio::time::sleep(Self::SCHEDULE_TICK), if pending => Wake::Schedule,
+            };
+            match (wake, announce_port) {
+                (Wake::Announce, Some(port)) => {
+                    let packet = NetworkPacket::new_discovery(&DiscoveryMessage::RelayAnnounce, self.sender_id, 0);
+                    let mut encoded = Vec::new();
+                    packet.encode_into(&mut encoded);
+                    if let Err(e) = self.socket.send_to(&encoded, SocketAddr::from(([255, 255, 255, 255], port))).await {
+                        println!("Annonce du relais impossible : {}", e);
+                    }
+                }
+                (Wake::Datagram(Ok((len, source))), _) => self.handle_datagram(&datagram[..len], source).await,
+                // Erreur ICMP remontée par le système (pair disparu) : on continue
+                (Wake::Datagram(Err(_)) | Wake::Announce | Wake::Schedule, _) => {}
+            }
+            self.flush_scheduled().await;
+        }
+    }
+
+    /// Envoie les datagrammes que l'ordonnanceur autorise maintenant
+    async fn flush_scheduled(&mut self) {
+        while let Some((target, datagram)) = self.scheduler.pop(Instant::now()) {
+            // Pair disparu : le datagramme est perdu, comme sur un lien direct
+            let _ = self.socket.send_to(&datagram, target).await;
+        }
+    }
+
+    /// Met en file un datagramme relayé, ou répond à une sonde `ping`
+    async fn handle_datagram(&mut self, data: &[u8], source: SocketAddr) {
+        if let Some((target, payload)) = decapsulate(data) {
+            let mut relayed = Vec::with_capacity(data.len());
+            encapsulate(&mut relayed, source, payload);
+            self.scheduler.push(source, target, relayed);
+            return;
+        }
+
+        let ping = NetworkPacket::decode_from(data)
+            .filter(NetworkPacket::verify_checksum)
+            .and_then(|packet| packet.discovery_message());
+        if let Some(DiscoveryMessage::Ping { seq }) = ping {
+            let pong = NetworkPacket::new_discovery(&DiscoveryMessage::Pong { seq }, self.sender_id, 0);
+            pong.encode_into(&mut self.buffer);
+            let _ = self.socket.send_to(&self.buffer, source).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_header_roundtrip() {
+        for addr in ["192.168.1.10:9001", "[2001:db8::7]:9001"] {
+            let addr: SocketAddr = addr.parse().unwrap();
+            let mut buffer = Vec::new();
+            encapsulate(&mut buffer, addr, b"payload");
+
+            assert_eq!(decapsulate(&buffer), Some((addr, &b"payload"[..])));
+            assert!(decapsulate(&buffer[..buffer.len() - 8]).is_none());
+        }
+
+        // Datagramme Voc ordinaire : pas d'en-tête de relais
+        let mut heartbeat = Vec::new();
+        NetworkPacket::new_heartbeat(1, 2).encode_into(&mut heartbeat);
+        assert!(decapsulate(&heartbeat).is_none());
+    }
+
+    #[test]
+    fn test_record_relay_announcement() {
+        let relay: SocketAddr = "192.168.1.2:9100".parse().unwrap();
+        let mut announce = Vec::new();
+        NetworkPacket::new_discovery(&DiscoveryMessage::RelayAnnounce, 5, 0).encode_into(&mut announce);
+        let mut probe = Vec::new();
+        NetworkPacket::new_discovery(&DiscoveryMessage::Probe, 6, 0).encode_into(&mut probe);
+
+        let mut relays = Vec::new();
+        record_announcement(&mut relays, &announce, relay);
+        record_announcement(&mut relays, &announce, relay);
+        record_announcement(&mut relays, &probe, "192.168.1.3:9001".parse().unwrap());
+        assert_eq!(relays, vec![relay]);
+    }
+
+    #[test]
+    fn test_fair_scheduler_interleaves_senders() {
+        let bursty: SocketAddr = "192.168.1.10:9001".parse().unwrap();
+        let quiet: SocketAddr = "192.168.1.11:9001".parse().unwrap();
+        let target: SocketAddr = "192.168.1.12:9001".parse().unwrap();
+        let now = Instant::now();
+        let mut scheduler = FairScheduler::new(None);
+
+        // Rafale d'un pair, puis une frame de l'autre : servie au deuxième tour
+        for _ in 0..20 {
+            scheduler.push(bursty, target, vec![1; 1000]);
+        }
+        scheduler.push(quiet, target, vec![2; 100]);
+        let first: Vec<u8> = std::iter::from_fn(|| scheduler.pop(now))
+            .take(4)
+            .map(|(_, datagram)| datagram[0])
+            .collect();
+        assert_eq!(first, vec![1, 1, 2, 1]);
+
+        // File pleine : les plus anciens sont abandonnés
+        for _ in 0..FairScheduler::MAX_QUEUED_PER_SENDER + 5 {
+            scheduler.push(quiet, target, vec![2; 100]);
+        }
+        assert_eq!(scheduler.stats().dropped, 5);
+    }
+
+    #[test]
+    fn test_fair_scheduler_caps_each_sender() {
+        let capped: SocketAddr = "192.168.1.10:9001".parse().unwrap();
+        let target: SocketAddr = "192.168.1.12:9001".parse().unwrap();
+        let now = Instant::now();
+        let mut scheduler = FairScheduler::new(Some(80_000)); // 10 000 octets/sec
+
+        for _ in 0..4 {
+            scheduler.push(capped, target, vec![0; 400]);
+        }
+        // Rafale du pacer (500 octets) : deux datagrammes, puis attente
+        assert!(scheduler.pop(now).is_some());
+        assert!(scheduler.pop(now).is_some());
+        assert!(scheduler.pop(now).is_none());
+        assert!(scheduler.has_pending());
+        assert_eq!(scheduler.stats().throttled, 1);
+
+        // Dette remboursée après ~30ms
+        assert!(scheduler.pop(now + std::time::Duration::from_millis(40)).is_some());
+        assert_eq!(scheduler.stats().forwarded, 3);
+    }
+
+    #[test]
+    fn test_fair_scheduler_forgets_idle_senders() {
+        let target: SocketAddr = "192.168.1.12:9001".parse().unwrap();
+        let source = |index: usize| SocketAddr::from(([10, 0, (index >> 8) as u8, index as u8], 9001));
+        let now = Instant::now();
+
+        // Sans plafond, une file vidée est oubliée aussitôt
+        let mut scheduler = FairScheduler::new(None);
+        for index in 0..3 * FairScheduler::MAX_SENDERS {
+            scheduler.push(source(index), target, vec![0; 100]);
+            assert!(scheduler.pop(now).is_some());
+        }
+        assert!(scheduler.senders.is_empty());
+
+        // Plafonnés : les expéditeurs inactifs sont oubliés au-delà de MAX_SENDERS
+        let mut scheduler = FairScheduler::new(Some(80_000));
+        for index in 0..FairScheduler::MAX_SENDERS + 1 {
+            scheduler.push(source(index), target, vec![0; 100]);
+            assert!(scheduler.pop(now).is_some());
+        }
+        assert_eq!(scheduler.senders.len(), 1);
+        assert_eq!(scheduler.stats().evicted, FairScheduler::MAX_SENDERS as u64);
+
+        // Tous en attente : le nouvel expéditeur est refusé
+        for index in 0..FairScheduler::MAX_SENDERS + 1 {
+            scheduler.push(source(10_000 + index), target, vec![0; 100]);
+        }
+        assert_eq!(scheduler.senders.len(), FairScheduler::MAX_SENDERS);
+        assert_eq!(scheduler.stats().evicted, FairScheduler::MAX_SENDERS as u64 + 1);
+        assert_eq!(scheduler.stats().dropped, 1);
+    }
+}
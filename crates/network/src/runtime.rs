@@ -0,0 +1,207 @@
+//! Exécution dans un runtime tokio fourni par l'application
+//!
+//! Les sockets et les timers tokio s'attachent au runtime « courant » au
+//! moment de leur création, et paniquent s'il n'y en a pas. Une application
+//! graphique (egui, tauri) fait souvent tourner son interface hors de tout
+//! runtime, ou possède le sien : elle injecte alors un `Handle`
+//! (`UdpNetworkManager::new_with_runtime`) et le manager entre dans ce runtime
+//! à chaque poll de ses opérations, quel que soit l'exécuteur qui les pilote.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::runtime::Handle;
+use tokio::time::error::Elapsed;
+
+use crate::{KeepaliveSink, KeepaliveSource, NetworkPacket, NetworkResult, NetworkStats, NetworkTransport, PacketTap, ReceiveTimings};
+
+/// Runtime dans lequel le manager crée ses sockets et ses timers
+///
+/// Sans handle injecté, c'est le runtime de l'appelant (comportement tokio habituel).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RuntimeContext {
+    handle: Option<Handle>,
+}
+
+impl RuntimeContext {
+    pub(crate) fn new(handle: Handle) -> Self {
+        Self { handle: Some(handle) }
+    }
+
+    /// Vrai si un runtime a été injecté (`with_runtime`)
+    pub(crate) fn is_injected(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Exécute `future` dans le contexte du runtime injecté
+    pub(crate) fn scope<F: Future>(&self, future: F) -> InRuntime<F> {
+        InRuntime { handle: self.handle.clone(), future: Box::pin(future) }
+    }
+
+    /// Instant courant selon l'horloge du runtime injecté
+    ///
+    /// Identique à `Instant::now()`, sauf sous une horloge suspendue
+    /// (`tokio::time::pause`) : délais et échéances du manager suivent alors
+    /// le temps virtuel de ses timers.
+    pub(crate) fn now(&self) -> Instant {
+        let _guard = self.handle.as_ref().map(Handle::enter);
+        tokio::time::Instant::now().into_std()
+    }
+
+    /// `tokio::time::sleep`, créé dans le runtime injecté (au premier poll)
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        self.scope(async move { tokio::time::sleep(duration).await }).await
+    }
+
+    /// `tokio::time::timeout`, créé dans le runtime injecté (au premier poll)
+    pub(crate) async fn timeout<F: Future>(&self, duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+        self.scope(async move { tokio::time::timeout(duration, future).await }).await
+    }
+}
+
+/// Future pollée à l'intérieur d'un runtime donné
+///
+/// Le contexte n'est tenu que pendant chaque poll : la future reste `Send`
+/// et peut être pilotée par n'importe quel exécuteur.
+pub(crate) struct InRuntime<F> {
+    handle: Option<Handle>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for InRuntime<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _guard = this.handle.as_ref().map(Handle::enter);
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Transport dont chaque opération s'exécute dans un runtime donné
+///
+/// Le bind enregistre ainsi le socket auprès du réacteur de ce runtime, et
+/// les timeouts de réception utilisent son horloge.
+pub(crate) struct RuntimeTransport {
+    inner: Box<dyn NetworkTransport + Send + Sync>,
+    runtime: RuntimeContext,
+}
+
+impl RuntimeTransport {
+    pub(crate) fn new(inner: Box<dyn NetworkTransport + Send + Sync>, runtime: RuntimeContext) -> Self {
+        Self { inner, runtime }
+    }
+}
+
+#[async_trait]
+impl NetworkTransport for RuntimeTransport {
+    async fn bind(&mut self, local_port: u16) -> NetworkResult<()> {
+        self.runtime.scope(self.inner.bind(local_port)).await
+    }
+
+    async fn send_packet(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+        self.runtime.scope(self.inner.send_packet(packet, target_addr)).await
+    }
+
+    async fn receive_packet(&self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
+        self.runtime.scope(self.inner.receive_packet()).await
+    }
+
+    async fn shutdown(&mut self) -> NetworkResult<()> {
+        self.runtime.scope(self.inner.shutdown()).await
+    }
+
+    fn stats(&self) -> NetworkStats {
+        self.inner.stats()
+    }
+
+    async fn reset_stats(&self) {
+        self.runtime.scope(self.inner.reset_stats()).await
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn is_active(&self) -> bool {
+        self.inner.is_active()
+    }
+
+    fn set_tap(&mut self, tap: Option<PacketTap>) {
+        self.inner.set_tap(tap);
+    }
+
+    fn set_padding(&mut self, packet_size: Option<usize>) {
+        self.inner.set_padding(packet_size);
+    }
+
+    fn keepalive_sink(&self) -> Option<Arc<dyn KeepaliveSink>> {
+        self.inner.keepalive_sink()
+    }
+
+    fn keepalive_source(&self) -> Option<Arc<dyn KeepaliveSource>> {
+        self.inner.keepalive_source()
+    }
+
+    fn receive_timings(&self) -> Option<&ReceiveTimings> {
+        self.inner.receive_timings()
+    }
+
+    fn set_relay_route(&mut self, peer: SocketAddr, relay: Option<SocketAddr>) -> NetworkResult<()> {
+        self.inner.set_relay_route(peer, relay)
+    }
+
+    fn relay_route(&self, peer: SocketAddr) -> Option<SocketAddr> {
+        self.inner.relay_route(peer)
+    }
+}
+
+/// Exécuteur minimal, sans runtime tokio, pour les tests
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(std::thread::Thread);
+
+    impl std::task::Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_outside_runtime() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let context = RuntimeContext::new(runtime.handle().clone());
+        assert!(Handle::try_current().is_err());
+
+        // Pilotée par un exécuteur sans runtime tokio : le timer fonctionne quand même
+        let elapsed = block_on(context.timeout(
+            Duration::from_millis(10),
+            std::future::pending::<()>(),
+        ));
+        assert!(elapsed.is_err());
+    }
+}
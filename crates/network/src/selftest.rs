@@ -0,0 +1,250 @@
+//! Auto-diagnostic de la pile complète sans pair distant
+//!
+//! Deux managers reliés par une paire de transports simulés passent un appel
+//! de bout en bout : microphone factice → codec → réseau (avec perte simulée)
+//! → codec → haut-parleur factice. Le rapport indique ce qui fonctionne, pour
+//! aider au support sans avoir besoin d'un second poste.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use audio::{
+    AudioCapture, AudioCodec, AudioConfig, AudioPlayback, AudioResult, MockCapture, MockPlayback,
+};
+
+use crate::{NetworkConfig, NetworkManager, NetworkResult, SimulatedTransport, UdpNetworkManager, utils};
+
+/// Durée de l'appel de test
+pub const SELF_TEST_DURATION: Duration = Duration::from_secs(5);
+
+/// Taux de perte simulé sur le trajet appelant → appelé
+pub const SELF_TEST_LOSS_RATE: f32 = 0.05;
+
+/// Attente des derniers paquets après la fin de l'émission
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Frames finales qui peuvent manquer (perdues) sans que l'appel soit jugé interrompu
+const END_TOLERANCE_FRAMES: u64 = 5;
+
+/// Rapport d'auto-diagnostic
+#[derive(Clone, Debug, Serialize)]
+pub struct SelfTestReport {
+    /// Durée de l'appel simulé
+    pub duration: Duration,
+
+    /// Taux de perte simulé
+    pub simulated_loss_rate: f32,
+
+    /// Frames envoyées par l'appelant
+    pub frames_sent: u64,
+
+    /// Frames reçues et jouées par l'appelé
+    pub frames_received: u64,
+
+    /// Frames manquantes détectées par les numéros de séquence
+    pub frames_lost: u64,
+
+    /// Encodage et décodage sans erreur, taille des frames décodées correcte
+    pub codec_ok: bool,
+
+    /// Frames délivrées dans l'ordre, sans doublon
+    pub buffer_ok: bool,
+
+    /// L'appel a continué malgré la perte simulée
+    pub loss_handled: bool,
+
+    /// Erreurs rencontrées pendant le test
+    pub errors: Vec<String>,
+}
+
+impl SelfTestReport {
+    /// Vrai si toutes les vérifications sont passées
+    pub fn passed(&self) -> bool {
+        self.codec_ok && self.buffer_ok && self.loss_handled
+    }
+
+    /// Génère un résumé textuel du rapport
+    pub fn summary(&self) -> String {
+        let status = |ok: bool| if ok { "OK" } else { "ÉCHEC" };
+        format!(
+            "Auto-diagnostic {} ({:.1}s) : codec {}, buffer {}, pertes {} - {} envoyées, {} reçues, {} perdues ({:.0}% simulées)",
+            status(self.passed()),
+            self.duration.as_secs_f32(),
+            status(self.codec_ok),
+            status(self.buffer_ok),
+            status(self.loss_handled),
+            self.frames_sent,
+            self.frames_received,
+            self.frames_lost,
+            self.simulated_loss_rate * 100.0,
+        )
+    }
+}
+
+/// Déroule l'appel de test
+///
+/// `make_codec` est appelé deux fois : un codec pour l'appelant, un pour l'appelé.
+pub(crate) async fn run_self_test(
+    config: NetworkConfig,
+    audio_config: AudioConfig,
+    duration: Duration,
+    loss_rate: f32,
+    make_codec: impl Fn() -> AudioResult<Box<dyn AudioCodec>>,
+) -> NetworkResult<SelfTestReport> {
+    let mut report = SelfTestReport {
+        duration,
+        simulated_loss_rate: loss_rate,
+        frames_sent: 0,
+        frames_received: 0,
+        frames_lost: 0,
+        codec_ok: false,
+        buffer_ok: false,
+        loss_handled: false,
+        errors: Vec::new(),
+    };
+
+    let (mut encoder, mut decoder) = match (make_codec(), make_codec()) {
+        (Ok(encoder), Ok(decoder)) => (encoder, decoder),
+        (Err(e), _) | (_, Err(e)) => {
+            report.errors.push(format!("Initialisation du codec : {}", e));
+            return Ok(report);
+        }
+    };
+
+    // Paire de managers reliés, sans proxy ni redirection de port
+    let mut config = config;
+    config.proxy = None;
+    config.port_mapping = false;
+    let (mut caller_transport, callee_transport) = SimulatedTransport::pair(config.clone())?;
+    caller_transport.set_simulation_params(0, loss_rate, 0);
+    let mut caller = UdpNetworkManager::with_transport(config.clone(), Box::new(caller_transport))?;
+    let mut callee = UdpNetworkManager::with_transport(config, Box::new(callee_transport))?;
+
+    let (caller_port, callee_port) = (9001, 9002);
+    let (dialed, accepted) = tokio::join!(
+        caller.open(caller_port, Some(utils::localhost(callee_port))),
+        callee.open(callee_port, None),
+    );
+    dialed?;
+    accepted?;
+
+    let mut capture = MockCapture::new(audio_config.clone());
+    let mut playback = MockPlayback::new();
+    capture.start().await?;
+    playback.start().await?;
+
+    let expected_samples = audio_config.samples_per_frame() * audio_config.channels as usize;
+    let mut codec_errors = Vec::new();
+    let mut sequences = Vec::new();
+
+    let deadline = Instant::now() + duration;
+    let send_side = async {
+        let mut sent = 0u64;
+        while Instant::now() < deadline {
+            let frame = capture.next_frame().await?;
+            match encoder.encode(&frame) {
+                Ok(compressed) => {
+                    caller.send_audio(compressed).await?;
+                    sent += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        NetworkResult::Ok(sent)
+    };
+    let receive_side = async {
+        // Reçoit jusqu'à ce que plus rien n'arrive après la fin de l'émission
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now()) + DRAIN_TIMEOUT;
+            let compressed = match tokio::time::timeout(wait, callee.receive_audio()).await {
+                Ok(result) => result?,
+                Err(_) => break,
+            };
+            sequences.push(compressed.sequence_number);
+            // Rafale de pertes : le décodeur repart d'un état neuf à cette frame
+            if callee.take_stream_resync().is_some()
+                && let Err(e) = decoder.reset()
+            {
+                codec_errors.push(format!("Réinitialisation du décodeur : {}", e));
+            }
+            match decoder.decode(&compressed) {
+                Ok(frame) if frame.samples.len() == expected_samples => {
+                    playback.play_frame(frame).await?;
+                }
+                Ok(frame) => codec_errors.push(format!(
+                    "Frame {} décodée avec {} échantillons au lieu de {}",
+                    compressed.sequence_number, frame.samples.len(), expected_samples
+                )),
+                Err(e) => codec_errors.push(format!("Décodage de la frame {} : {}", compressed.sequence_number, e)),
+            }
+        }
+        NetworkResult::Ok(())
+    };
+    let (sent, received) = tokio::join!(send_side, receive_side);
+
+    report.frames_sent = match sent {
+        Ok(sent) => sent,
+        Err(e) => {
+            report.errors.push(format!("Émission : {}", e));
+            0
+        }
+    };
+    if let Err(e) = received {
+        report.errors.push(format!("Réception : {}", e));
+    }
+    report.frames_received = playback.played_frames().len() as u64;
+    report.frames_lost = report.frames_sent.saturating_sub(sequences.len() as u64);
+
+    report.codec_ok = report.frames_sent > 0 && codec_errors.is_empty();
+    report.buffer_ok = !sequences.is_empty() && sequences.windows(2).all(|pair| pair[0] < pair[1]);
+
+    // La perte ne doit ni interrompre l'appel ni faire perdre la majorité des frames
+    let last_sequence = sequences.last().copied().unwrap_or(0);
+    let reached_end = last_sequence + END_TOLERANCE_FRAMES >= report.frames_sent;
+    report.loss_handled = report.errors.is_empty()
+        && reached_end
+        && report.frames_received * 2 >= report.frames_sent;
+    report.errors.extend(codec_errors);
+
+    caller.disconnect().await?;
+    callee.disconnect().await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use audio::{AudioFrame, CompressedFrame};
+
+    /// Codec factice à débit de type Opus (évite de dépendre d'Opus dans les tests)
+    struct FakeCodec;
+
+    impl AudioCodec for FakeCodec {
+        fn encode(&mut self, frame: &AudioFrame) -> AudioResult<CompressedFrame> {
+            Ok(CompressedFrame::new(vec![0x5A; 80], frame.samples.len(), frame.timestamp, frame.sequence_number))
+        }
+
+        fn decode(&mut self, compressed: &CompressedFrame) -> AudioResult<AudioFrame> {
+            Ok(AudioFrame::silence(compressed.original_sample_count, compressed.sequence_number))
+        }
+
+        fn reset(&mut self) -> AudioResult<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_self_test_passes_with_loss() {
+        let report = run_self_test(
+            NetworkConfig::test_config(),
+            AudioConfig::low_latency(),
+            Duration::from_millis(500),
+            0.1,
+            || Ok(Box::new(FakeCodec) as Box<dyn AudioCodec>),
+        ).await.unwrap();
+
+        assert!(report.passed(), "{}", report.summary());
+        assert!(report.frames_sent > 10);
+        assert_eq!(report.frames_sent, report.frames_received + report.frames_lost);
+    }
+}
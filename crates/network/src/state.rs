@@ -0,0 +1,269 @@
+//! Machine à états de la connexion
+//!
+//! Centralise les changements de `ConnectionState` : chaque transition est
+//! validée puis journalisée avec sa raison, dans un historique borné
+//! consultable après coup (« pourquoi mon appel a-t-il coupé ? »).
+//! Les mêmes transitions mesurent le temps passé connecté et comptent les
+//! reconnexions (voir `NetworkStats::total_uptime`).
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::{ConnectionState, NetworkError, NetworkResult, NetworkStats};
+
+/// Transition d'état journalisée
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateTransition {
+    /// Moment de la transition
+    pub at: Instant,
+    /// État quitté
+    pub from: ConnectionState,
+    /// État atteint
+    pub to: ConnectionState,
+    /// Raison de la transition
+    pub reason: String,
+}
+
+impl fmt::Display for StateTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {} ({})", self.from.description(), self.to.description(), self.reason)
+    }
+}
+
+/// État de connexion courant et historique de ses transitions
+#[derive(Debug)]
+pub(crate) struct ConnectionStateMachine {
+    current: ConnectionState,
+    history: VecDeque<StateTransition>,
+    /// Début de la session connectée en cours
+    connected_since: Option<Instant>,
+    /// Temps connecté cumulé des sessions terminées
+    completed_uptime: Duration,
+    /// Pair de la dernière session connectée
+    last_session_peer: Option<SocketAddr>,
+    /// Connexions rétablies avec le pair de la session précédente
+    reconnections: u32,
+}
+
+impl ConnectionStateMachine {
+    /// Nombre de transitions conservées dans l'historique
+    pub(crate) const HISTORY_CAPACITY: usize = 64;
+
+    /// Crée une machine à l'état `Disconnected`
+    pub(crate) fn new() -> Self {
+        Self {
+            current: ConnectionState::Disconnected,
+            history: VecDeque::with_capacity(Self::HISTORY_CAPACITY),
+            connected_since: None,
+            completed_uptime: Duration::ZERO,
+            last_session_peer: None,
+            reconnections: 0,
+        }
+    }
+
+    /// État courant
+    pub(crate) fn current(&self) -> &ConnectionState {
+        &self.current
+    }
+
+    /// Passe dans l'état `to` à l'instant `now` si la transition est autorisée
+    ///
+    /// Rester déconnecté n'est pas une transition et n'est pas journalisé.
+    /// `now` vient de l'horloge du manager (temps virtuel sous
+    /// `tokio::time::pause`), comme pour les mesures de temps connecté.
+    ///
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : transition interdite depuis l'état courant
+    pub(crate) fn transition(&mut self, to: ConnectionState, reason: &str, now: Instant) -> NetworkResult<()> {
+        if self.current == ConnectionState::Disconnected && to == ConnectionState::Disconnected {
+            return Ok(());
+        }
+        if !self.current.can_transition_to(&to) {
+            return Err(NetworkError::InvalidState {
+                operation: format!("transition vers « {} » ({})", to.description(), reason),
+                current_state: self.current.description(),
+            });
+        }
+
+        self.track_uptime(&to, now);
+        let from = std::mem::replace(&mut self.current, to.clone());
+        if self.history.len() == Self::HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(StateTransition {
+            at: now,
+            from,
+            to,
+            reason: reason.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Ouvre ou ferme la session connectée selon l'état atteint
+    ///
+    /// Une reprise (Connected → Connected) prolonge la session en cours ;
+    /// une nouvelle connexion au pair de la session précédente compte comme
+    /// une reconnexion.
+    fn track_uptime(&mut self, to: &ConnectionState, now: Instant) {
+        match (self.current.is_connected(), to.is_connected()) {
+            (false, true) => {
+                let peer = to.peer_addr();
+                if self.last_session_peer.is_some() && self.last_session_peer == peer {
+                    self.reconnections += 1;
+                }
+                self.last_session_peer = peer;
+                self.connected_since = Some(now);
+            }
+            (true, false) => {
+                if let Some(since) = self.connected_since.take() {
+                    self.completed_uptime += now.saturating_duration_since(since);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Durée de la session connectée en cours à `now` (zéro si déconnecté)
+    pub(crate) fn session_uptime(&self, now: Instant) -> Duration {
+        self.connected_since.map_or(Duration::ZERO, |since| now.saturating_duration_since(since))
+    }
+
+    /// Temps passé connecté à `now`, cumulé sur toutes les sessions
+    pub(crate) fn total_uptime(&self, now: Instant) -> Duration {
+        self.completed_uptime + self.session_uptime(now)
+    }
+
+    /// Nombre de reconnexions au pair de la session précédente
+    pub(crate) fn reconnections(&self) -> u32 {
+        self.reconnections
+    }
+
+    /// Reporte le temps connecté à `now` et les reconnexions dans un snapshot des stats
+    pub(crate) fn record_uptime(&self, stats: &mut NetworkStats, now: Instant) {
+        stats.session_uptime_ms = self.session_uptime(now).as_millis() as u64;
+        stats.connection_uptime_ms = self.total_uptime(now).as_millis() as u64;
+        stats.reconnection_count = self.reconnections();
+    }
+
+    /// Remet à zéro le temps cumulé et les reconnexions
+    ///
+    /// Une session en cours est comptée depuis `now`.
+    pub(crate) fn reset_uptime(&mut self, now: Instant) {
+        self.completed_uptime = Duration::ZERO;
+        self.reconnections = 0;
+        if self.connected_since.is_some() {
+            self.connected_since = Some(now);
+        }
+    }
+
+    /// Note la réception d'un heartbeat (sans changer d'état)
+    pub(crate) fn touch_heartbeat(&mut self) {
+        if let ConnectionState::Connected { ref mut last_heartbeat, .. } = self.current {
+            *last_heartbeat = Instant::now();
+        }
+    }
+
+    /// Transitions journalisées, de la plus ancienne à la plus récente
+    pub(crate) fn history(&self) -> Vec<StateTransition> {
+        self.history.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils, SessionRoute};
+
+    fn connecting() -> ConnectionState {
+        ConnectionState::Connecting {
+            target_addr: utils::localhost(9001),
+            started_at: Instant::now(),
+            attempt_count: 1,
+        }
+    }
+
+    fn connected() -> ConnectionState {
+        ConnectionState::Connected {
+            peer_addr: utils::localhost(9001),
+            session_id: 42,
+            connected_at: Instant::now(),
+            last_heartbeat: Instant::now(),
+            route: SessionRoute::Direct,
+        }
+    }
+
+    #[test]
+    fn test_transitions_are_validated_and_logged() {
+        let mut machine = ConnectionStateMachine::new();
+
+        // Pas de connexion sans passer par Connecting
+        assert!(matches!(
+            machine.transition(connected(), "test", Instant::now()),
+            Err(NetworkError::InvalidState { .. })
+        ));
+
+        machine.transition(ConnectionState::Disconnected, "déjà déconnecté", Instant::now()).unwrap();
+        machine.transition(connecting(), "appel", Instant::now()).unwrap();
+        machine.transition(connected(), "handshake réussi", Instant::now()).unwrap();
+        assert!(machine.transition(connecting(), "appel", Instant::now()).is_err());
+        // Reprise de la même session seulement
+        machine.transition(connected(), "session reprise", Instant::now()).unwrap();
+        let mut other_session = connected();
+        if let ConnectionState::Connected { ref mut session_id, .. } = other_session {
+            *session_id = 43;
+        }
+        assert!(machine.transition(other_session, "autre appel", Instant::now()).is_err());
+        machine.transition(ConnectionState::Disconnected, "déconnexion du pair", Instant::now()).unwrap();
+
+        let history = machine.history();
+        assert_eq!(history.len(), 4);
+        assert!(history[1].to.is_connected());
+        assert_eq!(history[3].reason, "déconnexion du pair");
+        assert!(history[3].to_string().ends_with("(déconnexion du pair)"));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut machine = ConnectionStateMachine::new();
+        for attempt in 0..ConnectionStateMachine::HISTORY_CAPACITY {
+            machine.transition(connecting(), &format!("tentative {}", attempt), Instant::now()).unwrap();
+            machine.transition(ConnectionState::Disconnected, "abandon", Instant::now()).unwrap();
+        }
+
+        let history = machine.history();
+        assert_eq!(history.len(), ConnectionStateMachine::HISTORY_CAPACITY);
+        assert_eq!(history.last().unwrap().reason, "abandon");
+    }
+
+    #[test]
+    fn test_uptime_accumulates_across_reconnects() {
+        let mut machine = ConnectionStateMachine::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        assert_eq!(machine.total_uptime(start), Duration::ZERO);
+
+        machine.transition(connecting(), "appel", at(0)).unwrap();
+        machine.transition(connected(), "handshake réussi", at(10)).unwrap();
+        // Une reprise ne démarre pas de nouvelle session
+        machine.transition(connected(), "session reprise", at(30)).unwrap();
+        assert_eq!(machine.session_uptime(at(40)), Duration::from_millis(30));
+        machine.transition(ConnectionState::Disconnected, "coupure", at(50)).unwrap();
+        assert_eq!(machine.session_uptime(at(60)), Duration::ZERO);
+        assert_eq!(machine.total_uptime(at(60)), Duration::from_millis(40));
+        assert_eq!(machine.reconnections(), 0);
+
+        machine.transition(connecting(), "reconnexion", at(100)).unwrap();
+        machine.transition(connected(), "handshake réussi", at(110)).unwrap();
+        let mut stats = NetworkStats::new();
+        machine.record_uptime(&mut stats, at(130));
+        assert_eq!((stats.session_uptime_ms, stats.connection_uptime_ms, stats.reconnection_count), (20, 60, 1));
+
+        // La session en cours repart de l'instant du reset
+        machine.reset_uptime(at(200));
+        assert_eq!(machine.reconnections(), 0);
+        assert_eq!(machine.total_uptime(at(250)), Duration::from_millis(50));
+        assert_eq!(machine.history().last().unwrap().at, at(110));
+    }
+}
@@ -0,0 +1,151 @@
+//! Envoi audio depuis un contexte synchrone (thread de capture temps réel)
+//!
+//! Le callback de capture ne doit ni bloquer ni attendre un verrou : un
+//! mutex tenu par une tâche réseau suffit à provoquer un craquement. Les
+//! frames passent donc par une file bornée sans verrou (`ArrayQueue`), que le
+//! manager vide depuis son côté async (`UdpNetworkManager::send_queued_audio`,
+//! et à chaque tour de `receive_audio`).
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crossbeam_queue::ArrayQueue;
+use thiserror::Error;
+
+use audio::CompressedFrame;
+
+/// Raison du refus d'une frame par `SyncAudioSender::try_send_frame`
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TrySendError {
+    /// File pleine : le réseau ne suit pas, la frame est abandonnée
+    #[error("file d'envoi audio pleine")]
+    Full,
+
+    /// Le manager a été détruit, plus rien ne sera envoyé
+    #[error("manager réseau fermé")]
+    Closed,
+}
+
+/// File partagée entre les handles synchrones et le manager
+#[derive(Debug)]
+struct SyncAudioQueue {
+    frames: ArrayQueue<CompressedFrame>,
+    closed: AtomicBool,
+    rejected: AtomicU64,
+}
+
+/// Handle d'envoi audio utilisable depuis n'importe quel thread, sans runtime
+///
+/// Obtenu par `UdpNetworkManager::sync_audio_sender`. `try_send_frame` ne
+/// prend aucun verrou et ne bloque jamais ; la seule allocation est la copie
+/// des données de la frame. Le handle se clone librement.
+///
+/// # Example
+/// ```rust
+/// use network::{UdpNetworkManager, NetworkConfig, CompressedFrame};
+/// use std::time::Instant;
+///
+/// let mut manager = UdpNetworkManager::new_simulated(NetworkConfig::test_config()).unwrap();
+/// let sender = manager.sync_audio_sender();
+///
+/// // Depuis le callback de capture
+/// let frame = CompressedFrame::new(vec![0; 40], 960, Instant::now(), 1);
+/// sender.try_send_frame(&frame).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyncAudioSender {
+    queue: Arc<SyncAudioQueue>,
+}
+
+impl SyncAudioSender {
+    /// Dépose une copie de la frame dans la file d'envoi, sans attendre
+    ///
+    /// # Erreurs
+    /// - `TrySendError::Full` : file pleine, la frame n'est pas envoyée
+    /// - `TrySendError::Closed` : le manager n'existe plus
+    pub fn try_send_frame(&self, frame: &CompressedFrame) -> Result<(), TrySendError> {
+        if self.queue.closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Closed);
+        }
+        self.queue.frames.push(frame.clone()).map_err(|_| {
+            self.queue.rejected.fetch_add(1, Ordering::Relaxed);
+            TrySendError::Full
+        })
+    }
+
+    /// Frames refusées faute de place depuis la création de la file
+    pub fn rejected_frames(&self) -> u64 {
+        self.queue.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Indique si le manager a été détruit
+    pub fn is_closed(&self) -> bool {
+        self.queue.closed.load(Ordering::Acquire)
+    }
+}
+
+/// Côté manager de la file : la ferme quand il est détruit
+#[derive(Debug)]
+pub(crate) struct SyncAudioReceiver {
+    queue: Arc<SyncAudioQueue>,
+}
+
+impl SyncAudioReceiver {
+    /// Crée une file de `capacity` frames et son côté manager
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            queue: Arc::new(SyncAudioQueue {
+                frames: ArrayQueue::new(capacity.max(1)),
+                closed: AtomicBool::new(false),
+                rejected: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Nouveau handle d'envoi sur cette file
+    pub(crate) fn sender(&self) -> SyncAudioSender {
+        SyncAudioSender { queue: self.queue.clone() }
+    }
+
+    /// Retire la plus ancienne frame en attente
+    pub(crate) fn try_recv(&self) -> Option<CompressedFrame> {
+        self.queue.frames.pop()
+    }
+}
+
+impl Drop for SyncAudioReceiver {
+    fn drop(&mut self) {
+        self.queue.closed.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_sync_sender_full_and_closed() {
+        let receiver = SyncAudioReceiver::new(2);
+        let sender = receiver.sender();
+        let frame = CompressedFrame::new(vec![1; 10], 960, Instant::now(), 1);
+
+        // Depuis un autre thread, comme le callback de capture
+        let remote = sender.clone();
+        let pushed = std::thread::spawn(move || {
+            (0..3).map(|_| remote.try_send_frame(&frame)).collect::<Vec<_>>()
+        }).join().unwrap();
+        assert_eq!(pushed, vec![Ok(()), Ok(()), Err(TrySendError::Full)]);
+        assert_eq!(sender.rejected_frames(), 1);
+
+        assert!(receiver.try_recv().is_some());
+        assert!(receiver.try_recv().is_some());
+        assert!(receiver.try_recv().is_none());
+
+        drop(receiver);
+        assert!(sender.is_closed());
+        let frame = CompressedFrame::new(vec![2; 10], 960, Instant::now(), 2);
+        assert_eq!(sender.try_send_frame(&frame), Err(TrySendError::Closed));
+    }
+}
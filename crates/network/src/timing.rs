@@ -0,0 +1,255 @@
+//! Mesure de la durée des étapes du chemin de réception
+//!
+//! Sur du matériel modeste (Raspberry Pi), le budget de 20ms par frame se
+//! partage entre le réseau, le décodage et la lecture. Activée par
+//! `NetworkConfig::timing_stats`, la collecte chronomètre chaque étape du
+//! traitement d'un datagramme et l'agrège dans un histogramme, lisible via
+//! `NetworkTransport::timing_stats`. Les compteurs sont atomiques : la mesure
+//! ne prend aucun verrou et coûte deux lectures d'horloge par étape.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Nombre de classes de l'histogramme : la dernière couvre 2^23µs (~8s) et plus
+pub const TIMING_BUCKETS: usize = 24;
+
+/// Étape du chemin de réception
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReceiveStage {
+    /// Lecture de la version et désérialisation du datagramme (transport)
+    Deserialize,
+    /// Vérification du checksum (transport)
+    Checksum,
+    /// Traitement du paquet par le moteur : réordonnancement dans le buffer
+    /// anti-jitter (manager, paquets audio)
+    BufferInsert,
+    /// Après l'insertion : actions du protocole et statistiques, jusqu'à la
+    /// remise de la frame à l'appelant de `receive_audio`, qui la décode (manager)
+    DecodeHandoff,
+}
+
+impl ReceiveStage {
+    /// Toutes les étapes, dans l'ordre du chemin de réception
+    pub const ALL: [ReceiveStage; 4] = [
+        ReceiveStage::Deserialize,
+        ReceiveStage::Checksum,
+        ReceiveStage::BufferInsert,
+        ReceiveStage::DecodeHandoff,
+    ];
+
+    /// Nom court, pour l'affichage
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReceiveStage::Deserialize => "désérialisation",
+            ReceiveStage::Checksum => "checksum",
+            ReceiveStage::BufferInsert => "insertion buffer",
+            ReceiveStage::DecodeHandoff => "remise au décodeur",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Compteurs d'une étape
+#[derive(Debug, Default)]
+struct StageCounters {
+    buckets: [AtomicU64; TIMING_BUCKETS],
+    count: AtomicU64,
+    total_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+/// Collecteur partagé des durées d'étapes
+///
+/// Les clones partagent les mêmes compteurs : le transport mesure ses
+/// étapes, le manager y ajoute les siennes.
+///
+/// # Example
+/// ```rust
+/// use network::{ReceiveStage, ReceiveTimings};
+///
+/// let timings = ReceiveTimings::new();
+/// let value = timings.measure(ReceiveStage::Checksum, || 2 + 2);
+/// assert_eq!(value, 4);
+/// assert_eq!(timings.snapshot().stage(ReceiveStage::Checksum).count, 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReceiveTimings {
+    stages: Arc<[StageCounters; 4]>,
+}
+
+impl ReceiveTimings {
+    /// Crée un collecteur vide
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ajoute une mesure à l'étape
+    pub fn record(&self, stage: ReceiveStage, elapsed: Duration) {
+        let counters = &self.stages[stage.index()];
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        counters.buckets[bucket_index(elapsed)].fetch_add(1, Ordering::Relaxed);
+        counters.count.fetch_add(1, Ordering::Relaxed);
+        counters.total_ns.fetch_add(nanos, Ordering::Relaxed);
+        counters.max_ns.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Exécute `f` en chronométrant l'étape
+    pub fn measure<T>(&self, stage: ReceiveStage, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(stage, started.elapsed());
+        result
+    }
+
+    /// Copie des histogrammes à cet instant
+    pub fn snapshot(&self) -> TimingStats {
+        TimingStats {
+            stages: ReceiveStage::ALL.map(|stage| {
+                let counters = &self.stages[stage.index()];
+                StageTiming {
+                    stage,
+                    count: counters.count.load(Ordering::Relaxed),
+                    total: Duration::from_nanos(counters.total_ns.load(Ordering::Relaxed)),
+                    max: Duration::from_nanos(counters.max_ns.load(Ordering::Relaxed)),
+                    histogram: counters.buckets.each_ref().map(|bucket| bucket.load(Ordering::Relaxed)),
+                }
+            }),
+        }
+    }
+
+    /// Remet tous les compteurs à zéro
+    pub fn reset(&self) {
+        for counters in self.stages.iter() {
+            for bucket in &counters.buckets {
+                bucket.store(0, Ordering::Relaxed);
+            }
+            counters.count.store(0, Ordering::Relaxed);
+            counters.total_ns.store(0, Ordering::Relaxed);
+            counters.max_ns.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Chronomètre l'étape si la collecte est activée, sinon exécute simplement `f`
+pub(crate) fn measure<T>(timings: Option<&ReceiveTimings>, stage: ReceiveStage, f: impl FnOnce() -> T) -> T {
+    match timings {
+        Some(timings) => timings.measure(stage, f),
+        None => f(),
+    }
+}
+
+/// Classe d'une durée : la classe `i` couvre [2^i, 2^(i+1)) µs (la 0 : moins de 2µs)
+fn bucket_index(elapsed: Duration) -> usize {
+    let micros = elapsed.as_micros().max(1);
+    (micros.ilog2() as usize).min(TIMING_BUCKETS - 1)
+}
+
+/// Durées mesurées pour une étape
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageTiming {
+    /// Étape mesurée
+    pub stage: ReceiveStage,
+    /// Nombre de mesures
+    pub count: u64,
+    /// Somme des durées
+    pub total: Duration,
+    /// Plus longue durée
+    pub max: Duration,
+    /// Nombre de mesures par classe (voir `bucket_upper_bound`)
+    pub histogram: [u64; TIMING_BUCKETS],
+}
+
+impl StageTiming {
+    /// Durée moyenne (zéro sans mesure)
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+
+    /// Borne haute de la classe contenant le centile `percentile` (0-100)
+    ///
+    /// Précis à un facteur 2 près, ce qui suffit à repérer l'étape qui
+    /// déborde ; bornée par `max`.
+    pub fn percentile(&self, percentile: f32) -> Duration {
+        let rank = ((self.count as f64 * percentile.clamp(0.0, 100.0) as f64 / 100.0).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank && index + 1 < TIMING_BUCKETS {
+                return Self::bucket_upper_bound(index).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Borne haute (exclue) de la classe `index` de l'histogramme
+    pub fn bucket_upper_bound(index: usize) -> Duration {
+        Duration::from_micros(1 << (index + 1))
+    }
+}
+
+/// Histogrammes de toutes les étapes de réception
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TimingStats {
+    stages: [StageTiming; 4],
+}
+
+impl TimingStats {
+    /// Mesures d'une étape
+    pub fn stage(&self, stage: ReceiveStage) -> &StageTiming {
+        &self.stages[stage.index()]
+    }
+
+    /// Mesures de toutes les étapes, dans l'ordre du chemin de réception
+    pub fn stages(&self) -> &[StageTiming] {
+        &self.stages
+    }
+
+    /// Résumé lisible : moyenne, p99 et maximum de chaque étape
+    pub fn summary(&self) -> String {
+        self.stages.iter()
+            .map(|timing| format!(
+                "{} : {} mesures, moy {:?}, p99 {:?}, max {:?}",
+                timing.stage.name(), timing.count, timing.mean(), timing.percentile(99.0), timing.max
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_and_percentiles() {
+        let timings = ReceiveTimings::new();
+        for _ in 0..98 {
+            timings.record(ReceiveStage::Deserialize, Duration::from_micros(3));
+        }
+        timings.record(ReceiveStage::Deserialize, Duration::from_micros(900));
+        timings.record(ReceiveStage::Deserialize, Duration::from_secs(60));
+
+        let stats = timings.snapshot();
+        let deserialize = stats.stage(ReceiveStage::Deserialize);
+        assert_eq!(deserialize.count, 100);
+        assert_eq!(deserialize.histogram[1], 98);
+        assert_eq!(deserialize.histogram[9], 1);
+        assert_eq!(deserialize.histogram[TIMING_BUCKETS - 1], 1);
+        assert_eq!(deserialize.percentile(50.0), Duration::from_micros(4));
+        assert_eq!(deserialize.percentile(99.0), Duration::from_micros(1024));
+        assert_eq!(deserialize.percentile(100.0), Duration::from_secs(60));
+        assert_eq!(stats.stage(ReceiveStage::Checksum).mean(), Duration::ZERO);
+
+        // Les clones partagent les compteurs
+        timings.clone().reset();
+        assert_eq!(timings.snapshot().stage(ReceiveStage::Deserialize).count, 0);
+    }
+}
@@ -3,12 +3,25 @@
 //! Ce module définit les interfaces (traits) que doivent implémenter
 //! tous les composants réseau. Cela permet d'avoir du code modulaire
 //! et testable avec différentes implémentations.
+//! 
+//! `NetworkManager` est scellé : le protocole vit dans `UdpNetworkManager`,
+//! qui accepte en revanche n'importe quel `NetworkTransport`.
 
 use async_trait::async_trait;
 use std::net::SocketAddr;
-use crate::{NetworkPacket, NetworkStats, ConnectionState, NetworkResult};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::{
+    NetworkPacket, NetworkStats, ConnectionState, NetworkResult, NetworkError, PacketTap, PeerStatsReport, KeepaliveSink, KeepaliveSource, SendOutcome,
+    ReceiveTimings, TimingStats
+};
 use audio::CompressedFrame;
 
+/// Supertrait des traits qui ne s'implémentent pas hors du crate
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
 /// Trait pour le transport réseau bas niveau
 /// 
 /// Ce trait abstrait permet d'utiliser différentes implémentations :
@@ -18,6 +31,13 @@ use audio::CompressedFrame;
 /// 
 /// `#[async_trait]` permet d'avoir des fonctions async dans les traits.
 /// `Send + Sync` indique que l'objet peut être transféré entre threads.
+/// 
+/// `send_packet`, `receive_packet` et `reset_stats` prennent `&self` : une
+/// implémentation doit supporter leurs appels concurrents (mutabilité
+/// intérieure, buffers propres à chaque appel), pour qu'une tâche d'envoi et
+/// une tâche de réception partagent le transport derrière un simple `Arc`.
+/// Seul le cycle de vie (`bind`, `shutdown`) et la configuration demandent
+/// un accès exclusif.
 #[async_trait]
 pub trait NetworkTransport: Send + Sync {
     /// Démarre le transport et bind sur le port local
@@ -56,7 +76,7 @@ pub trait NetworkTransport: Send + Sync {
     /// - `NetworkError::PacketTooLarge` : Paquet trop volumineux
     /// - `NetworkError::IoError` : Erreur de transmission
     /// - `NetworkError::PeerDisconnected` : Destinataire injoignable
-    async fn send_packet(&mut self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()>;
+    async fn send_packet(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()>;
     
     /// Reçoit le prochain paquet disponible
     /// 
@@ -70,7 +90,7 @@ pub trait NetworkTransport: Send + Sync {
     /// - `NetworkError::Timeout` : Pas de paquet reçu dans le délai
     /// - `NetworkError::CorruptedPacket` : Paquet avec checksum invalide
     /// - `NetworkError::InvalidPacketFormat` : Format de paquet invalide
-    async fn receive_packet(&mut self) -> NetworkResult<(NetworkPacket, SocketAddr)>;
+    async fn receive_packet(&self) -> NetworkResult<(NetworkPacket, SocketAddr)>;
     
     /// Arrête le transport et libère les ressources
     async fn shutdown(&mut self) -> NetworkResult<()>;
@@ -78,19 +98,103 @@ pub trait NetworkTransport: Send + Sync {
     /// Retourne les statistiques de transport
     fn stats(&self) -> NetworkStats;
     
+    /// Remet les statistiques de transport à zéro
+    /// 
+    /// Le reset est atomique vis-à-vis des mises à jour concurrentes
+    /// (envoi/réception en cours sur une autre tâche).
+    async fn reset_stats(&self);
+    
     /// Retourne l'adresse locale d'écoute
     fn local_addr(&self) -> Option<SocketAddr>;
     
     /// Vérifie si le transport est actif
     fn is_active(&self) -> bool;
+    
+    /// Installe (ou retire avec `None`) un tap de capture
+    /// 
+    /// Le tap est appelé pour chaque datagramme envoyé ou reçu, avant
+    /// validation, afin de diagnostiquer les problèmes de protocole.
+    /// Voir `CaptureWriter` pour un enregistrement au format pcapng.
+    fn set_tap(&mut self, tap: Option<PacketTap>);
+    
+    /// Complète (ou plus, avec `None`) les paquets audio et factices jusqu'à
+    /// `packet_size` octets (voir `PaddingConfig`)
+    /// 
+    /// Sans effet par défaut, pour les transports qui ne sérialisent pas
+    /// les paquets.
+    fn set_padding(&mut self, _packet_size: Option<usize>) {}
+    
+    /// Chemin d'envoi synchrone pour les heartbeats, indépendant du runtime
+    /// 
+    /// Utilisé par le manager pour émettre les heartbeats depuis un thread
+    /// dédié. Retourne `None` par défaut (ou si le transport n'est pas bind) :
+    /// les heartbeats ne partent alors qu'avec le trafic audio.
+    fn keepalive_sink(&self) -> Option<Arc<dyn KeepaliveSink>> {
+        None
+    }
+    
+    /// Observation des arrivées, pour surveiller le pair hors des lectures
+    /// 
+    /// Utilisé par le manager pour constater le silence du pair même quand
+    /// l'application ne lit pas le réseau ; les datagrammes ne sont pas
+    /// consommés. Retourne `None` par défaut : ce silence n'est alors
+    /// constaté qu'à la lecture suivante.
+    fn keepalive_source(&self) -> Option<Arc<dyn KeepaliveSource>> {
+        None
+    }
+    
+    /// Collecteur des durées d'étapes de réception, si activé
+    /// 
+    /// Présent quand `NetworkConfig::timing_stats` est vrai (et que le
+    /// transport le gère) ; le manager y ajoute ses propres étapes.
+    fn receive_timings(&self) -> Option<&ReceiveTimings> {
+        None
+    }
+    
+    /// Fait passer (ou plus, avec `None`) le trafic vers `peer` par un relais
+    /// 
+    /// Les datagrammes vers `peer` sont alors encapsulés et envoyés au relais
+    /// (voir `RelayServer`). Un transport qui reçoit un datagramme relayé
+    /// enregistre de lui-même la route vers son expéditeur.
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::InvalidState` : relais non supporté par ce transport
+    fn set_relay_route(&mut self, _peer: SocketAddr, _relay: Option<SocketAddr>) -> NetworkResult<()> {
+        Err(NetworkError::InvalidState {
+            operation: "set_relay_route".to_string(),
+            current_state: "relais non supporté par ce transport".to_string(),
+        })
+    }
+    
+    /// Relais emprunté pour joindre `peer`, `None` en direct
+    fn relay_route(&self, _peer: SocketAddr) -> Option<SocketAddr> {
+        None
+    }
+    
+    /// Histogrammes des durées d'étapes de réception, si activés
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{NetworkTransport, SimulatedTransport, NetworkConfig, ReceiveStage};
+    /// 
+    /// let mut config = NetworkConfig::test_config();
+    /// config.timing_stats = true;
+    /// let transport = SimulatedTransport::new(config).unwrap();
+    /// let stats = transport.timing_stats().unwrap();
+    /// assert_eq!(stats.stage(ReceiveStage::BufferInsert).count, 0);
+    /// ```
+    fn timing_stats(&self) -> Option<TimingStats> {
+        self.receive_timings().map(ReceiveTimings::snapshot)
+    }
 }
 
 /// Trait pour la gestion de connexion P2P haut niveau
 /// 
 /// Ce trait gère la logique métier de connexion peer-to-peer,
 /// incluant les handshakes, heartbeats, et la récupération d'erreurs.
+/// Trait scellé : seul `UdpNetworkManager` l'implémente.
 #[async_trait]
-pub trait NetworkManager: Send + Sync {
+pub trait NetworkManager: sealed::Sealed + Send + Sync {
     /// Démarre l'écoute en mode serveur
     /// 
     /// Le manager attend qu'un peer se connecte sur le port spécifié.
@@ -115,7 +219,8 @@ pub trait NetworkManager: Send + Sync {
     
     /// Se connecte à un peer distant
     /// 
-    /// Initie une connexion P2P avec handshake complet.
+    /// Initie une connexion P2P avec handshake complet, réessayé jusqu'à
+    /// `NetworkConfig::max_retry_attempts` fois en cas d'échec passager.
     /// 
     /// # Arguments
     /// * `peer_addr` - Adresse du peer distant (IP:PORT)
@@ -130,10 +235,14 @@ pub trait NetworkManager: Send + Sync {
     /// # Arguments
     /// * `frame` - Frame audio compressée à envoyer
     /// 
+    /// # Returns
+    /// Sort de la frame : envoyée, en file d'envoi ou abandonnée (voir
+    /// `SendOutcome`) ; seuls les deux derniers signalent une contre-pression
+    /// 
     /// # Erreurs
     /// - `NetworkError::InvalidState` : Pas de connexion active
     /// - `NetworkError::BufferOverflow` : Buffer d'envoi plein
-    async fn send_audio(&mut self, frame: CompressedFrame) -> NetworkResult<()>;
+    async fn send_audio(&mut self, frame: CompressedFrame) -> NetworkResult<SendOutcome>;
     
     /// Reçoit une frame audio du peer distant
     /// 
@@ -160,6 +269,36 @@ pub trait NetworkManager: Send + Sync {
     /// Retourne les statistiques réseau combinées
     fn network_stats(&self) -> NetworkStats;
     
+    /// Retourne l'adresse du pair, sans avoir à inspecter `ConnectionState`
+    /// 
+    /// Disponible pendant la connexion, une fois connecté et après une
+    /// erreur ; `None` une fois déconnecté.
+    fn peer_addr(&self) -> Option<SocketAddr>;
+    
+    /// Retourne l'ID de la session courante
+    /// 
+    /// Avant la connexion, c'est l'ID proposé au pair ; ensuite, l'ID commun
+    /// négocié au handshake.
+    fn session_id(&self) -> u64;
+    
+    /// Retourne l'adresse locale (`None` tant que le transport n'est pas lié)
+    fn local_addr(&self) -> Option<SocketAddr>;
+    
+    /// Retourne la durée de la connexion en cours (`None` si non connecté)
+    fn connected_duration(&self) -> Option<Duration>;
+    
+    /// Retourne le dernier rapport de réception envoyé par le pair
+    /// 
+    /// Indique comment notre audio est reçu de l'autre côté (pertes, jitter).
+    /// `None` tant qu'aucun rapport n'a été reçu (ou pair sans rapports).
+    fn remote_stats(&self) -> Option<PeerStatsReport>;
+    
+    /// Retourne l'état du buffer anti-jitter de réception
+    /// 
+    /// Profondeur, délai visé, paquets perdus, écartés en retard ou masqués :
+    /// lu en direct, pour l'affichage et le réglage de `target_delay_ms`.
+    fn buffer_stats(&self) -> BufferStats;
+    
     /// Force une reconnexion si possible
     /// 
     /// Utile après une erreur réseau ou une coupure temporaire.
@@ -241,13 +380,24 @@ pub trait NetworkBuffer: Send + Sync {
 
 /// Statistiques du buffer réseau
 #[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct BufferStats {
     /// Nombre de paquets en attente
     pub packets_buffered: usize,
     
-    /// Nombre de paquets rejetés (trop anciens)
+    /// Nombre de paquets éjectés faute de place (buffer plein), y compris
+    /// pour tenir les budgets en octets
     pub packets_dropped: u64,
     
+    /// Octets en attente (en-têtes et frames compressées)
+    pub bytes_buffered: usize,
+    
+    /// Paquets éjectés pour tenir `NetworkConfig::max_buffer_bytes`
+    pub byte_budget_drops: u64,
+    
+    /// Paquets éjectés pour tenir `NetworkConfig::max_buffer_bytes_per_sender`
+    pub sender_cap_drops: u64,
+    
     /// Nombre de paquets en double rejetés
     pub duplicates_dropped: u64,
     
@@ -259,6 +409,18 @@ pub struct BufferStats {
     
     /// Délai d'attente moyen des paquets dans le buffer
     pub avg_delay_ms: f32,
+    
+    /// Délai visé : attente d'un paquet manquant avant de le déclarer perdu
+    pub target_delay_ms: f32,
+    
+    /// Paquets déclarés perdus
+    pub packets_lost: u64,
+    
+    /// Paquets arrivés après que leur créneau a été sauté, écartés
+    pub late_discarded: u64,
+    
+    /// Frames manquantes masquées à la lecture (créneaux sautés)
+    pub concealments: u64,
 }
 
 /// Trait pour les implémentations de test et simulation
@@ -5,16 +5,36 @@
 //! les fonctionnalités nécessaires pour une communication audio temps réel.
 
 use async_trait::async_trait;
+#[cfg(feature = "udp")]
 use tokio::net::UdpSocket;
-use tokio::time::{timeout, Duration};
+use tokio::time::timeout;
+#[cfg(feature = "simulator")]
+use tokio::time::Duration;
+#[cfg(any(feature = "udp", feature = "simulator"))]
 use std::time::Instant;
 use std::net::SocketAddr;
+#[cfg(any(feature = "udp", feature = "simulator"))]
+use std::collections::VecDeque;
 use std::sync::Arc;
+#[cfg(feature = "udp")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(any(feature = "udp", feature = "simulator"))]
+use std::sync::Mutex as StdMutex;
+#[cfg(feature = "udp")]
 use tokio::sync::Mutex;
 
 use crate::{
-    NetworkTransport, NetworkPacket, NetworkStats, NetworkConfig, NetworkResult, NetworkError
+    NetworkTransport, NetworkPacket, NetworkStats, NetworkConfig, NetworkResult, NetworkError,
+    PacketTap, TapDirection, KeepaliveSink, KeepaliveSource, ReceiveStage, ReceiveTimings
 };
+use crate::timing;
+#[cfg(feature = "simulator")]
+use crate::CapturedDatagram;
+use crate::capture;
+#[cfg(any(feature = "udp", feature = "simulator"))]
+use crate::keepalive::ArrivalMonitor;
+#[cfg(feature = "udp")]
+use crate::relay::{self, RelayRoutes};
 
 /// Implémentation du transport UDP avec tokio
 /// 
@@ -27,6 +47,8 @@ use crate::{
 /// - Buffer configurable pour optimiser les performances
 /// - Validation automatique des paquets (checksum, taille)
 /// - Statistiques temps réel pour monitoring
+/// - Envoi et réception utilisables depuis plusieurs tâches à la fois
+///   (`&self`, buffers alloués à chaque appel)
 /// 
 /// # Example
 /// ```rust,no_run
@@ -41,6 +63,7 @@ use crate::{
 /// # Ok(())
 /// # }
 /// ```
+#[cfg(feature = "udp")]
 pub struct UdpTransport {
     /// Configuration réseau
     config: NetworkConfig,
@@ -51,19 +74,139 @@ pub struct UdpTransport {
     /// Statistiques réseau
     stats: Arc<Mutex<NetworkStats>>,
     
-    /// Buffer temporaire pour la sérialisation
-    send_buffer: Vec<u8>,
-    
-    /// Buffer temporaire pour la réception
-    receive_buffer: Vec<u8>,
-    
     /// Adresse locale d'écoute
     local_addr: Option<SocketAddr>,
     
     /// Indique si le transport est actif
     is_active: bool,
+    
+    /// Tap de capture des datagrammes (diagnostic)
+    tap: Option<PacketTap>,
+    
+    /// Copie synchrone du socket pour le thread de heartbeat
+    keepalive: Option<Arc<UdpKeepalive>>,
+    
+    /// Durées des étapes de réception (si `config.timing_stats`)
+    timings: Option<ReceiveTimings>,
+    
+    /// Pairs joints via un relais (partagé avec le chemin des heartbeats)
+    relay_routes: RelayRoutes,
+    
+    /// Taille des paquets audio et factices en mode padding
+    padding: Option<usize>,
 }
 
+/// Envoi bloquant, et repérage des arrivées, sur une copie du socket UDP
+/// (hors runtime tokio)
+#[cfg(feature = "udp")]
+struct UdpKeepalive {
+    socket: std::net::UdpSocket,
+    relay_routes: RelayRoutes,
+    /// Réceptions `receive_packet` en cours : le repérage n'y touche pas
+    receiving: AtomicUsize,
+    /// Repérage en cours : une réception attend qu'il se termine
+    scanning: AtomicBool,
+    /// Datagrammes lus par le repérage, dus à la prochaine réception
+    backlog: StdMutex<VecDeque<(Vec<u8>, SocketAddr)>>,
+    /// Dernière arrivée du pair surveillé
+    arrivals: ArrivalMonitor,
+}
+
+#[cfg(feature = "udp")]
+impl UdpKeepalive {
+    /// Datagrammes gardés au plus pour la prochaine réception (au-delà, les
+    /// plus anciens sont perdus, comme par un buffer de socket plein)
+    const BACKLOG_CAPACITY: usize = 512;
+    
+    fn new(socket: std::net::UdpSocket, relay_routes: RelayRoutes) -> Self {
+        Self {
+            socket,
+            relay_routes,
+            receiving: AtomicUsize::new(0),
+            scanning: AtomicBool::new(false),
+            backlog: StdMutex::new(VecDeque::new()),
+            arrivals: ArrivalMonitor::default(),
+        }
+    }
+    
+    /// Marque une réception en cours, une fois un éventuel repérage terminé
+    async fn begin_receive(&self) -> ReceivingGuard<'_> {
+        self.receiving.fetch_add(1, Ordering::SeqCst);
+        while self.scanning.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+        ReceivingGuard(&self.receiving)
+    }
+    
+    /// Plus ancien datagramme lu par le repérage
+    fn pop_backlog(&self) -> Option<(Vec<u8>, SocketAddr)> {
+        self.backlog.lock().unwrap().pop_front()
+    }
+}
+
+/// Réception en cours sur le socket UDP (jusqu'à destruction)
+#[cfg(feature = "udp")]
+struct ReceivingGuard<'a>(&'a AtomicUsize);
+
+#[cfg(feature = "udp")]
+impl Drop for ReceivingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "udp")]
+impl KeepaliveSink for UdpKeepalive {
+    fn send_now(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+        let mut packet = packet.clone();
+        let mut datagram = Vec::new();
+        encode_packet(&mut packet, &mut datagram)?;
+        
+        let relay = self.relay_routes.lock().unwrap().get(&target_addr).copied();
+        let (datagram, target_addr) = match relay {
+            Some(relay) => {
+                let mut relayed = Vec::with_capacity(datagram.len() + 32);
+                relay::encapsulate(&mut relayed, target_addr, &datagram);
+                (relayed, relay)
+            }
+            None => (datagram, target_addr),
+        };
+        
+        // Socket non bloquant (partagé avec tokio) : buffer plein = heartbeat perdu
+        self.socket.send_to(&datagram, target_addr)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "udp")]
+impl KeepaliveSource for UdpKeepalive {
+    fn last_arrival(&self, peer_addr: SocketAddr) -> Option<Instant> {
+        self.arrivals.watch(peer_addr);
+        
+        // Pendant une réception, c'est elle qui note les arrivées ; sinon les
+        // datagrammes en attente sont lus, bruts, pour `receive_packet`
+        self.scanning.store(true, Ordering::SeqCst);
+        if self.receiving.load(Ordering::SeqCst) == 0 {
+            let mut receive_buffer = vec![0u8; UdpTransport::DATAGRAM_CAPACITY];
+            while let Ok((bytes_received, datagram_source)) = self.socket.recv_from(&mut receive_buffer) {
+                let datagram = receive_buffer[..bytes_received].to_vec();
+                let sender = relay::decapsulate(&datagram).map_or(datagram_source, |(sender, _)| sender);
+                self.arrivals.record(sender, Instant::now());
+                
+                let mut backlog = self.backlog.lock().unwrap();
+                if backlog.len() == Self::BACKLOG_CAPACITY {
+                    backlog.pop_front();
+                }
+                backlog.push_back((datagram, datagram_source));
+            }
+        }
+        self.scanning.store(false, Ordering::SeqCst);
+        
+        self.arrivals.watch(peer_addr)
+    }
+}
+
+#[cfg(feature = "udp")]
 impl UdpTransport {
     /// Crée une nouvelle instance de transport UDP
     /// 
@@ -82,94 +225,80 @@ impl UdpTransport {
     /// ```
     pub fn new(config: NetworkConfig) -> NetworkResult<Self> {
         Ok(Self {
+            timings: config.timing_stats.then(ReceiveTimings::new),
             config,
             socket: None,
             stats: Arc::new(Mutex::new(NetworkStats::new())),
-            send_buffer: Vec::with_capacity(2048), // Pré-alloue pour éviter des réallocations
-            receive_buffer: vec![0u8; 2048],
             local_addr: None,
             is_active: false,
+            tap: None,
+            keepalive: None,
+            relay_routes: RelayRoutes::default(),
+            padding: None,
         })
     }
     
+    /// Taille des buffers d'envoi et de réception alloués à chaque appel
+    const DATAGRAM_CAPACITY: usize = 2048;
+    
     /// Sérialise un paquet en bytes pour transmission
     /// 
-    /// Utilise bincode pour une sérialisation efficace et compacte.
     /// Met à jour le send_timestamp avant sérialisation et recalcule le checksum.
-    fn serialize_packet(&mut self, packet: &mut NetworkPacket) -> NetworkResult<&[u8]> {
-        // Met à jour le timestamp d'envoi
-        packet.send_timestamp = Instant::now();
-        
-        // Recalcule le checksum du paquet réel (après modification du timestamp)
-        // CORRECTION: Il faut calculer le checksum du paquet actuel, pas d'un paquet temporaire
-        packet.checksum = packet.calculate_checksum();
-        
-        // Sérialise dans le buffer pré-alloué
-        self.send_buffer.clear();
-        
-        match bincode::serialize_into(&mut self.send_buffer, packet) {
-            Ok(()) => {
-                // Vérification de la taille
-                if self.send_buffer.len() > NetworkPacket::MAX_PACKET_SIZE {
-                    return Err(NetworkError::packet_too_large(
-                        self.send_buffer.len(),
-                        NetworkPacket::MAX_PACKET_SIZE,
-                    ));
-                }
-                Ok(&self.send_buffer)
-            }
-            Err(e) => Err(NetworkError::SerializationError(e)),
-        }
+    /// Le buffer appartient à l'appel : plusieurs envois peuvent être en cours.
+    fn serialize_packet(&self, packet: &mut NetworkPacket) -> NetworkResult<Vec<u8>> {
+        let mut datagram = Vec::with_capacity(Self::DATAGRAM_CAPACITY);
+        encode_packet(packet, &mut datagram)?;
+        Ok(datagram)
     }
     
     /// Désérialise des bytes en paquet
     /// 
     /// Valide automatiquement le checksum et la version du protocole.
     fn deserialize_packet(&self, data: &[u8], source_addr: SocketAddr) -> NetworkResult<NetworkPacket> {
-        // Désérialisation
-        let packet: NetworkPacket = bincode::deserialize(data)
-            .map_err(|_| NetworkError::InvalidPacketFormat { addr: source_addr })?;
-        
-        // Validation de la version du protocole
-        if packet.protocol_version != NetworkPacket::CURRENT_PROTOCOL_VERSION {
-            return Err(NetworkError::InvalidPacketFormat { addr: source_addr });
-        }
-        
-        // Validation du checksum
-        if !packet.verify_checksum() {
-            return Err(NetworkError::corrupted_packet(source_addr));
-        }
-        
-        // Vérification de l'âge du paquet
-        if packet.is_stale(self.config.max_packet_age) {
-            return Err(NetworkError::PacketTooOld {
-                sequence: packet.compressed_frame.sequence_number,
-                age_ms: packet.age().as_millis() as u64,
-            });
-        }
-        
-        Ok(packet)
+        decode_packet(data, source_addr, &self.config, self.timings.as_ref())
     }
     
-    /// Met à jour les statistiques après envoi d'un paquet
-    async fn update_send_stats(&self, packet: &NetworkPacket, _target_addr: SocketAddr) {
+    /// Met à jour les statistiques après envoi d'un datagramme de `bytes` octets
+    async fn update_send_stats(&self, bytes: usize, _target_addr: SocketAddr) {
         let mut stats = self.stats.lock().await;
         stats.packets_sent += 1;
         stats.last_updated = Instant::now();
         
         // Mise à jour de la bande passante
-        let packet_size = packet.estimated_size() as f32;
-        let elapsed = stats.last_updated.duration_since(Instant::now() - Duration::from_secs(1));
-        if elapsed.as_secs_f32() > 0.0 {
-            stats.bandwidth_bytes_per_sec = packet_size / elapsed.as_secs_f32();
-        }
+        let now = stats.last_updated;
+        stats.record_bytes_sent(bytes, now);
+        stats.refresh_bitrates(now);
+    }
+    
+    /// Traite un datagramme reçu : relais, tap, désérialisation et statistiques
+    async fn process_datagram(&self, received: &[u8], datagram_source: SocketAddr) -> NetworkResult<(NetworkPacket, SocketAddr)> {
+        // Datagramme relayé : l'expéditeur est dans l'en-tête, et nos
+        // réponses doivent reprendre le même chemin
+        let (source_addr, data) = match relay::decapsulate(received) {
+            Some((sender, inner)) => {
+                self.relay_routes.lock().unwrap().insert(sender, datagram_source);
+                (sender, inner)
+            }
+            None => (datagram_source, received),
+        };
+        capture::emit(&self.tap, TapDirection::Received, self.local_addr, source_addr, data);
+        
+        // Désérialisation et validation
+        let packet = self.deserialize_packet(data, source_addr)?;
+        
+        // Mise à jour des statistiques
+        self.update_receive_stats(&packet, received.len(), source_addr).await;
+        
+        Ok((packet, source_addr))
     }
     
-    /// Met à jour les statistiques après réception d'un paquet
-    async fn update_receive_stats(&self, packet: &NetworkPacket, _source_addr: SocketAddr) {
+    /// Met à jour les statistiques après réception d'un datagramme de `bytes` octets
+    async fn update_receive_stats(&self, packet: &NetworkPacket, bytes: usize, _source_addr: SocketAddr) {
         let mut stats = self.stats.lock().await;
         stats.packets_received += 1;
         stats.last_updated = Instant::now();
+        let now = stats.last_updated;
+        stats.record_bytes_received(bytes, now);
         
         // Calcul du RTT si c'est un paquet de type heartbeat
         if matches!(packet.packet_type, crate::PacketType::Heartbeat) {
@@ -193,6 +322,7 @@ impl UdpTransport {
     }
 }
 
+#[cfg(feature = "udp")]
 #[async_trait]
 impl NetworkTransport for UdpTransport {
     /// Bind le socket UDP sur le port local
@@ -207,11 +337,27 @@ impl NetworkTransport for UdpTransport {
             });
         }
         
-        // Création du socket
-        let addr = SocketAddr::from(([0, 0, 0, 0], local_port));
-        let socket = UdpSocket::bind(addr).await
+        // Création du socket, sur toutes les interfaces sauf interface ou IP imposée.
+        // Le socket std est dupliqué avant d'être confié à tokio : la copie sert
+        // au thread de heartbeat, qui doit pouvoir émettre si le runtime est bloqué.
+        let addr = self.config.bind_addr(local_port)?;
+        let std_socket = std::net::UdpSocket::bind(addr)
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|e| NetworkError::bind_failed(local_port, e))?;
+        match std_socket.try_clone() {
+            Ok(socket) => {
+                self.keepalive = Some(Arc::new(UdpKeepalive::new(socket, self.relay_routes.clone())));
+            }
+            Err(e) => println!("Heartbeats hors runtime indisponibles : {}", e),
+        }
+        let socket = UdpSocket::from_std(std_socket)
             .map_err(|e| NetworkError::bind_failed(local_port, e))?;
         
+        // Autorise l'envoi en broadcast pour la découverte LAN
+        if let Err(e) = socket.set_broadcast(true) {
+            println!("Broadcast UDP indisponible : {}", e);
+        }
+        
         // Configuration des buffers système (non disponible avec tokio::net::UdpSocket)
         // Les buffers seront configurés par le système d'exploitation
         
@@ -229,28 +375,36 @@ impl NetworkTransport for UdpTransport {
     /// Envoie un paquet vers une adresse cible
     /// 
     /// La fonction sérialise le paquet, l'envoie via UDP, et met à jour les statistiques.
-    async fn send_packet(&mut self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+    async fn send_packet(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
         // Vérification de l'état avant toute opération
         let socket = self.socket.as_ref()
             .ok_or_else(|| NetworkError::InvalidState {
                 operation: "send_packet".to_string(),
                 current_state: "not bound".to_string(),
-            })?
-            .clone(); // Clone l'Arc pour éviter les conflits d'emprunts
-        
-        // Copie du timeout pour éviter l'emprunt de self.config
-        let connection_timeout = self.config.connection_timeout;
+            })?;
         
         // Copie le paquet pour pouvoir le modifier (timestamp)
         let mut packet_to_send = packet.clone();
+        let mut datagram = self.serialize_packet(&mut packet_to_send)?;
+        if let Some(packet_size) = self.padding {
+            pad_datagram(&packet_to_send, &mut datagram, packet_size);
+        }
         
-        // Sérialisation (maintenant safe car on a cloné les références nécessaires)
-        let data = self.serialize_packet(&mut packet_to_send)?;
+        // Pair joint via un relais : le datagramme est encapsulé à son intention
+        let relay = self.relay_routes.lock().unwrap().get(&target_addr).copied();
+        let mut relayed = Vec::new();
+        let (data, destination) = match relay {
+            Some(relay) => {
+                relay::encapsulate(&mut relayed, target_addr, &datagram);
+                (&relayed, relay)
+            }
+            None => (&datagram, target_addr),
+        };
         
         // Envoi avec timeout
         let send_result = timeout(
-            connection_timeout,
-            socket.send_to(data, target_addr)
+            self.config.connection_timeout,
+            socket.send_to(data, destination)
         ).await;
         
         match send_result {
@@ -265,8 +419,10 @@ impl NetworkTransport for UdpTransport {
                     ));
                 }
                 
+                capture::emit(&self.tap, TapDirection::Sent, self.local_addr, target_addr, &datagram);
+                
                 // Mise à jour des statistiques
-                self.update_send_stats(&packet_to_send, target_addr).await;
+                self.update_send_stats(bytes_sent, target_addr).await;
                 
                 Ok(())
             }
@@ -281,31 +437,38 @@ impl NetworkTransport for UdpTransport {
     /// Reçoit le prochain paquet disponible
     /// 
     /// Cette fonction bloque jusqu'à réception d'un paquet valide ou timeout.
-    async fn receive_packet(&mut self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
+    async fn receive_packet(&self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
         let socket = self.socket.as_ref()
             .ok_or_else(|| NetworkError::InvalidState {
                 operation: "receive_packet".to_string(),
                 current_state: "not bound".to_string(),
             })?;
         
-        // Réception avec timeout
+        // Datagrammes repérés pour le watchdog pendant qu'aucune réception
+        // n'était en cours : traités en premier, comme s'ils arrivaient
+        let _receiving = match &self.keepalive {
+            Some(keepalive) => Some(keepalive.begin_receive().await),
+            None => None,
+        };
+        if let Some((datagram, datagram_source)) = self.keepalive.as_ref().and_then(|keepalive| keepalive.pop_backlog()) {
+            return self.process_datagram(&datagram, datagram_source).await;
+        }
+        
+        // Réception avec timeout, dans un buffer propre à cet appel
+        let mut receive_buffer = vec![0u8; Self::DATAGRAM_CAPACITY];
         let receive_result = timeout(
             self.config.connection_timeout,
-            socket.recv_from(&mut self.receive_buffer)
+            socket.recv_from(&mut receive_buffer)
         ).await;
         
         match receive_result {
-            Ok(Ok((bytes_received, source_addr))) => {
-                // Désérialisation et validation
-                let packet = self.deserialize_packet(
-                    &self.receive_buffer[..bytes_received],
-                    source_addr
-                )?;
-                
-                // Mise à jour des statistiques
-                self.update_receive_stats(&packet, source_addr).await;
-                
-                Ok((packet, source_addr))
+            Ok(Ok((bytes_received, datagram_source))) => {
+                let received = &receive_buffer[..bytes_received];
+                if let Some(keepalive) = &self.keepalive {
+                    let sender = relay::decapsulate(received).map_or(datagram_source, |(sender, _)| sender);
+                    keepalive.arrivals.record(sender, Instant::now());
+                }
+                self.process_datagram(received, datagram_source).await
             }
             Ok(Err(e)) => Err(NetworkError::IoError(e)),
             Err(_) => Err(NetworkError::Timeout),
@@ -315,8 +478,10 @@ impl NetworkTransport for UdpTransport {
     /// Arrête le transport et libère les ressources
     async fn shutdown(&mut self) -> NetworkResult<()> {
         self.socket = None;
+        self.keepalive = None;
         self.local_addr = None;
         self.is_active = false;
+        self.relay_routes.lock().unwrap().clear();
         
         // Reset des statistiques
         let mut stats = self.stats.lock().await;
@@ -330,11 +495,23 @@ impl NetworkTransport for UdpTransport {
     fn stats(&self) -> NetworkStats {
         // Version synchrone - on utilise try_lock pour éviter de bloquer
         match self.stats.try_lock() {
-            Ok(stats) => stats.clone(),
+            Ok(stats) => {
+                let mut stats = stats.clone();
+                stats.refresh_bitrates(Instant::now());
+                stats
+            }
             Err(_) => NetworkStats::default(), // Si le lock échoue, retourne des stats vides
         }
     }
     
+    /// Remet les statistiques (et les durées d'étapes) à zéro sous le lock partagé
+    async fn reset_stats(&self) {
+        self.stats.lock().await.reset();
+        if let Some(timings) = &self.timings {
+            timings.reset();
+        }
+    }
+    
     /// Retourne l'adresse locale d'écoute
     fn local_addr(&self) -> Option<SocketAddr> {
         self.local_addr
@@ -344,12 +521,170 @@ impl NetworkTransport for UdpTransport {
     fn is_active(&self) -> bool {
         self.is_active && self.socket.is_some()
     }
+    
+    /// Installe le tap de capture des datagrammes
+    fn set_tap(&mut self, tap: Option<PacketTap>) {
+        self.tap = tap;
+    }
+    
+    fn set_padding(&mut self, packet_size: Option<usize>) {
+        self.padding = packet_size;
+    }
+    
+    fn keepalive_sink(&self) -> Option<Arc<dyn KeepaliveSink>> {
+        self.keepalive.clone().map(|sink| sink as Arc<dyn KeepaliveSink>)
+    }
+    
+    fn keepalive_source(&self) -> Option<Arc<dyn KeepaliveSource>> {
+        self.keepalive.clone().map(|source| source as Arc<dyn KeepaliveSource>)
+    }
+    
+    fn receive_timings(&self) -> Option<&ReceiveTimings> {
+        self.timings.as_ref()
+    }
+    
+    fn set_relay_route(&mut self, peer: SocketAddr, relay: Option<SocketAddr>) -> NetworkResult<()> {
+        let mut routes = self.relay_routes.lock().unwrap();
+        match relay {
+            Some(relay) => routes.insert(peer, relay),
+            None => routes.remove(&peer),
+        };
+        Ok(())
+    }
+    
+    fn relay_route(&self, peer: SocketAddr) -> Option<SocketAddr> {
+        self.relay_routes.lock().unwrap().get(&peer).copied()
+    }
+}
+
+/// Sérialise un paquet dans `buffer` pour transmission
+/// 
+/// Partagé par les transports basés sur UDP (direct ou via proxy).
+/// Met à jour le send_timestamp avant sérialisation et recalcule le checksum.
+#[cfg(feature = "udp")]
+pub(crate) fn encode_packet(packet: &mut NetworkPacket, buffer: &mut Vec<u8>) -> NetworkResult<()> {
+    // Met à jour le timestamp d'envoi
+    packet.send_timestamp = Instant::now();
+    
+    // Recalcule le checksum du paquet réel (après modification du timestamp)
+    // CORRECTION: Il faut calculer le checksum du paquet actuel, pas d'un paquet temporaire
+    packet.checksum = packet.calculate_checksum();
+    
+    // Sérialise dans le buffer pré-alloué (format legacy si le pair est ancien)
+    if packet.protocol_version == NetworkPacket::CURRENT_PROTOCOL_VERSION {
+        packet.encode_into(buffer);
+    } else {
+        #[cfg(feature = "legacy-protocol")]
+        crate::legacy::encode_legacy(packet, buffer)?;
+        
+        #[cfg(not(feature = "legacy-protocol"))]
+        return Err(NetworkError::ConfigError(format!(
+            "encodage en protocole v{} non supporté (feature legacy-protocol désactivée)",
+            packet.protocol_version
+        )));
+    }
+    
+    // Vérification de la taille
+    if buffer.len() > NetworkPacket::MAX_PACKET_SIZE {
+        return Err(NetworkError::packet_too_large(
+            buffer.len(),
+            NetworkPacket::MAX_PACKET_SIZE,
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Complète le datagramme d'un paquet audio ou factice (mode padding)
+/// 
+/// Les autres paquets (heartbeats, contrôle) ne dépendent pas de l'activité
+/// vocale et partent tels quels, comme les formats bincode (v1, v2) qui
+/// n'ont pas de flags.
+#[cfg(feature = "udp")]
+pub(crate) fn pad_datagram(packet: &NetworkPacket, datagram: &mut Vec<u8>, packet_size: usize) {
+    let padded_type = matches!(packet.packet_type, crate::PacketType::Audio | crate::PacketType::Padding);
+    if padded_type && packet.protocol_version >= 3 {
+        NetworkPacket::pad_datagram(datagram, packet_size);
+    }
+}
+
+/// Désérialise et valide un paquet reçu
+/// 
+/// Valide automatiquement le checksum, la version du protocole et l'âge du paquet.
+/// Les versions antérieures supportées sont converties (feature `legacy-protocol`) ;
+/// les autres donnent `NetworkError::UnsupportedVersion`.
+/// 
+/// Avec `timings`, la désérialisation et le checksum sont chronométrés.
+pub(crate) fn decode_packet(
+    data: &[u8],
+    source_addr: SocketAddr,
+    config: &NetworkConfig,
+    timings: Option<&ReceiveTimings>,
+) -> NetworkResult<NetworkPacket> {
+    let packet = timing::measure(timings, ReceiveStage::Deserialize, || {
+        // Lecture de la version avant désérialisation : le format en dépend
+        let version = NetworkPacket::peek_version(data)
+            .ok_or(NetworkError::InvalidPacketFormat { addr: source_addr })?;
+        
+        if !NetworkPacket::is_version_supported(version) {
+            return Err(NetworkError::unsupported_version(source_addr, version));
+        }
+        
+        // Désérialisation
+        if version == NetworkPacket::CURRENT_PROTOCOL_VERSION {
+            NetworkPacket::decode_from(data)
+                .ok_or(NetworkError::InvalidPacketFormat { addr: source_addr })
+        } else {
+            #[cfg(feature = "legacy-protocol")]
+            { crate::legacy::decode_legacy(version, data, source_addr) }
+            
+            #[cfg(not(feature = "legacy-protocol"))]
+            unreachable!("is_version_supported n'accepte que la version courante sans legacy-protocol")
+        }
+    })?;
+    
+    // Validation du checksum
+    if !timing::measure(timings, ReceiveStage::Checksum, || packet.verify_checksum()) {
+        return Err(NetworkError::corrupted_packet(source_addr));
+    }
+    
+    // Vérification de l'âge du paquet
+    if packet.is_stale(config.max_packet_age) {
+        return Err(NetworkError::PacketTooOld {
+            sequence: packet.sequence_number(),
+            age_ms: packet.age().as_millis() as u64,
+        });
+    }
+    
+    Ok(packet)
+}
+
+/// File de paquets partagée entre transports simulés, avec leur instant de livraison
+/// 
+/// Les instants sont ceux de l'horloge tokio : sous une horloge suspendue
+/// (`tokio::time::pause`, tests en temps virtuel), latence et gigue simulées
+/// s'écoulent en temps virtuel comme les timers du manager.
+#[cfg(feature = "simulator")]
+type SimulatedQueue = Arc<StdMutex<VecDeque<(NetworkPacket, SocketAddr, tokio::time::Instant)>>>;
+
+/// Retire de la file le premier paquet arrivé à échéance de livraison
+#[cfg(feature = "simulator")]
+fn pop_delivered(queue: &SimulatedQueue) -> Option<(NetworkPacket, SocketAddr, tokio::time::Instant)> {
+    let mut queue = queue.lock().unwrap();
+    let now = tokio::time::Instant::now();
+    queue.iter()
+        .enumerate()
+        .filter(|(_, (_, _, deliver_at))| *deliver_at <= now)
+        .min_by_key(|(_, (_, _, deliver_at))| *deliver_at)
+        .map(|(index, _)| index)
+        .and_then(|index| queue.remove(index))
 }
 
 /// Implémentation de transport simulé pour les tests
 /// 
 /// Cette implémentation permet de tester le comportement réseau
 /// en simulant différentes conditions (latence, perte, etc.).
+#[cfg(feature = "simulator")]
 pub struct SimulatedTransport {
     /// Configuration de base
     config: NetworkConfig,
@@ -361,32 +696,70 @@ pub struct SimulatedTransport {
     corruption_rate: f32,
     
     /// Buffer interne pour simuler la réception
-    receive_queue: std::collections::VecDeque<(NetworkPacket, SocketAddr)>,
+    receive_queue: SimulatedQueue,
+    
+    /// File de réception du pair (transports créés par `pair`), sinon loopback
+    peer_queue: Option<SimulatedQueue>,
     
-    /// Statistiques
-    stats: NetworkStats,
+    /// Statistiques (envoi et réception peuvent tourner sur deux tâches)
+    stats: StdMutex<NetworkStats>,
     
     /// État du transport
     is_active: bool,
     local_addr: Option<SocketAddr>,
+    
+    /// Tap de capture (les paquets sont sérialisés uniquement s'il est installé)
+    tap: Option<PacketTap>,
+    
+    /// Durées des étapes de réception (si `config.timing_stats`)
+    timings: Option<ReceiveTimings>,
+    
+    /// Dernière arrivée du pair surveillé (partagée avec `keepalive_source`)
+    arrivals: Arc<ArrivalMonitor>,
 }
 
+#[cfg(feature = "simulator")]
 impl SimulatedTransport {
     /// Crée un nouveau transport simulé
     pub fn new(config: NetworkConfig) -> NetworkResult<Self> {
         Ok(Self {
+            timings: config.timing_stats.then(ReceiveTimings::new),
             config,
             latency_ms: 0,
             loss_rate: 0.0,
             jitter_ms: 0,
             corruption_rate: 0.0,
-            receive_queue: std::collections::VecDeque::new(),
-            stats: NetworkStats::new(),
+            receive_queue: SimulatedQueue::default(),
+            peer_queue: None,
+            stats: StdMutex::new(NetworkStats::new()),
             is_active: false,
             local_addr: None,
+            tap: None,
+            arrivals: Arc::default(),
         })
     }
     
+    /// Crée deux transports simulés reliés entre eux
+    /// 
+    /// Ce que l'un envoie arrive dans la file de réception de l'autre, avec
+    /// pour source l'adresse locale de l'émetteur. Les paramètres de
+    /// simulation (perte, latence) s'appliquent au sens d'émission.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{SimulatedTransport, NetworkConfig};
+    /// 
+    /// let (mut a, b) = SimulatedTransport::pair(NetworkConfig::test_config()).unwrap();
+    /// a.set_simulation_params(0, 0.05, 0); // 5% de perte de a vers b
+    /// ```
+    pub fn pair(config: NetworkConfig) -> NetworkResult<(Self, Self)> {
+        let mut a = Self::new(config.clone())?;
+        let mut b = Self::new(config)?;
+        a.peer_queue = Some(b.receive_queue.clone());
+        b.peer_queue = Some(a.receive_queue.clone());
+        Ok((a, b))
+    }
+    
     /// Configure les paramètres de simulation
     pub fn set_simulation_params(&mut self, latency_ms: u32, loss_rate: f32, jitter_ms: u32) {
         self.latency_ms = latency_ms;
@@ -394,28 +767,107 @@ impl SimulatedTransport {
         self.jitter_ms = jitter_ms;
     }
     
-    /// Simule l'envoi d'un paquet vers soi-même (loopback)
-    fn simulate_loopback(&mut self, packet: NetworkPacket, target_addr: SocketAddr) {
+    /// Rejoue les datagrammes reçus d'une capture (voir `CaptureWriter`)
+    /// 
+    /// Les datagrammes `Received` sont validés comme par un transport UDP puis
+    /// placés dans la file de réception, dans l'ordre de la capture. Les
+    /// datagrammes invalides sont ignorés (ce sont souvent eux qu'on cherche).
+    /// 
+    /// # Returns
+    /// Le nombre de paquets placés dans la file de réception
+    pub fn replay_capture(&mut self, datagrams: &[CapturedDatagram]) -> usize {
+        let mut queued = 0;
+        
+        for captured in datagrams.iter().filter(|d| d.direction == TapDirection::Received) {
+            match decode_packet(&captured.datagram, captured.remote_addr, &self.config, self.timings.as_ref()) {
+                Ok(packet) => {
+                    self.receive_queue.lock().unwrap().push_back((packet, captured.remote_addr, tokio::time::Instant::now()));
+                    queued += 1;
+                }
+                Err(e) => println!("Rejeu : datagramme de {} ignoré ({})", captured.remote_addr, e),
+            }
+        }
+        
+        queued
+    }
+    
+    /// Sérialise le paquet pour le tap (uniquement si un tap est installé)
+    fn emit_tap(&self, direction: TapDirection, packet: &NetworkPacket, remote_addr: SocketAddr) {
+        if self.tap.is_some() {
+            let mut datagram = Vec::new();
+            packet.encode_into(&mut datagram);
+            capture::emit(&self.tap, direction, self.local_addr, remote_addr, &datagram);
+        }
+    }
+    
+    /// Simule l'envoi d'un paquet vers le pair, ou vers soi-même (loopback)
+    fn simulate_loopback(&self, packet: NetworkPacket, target_addr: SocketAddr) {
         // Simulation de perte de paquets
         if fastrand::f32() < self.loss_rate {
-            self.stats.packets_lost += 1;
+            self.stats.lock().unwrap().packets_lost += 1;
             return;
         }
         
-        // Simulation de latence
-        let _actual_latency = if self.jitter_ms > 0 {
+        // Simulation de latence : le paquet n'est livrable qu'après ce délai,
+        // la gigue pouvant réordonner les paquets
+        let actual_latency = if self.jitter_ms > 0 {
             self.latency_ms + fastrand::u32(0..self.jitter_ms)
         } else {
             self.latency_ms
         };
+        let deliver_at = tokio::time::Instant::now() + Duration::from_millis(actual_latency as u64);
         
-        // Pour simplifier, on ajoute directement dans la queue
-        // Dans un vrai simulateur, on utiliserait un timer
-        self.receive_queue.push_back((packet, target_addr));
-        self.stats.packets_sent += 1;
+        match (&self.peer_queue, self.local_addr) {
+            (Some(peer_queue), Some(source)) => peer_queue.lock().unwrap().push_back((packet, source, deliver_at)),
+            _ => self.receive_queue.lock().unwrap().push_back((packet, target_addr, deliver_at)),
+        }
+        self.stats.lock().unwrap().packets_sent += 1;
+    }
+}
+
+/// Livraison directe dans la file du pair simulé, avec la latence de base
+#[cfg(feature = "simulator")]
+struct SimulatedKeepalive {
+    queue: SimulatedQueue,
+    /// Source vue par le pair (`None` en loopback : la cible est utilisée)
+    source: Option<SocketAddr>,
+    latency: Duration,
+}
+
+#[cfg(feature = "simulator")]
+impl KeepaliveSink for SimulatedKeepalive {
+    fn send_now(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+        let deliver_at = tokio::time::Instant::now() + self.latency;
+        let source = self.source.unwrap_or(target_addr);
+        self.queue.lock().unwrap().push_back((packet.clone(), source, deliver_at));
+        Ok(())
     }
 }
 
+/// Consultation de la file de réception simulée, sans la vider
+#[cfg(feature = "simulator")]
+struct SimulatedArrivals {
+    queue: SimulatedQueue,
+    arrivals: Arc<ArrivalMonitor>,
+}
+
+#[cfg(feature = "simulator")]
+impl KeepaliveSource for SimulatedArrivals {
+    fn last_arrival(&self, peer_addr: SocketAddr) -> Option<Instant> {
+        self.arrivals.watch(peer_addr);
+        let now = tokio::time::Instant::now();
+        let delivered = self.queue.lock().unwrap().iter()
+            .filter(|(_, source, deliver_at)| *source == peer_addr && *deliver_at <= now)
+            .map(|(_, _, deliver_at)| deliver_at.into_std())
+            .max();
+        if let Some(at) = delivered {
+            self.arrivals.record(peer_addr, at);
+        }
+        self.arrivals.watch(peer_addr)
+    }
+}
+
+#[cfg(feature = "simulator")]
 #[async_trait]
 impl NetworkTransport for SimulatedTransport {
     async fn bind(&mut self, local_port: u16) -> NetworkResult<()> {
@@ -425,7 +877,7 @@ impl NetworkTransport for SimulatedTransport {
         Ok(())
     }
     
-    async fn send_packet(&mut self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
+    async fn send_packet(&self, packet: &NetworkPacket, target_addr: SocketAddr) -> NetworkResult<()> {
         if !self.is_active {
             return Err(NetworkError::InvalidState {
                 operation: "send_packet".to_string(),
@@ -440,11 +892,14 @@ impl NetworkTransport for SimulatedTransport {
             packet_copy.checksum = 0xDEADBEEF;
         }
         
+        self.emit_tap(TapDirection::Sent, &packet_copy, target_addr);
+        // Un paquet perdu en route a tout de même été émis
+        self.stats.lock().unwrap().record_bytes_sent(packet_copy.estimated_size(), Instant::now());
         self.simulate_loopback(packet_copy, target_addr);
         Ok(())
     }
     
-    async fn receive_packet(&mut self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
+    async fn receive_packet(&self) -> NetworkResult<(NetworkPacket, SocketAddr)> {
         if !self.is_active {
             return Err(NetworkError::InvalidState {
                 operation: "receive_packet".to_string(),
@@ -452,16 +907,17 @@ impl NetworkTransport for SimulatedTransport {
             });
         }
         
-        // Simulation d'attente
-        if self.latency_ms > 0 {
-            tokio::time::sleep(Duration::from_millis(self.latency_ms as u64)).await;
-        }
-        
         // Utilisation du timeout de configuration
         match timeout(self.config.connection_timeout, async {
             loop {
-                if let Some((packet, addr)) = self.receive_queue.pop_front() {
-                    self.stats.packets_received += 1;
+                if let Some((packet, addr, deliver_at)) = pop_delivered(&self.receive_queue) {
+                    self.arrivals.record(addr, deliver_at.into_std());
+                    {
+                        let mut stats = self.stats.lock().unwrap();
+                        stats.packets_received += 1;
+                        stats.record_bytes_received(packet.estimated_size(), Instant::now());
+                    }
+                    self.emit_tap(TapDirection::Received, &packet, addr);
                     return Ok((packet, addr));
                 }
                 // Simulation d'attente active
@@ -475,14 +931,23 @@ impl NetworkTransport for SimulatedTransport {
     
     async fn shutdown(&mut self) -> NetworkResult<()> {
         self.is_active = false;
-        self.receive_queue.clear();
-        self.stats.reset();
+        self.receive_queue.lock().unwrap().clear();
+        self.stats.lock().unwrap().reset();
         println!("Transport simulé arrêté");
         Ok(())
     }
     
     fn stats(&self) -> NetworkStats {
-        self.stats.clone()
+        let mut stats = self.stats.lock().unwrap().clone();
+        stats.refresh_bitrates(Instant::now());
+        stats
+    }
+    
+    async fn reset_stats(&self) {
+        self.stats.lock().unwrap().reset();
+        if let Some(timings) = &self.timings {
+            timings.reset();
+        }
     }
     
     fn local_addr(&self) -> Option<SocketAddr> {
@@ -492,9 +957,42 @@ impl NetworkTransport for SimulatedTransport {
     fn is_active(&self) -> bool {
         self.is_active
     }
+    
+    fn set_tap(&mut self, tap: Option<PacketTap>) {
+        self.tap = tap;
+    }
+    
+    fn keepalive_sink(&self) -> Option<Arc<dyn KeepaliveSink>> {
+        if !self.is_active {
+            return None;
+        }
+        let (queue, source) = match (&self.peer_queue, self.local_addr) {
+            (Some(peer_queue), Some(source)) => (peer_queue.clone(), Some(source)),
+            _ => (self.receive_queue.clone(), None),
+        };
+        Some(Arc::new(SimulatedKeepalive {
+            queue,
+            source,
+            latency: Duration::from_millis(self.latency_ms as u64),
+        }))
+    }
+    
+    fn keepalive_source(&self) -> Option<Arc<dyn KeepaliveSource>> {
+        if !self.is_active {
+            return None;
+        }
+        Some(Arc::new(SimulatedArrivals {
+            queue: self.receive_queue.clone(),
+            arrivals: self.arrivals.clone(),
+        }))
+    }
+    
+    fn receive_timings(&self) -> Option<&ReceiveTimings> {
+        self.timings.as_ref()
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "udp", feature = "simulator"))]
 mod tests {
     use super::*;
     use std::time::Instant;
@@ -533,13 +1031,114 @@ mod tests {
         assert_eq!(transport.local_addr(), Some("127.0.0.1:9001".parse().unwrap()));
     }
     
+    #[tokio::test]
+    async fn test_simulated_latency_delays_delivery() {
+        use crate::NetworkPacket;
+        
+        let (mut a, mut b) = SimulatedTransport::pair(NetworkConfig::test_config()).unwrap();
+        a.bind(9001).await.unwrap();
+        b.bind(9002).await.unwrap();
+        a.set_simulation_params(60, 0.0, 0);
+        
+        let sent_at = Instant::now();
+        a.send_packet(&NetworkPacket::new_heartbeat(1, 2), "127.0.0.1:9002".parse().unwrap()).await.unwrap();
+        
+        // Pas encore livré
+        assert!(timeout(Duration::from_millis(20), b.receive_packet()).await.is_err());
+        let (_, source) = b.receive_packet().await.unwrap();
+        assert_eq!(source, "127.0.0.1:9001".parse().unwrap());
+        assert!(sent_at.elapsed() >= Duration::from_millis(60));
+    }
+    
+    #[tokio::test]
+    async fn test_simulated_tap_and_replay() {
+        use crate::NetworkPacket;
+        use std::sync::Mutex as StdMutex;
+        
+        let config = NetworkConfig::test_config();
+        let mut transport = SimulatedTransport::new(config.clone()).unwrap();
+        transport.bind(9001).await.unwrap();
+        
+        // Le tap voit l'envoi et la réception
+        let events = Arc::new(StdMutex::new(Vec::new()));
+        let recorded = events.clone();
+        transport.set_tap(Some(Box::new(move |event| {
+            recorded.lock().unwrap().push((event.direction, event.datagram.to_vec()));
+        })));
+        
+        let peer: SocketAddr = "127.0.0.1:9002".parse().unwrap();
+        transport.send_packet(&NetworkPacket::new_heartbeat(1, 2), peer).await.unwrap();
+        transport.receive_packet().await.unwrap();
+        
+        let events = events.lock().unwrap().clone();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].0, TapDirection::Sent);
+        assert_eq!(events[1].0, TapDirection::Received);
+        
+        // Rejeu : seuls les datagrammes reçus valides sont injectés
+        let captured: Vec<CapturedDatagram> = events.into_iter()
+            .map(|(direction, datagram)| CapturedDatagram {
+                direction,
+                timestamp: std::time::SystemTime::now(),
+                local_addr: "127.0.0.1:9001".parse().unwrap(),
+                remote_addr: peer,
+                datagram,
+            })
+            .chain(std::iter::once(CapturedDatagram {
+                direction: TapDirection::Received,
+                timestamp: std::time::SystemTime::now(),
+                local_addr: "127.0.0.1:9001".parse().unwrap(),
+                remote_addr: peer,
+                datagram: vec![0xFF; 8],
+            }))
+            .collect();
+        
+        let mut replay = SimulatedTransport::new(config).unwrap();
+        replay.bind(9001).await.unwrap();
+        assert_eq!(replay.replay_capture(&captured), 1);
+        
+        let (packet, source) = replay.receive_packet().await.unwrap();
+        assert_eq!(packet.sender_id, 1);
+        assert_eq!(source, peer);
+    }
+    
+    #[tokio::test]
+    async fn test_udp_transport_shared_between_tasks() {
+        use crate::{NetworkPacket, utils};
+        
+        let mut transport = UdpTransport::new(NetworkConfig::test_config()).unwrap();
+        transport.bind(0).await.unwrap();
+        let own_addr = utils::localhost(transport.local_addr().unwrap().port());
+        
+        // Une tâche reçoit pendant qu'une autre envoie, sans Mutex autour du transport
+        let transport = Arc::new(transport);
+        let receiver = tokio::spawn({
+            let transport = transport.clone();
+            async move {
+                let mut senders = Vec::new();
+                for _ in 0..20 {
+                    senders.push(transport.receive_packet().await.unwrap().0.sender_id);
+                }
+                senders
+            }
+        });
+        for sender_id in 0..20 {
+            transport.send_packet(&NetworkPacket::new_heartbeat(sender_id, 0), own_addr).await.unwrap();
+        }
+        
+        let mut senders = receiver.await.unwrap();
+        senders.sort_unstable();
+        assert_eq!(senders, (0..20).collect::<Vec<_>>());
+        assert_eq!(transport.stats().packets_sent, 20);
+    }
+    
     #[tokio::test]
     async fn test_packet_serialization() {
         use crate::{NetworkPacket};
         use audio::CompressedFrame;
         
         let config = NetworkConfig::default();
-        let mut transport = UdpTransport::new(config).unwrap();
+        let transport = UdpTransport::new(config).unwrap();
         
         let frame = CompressedFrame::new(vec![1, 2, 3, 4], 960, Instant::now(), 42);
         let mut packet = NetworkPacket::new_audio(frame, 123, 456);
@@ -561,4 +1160,28 @@ mod tests {
         let result = transport.deserialize_packet(invalid_data, source_addr);
         assert!(result.is_err());
     }
+    
+    #[tokio::test]
+    async fn test_receive_timings() {
+        use crate::{NetworkPacket, ReceiveStage};
+        
+        let mut config = NetworkConfig::default();
+        assert!(UdpTransport::new(config.clone()).unwrap().timing_stats().is_none());
+        config.timing_stats = true;
+        let transport = UdpTransport::new(config).unwrap();
+        let source_addr: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        
+        let mut packet = NetworkPacket::new_heartbeat(1, 2);
+        let datagram = transport.serialize_packet(&mut packet).unwrap();
+        transport.deserialize_packet(&datagram, source_addr).unwrap();
+        assert!(transport.deserialize_packet(b"invalid packet data", source_addr).is_err());
+        
+        // Un datagramme illisible est compté en désérialisation mais pas en checksum
+        let stats = transport.timing_stats().unwrap();
+        assert_eq!(stats.stage(ReceiveStage::Deserialize).count, 2);
+        assert_eq!(stats.stage(ReceiveStage::Checksum).count, 1);
+        
+        transport.reset_stats().await;
+        assert_eq!(transport.timing_stats().unwrap().stage(ReceiveStage::Deserialize).count, 0);
+    }
 }
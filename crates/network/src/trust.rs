@@ -0,0 +1,235 @@
+//! Confiance au premier usage (TOFU) dans l'identité des pairs
+//!
+//! À la première connexion d'une identité inconnue (`PeerIdentity`), le
+//! manager demande à l'application (`TrustCallback`) si elle l'accepte pour
+//! cet appel, la retient ou la refuse. Les décisions retenues sont
+//! conservées dans `KnownPeers`, sur disque avec
+//! `NetworkConfig::known_peers_file` : une identité refusée est ensuite
+//! rejetée sans question (`NetworkError::UntrustedPeer`), et un pair connu
+//! qui se présente à la même adresse avec une autre identité aussi
+//! (`NetworkError::FingerprintChanged`), comme `known_hosts` pour SSH.
+//!
+//! Seule une identité prouvée sur un défi tiré par le manager pour ce
+//! handshake est jugée (voir `identity`) : un handshake capturé puis rejoué
+//! laisse le pair anonyme, il n'hérite ni de la confiance retenue pour
+//! l'identité qu'il annonce, ni d'une décision à retenir.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{NetworkError, NetworkResult, PeerIdentity};
+
+/// Réponse de l'application pour une identité inconnue
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrustDecision {
+    /// Acceptée pour cet appel seulement : la question reviendra
+    Accept,
+    /// Acceptée et retenue : les appels suivants passent sans question
+    Remember,
+    /// Refusée et retenue : les appels suivants sont rejetés sans question
+    Reject,
+}
+
+/// Identité inconnue présentée à l'application
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TrustRequest {
+    /// Adresse du pair
+    pub addr: SocketAddr,
+    /// Identité prouvée par le pair sur notre défi (non rejouable)
+    pub identity: PeerIdentity,
+}
+
+impl TrustRequest {
+    /// Empreinte à comparer avec celle que le pair lit sur son écran
+    pub fn fingerprint(&self) -> String {
+        self.identity.fingerprint()
+    }
+}
+
+/// Question posée à l'application pour chaque identité inconnue
+///
+/// Appelée depuis le manager dès que l'identité du pair est prouvée : dans
+/// sa réponse à notre Hello, ou par sa preuve juste après notre `Accept`.
+/// Une attente de l'utilisateur retarde l'appel d'autant (le pair patiente
+/// jusqu'à son `connection_timeout`).
+pub type TrustCallback = Box<dyn Fn(&TrustRequest) -> TrustDecision + Send + Sync>;
+
+/// Situation d'une identité par rapport aux décisions retenues
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrustStatus {
+    /// Identité retenue comme de confiance
+    Trusted,
+    /// Identité refusée
+    Rejected,
+    /// Un pair de confiance utilisait une autre identité à cette adresse
+    Changed { expected: PeerIdentity },
+    /// Aucune décision retenue
+    Unknown,
+}
+
+/// Décision retenue pour une identité
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownPeer {
+    /// Dernière adresse du pair
+    pub addr: SocketAddr,
+    /// Identité du pair, en hexadécimal dans le fichier
+    #[serde(serialize_with = "serialize_identity", deserialize_with = "deserialize_identity")]
+    pub identity: PeerIdentity,
+    /// Acceptée (`Remember`) ou refusée (`Reject`)
+    pub trusted: bool,
+}
+
+/// Contenu du fichier des pairs connus
+#[derive(Default, Serialize, Deserialize)]
+struct KnownPeersFile {
+    #[serde(default)]
+    peers: Vec<KnownPeer>,
+}
+
+/// Décisions retenues, enregistrées à chaque modification si un fichier
+/// est associé
+///
+/// # Example
+/// ```rust,no_run
+/// use network::KnownPeers;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let known = KnownPeers::load("known_peers.toml")?;
+/// for peer in known.peers() {
+///     println!("{} {} : {}", peer.addr, peer.identity.fingerprint(),
+///         if peer.trusted { "confiance" } else { "refusé" });
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KnownPeers {
+    peers: Vec<KnownPeer>,
+    path: Option<PathBuf>,
+}
+
+impl KnownPeers {
+    /// Décisions en mémoire seulement, perdues à la fermeture
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lit les décisions enregistrées dans `path` (aucune si le fichier
+    /// n'existe pas encore) ; les suivantes y seront enregistrées
+    ///
+    /// # Erreurs
+    /// - `NetworkError::IoError` : fichier illisible
+    /// - `NetworkError::ConfigError` : contenu invalide
+    pub fn load(path: impl AsRef<Path>) -> NetworkResult<Self> {
+        let path = path.as_ref();
+        let peers = if path.exists() {
+            let text = fs::read_to_string(path)?;
+            let file: KnownPeersFile = toml::from_str(&text)
+                .map_err(|e| NetworkError::ConfigError(format!("{} : {}", path.display(), e)))?;
+            file.peers
+        } else {
+            Vec::new()
+        };
+        Ok(Self { peers, path: Some(path.to_path_buf()) })
+    }
+
+    /// Décisions retenues
+    pub fn peers(&self) -> &[KnownPeer] {
+        &self.peers
+    }
+
+    /// Situation de `identity`, présentée par le pair à `addr`
+    pub fn check(&self, addr: SocketAddr, identity: PeerIdentity) -> TrustStatus {
+        if let Some(known) = self.peers.iter().find(|peer| peer.identity == identity) {
+            return if known.trusted { TrustStatus::Trusted } else { TrustStatus::Rejected };
+        }
+        match self.peers.iter().find(|peer| peer.addr == addr && peer.trusted) {
+            Some(known) => TrustStatus::Changed { expected: known.identity },
+            None => TrustStatus::Unknown,
+        }
+    }
+
+    /// Retient la décision pour `identity` (remplace la précédente)
+    pub fn remember(&mut self, addr: SocketAddr, identity: PeerIdentity, trusted: bool) -> NetworkResult<()> {
+        self.peers.retain(|peer| peer.identity != identity);
+        self.peers.push(KnownPeer { addr, identity, trusted });
+        self.save()
+    }
+
+    /// Oublie la décision pour `identity` (la question sera reposée)
+    ///
+    /// Permet notamment d'accepter le changement d'identité d'un pair
+    /// (réinstallation) après `NetworkError::FingerprintChanged`.
+    pub fn forget(&mut self, identity: PeerIdentity) -> NetworkResult<()> {
+        self.peers.retain(|peer| peer.identity != identity);
+        self.save()
+    }
+
+    fn save(&self) -> NetworkResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = KnownPeersFile { peers: self.peers.clone() };
+        let text = toml::to_string_pretty(&file)
+            .map_err(|e| NetworkError::ConfigError(format!("Sérialisation des pairs connus impossible: {}", e)))?;
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+fn serialize_identity<S: Serializer>(identity: &PeerIdentity, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(identity)
+}
+
+fn deserialize_identity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PeerIdentity, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Identity;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([192, 168, 1, 20], port))
+    }
+
+    #[test]
+    fn test_known_peers_decisions() {
+        let alice = Identity::from_secret_bytes([1; 32]).public();
+        let mallory = Identity::from_secret_bytes([2; 32]).public();
+        let mut known = KnownPeers::new();
+        assert_eq!(known.check(addr(9001), alice), TrustStatus::Unknown);
+
+        known.remember(addr(9001), alice, true).unwrap();
+        assert_eq!(known.check(addr(9001), alice), TrustStatus::Trusted);
+        // Le pair a changé d'adresse : toujours reconnu
+        assert_eq!(known.check(addr(9002), alice), TrustStatus::Trusted);
+        // Autre identité à l'adresse d'Alice
+        assert_eq!(known.check(addr(9001), mallory), TrustStatus::Changed { expected: alice });
+
+        known.remember(addr(9001), mallory, false).unwrap();
+        assert_eq!(known.check(addr(9001), mallory), TrustStatus::Rejected);
+        known.forget(mallory).unwrap();
+        assert_eq!(known.check(addr(9003), mallory), TrustStatus::Unknown);
+    }
+
+    #[test]
+    fn test_known_peers_persist() {
+        let path = std::env::temp_dir().join(format!("voc-known-peers-{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let alice = Identity::from_secret_bytes([1; 32]).public();
+
+        KnownPeers::load(&path).unwrap().remember(addr(9001), alice, true).unwrap();
+        let reloaded = KnownPeers::load(&path).unwrap();
+        assert_eq!(reloaded.peers(), &[KnownPeer { addr: addr(9001), identity: alice, trusted: true }]);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,25 +2,75 @@
 //! 
 //! Ce module définit les structures principales pour la communication réseau :
 //! - NetworkPacket : Paquet réseau pour transport audio P2P
+//! - PacketPayload : Contenu d'un paquet (audio, contrôle, octets bruts)
 //! - ConnectionState : États de connexion entre pairs
 //! - NetworkConfig : Configuration du système réseau
 //! - NetworkStats : Statistiques et métriques réseau
 
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::borrow::Cow;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use audio::CompressedFrame;
+use audio::{AudioConfig, CompressedFrame, FrameMetadata};
+use crate::congestion::CongestionState;
+#[cfg(any(feature = "udp", feature = "simulator"))]
+use crate::quality::RateWindow;
+use crate::error::{NetworkError, NetworkResult};
+use crate::discovery::DiscoveryMessage;
+use crate::identity::{HandshakeIdentity, ProofChallenge};
 
 /// Paquet réseau pour le transport d'audio P2P
 /// 
-/// Cette structure encapsule les frames audio compressées pour transmission UDP.
+/// Cette structure encapsule les frames audio compressées pour transmission UDP,
+/// ainsi que les messages du protocole (voir `PacketPayload`).
 /// Elle inclut les métadonnées nécessaires pour la détection d'erreurs,
 /// la synchronisation et les statistiques de performance.
 /// 
 /// Structure du paquet :
-/// - Header : métadonnées (32 bytes)
-/// - Payload : frame audio compressée (80-200 bytes typique)
+/// - Header : métadonnées (40 bytes)
+/// - Payload : frame audio compressée (80-200 bytes typique) ou message
 /// - Total : ~120-250 bytes par paquet (largement < MTU 1400 bytes)
+/// 
+/// # Format sur le réseau (v4)
+/// 
+/// En-tête de taille fixe, entiers en big-endian, décrit par `WIRE_LAYOUT` :
+/// 
+/// | Offset | Taille | Champ                   |
+/// |--------|--------|-------------------------|
+/// | 0      | 2      | magic `"VC"`            |
+/// | 2      | 1      | protocol_version        |
+/// | 3      | 1      | packet_type             |
+/// | 4      | 2      | flags (voir `FLAG_*`)   |
+/// | 6      | 2      | payload_len             |
+/// | 8      | 8      | sender_id               |
+/// | 16     | 8      | session_id              |
+/// | 24     | 8      | sequence_number         |
+/// | 32     | 4      | original_sample_count   |
+/// | 36     | 4      | checksum                |
+/// | 40     | n      | payload                 |
+/// 
+/// `sequence_number` et `original_sample_count` valent 0 hors paquets audio.
+/// Le payload est la frame compressée (audio), le `ControlMessage` sérialisé
+/// (contrôle) ou les octets bruts du message (autres types).
+/// 
+/// Les flags portent les métadonnées d'activité vocale de la frame audio
+/// (`CompressedFrame::metadata`) : bit 15 = présentes, bit 7 = parole,
+/// bits 0-6 = niveau en -dBov. Ils ne sont pas couverts par le checksum,
+/// pour rester lisibles par les builds v3 qui les ignorent.
+/// 
+/// Bit 14 = payload complété jusqu'à une taille fixe (mode padding, voir
+/// `PaddingConfig`) : les deux derniers octets du payload donnent la taille
+/// du bourrage, eux compris. Un paquet complété ne porte pas de métadonnées.
+/// 
+/// Bit 13 = frame datée (`CompressedFrame::media_timestamp`) : les 8 octets
+/// qui suivent la frame (avant un éventuel bourrage) donnent sa position sur
+/// l'horloge de capture, couverte par le checksum. Envoyée seulement au pair
+/// qui l'a demandée (`ControlMessage::MediaTimestamps`).
+/// 
+/// Les versions 1 et 2 (sérialisation bincode, sans magic) et la version 3
+/// (même en-tête, identifiants sur 32 bits) restent lisibles via la feature
+/// `legacy-protocol`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NetworkPacket {
     /// Version du protocole pour compatibilité future
@@ -30,13 +80,16 @@ pub struct NetworkPacket {
     pub packet_type: PacketType,
     
     /// ID unique du sender (pour support multi-peer futur)
-    pub sender_id: u32,
+    /// 
+    /// 64 bits depuis l'en-tête v4 ; les versions précédentes n'en portent
+    /// que les 32 bits de poids faible (voir `wire_id`)
+    pub sender_id: u64,
     
-    /// ID de session pour détecter les reconnexions
-    pub session_id: u32,
+    /// ID de session pour détecter les reconnexions (64 bits, voir `sender_id`)
+    pub session_id: u64,
     
-    /// Frame audio compressée transportée
-    pub compressed_frame: CompressedFrame,
+    /// Contenu transporté (frame audio, message de contrôle, octets bruts)
+    pub payload: PacketPayload,
     
     /// Timestamp d'envoi pour calcul RTT et latence
     /// Skip la sérialisation car Instant n'est pas portable entre machines
@@ -50,34 +103,99 @@ pub struct NetworkPacket {
 
 impl NetworkPacket {
     /// Version actuelle du protocole
-    pub const CURRENT_PROTOCOL_VERSION: u8 = 1;
+    /// 
+    /// - v1 : audio, heartbeat, handshake, disconnect
+    /// - v2 : ajout des paquets `Discovery` et `Error`
+    /// - v3 : magic bytes + en-tête explicite (voir `WIRE_LAYOUT`) ; paquets
+    ///   `Control` (renégociation du codec) ajoutés ensuite, rejetés comme
+    ///   invalides par les builds v3 antérieures
+    /// - v4 : `sender_id` et `session_id` sur 64 bits (en-tête de 40 octets)
+    /// 
+    /// Le format de chaque version est figé par les fixtures de `golden/` :
+    /// procédure de changement de version dans `docs/FORMAT_RESEAU.md`.
+    pub const CURRENT_PROTOCOL_VERSION: u8 = 4;
+    
+    /// Plus ancienne version acceptée (via les convertisseurs `legacy-protocol`)
+    pub const MIN_SUPPORTED_PROTOCOL_VERSION: u8 = 1;
     
     /// Taille maximum autorisée pour un paquet (MTU safe)
     pub const MAX_PACKET_SIZE: usize = 1400;
     
-    /// Crée un nouveau paquet audio
+    /// Taille maximum des données de l'application jointes aux heartbeats
+    /// (voir `new_heartbeat_with_payload`)
+    pub const MAX_HEARTBEAT_PAYLOAD: usize = 64;
+    
+    /// Octets magiques en tête de chaque datagramme (détection heuristique)
+    pub const MAGIC: [u8; 2] = *b"VC";
+    
+    /// Taille de l'en-tête fixe
+    pub const HEADER_SIZE: usize = 40;
+    
+    /// Flag : l'en-tête porte les métadonnées de la frame
+    pub const FLAG_METADATA: u16 = 0x8000;
+    
+    /// Flag : la frame contient de la parole
+    pub const FLAG_SPEECH: u16 = 0x0080;
+    
+    /// Masque du niveau de la frame (-dBov, 7 bits)
+    pub const FLAG_LEVEL_MASK: u16 = 0x007F;
+    
+    /// Flag : le payload est suivi d'un bourrage (mode padding)
+    pub const FLAG_PADDED: u16 = 0x4000;
+    
+    /// Flag : la frame est suivie de son horodatage média (8 octets)
+    pub const FLAG_MEDIA_TIMESTAMP: u16 = 0x2000;
+    
+    /// Taille de l'horodatage média en fin de payload
+    pub const MEDIA_TIMESTAMP_SIZE: usize = 8;
+    
+    /// Octets de l'en-tête jusqu'à la taille du payload incluse, communs à
+    /// toutes les versions à magic (v3 et suivantes)
+    const PADDED_HEADER_PREFIX: usize = 8;
+    
+    /// Description de l'en-tête fixe, source unique du format
+    /// 
+    /// Utilisée par `encode_into`/`decode_from` (tests de cohérence) et pour
+    /// générer le dissecteur Wireshark (`utils::wireshark_dissector_lua`).
+    pub const WIRE_LAYOUT: &'static [WireField] = &[
+        WireField { name: "magic", offset: 0, size: 2, description: "Octets magiques \"VC\"" },
+        WireField { name: "version", offset: 2, size: 1, description: "Version du protocole" },
+        WireField { name: "packet_type", offset: 3, size: 1, description: "Type de paquet" },
+        WireField { name: "flags", offset: 4, size: 2, description: "Drapeaux (métadonnées de frame)" },
+        WireField { name: "payload_len", offset: 6, size: 2, description: "Taille du payload" },
+        WireField { name: "sender_id", offset: 8, size: 8, description: "ID de l'expéditeur" },
+        WireField { name: "session_id", offset: 16, size: 8, description: "ID de session" },
+        WireField { name: "sequence_number", offset: 24, size: 8, description: "Numéro de séquence" },
+        WireField { name: "sample_count", offset: 32, size: 4, description: "Échantillons de la frame d'origine" },
+        WireField { name: "checksum", offset: 36, size: 4, description: "Checksum XOR" },
+    ];
+    
+    /// Crée un paquet du type donné, dans la version courante du protocole
+    /// 
+    /// Permet de transporter un contenu hors audio (`PacketPayload::Raw`).
+    /// Le checksum est calculé.
     /// 
     /// # Arguments
-    /// * `compressed_frame` - La frame audio compressée à transporter
+    /// * `packet_type` - Type du paquet
+    /// * `payload` - Contenu transporté
     /// * `sender_id` - ID unique de l'expéditeur
     /// * `session_id` - ID de la session courante
     /// 
     /// # Example
     /// ```rust
-    /// use network::{NetworkPacket, PacketType};
-    /// use audio::CompressedFrame;
-    /// use std::time::Instant;
+    /// use network::{NetworkPacket, PacketPayload, PacketType};
     /// 
-    /// let frame = CompressedFrame::new(vec![1, 2, 3], 960, Instant::now(), 42);
-    /// let packet = NetworkPacket::new_audio(frame, 123, 456);
+    /// let packet = NetworkPacket::new(PacketType::Disconnect, PacketPayload::None, 1, 2);
+    /// assert!(packet.verify_checksum());
+    /// assert!(packet.audio_frame().is_none());
     /// ```
-    pub fn new_audio(compressed_frame: CompressedFrame, sender_id: u32, session_id: u32) -> Self {
+    pub fn new(packet_type: PacketType, payload: PacketPayload, sender_id: u64, session_id: u64) -> Self {
         let mut packet = Self {
             protocol_version: Self::CURRENT_PROTOCOL_VERSION,
-            packet_type: PacketType::Audio,
+            packet_type,
             sender_id,
             session_id,
-            compressed_frame,
+            payload,
             send_timestamp: Instant::now(),
             checksum: 0,
         };
@@ -86,23 +204,587 @@ impl NetworkPacket {
         packet
     }
     
+    /// Crée un nouveau paquet audio
+    /// 
+    /// # Arguments
+    /// * `compressed_frame` - La frame audio compressée à transporter
+    /// * `sender_id` - ID unique de l'expéditeur
+    /// * `session_id` - ID de la session courante
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{NetworkPacket, PacketType};
+    /// use audio::CompressedFrame;
+    /// use std::time::Instant;
+    /// 
+    /// let frame = CompressedFrame::new(vec![1, 2, 3], 960, Instant::now(), 42);
+    /// let packet = NetworkPacket::new_audio(frame, 123, 456);
+    /// ```
+    pub fn new_audio(compressed_frame: CompressedFrame, sender_id: u64, session_id: u64) -> Self {
+        Self::new(PacketType::Audio, PacketPayload::Audio(compressed_frame), sender_id, session_id)
+    }
+    
     /// Crée un paquet heartbeat (keep-alive)
-    pub fn new_heartbeat(sender_id: u32, session_id: u32) -> Self {
-        // Frame vide pour heartbeat
-        let empty_frame = CompressedFrame::new(vec![], 0, Instant::now(), 0);
+    pub fn new_heartbeat(sender_id: u64, session_id: u64) -> Self {
+        Self::new(PacketType::Heartbeat, PacketPayload::None, sender_id, session_id)
+    }
+    
+    /// Crée un paquet factice, envoyé à la place d'une frame en mode padding
+    /// 
+    /// Sans contenu : le transport le complète à la taille des paquets audio.
+    pub fn new_padding(sender_id: u64, session_id: u64) -> Self {
+        Self::new(PacketType::Padding, PacketPayload::None, sender_id, session_id)
+    }
+    
+    /// Crée un paquet heartbeat portant le rapport de réception local
+    /// 
+    /// Le pair apprend ainsi la qualité avec laquelle son audio est reçu.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{NetworkPacket, PeerStatsReport};
+    /// 
+    /// let report = PeerStatsReport { packets_received: 250, packets_lost: 5, loss_percent: 2.0, jitter_ms: 4.5 };
+    /// let packet = NetworkPacket::new_heartbeat_with_stats(&report, 1, 2);
+    /// assert_eq!(packet.peer_stats(), Some(report));
+    /// ```
+    pub fn new_heartbeat_with_stats(report: &PeerStatsReport, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(report).unwrap_or_default();
+        Self::new(PacketType::Heartbeat, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait le rapport de réception d'un paquet `Heartbeat`
+    /// 
+    /// Retourne `None` pour un heartbeat simple (sans rapport). Sans compte
+    /// de pertes (pairs des versions précédentes, voir
+    /// `new_heartbeat_with_losses`), `packets_lost` est déduit de
+    /// `loss_percent`, arrondi.
+    pub fn peer_stats(&self) -> Option<PeerStatsReport> {
+        if self.packet_type != PacketType::Heartbeat {
+            return None;
+        }
+        let mut report: PeerStatsReport = self.deserialize_raw()?;
+        report.packets_lost = match self.deserialize_raw::<(PeerStatsReport, FlowControlHint, Vec<u8>, u64)>() {
+            Some((_, _, _, packets_lost)) => packets_lost,
+            None => report.estimated_lost(),
+        };
+        Some(report)
+    }
+    
+    /// Crée un heartbeat portant le rapport de réception et l'occupation du
+    /// buffer anti-jitter local
+    /// 
+    /// L'indication est sérialisée après le rapport : un pair qui ne la
+    /// connaît pas lit le rapport et ignore la suite.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{FlowControlHint, NetworkPacket, PeerStatsReport};
+    /// 
+    /// let report = PeerStatsReport { packets_received: 250, packets_lost: 0, loss_percent: 0.0, jitter_ms: 4.5 };
+    /// let hint = FlowControlHint { buffer_fill_percent: 95, overflow_drops: 3 };
+    /// let packet = NetworkPacket::new_heartbeat_with_feedback(&report, hint, 1, 2);
+    /// assert_eq!(packet.peer_stats(), Some(report));
+    /// assert_eq!(packet.flow_control_hint(), Some(hint));
+    /// ```
+    pub fn new_heartbeat_with_feedback(report: &PeerStatsReport, hint: FlowControlHint, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(&(report, hint)).unwrap_or_default();
+        Self::new(PacketType::Heartbeat, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait l'indication de contrôle de flux d'un paquet `Heartbeat`
+    /// 
+    /// `None` pour les pairs qui n'en envoient pas (versions précédentes).
+    pub fn flow_control_hint(&self) -> Option<FlowControlHint> {
+        if self.packet_type != PacketType::Heartbeat {
+            return None;
+        }
+        self.deserialize_raw::<(PeerStatsReport, FlowControlHint)>().map(|(_, hint)| hint)
+    }
+    
+    /// Crée un heartbeat portant, en plus du rapport et de l'indication de
+    /// contrôle de flux, des données libres de l'application (au plus
+    /// `MAX_HEARTBEAT_PAYLOAD` octets)
+    /// 
+    /// Sérialisées après l'indication : ignorées des pairs qui ne les
+    /// connaissent pas.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{FlowControlHint, NetworkPacket, PeerStatsReport};
+    /// 
+    /// let report = PeerStatsReport { packets_received: 250, packets_lost: 0, loss_percent: 0.0, jitter_ms: 4.5 };
+    /// let hint = FlowControlHint { buffer_fill_percent: 40, overflow_drops: 0 };
+    /// let packet = NetworkPacket::new_heartbeat_with_payload(&report, hint, b"batterie=80", 1, 2);
+    /// assert_eq!(packet.flow_control_hint(), Some(hint));
+    /// assert_eq!(packet.heartbeat_payload(), Some(b"batterie=80".to_vec()));
+    /// ```
+    pub fn new_heartbeat_with_payload(
+        report: &PeerStatsReport,
+        hint: FlowControlHint,
+        payload: &[u8],
+        sender_id: u64,
+        session_id: u64,
+    ) -> Self {
+        let data = bincode::serialize(&(report, hint, payload)).unwrap_or_default();
+        Self::new(PacketType::Heartbeat, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Crée un heartbeat complet : rapport, contrôle de flux, données de
+    /// l'application (vides s'il n'y en a pas) puis compte exact des pertes
+    /// (`PeerStatsReport::packets_lost`)
+    /// 
+    /// Le compte est sérialisé en dernier : les pairs qui ne le connaissent
+    /// pas l'ignorent. Des données vides équivalent à une absence de données.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{FlowControlHint, NetworkPacket, PeerStatsReport};
+    /// 
+    /// let report = PeerStatsReport { packets_received: 1000, packets_lost: 7, loss_percent: 0.7, jitter_ms: 4.5 };
+    /// let hint = FlowControlHint { buffer_fill_percent: 40, overflow_drops: 0 };
+    /// let packet = NetworkPacket::new_heartbeat_with_losses(&report, hint, &[], 1, 2);
+    /// assert_eq!(packet.peer_stats().map(|report| report.packets_lost), Some(7));
+    /// assert_eq!(packet.heartbeat_payload(), Some(Vec::new()));
+    /// ```
+    pub fn new_heartbeat_with_losses(
+        report: &PeerStatsReport,
+        hint: FlowControlHint,
+        payload: &[u8],
+        sender_id: u64,
+        session_id: u64,
+    ) -> Self {
+        let data = bincode::serialize(&(report, hint, payload, report.packets_lost)).unwrap_or_default();
+        Self::new(PacketType::Heartbeat, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait les données de l'application d'un paquet `Heartbeat`
+    /// 
+    /// `None` pour un heartbeat qui n'en porte pas, ou au-delà de
+    /// `MAX_HEARTBEAT_PAYLOAD` octets.
+    pub fn heartbeat_payload(&self) -> Option<Vec<u8>> {
+        if self.packet_type != PacketType::Heartbeat {
+            return None;
+        }
+        self.deserialize_raw::<(PeerStatsReport, FlowControlHint, Vec<u8>)>()
+            .map(|(_, _, payload)| payload)
+            .filter(|payload| payload.len() <= Self::MAX_HEARTBEAT_PAYLOAD)
+    }
+    
+    /// Crée un paquet de découverte LAN (sonde ou réponse)
+    /// 
+    /// Le message est sérialisé dans le payload.
+    pub fn new_discovery(message: &DiscoveryMessage, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(message).unwrap_or_default();
+        Self::new(PacketType::Discovery, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait le message de découverte d'un paquet `Discovery`
+    pub fn discovery_message(&self) -> Option<DiscoveryMessage> {
+        if self.packet_type != PacketType::Discovery {
+            return None;
+        }
+        self.deserialize_raw()
+    }
+    
+    /// Crée un paquet de handshake portant un message `Hello` ou `Accept`
+    pub fn new_handshake(message: HandshakeMessage, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(&message).unwrap_or_default();
+        Self::new(PacketType::Handshake, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Crée un paquet de handshake suivi d'informations sur l'expéditeur
+    /// 
+    /// Les informations sont sérialisées après le message : un pair qui ne
+    /// les connaît pas lit le message et ignore la suite.
+    pub fn new_handshake_with_info(message: HandshakeMessage, info: HandshakeInfo, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(&(message, info)).unwrap_or_default();
+        Self::new(PacketType::Handshake, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Crée un paquet de handshake suivi des informations sur l'expéditeur et
+    /// de son jeton de reprise
+    /// 
+    /// Le pair qui présente ce jeton dans un `HandshakeMessage::Resume`
+    /// reprend la session interrompue (voir `NetworkConfig::resume_grace`).
+    /// Comme les informations, le jeton est ignoré des pairs qui ne le
+    /// connaissent pas.
+    pub fn new_handshake_with_token(message: HandshakeMessage, info: HandshakeInfo, resume_token: u64, sender_id: u64, session_id: u64) -> Self {
+        let data = bincode::serialize(&(message, info, resume_token)).unwrap_or_default();
+        Self::new(PacketType::Handshake, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Crée un paquet de handshake suivi des informations sur l'expéditeur,
+    /// de son jeton de reprise et de son identité (voir `Identity`)
+    /// 
+    /// Un jeton nul signifie que la reprise est désactivée. Comme les
+    /// informations et le jeton, l'identité est ignorée des pairs qui ne la
+    /// connaissent pas.
+    pub fn new_handshake_with_identity(
+        message: HandshakeMessage,
+        info: HandshakeInfo,
+        resume_token: u64,
+        identity: &HandshakeIdentity,
+        sender_id: u64,
+        session_id: u64,
+    ) -> Self {
+        let data = bincode::serialize(&(message, info, resume_token, identity)).unwrap_or_default();
+        Self::new(PacketType::Handshake, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Crée un paquet de handshake avec identité, suivi du défi de
+    /// l'expéditeur (voir `ProofChallenge`)
+    /// 
+    /// Le pair prouve son identité sur ce défi : dans son `Accept`, ou par
+    /// `ControlMessage::IdentityProof` s'il a envoyé le `Hello`. Les pairs qui
+    /// ne le connaissent pas l'ignorent.
+    pub fn new_handshake_with_challenge(
+        message: HandshakeMessage,
+        info: HandshakeInfo,
+        resume_token: u64,
+        identity: &HandshakeIdentity,
+        challenge: u64,
+        sender_id: u64,
+        session_id: u64,
+    ) -> Self {
+        let data = bincode::serialize(&(message, info, resume_token, identity, challenge)).unwrap_or_default();
+        Self::new(PacketType::Handshake, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait le message d'un paquet `Handshake`
+    /// 
+    /// Retourne `None` pour un handshake sans message (pairs legacy v1/v2),
+    /// à interpréter selon le contexte (demande ou réponse).
+    pub fn handshake_message(&self) -> Option<HandshakeMessage> {
+        if self.packet_type != PacketType::Handshake {
+            return None;
+        }
+        self.deserialize_raw()
+    }
+    
+    /// Extrait les informations jointes au message d'un paquet `Handshake`
+    /// 
+    /// `None` pour les pairs qui n'en envoient pas (versions précédentes).
+    pub fn handshake_info(&self) -> Option<HandshakeInfo> {
+        if self.packet_type != PacketType::Handshake {
+            return None;
+        }
+        self.deserialize_raw::<(HandshakeMessage, HandshakeInfo)>().map(|(_, info)| info)
+    }
+    
+    /// Extrait le jeton de reprise joint au message d'un paquet `Handshake`
+    /// 
+    /// `None` pour les pairs qui n'en annoncent pas (reprise désactivée,
+    /// versions précédentes).
+    pub fn resume_token(&self) -> Option<u64> {
+        if self.packet_type != PacketType::Handshake {
+            return None;
+        }
+        self.deserialize_raw::<(HandshakeMessage, HandshakeInfo, u64)>()
+            .map(|(_, _, token)| token)
+            .filter(|&token| token != 0)
+    }
+    
+    /// Extrait l'identité jointe au message d'un paquet `Handshake`, sans la
+    /// vérifier (voir `HandshakeIdentity::verify`)
+    /// 
+    /// `None` pour les pairs qui n'en annoncent pas (versions précédentes).
+    pub fn handshake_identity(&self) -> Option<HandshakeIdentity> {
+        if self.packet_type != PacketType::Handshake {
+            return None;
+        }
+        self.deserialize_raw::<(HandshakeMessage, HandshakeInfo, u64, HandshakeIdentity)>()
+            .map(|(_, _, _, identity)| identity)
+    }
+    
+    /// Extrait le défi joint à un paquet `Handshake`, avec les identifiants
+    /// de l'expéditeur (voir `new_handshake_with_challenge`)
+    /// 
+    /// `None` pour les pairs qui n'en envoient pas (versions précédentes).
+    pub fn handshake_challenge(&self) -> Option<ProofChallenge> {
+        if self.packet_type != PacketType::Handshake {
+            return None;
+        }
+        self.deserialize_raw::<(HandshakeMessage, HandshakeInfo, u64, HandshakeIdentity, u64)>()
+            .map(|(_, _, _, _, nonce)| ProofChallenge { sender_id: self.sender_id, session_id: self.session_id, nonce })
+    }
+    
+    /// Crée un paquet d'erreur protocolaire à destination du pair distant
+    /// 
+    /// Permet au pair de connaître la raison d'un refus (serveur plein,
+    /// version incompatible...) au lieu d'attendre un timeout.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{NetworkPacket, ProtocolErrorCode};
+    /// 
+    /// let packet = NetworkPacket::new_error(ProtocolErrorCode::ServerFull, "appel en cours", 1, 2);
+    /// let error = packet.error_message().unwrap();
+    /// assert_eq!(error.code, ProtocolErrorCode::ServerFull);
+    /// ```
+    pub fn new_error(code: ProtocolErrorCode, description: &str, sender_id: u64, session_id: u64) -> Self {
+        let message = ProtocolErrorMessage {
+            code,
+            description: description.to_string(),
+        };
+        let data = bincode::serialize(&message).unwrap_or_default();
+        Self::new(PacketType::Error, PacketPayload::Raw(data), sender_id, session_id)
+    }
+    
+    /// Extrait le message d'erreur d'un paquet `Error`
+    pub fn error_message(&self) -> Option<ProtocolErrorMessage> {
+        if self.packet_type != PacketType::Error {
+            return None;
+        }
+        self.deserialize_raw()
+    }
+    
+    /// Crée un paquet de contrôle en cours d'appel
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::{CodecParams, ControlMessage, NetworkPacket};
+    /// 
+    /// let message = ControlMessage::Renegotiate { params: CodecParams::music(), switch_at: 120 };
+    /// let packet = NetworkPacket::new_control(&message, 1, 2);
+    /// assert_eq!(packet.control_message(), Some(message));
+    /// ```
+    pub fn new_control(message: &ControlMessage, sender_id: u64, session_id: u64) -> Self {
+        Self::new(PacketType::Control, PacketPayload::Control(message.clone()), sender_id, session_id)
+    }
+    
+    /// Extrait le message d'un paquet `Control`
+    pub fn control_message(&self) -> Option<ControlMessage> {
+        match (self.packet_type, &self.payload) {
+            (PacketType::Control, PacketPayload::Control(message)) => Some(message.clone()),
+            _ => None,
+        }
+    }
+    
+    /// Frame audio transportée (paquets `Audio`)
+    pub fn audio_frame(&self) -> Option<&CompressedFrame> {
+        match &self.payload {
+            PacketPayload::Audio(frame) => Some(frame),
+            _ => None,
+        }
+    }
+    
+    /// Consomme le paquet et retourne sa frame audio
+    pub fn into_audio_frame(self) -> Option<CompressedFrame> {
+        match self.payload {
+            PacketPayload::Audio(frame) => Some(frame),
+            _ => None,
+        }
+    }
+    
+    /// Numéro de séquence de la frame audio (0 pour les autres paquets)
+    pub fn sequence_number(&self) -> u64 {
+        self.audio_frame().map_or(0, |frame| frame.sequence_number)
+    }
+    
+    /// Désérialise un message porté en octets bruts (`PacketPayload::Raw`)
+    fn deserialize_raw<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        match &self.payload {
+            PacketPayload::Raw(data) if !data.is_empty() => bincode::deserialize(data).ok(),
+            _ => None,
+        }
+    }
+    
+    /// Lit la version du protocole d'un datagramme sans le désérialiser
+    /// 
+    /// Depuis la v3, la version suit les octets magiques ; les formats
+    /// bincode (v1, v2) n'ont pas de magic et commencent par la version.
+    pub fn peek_version(data: &[u8]) -> Option<u8> {
+        if data.starts_with(&Self::MAGIC) {
+            data.get(2).copied()
+        } else {
+            data.first().copied()
+        }
+    }
+    
+    /// Écrit le paquet au format réseau courant dans `buffer`
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::NetworkPacket;
+    /// 
+    /// let packet = NetworkPacket::new_heartbeat(1, 2);
+    /// let mut buffer = Vec::new();
+    /// packet.encode_into(&mut buffer);
+    /// 
+    /// assert_eq!(&buffer[..2], &NetworkPacket::MAGIC);
+    /// let decoded = NetworkPacket::decode_from(&buffer).unwrap();
+    /// assert_eq!(decoded.sender_id, 1);
+    /// ```
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        let payload = self.payload.to_bytes();
+        let media_timestamp = self.media_timestamp();
+        let payload_len = payload.len() + media_timestamp.map_or(0, |_| Self::MEDIA_TIMESTAMP_SIZE);
         
-        let mut packet = Self {
-            protocol_version: Self::CURRENT_PROTOCOL_VERSION,
-            packet_type: PacketType::Heartbeat,
-            sender_id,
-            session_id,
-            compressed_frame: empty_frame,
+        buffer.clear();
+        buffer.reserve(Self::HEADER_SIZE + payload_len);
+        buffer.extend_from_slice(&Self::MAGIC);
+        buffer.push(self.protocol_version);
+        buffer.push(self.packet_type as u8);
+        buffer.extend_from_slice(&self.flags().to_be_bytes());
+        buffer.extend_from_slice(&(payload_len as u16).to_be_bytes());
+        buffer.extend_from_slice(&self.sender_id.to_be_bytes());
+        buffer.extend_from_slice(&self.session_id.to_be_bytes());
+        buffer.extend_from_slice(&self.sequence_number().to_be_bytes());
+        buffer.extend_from_slice(&(self.payload.original_sample_count() as u32).to_be_bytes());
+        buffer.extend_from_slice(&self.checksum.to_be_bytes());
+        buffer.extend_from_slice(&payload);
+        if let Some(media_timestamp) = media_timestamp {
+            buffer.extend_from_slice(&media_timestamp.to_be_bytes());
+        }
+    }
+    
+    /// Lit un paquet au format réseau courant
+    /// 
+    /// Retourne `None` si le magic, le type ou la taille du payload sont
+    /// incohérents. Le checksum n'est pas vérifié ici.
+    pub fn decode_from(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::HEADER_SIZE || !data.starts_with(&Self::MAGIC) {
+            return None;
+        }
+        
+        let be_u16 = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        let be_u32 = |offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+        let be_u64 = |offset: usize| u64::from_be_bytes(data[offset..offset + 8].try_into().unwrap());
+        
+        let payload_len = be_u16(6) as usize;
+        if data.len() != Self::HEADER_SIZE + payload_len {
+            return None;
+        }
+        
+        // Bourrage retiré : sa taille (2 derniers octets) l'inclut elle-même
+        let mut payload = &data[Self::HEADER_SIZE..];
+        if be_u16(4) & Self::FLAG_PADDED != 0 {
+            if payload.len() < 2 {
+                return None;
+            }
+            let padding_len = be_u16(data.len() - 2) as usize;
+            if !(2..=payload.len()).contains(&padding_len) {
+                return None;
+            }
+            payload = &payload[..payload.len() - padding_len];
+        }
+        
+        // Horodatage média en fin de frame
+        let mut media_timestamp = None;
+        if be_u16(4) & Self::FLAG_MEDIA_TIMESTAMP != 0 {
+            let frame_len = payload.len().checked_sub(Self::MEDIA_TIMESTAMP_SIZE)?;
+            media_timestamp = Some(u64::from_be_bytes(payload[frame_len..].try_into().unwrap()));
+            payload = &payload[..frame_len];
+        }
+        
+        let packet_type = PacketType::from_u8(data[3])?;
+        let sequence_number = be_u64(24);
+        let mut frame = CompressedFrame::new(
+            payload.to_vec(),
+            be_u32(32) as usize,
+            Instant::now(),
+            sequence_number,
+        );
+        frame.metadata = Self::metadata_from_flags(be_u16(4));
+        frame.media_timestamp = media_timestamp;
+        
+        Some(Self {
+            protocol_version: data[2],
+            packet_type,
+            sender_id: be_u64(8),
+            session_id: be_u64(16),
+            payload: PacketPayload::from_frame(packet_type, frame),
             send_timestamp: Instant::now(),
-            checksum: 0,
+            checksum: be_u32(36),
+        })
+    }
+    
+    /// Flags de l'en-tête réseau (métadonnées et horodatage de la frame)
+    pub fn flags(&self) -> u16 {
+        let metadata = match self.audio_frame().and_then(|frame| frame.metadata) {
+            Some(metadata) => {
+                let speech = if metadata.is_speech { Self::FLAG_SPEECH } else { 0 };
+                Self::FLAG_METADATA | speech | (metadata.level_dbov as u16 & Self::FLAG_LEVEL_MASK)
+            }
+            None => 0,
         };
-        
-        packet.checksum = packet.calculate_checksum();
-        packet
+        let media_timestamp = if self.media_timestamp().is_some() { Self::FLAG_MEDIA_TIMESTAMP } else { 0 };
+        metadata | media_timestamp
+    }
+    
+    /// Horodatage média de la frame audio transportée, s'il y en a un
+    pub fn media_timestamp(&self) -> Option<u64> {
+        self.audio_frame().and_then(|frame| frame.media_timestamp)
+    }
+    
+    /// Complète un datagramme encodé par `encode_into` jusqu'à `packet_size` octets
+    /// 
+    /// Positionne `FLAG_PADDED` à la place des métadonnées, qui trahiraient
+    /// l'activité vocale (`FLAG_MEDIA_TIMESTAMP` est conservé). Retourne `false` (datagramme inchangé) si le
+    /// datagramme ne laisse pas la place des deux octets de taille.
+    /// 
+    /// Les flags et la taille du payload ont la même place dans les en-têtes
+    /// v3 et v4 : un datagramme v3 se complète de la même façon.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::NetworkPacket;
+    /// 
+    /// let mut buffer = Vec::new();
+    /// NetworkPacket::new_padding(1, 2).encode_into(&mut buffer);
+    /// assert!(NetworkPacket::pad_datagram(&mut buffer, 200));
+    /// assert_eq!(buffer.len(), 200);
+    /// assert!(NetworkPacket::decode_from(&buffer).unwrap().verify_checksum());
+    /// ```
+    pub fn pad_datagram(buffer: &mut Vec<u8>, packet_size: usize) -> bool {
+        if buffer.len() < Self::PADDED_HEADER_PREFIX || buffer.len() + 2 > packet_size {
+            return false;
+        }
+        let padding_len = packet_size - buffer.len();
+        let payload_len = u16::from_be_bytes([buffer[6], buffer[7]]) as usize + padding_len;
+        let flags = (u16::from_be_bytes([buffer[4], buffer[5]]) & Self::FLAG_MEDIA_TIMESTAMP) | Self::FLAG_PADDED;
+        buffer[4..6].copy_from_slice(&flags.to_be_bytes());
+        buffer[6..8].copy_from_slice(&(payload_len as u16).to_be_bytes());
+        buffer.resize(packet_size - 2, 0);
+        buffer.extend_from_slice(&(padding_len as u16).to_be_bytes());
+        true
+    }
+    
+    /// Métadonnées de frame lues dans les flags de l'en-tête
+    fn metadata_from_flags(flags: u16) -> Option<FrameMetadata> {
+        (flags & Self::FLAG_METADATA != 0).then_some(FrameMetadata {
+            is_speech: flags & Self::FLAG_SPEECH != 0,
+            level_dbov: (flags & Self::FLAG_LEVEL_MASK) as u8,
+        })
+    }
+    
+    /// Vérifie si une version de protocole peut être lue par cette build
+    pub fn is_version_supported(version: u8) -> bool {
+        let legacy_enabled = cfg!(feature = "legacy-protocol");
+        version == Self::CURRENT_PROTOCOL_VERSION
+            || (legacy_enabled
+                && (Self::MIN_SUPPORTED_PROTOCOL_VERSION..Self::CURRENT_PROTOCOL_VERSION).contains(&version))
+    }
+    
+    /// Identifiant tel que le porte l'en-tête de la version `version`
+    /// 
+    /// Avant la v4, l'en-tête n'a que 32 bits par identifiant : seuls les
+    /// bits de poids faible sont transmis, et le pair les relit tels quels.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::NetworkPacket;
+    /// 
+    /// assert_eq!(NetworkPacket::wire_id(0x1122_3344_5566_7788, 3), 0x5566_7788);
+    /// assert_eq!(NetworkPacket::wire_id(0x1122_3344_5566_7788, 4), 0x1122_3344_5566_7788);
+    /// ```
+    pub fn wire_id(id: u64, version: u8) -> u64 {
+        if version < 4 {
+            id & u64::from(u32::MAX)
+        } else {
+            id
+        }
     }
     
     /// Calcule un checksum simple pour détecter les erreurs
@@ -112,19 +794,27 @@ impl NetworkPacket {
         let mut checksum = 0u32;
         checksum ^= self.protocol_version as u32;
         checksum ^= self.packet_type as u32;
-        checksum ^= self.sender_id;
-        checksum ^= self.session_id;
-        checksum ^= self.compressed_frame.sequence_number as u32;
-        checksum ^= self.compressed_frame.original_sample_count as u32;
-        
-        // XOR des données audio
-        for chunk in self.compressed_frame.data.chunks(4) {
-            let mut bytes = [0u8; 4];
-            for (i, &b) in chunk.iter().enumerate() {
-                bytes[i] = b;
-            }
-            checksum ^= u32::from_le_bytes(bytes);
+        // Identifiants tels que transmis (32 bits avant la v4), repliés sur 32 bits
+        for id in [self.sender_id, self.session_id] {
+            let id = Self::wire_id(id, self.protocol_version);
+            checksum ^= (id >> 32) as u32 ^ id as u32;
+        }
+        checksum ^= self.sequence_number() as u32;
+        checksum ^= self.payload.original_sample_count() as u32;
+        if let Some(media_timestamp) = self.media_timestamp() {
+            checksum ^= (media_timestamp >> 32) as u32 ^ media_timestamp as u32;
+        }
+        
+        // XOR des données du payload par mots de 4 octets, le dernier complété
+        // par des zéros (boucle sans copie octet par octet : 50 paquets/s sur ARM)
+        let payload = self.payload.to_bytes();
+        let mut words = payload.chunks_exact(4);
+        for word in &mut words {
+            checksum ^= u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
         }
+        let mut tail = [0u8; 4];
+        tail[..words.remainder().len()].copy_from_slice(words.remainder());
+        checksum ^= u32::from_le_bytes(tail);
         
         checksum
     }
@@ -137,7 +827,8 @@ impl NetworkPacket {
     /// Calcule la taille sérialisée du paquet
     pub fn estimated_size(&self) -> usize {
         // Estimation basée sur la structure (pour éviter de sérialiser)
-        32 + self.compressed_frame.data.len() // header + payload
+        Self::HEADER_SIZE + self.payload.to_bytes().len() // header + payload
+            + self.media_timestamp().map_or(0, |_| Self::MEDIA_TIMESTAMP_SIZE)
     }
     
     /// Vérifie si le paquet est trop volumineux
@@ -168,19 +859,453 @@ pub enum PacketType {
     Handshake = 3,
     /// Paquet de disconnection propre
     Disconnect = 4,
+    /// Sonde/réponse de découverte sur le réseau local
+    Discovery = 5,
+    /// Erreur protocolaire (refus de handshake, expulsion...)
+    Error = 6,
+    /// Message de contrôle en cours d'appel (renégociation du codec)
+    Control = 7,
+    /// Paquet factice du mode padding, ignoré à la réception
+    Padding = 8,
 }
 
-/// États de connexion P2P
-/// 
-/// Représente l'état de la connexion entre deux pairs.
-/// Utilisé pour la logique de retry et l'UI.
-/// 
-/// Note: Les champs `Instant` ne sont pas sérialisés car ils ne sont pas 
-/// nécessaires pour la persistance et `Instant` n'implémente pas `Default`.
-#[derive(Clone, Debug, PartialEq)]
-pub enum ConnectionState {
-    /// Aucune connexion active
-    Disconnected,
+impl PacketType {
+    /// Tous les types de paquets, dans l'ordre des codes
+    pub const ALL: [PacketType; 8] = [
+        PacketType::Audio,
+        PacketType::Heartbeat,
+        PacketType::Handshake,
+        PacketType::Disconnect,
+        PacketType::Discovery,
+        PacketType::Error,
+        PacketType::Control,
+        PacketType::Padding,
+    ];
+    
+    /// Retrouve un type à partir de son code sur le réseau
+    pub fn from_u8(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|packet_type| *packet_type as u8 == code)
+    }
+}
+
+/// Contenu transporté par un `NetworkPacket`
+/// 
+/// Sur le réseau, seul le payload sérialisé circule (voir `to_bytes`) : le
+/// format est le même qu'à l'époque où chaque paquet portait une frame audio,
+/// vide pour les heartbeats. À la réception, le variant est déduit du type de
+/// paquet (`from_frame`).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PacketPayload {
+    /// Frame audio compressée (paquets `Audio`)
+    Audio(CompressedFrame),
+    /// Message de contrôle en cours d'appel (paquets `Control`)
+    Control(ControlMessage),
+    /// Octets opaques : messages sérialisés (handshake, découverte, erreur,
+    /// rapport de réception) ou données d'une application tierce
+    Raw(Vec<u8>),
+    /// Aucun contenu (heartbeat simple, disconnect)
+    None,
+}
+
+impl PacketPayload {
+    /// Octets du payload tels qu'envoyés sur le réseau
+    pub fn to_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            PacketPayload::Audio(frame) => Cow::Borrowed(&frame.data),
+            PacketPayload::Control(message) => Cow::Owned(bincode::serialize(message).unwrap_or_default()),
+            PacketPayload::Raw(data) => Cow::Borrowed(data),
+            PacketPayload::None => Cow::Borrowed(&[]),
+        }
+    }
+    
+    /// Nombre d'échantillons de la frame audio d'origine (0 hors audio)
+    pub fn original_sample_count(&self) -> usize {
+        match self {
+            PacketPayload::Audio(frame) => frame.original_sample_count,
+            _ => 0,
+        }
+    }
+    
+    /// Reconstruit le payload d'un paquet `packet_type` à partir de la frame lue
+    /// 
+    /// Un message de contrôle illisible (ou d'une version plus récente) est
+    /// gardé en octets bruts, pour que le checksum reste vérifiable.
+    pub(crate) fn from_frame(packet_type: PacketType, frame: CompressedFrame) -> Self {
+        match packet_type {
+            PacketType::Audio => PacketPayload::Audio(frame),
+            PacketType::Control => match bincode::deserialize::<ControlMessage>(&frame.data) {
+                // Octets en trop : re-sérialisé, le message ne correspondrait plus au checksum
+                Ok(message) if bincode::serialized_size(&message).ok() == Some(frame.data.len() as u64) => {
+                    PacketPayload::Control(message)
+                }
+                _ => PacketPayload::Raw(frame.data),
+            },
+            _ if frame.data.is_empty() => PacketPayload::None,
+            _ => PacketPayload::Raw(frame.data),
+        }
+    }
+    
+    /// Frame équivalente, pour les formats qui transportent toujours une frame (v1, v2)
+    #[cfg(feature = "legacy-protocol")]
+    pub(crate) fn to_frame(&self) -> CompressedFrame {
+        match self {
+            PacketPayload::Audio(frame) => frame.clone(),
+            other => CompressedFrame::new(other.to_bytes().into_owned(), 0, Instant::now(), 0),
+        }
+    }
+}
+
+/// Champ de l'en-tête fixe d'un paquet (voir `NetworkPacket::WIRE_LAYOUT`)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WireField {
+    /// Nom du champ (utilisé comme identifiant dans le dissecteur)
+    pub name: &'static str,
+    /// Position en octets depuis le début du datagramme
+    pub offset: usize,
+    /// Taille en octets
+    pub size: usize,
+    /// Description lisible
+    pub description: &'static str,
+}
+
+/// Message transporté dans la frame d'un paquet `PacketType::Handshake`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandshakeMessage {
+    /// Demande de connexion (retransmise jusqu'à réponse)
+    Hello,
+    /// Acceptation : la session de l'expéditeur devient la session commune
+    Accept,
+    /// Reprise d'une session interrompue, avec le jeton annoncé par le pair
+    /// dans son handshake (voir `NetworkPacket::resume_token`)
+    Resume { token: u64 },
+    /// Acceptation d'une reprise : la session continue (séquences, codec)
+    ResumeAccept,
+}
+
+/// Informations sur l'expéditeur jointes au message de handshake
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeInfo {
+    /// Adresse locale de l'expéditeur, telle que vue par son système :
+    /// différente de l'adresse source reçue derrière une traduction d'adresse (NAT)
+    pub local_addr: SocketAddr,
+}
+
+/// Codes d'erreur protocolaire transmis au pair distant
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u16)]
+pub enum ProtocolErrorCode {
+    /// Authentification refusée
+    AuthFailed = 1,
+    /// Version de protocole incompatible
+    VersionMismatch = 2,
+    /// Le serveur est déjà en communication
+    ServerFull = 3,
+    /// Le pair a été expulsé de l'appel
+    Kicked = 4,
+}
+
+impl ProtocolErrorCode {
+    /// Description par défaut du code, affichable à l'utilisateur
+    pub fn default_description(&self) -> &'static str {
+        match self {
+            ProtocolErrorCode::AuthFailed => "authentification refusée",
+            ProtocolErrorCode::VersionMismatch => "version de protocole incompatible",
+            ProtocolErrorCode::ServerFull => "serveur déjà en communication",
+            ProtocolErrorCode::Kicked => "expulsé par le pair distant",
+        }
+    }
+}
+
+/// Rapport de réception transporté par les heartbeats
+/// 
+/// Décrit comment l'audio du pair est reçu localement ; côté émetteur, il
+/// renseigne sur les pertes en aval (ex: pour adapter le débit).
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerStatsReport {
+    /// Paquets audio reçus depuis le début de l'appel
+    pub packets_received: u64,
+    /// Paquets audio perdus depuis le début de l'appel
+    /// 
+    /// Transmis après le reste du heartbeat (`new_heartbeat_with_losses`),
+    /// hors de la structure sérialisée que lisent les pairs plus anciens.
+    #[serde(skip)]
+    pub packets_lost: u64,
+    /// Pourcentage de paquets audio perdus
+    pub loss_percent: f32,
+    /// Jitter observé à la réception, en millisecondes
+    pub jitter_ms: f32,
+}
+
+impl PeerStatsReport {
+    /// Pertes déduites du pourcentage, pour un pair qui n'en envoie pas le compte
+    /// 
+    /// Zéro tant que le pair n'a rien reçu : le nombre ne peut pas être retrouvé.
+    fn estimated_lost(&self) -> u64 {
+        if self.packets_received == 0 || self.loss_percent >= 100.0 {
+            return 0;
+        }
+        let loss = self.loss_percent as f64;
+        (self.packets_received as f64 * loss / (100.0 - loss)).round() as u64
+    }
+}
+
+/// Occupation du buffer de réception, jointe au rapport des heartbeats
+/// 
+/// Quand le récepteur consomme moins vite que l'émetteur ne produit (horloges
+/// audio qui dérivent, machine surchargée), son buffer anti-jitter déborde.
+/// L'émetteur qui reçoit une indication de saturation saute des frames
+/// plutôt que de laisser le récepteur éjecter les siennes indéfiniment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowControlHint {
+    /// Remplissage du buffer anti-jitter, en pourcentage de sa capacité
+    pub buffer_fill_percent: u8,
+    /// Paquets éjectés faute de place depuis le début de l'appel
+    pub overflow_drops: u64,
+}
+
+impl FlowControlHint {
+    /// Remplissage à partir duquel le buffer est considéré saturé
+    pub const HIGH_WATER_PERCENT: u8 = 80;
+    
+    /// Le récepteur demande-t-il à l'émetteur de ralentir ?
+    /// 
+    /// Vrai si le buffer est saturé ou si des paquets ont été éjectés depuis
+    /// l'indication précédente.
+    /// 
+    /// # Arguments
+    /// * `previous` - Indication précédente du même pair, s'il y en a une
+    pub fn is_congested(&self, previous: Option<&FlowControlHint>) -> bool {
+        let new_drops = self.overflow_drops > previous.map_or(0, |hint| hint.overflow_drops);
+        self.buffer_fill_percent >= Self::HIGH_WATER_PERCENT || new_drops
+    }
+}
+
+/// Codec audio utilisable sur le réseau
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodecKind {
+    /// Opus (seul codec disponible pour l'instant)
+    Opus,
+}
+
+/// Paramètres du codec négociés entre les pairs
+/// 
+/// # Example
+/// ```rust
+/// use audio::AudioConfig;
+/// use network::CodecParams;
+/// 
+/// let mut config = AudioConfig::default();
+/// CodecParams::music().apply_to(&mut config);
+/// assert_eq!(config.opus_bitrate, 64000);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodecParams {
+    /// Codec utilisé
+    pub codec: CodecKind,
+    /// Débit cible en bits par seconde
+    pub bitrate_bps: u32,
+    /// Durée d'une frame en millisecondes
+    pub frame_duration_ms: u16,
+}
+
+impl CodecParams {
+    /// Paramètres de la voix (défaut de `AudioConfig`)
+    pub fn voice() -> Self {
+        Self::from_config(&AudioConfig::default())
+    }
+    
+    /// Mode musique : débit doublé pour conserver les aigus
+    pub fn music() -> Self {
+        Self {
+            bitrate_bps: 64000,
+            ..Self::voice()
+        }
+    }
+    
+    /// Reprend les paramètres d'une configuration audio
+    pub fn from_config(config: &AudioConfig) -> Self {
+        Self {
+            codec: CodecKind::Opus,
+            bitrate_bps: config.opus_bitrate,
+            frame_duration_ms: config.frame_duration_ms,
+        }
+    }
+    
+    /// Applique les paramètres à une configuration audio (avant de recréer le codec)
+    pub fn apply_to(&self, config: &mut AudioConfig) {
+        config.opus_bitrate = self.bitrate_bps;
+        config.frame_duration_ms = self.frame_duration_ms;
+    }
+}
+
+/// Description complète d'un flux audio émis : codec, fréquence et canaux
+/// 
+/// Envoyée au pair qui n'arrive pas à décoder nos frames
+/// (`ControlMessage::DescribeStream`), pour qu'il recrée son décodeur en
+/// conséquence (voir `UdpNetworkManager::report_decode_result`).
+/// 
+/// # Example
+/// ```rust
+/// use audio::AudioConfig;
+/// use network::StreamDescription;
+/// 
+/// let mut sender = AudioConfig::default();
+/// sender.sample_rate = 16000;
+/// sender.channels = 2;
+/// let mut decoder_config = AudioConfig::default();
+/// StreamDescription::from_config(&sender).apply_to(&mut decoder_config);
+/// assert_eq!((decoder_config.sample_rate, decoder_config.channels), (16000, 2));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamDescription {
+    /// Paramètres du codec
+    pub params: CodecParams,
+    /// Fréquence d'échantillonnage en Hz
+    pub sample_rate: u32,
+    /// Nombre de canaux
+    pub channels: u16,
+}
+
+impl StreamDescription {
+    /// Reprend les paramètres d'une configuration audio (celle de l'encodeur)
+    pub fn from_config(config: &AudioConfig) -> Self {
+        Self {
+            params: CodecParams::from_config(config),
+            sample_rate: config.sample_rate,
+            channels: config.channels,
+        }
+    }
+    
+    /// Applique la description à une configuration audio (avant de recréer le décodeur)
+    pub fn apply_to(&self, config: &mut AudioConfig) {
+        self.params.apply_to(config);
+        config.sample_rate = self.sample_rate;
+        config.channels = self.channels;
+    }
+}
+
+impl Default for StreamDescription {
+    fn default() -> Self {
+        Self::from_config(&AudioConfig::default())
+    }
+}
+
+/// Discontinuité du flux audio : l'état du codec n'est plus valable
+/// 
+/// Après une reconnexion ou une longue rafale de pertes, continuer à décoder
+/// avec l'état prédictif d'Opus produit des artefacts. Le moteur d'appel
+/// récupère ces évènements avec `UdpNetworkManager::take_stream_resync`,
+/// avant de décoder la frame reçue, et réinitialise son codec à ce moment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamResync {
+    /// Nouvelle session établie (reconnexion) : encodeur et décodeur repartent de zéro
+    SessionRestart { session_id: u64 },
+    /// Saut de séquence de `from` à `to` : seul le décodeur a perdu le fil
+    SequenceJump { from: u64, to: u64 },
+}
+
+impl StreamResync {
+    /// Indique si l'encodeur doit aussi être réinitialisé
+    /// 
+    /// Une perte en réception ne concerne pas notre flux émis : réinitialiser
+    /// l'encodeur créerait au contraire une discontinuité chez le pair.
+    pub fn resets_encoder(&self) -> bool {
+        matches!(self, StreamResync::SessionRestart { .. })
+    }
+}
+
+/// Sort d'une frame confiée à `NetworkManager::send_audio`
+/// 
+/// Seules les erreurs de connexion ou de socket sont des erreurs : une frame
+/// retardée ou abandonnée est un signal de contre-pression, que l'appelant
+/// peut suivre (débit de capture, statistiques) sans couper l'appel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// La frame est partie sur le socket, après les frames en attente :
+    /// `bytes` octets envoyés par l'appel, copies redondantes comprises
+    Sent { bytes: usize },
+    /// Envoi bloqué : la frame attend le prochain appel dans la file
+    /// d'envoi, qui compte `queue_len` frames
+    Queued { queue_len: usize },
+    /// Frame abandonnée sans être numérotée : le buffer du pair est saturé
+    /// (contrôle de flux)
+    DroppedDueToBackpressure,
+}
+
+impl SendOutcome {
+    /// Indique si la frame a quitté le manager
+    pub fn is_sent(&self) -> bool {
+        matches!(self, SendOutcome::Sent { .. })
+    }
+}
+
+/// Message transporté dans la frame d'un paquet `PacketType::Control`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlMessage {
+    /// Nouveaux paramètres pour le flux audio de l'expéditeur, à partir de
+    /// sa frame numéro `switch_at` (retransmis jusqu'à acquittement)
+    Renegotiate { params: CodecParams, switch_at: u64 },
+    /// Acquittement : le décodeur sera réinitialisé à la frame `switch_at`
+    RenegotiateAck { switch_at: u64 },
+    /// L'expéditeur veut compléter ses paquets audio et envoyer des paquets
+    /// factices (mode padding), si le destinataire les comprend
+    Padding,
+    /// Acquittement : le destinataire comprend les paquets complétés
+    PaddingAck,
+    /// L'expéditeur veut recevoir l'horodatage média des frames du
+    /// destinataire (`NetworkPacket::FLAG_MEDIA_TIMESTAMP`)
+    MediaTimestamps,
+    /// Acquittement : les frames suivantes seront horodatées
+    MediaTimestampsAck,
+    /// L'expéditeur n'arrive pas à décoder les frames du destinataire et
+    /// demande la description de son flux (retransmis tant que les échecs
+    /// continuent)
+    DescribeStream,
+    /// Réponse : description du flux audio de l'expéditeur
+    StreamDescribed(StreamDescription),
+    /// Preuve d'identité de l'expéditeur, liée au défi de l'`Accept` du
+    /// destinataire (retransmise jusqu'à acquittement)
+    IdentityProof(HandshakeIdentity),
+    /// Acquittement : la preuve a été vérifiée
+    IdentityProofAck,
+}
+
+/// Message transporté dans la frame d'un paquet `PacketType::Error`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolErrorMessage {
+    /// Code d'erreur structuré
+    pub code: ProtocolErrorCode,
+    /// Description libre (détail pour l'utilisateur)
+    pub description: String,
+}
+
+/// Événement réseau à signaler à l'application (voir
+/// `UdpNetworkManager::take_network_event`)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NetworkEvent {
+    /// Paquets reçus d'une autre adresse que celle du pair et ignorés : le
+    /// pair envoie sans doute depuis un autre port, ou un NAT réécrit son
+    /// adresse. Signalé au plus une fois par intervalle de journalisation.
+    UnexpectedSource(SocketAddr),
+    
+    /// Le pair est resté muet plus de `heartbeat_timeout` pendant que
+    /// l'application ne lisait pas le réseau : la connexion est déjà passée
+    /// en erreur, sans attendre le prochain `receive_audio`.
+    PeerTimedOut(SocketAddr),
+}
+
+/// États de connexion P2P
+/// 
+/// Représente l'état de la connexion entre deux pairs.
+/// Utilisé pour la logique de retry et l'UI.
+/// 
+/// Note: Les champs `Instant` ne sont pas sérialisés car ils ne sont pas 
+/// nécessaires pour la persistance et `Instant` n'implémente pas `Default`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// Aucune connexion active
+    Disconnected,
     
     /// Tentative de connexion en cours
     Connecting { 
@@ -192,9 +1317,10 @@ pub enum ConnectionState {
     /// Connexion établie et active
     Connected { 
         peer_addr: SocketAddr,
-        session_id: u32,
+        session_id: u64,
         connected_at: Instant,
         last_heartbeat: Instant,
+        route: SessionRoute,
     },
     
     /// Erreur de connexion
@@ -226,126 +1352,790 @@ impl ConnectionState {
     }
     
     /// Récupère le session ID si connecté
-    pub fn session_id(&self) -> Option<u32> {
+    pub fn session_id(&self) -> Option<u64> {
         match self {
             ConnectionState::Connected { session_id, .. } => Some(*session_id),
             _ => None,
         }
     }
     
-    /// Description textuelle de l'état pour l'UI
-    pub fn description(&self) -> String {
-        match self {
-            ConnectionState::Disconnected => "Déconnecté".to_string(),
-            ConnectionState::Connecting { target_addr, attempt_count, .. } => {
-                format!("Connexion vers {} (tentative {})", target_addr, attempt_count)
-            }
-            ConnectionState::Connected { peer_addr, .. } => {
-                format!("Connecté à {}", peer_addr)
-            }
-            ConnectionState::Error { last_error, can_retry, .. } => {
-                if *can_retry {
-                    format!("Erreur (retry possible): {}", last_error)
-                } else {
-                    format!("Erreur fatale: {}", last_error)
-                }
+    /// Chemin de la session si connecté (direct ou via un relais)
+    pub fn route(&self) -> Option<SessionRoute> {
+        match self {
+            ConnectionState::Connected { route, .. } => Some(*route),
+            _ => None,
+        }
+    }
+    
+    /// Vérifie si la machine à états autorise le passage vers `next`
+    /// 
+    /// Une connexion s'établit toujours via `Connecting`, et une connexion
+    /// établie doit être fermée (ou tomber en erreur) avant d'en ouvrir une autre.
+    /// Seule exception : la reprise de la même session par le pair, depuis
+    /// une autre adresse (`HandshakeMessage::Resume`).
+    pub fn can_transition_to(&self, next: &ConnectionState) -> bool {
+        use ConnectionState::*;
+        if let (Connected { session_id: current, .. }, Connected { session_id: resumed, .. }) = (self, next) {
+            return current == resumed;
+        }
+        matches!(
+            (self, next),
+            (_, Disconnected | Error { .. })
+                | (Disconnected | Connecting { .. } | Error { .. }, Connecting { .. })
+                | (Connecting { .. }, Connected { .. })
+        )
+    }
+    
+    /// Description textuelle de l'état pour l'UI
+    pub fn description(&self) -> String {
+        match self {
+            ConnectionState::Disconnected => "Déconnecté".to_string(),
+            ConnectionState::Connecting { target_addr, attempt_count, .. } => {
+                format!("Connexion vers {} (tentative {})", target_addr, attempt_count)
+            }
+            ConnectionState::Connected { peer_addr, route: SessionRoute::Direct, .. } => {
+                format!("Connecté à {}", peer_addr)
+            }
+            ConnectionState::Connected { peer_addr, route: SessionRoute::Relayed { relay }, .. } => {
+                format!("Connecté à {} via le relais {}", peer_addr, relay)
+            }
+            ConnectionState::Error { last_error, can_retry, .. } => {
+                if *can_retry {
+                    format!("Erreur (retry possible): {}", last_error)
+                } else {
+                    format!("Erreur fatale: {}", last_error)
+                }
+            }
+        }
+    }
+}
+
+/// Chemin emprunté par le trafic d'une session
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionRoute {
+    /// Pair joint directement
+    Direct,
+    /// Trafic encapsulé et transmis par un relais (voir `RelayServer`)
+    Relayed {
+        /// Adresse du relais
+        relay: SocketAddr,
+    },
+}
+
+/// Configuration du système réseau
+/// 
+/// Centralise tous les paramètres configurables du système réseau.
+/// Permet d'ajuster les performances selon l'environnement (LAN vs WAN).
+/// 
+/// Sérialisable en TOML : les durées s'écrivent en clair (`"250ms"`, `"5s"`)
+/// et les champs absents prennent leur valeur par défaut.
+/// 
+/// # Example
+/// ```rust
+/// use network::NetworkConfig;
+/// use std::time::Duration;
+/// 
+/// let config = NetworkConfig::from_toml_str("heartbeat_interval = \"500ms\"\nheartbeat_timeout = \"3s\"").unwrap();
+/// assert_eq!(config.heartbeat_interval, Duration::from_millis(500));
+/// assert_eq!(config.local_port, 9001);
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct NetworkConfig {
+    /// Port d'écoute local (défaut: 9001)
+    pub local_port: u16,
+    
+    /// Taille du buffer UDP en bytes (défaut: 64KB)
+    pub socket_buffer_size: usize,
+    
+    /// Audio maximum en attente dans le buffer de réception, en ms : au-delà,
+    /// les paquets les plus anciens sont éjectés (défaut: 2000)
+    pub max_delay_ms: u32,
+    
+    /// Attente des paquets en retard, en ms : un paquet manquant n'est déclaré
+    /// perdu qu'une fois cette durée d'audio plus récent reçue, ce qui laisse à
+    /// un retardataire le temps d'être réinséré dans l'ordre (défaut: 40,
+    /// 0 = aucune attente)
+    /// 
+    /// Les deux délais sont convertis en paquets selon la durée des frames
+    /// reçues (voir `delay_frames`).
+    pub target_delay_ms: u32,
+    
+    /// Mémoire maximum du buffer de réception, en octets d'en-têtes et de
+    /// frames compressées : au-delà, les paquets les plus anciens sont
+    /// éjectés, quel que soit `max_delay_ms` (défaut: 128KB)
+    pub max_buffer_bytes: usize,
+    
+    /// Part de `max_buffer_bytes` qu'un même expéditeur (`sender_id`) peut
+    /// occuper : au-delà, ses paquets les plus anciens sont éjectés
+    /// (défaut: 64KB)
+    pub max_buffer_bytes_per_sender: usize,
+    
+    /// Timeout pour les tentatives de connexion (défaut: 5s)
+    #[serde(with = "humantime_serde")]
+    pub connection_timeout: Duration,
+    
+    /// Délai initial avant retransmission du handshake, doublé à chaque
+    /// tentative (défaut: 250ms)
+    #[serde(with = "humantime_serde")]
+    pub handshake_retry_interval: Duration,
+    
+    /// Intervalle entre les heartbeats (défaut: 1s)
+    #[serde(with = "humantime_serde")]
+    pub heartbeat_interval: Duration,
+    
+    /// Durée max sans heartbeat avant disconnection (défaut: 5s)
+    #[serde(with = "humantime_serde")]
+    pub heartbeat_timeout: Duration,
+    
+    /// Age maximum d'un paquet avant rejet (défaut: 100ms)
+    #[serde(with = "humantime_serde")]
+    pub max_packet_age: Duration,
+    
+    /// Nombre maximum de tentatives de connexion, et de reconnexion
+    /// après une perte du pair (défaut: 5)
+    pub max_retry_attempts: u32,
+    
+    /// Délai entre les tentatives de connexion, doublé à chaque échec dans
+    /// la limite de `connection_timeout` (défaut: 2s)
+    #[serde(with = "humantime_serde")]
+    pub retry_delay: Duration,
+    
+    /// Demande une redirection de port au routeur en mode écoute
+    /// (UPnP / NAT-PMP, nécessite la feature `upnp`, défaut: false)
+    pub port_mapping: bool,
+    
+    /// Proxy SOCKS5 à traverser pour le trafic UDP (défaut: aucun)
+    pub proxy: Option<ProxyConfig>,
+    
+    /// Redondance à l'émission de l'audio (défaut: aucune)
+    pub redundancy: RedundancyMode,
+    
+    /// Débit montant maximum vers le pair, en bits/sec : le pacer n'envoie
+    /// pas au-delà, même si le contrôle de congestion l'autorise (défaut:
+    /// aucun plafond)
+    pub max_bandwidth_bps: Option<u32>,
+    
+    /// Interface réseau par laquelle passer (ex: `"eth0"`, `"wlan0"`), pour
+    /// forcer le LAN plutôt qu'un VPN sur une machine multi-interfaces
+    /// (défaut: aucune, exclusif avec `bind_ip`)
+    /// 
+    /// Le socket est lié à la première adresse IPv4 de l'interface (IPv6 à
+    /// défaut) : les broadcasts de découverte LAN ne sont alors plus reçus.
+    pub bind_interface: Option<String>,
+    
+    /// Adresse IP locale à laquelle lier le socket (défaut: toutes)
+    pub bind_ip: Option<IpAddr>,
+    
+    /// Chronomètre les étapes du chemin de réception (voir
+    /// `NetworkTransport::timing_stats`, défaut: false)
+    pub timing_stats: bool,
+    
+    /// Période d'échantillonnage de l'historique de qualité (défaut: 1s)
+    #[serde(with = "humantime_serde")]
+    pub stats_sample_period: Duration,
+    
+    /// Affiche les paquets ignorés (corrompus, trop vieux), regroupés par
+    /// `ErrorLog` ; ils restent comptés dans les stats (défaut: true)
+    pub log_packet_errors: bool,
+    
+    /// Annonce périodique de notre présence sur le LAN (défaut: aucune)
+    pub presence: Option<PresenceConfig>,
+    
+    /// Fait passer les heartbeats devant l'audio quand le buffer d'envoi du
+    /// socket est plein, au lieu de les perdre (défaut: false)
+    pub control_priority: bool,
+    
+    /// Durée de la mesure de gigue avant chaque appel sortant : la fenêtre
+    /// `target_delay_ms` est alors calé sur la gigue mesurée plutôt que
+    /// fixé d'avance (défaut: aucune mesure)
+    #[serde(with = "humantime_serde")]
+    pub jitter_probe: Option<Duration>,
+    
+    /// Relais candidats, essayés quand la connexion directe échoue : le plus
+    /// proche (RTT) est retenu (défaut: aucun)
+    pub relays: Vec<SocketAddr>,
+    
+    /// Délai pendant lequel une session interrompue (timeout, changement de
+    /// réseau) peut être reprise par un seul paquet `Resume`, compté depuis
+    /// le dernier signe de vie du pair ; 0 désactive la reprise (défaut: 30s)
+    #[serde(with = "humantime_serde")]
+    pub resume_grace: Duration,
+    
+    /// Mode padding : paquets audio de taille fixe et paquets factices
+    /// pendant les silences, contre l'analyse de trafic (défaut: aucun)
+    pub padding: Option<PaddingConfig>,
+    
+    /// Dégradation progressive quand la qualité reste mauvaise (voir
+    /// `DegradationLadder`, défaut: aucune)
+    pub degradation: Option<DegradationConfig>,
+    
+    /// Fichier de la clé privée de l'instance (voir `Identity`), créé au
+    /// premier lancement : les pairs nous reconnaissent d'une session à
+    /// l'autre (défaut: aucun, identité éphémère propre à chaque manager)
+    pub identity_file: Option<PathBuf>,
+    
+    /// Fichier des décisions de confiance dans l'identité des pairs (voir
+    /// `KnownPeers`), relu au démarrage (défaut: aucun, décisions gardées en
+    /// mémoire)
+    pub known_peers_file: Option<PathBuf>,
+    
+    /// Demande au pair l'horodatage média de ses frames (voir
+    /// `CompressedFrame::media_timestamp`), pour synchroniser un rendu
+    /// externe sur son audio ; 8 octets de plus par paquet (défaut: false)
+    pub media_timestamps: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            local_port: 9001,
+            socket_buffer_size: 65536, // 64KB
+            max_delay_ms: 2000,        // 2s d'audio
+            target_delay_ms: 40,       // 40ms de réordonnancement toléré
+            max_buffer_bytes: 128 * 1024, // ~90 paquets de taille maximale
+            max_buffer_bytes_per_sender: 64 * 1024, // Moitié du budget
+            connection_timeout: Duration::from_secs(5),
+            handshake_retry_interval: Duration::from_millis(250),
+            heartbeat_interval: Duration::from_secs(1),
+            heartbeat_timeout: Duration::from_secs(5),
+            max_packet_age: Duration::from_millis(100),
+            max_retry_attempts: 5,
+            retry_delay: Duration::from_secs(2),
+            port_mapping: false,
+            proxy: None,
+            redundancy: RedundancyMode::None,
+            max_bandwidth_bps: None,
+            bind_interface: None,
+            bind_ip: None,
+            timing_stats: false,
+            stats_sample_period: Duration::from_secs(1),
+            log_packet_errors: true,
+            presence: None,
+            control_priority: false,
+            jitter_probe: None,
+            relays: Vec::new(),
+            resume_grace: Duration::from_secs(30),
+            padding: None,
+            degradation: None,
+            identity_file: None,
+            known_peers_file: None,
+            media_timestamps: false,
+        }
+    }
+}
+
+/// Délais du buffer de réception en frames (voir `NetworkConfig::delay_frames`)
+pub(crate) fn delay_frames(target_delay_ms: u32, max_delay_ms: u32, frame_duration: Duration) -> (u64, usize) {
+    let frame_ms = frame_duration.as_millis().max(1) as u64;
+    let max = (max_delay_ms as u64 / frame_ms).max(1);
+    let target = ((target_delay_ms as u64 + frame_ms / 2) / frame_ms).min(max - 1);
+    (target, max as usize)
+}
+
+impl NetworkConfig {
+    /// Configuration optimisée pour LAN (latence faible)
+    pub fn lan_optimized() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_millis(500),
+            heartbeat_timeout: Duration::from_secs(2),
+            max_packet_age: Duration::from_millis(50),
+            connection_timeout: Duration::from_secs(2),
+            target_delay_ms: 20,
+            ..Default::default()
+        }
+    }
+    
+    /// Configuration pour WAN (plus tolérante)
+    pub fn wan_optimized() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(2),
+            heartbeat_timeout: Duration::from_secs(10),
+            max_packet_age: Duration::from_millis(200),
+            connection_timeout: Duration::from_secs(10),
+            target_delay_ms: 80,
+            ..Default::default()
+        }
+    }
+    
+    /// Configuration économe en CPU (ARM, Raspberry Pi Zero 2 W)
+    /// 
+    /// À combiner avec `AudioConfig::low_power` (frames de 40ms) : moins de
+    /// heartbeats, historique de qualité échantillonné toutes les 5s et
+    /// aucun affichage par paquet.
+    pub fn low_power() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(2),
+            heartbeat_timeout: Duration::from_secs(10),
+            target_delay_ms: 40, // Une frame de 40ms
+            stats_sample_period: Duration::from_secs(5),
+            log_packet_errors: false,
+            ..Default::default()
+        }
+    }
+    
+    /// Configuration pour tests (paramètres accélérés)
+    pub fn test_config() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_millis(100),
+            heartbeat_timeout: Duration::from_millis(500),
+            max_packet_age: Duration::from_millis(50),
+            connection_timeout: Duration::from_millis(1000),
+            handshake_retry_interval: Duration::from_millis(50),
+            max_retry_attempts: 2,
+            retry_delay: Duration::from_millis(100),
+            ..Default::default()
+        }
+    }
+    
+    /// Convertit les délais du buffer de réception en frames de `frame_duration`
+    /// 
+    /// Retourne `(target, max)` : l'attente des retardataires, arrondie à la
+    /// frame la plus proche et bornée à `max - 1`, et la capacité du buffer,
+    /// arrondie à la frame inférieure (au moins une). Des frames de 10, 20 ou
+    /// 60ms donnent ainsi, au plus près, le même délai en ms.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::NetworkConfig;
+    /// use std::time::Duration;
+    /// 
+    /// let config = NetworkConfig::default();
+    /// assert_eq!(config.delay_frames(Duration::from_millis(20)), (2, 100));
+    /// assert_eq!(config.delay_frames(Duration::from_millis(10)), (4, 200));
+    /// assert_eq!(config.delay_frames(Duration::from_millis(60)), (1, 33));
+    /// ```
+    pub fn delay_frames(&self, frame_duration: Duration) -> (u64, usize) {
+        delay_frames(self.target_delay_ms, self.max_delay_ms, frame_duration)
+    }
+    
+    /// Vérifie que la configuration est cohérente
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` nommant le premier champ invalide
+    pub fn validate(&self) -> NetworkResult<()> {
+        let invalid = |field: &str, reason: String| {
+            Err(NetworkError::ConfigError(format!("{} invalide : {}", field, reason)))
+        };
+        let format = crate::utils::format_duration;
+        
+        if self.max_delay_ms == 0 {
+            return invalid("max_delay_ms", "doit être supérieur à 0".to_string());
+        }
+        if self.target_delay_ms >= self.max_delay_ms {
+            return invalid("target_delay_ms", format!(
+                "{}ms (doit être inférieur à max_delay_ms = {}ms)",
+                self.target_delay_ms, self.max_delay_ms));
+        }
+        if self.max_buffer_bytes_per_sender < NetworkPacket::MAX_PACKET_SIZE {
+            return invalid("max_buffer_bytes_per_sender", format!(
+                "{} octets (au moins un paquet de {} octets)",
+                self.max_buffer_bytes_per_sender, NetworkPacket::MAX_PACKET_SIZE));
+        }
+        if self.max_buffer_bytes < self.max_buffer_bytes_per_sender {
+            return invalid("max_buffer_bytes", format!(
+                "{} octets (doit être au moins max_buffer_bytes_per_sender = {})",
+                self.max_buffer_bytes, self.max_buffer_bytes_per_sender));
+        }
+        for (field, duration) in [
+            ("connection_timeout", self.connection_timeout),
+            ("handshake_retry_interval", self.handshake_retry_interval),
+            ("heartbeat_interval", self.heartbeat_interval),
+            ("max_packet_age", self.max_packet_age),
+        ] {
+            if duration.is_zero() {
+                return invalid(field, "doit être supérieur à 0".to_string());
+            }
+        }
+        if self.handshake_retry_interval > self.connection_timeout {
+            return invalid("handshake_retry_interval", format!(
+                "{} (doit être inférieur à connection_timeout = {})",
+                format(self.handshake_retry_interval), format(self.connection_timeout)));
+        }
+        if self.bind_interface.is_some() && self.bind_ip.is_some() {
+            return invalid("bind_ip", "exclusif avec bind_interface".to_string());
+        }
+        if self.heartbeat_timeout <= self.heartbeat_interval {
+            return invalid("heartbeat_timeout", format!(
+                "{} (doit dépasser heartbeat_interval = {})",
+                format(self.heartbeat_timeout), format(self.heartbeat_interval)));
+        }
+        if self.max_bandwidth_bps == Some(0) {
+            return invalid("max_bandwidth_bps", "doit être supérieur à 0".to_string());
+        }
+        if let Some(presence) = &self.presence {
+            if presence.display_name.trim().is_empty()
+                || presence.display_name.len() > PresenceConfig::MAX_DISPLAY_NAME_LEN
+            {
+                return invalid("presence.display_name", format!(
+                    "{:?} (1 à {} octets)", presence.display_name, PresenceConfig::MAX_DISPLAY_NAME_LEN));
+            }
+            if presence.interval.is_zero() {
+                return invalid("presence.interval", "doit être supérieur à 0".to_string());
+            }
+        }
+        if let Some(padding) = &self.padding
+            && !(PaddingConfig::MIN_PACKET_SIZE..=NetworkPacket::MAX_PACKET_SIZE).contains(&padding.packet_size)
+        {
+            return invalid("padding.packet_size", format!(
+                "{} octets (doit être entre {} et {})",
+                padding.packet_size, PaddingConfig::MIN_PACKET_SIZE, NetworkPacket::MAX_PACKET_SIZE));
+        }
+        if let Some(degradation) = &self.degradation
+            && (degradation.degrade_after == 0 || degradation.recover_after == 0)
+        {
+            return invalid("degradation", "degrade_after et recover_after doivent être supérieurs à 0".to_string());
+        }
+        if self.jitter_probe.is_some_and(|probe| probe.is_zero()) {
+            return invalid("jitter_probe", "doit être supérieur à 0".to_string());
+        }
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
+        }
+        Ok(())
+    }
+    
+    /// Adresse locale à laquelle lier le socket UDP
+    /// 
+    /// `bind_ip` si défini, sinon l'adresse de `bind_interface`, sinon
+    /// toutes les interfaces (`0.0.0.0`).
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` si l'interface n'existe pas ou n'a pas d'adresse
+    pub fn bind_addr(&self, port: u16) -> NetworkResult<SocketAddr> {
+        if let Some(ip) = self.bind_ip {
+            return Ok(SocketAddr::new(ip, port));
+        }
+        let Some(name) = &self.bind_interface else {
+            return Ok(SocketAddr::from(([0, 0, 0, 0], port)));
+        };
+        
+        let interface = crate::utils::list_interfaces()?
+            .into_iter()
+            .find(|interface| &interface.name == name)
+            .ok_or_else(|| NetworkError::ConfigError(format!("bind_interface invalide : interface {} introuvable", name)))?;
+        let ip = interface.ips.iter().find(|ip| ip.is_ipv4())
+            .or(interface.ips.first())
+            .ok_or_else(|| NetworkError::ConfigError(format!("bind_interface invalide : {} n'a pas d'adresse IP", name)))?;
+        Ok(SocketAddr::new(*ip, port))
+    }
+    
+    /// Lit une configuration TOML puis la valide
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` indiquant le champ fautif (valeur mal
+    ///   formée comme `"5x"`, ou incohérente)
+    pub fn from_toml_str(content: &str) -> NetworkResult<Self> {
+        let config: Self = serde_path_to_error::deserialize(toml::Deserializer::new(content))
+            .map_err(|e| NetworkError::ConfigError(format!("{} invalide : {}", e.path(), e.inner().message())))?;
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Charge une configuration depuis un fichier TOML
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` si le fichier est illisible ou invalide
+    pub fn load(path: impl AsRef<Path>) -> NetworkResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| NetworkError::ConfigError(format!("Lecture de {} impossible: {}", path.display(), e)))?;
+        Self::from_toml_str(&content)
+            .map_err(|e| NetworkError::ConfigError(format!("{} ({})", e, path.display())))
+    }
+    
+    /// Enregistre la configuration dans un fichier TOML
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` si le fichier ne peut pas être écrit
+    pub fn save(&self, path: impl AsRef<Path>) -> NetworkResult<()> {
+        let path = path.as_ref();
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| NetworkError::ConfigError(format!("Sérialisation de la configuration impossible: {}", e)))?;
+        std::fs::write(path, content)
+            .map_err(|e| NetworkError::ConfigError(format!("Écriture de {} impossible: {}", path.display(), e)))
+    }
+}
+
+/// Redondance appliquée aux paquets audio envoyés
+/// 
+/// Pour les liens très instables (Wi-Fi avec pertes en rafale > 10%), où la
+/// FEC du codec ne suffit pas. Le récepteur écarte les copies grâce au
+/// numéro de séquence.
+/// 
+/// # Example
+/// ```rust
+/// use network::{NetworkConfig, RedundancyMode};
+/// 
+/// let mut config = NetworkConfig::wan_optimized();
+/// config.redundancy = RedundancyMode::Duplicate(2);
+/// assert_eq!(config.redundancy.copies(), 2);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedundancyMode {
+    /// Chaque paquet est envoyé une seule fois
+    #[default]
+    None,
+    /// Chaque paquet est envoyé n fois, espacées de `DUPLICATE_SPACING`
+    Duplicate(u8),
+}
+
+impl RedundancyMode {
+    /// Espacement entre deux copies d'un même paquet
+    /// 
+    /// Assez court pour rester dans la durée d'une frame, assez long pour
+    /// que les copies ne tombent pas dans la même rafale de pertes.
+    pub const DUPLICATE_SPACING: Duration = Duration::from_millis(3);
+    
+    /// Nombre total d'envois par paquet audio (au moins 1)
+    pub fn copies(&self) -> u8 {
+        match self {
+            RedundancyMode::None => 1,
+            RedundancyMode::Duplicate(n) => (*n).max(1),
+        }
+    }
+}
+
+/// Configuration d'un proxy SOCKS5 (UDP ASSOCIATE)
+/// 
+/// # Example
+/// ```rust
+/// use network::{NetworkConfig, ProxyConfig};
+/// 
+/// let mut config = NetworkConfig::default();
+/// config.proxy = Some(
+///     ProxyConfig::socks5("10.0.0.1:1080".parse().unwrap())
+///         .with_credentials("alice", "secret")
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProxyConfig {
+    /// Adresse TCP du serveur SOCKS5
+    pub addr: SocketAddr,
+    
+    /// Identifiants optionnels (authentification username/password, RFC 1929)
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Longueur maximale de l'identifiant et du mot de passe, en octets
+    /// (longueur sur un octet dans la sous-négociation RFC 1929)
+    pub const MAX_CREDENTIAL_LEN: usize = 255;
+    
+    /// Proxy SOCKS5 sans authentification
+    pub fn socks5(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            username: None,
+            password: None,
+        }
+    }
+    
+    /// Ajoute des identifiants username/password
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.username = Some(username.to_string());
+        self.password = Some(password.to_string());
+        self
+    }
+    
+    /// Vérifie que les identifiants peuvent être transmis au proxy
+    /// 
+    /// # Erreurs
+    /// - `NetworkError::ConfigError` : identifiant ou mot de passe de plus de
+    ///   `MAX_CREDENTIAL_LEN` octets
+    pub fn validate(&self) -> NetworkResult<()> {
+        for (field, value) in [("proxy.username", &self.username), ("proxy.password", &self.password)] {
+            if let Some(value) = value
+                && value.len() > Self::MAX_CREDENTIAL_LEN
+            {
+                return Err(NetworkError::ConfigError(format!(
+                    "{} invalide : {} octets (au plus {})", field, value.len(), Self::MAX_CREDENTIAL_LEN)));
             }
         }
+        Ok(())
     }
 }
 
-/// Configuration du système réseau
+/// Mode padding : rendre le trafic indépendant de l'activité vocale
 /// 
-/// Centralise tous les paramètres configurables du système réseau.
-/// Permet d'ajuster les performances selon l'environnement (LAN vs WAN).
-#[derive(Clone, Debug)]
-pub struct NetworkConfig {
-    /// Port d'écoute local (défaut: 9001)
-    pub local_port: u16,
-    
-    /// Taille du buffer UDP en bytes (défaut: 64KB)
-    pub socket_buffer_size: usize,
-    
-    /// Taille du buffer de réception en paquets (défaut: 100)
-    pub receive_buffer_size: usize,
-    
-    /// Timeout pour les tentatives de connexion (défaut: 5s)
-    pub connection_timeout: Duration,
+/// Un observateur du réseau déduit qui parle, et quand, de la taille et du
+/// rythme des paquets audio, ainsi que des métadonnées de frame de l'en-tête.
+/// En mode padding, une fois l'offre acquittée par le pair
+/// (`ControlMessage::Padding`), chaque paquet audio est complété jusqu'à
+/// `packet_size` octets, sans métadonnées (`NetworkPacket::FLAG_PADDED`), et
+/// `UdpNetworkManager::send_padding` comble les créneaux sans audio par des
+/// paquets factices de même taille : le débit est constant.
+/// 
+/// Le contenu reste lisible tant que le trafic n'est pas chiffré : le mode
+/// ne masque que la taille, le rythme et les métadonnées.
+/// 
+/// # Example
+/// ```rust
+/// use network::{NetworkConfig, PaddingConfig};
+/// 
+/// let mut config = NetworkConfig::default();
+/// config.padding = Some(PaddingConfig::default());
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PaddingConfig {
+    /// Taille de chaque datagramme audio ou factice, en-tête compris
+    /// (défaut: 256)
+    /// 
+    /// Doit couvrir la plus grande frame du codec : une frame plus grande
+    /// part sans bourrage, et trahit sa taille.
+    #[serde(default = "PaddingConfig::default_packet_size")]
+    pub packet_size: usize,
+}
+
+impl PaddingConfig {
+    /// Plus petite taille de paquet : en-tête et taille du bourrage
+    pub const MIN_PACKET_SIZE: usize = NetworkPacket::HEADER_SIZE + 2;
     
-    /// Intervalle entre les heartbeats (défaut: 1s)
-    pub heartbeat_interval: Duration,
+    fn default_packet_size() -> usize {
+        256
+    }
+}
+
+impl Default for PaddingConfig {
+    /// Paquets de 256 octets : frames Opus jusqu'à ~89 kbps en 20ms
+    fn default() -> Self {
+        Self { packet_size: Self::default_packet_size() }
+    }
+}
+
+/// Seuils de l'échelle de dégradation (`DegradationLadder`)
+/// 
+/// Un échantillon de qualité (`NetworkConfig::stats_sample_period`) est
+/// mauvais dès qu'un seuil est dépassé, bon quand toutes les mesures sont
+/// sous la moitié des seuils.
+/// 
+/// # Example
+/// ```rust
+/// use network::{DegradationConfig, NetworkConfig};
+/// 
+/// let mut degradation = DegradationConfig::default();
+/// degradation.max_loss_percent = 3.0;
+/// let mut config = NetworkConfig::default();
+/// config.degradation = Some(degradation);
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct DegradationConfig {
+    /// Perte en réception au-delà de laquelle la qualité est mauvaise,
+    /// en pourcentage (défaut: 5)
+    pub max_loss_percent: f32,
     
-    /// Durée max sans heartbeat avant disconnection (défaut: 5s)
-    pub heartbeat_timeout: Duration,
+    /// RTT au-delà duquel la qualité est mauvaise, en ms (défaut: 250)
+    pub max_rtt_ms: f32,
     
-    /// Age maximum d'un paquet avant rejet (défaut: 100ms)
-    pub max_packet_age: Duration,
+    /// Gigue au-delà de laquelle la qualité est mauvaise, en ms (défaut: 40)
+    pub max_jitter_ms: f32,
     
-    /// Nombre maximum de tentatives de reconnexion (défaut: 5)
-    pub max_retry_attempts: u32,
+    /// Échantillons mauvais consécutifs avant de descendre d'un échelon
+    /// (défaut: 3)
+    pub degrade_after: u32,
     
-    /// Délai entre les tentatives de reconnexion (défaut: 2s)
-    pub retry_delay: Duration,
+    /// Échantillons bons consécutifs avant de remonter d'un échelon
+    /// (défaut: 10)
+    pub recover_after: u32,
 }
 
-impl Default for NetworkConfig {
+impl Default for DegradationConfig {
     fn default() -> Self {
         Self {
-            local_port: 9001,
-            socket_buffer_size: 65536, // 64KB
-            receive_buffer_size: 100,  // ~100 frames = ~2s d'audio
-            connection_timeout: Duration::from_secs(5),
-            heartbeat_interval: Duration::from_secs(1),
-            heartbeat_timeout: Duration::from_secs(5),
-            max_packet_age: Duration::from_millis(100),
-            max_retry_attempts: 5,
-            retry_delay: Duration::from_secs(2),
+            max_loss_percent: 5.0,
+            max_rtt_ms: 250.0,
+            max_jitter_ms: 40.0,
+            degrade_after: 3,
+            recover_after: 10,
         }
     }
 }
 
-impl NetworkConfig {
-    /// Configuration optimisée pour LAN (latence faible)
-    pub fn lan_optimized() -> Self {
+/// Annonces de présence sur le réseau local (« qui est en ligne »)
+/// 
+/// Tant que le transport est bindé, une annonce portant le nom affiché et
+/// les capacités de l'instance part en broadcast vers `port` toutes les
+/// `interval`. Les annonces se collectent avec `utils::collect_presence`.
+/// 
+/// # Example
+/// ```rust
+/// use network::{NetworkConfig, PresenceConfig};
+/// 
+/// let mut config = NetworkConfig::default();
+/// config.presence = Some(PresenceConfig::new("Salon"));
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PresenceConfig {
+    /// Nom affiché aux autres instances
+    pub display_name: String,
+    
+    /// Intervalle entre deux annonces (défaut: 5s)
+    #[serde(with = "humantime_serde", default = "PresenceConfig::default_interval")]
+    pub interval: Duration,
+    
+    /// Port de destination des annonces (défaut: `PRESENCE_PORT`)
+    #[serde(default = "PresenceConfig::default_port")]
+    pub port: u16,
+}
+
+impl PresenceConfig {
+    /// Longueur maximale du nom affiché, en octets
+    pub const MAX_DISPLAY_NAME_LEN: usize = 64;
+    
+    /// Annonces sous le nom `display_name`, avec l'intervalle et le port par défaut
+    pub fn new(display_name: &str) -> Self {
         Self {
-            heartbeat_interval: Duration::from_millis(500),
-            heartbeat_timeout: Duration::from_secs(2),
-            max_packet_age: Duration::from_millis(50),
-            connection_timeout: Duration::from_secs(2),
-            ..Default::default()
+            display_name: display_name.to_string(),
+            interval: Self::default_interval(),
+            port: Self::default_port(),
         }
     }
     
-    /// Configuration pour WAN (plus tolérante)
-    pub fn wan_optimized() -> Self {
-        Self {
-            heartbeat_interval: Duration::from_secs(2),
-            heartbeat_timeout: Duration::from_secs(10),
-            max_packet_age: Duration::from_millis(200),
-            connection_timeout: Duration::from_secs(10),
-            ..Default::default()
-        }
+    fn default_interval() -> Duration {
+        Duration::from_secs(5)
     }
     
-    /// Configuration pour tests (paramètres accélérés)
-    pub fn test_config() -> Self {
-        Self {
-            heartbeat_interval: Duration::from_millis(100),
-            heartbeat_timeout: Duration::from_millis(500),
-            max_packet_age: Duration::from_millis(50),
-            connection_timeout: Duration::from_millis(1000),
-            max_retry_attempts: 2,
-            retry_delay: Duration::from_millis(100),
-            ..Default::default()
-        }
+    fn default_port() -> u16 {
+        crate::presence::PRESENCE_PORT
     }
 }
 
+/// Interface réseau de la machine, telle que proposée à l'utilisateur
+/// 
+/// Obtenue par `utils::list_interfaces()` ; son `name` peut être repris dans
+/// `NetworkConfig::bind_interface`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    /// Nom système (ex: `eth0`, `wlan0`, `tun0`)
+    pub name: String,
+    
+    /// Adresses IP de l'interface (IPv4 en premier)
+    pub ips: Vec<IpAddr>,
+    
+    /// Interface de bouclage (`lo`)
+    pub is_loopback: bool,
+    
+    /// L'interface a une adresse de broadcast IPv4 (découverte LAN possible)
+    pub supports_broadcast: bool,
+}
+
 /// Statistiques réseau pour monitoring
 /// 
 /// Collecte des métriques sur les performances réseau.
 /// Intégrable avec les AudioStats pour un monitoring global.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct NetworkStats {
     /// Nombre de paquets envoyés
     pub packets_sent: u64,
@@ -362,6 +2152,35 @@ pub struct NetworkStats {
     /// Nombre de paquets rejetés (trop vieux)
     pub packets_rejected: u64,
     
+    /// Paquets ignorés car envoyés depuis une autre adresse que celle du
+    /// pair (mauvais port, NAT qui réécrit l'adresse...)
+    #[serde(default)]
+    pub packets_from_unknown_peer: u64,
+    
+    /// Nombre de paquets arrivés après que leur créneau de lecture a été
+    /// sauté (comptés à part, et non comme perdus)
+    #[serde(default)]
+    pub packets_late: u64,
+    
+    /// Nombre de frames audio non envoyées à la demande du pair, dont le
+    /// buffer de réception débordait (voir `FlowControlHint`)
+    #[serde(default)]
+    pub frames_skipped: u64,
+    
+    /// Nombre d'erreurs répétées non affichées (journalisation limitée)
+    #[serde(default)]
+    pub errors_suppressed: u64,
+    
+    /// Paquets retardés par le plafond de débit
+    /// (`NetworkConfig::max_bandwidth_bps`)
+    #[serde(default)]
+    pub packets_throttled: u64,
+    
+    /// Nombre de sessions reprises sans nouveau handshake (voir
+    /// `NetworkConfig::resume_grace`)
+    #[serde(default)]
+    pub sessions_resumed: u32,
+    
     /// RTT moyen en millisecondes
     pub avg_rtt_ms: f32,
     
@@ -371,12 +2190,60 @@ pub struct NetworkStats {
     /// Bande passante utilisée (bytes/sec)
     pub bandwidth_bytes_per_sec: f32,
     
-    /// Nombre de reconnexions
+    /// Octets émis et reçus sur le réseau (en-tête, payload et
+    /// encapsulation éventuelle : tout le datagramme)
+    #[serde(default)]
+    pub bytes_sent: u64,
+    #[serde(default)]
+    pub bytes_received: u64,
+    
+    /// Débits émis et reçus en kbit/s, moyennés sur les dernières secondes
+    /// (`NetworkStats::BITRATE_WINDOW`)
+    #[serde(default)]
+    pub send_bitrate_kbps: f32,
+    #[serde(default)]
+    pub recv_bitrate_kbps: f32,
+    
+    /// Fenêtres glissantes des débits (tenues par le transport)
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    #[serde(skip)]
+    pub(crate) send_window: RateWindow,
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    #[serde(skip)]
+    pub(crate) recv_window: RateWindow,
+    
+    /// Nombre de reconnexions au pair de la session précédente
     pub reconnection_count: u32,
     
-    /// Durée de la connexion courante
+    /// Temps passé connecté, cumulé sur toutes les sessions (voir
+    /// `total_uptime`)
     pub connection_uptime_ms: u64,
     
+    /// Durée de la session connectée en cours, 0 si déconnecté (voir
+    /// `current_session_uptime`)
+    #[serde(default)]
+    pub session_uptime_ms: u64,
+    
+    /// État du contrôle de congestion (débit de pacing, phase, RTT)
+    pub congestion: CongestionState,
+    
+    /// Heartbeats du pair manqués d'affilée (voir `Liveness::missed`)
+    #[serde(default)]
+    pub missed_heartbeats: u32,
+    
+    /// Envoi de notre dernier heartbeat
+    #[serde(skip)]
+    pub last_heartbeat_sent: Option<Instant>,
+    
+    /// Réception du dernier heartbeat du pair
+    #[serde(skip)]
+    pub last_heartbeat_received: Option<Instant>,
+    
+    /// RTT relevé à chaque heartbeat reçu, en millisecondes, du plus ancien
+    /// au plus récent (au plus `HEARTBEAT_RTT_HISTORY` valeurs)
+    #[serde(default)]
+    pub heartbeat_rtt_ms: Vec<f32>,
+    
     /// Dernière mise à jour des stats
     /// Skip la sérialisation car Instant ne peut pas être sérialisé de manière portable
     /// Utilise une valeur par défaut lors de la désérialisation
@@ -394,27 +2261,142 @@ impl Default for NetworkStats {
             packets_lost: 0,
             packets_corrupted: 0,
             packets_rejected: 0,
+            packets_from_unknown_peer: 0,
+            packets_late: 0,
+            frames_skipped: 0,
+            errors_suppressed: 0,
+            packets_throttled: 0,
+            sessions_resumed: 0,
             avg_rtt_ms: 0.0,
             avg_jitter_ms: 0.0,
             bandwidth_bytes_per_sec: 0.0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            send_bitrate_kbps: 0.0,
+            recv_bitrate_kbps: 0.0,
+            #[cfg(any(feature = "udp", feature = "simulator"))]
+            send_window: RateWindow::default(),
+            #[cfg(any(feature = "udp", feature = "simulator"))]
+            recv_window: RateWindow::default(),
             reconnection_count: 0,
             connection_uptime_ms: 0,
+            session_uptime_ms: 0,
+            congestion: CongestionState::default(),
+            missed_heartbeats: 0,
+            last_heartbeat_sent: None,
+            last_heartbeat_received: None,
+            heartbeat_rtt_ms: Vec::new(),
             last_updated: Instant::now(),
         }
     }
 }
 
 impl NetworkStats {
+    /// Nombre de mesures gardées dans `heartbeat_rtt_ms`
+    pub const HEARTBEAT_RTT_HISTORY: usize = 32;
+    
+    /// Fenêtre sur laquelle `send_bitrate_kbps` et `recv_bitrate_kbps`
+    /// sont moyennés
+    pub const BITRATE_WINDOW: Duration = Duration::from_secs(2);
+    
     /// Crée de nouvelles statistiques
     pub fn new() -> Self {
         Self::default()
     }
     
+    /// Enregistre la réception d'un heartbeat et le RTT courant
+    pub fn record_heartbeat(&mut self, at: Instant, rtt_ms: f32) {
+        self.last_heartbeat_received = Some(at);
+        self.missed_heartbeats = 0;
+        if self.heartbeat_rtt_ms.len() == Self::HEARTBEAT_RTT_HISTORY {
+            self.heartbeat_rtt_ms.remove(0);
+        }
+        self.heartbeat_rtt_ms.push(rtt_ms);
+    }
+    
     /// Remet les statistiques à zéro
     pub fn reset(&mut self) {
         *self = Self::new();
     }
     
+    /// Compte un datagramme émis de `bytes` octets
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    pub(crate) fn record_bytes_sent(&mut self, bytes: usize, now: Instant) {
+        self.bytes_sent += bytes as u64;
+        self.send_window.record(now, bytes);
+    }
+    
+    /// Compte un datagramme reçu de `bytes` octets
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    pub(crate) fn record_bytes_received(&mut self, bytes: usize, now: Instant) {
+        self.bytes_received += bytes as u64;
+        self.recv_window.record(now, bytes);
+    }
+    
+    /// Recalcule les débits sur la fenêtre se terminant à `now`
+    /// 
+    /// Appelé sur chaque snapshot : sans trafic, les débits retombent à zéro
+    /// au lieu de garder la valeur du dernier datagramme.
+    #[cfg(any(feature = "udp", feature = "simulator"))]
+    pub(crate) fn refresh_bitrates(&mut self, now: Instant) {
+        self.send_bitrate_kbps = self.send_window.kbps(now);
+        self.recv_bitrate_kbps = self.recv_window.kbps(now);
+        self.bandwidth_bytes_per_sec = self.send_bitrate_kbps * 1000.0 / 8.0;
+    }
+    
+    /// Ajoute le trafic compté par un transport (octets cumulés, débits courants)
+    pub(crate) fn merge_traffic(&mut self, transport: &NetworkStats) {
+        self.bytes_sent += transport.bytes_sent;
+        self.bytes_received += transport.bytes_received;
+        self.send_bitrate_kbps = transport.send_bitrate_kbps;
+        self.recv_bitrate_kbps = transport.recv_bitrate_kbps;
+        self.bandwidth_bytes_per_sec = transport.bandwidth_bytes_per_sec;
+    }
+    
+    /// Calcule la différence avec un snapshot précédent
+    /// 
+    /// Les compteurs cumulatifs (paquets, octets, reconnexions, temps
+    /// connecté) sont soustraits, les mesures instantanées (RTT, jitter,
+    /// débits, congestion, session en cours) sont reprises telles quelles
+    /// depuis `self`.
+    /// 
+    /// # Arguments
+    /// * `previous` - Snapshot de référence (début de l'intervalle)
+    pub fn delta_since(&self, previous: &NetworkStats) -> NetworkStats {
+        NetworkStats {
+            packets_sent: self.packets_sent.saturating_sub(previous.packets_sent),
+            packets_received: self.packets_received.saturating_sub(previous.packets_received),
+            packets_lost: self.packets_lost.saturating_sub(previous.packets_lost),
+            packets_corrupted: self.packets_corrupted.saturating_sub(previous.packets_corrupted),
+            packets_rejected: self.packets_rejected.saturating_sub(previous.packets_rejected),
+            packets_from_unknown_peer: self.packets_from_unknown_peer.saturating_sub(previous.packets_from_unknown_peer),
+            packets_late: self.packets_late.saturating_sub(previous.packets_late),
+            frames_skipped: self.frames_skipped.saturating_sub(previous.frames_skipped),
+            errors_suppressed: self.errors_suppressed.saturating_sub(previous.errors_suppressed),
+            packets_throttled: self.packets_throttled.saturating_sub(previous.packets_throttled),
+            bytes_sent: self.bytes_sent.saturating_sub(previous.bytes_sent),
+            bytes_received: self.bytes_received.saturating_sub(previous.bytes_received),
+            reconnection_count: self.reconnection_count.saturating_sub(previous.reconnection_count),
+            connection_uptime_ms: self.connection_uptime_ms.saturating_sub(previous.connection_uptime_ms),
+            sessions_resumed: self.sessions_resumed.saturating_sub(previous.sessions_resumed),
+            ..self.clone()
+        }
+    }
+    
+    /// Durée de la session connectée en cours (zéro si déconnecté)
+    /// 
+    /// Une reprise de session (`sessions_resumed`) ne la remet pas à zéro,
+    /// une reconnexion si.
+    pub fn current_session_uptime(&self) -> Duration {
+        Duration::from_millis(self.session_uptime_ms)
+    }
+    
+    /// Temps passé connecté depuis la création du manager (ou le dernier
+    /// `reset_stats`), toutes sessions confondues
+    pub fn total_uptime(&self) -> Duration {
+        Duration::from_millis(self.connection_uptime_ms)
+    }
+    
     /// Calcule le pourcentage de perte de paquets
     pub fn loss_percentage(&self) -> f32 {
         if self.packets_sent == 0 {
@@ -449,6 +2431,83 @@ impl NetworkStats {
     }
 }
 
+/// Statistiques réseau sur un intervalle de temps
+/// 
+/// Retourné par `UdpNetworkManager::stats_interval_snapshot()` pour
+/// permettre aux dashboards de tracer des débits par seconde.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NetworkStatsInterval {
+    /// Durée de l'intervalle en millisecondes
+    pub interval_ms: u64,
+    
+    /// Différence des statistiques sur l'intervalle
+    pub delta: NetworkStats,
+}
+
+impl NetworkStatsInterval {
+    /// Paquets envoyés par seconde sur l'intervalle
+    pub fn packets_sent_per_sec(&self) -> f32 {
+        self.per_sec(self.delta.packets_sent)
+    }
+    
+    /// Paquets reçus par seconde sur l'intervalle
+    pub fn packets_received_per_sec(&self) -> f32 {
+        self.per_sec(self.delta.packets_received)
+    }
+    
+    /// Paquets perdus par seconde sur l'intervalle
+    pub fn packets_lost_per_sec(&self) -> f32 {
+        self.per_sec(self.delta.packets_lost)
+    }
+    
+    fn per_sec(&self, count: u64) -> f32 {
+        if self.interval_ms == 0 {
+            return 0.0;
+        }
+        count as f32 * 1000.0 / self.interval_ms as f32
+    }
+}
+
+/// Signes de vie du pair, entre deux heartbeats
+/// 
+/// Permet d'afficher "pair injoignable depuis 3 s" avant que
+/// `heartbeat_timeout` ne coupe la connexion.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Liveness {
+    /// Dernier heartbeat reçu du pair, ou établissement de la session s'il
+    /// n'en a encore envoyé aucun (`None` hors connexion)
+    pub last_seen: Option<Instant>,
+    
+    /// Heartbeats manqués d'affilée
+    /// 
+    /// Un heartbeat compte comme manqué une période entière après l'instant
+    /// où il était attendu, pour ne pas signaler un simple retard.
+    pub missed: u32,
+    
+    /// Connecté et aucun heartbeat manqué
+    pub healthy: bool,
+}
+
+impl Liveness {
+    /// Silence du pair à afficher, s'il n'est pas en bonne santé
+    /// 
+    /// # Example
+    /// ```rust
+    /// use network::Liveness;
+    /// use std::time::{Duration, Instant};
+    /// 
+    /// let now = Instant::now();
+    /// let liveness = Liveness { last_seen: Some(now - Duration::from_secs(3)), missed: 2, healthy: false };
+    /// assert_eq!(liveness.unresponsive_for(now), Some(Duration::from_secs(3)));
+    /// ```
+    pub fn unresponsive_for(&self, now: Instant) -> Option<Duration> {
+        if self.healthy {
+            return None;
+        }
+        self.last_seen.map(|seen| now.saturating_duration_since(seen))
+    }
+}
+
 /// Qualité de la connexion réseau
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ConnectionQuality {
@@ -494,7 +2553,8 @@ mod tests {
         assert_eq!(packet.packet_type, PacketType::Audio);
         assert_eq!(packet.sender_id, 123);
         assert_eq!(packet.session_id, 456);
-        assert_eq!(packet.compressed_frame.data, frame.data);
+        assert_eq!(packet.audio_frame().unwrap().data, frame.data);
+        assert_eq!(packet.sequence_number(), 42);
     }
     
     #[test]
@@ -506,8 +2566,237 @@ mod tests {
         
         // Test avec données modifiées
         let mut corrupted = packet.clone();
-        corrupted.compressed_frame.data[0] = 99;
+        if let PacketPayload::Audio(frame) = &mut corrupted.payload {
+            frame.data[0] = 99;
+        }
         assert!(!corrupted.verify_checksum());
+        
+        // Valeur inchangée sur le fil : dernier mot incomplet complété par des zéros
+        let frame = CompressedFrame::new(vec![1, 2, 3, 4, 5, 6], 960, Instant::now(), 42);
+        let packet = NetworkPacket::new_audio(frame, 123, 456);
+        let header = NetworkPacket::CURRENT_PROTOCOL_VERSION as u32 ^ PacketType::Audio as u32 ^ 123 ^ 456 ^ 42 ^ 960;
+        assert_eq!(packet.calculate_checksum(), header ^ 0x0403_0201 ^ 0x0000_0605);
+    }
+    
+    #[test]
+    fn test_error_packet() {
+        let packet = NetworkPacket::new_error(ProtocolErrorCode::VersionMismatch, "v2 requise", 1, 2);
+        assert_eq!(packet.packet_type, PacketType::Error);
+        assert!(packet.verify_checksum());
+        
+        let message = packet.error_message().unwrap();
+        assert_eq!(message.code, ProtocolErrorCode::VersionMismatch);
+        assert_eq!(message.description, "v2 requise");
+        
+        // Un heartbeat ne porte pas de message d'erreur
+        assert_eq!(NetworkPacket::new_heartbeat(1, 2).error_message(), None);
+    }
+    
+    #[test]
+    fn test_handshake_packet() {
+        let hello = NetworkPacket::new_handshake(HandshakeMessage::Hello, 1, 2);
+        assert_eq!(hello.packet_type, PacketType::Handshake);
+        assert!(hello.verify_checksum());
+        assert_eq!(hello.handshake_message(), Some(HandshakeMessage::Hello));
+        
+        let accept = NetworkPacket::new_handshake(HandshakeMessage::Accept, 1, 2);
+        assert_eq!(accept.handshake_message(), Some(HandshakeMessage::Accept));
+        
+        assert_eq!(accept.handshake_info(), None);
+        
+        // Les informations jointes n'empêchent pas de lire le message
+        let info = HandshakeInfo { local_addr: "192.168.1.10:41234".parse().unwrap() };
+        let hello = NetworkPacket::new_handshake_with_info(HandshakeMessage::Hello, info, 1, 2);
+        assert_eq!(hello.handshake_message(), Some(HandshakeMessage::Hello));
+        assert_eq!(hello.handshake_info(), Some(info));
+        assert_eq!(hello.resume_token(), None);
+        
+        // Le jeton de reprise suit les informations, lues comme avant
+        let accept = NetworkPacket::new_handshake_with_token(HandshakeMessage::Accept, info, 0xfeed, 1, 2);
+        assert_eq!(accept.handshake_message(), Some(HandshakeMessage::Accept));
+        assert_eq!(accept.handshake_info(), Some(info));
+        assert_eq!(accept.resume_token(), Some(0xfeed));
+        
+        // Handshake legacy sans message
+        let mut legacy = NetworkPacket::new_heartbeat(1, 2);
+        legacy.packet_type = PacketType::Handshake;
+        assert_eq!(legacy.handshake_message(), None);
+    }
+    
+    #[test]
+    fn test_version_support() {
+        assert!(NetworkPacket::is_version_supported(NetworkPacket::CURRENT_PROTOCOL_VERSION));
+        assert!(!NetworkPacket::is_version_supported(0));
+        assert!(!NetworkPacket::is_version_supported(NetworkPacket::CURRENT_PROTOCOL_VERSION + 1));
+        assert_eq!(
+            NetworkPacket::is_version_supported(NetworkPacket::MIN_SUPPORTED_PROTOCOL_VERSION),
+            cfg!(feature = "legacy-protocol")
+        );
+        
+        let mut encoded = Vec::new();
+        NetworkPacket::new_heartbeat(1, 2).encode_into(&mut encoded);
+        assert_eq!(NetworkPacket::peek_version(&encoded), Some(NetworkPacket::CURRENT_PROTOCOL_VERSION));
+        assert_eq!(NetworkPacket::peek_version(&[]), None);
+        
+        // Format bincode legacy : la version est le premier octet
+        let mut legacy = NetworkPacket::new_heartbeat(1, 2);
+        legacy.protocol_version = 2;
+        let serialized = bincode::serialize(&legacy).unwrap();
+        assert_eq!(NetworkPacket::peek_version(&serialized), Some(2));
+    }
+    
+    #[test]
+    fn test_wire_layout() {
+        let frame = CompressedFrame::new(vec![9, 8, 7], 960, Instant::now(), 0x0102_0304_0506_0708);
+        let packet = NetworkPacket::new_audio(frame, 0xAABB_CCDD_EEFF_0011, 0x1122_3344);
+        let mut encoded = Vec::new();
+        packet.encode_into(&mut encoded);
+        
+        assert_eq!(encoded.len(), NetworkPacket::HEADER_SIZE + 3);
+        assert_eq!(encoded.len(), packet.estimated_size());
+        
+        // Chaque champ décrit par WIRE_LAYOUT est bien à sa position
+        let field = |name: &str| {
+            let field = NetworkPacket::WIRE_LAYOUT.iter().find(|f| f.name == name).unwrap();
+            &encoded[field.offset..field.offset + field.size]
+        };
+        assert_eq!(field("magic"), b"VC");
+        assert_eq!(field("version"), &[NetworkPacket::CURRENT_PROTOCOL_VERSION]);
+        assert_eq!(field("packet_type"), &[PacketType::Audio as u8]);
+        assert_eq!(field("payload_len"), &[0, 3]);
+        assert_eq!(field("sender_id"), &[0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF, 0x00, 0x11]);
+        assert_eq!(field("session_id"), &[0, 0, 0, 0, 0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(field("sequence_number"), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(field("checksum"), &packet.checksum.to_be_bytes());
+        
+        let last = NetworkPacket::WIRE_LAYOUT.last().unwrap();
+        assert_eq!(last.offset + last.size, NetworkPacket::HEADER_SIZE);
+        
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.packet_type, PacketType::Audio);
+        assert_eq!((decoded.sender_id, decoded.session_id), (0xAABB_CCDD_EEFF_0011, 0x1122_3344));
+        let decoded_frame = decoded.audio_frame().unwrap();
+        assert_eq!(decoded_frame.data, vec![9, 8, 7]);
+        assert_eq!(decoded_frame.original_sample_count, 960);
+        assert!(decoded.verify_checksum());
+        
+        // Les 32 bits de poids fort sont couverts par le checksum
+        let mut other = decoded.clone();
+        other.sender_id ^= 1 << 40;
+        assert!(!other.verify_checksum());
+        
+        // Payload tronqué ou type inconnu : rejeté
+        assert!(NetworkPacket::decode_from(&encoded[..encoded.len() - 1]).is_none());
+        encoded[3] = 42;
+        assert!(NetworkPacket::decode_from(&encoded).is_none());
+    }
+    
+    #[test]
+    fn test_frame_metadata_flags() {
+        let mut frame = CompressedFrame::new(vec![1, 2], 960, Instant::now(), 5);
+        let unknown = NetworkPacket::new_audio(frame.clone(), 1, 2);
+        assert_eq!(unknown.flags(), 0);
+        
+        let metadata = FrameMetadata { is_speech: true, level_dbov: 23 };
+        frame.metadata = Some(metadata);
+        let packet = NetworkPacket::new_audio(frame, 1, 2);
+        let mut encoded = Vec::new();
+        packet.encode_into(&mut encoded);
+        assert_eq!(&encoded[4..6], &(NetworkPacket::FLAG_METADATA | NetworkPacket::FLAG_SPEECH | 23).to_be_bytes());
+        
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.audio_frame().unwrap().metadata, Some(metadata));
+        // Hors checksum : même checksum qu'avant l'ajout des flags
+        assert_eq!(decoded.checksum, unknown.checksum);
+        assert!(decoded.verify_checksum());
+    }
+    
+    #[test]
+    fn test_padded_datagram() {
+        let mut frame = CompressedFrame::new(vec![7; 40], 960, Instant::now(), 5);
+        frame.metadata = Some(FrameMetadata { is_speech: true, level_dbov: 12 });
+        let packet = NetworkPacket::new_audio(frame, 1, 2);
+        let mut encoded = Vec::new();
+        packet.encode_into(&mut encoded);
+        
+        // Taille fixe, métadonnées masquées, frame intacte
+        assert!(NetworkPacket::pad_datagram(&mut encoded, 128));
+        assert_eq!(encoded.len(), 128);
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.audio_frame().unwrap().data, vec![7; 40]);
+        assert_eq!(decoded.audio_frame().unwrap().metadata, None);
+        assert!(decoded.verify_checksum());
+        
+        // Trop petit pour la frame : datagramme inchangé
+        packet.encode_into(&mut encoded);
+        let original = encoded.clone();
+        assert!(!NetworkPacket::pad_datagram(&mut encoded, 60));
+        assert_eq!(encoded, original);
+        
+        // Taille de bourrage incohérente : paquet rejeté
+        NetworkPacket::pad_datagram(&mut encoded, 128);
+        let last = encoded.len() - 1;
+        encoded[last] = 0xFF;
+        assert!(NetworkPacket::decode_from(&encoded).is_none());
+    }
+    
+    #[test]
+    fn test_media_timestamp_trailer() {
+        let mut frame = CompressedFrame::new(vec![7; 40], 960, Instant::now(), 5);
+        let plain = NetworkPacket::new_audio(frame.clone(), 1, 2);
+        frame.media_timestamp = Some(0x0000_0001_0000_03C0);
+        let packet = NetworkPacket::new_audio(frame, 1, 2);
+        assert_eq!(packet.flags(), NetworkPacket::FLAG_MEDIA_TIMESTAMP);
+        assert_ne!(packet.checksum, plain.checksum);
+        
+        let mut encoded = Vec::new();
+        packet.encode_into(&mut encoded);
+        assert_eq!(encoded.len(), NetworkPacket::HEADER_SIZE + 40 + NetworkPacket::MEDIA_TIMESTAMP_SIZE);
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.audio_frame().unwrap().data, vec![7; 40]);
+        assert_eq!(decoded.media_timestamp(), Some(0x0000_0001_0000_03C0));
+        assert!(decoded.verify_checksum());
+        
+        // Conservé sous le bourrage
+        assert!(NetworkPacket::pad_datagram(&mut encoded, 128));
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.media_timestamp(), Some(0x0000_0001_0000_03C0));
+        assert!(decoded.verify_checksum());
+        
+        // Payload trop court pour l'horodatage : rejeté
+        plain.encode_into(&mut encoded);
+        encoded.truncate(NetworkPacket::HEADER_SIZE + 4);
+        encoded[4..6].copy_from_slice(&NetworkPacket::FLAG_MEDIA_TIMESTAMP.to_be_bytes());
+        encoded[6..8].copy_from_slice(&4u16.to_be_bytes());
+        assert!(NetworkPacket::decode_from(&encoded).is_none());
+    }
+    
+    #[test]
+    fn test_payload_wire_compatibility() {
+        // Heartbeat simple : payload vide, comme l'ancienne frame vide
+        let heartbeat = NetworkPacket::new_heartbeat(1, 2);
+        let mut encoded = Vec::new();
+        heartbeat.encode_into(&mut encoded);
+        assert_eq!(encoded.len(), NetworkPacket::HEADER_SIZE);
+        assert_eq!(&encoded[24..36], &[0; 12]);
+        assert_eq!(NetworkPacket::decode_from(&encoded).unwrap().payload, PacketPayload::None);
+        
+        // Contrôle : le message sérialisé est le payload, relu tel quel
+        let message = ControlMessage::RenegotiateAck { switch_at: 300 };
+        let control = NetworkPacket::new_control(&message, 1, 2);
+        control.encode_into(&mut encoded);
+        assert_eq!(&encoded[NetworkPacket::HEADER_SIZE..], &bincode::serialize(&message).unwrap()[..]);
+        let decoded = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(decoded.payload, PacketPayload::Control(message));
+        assert!(decoded.verify_checksum());
+        
+        // Message de contrôle inconnu : octets bruts, checksum toujours vérifiable
+        let mut unknown = NetworkPacket::new(PacketType::Control, PacketPayload::Raw(vec![9; 7]), 1, 2);
+        unknown.encode_into(&mut encoded);
+        unknown = NetworkPacket::decode_from(&encoded).unwrap();
+        assert_eq!(unknown.payload, PacketPayload::Raw(vec![9; 7]));
+        assert_eq!(unknown.control_message(), None);
+        assert!(unknown.verify_checksum());
     }
     
     #[test]
@@ -528,10 +2817,13 @@ mod tests {
             session_id: 42,
             connected_at: Instant::now(),
             last_heartbeat: Instant::now(),
+            route: SessionRoute::Direct,
         };
         assert!(connected.is_connected());
         assert!(!connected.is_connecting());
         assert_eq!(connected.session_id(), Some(42));
+        assert_eq!(connected.route(), Some(SessionRoute::Direct));
+        assert_eq!(connecting.route(), None);
     }
     
     #[test]
@@ -547,6 +2839,92 @@ mod tests {
         let test = NetworkConfig::test_config();
         assert!(test.connection_timeout < lan.connection_timeout);
         assert_eq!(test.max_retry_attempts, 2);
+        
+        // Low power : moins de heartbeats et d'échantillons, pas de log par paquet
+        let low_power = NetworkConfig::low_power();
+        assert!(low_power.heartbeat_interval > NetworkConfig::default().heartbeat_interval);
+        assert!(low_power.stats_sample_period > NetworkConfig::default().stats_sample_period);
+        assert!(!low_power.log_packet_errors);
+    }
+    
+    #[test]
+    fn test_network_config_toml() {
+        for preset in [NetworkConfig::default(), NetworkConfig::lan_optimized(),
+                       NetworkConfig::wan_optimized(), NetworkConfig::test_config(),
+                       NetworkConfig::low_power()] {
+            assert!(preset.validate().is_ok());
+        }
+        
+        let mut config = NetworkConfig::wan_optimized();
+        config.redundancy = RedundancyMode::Duplicate(2);
+        config.proxy = Some(ProxyConfig::socks5("10.0.0.1:1080".parse().unwrap()));
+        let content = toml::to_string_pretty(&config).unwrap();
+        assert!(content.contains("heartbeat_timeout = \"10s\""), "{}", content);
+        assert!(content.contains("handshake_retry_interval = \"250ms\""), "{}", content);
+        
+        let loaded = NetworkConfig::from_toml_str(&content).unwrap();
+        assert_eq!(loaded.heartbeat_timeout, config.heartbeat_timeout);
+        assert_eq!(loaded.redundancy, config.redundancy);
+        assert_eq!(loaded.proxy, config.proxy);
+        
+        let presence = NetworkConfig::from_toml_str("[presence]\ndisplay_name = \"Salon\"").unwrap();
+        assert_eq!(presence.presence, Some(PresenceConfig::new("Salon")));
+        let unnamed = NetworkConfig::from_toml_str("[presence]\ndisplay_name = \" \"").unwrap_err();
+        assert!(unnamed.to_string().contains("presence.display_name"), "{}", unnamed);
+        
+        // Les erreurs désignent le champ fautif
+        let malformed = NetworkConfig::from_toml_str("max_packet_age = \"5x\"").unwrap_err();
+        assert!(malformed.to_string().contains("max_packet_age"), "{}", malformed);
+        let inconsistent = NetworkConfig::from_toml_str("heartbeat_timeout = \"500ms\"").unwrap_err();
+        assert!(inconsistent.to_string().contains("heartbeat_timeout"), "{}", inconsistent);
+        let budget = NetworkConfig::from_toml_str("max_buffer_bytes = 32768").unwrap_err();
+        assert!(budget.to_string().contains("max_buffer_bytes"), "{}", budget);
+    }
+    
+    #[test]
+    fn test_bind_addr() {
+        let mut config = NetworkConfig::default();
+        assert_eq!(config.bind_addr(9001).unwrap(), "0.0.0.0:9001".parse().unwrap());
+        
+        config.bind_ip = Some("127.0.0.1".parse().unwrap());
+        assert_eq!(config.bind_addr(9001).unwrap(), "127.0.0.1:9001".parse().unwrap());
+        
+        // Interface de bouclage, présente sur toute machine
+        let loopback = crate::utils::list_interfaces().unwrap()
+            .into_iter()
+            .find(|interface| interface.is_loopback && interface.ips.iter().any(|ip| ip.is_ipv4()))
+            .unwrap();
+        config.bind_interface = Some(loopback.name);
+        assert!(config.validate().is_err());
+        config.bind_ip = None;
+        assert!(config.bind_addr(9001).unwrap().ip().is_loopback());
+        
+        config.bind_interface = Some("voc-inexistante0".to_string());
+        assert!(config.bind_addr(9001).unwrap_err().to_string().contains("voc-inexistante0"));
+    }
+    
+    #[test]
+    fn test_network_stats_delta() {
+        let mut previous = NetworkStats::new();
+        previous.packets_sent = 100;
+        previous.packets_lost = 2;
+        
+        let mut current = previous.clone();
+        current.packets_sent = 150;
+        current.packets_lost = 5;
+        current.avg_rtt_ms = 12.0;
+        
+        let delta = current.delta_since(&previous);
+        assert_eq!(delta.packets_sent, 50);
+        assert_eq!(delta.packets_lost, 3);
+        assert_eq!(delta.avg_rtt_ms, 12.0);
+        
+        // Un reset entre deux snapshots ne doit pas provoquer d'underflow
+        let reset = NetworkStats::new().delta_since(&current);
+        assert_eq!(reset.packets_sent, 0);
+        
+        let interval = NetworkStatsInterval { interval_ms: 500, delta };
+        assert_eq!(interval.packets_sent_per_sec(), 100.0);
     }
     
     #[test]
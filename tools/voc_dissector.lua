@@ -0,0 +1,59 @@
+-- Dissecteur Wireshark pour le protocole Voc v4
+-- Généré depuis NetworkPacket::WIRE_LAYOUT : ne pas modifier à la main
+-- Installation : copier dans le dossier des plugins Lua de Wireshark
+
+local voc = Proto("voc", "Voc voice chat")
+
+local packet_types = {
+    [1] = "Audio",
+    [2] = "Heartbeat",
+    [3] = "Handshake",
+    [4] = "Disconnect",
+    [5] = "Discovery",
+    [6] = "Error",
+    [7] = "Control",
+    [8] = "Padding",
+}
+
+local f_magic = ProtoField.bytes("voc.magic", "Octets magiques \"VC\"")
+local f_version = ProtoField.uint8("voc.version", "Version du protocole")
+local f_packet_type = ProtoField.uint8("voc.packet_type", "Type de paquet", base.DEC, packet_types)
+local f_flags = ProtoField.uint16("voc.flags", "Drapeaux (métadonnées de frame)")
+local f_payload_len = ProtoField.uint16("voc.payload_len", "Taille du payload")
+local f_sender_id = ProtoField.uint64("voc.sender_id", "ID de l'expéditeur")
+local f_session_id = ProtoField.uint64("voc.session_id", "ID de session")
+local f_sequence_number = ProtoField.uint64("voc.sequence_number", "Numéro de séquence")
+local f_sample_count = ProtoField.uint32("voc.sample_count", "Échantillons de la frame d'origine")
+local f_checksum = ProtoField.uint32("voc.checksum", "Checksum XOR")
+local f_payload = ProtoField.bytes("voc.payload", "Payload")
+
+voc.fields = { f_magic, f_version, f_packet_type, f_flags, f_payload_len, f_sender_id, f_session_id, f_sequence_number, f_sample_count, f_checksum, f_payload }
+
+local function dissect(buffer, pinfo, tree)
+    if buffer:len() < 40 then return false end
+    if buffer(0, 2):bytes():tohex() ~= "5643" then return false end
+
+    pinfo.cols.protocol = "VOC"
+    local type_name = packet_types[buffer(3, 1):uint()] or "Inconnu"
+    pinfo.cols.info = "Voc " .. type_name
+
+    local subtree = tree:add(voc, buffer(), "Voc " .. type_name)
+    subtree:add(f_magic, buffer(0, 2))
+    subtree:add(f_version, buffer(2, 1))
+    subtree:add(f_packet_type, buffer(3, 1))
+    subtree:add(f_flags, buffer(4, 2))
+    subtree:add(f_payload_len, buffer(6, 2))
+    subtree:add(f_sender_id, buffer(8, 8))
+    subtree:add(f_session_id, buffer(16, 8))
+    subtree:add(f_sequence_number, buffer(24, 8))
+    subtree:add(f_sample_count, buffer(32, 4))
+    subtree:add(f_checksum, buffer(36, 4))
+    if buffer:len() > 40 then
+        subtree:add(f_payload, buffer(40))
+    end
+    return true
+end
+
+voc.dissector = function(buffer, pinfo, tree) dissect(buffer, pinfo, tree) end
+voc:register_heuristic("udp", dissect)
+DissectorTable.get("udp.port"):add(9001, voc)